@@ -0,0 +1,134 @@
+//! `api`: JSON-RPC 2.0 on stdin and stdout, so an editor or a GUI keeps one
+//! switcher running and drives it through requests instead of starting it
+//! for each call (and parsing what it prints).
+//!
+//! One request per line, and one response line back for each that has an
+//! id; one without is run all the same, unanswered:
+//!
+//! ```text
+//! {"jsonrpc": "2.0", "id": 1, "method": "switch", "params": {"name": "work"}}
+//! {"jsonrpc": "2.0", "id": 1, "result": {"account": "work", "cancelled": false}}
+//! ```
+//!
+//! Methods: `current`, `list`, `save`, `switch` and `delete` (`{"name":
+//! ...}`, `delete` also `"keep_data"`) and `rename` (`{"from": ..., "to":
+//! ...}`). A failure answers with the error `--json` prints in `data`. Status
+//! messages go to stderr, and nothing is asked on stdin.
+
+use crate::jsonrpc;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::io::{BufRead, Write};
+
+/// The methods understood, as `Request` names them
+pub const METHODS: &[&str] = &["current", "list", "save", "switch", "delete", "rename"];
+
+/// What a request asks for.
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(tag = "method", content = "params", rename_all = "kebab-case")]
+pub enum Request {
+    Current,
+    List,
+    Save {
+        name: String,
+    },
+    Switch {
+        name: String,
+    },
+    Delete {
+        name: String,
+        #[serde(default)]
+        keep_data: bool,
+    },
+    Rename {
+        from: String,
+        to: String,
+    },
+}
+
+/// Reads requests from `input` until it ends, running each through `handle`
+/// and answering it on `output`.
+pub fn serve(
+    input: impl BufRead,
+    mut output: impl Write,
+    mut handle: impl FnMut(Request) -> Result<Value>,
+) -> Result<()> {
+    for line in input.lines() {
+        let line = line.context("Failed to read a request")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (id, request) = jsonrpc::request::<Request>(&line, METHODS);
+        let outcome = request.and_then(|request| handle(request).map_err(jsonrpc::Error::from));
+        // A notification went through, and gets no answer
+        if id.is_none() && outcome.is_ok() {
+            continue;
+        }
+        writeln!(output, "{}", jsonrpc::response(id, outcome))
+            .context("Failed to write a response")?;
+        output.flush().context("Failed to write a response")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AccountError;
+    use serde_json::json;
+
+    #[test]
+    fn test_requests_answered_in_turn() {
+        let input = [
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "switch", "params": {"name": "work"}}"#,
+            "",
+            r#"{"jsonrpc": "2.0", "method": "save", "params": {"name": "work"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 2, "method": "delete", "params": {"name": "gone"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 3, "method": "rename", "params": {"from": "a"}}"#,
+            r#"{"jsonrpc": "2.0", "id": 4, "method": "shutdown"}"#,
+        ]
+        .join("\n");
+        let mut handled = Vec::new();
+        let mut output = Vec::new();
+        serve(input.as_bytes(), &mut output, |request| {
+            let answer = match &request {
+                Request::Delete { name, .. } => Err(AccountError::NotFound(name.clone()).into()),
+                _ => Ok(json!({"ok": true})),
+            };
+            handled.push(request);
+            answer
+        })
+        .unwrap();
+
+        assert_eq!(
+            handled,
+            [
+                Request::Switch {
+                    name: "work".to_string()
+                },
+                Request::Save {
+                    name: "work".to_string()
+                },
+                Request::Delete {
+                    name: "gone".to_string(),
+                    keep_data: false
+                },
+            ]
+        );
+        let responses: Vec<Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        // Nothing for the notification
+        assert_eq!(responses.len(), 4);
+        assert_eq!(
+            responses[0],
+            json!({"jsonrpc": "2.0", "id": 1, "result": {"ok": true}})
+        );
+        assert_eq!(responses[1]["error"]["data"]["kind"], "not-found");
+        assert_eq!(responses[2]["error"]["code"], jsonrpc::INVALID_PARAMS);
+        assert_eq!(responses[3]["error"]["code"], jsonrpc::METHOD_NOT_FOUND);
+    }
+}
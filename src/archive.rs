@@ -0,0 +1,389 @@
+use crate::claude::{self, Identity};
+use crate::compression;
+use crate::size::DirUsage;
+use crate::store;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+
+const FORMAT_VERSION: u32 = 1;
+const MANIFEST_ENTRY: &str = "manifest.json";
+const FILES_PREFIX: &str = "files";
+
+/// Where `archive` keeps the accounts it packed, in the data directory
+pub const COLD_DIR: &str = ".archived";
+// Written once and read rarely, so worth the slowest level
+const COLD_LEVEL: i32 = 19;
+
+/// Where the archive of `name` is kept. Names may contain `/`, so it's
+/// escaped, as for versions.
+pub fn cold_path(root: &Path, name: &str) -> PathBuf {
+    root.join(COLD_DIR).join(format!(
+        "{}.tar.zst",
+        name.replace('%', "%25").replace('/', "%2F")
+    ))
+}
+
+/// [`pack_with`] for cold storage: the files as they're stored, compressed
+/// together.
+pub fn pack_cold(manifest: &ArchiveManifest, dir: &Path) -> Result<Vec<u8>> {
+    let data = pack_with(manifest, dir, |path| {
+        fs::read(path).with_context(|| format!("Failed to read file: {}", path.display()))
+    })?;
+    compression::compress(data, COLD_LEVEL)
+}
+
+/// Unpacks what [`pack_cold`] packed into `dest`.
+pub fn unpack_cold(data: Vec<u8>, dest: &Path) -> Result<ArchiveManifest> {
+    unpack(&compression::decompress(data)?, dest)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ArchiveManifest {
+    pub format_version: u32,
+    pub name: String,
+    pub saved_at: String,
+    pub created_at: String,
+    pub expires_at: Option<String>,
+}
+
+impl ArchiveManifest {
+    pub fn new(
+        name: &str,
+        saved_at: &str,
+        created_at: DateTime<Utc>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            name: name.to_string(),
+            saved_at: saved_at.to_string(),
+            created_at: created_at.to_rfc3339(),
+            expires_at: expires_at.map(|t| t.to_rfc3339()),
+        }
+    }
+
+    pub fn is_expired(&self, now: DateTime<Utc>) -> Result<bool> {
+        match &self.expires_at {
+            Some(expires_at) => {
+                let expires_at = DateTime::parse_from_rfc3339(expires_at)
+                    .context("Archive contains an invalid expiry timestamp")?;
+                Ok(expires_at <= now)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+/// Packs an account directory and its manifest into an in-memory tar archive.
+pub fn pack(manifest: &ArchiveManifest, dir: &Path) -> Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(Vec::new());
+
+    let manifest_json =
+        serde_json::to_vec_pretty(manifest).context("Failed to serialize archive manifest")?;
+    append_file(&mut builder, Path::new(MANIFEST_ENTRY), &manifest_json)?;
+
+    builder
+        .append_dir_all(FILES_PREFIX, dir)
+        .with_context(|| format!("Failed to archive directory: {}", dir.display()))?;
+
+    builder.into_inner().context("Failed to finalize archive")
+}
+
+/// Like [`pack`], but reads each file through `read`, e.g. to decrypt
+/// snapshots stored encrypted. Only regular files are included.
+pub fn pack_with(
+    manifest: &ArchiveManifest,
+    dir: &Path,
+    read: impl Fn(&Path) -> Result<Vec<u8>>,
+) -> Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(Vec::new());
+
+    let manifest_json =
+        serde_json::to_vec_pretty(manifest).context("Failed to serialize archive manifest")?;
+    append_file(&mut builder, Path::new(MANIFEST_ENTRY), &manifest_json)?;
+
+    for entry in store::walk_dir(dir) {
+        let entry = entry?;
+        let contents = read(&entry.path)?;
+        append_file(
+            &mut builder,
+            &Path::new(FILES_PREFIX).join(&entry.relative_path),
+            &contents,
+        )?;
+    }
+
+    builder.into_inner().context("Failed to finalize archive")
+}
+
+pub(crate) fn append_file(
+    builder: &mut tar::Builder<Vec<u8>>,
+    path: &Path,
+    contents: &[u8],
+) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o600);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, path, contents)
+        .with_context(|| format!("Failed to write archive entry: {}", path.display()))
+}
+
+pub fn read_manifest(data: &[u8]) -> Result<ArchiveManifest> {
+    let mut archive = tar::Archive::new(data);
+
+    for entry in archive.entries().context("Failed to read archive")? {
+        let mut entry = entry.context("Failed to read archive entry")?;
+        if entry.path()?.as_ref() == Path::new(MANIFEST_ENTRY) {
+            return parse_manifest(&mut entry);
+        }
+    }
+
+    anyhow::bail!("Archive does not contain a manifest")
+}
+
+/// What an archive holds, gathered without extracting anything.
+#[derive(Debug)]
+pub struct ArchiveSummary {
+    pub manifest: ArchiveManifest,
+    pub usage: DirUsage,
+    pub identity: Option<Identity>,
+}
+
+pub fn inspect(data: &[u8]) -> Result<ArchiveSummary> {
+    let mut archive = tar::Archive::new(data);
+    let mut manifest = None;
+    let mut usage = DirUsage::default();
+    let mut identity = None;
+
+    for entry in archive.entries().context("Failed to read archive")? {
+        let mut entry = entry.context("Failed to read archive entry")?;
+        let path = entry.path()?.into_owned();
+
+        if path == Path::new(MANIFEST_ENTRY) {
+            manifest = Some(parse_manifest(&mut entry)?);
+            continue;
+        }
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        usage.files += 1;
+        usage.bytes += entry.size();
+
+        let is_credentials = safe_relative_path(&path).is_some_and(|relative| {
+            claude::CREDENTIAL_FILES
+                .iter()
+                .any(|f| relative == Path::new(f))
+        });
+        if is_credentials {
+            let mut contents = String::new();
+            entry
+                .read_to_string(&mut contents)
+                .context("Failed to read credentials from archive")?;
+            identity = claude::read_identity(&contents);
+        }
+    }
+
+    Ok(ArchiveSummary {
+        manifest: manifest.context("Archive does not contain a manifest")?,
+        usage,
+        identity,
+    })
+}
+
+/// Extracts the account files into `dest`, returning the archive manifest.
+pub fn unpack(data: &[u8], dest: &Path) -> Result<ArchiveManifest> {
+    let mut archive = tar::Archive::new(data);
+    let mut manifest = None;
+
+    fs::create_dir_all(dest)
+        .with_context(|| format!("Failed to create directory: {}", dest.display()))?;
+
+    for entry in archive.entries().context("Failed to read archive")? {
+        let mut entry = entry.context("Failed to read archive entry")?;
+        let path = entry.path()?.into_owned();
+
+        if path == Path::new(MANIFEST_ENTRY) {
+            manifest = Some(parse_manifest(&mut entry)?);
+            continue;
+        }
+
+        let Some(relative) = safe_relative_path(&path) else {
+            anyhow::bail!("Archive contains an unsafe path: {}", path.display());
+        };
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+
+        let target = dest.join(&relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        entry
+            .unpack(&target)
+            .with_context(|| format!("Failed to extract file: {}", target.display()))?;
+    }
+
+    manifest.context("Archive does not contain a manifest")
+}
+
+fn parse_manifest(entry: &mut impl Read) -> Result<ArchiveManifest> {
+    let mut contents = String::new();
+    entry
+        .read_to_string(&mut contents)
+        .context("Failed to read archive manifest")?;
+
+    let manifest: ArchiveManifest =
+        serde_json::from_str(&contents).context("Failed to parse archive manifest")?;
+
+    if manifest.format_version > FORMAT_VERSION {
+        anyhow::bail!(
+            "Archive format version {} is newer than supported version {}",
+            manifest.format_version,
+            FORMAT_VERSION
+        );
+    }
+    Ok(manifest)
+}
+
+// Strips the `files/` prefix and rejects anything that could escape the destination
+fn safe_relative_path(path: &Path) -> Option<PathBuf> {
+    safe_path_under(path, Path::new(FILES_PREFIX))
+}
+
+/// Strips `prefix` from an archive entry path, rejecting anything that could
+/// escape the destination it's extracted to.
+pub(crate) fn safe_path_under(path: &Path, prefix: &Path) -> Option<PathBuf> {
+    let relative = path.strip_prefix(prefix).ok()?;
+
+    let mut clean = PathBuf::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(part) => clean.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(clean)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use tempfile::TempDir;
+
+    fn create_account_dir() -> Result<TempDir> {
+        let dir = TempDir::new()?;
+        fs::write(dir.path().join("config.json"), r#"{"api_key": "test_key"}"#)?;
+        fs::create_dir_all(dir.path().join("nested"))?;
+        fs::write(dir.path().join("nested/file.txt"), "content")?;
+        Ok(dir)
+    }
+
+    #[test]
+    fn test_pack_and_unpack() -> Result<()> {
+        let source = create_account_dir()?;
+        let dest = TempDir::new()?;
+
+        let manifest = ArchiveManifest::new("work", "2024-01-01T00:00:00Z", Utc::now(), None);
+        let data = pack(&manifest, source.path())?;
+
+        let unpacked = unpack(&data, dest.path())?;
+        assert_eq!(unpacked.name, "work");
+        assert_eq!(unpacked.saved_at, "2024-01-01T00:00:00Z");
+        assert!(unpacked.expires_at.is_none());
+
+        let content = fs::read_to_string(dest.path().join("nested/file.txt"))?;
+        assert_eq!(content, "content");
+        assert!(dest.path().join("config.json").exists());
+        assert!(!dest.path().join(MANIFEST_ENTRY).exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_pack_with_transforms_contents() -> Result<()> {
+        let source = create_account_dir()?;
+        let dest = TempDir::new()?;
+
+        let manifest = ArchiveManifest::new("work", "2024-01-01T00:00:00Z", Utc::now(), None);
+        let data = pack_with(&manifest, source.path(), |path| {
+            Ok(fs::read_to_string(path)?.to_uppercase().into_bytes())
+        })?;
+
+        unpack(&data, dest.path())?;
+        let content = fs::read_to_string(dest.path().join("nested/file.txt"))?;
+        assert_eq!(content, "CONTENT");
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_manifest() -> Result<()> {
+        let source = create_account_dir()?;
+        let manifest = ArchiveManifest::new("work", "2024-01-01T00:00:00Z", Utc::now(), None);
+        let data = pack(&manifest, source.path())?;
+
+        assert_eq!(read_manifest(&data)?.name, "work");
+        Ok(())
+    }
+
+    #[test]
+    fn test_inspect() -> Result<()> {
+        let source = create_account_dir()?;
+        fs::write(
+            source.path().join(".credentials.json"),
+            r#"{"claudeAiOauth": {"accessToken": "sk-ant-oat01-0123456789", "subscriptionType": "pro"}}"#,
+        )?;
+        let manifest = ArchiveManifest::new("work", "2024-01-01T00:00:00Z", Utc::now(), None);
+        let data = pack(&manifest, source.path())?;
+
+        let summary = inspect(&data)?;
+        assert_eq!(summary.manifest.name, "work");
+        assert_eq!(summary.usage.files, 3);
+        assert_eq!(
+            summary.usage.bytes,
+            crate::size::dir_usage(source.path())?.bytes
+        );
+
+        let identity = summary.identity.unwrap();
+        assert_eq!(identity.plan.as_deref(), Some("pro"));
+        assert_eq!(identity.token.as_deref(), Some("sk-ant-o…6789"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_manifest_invalid_data() {
+        assert!(read_manifest(b"not an archive").is_err());
+    }
+
+    #[test]
+    fn test_manifest_expiry() -> Result<()> {
+        let now = Utc::now();
+
+        let fresh = ArchiveManifest::new("a", "", now, Some(now + Duration::hours(1)));
+        assert!(!fresh.is_expired(now)?);
+
+        let stale = ArchiveManifest::new("a", "", now, Some(now - Duration::hours(1)));
+        assert!(stale.is_expired(now)?);
+
+        let forever = ArchiveManifest::new("a", "", now, None);
+        assert!(!forever.is_expired(now)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_safe_relative_path() {
+        assert_eq!(
+            safe_relative_path(Path::new("files/a/b.json")),
+            Some(PathBuf::from("a/b.json"))
+        );
+        assert_eq!(safe_relative_path(Path::new("files/../evil")), None);
+        assert_eq!(safe_relative_path(Path::new("other/file")), None);
+    }
+}
@@ -0,0 +1,265 @@
+//! `audit`: the files holding tokens or keys, in the store and the live
+//! configuration, with who can read them and whether they're encrypted.
+//!
+//! A file counts when its name looks like it holds a secret
+//! (`.credentials.json`, `*.pem`, names with `token` in them, ...) or its
+//! contents do: an Anthropic key or token, an OAuth token field, a private
+//! key. Sealed files can't be looked into, so only their names count. Big
+//! files, such as transcripts, are only judged by name too.
+//!
+//! Anything others can read is flagged, and so is a secret kept in the store
+//! unencrypted. The live configuration can't be encrypted, as Claude Code
+//! reads it as it is.
+
+use crate::compression;
+use crate::crypto;
+use crate::store;
+use crate::tr;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+// Larger files are judged by their name alone
+const MAX_SCANNED: u64 = 1024 * 1024;
+
+const SECRET_NAMES: &[&str] = &[".credentials.json", ".netrc", ".env"];
+const SECRET_EXTENSIONS: &[&str] = &["pem", "key", "p12", "pfx"];
+const SECRET_WORDS: &[&str] = &["credential", "secret", "token"];
+const SECRET_CONTENTS: &[&str] = &[
+    "sk-ant-",
+    "\"accessToken\"",
+    "\"refreshToken\"",
+    "\"primaryApiKey\"",
+    "\"apiKey\"",
+    "PRIVATE KEY-----",
+];
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Location {
+    /// A snapshot, version or anything else in the store
+    Store,
+    /// The configuration Claude Code uses
+    Live,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Reason {
+    Name,
+    Contents,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Flag {
+    WorldReadable,
+    GroupReadable,
+    /// In the store without encryption
+    Unencrypted,
+}
+
+impl Flag {
+    pub fn label(self) -> String {
+        match self {
+            Flag::WorldReadable => tr!("audit-world-readable"),
+            Flag::GroupReadable => tr!("audit-group-readable"),
+            Flag::Unencrypted => tr!("audit-unencrypted"),
+        }
+    }
+}
+
+/// A file found holding a secret.
+#[derive(Serialize, Debug)]
+pub struct Secret {
+    pub path: PathBuf,
+    pub location: Location,
+    pub reason: Reason,
+    /// Its permissions, as Unix mode bits
+    pub mode: Option<u32>,
+    pub encrypted: bool,
+    pub flags: Vec<Flag>,
+}
+
+/// The files below `dir` (or `dir` itself, when it's a file) that hold
+/// secrets. One that's gone is nothing to report.
+pub fn scan(dir: &Path, location: Location) -> Result<Vec<Secret>> {
+    let mut secrets = Vec::new();
+    if dir.is_file() {
+        secrets.extend(inspect(dir, location)?);
+        return Ok(secrets);
+    }
+    if !dir.is_dir() {
+        return Ok(secrets);
+    }
+    for entry in store::walk_dir(dir) {
+        secrets.extend(inspect(&entry?.path, location)?);
+    }
+    Ok(secrets)
+}
+
+fn inspect(path: &Path, location: Location) -> Result<Option<Secret>> {
+    let data = read_head(path)?;
+    let encrypted = crypto::is_encrypted(&data) || crypto::is_sealed(&data);
+    let by_name = has_secret_name(path);
+    let by_contents = !encrypted && {
+        // A compressed file that won't open is judged by its name alone
+        let data = match compression::is_compressed(&data) {
+            true => compression::decompress(data).unwrap_or_default(),
+            false => data,
+        };
+        has_secret_contents(&data)
+    };
+    let reason = match (by_name, by_contents) {
+        (true, _) => Reason::Name,
+        (false, true) => Reason::Contents,
+        (false, false) => return Ok(None),
+    };
+
+    let mode = mode(path)?;
+    let mut flags = Vec::new();
+    if mode.is_some_and(|mode| mode & 0o004 != 0) {
+        flags.push(Flag::WorldReadable);
+    } else if mode.is_some_and(|mode| mode & 0o040 != 0) {
+        flags.push(Flag::GroupReadable);
+    }
+    if location == Location::Store && !encrypted {
+        flags.push(Flag::Unencrypted);
+    }
+    Ok(Some(Secret {
+        path: path.to_path_buf(),
+        location,
+        reason,
+        mode,
+        encrypted,
+        flags,
+    }))
+}
+
+// The whole file when it's small enough to scan, else enough to tell
+// whether it's sealed
+fn read_head(path: &Path) -> Result<Vec<u8>> {
+    let file = File::open(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let limit = match file.metadata()?.len() <= MAX_SCANNED {
+        true => MAX_SCANNED,
+        false => 8,
+    };
+    let mut data = Vec::new();
+    file.take(limit)
+        .read_to_end(&mut data)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(data)
+}
+
+/// Whether the name of the file at `path` looks like it holds a secret.
+pub fn has_secret_name(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_ascii_lowercase();
+    let extension = path
+        .extension()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_ascii_lowercase();
+    SECRET_NAMES.contains(&name.as_str())
+        || SECRET_EXTENSIONS.contains(&extension.as_str())
+        || SECRET_WORDS.iter().any(|word| name.contains(word))
+}
+
+fn has_secret_contents(data: &[u8]) -> bool {
+    SECRET_CONTENTS.iter().any(|needle| {
+        data.windows(needle.len())
+            .any(|window| window == needle.as_bytes())
+    })
+}
+
+fn mode(path: &Path) -> Result<Option<u32>> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let metadata = fs::metadata(path)
+            .with_context(|| format!("Failed to read permissions: {}", path.display()))?;
+        Ok(Some(metadata.permissions().mode() & 0o777))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_scan_finds_secrets_by_name_and_contents() -> Result<()> {
+        let dir = TempDir::new()?;
+        let root = dir.path();
+        fs::create_dir_all(root.join("work/projects"))?;
+        fs::write(root.join("work/.credentials.json"), "{}")?;
+        fs::write(
+            root.join("work/settings.json"),
+            r#"{"env": {"ANTHROPIC_API_KEY": "sk-ant-api03-xyz"}}"#,
+        )?;
+        fs::write(root.join("work/projects/notes.md"), "nothing here")?;
+        fs::write(
+            root.join("work/.claude.json"),
+            compression::compress(br#"{"primaryApiKey": "x"}"#.to_vec(), 3)?,
+        )?;
+        let sealed = crypto::encrypt(br#"{"accessToken": "x"}"#, "passphrase")?;
+        fs::write(root.join("work/sealed.json"), sealed)?;
+
+        let mut secrets = scan(root, Location::Store)?;
+        secrets.sort_by(|a, b| a.path.cmp(&b.path));
+        let found: Vec<_> = secrets
+            .iter()
+            .map(|secret| {
+                let name = secret
+                    .path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned();
+                (name, secret.reason)
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (".claude.json".to_string(), Reason::Contents),
+                (".credentials.json".to_string(), Reason::Name),
+                ("settings.json".to_string(), Reason::Contents),
+            ]
+        );
+        assert!(
+            secrets
+                .iter()
+                .all(|secret| secret.flags.contains(&Flag::Unencrypted))
+        );
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_open_permissions_are_flagged() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new()?;
+        let path = dir.path().join(".credentials.json");
+        fs::write(&path, "{}")?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644))?;
+        let secrets = scan(&path, Location::Live)?;
+        assert_eq!(secrets[0].mode, Some(0o644));
+        assert_eq!(secrets[0].flags, [Flag::WorldReadable]);
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+        assert!(scan(dir.path(), Location::Live)?[0].flags.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,396 @@
+//! Backups of the whole store, for disaster recovery: every snapshot with
+//! its versions, the trash, the accounts file and history, and the settings,
+//! in one zstd-compressed tar (`claude-accounts-20240101T093000Z.tar.zst`).
+//!
+//! Snapshots go in as they are stored, so an encrypted store stays encrypted
+//! in the backup. Inside the archive, `accounts/` holds the store,
+//! `state/` the state directory and `settings.toml` the settings, next to a
+//! `backup.json` describing it.
+
+use crate::archive;
+use crate::lock;
+use crate::retry::retry;
+use crate::store;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Read};
+use std::path::{Path, PathBuf};
+
+/// Where backups go unless `--output` says otherwise, in the state directory
+pub const DIR: &str = "backups";
+/// Where a restore unpacks the archive before anything is replaced, in the
+/// state directory
+pub const STAGING_DIR: &str = ".restore";
+
+const FORMAT_VERSION: u32 = 1;
+const FILE_PREFIX: &str = "claude-accounts-";
+const FILE_SUFFIX: &str = ".tar.zst";
+const ID_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+const LEVEL: i32 = 3;
+const MANIFEST_ENTRY: &str = "backup.json";
+const ACCOUNTS_PREFIX: &str = "accounts";
+const STATE_PREFIX: &str = "state";
+const SETTINGS_ENTRY: &str = "settings.toml";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub created_at: String,
+    pub accounts: Vec<String>,
+    /// Where the store was, so account paths can be moved to a new one
+    pub store_dir: PathBuf,
+}
+
+impl BackupManifest {
+    pub fn new(created_at: DateTime<Utc>, accounts: Vec<String>, store_dir: &Path) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            created_at: created_at.to_rfc3339(),
+            accounts,
+            store_dir: store_dir.to_path_buf(),
+        }
+    }
+}
+
+/// What goes into a backup.
+pub struct Sources<'a> {
+    pub store_dir: &'a Path,
+    pub state_dir: &'a Path,
+    pub settings_file: &'a Path,
+    /// Files that aren't on disk, as paths within the store (keychain
+    /// credentials)
+    pub extra: Vec<(PathBuf, Vec<u8>)>,
+}
+
+/// Where a backup made at `created_at` goes in `dir`. Two within the same
+/// second get `-2`, `-3`, ... suffixes.
+pub fn path_in(dir: &Path, created_at: DateTime<Utc>) -> PathBuf {
+    let id = created_at.format(ID_FORMAT).to_string();
+    let mut path = dir.join(format!("{}{}{}", FILE_PREFIX, id, FILE_SUFFIX));
+    for n in 2.. {
+        if !path.exists() {
+            break;
+        }
+        path = dir.join(format!("{}{}-{}{}", FILE_PREFIX, id, n, FILE_SUFFIX));
+    }
+    path
+}
+
+/// Writes the backup to `path`, through a temporary file so a backup that
+/// fails halfway never looks complete. Returns its size.
+pub fn create(path: &Path, manifest: &BackupManifest, sources: &Sources) -> Result<u64> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("Failed to create backup directory")?;
+    }
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+
+    let result = write(&partial, manifest, sources);
+    if result.is_err() {
+        let _ = fs::remove_file(&partial);
+    }
+    result?;
+    retry(|| fs::rename(&partial, path)).context("Failed to write backup")?;
+    Ok(fs::metadata(path)?.len())
+}
+
+fn write(path: &Path, manifest: &BackupManifest, sources: &Sources) -> Result<()> {
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create backup: {}", path.display()))?;
+    let encoder = zstd::Encoder::new(BufWriter::new(file), LEVEL)
+        .context("Failed to start compressing backup")?;
+    let mut builder = tar::Builder::new(encoder);
+
+    let manifest_json =
+        serde_json::to_vec_pretty(manifest).context("Failed to serialize backup manifest")?;
+    append(&mut builder, Path::new(MANIFEST_ENTRY), &manifest_json)?;
+
+    for entry in store::walk_dir(sources.store_dir) {
+        let entry = entry?;
+        append_path(
+            &mut builder,
+            &Path::new(ACCOUNTS_PREFIX).join(&entry.relative_path),
+            &entry.path,
+        )?;
+    }
+    for (relative, contents) in &sources.extra {
+        append(
+            &mut builder,
+            &Path::new(ACCOUNTS_PREFIX).join(relative),
+            contents,
+        )?;
+    }
+    // An earlier backup, or this one when it's written there, has no place here
+    for entry in store::walk_dir(sources.state_dir).skip_dirs(&[DIR, STAGING_DIR]) {
+        let entry = entry?;
+        if entry.relative_path == Path::new(lock::FILE) {
+            continue;
+        }
+        append_path(
+            &mut builder,
+            &Path::new(STATE_PREFIX).join(&entry.relative_path),
+            &entry.path,
+        )?;
+    }
+    if sources.settings_file.is_file() {
+        append_path(
+            &mut builder,
+            Path::new(SETTINGS_ENTRY),
+            sources.settings_file,
+        )?;
+    }
+
+    let encoder = builder.into_inner().context("Failed to finalize backup")?;
+    let mut writer = encoder
+        .finish()
+        .context("Failed to finish compressing backup")?;
+    std::io::Write::flush(&mut writer).context("Failed to write backup")?;
+    writer
+        .into_inner()
+        .map_err(|e| e.into_error())
+        .context("Failed to write backup")?
+        .sync_all()
+        .context("Failed to write backup")
+}
+
+fn append(
+    builder: &mut tar::Builder<impl std::io::Write>,
+    path: &Path,
+    contents: &[u8],
+) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(contents.len() as u64);
+    header.set_mode(0o600);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, path, contents)
+        .with_context(|| format!("Failed to write backup entry: {}", path.display()))
+}
+
+fn append_path(
+    builder: &mut tar::Builder<impl std::io::Write>,
+    path: &Path,
+    source: &Path,
+) -> Result<()> {
+    let mut file =
+        File::open(source).with_context(|| format!("Failed to read file: {}", source.display()))?;
+    builder
+        .append_file(path, &mut file)
+        .with_context(|| format!("Failed to write backup entry: {}", path.display()))
+}
+
+/// What a backup unpacked to by [`extract`] holds.
+pub struct Extracted {
+    pub manifest: BackupManifest,
+    pub store_dir: PathBuf,
+    pub state_dir: PathBuf,
+    /// The settings, if the backup had any
+    pub settings_file: Option<PathBuf>,
+}
+
+/// Unpacks the backup at `path` into `dest`.
+pub fn extract(path: &Path, dest: &Path) -> Result<Extracted> {
+    let file =
+        File::open(path).with_context(|| format!("Failed to open backup: {}", path.display()))?;
+    let decoder = zstd::Decoder::new(BufReader::new(file))
+        .with_context(|| format!("Not a backup: {}", path.display()))?;
+    let mut archive = tar::Archive::new(decoder);
+
+    let store_dir = dest.join(ACCOUNTS_PREFIX);
+    let state_dir = dest.join(STATE_PREFIX);
+    fs::create_dir_all(&store_dir).context("Failed to create restore directory")?;
+    fs::create_dir_all(&state_dir).context("Failed to create restore directory")?;
+    let mut manifest = None;
+    let mut settings_file = None;
+
+    for entry in archive.entries().context("Failed to read backup")? {
+        let mut entry = entry.context("Failed to read backup entry")?;
+        let entry_path = entry.path()?.into_owned();
+
+        if entry_path == Path::new(MANIFEST_ENTRY) {
+            manifest = Some(parse_manifest(&mut entry)?);
+            continue;
+        }
+        let target = if entry_path == Path::new(SETTINGS_ENTRY) {
+            let target = dest.join(SETTINGS_ENTRY);
+            settings_file = Some(target.clone());
+            target
+        } else {
+            let relative = archive::safe_path_under(&entry_path, Path::new(ACCOUNTS_PREFIX))
+                .map(|relative| store_dir.join(relative))
+                .or_else(|| {
+                    archive::safe_path_under(&entry_path, Path::new(STATE_PREFIX))
+                        .map(|relative| state_dir.join(relative))
+                });
+            let Some(target) = relative else {
+                anyhow::bail!("Backup contains an unsafe path: {}", entry_path.display());
+            };
+            target
+        };
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        entry
+            .unpack(&target)
+            .with_context(|| format!("Failed to extract file: {}", target.display()))?;
+    }
+
+    Ok(Extracted {
+        manifest: manifest.context("Not a backup: it has no backup.json")?,
+        store_dir,
+        state_dir,
+        settings_file,
+    })
+}
+
+fn parse_manifest(entry: &mut impl Read) -> Result<BackupManifest> {
+    let mut contents = String::new();
+    entry
+        .read_to_string(&mut contents)
+        .context("Failed to read backup manifest")?;
+    let manifest: BackupManifest =
+        serde_json::from_str(&contents).context("Failed to parse backup manifest")?;
+
+    if manifest.format_version > FORMAT_VERSION {
+        anyhow::bail!(
+            "Backup format version {} is newer than supported version {}",
+            manifest.format_version,
+            FORMAT_VERSION
+        );
+    }
+    Ok(manifest)
+}
+
+/// The backups in `dir`, newest first. Other files are left out.
+pub fn list(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(dir).context("Failed to read backup directory")? {
+        let entry = entry.context("Failed to read backup directory")?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Some(id) = name
+            .strip_prefix(FILE_PREFIX)
+            .and_then(|name| name.strip_suffix(FILE_SUFFIX))
+        else {
+            continue;
+        };
+        // The timestamp, then the `-2` a second backup within it got
+        let (stamp, n) = match id.split_once('-') {
+            Some((stamp, n)) => (stamp.to_string(), n.parse().unwrap_or(0)),
+            None => (id.to_string(), 1),
+        };
+        backups.push(((stamp, n), entry.path()));
+    }
+    backups.sort_by(|a, b| b.0.cmp(&a.0));
+    Ok(backups.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Deletes all but the `keep` newest backups in `dir`, returning the ones
+/// deleted.
+pub fn rotate(dir: &Path, keep: usize) -> Result<Vec<PathBuf>> {
+    let removed: Vec<PathBuf> = list(dir)?.into_iter().skip(keep).collect();
+    for path in &removed {
+        retry(|| fs::remove_file(path))
+            .with_context(|| format!("Failed to remove backup: {}", path.display()))?;
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn time(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_create_and_extract() -> Result<()> {
+        let dir = TempDir::new()?;
+        let store_dir = dir.path().join("store");
+        let state_dir = dir.path().join("state");
+        fs::create_dir_all(store_dir.join("work"))?;
+        fs::write(store_dir.join("work/config.json"), "work")?;
+        fs::create_dir_all(state_dir.join(DIR))?;
+        fs::write(state_dir.join("accounts.json"), "{}")?;
+        fs::write(state_dir.join(lock::FILE), "")?;
+        fs::write(state_dir.join(DIR).join("old.tar.zst"), "old")?;
+        let settings_file = dir.path().join("settings.toml");
+        fs::write(&settings_file, "dedup = true\n")?;
+
+        let created_at = time("2024-01-01T09:30:00+00:00");
+        let path = path_in(&state_dir.join(DIR), created_at);
+        assert!(path.ends_with("claude-accounts-20240101T093000Z.tar.zst"));
+        let manifest = BackupManifest::new(created_at, vec!["work".to_string()], &store_dir);
+        let sources = Sources {
+            store_dir: &store_dir,
+            state_dir: &state_dir,
+            settings_file: &settings_file,
+            extra: vec![(PathBuf::from("work/.credentials.json"), b"sealed".to_vec())],
+        };
+        create(&path, &manifest, &sources)?;
+
+        let dest = dir.path().join("restore");
+        let extracted = extract(&path, &dest)?;
+        assert_eq!(extracted.manifest.accounts, ["work"]);
+        assert_eq!(extracted.manifest.store_dir, store_dir);
+        let restored = &extracted.store_dir;
+        assert_eq!(
+            fs::read_to_string(restored.join("work/config.json"))?,
+            "work"
+        );
+        assert_eq!(
+            fs::read(restored.join("work/.credentials.json"))?,
+            b"sealed"
+        );
+        assert!(extracted.state_dir.join("accounts.json").is_file());
+        // Neither the lock nor earlier backups
+        assert!(!extracted.state_dir.join(lock::FILE).exists());
+        assert!(!extracted.state_dir.join(DIR).exists());
+        let settings = extracted.settings_file.unwrap();
+        assert_eq!(fs::read_to_string(settings)?, "dedup = true\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_rejects_other_files() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("bundle.tar.zst");
+        fs::write(&path, "not a backup")?;
+        assert!(extract(&path, &dir.path().join("restore")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rotate_keeps_the_newest() -> Result<()> {
+        let dir = TempDir::new()?;
+        for day in [1, 2, 3, 4, 4] {
+            let created_at = time(&format!("2024-01-0{}T00:00:00+00:00", day));
+            fs::write(path_in(dir.path(), created_at), "")?;
+        }
+        fs::write(dir.path().join("notes.txt"), "")?;
+
+        let removed = rotate(dir.path(), 2)?;
+        assert_eq!(removed.len(), 3);
+        let kept = list(dir.path())?;
+        assert_eq!(kept.len(), 2);
+        assert!(kept[0].ends_with("claude-accounts-20240104T000000Z-2.tar.zst"));
+        assert!(kept[1].ends_with("claude-accounts-20240104T000000Z.tar.zst"));
+        assert!(dir.path().join("notes.txt").exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+//! A notice naming the active account, kept in the live `CLAUDE.md` so the
+//! identity is visible inside Claude Code itself.
+//!
+//! The notice sits between marker comments, so it can be replaced on every
+//! switch and stripped again before a snapshot is saved.
+
+pub const FILE: &str = "CLAUDE.md";
+
+const START: &str = "<!-- claude-account-switcher:banner -->";
+const END: &str = "<!-- /claude-account-switcher:banner -->";
+
+/// Fills in `{name}` in the configured banner text.
+pub fn render(template: &str, account: &str) -> String {
+    template.replace("{name}", account)
+}
+
+/// Puts `text` at the top of `contents`, replacing any earlier banner.
+pub fn insert(contents: &str, text: &str) -> String {
+    let rest = strip(contents);
+    let block = format!("{}\n{}\n{}\n", START, text.trim(), END);
+    if rest.is_empty() {
+        block
+    } else {
+        format!("{}\n{}", block, rest)
+    }
+}
+
+/// Removes the banner and the blank line that separates it from the rest.
+pub fn strip(contents: &str) -> String {
+    let Some(start) = contents.find(START) else {
+        return contents.to_string();
+    };
+    let Some(end) = contents[start..]
+        .find(END)
+        .map(|end| start + end + END.len())
+    else {
+        return contents.to_string();
+    };
+
+    let after = contents[end..]
+        .strip_prefix('\n')
+        .unwrap_or(&contents[end..]);
+    let after = if start == 0 {
+        after.strip_prefix('\n').unwrap_or(after)
+    } else {
+        after
+    };
+    format!("{}{}", &contents[..start], after)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insert_and_strip_roundtrip() {
+        let original = "# Notes\n\nUse tabs.\n";
+        let with_banner = insert(original, "ACTIVE ACCOUNT: work");
+
+        assert!(with_banner.starts_with(START));
+        assert!(with_banner.contains("ACTIVE ACCOUNT: work"));
+        assert_eq!(strip(&with_banner), original);
+    }
+
+    #[test]
+    fn test_insert_replaces_previous_banner() {
+        let once = insert("notes\n", "ACTIVE ACCOUNT: work");
+        let twice = insert(&once, "ACTIVE ACCOUNT: personal");
+
+        assert!(!twice.contains("work"));
+        assert_eq!(twice.matches(START).count(), 1);
+        assert_eq!(strip(&twice), "notes\n");
+    }
+
+    #[test]
+    fn test_banner_only_file_strips_to_empty() {
+        assert_eq!(strip(&insert("", "hello")), "");
+    }
+
+    #[test]
+    fn test_strip_without_banner_is_unchanged() {
+        assert_eq!(strip("plain\n"), "plain\n");
+        // An unterminated block is left alone rather than eating the file
+        assert_eq!(
+            strip(&format!("{}\ntext", START)),
+            format!("{}\ntext", START)
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            render("ACTIVE ACCOUNT: {name}", "work"),
+            "ACTIVE ACCOUNT: work"
+        );
+    }
+}
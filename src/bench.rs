@@ -0,0 +1,164 @@
+//! `bench`: how fast this machine puts an account in place each way
+//! `switch` can, to choose a `switch-mode` by. A made-up configuration is
+//! written into the store and then copied, hard linked and symlinked to
+//! beside the Claude directory, as a switch would. Everything written is
+//! removed again, so nothing real is touched.
+
+use crate::fidelity;
+use crate::hashing;
+use crate::platform;
+use crate::settings::SwitchMode;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Files written, unless told otherwise: about as many as a configuration
+/// with a few projects holds
+pub const DEFAULT_FILES: usize = 500;
+/// The size of each, in KiB
+pub const DEFAULT_FILE_KIB: u64 = 16;
+
+// Files per directory, so the tree is walked like a real one
+const PER_DIR: usize = 50;
+
+/// How long putting the configuration in place took one way, or why it
+/// can't be done between these two directories.
+#[derive(Debug)]
+pub struct Measurement {
+    pub mode: SwitchMode,
+    pub elapsed: std::result::Result<Duration, String>,
+}
+
+/// Removes the scratch directories, however the run ends.
+struct Scratch<'a>(&'a [&'a Path]);
+
+impl Drop for Scratch<'_> {
+    fn drop(&mut self) {
+        // The symlink is inside a directory, so only it goes, not the store
+        for dir in self.0 {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
+
+/// Writes `files` files of `file_size` bytes into `store`, then measures
+/// each way of putting them at `target`. Neither may exist beforehand.
+pub fn run(store: &Path, target: &Path, files: usize, file_size: u64) -> Result<Vec<Measurement>> {
+    // Only what's written here is removed afterwards
+    for dir in [store, target] {
+        if dir.exists() {
+            anyhow::bail!("{} is in the way of the benchmark", dir.display());
+        }
+    }
+    let _scratch = Scratch(&[store, target]);
+    let written = write_sample(store, files, file_size)?;
+    fs::create_dir_all(target).context("Failed to create the benchmark directory")?;
+
+    let copied = target.join("copy");
+    let linked = target.join("hardlink");
+    let symlinked = target.join("symlink");
+    Ok(vec![
+        Measurement {
+            mode: SwitchMode::Copy,
+            elapsed: measure(|| copy(store, &copied, &written)),
+        },
+        Measurement {
+            mode: SwitchMode::Hardlink,
+            elapsed: measure(|| hard_link(store, &linked, &written)),
+        },
+        Measurement {
+            mode: SwitchMode::Symlink,
+            elapsed: measure(|| platform::symlink_dir(store, &symlinked)),
+        },
+    ])
+}
+
+fn measure(f: impl FnOnce() -> std::io::Result<()>) -> std::result::Result<Duration, String> {
+    let started = Instant::now();
+    f().map(|()| started.elapsed()).map_err(|e| e.to_string())
+}
+
+/// The sample configuration, as paths relative to `dir`. The contents
+/// vary, so neither compression nor sparse files make it cheaper to copy.
+fn write_sample(dir: &Path, files: usize, file_size: u64) -> Result<Vec<PathBuf>> {
+    let mut written = Vec::with_capacity(files);
+    for i in 0..files {
+        let relative = Path::new("projects")
+            .join(format!("project-{}", i / PER_DIR))
+            .join(format!("session-{}.jsonl", i));
+        let path = dir.join(&relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create the benchmark directory")?;
+        }
+        let contents: Vec<u8> = (0..file_size)
+            .map(|n| (n.wrapping_mul(31).wrapping_add(i as u64) % 251) as u8)
+            .collect();
+        fs::write(&path, contents).context("Failed to write the benchmark files")?;
+        written.push(relative);
+    }
+    Ok(written)
+}
+
+/// Like a copying switch: the directories first, then the files on a
+/// thread per core.
+fn copy(src: &Path, dst: &Path, files: &[PathBuf]) -> std::io::Result<()> {
+    create_parents(dst, files)?;
+    let mut failed = None;
+    let _ = hashing::for_each_parallel(
+        files.iter(),
+        hashing::default_jobs(),
+        |relative| {
+            let (from, to) = (src.join(relative), dst.join(relative));
+            fs::copy(&from, &to).and_then(|_| fidelity::keep_metadata(&from, &to))
+        },
+        |copied| {
+            if let Err(e) = copied {
+                failed.get_or_insert(e);
+            }
+            Ok(())
+        },
+    );
+    failed.map_or(Ok(()), Err)
+}
+
+/// Like a linking switch, one file after the other.
+fn hard_link(src: &Path, dst: &Path, files: &[PathBuf]) -> std::io::Result<()> {
+    create_parents(dst, files)?;
+    for relative in files {
+        fs::hard_link(src.join(relative), dst.join(relative))?;
+    }
+    Ok(())
+}
+
+fn create_parents(dst: &Path, files: &[PathBuf]) -> std::io::Result<()> {
+    for relative in files {
+        if let Some(parent) = dst.join(relative).parent() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_bench_measures_each_mode_and_cleans_up() -> Result<()> {
+        let root = TempDir::new()?;
+        let store = root.path().join("store");
+        let target = root.path().join("target");
+
+        let measurements = run(&store, &target, 12, 1024)?;
+        let modes: Vec<_> = measurements.iter().map(|m| m.mode).collect();
+        assert_eq!(
+            modes,
+            [SwitchMode::Copy, SwitchMode::Hardlink, SwitchMode::Symlink]
+        );
+        assert!(measurements[0].elapsed.is_ok());
+        assert!(!store.exists() && !target.exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,154 @@
+//! Per-project account bindings: a `.claude-account` file naming the account
+//! to use anywhere below the directory that holds it, or a mapping kept in
+//! the accounts file by `project set` for directories that shouldn't get one.
+
+use crate::config::AccountsConfig;
+use crate::system;
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const FILE: &str = ".claude-account";
+
+/// A binding found while walking up from a working directory.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Binding {
+    pub account: String,
+    /// The `.claude-account` file the binding came from, or the mapped
+    /// directory for one set with `project set`
+    pub source: PathBuf,
+}
+
+/// Finds the nearest binding in `start` or one of its parents. Empty files
+/// don't count, so one can be emptied to fall through to a parent's binding.
+pub fn find(start: &Path) -> Result<Option<Binding>> {
+    nearest(start, &BTreeMap::new())
+}
+
+/// Like `find`, with the directories in `projects` bound to their accounts
+/// too. The nearest binding of either kind wins; in the same directory the
+/// file does.
+pub fn nearest(start: &Path, projects: &BTreeMap<PathBuf, String>) -> Result<Option<Binding>> {
+    // Mappings are stored canonical, so compare against the real path
+    let start = match projects.is_empty() {
+        true => start.to_path_buf(),
+        false => fs::canonicalize(start).unwrap_or_else(|_| start.to_path_buf()),
+    };
+    for dir in start.ancestors() {
+        if let Some(binding) = read(dir)? {
+            return Ok(Some(binding));
+        }
+        if let Some(account) = projects.get(dir) {
+            return Ok(Some(Binding {
+                account: account.clone(),
+                source: dir.to_path_buf(),
+            }));
+        }
+    }
+    Ok(None)
+}
+
+/// The binding in `dir`'s own `.claude-account` file, if it has one.
+fn read(dir: &Path) -> Result<Option<Binding>> {
+    let path = dir.join(FILE);
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    let contents = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read binding file: {}", path.display()))?;
+    let account = contents
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty());
+    Ok(account.map(|account| Binding {
+        account: account.to_string(),
+        source: path,
+    }))
+}
+
+/// The mappings `project set` keeps, read straight from the accounts file
+/// for the directory hook, which runs before the manager is set up. Empty
+/// when there's no readable file.
+pub fn projects() -> BTreeMap<PathBuf, String> {
+    system::accounts_contents()
+        .and_then(|contents| serde_json::from_str::<AccountsConfig>(&contents).ok())
+        .map(|config| config.projects)
+        .unwrap_or_default()
+}
+
+pub fn write(dir: &Path, account: &str) -> Result<PathBuf> {
+    let path = dir.join(FILE);
+    fs::write(&path, format!("{}\n", account))
+        .with_context(|| format!("Failed to write binding file: {}", path.display()))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_find_walks_up() -> Result<()> {
+        let dir = TempDir::new()?;
+        let nested = dir.path().join("src/deep");
+        fs::create_dir_all(&nested)?;
+        let source = write(dir.path(), "client")?;
+
+        let binding = find(&nested)?.unwrap();
+        assert_eq!(binding.account, "client");
+        assert_eq!(binding.source, source);
+        Ok(())
+    }
+
+    #[test]
+    fn test_nearest_binding_wins() -> Result<()> {
+        let dir = TempDir::new()?;
+        let nested = dir.path().join("oss");
+        fs::create_dir_all(&nested)?;
+        write(dir.path(), "work")?;
+        write(&nested, "personal")?;
+
+        assert_eq!(find(&nested)?.unwrap().account, "personal");
+        Ok(())
+    }
+
+    #[test]
+    fn test_mapping_and_file_nearest_wins() -> Result<()> {
+        let dir = TempDir::new()?;
+        let root = fs::canonicalize(dir.path())?;
+        let nested = root.join("client/app");
+        fs::create_dir_all(&nested)?;
+        let projects = BTreeMap::from([(root.join("client"), "client".to_string())]);
+
+        let binding = nearest(&nested, &projects)?.unwrap();
+        assert_eq!(binding.account, "client");
+        assert_eq!(binding.source, root.join("client"));
+
+        // A file further down is nearer; one in the same directory wins too
+        write(&nested, "personal")?;
+        assert_eq!(nearest(&nested, &projects)?.unwrap().account, "personal");
+        write(&root.join("client"), "work")?;
+        assert_eq!(
+            nearest(&root.join("client"), &projects)?.unwrap().account,
+            "work"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_file_is_skipped() -> Result<()> {
+        let dir = TempDir::new()?;
+        let nested = dir.path().join("scratch");
+        fs::create_dir_all(&nested)?;
+        fs::write(nested.join(FILE), "\n")?;
+
+        // Any binding above the temp dir would be picked up, so only check
+        // that the empty file itself didn't match
+        let found = find(&nested)?;
+        assert!(found.is_none_or(|binding| binding.source != nested.join(FILE)));
+        Ok(())
+    }
+}
@@ -0,0 +1,158 @@
+//! What this machine supports, probed once and cached in the state
+//! directory, so features that need more than plain files can be on by
+//! default and fall back quietly where they don't work.
+
+use crate::credstore::CredentialStore;
+use crate::platform;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+pub const FILE: &str = "capabilities.json";
+
+// Long enough that probing never slows down everyday commands, short enough
+// that an OS update or a newly unlocked keychain is noticed
+const MAX_AGE_DAYS: i64 = 7;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Capabilities {
+    /// The version that probed; another version probes again
+    pub version: String,
+    pub probed_at: DateTime<Utc>,
+    /// Directory symlinks can be created (Windows needs developer mode)
+    pub symlinks: bool,
+    /// The OS keychain answers
+    pub keychain: bool,
+    /// Files on the state directory's filesystem can be cloned, which makes
+    /// unencrypted copies nearly free
+    pub reflink: bool,
+}
+
+impl Capabilities {
+    /// Everything on, for tests and embedders that manage the environment.
+    pub fn assumed(now: DateTime<Utc>) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            probed_at: now,
+            symlinks: true,
+            keychain: true,
+            reflink: true,
+        }
+    }
+
+    /// Probes `dir` (the state directory) and the keychain.
+    pub fn probe(dir: &Path, keychain: &dyn CredentialStore, now: DateTime<Utc>) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            probed_at: now,
+            symlinks: probe_symlinks(dir),
+            keychain: keychain.is_available(),
+            reflink: probe_reflink(dir),
+        }
+    }
+
+    /// The cached result when it's recent and from this version, otherwise a
+    /// fresh probe, which is cached in turn.
+    pub fn load_or_probe(dir: &Path, keychain: &dyn CredentialStore, now: DateTime<Utc>) -> Self {
+        let path = dir.join(FILE);
+        let cached = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<Self>(&contents).ok())
+            .filter(|cached| {
+                cached.version == env!("CARGO_PKG_VERSION")
+                    && now - cached.probed_at < Duration::days(MAX_AGE_DAYS)
+            });
+        if let Some(cached) = cached {
+            return cached;
+        }
+
+        let probed = Self::probe(dir, keychain, now);
+        // An unwritable cache only means probing again next time
+        let _ = probed.save(&path);
+        probed
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents =
+            serde_json::to_string_pretty(self).context("Failed to serialize capabilities")?;
+        fs::write(path, contents).context("Failed to write capabilities file")
+    }
+}
+
+fn probe_symlinks(dir: &Path) -> bool {
+    let target = dir.join(format!(".cas-probe-{}", std::process::id()));
+    let link = dir.join(format!(".cas-probe-link-{}", std::process::id()));
+    if fs::create_dir_all(&target).is_err() {
+        return false;
+    }
+
+    let linked = platform::symlink_dir(&target, &link).is_ok();
+    if linked {
+        let _ = platform::remove_symlink(&link);
+    }
+    let _ = fs::remove_dir(&target);
+    linked
+}
+
+fn probe_reflink(dir: &Path) -> bool {
+    let source = dir.join(format!(".cas-probe-{}.src", std::process::id()));
+    let clone = dir.join(format!(".cas-probe-{}.dst", std::process::id()));
+    if fs::write(&source, b"probe").is_err() {
+        return false;
+    }
+
+    let cloned = platform::clone_file(&source, &clone).is_ok();
+    let _ = fs::remove_file(&source);
+    let _ = fs::remove_file(&clone);
+    cloned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credstore::MemoryStore;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_probe_is_cached() -> Result<()> {
+        let dir = TempDir::new()?;
+        let now = Utc::now();
+        let probed = Capabilities::load_or_probe(dir.path(), &MemoryStore::default(), now);
+        assert!(probed.keychain);
+        assert!(dir.path().join(FILE).exists());
+        // Probe files are cleaned up
+        assert_eq!(fs::read_dir(dir.path())?.count(), 1);
+
+        let mut cached = probed.clone();
+        cached.symlinks = !probed.symlinks;
+        cached.save(&dir.path().join(FILE))?;
+        assert_eq!(
+            Capabilities::load_or_probe(dir.path(), &MemoryStore::default(), now),
+            cached
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_stale_cache_is_probed_again() -> Result<()> {
+        let dir = TempDir::new()?;
+        let now = Utc::now();
+        let mut stale = Capabilities::assumed(now - Duration::days(MAX_AGE_DAYS + 1));
+        stale.version = "0.0.0".to_string();
+        stale.save(&dir.path().join(FILE))?;
+
+        let probed = Capabilities::load_or_probe(dir.path(), &MemoryStore::default(), now);
+        assert_eq!(probed.probed_at, now);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_probe_on_unix() -> Result<()> {
+        let dir = TempDir::new()?;
+        assert!(probe_symlinks(dir.path()));
+        Ok(())
+    }
+}
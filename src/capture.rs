@@ -0,0 +1,255 @@
+//! Gitignore-style patterns deciding which files under `~/.claude` a
+//! snapshot captures.
+//!
+//! A pattern without a `/` matches a file or directory name at any depth, one
+//! containing a `/` is matched from the top of the directory, and a trailing
+//! `/` matches directories only. `*` and `?` match within a single path
+//! segment and `**` matches any number of segments. A pattern that matches a
+//! directory also matches everything below it.
+
+use crate::claude;
+use anyhow::Result;
+use std::path::Path;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    segments: Vec<String>,
+    anchored: bool,
+    dir_only: bool,
+}
+
+impl Pattern {
+    pub fn parse(pattern: &str) -> Result<Self> {
+        let trimmed = pattern.trim();
+        let dir_only = trimmed.ends_with('/');
+        let body = trimmed.trim_end_matches('/');
+        let anchored = body.contains('/');
+
+        let segments: Vec<String> = body
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .map(str::to_string)
+            .collect();
+        if segments.is_empty() {
+            anyhow::bail!("Invalid capture pattern '{}'", pattern);
+        }
+
+        Ok(Self {
+            segments,
+            anchored,
+            dir_only,
+        })
+    }
+
+    /// Whether `path` (relative to the snapshot root) or one of its parent
+    /// directories matches.
+    pub fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        let parts: Vec<String> = path
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect();
+
+        (1..=parts.len())
+            .any(|len| self.matches_exactly(&parts[..len], len < parts.len() || is_dir))
+    }
+
+    fn matches_exactly(&self, parts: &[String], is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+
+        if self.anchored {
+            match_segments(&self.segments, parts)
+        } else {
+            parts
+                .last()
+                .is_some_and(|name| glob_match(&self.segments[0], name))
+        }
+    }
+}
+
+/// Which files a save copies: everything not excluded, narrowed down to the
+/// include patterns when there are any. Excludes win over includes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CapturePolicy {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+    credentials_only: bool,
+}
+
+impl CapturePolicy {
+    pub fn new<S: AsRef<str>>(include: &[S], exclude: &[S]) -> Result<Self> {
+        let parse = |patterns: &[S]| {
+            patterns
+                .iter()
+                .map(|pattern| Pattern::parse(pattern.as_ref()))
+                .collect::<Result<Vec<_>>>()
+        };
+
+        Ok(Self {
+            include: parse(include)?,
+            exclude: parse(exclude)?,
+            credentials_only: false,
+        })
+    }
+
+    /// Narrows the policy down to the credential files at the top of the
+    /// directory, so everything else stays where it is, shared by every
+    /// account.
+    pub fn credentials_only(mut self) -> Self {
+        self.credentials_only = true;
+        self
+    }
+
+    /// True when everything is captured.
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty() && !self.credentials_only
+    }
+
+    pub fn has_includes(&self) -> bool {
+        !self.include.is_empty() || self.credentials_only
+    }
+
+    /// Excluded directories don't need to be walked at all.
+    pub fn skips_dir(&self, path: &Path) -> bool {
+        self.credentials_only
+            || self
+                .exclude
+                .iter()
+                .any(|pattern| pattern.matches(path, true))
+    }
+
+    pub fn captures(&self, path: &Path) -> bool {
+        if self.credentials_only
+            && (path.components().count() != 1 || !claude::is_credential_file(path))
+        {
+            return false;
+        }
+        if self
+            .exclude
+            .iter()
+            .any(|pattern| pattern.matches(path, false))
+        {
+            return false;
+        }
+        self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|pattern| pattern.matches(path, false))
+    }
+}
+
+fn match_segments(pattern: &[String], parts: &[String]) -> bool {
+    match pattern.split_first() {
+        None => parts.is_empty(),
+        Some((first, rest)) if first == "**" => {
+            (0..=parts.len()).any(|skip| match_segments(rest, &parts[skip..]))
+        }
+        Some((first, rest)) => parts.split_first().is_some_and(|(name, remaining)| {
+            glob_match(first, name) && match_segments(rest, remaining)
+        }),
+    }
+}
+
+/// Whether `text` matches `pattern`, where `*` is any run of characters and
+/// `?` any one.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    fn matches(pattern: &[char], text: &[char]) -> bool {
+        match pattern.split_first() {
+            None => text.is_empty(),
+            Some(('*', rest)) => (0..=text.len()).any(|skip| matches(rest, &text[skip..])),
+            Some(('?', rest)) => !text.is_empty() && matches(rest, &text[1..]),
+            Some((c, rest)) => text.first() == Some(c) && matches(rest, &text[1..]),
+        }
+    }
+
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    matches(&pattern, &text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(pattern: &str) -> Pattern {
+        Pattern::parse(pattern).unwrap()
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.json", "settings.json"));
+        assert!(glob_match("settings.?son", "settings.json"));
+        assert!(!glob_match("*.json", "notes.md"));
+        assert!(glob_match("*", ""));
+    }
+
+    #[test]
+    fn test_unanchored_pattern_matches_at_any_depth() {
+        let p = pattern("*.jsonl");
+        assert!(p.matches(Path::new("log.jsonl"), false));
+        assert!(p.matches(Path::new("projects/foo/log.jsonl"), false));
+        assert!(!p.matches(Path::new("settings.json"), false));
+    }
+
+    #[test]
+    fn test_directory_pattern_covers_contents() {
+        let p = pattern("cache/");
+        assert!(p.matches(Path::new("cache"), true));
+        assert!(p.matches(Path::new("cache/blob"), false));
+        assert!(p.matches(Path::new("nested/cache/blob"), false));
+        // A file named like the directory isn't a directory
+        assert!(!p.matches(Path::new("cache"), false));
+    }
+
+    #[test]
+    fn test_anchored_and_double_star_patterns() {
+        let p = pattern("/projects");
+        assert!(p.matches(Path::new("projects/a/b.jsonl"), false));
+        assert!(!p.matches(Path::new("nested/projects"), true));
+
+        let p = pattern("agents/**/*.md");
+        assert!(p.matches(Path::new("agents/reviewer.md"), false));
+        assert!(p.matches(Path::new("agents/team/reviewer.md"), false));
+        assert!(!p.matches(Path::new("commands/reviewer.md"), false));
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        assert!(Pattern::parse("").is_err());
+        assert!(Pattern::parse("/").is_err());
+    }
+
+    #[test]
+    fn test_policy_excludes_win() -> Result<()> {
+        let policy = CapturePolicy::new(&["*.json"], &["projects/"])?;
+        assert!(policy.captures(Path::new("settings.json")));
+        assert!(!policy.captures(Path::new("CLAUDE.md")));
+        assert!(!policy.captures(Path::new("projects/foo/state.json")));
+        assert!(policy.skips_dir(Path::new("projects")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_credentials_only_policy() -> Result<()> {
+        let policy = CapturePolicy::default().credentials_only();
+        assert!(!policy.is_empty());
+        assert!(policy.captures(Path::new(".credentials.json")));
+        assert!(!policy.captures(Path::new("settings.json")));
+        assert!(!policy.captures(Path::new("projects/.credentials.json")));
+        assert!(policy.skips_dir(Path::new("projects")));
+
+        let policy = CapturePolicy::new(&[], &[".credentials.json"])?.credentials_only();
+        assert!(!policy.captures(Path::new(".credentials.json")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_policy_captures_everything() {
+        let policy = CapturePolicy::default();
+        assert!(policy.is_empty());
+        assert!(policy.captures(Path::new("projects/foo/log.jsonl")));
+        assert!(!policy.skips_dir(Path::new("projects")));
+    }
+}
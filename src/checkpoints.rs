@@ -0,0 +1,194 @@
+//! Named copies of the live directory, taken with `checkpoint save` before
+//! trying out risky settings or a new Claude Code version and put back with
+//! `checkpoint restore`. They belong to no account.
+//!
+//! They're kept beside the live directory, in `~/.claude.cas-checkpoints`,
+//! each in a directory named for its label: the copy in `live/` and what it
+//! is in `checkpoint.json`. Past `keep-checkpoints` the oldest go.
+
+use crate::platform;
+use crate::retry::retry;
+use crate::tr;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Appended to the live directory's path for the directory they're kept in
+pub const SUFFIX: &str = ".cas-checkpoints";
+/// How many are kept unless `keep-checkpoints` says otherwise
+pub const DEFAULT_KEEP: usize = 10;
+/// Appended to the live directory's path for a checkpoint being put in place
+pub const RESTORING_SUFFIX: &str = ".cas-restoring";
+
+const META_FILE: &str = "checkpoint.json";
+const LIVE_DIR: &str = "live";
+// Where a checkpoint is copied before it replaces one of the same label
+const INCOMING: &str = ".incoming";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    pub label: String,
+    pub created_at: DateTime<Utc>,
+    /// The account that was active when it was taken, when one was
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
+    /// The directory it's kept in
+    #[serde(skip)]
+    pub path: PathBuf,
+}
+
+impl Checkpoint {
+    /// The copy of the live directory.
+    pub fn live(&self) -> PathBuf {
+        self.path.join(LIVE_DIR)
+    }
+}
+
+/// Fails unless `label` can name a checkpoint.
+pub fn validate(label: &str) -> Result<()> {
+    if label.trim().is_empty() || label.starts_with('.') {
+        anyhow::bail!(tr!("checkpoint-bad-label", label = label));
+    }
+    Ok(())
+}
+
+/// Every checkpoint in `area`, newest first. Directories without a readable
+/// `checkpoint.json` (one being written, say) are left out.
+pub fn list(area: &Path) -> Result<Vec<Checkpoint>> {
+    if !area.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut checkpoints = Vec::new();
+    for entry in fs::read_dir(area).context("Failed to read checkpoints")? {
+        let entry = entry.context("Failed to read checkpoints")?;
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let Ok(contents) = fs::read_to_string(entry.path().join(META_FILE)) else {
+            continue;
+        };
+        let Ok(mut checkpoint) = serde_json::from_str::<Checkpoint>(&contents) else {
+            continue;
+        };
+        checkpoint.path = entry.path();
+        checkpoints.push(checkpoint);
+    }
+    checkpoints.sort_by(|a, b| {
+        b.created_at
+            .cmp(&a.created_at)
+            .then_with(|| a.label.cmp(&b.label))
+    });
+    Ok(checkpoints)
+}
+
+pub fn find(area: &Path, label: &str) -> Result<Option<Checkpoint>> {
+    Ok(list(area)?
+        .into_iter()
+        .find(|checkpoint| checkpoint.label == label))
+}
+
+/// An empty directory in `area` to copy the live directory into, with the
+/// `live/` directory to copy it to. [`commit`] puts it in place.
+pub fn incoming(area: &Path) -> Result<(PathBuf, PathBuf)> {
+    let dir = area.join(INCOMING);
+    if dir.exists() {
+        retry(|| fs::remove_dir_all(&dir)).context("Failed to clear an unfinished checkpoint")?;
+    }
+    let live = dir.join(LIVE_DIR);
+    fs::create_dir_all(&live).context("Failed to create checkpoints directory")?;
+    Ok((dir, live))
+}
+
+/// Records `checkpoint` for the copy in `incoming` and moves it to its
+/// label's place, replacing what was there.
+pub fn commit(area: &Path, incoming: &Path, mut checkpoint: Checkpoint) -> Result<Checkpoint> {
+    let contents =
+        serde_json::to_string_pretty(&checkpoint).context("Failed to serialize checkpoint")?;
+    fs::write(incoming.join(META_FILE), contents).context("Failed to write checkpoint")?;
+    let path = area.join(escape(&checkpoint.label));
+    if path.exists() {
+        remove(&path)?;
+    }
+    retry(|| fs::rename(incoming, &path)).context("Failed to keep checkpoint")?;
+    checkpoint.path = path;
+    Ok(checkpoint)
+}
+
+/// Removes all but the newest `keep`, returning the labels removed.
+pub fn prune(area: &Path, keep: usize) -> Result<Vec<String>> {
+    let mut removed = Vec::new();
+    for checkpoint in list(area)?.into_iter().skip(keep) {
+        remove(&checkpoint.path)?;
+        removed.push(checkpoint.label);
+    }
+    Ok(removed)
+}
+
+/// Removes the checkpoint kept in `path`. The live directory it was taken
+/// from may have been a symlink, which is removed rather than followed.
+pub fn remove(path: &Path) -> Result<()> {
+    let live = path.join(LIVE_DIR);
+    if live.is_symlink() {
+        platform::remove_symlink(&live).context("Failed to remove checkpoint")?;
+    }
+    retry(|| fs::remove_dir_all(path)).context("Failed to remove checkpoint")
+}
+
+// Labels may contain `/`
+fn escape(label: &str) -> String {
+    label.replace('%', "%25").replace('/', "%2F")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_commit_list_and_prune() -> Result<()> {
+        let dir = TempDir::new()?;
+        let area = dir.path().join("area");
+        let at = |minute| Utc.with_ymd_and_hms(2024, 1, 1, 9, minute, 0).unwrap();
+        let save = |label: &str, minute, account: Option<&str>| -> Result<Checkpoint> {
+            let (incoming, live) = incoming(&area)?;
+            fs::write(live.join("settings.json"), label)?;
+            commit(
+                &area,
+                &incoming,
+                Checkpoint {
+                    label: label.to_string(),
+                    created_at: at(minute),
+                    account: account.map(str::to_string),
+                    path: PathBuf::new(),
+                },
+            )
+        };
+
+        save("before/upgrade", 0, Some("work"))?;
+        save("risky", 5, None)?;
+        let replaced = save("before/upgrade", 10, None)?;
+        assert!(replaced.path.ends_with("before%2Fupgrade"));
+        assert_eq!(
+            fs::read_to_string(replaced.live().join("settings.json"))?,
+            "before/upgrade"
+        );
+
+        let labels: Vec<_> = list(&area)?
+            .into_iter()
+            .map(|checkpoint| checkpoint.label)
+            .collect();
+        assert_eq!(labels, ["before/upgrade", "risky"]);
+        assert_eq!(find(&area, "risky")?.unwrap().created_at, at(5));
+        assert!(find(&area, "missing")?.is_none());
+
+        assert_eq!(prune(&area, 1)?, ["risky"]);
+        assert_eq!(list(&area)?.len(), 1);
+        assert!(validate(".incoming").is_err());
+        assert!(validate(" ").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,213 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::path::Path;
+
+/// Files inside `~/.claude` that hold login secrets rather than settings.
+pub const CREDENTIAL_FILES: &[&str] = &[".credentials.json"];
+
+/// Claude Code's user settings, inside `~/.claude`.
+pub const SETTINGS_FILE: &str = "settings.json";
+
+pub fn is_credential_file(path: &Path) -> bool {
+    let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+
+    CREDENTIAL_FILES.contains(&file_name) || file_name.to_ascii_lowercase().contains("credential")
+}
+
+// Directories of the state Claude Code keeps per session, and of the
+// plugins it installs and updates
+const REWRITTEN_DIRS: &[&str] = &["todos", "statsig", "shell-snapshots", "ide", "plugins"];
+
+/// The memory file Claude Code edits when asked to remember something
+const MEMORY_FILE: &str = "CLAUDE.md";
+
+/// Whether Claude Code writes the file at `relative` as it runs, rather than
+/// only reading it: the login, the settings and memory, the history and
+/// transcripts it appends to, its plugins and its per-session state.
+pub fn is_rewritten(relative: &Path) -> bool {
+    let in_rewritten_dir = relative
+        .components()
+        .next()
+        .is_some_and(|first| REWRITTEN_DIRS.iter().any(|dir| first.as_os_str() == *dir));
+    let file_name = relative.file_name().and_then(|name| name.to_str());
+    in_rewritten_dir
+        || relative == Path::new(MEMORY_FILE)
+        || is_credential_file(relative)
+        || file_name.is_some_and(|name| name.starts_with("settings") && name.ends_with(".json"))
+        || relative.extension().is_some_and(|ext| ext == "jsonl")
+}
+
+/// What a credentials file reveals about the login, with the token redacted.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Identity {
+    pub plan: Option<String>,
+    pub token: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Reads the OAuth login from the contents of `.credentials.json`.
+pub fn read_identity(credentials: &str) -> Option<Identity> {
+    let value: Value = serde_json::from_str(credentials).ok()?;
+    let oauth = value.get("claudeAiOauth")?;
+
+    Some(Identity {
+        plan: oauth
+            .get("subscriptionType")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        token: oauth.get("accessToken").and_then(Value::as_str).map(redact),
+        expires_at: oauth
+            .get("expiresAt")
+            .and_then(Value::as_i64)
+            .and_then(DateTime::from_timestamp_millis),
+    })
+}
+
+/// The OAuth access token itself, unredacted, for asking the API about it.
+pub fn read_access_token(credentials: &str) -> Option<String> {
+    let value: Value = serde_json::from_str(credentials).ok()?;
+    value
+        .get("claudeAiOauth")?
+        .get("accessToken")?
+        .as_str()
+        .filter(|token| !token.is_empty())
+        .map(str::to_string)
+}
+
+/// The API key Claude Code was set up with (`primaryApiKey` in
+/// `.claude.json`), when it uses one instead of a login.
+pub fn read_api_key(claude_json: &str) -> Option<String> {
+    let value: Value = serde_json::from_str(claude_json).ok()?;
+    value
+        .get("primaryApiKey")?
+        .as_str()
+        .filter(|key| !key.is_empty())
+        .map(str::to_string)
+}
+
+/// Reads the signed-in email from `.claude.json`, which Claude Code keeps
+/// inside the config directory when `CLAUDE_CONFIG_DIR` points there.
+pub fn read_email(claude_json: &str) -> Option<String> {
+    read_oauth_account(claude_json, "emailAddress")
+}
+
+/// Reads the signed-in organization's name from `.claude.json`.
+pub fn read_organization(claude_json: &str) -> Option<String> {
+    read_oauth_account(claude_json, "organizationName")
+}
+
+fn read_oauth_account(claude_json: &str, key: &str) -> Option<String> {
+    let value: Value = serde_json::from_str(claude_json).ok()?;
+    value
+        .get("oauthAccount")?
+        .get(key)?
+        .as_str()
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+/// Keeps just enough of a secret to tell two apart.
+pub fn redact(secret: &str) -> String {
+    let chars: Vec<char> = secret.chars().collect();
+    if chars.len() < 16 {
+        return "****".to_string();
+    }
+
+    let head: String = chars[..8].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}…{}", head, tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_api_key() {
+        assert_eq!(
+            read_api_key(r#"{"primaryApiKey": "sk-ant-api03-x"}"#).as_deref(),
+            Some("sk-ant-api03-x")
+        );
+        assert_eq!(read_api_key(r#"{"primaryApiKey": ""}"#), None);
+        assert_eq!(read_api_key("{}"), None);
+    }
+
+    #[test]
+    fn test_is_rewritten() {
+        for path in [
+            ".credentials.json",
+            "settings.json",
+            "settings.local.json",
+            "history.jsonl",
+            "projects/-home-me-app/0b1c.jsonl",
+            "todos/0b1c-agent.json",
+            "CLAUDE.md",
+            "plugins/config.json",
+        ] {
+            assert!(is_rewritten(Path::new(path)), "{}", path);
+        }
+        for path in [
+            "agents/reviewer.md",
+            "agents/CLAUDE.md",
+            "commands/todos.md",
+        ] {
+            assert!(!is_rewritten(Path::new(path)), "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_is_credential_file() {
+        assert!(is_credential_file(Path::new(".credentials.json")));
+        assert!(is_credential_file(Path::new("nested/.credentials.json")));
+        assert!(is_credential_file(Path::new("Credentials.backup")));
+        assert!(!is_credential_file(Path::new("settings.json")));
+        assert!(!is_credential_file(Path::new("CLAUDE.md")));
+    }
+
+    #[test]
+    fn test_read_identity() {
+        let credentials = r#"{"claudeAiOauth": {
+            "accessToken": "sk-ant-REDACTED",
+            "expiresAt": 1767225600000,
+            "subscriptionType": "max"
+        }}"#;
+
+        let identity = read_identity(credentials).unwrap();
+        assert_eq!(identity.plan.as_deref(), Some("max"));
+        assert_eq!(identity.token.as_deref(), Some("sk-ant-o…mnop"));
+        assert_eq!(
+            identity.expires_at.unwrap().to_rfc3339(),
+            "2026-01-01T00:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_read_identity_without_oauth() {
+        assert_eq!(read_identity(r#"{"other": {}}"#), None);
+        assert_eq!(read_identity("not json"), None);
+    }
+
+    #[test]
+    fn test_redact_short_secret() {
+        assert_eq!(redact("short"), "****");
+    }
+
+    #[test]
+    fn test_read_email() {
+        let json = r#"{"oauthAccount": {"emailAddress": "dev@example.com"}}"#;
+        assert_eq!(read_email(json).as_deref(), Some("dev@example.com"));
+        assert_eq!(read_email("{}"), None);
+        assert_eq!(read_email("not json"), None);
+    }
+
+    #[test]
+    fn test_read_organization() {
+        let json =
+            r#"{"oauthAccount": {"emailAddress": "dev@example.com", "organizationName": "Acme"}}"#;
+        assert_eq!(read_organization(json).as_deref(), Some("Acme"));
+        let json = r#"{"oauthAccount": {"organizationName": ""}}"#;
+        assert_eq!(read_organization(json), None);
+    }
+}
@@ -0,0 +1,121 @@
+//! Which version of Claude Code is installed, recorded with each save and
+//! checked on switch: its configuration format changes between releases, so
+//! a snapshot saved under one can confuse another.
+//!
+//! Asking `claude --version` starts the program, so the answer is cached in
+//! the state directory for as long as the program found on `PATH` is the
+//! same file, unchanged.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::SystemTime;
+
+pub const FILE: &str = "claude-version.json";
+
+/// Claude Code's command, as installed
+const PROGRAM: &str = "claude";
+
+#[derive(Serialize, Deserialize, PartialEq)]
+struct Cached {
+    program: PathBuf,
+    modified: SystemTime,
+    version: String,
+}
+
+/// The version of the `claude` on `PATH`, or nothing when there's none or
+/// it can't say. `state_dir` keeps the answer for next time.
+pub fn installed(state_dir: &Path) -> Option<String> {
+    // The native installer links `claude` to the release it last installed
+    let program = find_program()?.canonicalize().ok()?;
+    let modified = fs::metadata(&program).and_then(|m| m.modified()).ok()?;
+    let path = state_dir.join(FILE);
+    let cached = fs::read_to_string(&path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<Cached>(&contents).ok())
+        .filter(|cached| cached.program == program && cached.modified == modified);
+    if let Some(cached) = cached {
+        return Some(cached.version);
+    }
+
+    let output = Command::new(&program)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let version = parse(&String::from_utf8_lossy(&output.stdout))?;
+    // An unwritable cache only means asking again next time
+    if let Ok(contents) = serde_json::to_string_pretty(&Cached {
+        program,
+        modified,
+        version: version.clone(),
+    }) {
+        let _ = fs::write(&path, contents);
+    }
+    Some(version)
+}
+
+fn find_program() -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    let name = match cfg!(windows) {
+        true => format!("{}.exe", PROGRAM),
+        false => PROGRAM.to_string(),
+    };
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(&name))
+        .find(|candidate| candidate.is_file())
+}
+
+/// The version in what `claude --version` printed: `2.0.14 (Claude Code)`.
+pub fn parse(output: &str) -> Option<String> {
+    output
+        .split_whitespace()
+        .next()
+        .filter(|word| word.starts_with(|c: char| c.is_ascii_digit()) && word.contains('.'))
+        .map(str::to_string)
+}
+
+/// Whether configurations saved under `saved` and read by `installed` may
+/// differ in format: their major or minor versions aren't the same. Patch
+/// releases leave the format alone.
+pub fn drifted(saved: &str, installed: &str) -> bool {
+    release(saved) != release(installed)
+}
+
+/// Whether `installed` is what the account is pinned to: `pin` itself, or a
+/// release of it when `pin` leaves out the later parts (`2.0` for 2.0.14).
+pub fn satisfies(installed: &str, pin: &str) -> bool {
+    installed == pin
+        || installed
+            .strip_prefix(pin)
+            .is_some_and(|rest| rest.starts_with(['.', '-', '+']))
+}
+
+// The major and minor numbers
+fn release(version: &str) -> (Option<u64>, Option<u64>) {
+    let mut parts = version.split(['.', '-', '+']).map(|part| part.parse().ok());
+    (parts.next().flatten(), parts.next().flatten())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_are_compared_by_release() {
+        assert_eq!(parse("2.0.14 (Claude Code)\n").as_deref(), Some("2.0.14"));
+        assert_eq!(parse("command not found"), None);
+
+        assert!(!drifted("2.0.14", "2.0.31"));
+        assert!(drifted("1.0.120", "2.0.1"));
+        assert!(drifted("2.0.14", "2.1.0-dev.1"));
+
+        assert!(satisfies("2.0.14", "2.0.14"));
+        assert!(satisfies("2.0.14", "2.0"));
+        assert!(!satisfies("2.0.14", "2.0.1"));
+        assert!(!satisfies("2.10.0", "2.1"));
+    }
+}
@@ -1,20 +1,1583 @@
-use clap::{Parser, Subcommand};
+use crate::bench;
+use crate::envfile;
+use crate::hashing;
+use crate::history::EventSource;
+use crate::import_from::Tool;
+use crate::menu::Launcher;
+use crate::output::{ColorChoice, Column, Document, OutputFormat};
+use crate::remote;
+use crate::segment;
+use crate::service::Schedule;
+use crate::settings::{self, SwitchMode};
+use crate::tr;
+use anyhow::Result;
+use clap::builder::PossibleValuesParser;
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(name = "claude-account")]
 #[command(version, about = "Manage multiple Claude Code CLI accounts")]
 #[command(author = "")]
 pub struct Cli {
+    /// How results are printed
+    #[arg(long, global = true, value_enum, conflicts_with_all = ["plain", "json"])]
+    pub format: Option<Format>,
+
+    /// Screen-reader friendly output: labelled lines, no symbols or alignment
+    #[arg(long, global = true, conflicts_with = "json")]
+    pub plain: bool,
+
+    /// Print results as JSON on stdout, for scripts; same as `--format json`
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// When to color output: as the `color` setting says, or on a terminal
+    /// unless NO_COLOR is set; never with `--plain`
+    #[arg(long, global = true, value_enum, value_name = "WHEN")]
+    pub color: Option<ColorChoice>,
+
+    /// When another instance is changing accounts, wait for it however long
+    /// it takes (by default, up to 30 seconds)
+    #[arg(long, global = true, conflicts_with = "no_wait")]
+    pub wait: bool,
+
+    /// When another instance is changing accounts, fail straight away
+    #[arg(long, global = true)]
+    pub no_wait: bool,
+
+    /// Log what is done to the files on stderr: -v for each step, -vv for
+    /// each file as well, -vvv for the files skipped too
+    #[arg(short, long, global = true, action = ArgAction::Count, conflicts_with = "quiet")]
+    pub verbose: u8,
+
+    /// Leave out progress and confirmation messages; results, warnings and
+    /// errors are still printed
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+
+    /// Report on stderr how long scanning, hashing, copying and the
+    /// accounts file took
+    #[arg(long, global = true)]
+    pub timings: bool,
+
+    /// Answer yes to every confirmation, for scripts, as `confirm = false`
+    /// does; without it a confirmation fails when stdin isn't a terminal
+    #[arg(short, long, global = true)]
+    pub yes: bool,
+
+    /// Claude Code's configuration directory, if not ~/.claude (also taken
+    /// from CLAUDE_CONFIG_DIR or the claude-dir setting)
+    #[arg(long, global = true, value_name = "DIR")]
+    pub claude_dir: Option<PathBuf>,
+
+    /// Use the system-wide store shared by every user on the machine (also
+    /// the system setting)
+    #[arg(long, global = true)]
+    pub system: bool,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
 
+// Each command's arguments are a struct of their own, so clap builds them in
+// a function of their own: inline, they made one stack frame of megabytes
 #[derive(Subcommand)]
 pub enum Commands {
-    Save { name: String },
-    Switch { name: String },
-    List,
-    Delete { name: String },
-    Rename { old_name: String, new_name: String },
+    /// Save the live configuration (~/.claude) as an account, or over one
+    Save(SaveArgs),
+    /// Switch to a saved account, putting its snapshot in place of ~/.claude
+    Switch(SwitchArgs),
+    /// List the saved accounts, marking the active one
+    List(ListArgs),
+    /// Show the disk space each account and its versions take, largest
+    /// first, and the store's total
+    Size(SizeArgs),
+    /// Log in to another account through Claude Code and save it as `name`,
+    /// then come back to the current account
+    Add(AddArgs),
+    /// Switch to an account, sign it in again and save the fresh login
+    Renew(RenewArgs),
+    /// Sign the live configuration out, saving the current account first
+    Logout(LogoutArgs),
+    /// Register a Claude directory kept elsewhere (e.g. in dotfiles) as an
+    /// account, used in place instead of copied into the store
+    AddExisting(AddExistingArgs),
+    /// Register a snapshot directory as an account: one in the store is used
+    /// where it is, one anywhere else is copied in
+    Adopt(AdoptArgs),
+    /// Remove accounts: names, aliases or globs (`'test-*'`)
+    Delete(DeleteArgs),
+    /// Require a confirmation and the typed account name before switching to it
+    Protect(ProtectArgs),
+    /// Only switch to an account while a given Claude Code version is
+    /// installed, for setups a newer configuration format would break
+    Pin(PinArgs),
+    /// Keep an account from being saved over, deleted or pruned without
+    /// --force; a switch away from it leaves the snapshot as it was
+    Lock(LockArgs),
+    /// Let an account be saved over, deleted and pruned again
+    Unlock(UnlockArgs),
+    /// Print the OAuth token or API key an account is signed in with, masked
+    /// unless --reveal, to use the account from SDK scripts or CI
+    Token(TokenArgs),
+    /// Print an account's OAuth token or API key, with the `env` of its
+    /// settings, as variables for a .env file or $GITHUB_ENV
+    Envfile(EnvfileArgs),
+    /// Pack a rarely used account into one compressed file (sealed when the
+    /// store is encrypted) and remove its snapshot directory
+    Archive(ArchiveArgs),
+    /// Unpack an archived account; switching to one does this on its own
+    Unarchive(UnarchiveArgs),
+    /// Record that an account (the active one by default) ran into a rate
+    /// limit, e.g. from a hook, so `list` shows it as limited until it lifts
+    LimitHit(LimitHitArgs),
+    /// Switch to the next account in the rotation (the `rotation` setting, or
+    /// all accounts by name), passing over rate-limited and expired ones
+    Rotate(RotateArgs),
+    /// List the files in the store and the live configuration holding tokens
+    /// or keys, flagging ones others can read or kept unencrypted
+    Audit,
+    /// Rank the accounts by how good a pick each is now: a working login,
+    /// no rate limit, the biggest plan, then the one rested longest
+    Suggest(SuggestArgs),
+    /// Pick the account to switch to from a launcher such as rofi or dmenu,
+    /// for binding to a global hotkey
+    Menu(MenuArgs),
+    /// Show or set an account's note: which client, email or billing it's for
+    Note(NoteArgs),
+    /// Label accounts (by client, team, ...) to filter and group `list` by
+    Tag(TagArgs),
+    /// Environment variables the shell hook exports while an account is
+    /// active, such as a client's proxy
+    Var(VarArgs),
+    /// Short names to switch with, as in `switch w`
+    Alias(AliasArgs),
+    /// Rename a saved account
+    Rename(RenameArgs),
+    /// Duplicate a saved account under a new name, e.g. to try other
+    /// settings with the same login. ~/.claude is left alone.
+    Copy(CopyArgs),
+    /// Settings-only profiles (settings.json, CLAUDE.md, MCP servers) to
+    /// layer over any account without touching its login
+    Template(TemplateArgs),
+    /// Carry the switcher's setup (settings and hooks, aliases, tags and
+    /// templates, but no account or login) to another machine as one file
+    Preset(PresetArgs),
+    /// An account together with environment variables (a model, a token
+    /// budget, ...), for one login used several ways
+    Profile(ProfileArgs),
+    /// Run one command under another account, then switch back to the
+    /// active one, even if the command fails or is interrupted
+    Run(RunArgs),
+    /// Run a command of the switcher on another machine over SSH, e.g.
+    /// `remote --host me@devbox switch work`; it has to be installed there,
+    /// and its JSON results are streamed back
+    Remote(RemoteArgs),
+    /// Print the exports that point Claude Code at an account's snapshot,
+    /// for using it in this terminal only: `eval "$(claude-account env work)"`
+    Env(EnvArgs),
+    /// Start a shell in which Claude Code uses an account's snapshot, leaving
+    /// the active account alone
+    Shell(ShellArgs),
+    /// List the terminals using an account through `env` or `shell`
+    Sessions,
+    /// Serve the accounts as MCP tools on stdin and stdout, for Claude or
+    /// another agent to list and switch them
+    McpServe,
+    /// Answer JSON-RPC requests on stdin and stdout, for editors and GUIs
+    /// that drive the switcher from one long-lived process
+    Api,
+    /// A background process answering `current`, `list` and `switch` over a
+    /// Unix socket, for prompts and scripts that ask often
+    Daemon(DaemonArgs),
+    /// Run the daemon or scheduled backups as user services, under systemd
+    /// or launchd
+    Service(ServiceArgs),
+    /// Print the active account for a shell prompt: fast, never writes
+    /// anything, and silent when no account is active
+    Prompt(PromptArgs),
+    /// Show the active account's plan, token expiry and unsaved changes,
+    /// then the store: accounts, size, the lock and what sync has pending.
+    /// `--porcelain` and `--when-stale-only` show the account alone, for
+    /// status bars
+    Status(StatusArgs),
+    /// Copy single files (settings.json, CLAUDE.md, ...) out of a saved
+    /// account into ~/.claude, without switching to it
+    Restore(RestoreArgs),
+    /// Print a file from a saved account without switching to it, with JSON
+    /// pretty-printed and secrets masked
+    Cat(CatArgs),
+    /// List the files in a saved account, or in one of its directories
+    Ls(LsArgs),
+    /// Copy settings files from one saved account into another, asking
+    /// before replacing any that differ
+    Merge(MergeArgs),
+    /// Show which files in ~/.claude changed since an account was saved: what
+    /// switching away without saving would lose
+    Diff(DiffArgs),
+    /// Print the active account, with who it's signed in as. With
+    /// `--verbose`, also when it was saved, whether ~/.claude drifted from
+    /// it since and when its token expires
     Current,
+    /// Exit with 0 when an account is saved under this name and 1 when not,
+    /// printing nothing
+    Exists(ExistsArgs),
+    /// Exit with 0 when this is the active account and 1 when not, printing
+    /// nothing
+    IsCurrent(IsCurrentArgs),
+    /// Show who an account is signed in as (email, organization, plan) and
+    /// where its snapshot lives
+    Info(InfoArgs),
+    /// Show what saved, switched or removed accounts, and what triggered it;
+    /// with an account name, list its earlier snapshots instead
+    #[command(visible_alias = "log")]
+    History(HistoryArgs),
+    /// Reverse the most recent switch, delete or rename
+    Undo,
+    /// Put back a live directory a switch replaced (the newest by default),
+    /// making the account that was active in it active again
+    RecoverLive(RecoverLiveArgs),
+    /// Finish or undo a save or switch that was stopped partway (killed,
+    /// the machine went down), asking which unless told
+    Recover(RecoverArgs),
+    /// Save named copies of the live configuration, whatever account is
+    /// active, and put one back, e.g. around trying a new Claude Code version
+    Checkpoint(CheckpointArgs),
+    /// List, restore or clear deleted accounts
+    Trash(TrashArgs),
+    /// Back up the whole store (every account, the history and settings) to
+    /// one archive, or restore it from one
+    Backup(BackupArgs),
+    /// Share the (encrypted) store between machines through a git repository
+    Sync(SyncArgs),
+    /// Put an earlier snapshot of an account back
+    Rollback(RollbackArgs),
+    /// Open an interactive dashboard of all accounts
+    Tui,
+    /// Create an encrypted, expiring bundle of an account for a teammate
+    Share(ShareArgs),
+    /// Write accounts to a portable archive for moving them to another machine
+    Export(ExportArgs),
+    /// Import accounts from a shared bundle or an exported archive
+    Import(ImportArgs),
+    /// Import the accounts another switcher keeps, copying them into the
+    /// store and leaving the other tool's files alone
+    ImportFrom(ImportFromArgs),
+    /// Walk through the main settings and save the current login, for a
+    /// first run
+    Init,
+    /// Check the live configuration and the account store for problems
+    Doctor(DoctorArgs),
+    /// Write a redacted tarball of the history, accounts.json, doctor output
+    /// and OS details to attach to a bug report
+    DebugBundle(DebugBundleArgs),
+    /// Re-link or drop accounts whose snapshot is missing or whose entry in
+    /// accounts.json can't be read
+    Repair(RepairArgs),
+    /// Rewrite accounts.json, history and settings in the current format
+    UpgradeConfig(UpgradeConfigArgs),
+    /// Upgrade accounts.json to the current schema and adopt snapshot
+    /// directories it doesn't list
+    Migrate(MigrateArgs),
+    /// Move accounts saved by an earlier version out of ~/.claude-accounts
+    MigrateLegacy(MigrateLegacyArgs),
+    /// Show a bundle's manifest, contents and redacted login without importing it
+    Inspect(InspectArgs),
+    /// Find which saved accounts belong to a login, by email, or hold some
+    /// text in their settings files
+    #[command(group = clap::ArgGroup::new("by").required(true).multiple(true))]
+    Find(FindArgs),
+    /// Search settings files in saved accounts (credential files are skipped)
+    Grep(GrepArgs),
+    /// Print SHA-256 checksums of a saved account's files, or verify them
+    Checksum(ChecksumArgs),
+    /// Check that saved logins still work: by their expiry, or with
+    /// --online by asking the API
+    Check(CheckArgs),
+    /// Check saved snapshots against what they held when they were saved
+    Verify(VerifyArgs),
+    /// Remove stored files no snapshot uses any more (with `dedup` on)
+    Gc(GcArgs),
+    /// Apply the retention rules: drop versions past `keep-versions`, strip
+    /// files matching `[prune] strip` from stored snapshots and delete
+    /// accounts unused for `[prune] unused-after`
+    Prune(PruneArgs),
+    /// Print the exports and unsets that bring the shell's variables up to
+    /// date with the active account's (run by `hook` before each prompt)
+    #[command(hide = true)]
+    HookEnv(HookEnvArgs),
+    /// Clear the clipboard after a delay if it still holds what was copied
+    /// (spawned by token --copy)
+    #[command(hide = true)]
+    ClearClipboard(ClearClipboardArgs),
+    /// Measure how fast this machine copies, hard links and symlinks an
+    /// account into place, to choose a switch-mode by
+    #[command(hide = true)]
+    Bench(BenchArgs),
+    /// Re-save an account after a delay if it's still active (spawned by switch)
+    #[command(hide = true)]
+    Resave(ResaveArgs),
+    /// Read and change the switcher's own settings (settings.toml)
+    Config(ConfigArgs),
+    /// Encrypt saved accounts at rest, or turn encryption back off
+    Encrypt(EncryptArgs),
+    /// Bind the current directory (and everything below it) to an account
+    Bind(BindArgs),
+    /// Bind directories to accounts without writing into them, keeping the
+    /// mappings in the switcher's own config
+    Project(ProjectArgs),
+    /// Print a `claude` shell function that switches to the project's bound
+    /// account before starting Claude Code
+    Wrapper(WrapperArgs),
+    /// Switch to the account bound to the current directory, if any
+    #[command(hide = true)]
+    EnsureBound(EnsureBoundArgs),
+    /// Switch to the account bound to the current directory, if it isn't
+    /// active already
+    Auto(AutoArgs),
+    /// Print a shell hook that runs `auto` whenever you change directory
+    Hook(HookArgs),
+    /// Print or install shell completion scripts
+    Completions(CompletionsArgs),
+    /// Generate documentation, such as man pages, for packaging
+    Docs(DocsArgs),
+}
+
+#[derive(Args)]
+pub struct SaveArgs {
+    pub name: String,
+    #[command(flatten)]
+    pub capture: CaptureArgs,
+    /// Save over an account that isn't the active one without asking
+    #[arg(long)]
+    pub overwrite: bool,
+    /// Keep a new account's snapshot in this directory, such as one on
+    /// an external drive, instead of the data directory
+    #[arg(long, value_name = "DIR")]
+    pub store: Option<PathBuf>,
+    /// Save over the account without asking, even when it's locked or
+    /// isn't the active one
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args)]
+pub struct SwitchArgs {
+    /// Account to switch to: a name, an alias, its number in `list`, or
+    /// `-` for the one active before; pick one interactively when omitted
+    pub name: Option<String>,
+    /// Switch to the account bound to the current directory, by
+    /// `project set` or a `.claude-account` file
+    #[arg(long, conflicts_with = "name")]
+    pub here: bool,
+    #[command(flatten)]
+    pub capture: CaptureArgs,
+    /// Copy files into place, or symlink the Claude directory into the store
+    #[arg(long, value_enum)]
+    pub mode: Option<SwitchMode>,
+    /// Save the active account first, changes to ~/.claude and all,
+    /// without asking, even if the `autosave` setting is off or it was
+    /// saved a moment ago
+    #[arg(long, visible_alias = "autosave", conflicts_with = "force")]
+    pub auto_save: bool,
+    /// Only save the active account first if ~/.claude changed since it
+    /// was saved, as with `autosave = false` in the settings
+    #[arg(long, conflicts_with = "auto_save")]
+    pub no_autosave: bool,
+    /// When ~/.claude changed since the active account was saved, switch
+    /// anyway and discard the changes
+    #[arg(long)]
+    pub force: bool,
+    /// Show what would change in ~/.claude (files, login and settings)
+    /// and ask before switching
+    #[arg(long, requires = "name")]
+    pub preview: bool,
+}
+
+#[derive(Args)]
+pub struct ListArgs {
+    /// Render accounts grouped into a tree with per-group counts and sizes
+    #[arg(long, value_enum)]
+    pub group_by: Option<GroupBy>,
+    /// Only accounts with this tag; repeat for accounts with all of them
+    #[arg(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+    /// Order of the accounts (within each group)
+    #[arg(long, value_enum, default_value_t = ListSort::Name)]
+    pub sort: ListSort,
+    /// Most recently used first; short for --sort last-used
+    #[arg(long, conflicts_with = "sort")]
+    pub recent: bool,
+    /// Show a table with each account's email, plan, tags, last use,
+    /// size and unsaved changes (grouped: the file count and size)
+    #[arg(short, long)]
+    pub long: bool,
+    /// Show these columns in a table, e.g. `name,email,size`
+    #[arg(long, value_enum, value_delimiter = ',', conflicts_with = "group_by")]
+    pub columns: Vec<Column>,
+    /// Only accounts saved more than this many days ago, or with a login
+    /// about to expire
+    #[arg(long, value_name = "N")]
+    pub stale_days: Option<u32>,
+}
+
+#[derive(Args)]
+pub struct SizeArgs {
+    /// Only this account
+    pub name: Option<String>,
+    /// Maximum number of directories measured in parallel
+    #[arg(short, long, default_value_t = hashing::default_jobs())]
+    pub jobs: usize,
+}
+
+#[derive(Args)]
+pub struct AddArgs {
+    pub name: String,
+    /// Run this for the login instead of `claude /login`, after `--`
+    #[arg(last = true, value_name = "COMMAND")]
+    pub login: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct RenewArgs {
+    pub name: String,
+    /// Run this for the login instead of `claude /login`, after `--`
+    #[arg(last = true, value_name = "COMMAND")]
+    pub login: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct LogoutArgs {
+    /// Remove the whole Claude directory, not only the credentials
+    #[arg(long)]
+    pub all: bool,
+    /// With --all, leave settings.json in place
+    #[arg(long, requires = "all")]
+    pub keep_settings: bool,
+}
+
+#[derive(Args)]
+pub struct AddExistingArgs {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+#[derive(Args)]
+pub struct AdoptArgs {
+    #[arg(required_unless_present = "scan", conflicts_with = "scan")]
+    pub path: Option<PathBuf>,
+    /// The account name (by default the directory's name, or its path in
+    /// the store)
+    #[arg(long, conflicts_with = "scan")]
+    pub name: Option<String>,
+    /// Adopt every directory in the store that no account uses
+    #[arg(long)]
+    pub scan: bool,
+}
+
+#[derive(Args)]
+pub struct DeleteArgs {
+    #[arg(required = true)]
+    pub names: Vec<String>,
+    /// Only forget the account; leave its snapshot directory on disk
+    #[arg(long)]
+    pub keep_data: bool,
+    /// Delete the account without asking, even when it's locked
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args)]
+pub struct ProtectArgs {
+    pub name: String,
+    /// Lift the requirement again
+    #[arg(long)]
+    pub off: bool,
+}
+
+#[derive(Args)]
+pub struct PinArgs {
+    pub name: String,
+    /// The version, or a release of it like `2.0` (defaults to the
+    /// installed one)
+    #[arg(long, value_name = "VERSION", conflicts_with = "off")]
+    pub claude_version: Option<String>,
+    /// Lift the pin again
+    #[arg(long)]
+    pub off: bool,
+}
+
+#[derive(Args)]
+pub struct LockArgs {
+    pub name: String,
+}
+
+#[derive(Args)]
+pub struct UnlockArgs {
+    pub name: String,
+}
+
+#[derive(Args)]
+pub struct TokenArgs {
+    pub name: String,
+    /// Print the whole token
+    #[arg(long)]
+    pub reveal: bool,
+    /// Copy the whole token to the clipboard instead, clearing it again
+    /// after --clear-after seconds
+    #[arg(long)]
+    pub copy: bool,
+    #[arg(long, value_name = "SECONDS", default_value_t = 30, requires = "copy")]
+    pub clear_after: u64,
+}
+
+#[derive(Args)]
+pub struct EnvfileArgs {
+    pub name: String,
+    #[arg(long, value_enum, default_value_t)]
+    pub format: envfile::Format,
+}
+
+#[derive(Args)]
+pub struct ArchiveArgs {
+    pub name: String,
+}
+
+#[derive(Args)]
+pub struct UnarchiveArgs {
+    pub name: String,
+}
+
+#[derive(Args)]
+pub struct LimitHitArgs {
+    pub name: Option<String>,
+    /// When the limit lifts (e.g. 90m); a five-hour usage window by default
+    #[arg(long, value_name = "DURATION")]
+    pub resets_in: Option<String>,
+    /// The account has quota again
+    #[arg(long, conflicts_with = "resets_in")]
+    pub clear: bool,
+}
+
+#[derive(Args)]
+pub struct RotateArgs {
+    /// Only accounts with this tag or in this namespace
+    #[arg(long)]
+    pub group: Option<String>,
+}
+
+#[derive(Args)]
+pub struct SuggestArgs {
+    /// Only accounts with this tag or in this namespace
+    #[arg(long)]
+    pub group: Option<String>,
+    /// Ask the API whether each login still works (see `check`)
+    #[arg(long)]
+    pub online: bool,
+    /// Switch to the best one
+    #[arg(long)]
+    pub switch: bool,
+}
+
+#[derive(Args)]
+pub struct MenuArgs {
+    /// The launcher to use, instead of the first one installed
+    #[arg(long, value_enum)]
+    pub via: Option<Launcher>,
+    /// Any other menu, run by the shell: it's given one account per line
+    /// on stdin and prints back the one chosen
+    #[arg(long, conflicts_with = "via")]
+    pub command: Option<String>,
+}
+
+#[derive(Args)]
+pub struct NoteArgs {
+    pub name: String,
+    /// The new note; without it (or --edit, --clear) the note is shown
+    #[arg(conflicts_with_all = ["edit", "clear"])]
+    pub text: Option<String>,
+    /// Write the note in $VISUAL or $EDITOR
+    #[arg(long, conflicts_with = "clear")]
+    pub edit: bool,
+    /// Remove the note
+    #[arg(long)]
+    pub clear: bool,
+}
+
+#[derive(Args)]
+pub struct TagArgs {
+    #[command(subcommand)]
+    pub action: TagAction,
+}
+
+#[derive(Args)]
+pub struct VarArgs {
+    #[command(subcommand)]
+    pub action: VarAction,
+}
+
+#[derive(Args)]
+pub struct AliasArgs {
+    #[command(subcommand)]
+    pub action: AliasAction,
+}
+
+#[derive(Args)]
+pub struct RenameArgs {
+    pub old_name: String,
+    pub new_name: String,
+}
+
+#[derive(Args)]
+pub struct CopyArgs {
+    pub source: String,
+    pub name: String,
+}
+
+#[derive(Args)]
+pub struct TemplateArgs {
+    #[command(subcommand)]
+    pub action: TemplateAction,
+}
+
+#[derive(Args)]
+pub struct PresetArgs {
+    #[command(subcommand)]
+    pub action: PresetAction,
+}
+
+#[derive(Args)]
+pub struct ProfileArgs {
+    #[command(subcommand)]
+    pub action: ProfileAction,
+}
+
+#[derive(Args)]
+pub struct RunArgs {
+    pub name: String,
+    /// The command and its arguments, after `--`
+    #[arg(last = true, required = true, value_name = "COMMAND")]
+    pub command: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct RemoteArgs {
+    /// Where to run it, as ssh takes it: `user@server` or a host from
+    /// ~/.ssh/config
+    #[arg(long)]
+    pub host: String,
+    /// The switcher on the remote machine, if it isn't on the PATH there
+    #[arg(long, value_name = "PATH", default_value = remote::DEFAULT_PROGRAM)]
+    pub program: String,
+    /// The command to run there and its arguments
+    #[arg(
+        trailing_var_arg = true,
+        allow_hyphen_values = true,
+        required = true,
+        value_name = "COMMAND"
+    )]
+    pub command: Vec<String>,
+}
+
+#[derive(Args)]
+pub struct EnvArgs {
+    pub name: String,
+    /// The shell to print them for; detected from $SHELL if not given
+    #[arg(long, value_enum)]
+    pub shell: Option<Shell>,
+}
+
+#[derive(Args)]
+pub struct ShellArgs {
+    pub name: String,
+}
+
+#[derive(Args)]
+pub struct DaemonArgs {
+    #[command(subcommand)]
+    pub action: DaemonAction,
+}
+
+#[derive(Args)]
+pub struct ServiceArgs {
+    #[command(subcommand)]
+    pub action: ServiceAction,
+}
+
+#[derive(Args)]
+pub struct PromptArgs {
+    /// What to print; `{name}`, `{email}`, `{organization}` and `{plan}`
+    /// are filled in
+    #[arg(default_value = segment::DEFAULT_TEMPLATE)]
+    pub template: String,
+}
+
+#[derive(Args)]
+pub struct StatusArgs {
+    /// One stable `key=value` line: name, plan, expires, dirty
+    #[arg(long)]
+    pub porcelain: bool,
+    /// Print nothing unless the token has expired or is about to, or
+    /// there are unsaved changes
+    #[arg(long)]
+    pub when_stale_only: bool,
+}
+
+#[derive(Args)]
+pub struct RestoreArgs {
+    pub name: String,
+    /// A file to restore, relative to the Claude directory; repeat for more
+    #[arg(long = "file", value_name = "FILE", required = true)]
+    pub files: Vec<PathBuf>,
+    /// Where to write instead: a directory, or the file's new path when
+    /// restoring one
+    #[arg(long, value_name = "PATH")]
+    pub to: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct CatArgs {
+    pub name: String,
+    /// The file, relative to the Claude directory
+    pub path: PathBuf,
+    /// Show secrets as they are
+    #[arg(long)]
+    pub reveal: bool,
+}
+
+#[derive(Args)]
+pub struct LsArgs {
+    pub name: String,
+    /// A directory, relative to the Claude directory
+    pub subdir: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct MergeArgs {
+    pub source: String,
+    pub dest: String,
+    /// The files to copy, relative to the Claude directory, e.g.
+    /// `settings.json,CLAUDE.md`
+    #[arg(long, value_name = "FILES", value_delimiter = ',', required = true)]
+    pub only: Vec<PathBuf>,
+    /// Write them into ~/.claude too (always done when the destination
+    /// is the active account)
+    #[arg(long)]
+    pub live: bool,
+    /// Say what would be copied without writing anything
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Copy them even when the destination is locked
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args)]
+pub struct DiffArgs {
+    /// The account to compare with (the active one by default)
+    pub name: Option<String>,
+    /// A second account to compare `name` with, instead of ~/.claude
+    pub other: Option<String>,
+    /// Also print a unified diff of each changed JSON file
+    #[arg(long)]
+    pub content: bool,
+}
+
+#[derive(Args)]
+pub struct ExistsArgs {
+    pub name: String,
+}
+
+#[derive(Args)]
+pub struct IsCurrentArgs {
+    pub name: String,
+}
+
+#[derive(Args)]
+pub struct InfoArgs {
+    pub name: String,
+}
+
+#[derive(Args)]
+pub struct HistoryArgs {
+    /// List the snapshots `rollback` can return this account to
+    #[arg(conflicts_with_all = ["source", "account"])]
+    pub name: Option<String>,
+    /// Only events from this source
+    #[arg(long, value_enum)]
+    pub source: Option<EventSource>,
+    /// Only events for this account
+    #[arg(long)]
+    pub account: Option<String>,
+    /// Show only the most recent N entries
+    #[arg(short = 'n', long)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Args)]
+pub struct RecoverLiveArgs {
+    /// Which one, as `--list` shows it
+    pub id: Option<String>,
+    /// List the ones kept instead
+    #[arg(long, conflicts_with = "id")]
+    pub list: bool,
+}
+
+#[derive(Args)]
+pub struct RecoverArgs {
+    /// Carry it out after all
+    #[arg(long, conflicts_with = "rollback")]
+    pub resume: bool,
+    /// Undo what it had done
+    #[arg(long)]
+    pub rollback: bool,
+}
+
+#[derive(Args)]
+pub struct CheckpointArgs {
+    #[command(subcommand)]
+    pub action: CheckpointAction,
+}
+
+#[derive(Args)]
+pub struct TrashArgs {
+    #[command(subcommand)]
+    pub action: TrashAction,
+}
+
+#[derive(Args)]
+pub struct BackupArgs {
+    #[command(subcommand)]
+    pub action: BackupAction,
+}
+
+#[derive(Args)]
+pub struct SyncArgs {
+    #[command(subcommand)]
+    pub action: SyncAction,
+}
+
+#[derive(Args)]
+pub struct RollbackArgs {
+    pub name: String,
+    /// Snapshot id from `history <name>` (defaults to the newest)
+    #[arg(long)]
+    pub to: Option<String>,
+}
+
+#[derive(Args)]
+pub struct ShareArgs {
+    pub name: String,
+    /// How long the bundle stays importable (e.g. 30m, 24h, 7d)
+    #[arg(long, default_value = "24h")]
+    pub expires: String,
+    /// Bundle file to write (defaults to <name>.casbundle)
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct ExportArgs {
+    /// Accounts to export: names, aliases or globs (`'client/*'`)
+    #[arg(required_unless_present = "all", conflicts_with = "all")]
+    pub names: Vec<String>,
+    /// Export every account
+    #[arg(long)]
+    pub all: bool,
+    /// Archive to write (defaults to <name>.tar.zst, or claude-accounts.tar.zst)
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    /// Encrypt the archive with a passphrase
+    #[arg(long)]
+    pub encrypt: bool,
+    /// Passphrase for --encrypt (prompted for when omitted)
+    #[arg(long, env = "CLAUDE_ACCOUNT_PASSPHRASE")]
+    pub passphrase: Option<String>,
+    /// Leave out the login and mask tokens and keys, to share the setup
+    /// (settings, MCP servers, CLAUDE.md) rather than the account
+    #[arg(long)]
+    pub redacted: bool,
+}
+
+#[derive(Args)]
+pub struct ImportArgs {
+    pub file: PathBuf,
+    /// Store the account under a different name
+    #[arg(long)]
+    pub name: Option<String>,
+    /// Bundle passphrase (prompted for when omitted)
+    #[arg(long, env = "CLAUDE_ACCOUNT_PASSPHRASE")]
+    pub passphrase: Option<String>,
+    /// Check the bundle and report what would be imported without writing anything
+    #[arg(long)]
+    pub dry_run: bool,
+    /// What to do when an imported name is already taken (fails by default)
+    #[arg(long, value_enum)]
+    pub on_conflict: Option<OnConflict>,
+    /// Replace an account of the same name even when it's locked
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args)]
+pub struct ImportFromArgs {
+    #[arg(value_enum)]
+    pub tool: Tool,
+    /// Where the tool keeps them, if not in its default place
+    #[arg(long)]
+    pub path: Option<PathBuf>,
+    /// Report what would be imported without writing anything
+    #[arg(long)]
+    pub dry_run: bool,
+    /// What to do when a name is already taken (fails by default)
+    #[arg(long, value_enum)]
+    pub on_conflict: Option<OnConflict>,
+    /// Replace accounts of the same names even when they're locked
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args)]
+pub struct DoctorArgs {
+    /// Apply the fixes that are safe to make automatically
+    #[arg(long)]
+    pub fix: bool,
+    /// Probe what this machine supports and report which features are active
+    #[arg(long, conflicts_with = "fix")]
+    pub capabilities: bool,
+}
+
+#[derive(Args)]
+pub struct DebugBundleArgs {
+    /// Where to write it; defaults to claude-account-debug-<time>.tar.zst
+    /// in the current directory
+    #[arg(short, long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct RepairArgs {
+    /// Point an account at the directory its snapshot is in now; can be
+    /// repeated
+    #[arg(long = "relink", value_name = "NAME=DIR")]
+    pub relink: Vec<String>,
+    /// Show what would change without writing anything
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Args)]
+pub struct UpgradeConfigArgs {
+    /// Show what would change without writing anything
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Args)]
+pub struct MigrateArgs {
+    /// Show what would change without writing anything
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Args)]
+pub struct MigrateLegacyArgs {
+    /// Old data directory, if not ~/.claude-accounts
+    #[arg(long)]
+    pub from: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct InspectArgs {
+    pub file: PathBuf,
+    /// Bundle passphrase (prompted for when omitted)
+    #[arg(long, env = "CLAUDE_ACCOUNT_PASSPHRASE")]
+    pub passphrase: Option<String>,
+}
+
+#[derive(Args)]
+pub struct FindArgs {
+    /// An email address, or part of one (`@example.com`), matched
+    /// ignoring case
+    #[arg(long, group = "by")]
+    pub email: Option<String>,
+    /// Text in the settings files, as `grep` looks for it (ignoring case)
+    #[arg(long, value_name = "TEXT", group = "by")]
+    pub grep: Option<String>,
+}
+
+#[derive(Args)]
+pub struct GrepArgs {
+    pub pattern: String,
+    /// Only search this account
+    #[arg(long)]
+    pub account: Option<String>,
+    /// Match case-insensitively
+    #[arg(short, long)]
+    pub ignore_case: bool,
+}
+
+#[derive(Args)]
+pub struct ChecksumArgs {
+    pub name: String,
+    /// Verify against a list previously produced by this command
+    #[arg(long)]
+    pub check: Option<PathBuf>,
+    /// Maximum number of files hashed in parallel
+    #[arg(short, long, default_value_t = hashing::default_jobs())]
+    pub jobs: usize,
+}
+
+#[derive(Args)]
+pub struct CheckArgs {
+    /// The accounts to check, by name, alias or glob (the current one
+    /// when left out)
+    #[arg(conflicts_with = "all")]
+    pub names: Vec<String>,
+    /// Check every account
+    #[arg(long)]
+    pub all: bool,
+    /// Show each token to the API to find revoked ones (nothing is
+    /// activated or refreshed)
+    #[arg(long)]
+    pub online: bool,
+}
+
+#[derive(Args)]
+pub struct VerifyArgs {
+    /// The accounts to check, by name, alias or glob (the current one
+    /// when left out)
+    #[arg(conflicts_with = "all")]
+    pub names: Vec<String>,
+    /// Check every account
+    #[arg(long)]
+    pub all: bool,
+    /// Save the current account again, whole, when its snapshot is damaged
+    #[arg(long)]
+    pub repair: bool,
+}
+
+#[derive(Args)]
+pub struct GcArgs {
+    /// Only report what would be removed
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Look for directories in the store no account uses instead, and
+    /// move them to the trash once confirmed
+    #[arg(long)]
+    pub orphans: bool,
+    /// Register the directories `--orphans` finds as accounts instead
+    #[arg(long, requires = "orphans")]
+    pub adopt: bool,
+}
+
+#[derive(Args)]
+pub struct PruneArgs {
+    /// Only report what would be removed
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Versions to keep per account, instead of `keep-versions`
+    #[arg(long, value_name = "N")]
+    pub keep_versions: Option<usize>,
+    /// Delete accounts not switched to for this long (e.g. 90d), instead
+    /// of `[prune] unused-after`
+    #[arg(long, value_name = "DURATION")]
+    pub unused_after: Option<String>,
+    /// Strip files matching this gitignore-style pattern (repeatable),
+    /// instead of `[prune] strip`
+    #[arg(long = "strip", value_name = "PATTERN")]
+    pub strip: Vec<String>,
+    /// Skip the confirmation, and delete, strip and remove versions of
+    /// locked accounts too
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args)]
+pub struct HookEnvArgs {
+    pub shell: Shell,
+}
+
+#[derive(Args)]
+pub struct ClearClipboardArgs {
+    /// Seconds to wait before clearing
+    #[arg(long, default_value_t = 0)]
+    pub after: u64,
+    /// Hash of what was copied
+    #[arg(long)]
+    pub digest: String,
+}
+
+#[derive(Args)]
+pub struct BenchArgs {
+    /// How many files to put in place
+    #[arg(long, default_value_t = bench::DEFAULT_FILES)]
+    pub files: usize,
+    /// The size of each file, in KiB
+    #[arg(long, value_name = "KIB", default_value_t = bench::DEFAULT_FILE_KIB)]
+    pub file_size: u64,
+}
+
+#[derive(Args)]
+pub struct ResaveArgs {
+    pub name: String,
+    /// Seconds to wait before saving
+    #[arg(long, default_value_t = 0)]
+    pub after: u64,
+}
+
+#[derive(Args)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub action: ConfigAction,
+}
+
+#[derive(Args)]
+pub struct EncryptArgs {
+    #[command(subcommand)]
+    pub action: EncryptAction,
+}
+
+#[derive(Args)]
+pub struct BindArgs {
+    pub name: String,
+}
+
+#[derive(Args)]
+pub struct ProjectArgs {
+    #[command(subcommand)]
+    pub action: ProjectAction,
+}
+
+#[derive(Args)]
+pub struct WrapperArgs {
+    /// Target shell (detected from $SHELL when omitted)
+    pub shell: Option<Shell>,
+    /// Only warn about a mismatch instead of switching
+    #[arg(long)]
+    pub warn: bool,
+}
+
+#[derive(Args)]
+pub struct EnsureBoundArgs {
+    #[arg(long)]
+    pub warn: bool,
+}
+
+#[derive(Args)]
+pub struct AutoArgs {
+    /// Only warn about a mismatch instead of switching
+    #[arg(long)]
+    pub warn: bool,
+}
+
+#[derive(Args)]
+pub struct HookArgs {
+    /// Target shell (detected from $SHELL when omitted)
+    pub shell: Option<Shell>,
+    /// Only warn about a mismatch instead of switching
+    #[arg(long)]
+    pub warn: bool,
+}
+
+#[derive(Args)]
+pub struct CompletionsArgs {
+    /// Target shell (detected from $SHELL when omitted)
+    pub shell: Option<Shell>,
+    /// Write the script to the shell's completion directory
+    #[arg(long)]
+    pub install: bool,
+}
+
+#[derive(Args)]
+pub struct DocsArgs {
+    #[command(subcommand)]
+    pub action: DocsAction,
+}
+
+#[derive(Subcommand)]
+pub enum DocsAction {
+    /// Print the man page, or write one for every command into a directory
+    Man {
+        /// Write claude-account-switcher.1 and a page per command here
+        #[arg(long, value_name = "DIR")]
+        out: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Print a setting as the other commands see it, CLAUDE_ACCOUNT_*
+    /// variables included; exits with 1 when it is at its default
+    Get {
+        #[arg(value_parser = PossibleValuesParser::new(settings::KEYS))]
+        key: String,
+    },
+    /// Set a setting in settings.toml. VALUE is TOML (`true`, `3`,
+    /// `["projects/"]`), or plain text
+    Set {
+        #[arg(value_parser = PossibleValuesParser::new(settings::KEYS))]
+        key: String,
+        value: String,
+    },
+    /// Remove a setting from settings.toml, back to its default
+    Unset {
+        #[arg(value_parser = PossibleValuesParser::new(settings::KEYS))]
+        key: String,
+    },
+    /// Print every setting that differs from its default
+    List,
+    /// Open settings.toml in $VISUAL or $EDITOR and check it afterwards
+    Edit,
+}
+
+#[derive(Subcommand)]
+pub enum DaemonAction {
+    /// Start it in the background
+    Start,
+    /// Ask it to shut down
+    Stop,
+    /// Say whether it's running, since when and how busy it's been
+    Status,
+    /// Serve in the foreground, for running under a service manager
+    Run,
+}
+
+#[derive(Subcommand)]
+pub enum ServiceAction {
+    /// Install and start them; installing one again replaces it
+    #[command(group = clap::ArgGroup::new("jobs").required(true).multiple(true))]
+    Install {
+        /// The daemon, started with the session and kept running
+        #[arg(long, visible_alias = "watch", group = "jobs")]
+        daemon: bool,
+        /// `backup auto` this often
+        #[arg(long, value_enum, value_name = "SCHEDULE", group = "jobs")]
+        backup: Option<Schedule>,
+        /// Backups to keep
+        #[arg(long, value_name = "N", default_value_t = 7, requires = "backup",
+              value_parser = clap::value_parser!(u64).range(1..))]
+        keep: u64,
+    },
+    /// Stop and remove them: the daemon's, the backups' or (with neither) both
+    Uninstall {
+        #[arg(long)]
+        daemon: bool,
+        #[arg(long)]
+        backup: bool,
+    },
+    /// Say which are installed and whether they're running
+    Status,
+}
+
+#[derive(Subcommand)]
+pub enum CheckpointAction {
+    /// Copy the live configuration as a checkpoint
+    Save {
+        label: String,
+        /// Replace a checkpoint of the same label
+        #[arg(long)]
+        force: bool,
+    },
+    /// Put a checkpoint in place of the live configuration, keeping the one
+    /// it replaces for `recover-live`
+    Restore { label: String },
+    /// List checkpoints, newest first
+    List,
+    /// Remove a checkpoint
+    Delete { label: String },
+}
+
+#[derive(Subcommand)]
+pub enum TrashAction {
+    /// List deleted accounts, most recently deleted first
+    List,
+    /// Register the most recent delete of an account again
+    Restore { name: String },
+    /// Permanently remove deleted accounts
+    Empty {
+        /// Only those deleted longer ago than this (e.g. 30d)
+        #[arg(long, value_name = "DURATION")]
+        older_than: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BackupAction {
+    /// Write a timestamped backup
+    Create {
+        /// Directory to write it to (defaults to the backups directory in the
+        /// state directory)
+        #[arg(short, long, value_name = "DIR")]
+        output: Option<PathBuf>,
+    },
+    /// Replace every account, the history and settings with a backup's
+    Restore { file: PathBuf },
+    /// Write a backup and delete all but the newest N, for a cron job or
+    /// systemd timer
+    Auto {
+        /// Backups to keep
+        #[arg(long, value_name = "N", default_value_t = 7,
+              value_parser = clap::value_parser!(u64).range(1..))]
+        keep: u64,
+        /// Directory to write it to (defaults to the backups directory in the
+        /// state directory)
+        #[arg(short, long, value_name = "DIR")]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TagAction {
+    /// Add tags to an account
+    Add {
+        name: String,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+    /// Remove tags from an account
+    Remove {
+        name: String,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum VarAction {
+    /// Set variables for an account, as NAME=VALUE
+    Set {
+        name: String,
+        #[arg(required = true, value_name = "NAME=VALUE")]
+        vars: Vec<String>,
+    },
+    /// Remove variables from an account
+    Unset {
+        name: String,
+        #[arg(required = true, value_name = "NAME")]
+        vars: Vec<String>,
+    },
+    /// List an account's variables
+    List { name: String },
+}
+
+#[derive(Subcommand)]
+pub enum AliasAction {
+    /// Make `alias` stand for the account `name`
+    Set { alias: String, name: String },
+    /// Forget an alias
+    Remove { alias: String },
+}
+
+#[derive(Subcommand)]
+pub enum ProjectAction {
+    /// Use the account `name` in a directory and everything below it
+    Set {
+        name: String,
+        /// The directory to map, instead of the current one
+        #[arg(long)]
+        path: Option<PathBuf>,
+    },
+    /// Forget a directory's mapping
+    Unset {
+        /// The directory to forget, instead of the current one
+        #[arg(long)]
+        path: Option<PathBuf>,
+    },
+    /// Show the mapped directories and their accounts
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum SyncAction {
+    /// Set up where to sync through; the store has to be encrypted
+    Init {
+        /// URL of a git repository (which may be empty), s3://bucket/prefix
+        /// or webdav://host/path
+        #[arg(long, visible_alias = "backend")]
+        remote: String,
+    },
+    /// Send the accounts saved or deleted here since the last sync
+    Push,
+    /// Bring in the accounts saved or deleted on other machines
+    Pull {
+        /// Replace the accounts locked here too
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TemplateAction {
+    /// Keep the settings of ~/.claude, or of a saved account, as a template
+    Save {
+        name: String,
+        /// Take the settings from this saved account instead of ~/.claude
+        #[arg(long)]
+        from: Option<String>,
+    },
+    /// Layer a template over ~/.claude, keeping the login
+    Apply {
+        name: String,
+    },
+    List,
+    Delete {
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PresetAction {
+    /// Write the preset to a file, or print it
+    Export {
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// Lay a preset over this setup: its settings replace the same ones
+    /// here, and its aliases and tags go to the accounts of the same names
+    Import { file: PathBuf },
+}
+
+#[derive(Subcommand)]
+pub enum ProfileAction {
+    /// Name an account and the environment to use it with
+    Create {
+        name: String,
+        #[arg(long)]
+        account: String,
+        /// A variable to set, as KEY=VALUE; can be repeated
+        #[arg(long = "env", value_name = "KEY=VALUE")]
+        env: Vec<String>,
+        /// Replace a profile of the same name
+        #[arg(long)]
+        force: bool,
+    },
+    /// Switch to the profile's account and print its exports, for
+    /// `eval "$(claude-account profile use fast)"`
+    Use {
+        name: String,
+        /// The shell to print the exports for; detected from $SHELL if not
+        /// given
+        #[arg(long, value_enum, conflicts_with = "write")]
+        shell: Option<Shell>,
+        /// Write the variables into the `env` of ~/.claude/settings.json
+        /// instead, for every terminal
+        #[arg(long)]
+        write: bool,
+    },
+    List,
+    Delete {
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum EncryptAction {
+    /// Set a store passphrase and encrypt every saved account
+    Enable,
+    /// Decrypt every saved account and remove the store key
+    Disable,
+}
+
+/// Overrides for the capture patterns in the settings file.
+#[derive(Args, Debug, Default)]
+pub struct CaptureArgs {
+    /// Only capture files matching this gitignore-style pattern (repeatable)
+    #[arg(long = "include", value_name = "PATTERN")]
+    pub include: Vec<String>,
+    /// Never capture files matching this gitignore-style pattern (repeatable)
+    #[arg(long = "exclude", value_name = "PATTERN")]
+    pub exclude: Vec<String>,
+}
+
+impl Cli {
+    /// The document `--format` asks `list` for, if any; other commands, and
+    /// a grouped list, can't print one.
+    pub fn document(&self) -> Result<Option<Document>> {
+        let Some((format, document)) = self
+            .format
+            .and_then(|format| Some((format, format.document()?)))
+        else {
+            return Ok(None);
+        };
+        let name = format
+            .to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default();
+        match &self.command {
+            Some(Commands::List(ListArgs { group_by: None, .. })) => Ok(Some(document)),
+            Some(Commands::List(..)) => {
+                anyhow::bail!(tr!("format-not-grouped", format = name))
+            }
+            _ => anyhow::bail!(tr!("format-list-only", format = name)),
+        }
+    }
+}
+
+/// What `--format` takes: one of the output formats, or for `list`, a
+/// document to paste elsewhere.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Aligned columns, markers and tree lines
+    Table,
+    /// Explicitly labelled lines without alignment or symbols, for screen readers
+    Plain,
+    /// One JSON document on stdout per command; messages go to stderr
+    Json,
+    /// Comma-separated values, for spreadsheets (`list` only)
+    Csv,
+    /// A Markdown table, for wikis (`list` only)
+    Markdown,
+}
+
+impl Format {
+    /// How everything but a document is printed.
+    pub fn output(self) -> OutputFormat {
+        match self {
+            Format::Plain => OutputFormat::Plain,
+            Format::Json => OutputFormat::Json,
+            Format::Table | Format::Csv | Format::Markdown => OutputFormat::Table,
+        }
+    }
+
+    pub fn document(self) -> Option<Document> {
+        match self {
+            Format::Csv => Some(Document::Csv),
+            Format::Markdown => Some(Document::Markdown),
+            Format::Table | Format::Plain | Format::Json => None,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnConflict {
+    /// Import under the first free name: `work-2`, `work-3`, ...
+    Rename,
+    /// Leave the existing account alone and don't import this one
+    Skip,
+    /// Overwrite the existing account with the imported one
+    Replace,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ListSort {
+    Name,
+    /// Most recently saved first
+    Saved,
+    /// Most recently switched to first; never used last
+    LastUsed,
+    /// Largest snapshot first
+    Size,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroupBy {
+    /// The part of the name before the last `/` (e.g. `client-a` in `client-a/dev`)
+    Namespace,
+    /// Each tag from `tag add`; an account with several is under each
+    Tag,
+    /// The email the snapshot was signed in as when it was saved
+    Identity,
 }
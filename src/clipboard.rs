@@ -0,0 +1,160 @@
+//! The system clipboard, for `token --copy`, through the command each
+//! platform has for it (`pbcopy`, `wl-copy`, `xclip`, ...), so nothing is
+//! linked in for a feature most runs never use.
+//!
+//! What's copied is cleared again after a while by a detached
+//! `clear-clipboard` process. Only a hash of it is passed to that process,
+//! which clears the clipboard only if it still holds the same thing.
+
+use crate::hashing;
+use crate::tr;
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+/// A program that reads the clipboard from stdin, with the one that
+/// prints it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tool {
+    pub copy: &'static [&'static str],
+    pub paste: &'static [&'static str],
+}
+
+const PBCOPY: Tool = Tool {
+    copy: &["pbcopy"],
+    paste: &["pbpaste"],
+};
+const CLIP: Tool = Tool {
+    copy: &["clip"],
+    paste: &["powershell", "-NoProfile", "-Command", "Get-Clipboard"],
+};
+const WL_COPY: Tool = Tool {
+    copy: &["wl-copy"],
+    paste: &["wl-paste", "--no-newline"],
+};
+const XCLIP: Tool = Tool {
+    copy: &["xclip", "-selection", "clipboard"],
+    paste: &["xclip", "-selection", "clipboard", "-o"],
+};
+const XSEL: Tool = Tool {
+    copy: &["xsel", "--clipboard", "--input"],
+    paste: &["xsel", "--clipboard", "--output"],
+};
+
+/// The tools worth trying on `os`, in order. Under Wayland its own comes
+/// before the X11 ones, which only reach XWayland programs.
+pub fn tools(os: &str, wayland: bool) -> Vec<Tool> {
+    match os {
+        "macos" => vec![PBCOPY],
+        "windows" => vec![CLIP],
+        _ if wayland => vec![WL_COPY, XCLIP, XSEL],
+        _ => vec![XCLIP, XSEL, WL_COPY],
+    }
+}
+
+fn available() -> Vec<Tool> {
+    tools(
+        std::env::consts::OS,
+        std::env::var_os("WAYLAND_DISPLAY").is_some(),
+    )
+}
+
+/// Puts `text` on the clipboard with the first tool that works.
+pub fn copy(text: &str) -> Result<()> {
+    for tool in available() {
+        if run_copy(tool, text).is_ok() {
+            return Ok(());
+        }
+    }
+    anyhow::bail!(tr!("clipboard-unavailable"))
+}
+
+fn run_copy(tool: Tool, text: &str) -> Result<()> {
+    let mut child = Command::new(tool.copy[0])
+        .args(&tool.copy[1..])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .context("No stdin for the clipboard tool")?
+        .write_all(text.as_bytes())?;
+    match child.wait()?.success() {
+        true => Ok(()),
+        false => anyhow::bail!("{} failed", tool.copy[0]),
+    }
+}
+
+/// What's on the clipboard, from the first tool that can say.
+pub fn paste() -> Option<String> {
+    available().into_iter().find_map(|tool| {
+        let output = Command::new(tool.paste[0])
+            .args(&tool.paste[1..])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+    })
+}
+
+/// The hash a clearing process compares the clipboard with.
+pub fn digest(text: &str) -> String {
+    hashing::hash_bytes(text.as_bytes())
+}
+
+/// Starts a detached process that clears the clipboard after `after`,
+/// unless something other than `text` has been copied since.
+pub fn clear_later(text: &str, after: Duration) -> Result<()> {
+    let exe = std::env::current_exe().context("Failed to find this program")?;
+    Command::new(exe)
+        .args([
+            "clear-clipboard",
+            "--after",
+            &after.as_secs().to_string(),
+            "--digest",
+            &digest(text),
+        ])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to start clearing the clipboard")?;
+    Ok(())
+}
+
+/// Waits for `after`, then empties the clipboard if it still holds what
+/// hashes to `expected`. Tools that print it add a line ending at times, so
+/// that doesn't count.
+pub fn clear(after: Duration, expected: &str) -> Result<()> {
+    thread::sleep(after);
+    let Some(current) = paste() else {
+        return Ok(());
+    };
+    let current = current.trim_end_matches(['\r', '\n']);
+    if digest(current) == expected {
+        copy("")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tools_by_platform() {
+        assert_eq!(tools("macos", false), [PBCOPY]);
+        assert_eq!(tools("windows", true), [CLIP]);
+        assert_eq!(tools("linux", true)[0], WL_COPY);
+        assert_eq!(tools("linux", false), [XCLIP, XSEL, WL_COPY]);
+        assert_eq!(tools("freebsd", false)[0], XCLIP);
+    }
+}
@@ -0,0 +1,128 @@
+//! How file contents change on their way into and out of the store:
+//! compressed and then sealed going in, opened and decompressed coming out.
+//! Each step is told apart by its header, so what comes out never depends
+//! on the settings a file was saved with.
+
+use crate::compression;
+use crate::crypto;
+use crate::encryption::Vault;
+use crate::storage::{LocalStorage, Storage};
+use anyhow::{Context, Result};
+use std::path::Path;
+
+#[derive(Clone, Copy)]
+pub enum Codec<'a> {
+    /// Copied as they are, as for external accounts
+    Plain,
+    /// Into the store: compressed at `level` if set, then sealed if there's a vault
+    Store {
+        vault: Option<&'a Vault>,
+        level: Option<i32>,
+    },
+    /// Out of the store, undoing whatever each file went through. Without a
+    /// vault sealed files stay sealed.
+    Load { vault: Option<&'a Vault> },
+}
+
+impl Codec<'_> {
+    pub fn apply(self, data: Vec<u8>) -> Result<Vec<u8>> {
+        match self {
+            Self::Plain => Ok(data),
+            Self::Store { vault, level } => {
+                let data = match level {
+                    Some(level) => compression::compress(data, level)?,
+                    None => data,
+                };
+                match vault {
+                    Some(vault) => vault.seal(data),
+                    None => Ok(data),
+                }
+            }
+            Self::Load { vault } => {
+                let data = match vault {
+                    Some(vault) => vault.open(data)?,
+                    None => data,
+                };
+                compression::decompress(data)
+            }
+        }
+    }
+
+    /// Whether `apply` would leave the file at `path` as it is, so it can be
+    /// copied without reading it in. Only the header is read.
+    pub fn keeps(self, path: &Path) -> Result<bool> {
+        self.keeps_in(&LocalStorage, path)
+    }
+
+    /// `keeps`, reading the header from `storage`.
+    pub fn keeps_in(self, storage: &dyn Storage, path: &Path) -> Result<bool> {
+        match self {
+            Self::Plain
+            | Self::Store {
+                vault: None,
+                level: None,
+            } => Ok(true),
+            Self::Store { .. } => Ok(false),
+            Self::Load { vault } => {
+                let header = storage
+                    .read_prefix(path, 8)
+                    .with_context(|| format!("Failed to read file: {}", path.display()))?;
+                let sealed = vault.is_some() && crypto::is_sealed(&header);
+                Ok(!sealed && !compression::is_compressed(&header))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_store_and_load() -> Result<()> {
+        let dir = TempDir::new()?;
+        let vault = Vault::create(&dir.path().join("key.json"), "passphrase")?;
+        let data = "{\"theme\": \"dark\"}\n".repeat(100).into_bytes();
+
+        let store = Codec::Store {
+            vault: Some(&vault),
+            level: Some(3),
+        };
+        let stored = store.apply(data.clone())?;
+        assert!(crypto::is_sealed(&stored));
+        let load = Codec::Load {
+            vault: Some(&vault),
+        };
+        assert_eq!(load.apply(stored.clone())?, data);
+
+        // Without the vault a sealed file can't be opened, so it's left alone
+        let path = dir.path().join("settings.json");
+        fs::write(&path, &stored)?;
+        assert!(Codec::Load { vault: None }.keeps(&path)?);
+        assert!(!load.keeps(&path)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_decompresses_without_the_setting() -> Result<()> {
+        let dir = TempDir::new()?;
+        let data = "line\n".repeat(100).into_bytes();
+        let stored = Codec::Store {
+            vault: None,
+            level: Some(3),
+        }
+        .apply(data.clone())?;
+
+        let path = dir.path().join("log.jsonl");
+        fs::write(&path, &stored)?;
+        let load = Codec::Load { vault: None };
+        assert!(!load.keeps(&path)?);
+        assert_eq!(load.apply(stored)?, data);
+
+        fs::write(&path, "")?;
+        assert!(load.keeps(&path)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+use crate::cli::Cli;
+use crate::paths;
+use crate::tr;
+use anyhow::{Context, Result};
+use clap::CommandFactory;
+use clap_complete::Shell;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub(crate) const BIN_NAME: &str = env!("CARGO_PKG_NAME");
+
+pub fn run(shell: Option<Shell>, install: bool) -> Result<()> {
+    let shell = match shell {
+        Some(shell) => shell,
+        None => detect_shell(std::env::var("SHELL").ok().as_deref())
+            .context("Could not detect your shell; pass it explicitly (bash, zsh, fish, ...)")?,
+    };
+
+    if !install {
+        generate(shell, &mut io::stdout());
+        return Ok(());
+    }
+
+    let home = paths::home_dir().context("Failed to determine home directory")?;
+    let xdg = |key: &str| {
+        std::env::var_os(key)
+            .filter(|_| !paths::is_sandboxed())
+            .map(PathBuf::from)
+    };
+    let target = install_path(shell, &home, xdg("XDG_DATA_HOME"), xdg("XDG_CONFIG_HOME"))?;
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+
+    let mut script = Vec::new();
+    generate(shell, &mut script);
+    fs::write(&target, script)
+        .with_context(|| format!("Failed to write completion file: {}", target.display()))?;
+
+    println!(
+        "{}",
+        tr!(
+            "completions-installed",
+            shell = shell,
+            path = target.display()
+        )
+    );
+    if let Some(hint) = activation_hint(shell, &target) {
+        println!("{}", hint);
+    }
+
+    Ok(())
+}
+
+fn generate(shell: Shell, out: &mut dyn io::Write) {
+    clap_complete::generate(shell, &mut Cli::command(), BIN_NAME, out);
+}
+
+pub(crate) fn detect_shell(shell_env: Option<&str>) -> Option<Shell> {
+    let name = Path::new(shell_env?).file_name()?.to_str()?;
+    name.parse().ok()
+}
+
+fn install_path(
+    shell: Shell,
+    home: &Path,
+    xdg_data_home: Option<PathBuf>,
+    xdg_config_home: Option<PathBuf>,
+) -> Result<PathBuf> {
+    // Relative ones are to be ignored, as the XDG spec says
+    let xdg = |dir: Option<PathBuf>, default: &str| {
+        dir.filter(|dir| dir.is_absolute())
+            .unwrap_or_else(|| home.join(default))
+    };
+    let data_home = xdg(xdg_data_home, ".local/share");
+
+    match shell {
+        Shell::Bash => Ok(data_home.join("bash-completion/completions").join(BIN_NAME)),
+        Shell::Zsh => Ok(home.join(".zfunc").join(format!("_{}", BIN_NAME))),
+        Shell::Fish => Ok(xdg(xdg_config_home, ".config")
+            .join("fish/completions")
+            .join(format!("{}.fish", BIN_NAME))),
+        _ => anyhow::bail!(
+            "Automatic installation is not supported for {}; redirect `completions {}` to a file instead",
+            shell,
+            shell
+        ),
+    }
+}
+
+fn activation_hint(shell: Shell, target: &Path) -> Option<String> {
+    match shell {
+        // zsh only picks up directories listed in fpath
+        Shell::Zsh => {
+            let dir = target.parent()?;
+            let in_fpath = std::env::var("FPATH")
+                .map(|fpath| fpath.split(':').any(|entry| Path::new(entry) == dir))
+                .unwrap_or(false);
+            (!in_fpath).then(|| tr!("completions-zsh-fpath", dir = dir.display()))
+        }
+        _ => Some(tr!("completions-restart")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_shell() {
+        assert_eq!(detect_shell(Some("/bin/bash")), Some(Shell::Bash));
+        assert_eq!(detect_shell(Some("/usr/local/bin/fish")), Some(Shell::Fish));
+        assert_eq!(detect_shell(Some("zsh")), Some(Shell::Zsh));
+        assert_eq!(detect_shell(Some("/bin/tcsh")), None);
+        assert_eq!(detect_shell(None), None);
+    }
+
+    #[test]
+    fn test_install_path() -> Result<()> {
+        let home = Path::new("/home/user");
+
+        assert_eq!(
+            install_path(Shell::Bash, home, None, None)?,
+            home.join(".local/share/bash-completion/completions")
+                .join(BIN_NAME)
+        );
+        assert_eq!(
+            install_path(Shell::Bash, home, Some(PathBuf::from("/data")), None)?,
+            Path::new("/data/bash-completion/completions").join(BIN_NAME)
+        );
+        assert_eq!(
+            install_path(Shell::Zsh, home, None, None)?,
+            home.join(".zfunc").join(format!("_{}", BIN_NAME))
+        );
+        assert_eq!(
+            install_path(Shell::Fish, home, None, None)?,
+            home.join(".config/fish/completions")
+                .join(format!("{}.fish", BIN_NAME))
+        );
+        assert_eq!(
+            install_path(Shell::Fish, home, None, Some(PathBuf::from("/conf")))?,
+            Path::new("/conf/fish/completions").join(format!("{}.fish", BIN_NAME))
+        );
+        // Relative ones are ignored
+        assert_eq!(
+            install_path(Shell::Fish, home, None, Some(PathBuf::from("conf")))?,
+            home.join(".config/fish/completions")
+                .join(format!("{}.fish", BIN_NAME))
+        );
+        assert!(install_path(Shell::PowerShell, home, None, None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_script() {
+        let mut script = Vec::new();
+        generate(Shell::Bash, &mut script);
+
+        let script = String::from_utf8(script).unwrap();
+        assert!(script.contains(BIN_NAME));
+        assert!(script.contains("switch"));
+    }
+}
@@ -0,0 +1,70 @@
+//! zstd compression of stored files, with `compress` set. Each file is
+//! compressed on its own, so incremental saves, `diff` and `dedup` keep
+//! working file by file.
+//!
+//! Compressed files start with a header of their own, so a store can mix
+//! them with files saved before compression was turned on, and reading never
+//! depends on the setting.
+
+use anyhow::{Context, Result};
+
+pub const MAGIC: &[u8; 8] = b"CASZSTD1";
+
+pub fn is_compressed(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+/// The levels `compress` accepts. zstd's negative levels trade most of the
+/// savings for speed, which a store of JSON has no use for.
+pub fn levels() -> std::ops::RangeInclusive<i32> {
+    1..=*zstd::compression_level_range().end()
+}
+
+/// Compresses `data` at `level`. Data that's already compressed, or that
+/// wouldn't get any smaller (images, sealed files), is returned as it was.
+pub fn compress(data: Vec<u8>, level: i32) -> Result<Vec<u8>> {
+    if is_compressed(&data) {
+        return Ok(data);
+    }
+    let compressed = zstd::encode_all(data.as_slice(), level).context("Failed to compress file")?;
+    if MAGIC.len() + compressed.len() >= data.len() {
+        return Ok(data);
+    }
+
+    let mut out = Vec::with_capacity(MAGIC.len() + compressed.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&compressed);
+    Ok(out)
+}
+
+/// Decompresses `data`, passing anything uncompressed through.
+pub fn decompress(data: Vec<u8>) -> Result<Vec<u8>> {
+    if !is_compressed(&data) {
+        return Ok(data);
+    }
+    zstd::decode_all(&data[MAGIC.len()..]).context("Failed to decompress stored file")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip() -> Result<()> {
+        let data = "{\"theme\": \"dark\"}\n".repeat(100).into_bytes();
+        let compressed = compress(data.clone(), 3)?;
+        assert!(is_compressed(&compressed));
+        assert!(compressed.len() < data.len());
+        assert_eq!(compress(compressed.clone(), 3)?, compressed);
+        assert_eq!(decompress(compressed)?, data);
+        Ok(())
+    }
+
+    #[test]
+    fn test_small_and_plain_data_pass_through() -> Result<()> {
+        assert_eq!(compress(b"{}".to_vec(), 3)?, b"{}");
+        assert_eq!(decompress(b"{}".to_vec())?, b"{}");
+        assert!(decompress(b"CASZSTD1garbage".to_vec()).is_err());
+        Ok(())
+    }
+}
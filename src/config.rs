@@ -1,22 +1,270 @@
+use crate::profile::Profile;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-#[derive(Serialize, Deserialize, Clone)]
+/// The accounts file, in the state directory.
+pub const FILE: &str = "accounts.json";
+
+/// The layout of the accounts file. Bump it, and add a step to
+/// `MIGRATIONS`, with any change that would keep older files from loading.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Takes the accounts file, parsed, from one schema version to the next.
+type Migration = fn(&mut serde_json::Value) -> Result<()>;
+
+/// The steps up to `SCHEMA_VERSION`; the one at index `n` upgrades a file
+/// at version `n`.
+const MIGRATIONS: &[Migration] = &[from_unversioned];
+
+/// Files from before versions were recorded (version 0) have the current
+/// layout; they only lack the version.
+fn from_unversioned(_: &mut serde_json::Value) -> Result<()> {
+    Ok(())
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct AccountMetadata {
     pub saved_at: String,
     pub path: PathBuf,
+    /// Registered with `add-existing`: the directory is managed elsewhere, so
+    /// it's used in place and never moved or deleted
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub external: bool,
+    /// Switching to this account asks for confirmation and the typed name
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub protected: bool,
+    /// Set with `lock`: `save`, `delete` and `prune` leave the snapshot
+    /// alone without `--force`, and a switch away doesn't save over it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub locked: bool,
+    /// When the account was last switched to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<String>,
+    /// How many times it has been switched to
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub switch_count: u64,
+    /// Who the snapshot was signed in as when it was saved. Absent for
+    /// snapshots saved by older versions or without a login.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<AccountIdentity>,
+    /// Labels from `tag add`, for `list --tag` and `list --group-by tag`
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub tags: BTreeSet<String>,
+    /// What the account is for, in the user's words, from `note`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// When the rate limit recorded by `limit-hit` lifts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limited_until: Option<String>,
+    /// Set by `archive`: the snapshot is packed into this file and its
+    /// directory is gone until `unarchive`, or a switch to it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive: Option<PathBuf>,
+    /// Variables from `var set`, which the shell hook exports while the
+    /// account is active, such as the `HTTPS_PROXY` a client's network needs
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// The Claude Code version installed when the snapshot was saved, if
+    /// it could be told
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claude_version: Option<String>,
+    /// Set with `pin`: switching to the account fails unless the installed
+    /// Claude Code is this version, or a release of it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pinned_version: Option<String>,
+}
+
+fn is_zero(count: &u64) -> bool {
+    *count == 0
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AccountIdentity {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub organization: Option<String>,
+    /// The subscription, as Claude Code names it ("pro", "max", ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan: Option<String>,
+}
+
+impl AccountIdentity {
+    pub fn is_empty(&self) -> bool {
+        self.email.is_none() && self.organization.is_none() && self.plan.is_none()
+    }
+}
+
+impl fmt::Display for AccountIdentity {
+    /// The known details, most telling first: `dev@example.com, Acme, max`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let details: Vec<&str> = [&self.email, &self.organization, &self.plan]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        f.write_str(&details.join(", "))
+    }
 }
 
-#[derive(Serialize, Deserialize, Default)]
+#[derive(Serialize, Deserialize)]
 pub struct AccountsConfig {
+    /// See `SCHEMA_VERSION`; 0 in files written before it was recorded
+    #[serde(default)]
+    pub schema_version: u32,
+    /// Counts the saves of the file, so one made from a copy loaded before
+    /// another instance saved it can tell and refuse; 0 before it was kept
+    #[serde(default)]
+    pub revision: u64,
     pub current: Option<String>,
+    /// The account active before `current`, for `switch -`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous: Option<String>,
     pub accounts: HashMap<String, AccountMetadata>,
+    /// Short names for accounts, set with `alias set`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, String>,
+    /// The number `list` shows for each account, which `switch` takes in
+    /// place of the name. Kept, so adding or deleting an account leaves the
+    /// others' numbers as they were.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub numbers: BTreeMap<String, usize>,
+    /// Directories mapped to accounts with `project set`, for projects that
+    /// shouldn't get a `.claude-account` file
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub projects: BTreeMap<PathBuf, String>,
+    /// Accounts with the environment to use them with, set with
+    /// `profile create`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+    /// Entries of `accounts` that didn't parse, kept as they were (and
+    /// written back) so one bad entry doesn't stop every command; `repair`
+    /// registers them again or drops them
+    #[serde(skip)]
+    pub broken: BTreeMap<String, BrokenEntry>,
+}
+
+/// An entry of the accounts file that didn't parse.
+#[derive(Clone, Debug)]
+pub struct BrokenEntry {
+    pub entry: serde_json::Value,
+    pub error: String,
+}
+
+/// What's wrong with an account, if anything, from [`AccountsConfig::health`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EntryHealth {
+    Healthy,
+    /// Its snapshot directory isn't there
+    Missing(PathBuf),
+    /// Its snapshot is kept outside the data directory, in this one, which
+    /// isn't mounted. Only the manager, which knows where the data directory
+    /// is, tells this apart from `Missing`
+    Unmounted(PathBuf),
+    /// Its entry in the accounts file didn't parse, for the reason given
+    Corrupt(String),
+}
+
+impl Default for AccountsConfig {
+    fn default() -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            revision: 0,
+            current: None,
+            previous: None,
+            accounts: HashMap::new(),
+            aliases: BTreeMap::new(),
+            numbers: BTreeMap::new(),
+            projects: BTreeMap::new(),
+            profiles: BTreeMap::new(),
+            broken: BTreeMap::new(),
+        }
+    }
+}
+
+/// The schema version the accounts file `contents` was written with.
+pub fn stored_version(contents: &str) -> Result<u32> {
+    let document: serde_json::Value =
+        serde_json::from_str(contents).context("Failed to parse accounts configuration")?;
+    Ok(version_of(&document))
+}
+
+/// The revision of the accounts file at `path` as it is now, 0 when there's
+/// none yet.
+pub fn stored_revision(path: &Path) -> Result<u64> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e).context("Failed to read accounts configuration file"),
+    };
+    let document: serde_json::Value =
+        serde_json::from_str(&contents).context("Failed to parse accounts configuration")?;
+    Ok(document
+        .get("revision")
+        .and_then(serde_json::Value::as_u64)
+        .unwrap_or(0))
+}
+
+fn version_of(document: &serde_json::Value) -> u32 {
+    document
+        .get("schema_version")
+        .and_then(serde_json::Value::as_u64)
+        .map_or(0, |version| version as u32)
+}
+
+/// Takes the entries of `accounts` that don't parse out of `document`.
+fn split_broken(document: &mut serde_json::Value) -> BTreeMap<String, BrokenEntry> {
+    let Some(accounts) = document
+        .get_mut("accounts")
+        .and_then(serde_json::Value::as_object_mut)
+    else {
+        return BTreeMap::new();
+    };
+    let mut broken = BTreeMap::new();
+    accounts.retain(
+        |name, entry| match serde_json::from_value::<AccountMetadata>(entry.clone()) {
+            Ok(_) => true,
+            Err(e) => {
+                broken.insert(
+                    name.clone(),
+                    BrokenEntry {
+                        entry: entry.clone(),
+                        error: e.to_string(),
+                    },
+                );
+                false
+            }
+        },
+    );
+    broken
+}
+
+/// Runs the migrations `document` hasn't had yet.
+fn migrate(mut document: serde_json::Value) -> Result<serde_json::Value> {
+    let version = version_of(&document);
+    if version > SCHEMA_VERSION {
+        anyhow::bail!(
+            "Accounts configuration schema version {} is newer than supported version {}",
+            version,
+            SCHEMA_VERSION
+        );
+    }
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(&mut document)?;
+    }
+    if let Some(fields) = document.as_object_mut() {
+        fields.insert("schema_version".to_string(), SCHEMA_VERSION.into());
+    }
+    Ok(document)
 }
 
 impl AccountsConfig {
+    /// Reads the accounts file at `path`, upgrading it in memory from
+    /// whichever schema version it was written with.
     pub fn load(path: &Path) -> Result<Self> {
         if !path.exists() {
             return Ok(Self::default());
@@ -24,15 +272,90 @@ impl AccountsConfig {
 
         let contents =
             fs::read_to_string(path).context("Failed to read accounts configuration file")?;
+        let mut document = serde_json::from_str(&contents)
+            .map_err(anyhow::Error::from)
+            .and_then(migrate)
+            .context("Failed to parse accounts configuration")?;
+
+        let broken = split_broken(&mut document);
+        let mut config: Self =
+            serde_json::from_value(document).context("Failed to parse accounts configuration")?;
+        config.broken = broken;
+        config.number_accounts();
+        Ok(config)
+    }
+
+    /// Gives the accounts without a number one, in name order after the
+    /// highest number given so far: files written before numbers were kept
+    /// have none.
+    fn number_accounts(&mut self) {
+        let mut unnumbered: Vec<String> = self
+            .accounts
+            .keys()
+            .filter(|name| !self.numbers.contains_key(*name))
+            .cloned()
+            .collect();
+        unnumbered.sort();
+        for name in unnumbered {
+            self.number(name);
+        }
+    }
 
-        serde_json::from_str(&contents).context("Failed to parse accounts configuration")
+    // The one after the highest, rather than a gap left by a deleted account
+    fn number(&mut self, name: String) {
+        let next = self.numbers.values().max().map_or(1, |max| max + 1);
+        self.numbers.entry(name).or_insert(next);
     }
 
     pub fn save(&self, path: &Path) -> Result<()> {
-        let contents =
-            serde_json::to_string_pretty(self).context("Failed to serialize configuration")?;
+        fs::write(path, self.to_json()?).context("Failed to write accounts configuration file")
+    }
+
+    /// The file contents `save` writes.
+    pub fn to_json(&self) -> Result<String> {
+        if self.broken.is_empty() {
+            return serde_json::to_string_pretty(self).context("Failed to serialize configuration");
+        }
+        let mut document =
+            serde_json::to_value(self).context("Failed to serialize configuration")?;
+        if let Some(accounts) = document
+            .get_mut("accounts")
+            .and_then(serde_json::Value::as_object_mut)
+        {
+            for (name, broken) in &self.broken {
+                accounts.insert(name.clone(), broken.entry.clone());
+            }
+        }
+        serde_json::to_string_pretty(&document).context("Failed to serialize configuration")
+    }
 
-        fs::write(path, contents).context("Failed to write accounts configuration file")
+    /// Whether `name` can be used: `None` when there's no such account.
+    pub fn health(&self, name: &str) -> Option<EntryHealth> {
+        if let Some(broken) = self.broken.get(name) {
+            return Some(EntryHealth::Corrupt(broken.error.clone()));
+        }
+        let meta = self.accounts.get(name)?;
+        if let Some(archive) = &meta.archive {
+            return Some(match archive.is_file() {
+                true => EntryHealth::Healthy,
+                false => EntryHealth::Missing(archive.clone()),
+            });
+        }
+        Some(match meta.path.is_dir() {
+            true => EntryHealth::Healthy,
+            false => EntryHealth::Missing(meta.path.clone()),
+        })
+    }
+
+    /// The accounts that can't be used, by name, with what's wrong.
+    pub fn unhealthy(&self) -> Vec<(String, EntryHealth)> {
+        let mut names: Vec<&String> = self.accounts.keys().chain(self.broken.keys()).collect();
+        names.sort();
+        names
+            .into_iter()
+            .filter_map(|name| Some((name.clone(), self.health(name)?)))
+            .filter(|(_, health)| *health != EntryHealth::Healthy)
+            .collect()
     }
 
     pub fn get_account(&self, name: &str) -> Option<&AccountMetadata> {
@@ -40,17 +363,46 @@ impl AccountsConfig {
     }
 
     pub fn add_account(&mut self, name: String, metadata: AccountMetadata) {
+        self.number(name.clone());
         self.accounts.insert(name, metadata);
     }
 
     pub fn remove_account(&mut self, name: &str) -> Option<AccountMetadata> {
+        if self.previous.as_deref() == Some(name) {
+            self.previous = None;
+        }
+        self.aliases.retain(|_, account| account != name);
+        self.numbers.remove(name);
+        self.projects.retain(|_, account| account != name);
+        self.profiles.retain(|_, profile| profile.account != name);
+        self.broken.remove(name);
         self.accounts.remove(name)
     }
 
     pub fn rename_account(&mut self, old_name: &str, new_name: String) -> Result<()> {
         if let Some(metadata) = self.accounts.remove(old_name) {
             self.accounts.insert(new_name.clone(), metadata);
+            if let Some(number) = self.numbers.remove(old_name) {
+                self.numbers.insert(new_name.clone(), number);
+            }
 
+            if self.previous.as_deref() == Some(old_name) {
+                self.previous = Some(new_name.clone());
+            }
+            let profiles = self
+                .profiles
+                .values_mut()
+                .map(|profile| &mut profile.account);
+            for account in self
+                .aliases
+                .values_mut()
+                .chain(self.projects.values_mut())
+                .chain(profiles)
+            {
+                if account == old_name {
+                    *account = new_name.clone();
+                }
+            }
             if self.current.as_deref() == Some(old_name) {
                 self.current = Some(new_name);
             }
@@ -60,6 +412,45 @@ impl AccountsConfig {
         }
     }
 
+    /// The number `list` shows for `name`.
+    pub fn number_of(&self, name: &str) -> Option<usize> {
+        self.numbers.get(name).copied()
+    }
+
+    /// The aliases standing for `name`.
+    pub fn aliases_of(&self, name: &str) -> Vec<&str> {
+        self.aliases
+            .iter()
+            .filter(|(_, account)| *account == name)
+            .map(|(alias, _)| alias.as_str())
+            .collect()
+    }
+
+    /// The account `target` stands for: the one of that name, else the one
+    /// with that alias, else the one with that number in `list`.
+    pub fn resolve(&self, target: &str) -> Option<&str> {
+        if let Some((name, _)) = self.accounts.get_key_value(target) {
+            return Some(name);
+        }
+        if let Some(name) = self.aliases.get(target) {
+            return Some(name);
+        }
+        let number: usize = target.parse().ok()?;
+        self.numbers
+            .iter()
+            .find(|(name, n)| **n == number && self.accounts.contains_key(*name))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Finds an existing account whose name matches `name` ignoring case but
+    /// is not identical to it.
+    pub fn find_case_conflict(&self, name: &str) -> Option<&str> {
+        self.accounts
+            .keys()
+            .find(|existing| existing.as_str() != name && existing.eq_ignore_ascii_case(name))
+            .map(String::as_str)
+    }
+
     pub fn is_empty(&self) -> bool {
         self.accounts.is_empty()
     }
@@ -77,6 +468,28 @@ mod tests {
         assert!(config.current.is_none());
         assert!(config.accounts.is_empty());
         assert!(config.is_empty());
+        assert_eq!(config.schema_version, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_migrations_reach_schema_version() -> Result<()> {
+        assert_eq!(MIGRATIONS.len(), SCHEMA_VERSION as usize);
+
+        let mut file = NamedTempFile::new()?;
+        write!(file, r#"{{"current": null, "accounts": {{}}}}"#)?;
+        assert_eq!(stored_version(&fs::read_to_string(file.path())?)?, 0);
+        let config = AccountsConfig::load(file.path())?;
+        assert_eq!(config.schema_version, SCHEMA_VERSION);
+        assert!(config.to_json()?.contains(r#""schema_version": 1"#));
+
+        let mut newer = NamedTempFile::new()?;
+        write!(
+            newer,
+            r#"{{"schema_version": 99, "current": null, "accounts": {{}}}}"#
+        )?;
+        let err = AccountsConfig::load(newer.path()).err().unwrap();
+        assert!(format!("{:#}", err).contains("newer than supported"));
+        Ok(())
     }
 
     #[test]
@@ -85,6 +498,7 @@ mod tests {
         let metadata = AccountMetadata {
             saved_at: "2024-01-01T00:00:00Z".to_string(),
             path: PathBuf::from("/test/path"),
+            ..Default::default()
         };
 
         config.add_account("test_account".to_string(), metadata);
@@ -99,6 +513,7 @@ mod tests {
         let metadata = AccountMetadata {
             saved_at: "2024-01-01T00:00:00Z".to_string(),
             path: PathBuf::from("/test/path"),
+            ..Default::default()
         };
 
         config.add_account("test_account".to_string(), metadata);
@@ -116,6 +531,7 @@ mod tests {
         let metadata = AccountMetadata {
             saved_at: "2024-01-01T00:00:00Z".to_string(),
             path: PathBuf::from("/test/path"),
+            ..Default::default()
         };
 
         config.add_account("test_account".to_string(), metadata);
@@ -140,6 +556,7 @@ mod tests {
         let metadata = AccountMetadata {
             saved_at: "2024-01-01T00:00:00Z".to_string(),
             path: PathBuf::from("/test/path"),
+            ..Default::default()
         };
 
         config.add_account("old_name".to_string(), metadata);
@@ -165,6 +582,7 @@ mod tests {
         let metadata = AccountMetadata {
             saved_at: "2024-01-01T00:00:00Z".to_string(),
             path: PathBuf::from("/test/path"),
+            ..Default::default()
         };
 
         config.add_account("old_name".to_string(), metadata);
@@ -175,20 +593,126 @@ mod tests {
         assert_eq!(config.current, Some("other_account".to_string()));
     }
 
+    #[test]
+    fn test_find_case_conflict() {
+        let mut config = AccountsConfig::default();
+        config.add_account(
+            "Work".to_string(),
+            AccountMetadata {
+                saved_at: "2024-01-01T00:00:00Z".to_string(),
+                path: PathBuf::from("/test/Work"),
+                ..Default::default()
+            },
+        );
+
+        assert_eq!(config.find_case_conflict("work"), Some("Work"));
+        assert_eq!(config.find_case_conflict("WORK"), Some("Work"));
+        assert_eq!(config.find_case_conflict("Work"), None);
+        assert_eq!(config.find_case_conflict("personal"), None);
+    }
+
+    #[test]
+    fn test_resolve_aliases_and_numbers() {
+        let mut config = AccountsConfig::default();
+        for name in ["work-main", "personal", "2"] {
+            config.add_account(
+                name.to_string(),
+                AccountMetadata {
+                    saved_at: "2024-01-01T00:00:00Z".to_string(),
+                    path: PathBuf::from("/test").join(name),
+                    ..Default::default()
+                },
+            );
+        }
+        config
+            .aliases
+            .insert("w".to_string(), "work-main".to_string());
+
+        // Numbered as they were added
+        assert_eq!(config.number_of("work-main"), Some(1));
+        assert_eq!(config.number_of("2"), Some(3));
+        assert_eq!(config.resolve("w"), Some("work-main"));
+        assert_eq!(config.resolve("1"), Some("work-main"));
+        // A name beats a number
+        assert_eq!(config.resolve("2"), Some("2"));
+        assert_eq!(config.resolve("0"), None);
+        assert_eq!(config.resolve("4"), None);
+
+        config
+            .rename_account("work-main", "work".to_string())
+            .unwrap();
+        assert_eq!(config.aliases_of("work"), ["w"]);
+        assert_eq!(config.resolve("1"), Some("work"));
+        config.remove_account("work");
+        assert!(config.aliases.is_empty());
+        assert_eq!(config.resolve("1"), None);
+
+        // The others keep theirs, and the number isn't given out again
+        config.add_account("new".to_string(), config.accounts["2"].clone());
+        assert_eq!(config.number_of("personal"), Some(2));
+        assert_eq!(config.number_of("new"), Some(4));
+    }
+
+    #[test]
+    fn test_corrupt_entries_are_set_aside_and_kept() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join(FILE);
+        let snapshot = dir.path().join("work");
+        fs::create_dir_all(&snapshot)?;
+        let entry = serde_json::json!({"saved_at": 5, "path": "/nowhere"});
+        let document = serde_json::json!({
+            "schema_version": SCHEMA_VERSION,
+            "current": "work",
+            "accounts": {
+                "work": {"saved_at": "2024-01-01T00:00:00Z", "path": snapshot},
+                "gone": {"saved_at": "2024-01-01T00:00:00Z", "path": dir.path().join("gone")},
+                "bad": entry,
+            },
+        });
+        fs::write(&path, document.to_string())?;
+
+        let config = AccountsConfig::load(&path)?;
+        assert_eq!(config.accounts.len(), 2);
+        assert_eq!(config.health("work"), Some(EntryHealth::Healthy));
+        assert_eq!(config.health("missing"), None);
+        let unhealthy = config.unhealthy();
+        assert_eq!(unhealthy[0].0, "bad");
+        assert!(
+            matches!(&unhealthy[0].1, EntryHealth::Corrupt(error) if error.contains("integer"))
+        );
+        assert_eq!(
+            unhealthy[1],
+            (
+                "gone".to_string(),
+                EntryHealth::Missing(dir.path().join("gone"))
+            )
+        );
+
+        // Written back as it was, until something removes it
+        config.save(&path)?;
+        let mut config = AccountsConfig::load(&path)?;
+        assert_eq!(config.broken["bad"].entry, entry);
+        config.remove_account("bad");
+        config.save(&path)?;
+        assert!(AccountsConfig::load(&path)?.broken.is_empty());
+        Ok(())
+    }
+
     #[test]
     fn test_save_and_load_config() -> Result<()> {
         let temp_file = NamedTempFile::new()?;
         let temp_path = temp_file.path();
 
         let mut config = AccountsConfig::default();
-        config.current = Some("test_account".to_string());
         config.add_account(
             "test_account".to_string(),
             AccountMetadata {
                 saved_at: "2024-01-01T00:00:00Z".to_string(),
                 path: PathBuf::from("/test/path"),
+                ..Default::default()
             },
         );
+        config.current = Some("test_account".to_string());
 
         config.save(temp_path)?;
 
@@ -228,6 +752,7 @@ mod tests {
                 AccountMetadata {
                     saved_at: format!("2024-01-{:02}T00:00:00Z", i),
                     path: PathBuf::from(format!("/test/path_{}", i)),
+                    ..Default::default()
                 },
             );
         }
@@ -0,0 +1,158 @@
+//! `config`: the switcher's own settings from the command line. It works on
+//! `settings.toml` directly, without setting up the account store, so a
+//! file that no longer parses can still be fixed from here.
+//!
+//! `get` and `list` show the settings as the other commands see them, with
+//! the `CLAUDE_ACCOUNT_*` variables laid over the file; `set`, `unset` and
+//! `edit` change the file alone.
+
+use crate::cli::ConfigAction;
+use crate::editor::Editor;
+use crate::output::{self, OutputFormat};
+use crate::paths::Paths;
+use crate::settings::{self, Settings};
+use crate::tr;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// Runs `action`. Returns false when `get` finds the key unset, which exits
+/// with 1 as `git config` does.
+pub fn run(action: ConfigAction, format: OutputFormat) -> Result<bool> {
+    let path = Paths::current()?.settings_file;
+    match action {
+        ConfigAction::Get { key } => {
+            let table = effective(&path)?;
+            let Some(value) = settings::get_key(&table, &key) else {
+                return Ok(false);
+            };
+            if format.is_json() {
+                output::print_json(value)?;
+            } else {
+                println!("{}", display(value));
+            }
+        }
+        ConfigAction::List => {
+            let table = effective(&path)?;
+            if format.is_json() {
+                output::print_json(&table)?;
+                return Ok(true);
+            }
+            if table.is_empty() {
+                println!("{}", tr!("config-empty"));
+            }
+            for key in settings::KEYS {
+                if let Some(value) = settings::get_key(&table, key) {
+                    println!("{} = {}", key, value);
+                }
+            }
+        }
+        ConfigAction::Set { key, value } => {
+            update(&path, &key, Some(settings::parse_value(&value)))?;
+        }
+        ConfigAction::Unset { key } => update(&path, &key, None)?,
+        ConfigAction::Edit => edit(&path)?,
+    }
+    Ok(true)
+}
+
+/// The settings file as a table, with the variables applied.
+fn effective(path: &Path) -> Result<toml::Table> {
+    let settings = Settings::load(path)?.with_env(std::env::vars())?;
+    toml::Table::try_from(&settings).context("Failed to serialize settings")
+}
+
+/// Sets `key` in the file, or removes it, once the result is known to be
+/// valid. The file is written back in full, so comments don't survive.
+pub(crate) fn update(path: &Path, key: &str, value: Option<toml::Value>) -> Result<()> {
+    let contents = if path.exists() {
+        fs::read_to_string(path).context("Failed to read settings file")?
+    } else {
+        String::new()
+    };
+    let mut table: toml::Table = contents
+        .parse()
+        .with_context(|| format!("Failed to parse settings file: {}", path.display()))?;
+    settings::set_key(&mut table, key, value);
+
+    let settings =
+        Settings::deserialize(table).with_context(|| tr!("config-invalid", key = key))?;
+    settings.validate()?;
+    settings.save(path)?;
+
+    if contents
+        .lines()
+        .any(|line| line.trim_start().starts_with('#'))
+    {
+        eprintln!("{}", tr!("config-comments-dropped"));
+    }
+    let var = settings::env_var(key);
+    if std::env::var_os(&var).is_some() {
+        eprintln!("{}", tr!("config-env-overrides", var = var, key = key));
+    }
+    Ok(())
+}
+
+/// Opens the file in `$VISUAL` or `$EDITOR`, then checks what was saved.
+fn edit(path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).context("Failed to create settings directory")?;
+    }
+    let editor = Editor::from_env();
+    if !editor.edit(path)? {
+        anyhow::bail!(tr!("config-editor-failed", editor = editor.program()));
+    }
+
+    Settings::load(path)?.validate()
+}
+
+/// Strings as they are, anything else as TOML.
+fn display(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_update_validates_and_keeps_other_keys() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("settings.toml");
+        fs::write(&path, "dedup = true\n")?;
+
+        update(
+            &path,
+            "prune.unused-after",
+            Some(settings::parse_value("90d")),
+        )?;
+        update(&path, "mode", Some(settings::parse_value("symlink")))?;
+        let settings = Settings::load(&path)?;
+        assert!(settings.dedup);
+        assert_eq!(settings.prune.unused_after.as_deref(), Some("90d"));
+
+        // Neither is written
+        assert!(update(&path, "compress", Some(settings::parse_value("99"))).is_err());
+        assert!(update(&path, "keep-versions", Some(settings::parse_value("many"))).is_err());
+        assert_eq!(Settings::load(&path)?, settings);
+
+        update(&path, "prune.unused-after", None)?;
+        assert!(!fs::read_to_string(&path)?.contains("prune"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(display(&settings::parse_value("symlink")), "symlink");
+        assert_eq!(display(&settings::parse_value("3")), "3");
+        assert_eq!(
+            display(&settings::parse_value("[\"projects/\"]")),
+            "[\"projects/\"]"
+        );
+    }
+}
@@ -0,0 +1,297 @@
+//! Crash reports: a panic hook that writes what was going on to a local file
+//! and tells the user how to recover. Nothing is sent anywhere.
+//!
+//! Operations note their progress in a per-thread journal, so a report from
+//! a panic halfway through a switch says which step was interrupted.
+
+use crate::claude;
+use crate::interrupt;
+use crate::paths;
+use crate::tr;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::fs;
+use std::panic::{self, PanicHookInfo};
+use std::path::{Path, PathBuf};
+
+thread_local! {
+    static JOURNAL: RefCell<Vec<Phase>> = const { RefCell::new(Vec::new()) };
+}
+
+/// A step within an operation, named by what it changes on disk.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Step {
+    Starting,
+    KeepingVersion,
+    CopyingSnapshot,
+    SwappingLive,
+    RestoringLive,
+    RestoringCredentials,
+    RestoringVersion,
+    UpdatingConfig,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Phase {
+    pub operation: &'static str,
+    pub account: String,
+    pub step: Step,
+}
+
+/// Keeps an operation in the journal until dropped.
+#[must_use]
+pub struct Operation(());
+
+/// Starts journaling `operation` on `account`. Operations nest: a switch
+/// starts with the save of the outgoing account.
+pub fn begin(operation: &'static str, account: &str) -> Operation {
+    tracing::info!(account = %account, "{}", operation);
+    JOURNAL.with_borrow_mut(|journal| {
+        journal.push(Phase {
+            operation,
+            account: account.to_string(),
+            step: Step::Starting,
+        })
+    });
+    Operation(())
+}
+
+/// Records that the innermost operation reached `step`, in the marker an
+/// interrupted one leaves behind too (see `interrupt`).
+pub fn step(step: Step) {
+    JOURNAL.with_borrow_mut(|journal| {
+        if let Some(phase) = journal.last_mut() {
+            tracing::info!(account = %phase.account, "{} {:?}", phase.operation, step);
+            phase.step = step;
+            interrupt::note(step, &phase.account);
+        }
+    });
+}
+
+impl Drop for Operation {
+    fn drop(&mut self) {
+        JOURNAL.with_borrow_mut(|journal| journal.pop());
+    }
+}
+
+pub fn journal() -> Vec<Phase> {
+    JOURNAL.with_borrow(Clone::clone)
+}
+
+#[derive(Serialize, Debug)]
+pub struct Report {
+    pub version: &'static str,
+    pub time: String,
+    pub os: &'static str,
+    pub arch: &'static str,
+    pub command: Vec<String>,
+    pub message: String,
+    pub location: Option<String>,
+    pub journal: Vec<Phase>,
+    pub recovery: Option<String>,
+    pub backtrace: String,
+}
+
+impl Report {
+    /// Collects a report for the current thread, redacting `home` and
+    /// anything that looks like a secret.
+    pub fn new(message: &str, location: Option<String>, home: Option<&Path>) -> Self {
+        let journal = journal();
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            time: Utc::now().to_rfc3339(),
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            command: redact_args(std::env::args().skip(1), home),
+            message: redact(message, home),
+            location,
+            recovery: recovery(&journal),
+            journal,
+            backtrace: redact(&Backtrace::force_capture().to_string(), home),
+        }
+    }
+
+    /// Writes the report as JSON into `dir`, returning its path.
+    pub fn write(&self, dir: &Path) -> Result<PathBuf> {
+        fs::create_dir_all(dir).context("Failed to create crash report directory")?;
+        let name = format!(
+            "crash-{}-{}.json",
+            Utc::now().format("%Y%m%dT%H%M%SZ"),
+            std::process::id()
+        );
+        let path = dir.join(name);
+        let contents =
+            serde_json::to_string_pretty(self).context("Failed to serialize crash report")?;
+        fs::write(&path, contents).context("Failed to write crash report")?;
+        Ok(path)
+    }
+}
+
+/// Installs the hook. The standard panic message is still printed first.
+pub fn install(dir: PathBuf) {
+    let home = paths::home_dir();
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        previous(info);
+
+        let location = info
+            .location()
+            .map(|location| format!("{}:{}", location.file(), location.line()));
+        let report = Report::new(&payload(info), location, home.as_deref());
+        match report.write(&dir) {
+            Ok(path) => eprintln!("{}", tr!("crash-report-written", path = path.display())),
+            Err(e) => eprintln!("{}", tr!("crash-report-failed", error = format!("{:#}", e))),
+        }
+        if let Some(recovery) = &report.recovery {
+            eprintln!("{}", recovery);
+        }
+    }));
+}
+
+fn payload(info: &PanicHookInfo) -> String {
+    let payload = info.payload();
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "(no message)".to_string())
+}
+
+/// What to do about the step the outermost operation was interrupted in.
+fn recovery(journal: &[Phase]) -> Option<String> {
+    let outer = journal.first()?;
+    let step = journal.last()?.step;
+    let account = &outer.account;
+    Some(match step {
+        Step::Starting => return None,
+        Step::KeepingVersion | Step::CopyingSnapshot => {
+            tr!("crash-recovery-snapshot", name = journal.last()?.account)
+        }
+        Step::SwappingLive | Step::RestoringLive | Step::RestoringCredentials => {
+            tr!("crash-recovery-live", name = account)
+        }
+        Step::RestoringVersion => tr!("crash-recovery-version", name = account),
+        Step::UpdatingConfig => tr!("crash-recovery-config"),
+    })
+}
+
+/// Replaces the home directory with `~` and masks Anthropic API keys and
+/// OAuth tokens.
+pub fn redact(text: &str, home: Option<&Path>) -> String {
+    let text = match home.and_then(Path::to_str).filter(|home| !home.is_empty()) {
+        Some(home) => text.replace(home, "~"),
+        None => text.to_string(),
+    };
+
+    let mut redacted = String::with_capacity(text.len());
+    let mut rest = text.as_str();
+    while let Some(start) = rest.find("sk-ant-") {
+        redacted.push_str(&rest[..start]);
+        let secret = &rest[start..];
+        let end = secret
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+            .unwrap_or(secret.len());
+        redacted.push_str(&claude::redact(&secret[..end]));
+        rest = &secret[end..];
+    }
+    redacted.push_str(rest);
+    redacted
+}
+
+/// Redacts each argument and drops passphrase values altogether.
+pub fn redact_args(args: impl IntoIterator<Item = String>, home: Option<&Path>) -> Vec<String> {
+    let mut redacted = Vec::new();
+    let mut hide_next = false;
+    for arg in args {
+        if hide_next {
+            redacted.push("****".to_string());
+            hide_next = false;
+        } else if arg == "--passphrase" {
+            redacted.push(arg);
+            hide_next = true;
+        } else if arg.starts_with("--passphrase=") {
+            redacted.push("--passphrase=****".to_string());
+        } else {
+            redacted.push(redact(&arg, home));
+        }
+    }
+    redacted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_journal_nests_operations() {
+        {
+            let _switch = begin("switch", "work");
+            {
+                let _save = begin("save", "personal");
+                step(Step::CopyingSnapshot);
+                assert_eq!(journal().len(), 2);
+            }
+            step(Step::RestoringLive);
+
+            let journal = journal();
+            assert_eq!(journal.len(), 1);
+            assert_eq!(journal[0].step, Step::RestoringLive);
+            assert!(recovery(&journal).unwrap().contains("switch work"));
+        }
+        assert!(journal().is_empty());
+    }
+
+    #[test]
+    fn test_redact() {
+        let home = Path::new("/home/alice");
+        assert_eq!(
+            redact("failed at /home/alice/.claude/x", Some(home)),
+            "failed at ~/.claude/x"
+        );
+
+        let text = redact("token sk-ant-REDACTED, done", None);
+        assert!(!text.contains("abcdefghijklmnop"));
+        assert!(text.ends_with(", done"));
+    }
+
+    #[test]
+    fn test_redact_args_hides_passphrases() {
+        let args = [
+            "import",
+            "--passphrase",
+            "hunter2",
+            "--passphrase=hunter2",
+            "b.tar",
+        ]
+        .map(String::from);
+        assert_eq!(
+            redact_args(args, None),
+            [
+                "import",
+                "--passphrase",
+                "****",
+                "--passphrase=****",
+                "b.tar"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_report_written_as_json() -> Result<()> {
+        let dir = TempDir::new()?;
+        let _op = begin("rollback", "work");
+        step(Step::RestoringVersion);
+
+        let path = Report::new("boom", None, None).write(dir.path())?;
+        let report: serde_json::Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+        assert_eq!(report["message"], "boom");
+        assert_eq!(report["journal"][0]["step"], "restoring-version");
+        assert!(report["recovery"].as_str().unwrap().contains("rollback"));
+        Ok(())
+    }
+}
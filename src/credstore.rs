@@ -0,0 +1,163 @@
+//! Keeps the contents of `.credentials.json` in the OS keychain (macOS
+//! Keychain, Secret Service on Linux, Credential Manager on Windows) instead
+//! of in the snapshot directory.
+
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Service name the keychain entries are filed under; the account name is the
+/// entry's user.
+pub const SERVICE: &str = "claude-account-switcher";
+
+/// Where account credentials live when `credentials = "keychain"`.
+pub trait CredentialStore: Send + Sync {
+    /// Returns the stored credentials, or `None` when the account has none.
+    fn load(&self, account: &str) -> Result<Option<String>>;
+
+    fn store(&self, account: &str, credentials: &str) -> Result<()>;
+
+    /// Removing an account without stored credentials is not an error.
+    fn remove(&self, account: &str) -> Result<()>;
+
+    /// Whether the store can be used at all here.
+    fn is_available(&self) -> bool {
+        true
+    }
+}
+
+/// The platform's native secret store, with the entries of one account store.
+pub struct Keychain {
+    service: String,
+}
+
+impl Keychain {
+    /// The entries of the store in `dir`. The user's own store files them
+    /// under `SERVICE`, as it always has; any other (the system store, a
+    /// sandbox) under `SERVICE` and its path, so that the same account name
+    /// in two stores is two entries.
+    pub fn for_store(dir: &Path, own: bool) -> Self {
+        let service = match own {
+            true => SERVICE.to_string(),
+            false => format!("{} ({})", SERVICE, dir.display()),
+        };
+        Self { service }
+    }
+
+    fn entry(&self, account: &str) -> Result<keyring::Entry> {
+        keyring::Entry::new(&self.service, account).context("Failed to open keychain entry")
+    }
+}
+
+impl CredentialStore for Keychain {
+    fn load(&self, account: &str) -> Result<Option<String>> {
+        match self.entry(account)?.get_password() {
+            Ok(credentials) => Ok(Some(credentials)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e).context("Failed to read credentials from the keychain"),
+        }
+    }
+
+    fn store(&self, account: &str, credentials: &str) -> Result<()> {
+        self.entry(account)?
+            .set_password(credentials)
+            .context("Failed to write credentials to the keychain")
+    }
+
+    fn remove(&self, account: &str) -> Result<()> {
+        match self.entry(account)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e).context("Failed to remove credentials from the keychain"),
+        }
+    }
+
+    /// Looks up an entry that never exists: a missing entry means the
+    /// keychain answered, anything else (no Secret Service, a locked-down
+    /// session) means it can't be used.
+    fn is_available(&self) -> bool {
+        self.entry("__capability-probe__")
+            .is_ok_and(|entry| matches!(entry.get_password(), Err(keyring::Error::NoEntry)))
+    }
+}
+
+/// Keeps credentials in memory, for tests and embedders without a keychain.
+#[derive(Default)]
+pub struct MemoryStore {
+    entries: Mutex<HashMap<String, String>>,
+}
+
+impl CredentialStore for MemoryStore {
+    fn load(&self, account: &str) -> Result<Option<String>> {
+        Ok(self.entries.lock().unwrap().get(account).cloned())
+    }
+
+    fn store(&self, account: &str, credentials: &str) -> Result<()> {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(account.to_string(), credentials.to_string());
+        Ok(())
+    }
+
+    fn remove(&self, account: &str) -> Result<()> {
+        self.entries.lock().unwrap().remove(account);
+        Ok(())
+    }
+}
+
+/// Moves stored credentials to a new account name.
+pub fn rename(store: &dyn CredentialStore, old: &str, new: &str) -> Result<()> {
+    if let Some(credentials) = store.load(old)? {
+        store.store(new, &credentials)?;
+        store.remove(old)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_store_roundtrip() -> Result<()> {
+        let store = MemoryStore::default();
+        assert_eq!(store.load("work")?, None);
+
+        store.store("work", "{\"token\":1}")?;
+        assert_eq!(store.load("work")?.as_deref(), Some("{\"token\":1}"));
+
+        store.remove("work")?;
+        store.remove("work")?;
+        assert_eq!(store.load("work")?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stores_keep_their_own_entries() {
+        let own = Keychain::for_store(Path::new("/home/me/.local/share/claude"), true);
+        assert_eq!(own.service, SERVICE);
+        let system = Keychain::for_store(Path::new("/srv/claude-accounts"), false);
+        let sandbox = Keychain::for_store(Path::new("/tmp/sandbox/data"), false);
+        assert_eq!(
+            system.service,
+            "claude-account-switcher (/srv/claude-accounts)"
+        );
+        assert_ne!(system.service, sandbox.service);
+    }
+
+    #[test]
+    fn test_rename_moves_entry() -> Result<()> {
+        let store = MemoryStore::default();
+        store.store("old", "secret")?;
+
+        rename(&store, "old", "new")?;
+        assert_eq!(store.load("old")?, None);
+        assert_eq!(store.load("new")?.as_deref(), Some("secret"));
+
+        // Nothing stored is nothing to move
+        rename(&store, "missing", "other")?;
+        assert_eq!(store.load("other")?, None);
+        Ok(())
+    }
+}
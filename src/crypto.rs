@@ -0,0 +1,196 @@
+use anyhow::{Context, Result};
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+
+pub const MAGIC: &[u8; 8] = b"CASENC01";
+/// Files sealed with an already derived key carry no salt of their own
+pub const SEALED_MAGIC: &[u8; 8] = b"CASSEAL1";
+pub const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const HEADER_LEN: usize = MAGIC.len() + SALT_LEN + NONCE_LEN;
+
+// Unambiguous alphabet for generated passphrases (no 0/O, 1/l/I)
+const PASSPHRASE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+pub fn encrypt(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    fill_random(&mut salt)?;
+    fill_random(&mut nonce)?;
+
+    let cipher = XChaCha20Poly1305::new(&derive_key(passphrase, &salt)?);
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce), plaintext)
+        .map_err(|_| anyhow::anyhow!("Failed to encrypt data"))?;
+
+    let mut out = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+pub fn decrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    if !is_encrypted(data) || data.len() < HEADER_LEN {
+        anyhow::bail!("Data is not in the encrypted format");
+    }
+
+    let salt = &data[MAGIC.len()..MAGIC.len() + SALT_LEN];
+    let nonce = &data[MAGIC.len() + SALT_LEN..HEADER_LEN];
+
+    let cipher = XChaCha20Poly1305::new(&derive_key(passphrase, salt)?);
+    cipher
+        .decrypt(XNonce::from_slice(nonce), &data[HEADER_LEN..])
+        .map_err(|_| anyhow::anyhow!("Decryption failed: wrong passphrase or corrupted data"))
+}
+
+pub fn is_sealed(data: &[u8]) -> bool {
+    data.starts_with(SEALED_MAGIC)
+}
+
+/// A key derived once from a passphrase, so many small files can be sealed
+/// without paying for key derivation each time.
+pub struct StoreKey(Key);
+
+impl StoreKey {
+    pub fn derive(passphrase: &str, salt: &[u8]) -> Result<Self> {
+        Ok(Self(derive_key(passphrase, salt)?))
+    }
+
+    pub fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        fill_random(&mut nonce)?;
+
+        let ciphertext = XChaCha20Poly1305::new(&self.0)
+            .encrypt(XNonce::from_slice(&nonce), plaintext)
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt data"))?;
+
+        let mut out = Vec::with_capacity(SEALED_MAGIC.len() + NONCE_LEN + ciphertext.len());
+        out.extend_from_slice(SEALED_MAGIC);
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&ciphertext);
+        Ok(out)
+    }
+
+    pub fn open(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let header_len = SEALED_MAGIC.len() + NONCE_LEN;
+        if !is_sealed(data) || data.len() < header_len {
+            anyhow::bail!("Data is not in the sealed format");
+        }
+
+        let nonce = &data[SEALED_MAGIC.len()..header_len];
+        XChaCha20Poly1305::new(&self.0)
+            .decrypt(XNonce::from_slice(nonce), &data[header_len..])
+            .map_err(|_| anyhow::anyhow!("Decryption failed: wrong key or corrupted data"))
+    }
+}
+
+pub fn generate_salt() -> Result<[u8; SALT_LEN]> {
+    let mut salt = [0u8; SALT_LEN];
+    fill_random(&mut salt)?;
+    Ok(salt)
+}
+
+pub fn generate_passphrase() -> Result<String> {
+    // Bytes past the last whole multiple of the alphabet's length are drawn
+    // again, so every character is as likely as any other
+    let limit = 256 - 256 % PASSPHRASE_ALPHABET.len();
+    let mut chars = Vec::with_capacity(24);
+    while chars.len() < 24 {
+        let mut bytes = [0u8; 32];
+        fill_random(&mut bytes)?;
+        chars.extend(
+            bytes
+                .iter()
+                .filter(|b| usize::from(**b) < limit)
+                .map(|b| PASSPHRASE_ALPHABET[usize::from(*b) % PASSPHRASE_ALPHABET.len()] as char),
+        );
+    }
+    chars.truncate(24);
+
+    // Group in blocks of six so it can be read out or typed without mistakes
+    Ok(chars
+        .chunks(6)
+        .map(|chunk| chunk.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("-"))
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<Key> {
+    let mut key = Key::default();
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow::anyhow!("Failed to derive encryption key: {}", e))?;
+    Ok(key)
+}
+
+fn fill_random(buf: &mut [u8]) -> Result<()> {
+    getrandom::fill(buf)
+        .map_err(|e| anyhow::anyhow!("{}", e))
+        .context("Failed to obtain random bytes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() -> Result<()> {
+        let data = b"secret credentials";
+        let encrypted = encrypt(data, "passphrase")?;
+
+        assert!(is_encrypted(&encrypted));
+        assert_ne!(&encrypted[HEADER_LEN..], data);
+        assert_eq!(decrypt(&encrypted, "passphrase")?, data);
+        Ok(())
+    }
+
+    #[test]
+    fn test_decrypt_wrong_passphrase() -> Result<()> {
+        let encrypted = encrypt(b"secret", "right")?;
+        let result = decrypt(&encrypted, "wrong");
+
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("wrong passphrase"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_decrypt_plain_data() {
+        assert!(!is_encrypted(b"plain"));
+        assert!(decrypt(b"plain", "passphrase").is_err());
+    }
+
+    #[test]
+    fn test_store_key_seal_open() -> Result<()> {
+        let salt = generate_salt()?;
+        let key = StoreKey::derive("passphrase", &salt)?;
+
+        let sealed = key.seal(b"token")?;
+        assert!(is_sealed(&sealed));
+        assert!(!is_encrypted(&sealed));
+        assert_eq!(key.open(&sealed)?, b"token");
+
+        let other = StoreKey::derive("other", &salt)?;
+        assert!(other.open(&sealed).is_err());
+        assert!(key.open(b"token").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_passphrase() -> Result<()> {
+        let first = generate_passphrase()?;
+        let second = generate_passphrase()?;
+
+        assert_eq!(first.len(), 27);
+        assert_eq!(first.split('-').count(), 4);
+        assert_ne!(first, second);
+        Ok(())
+    }
+}
@@ -0,0 +1,440 @@
+//! `daemon`: a background process answering on a Unix socket in the state
+//! directory, so prompt integrations and scripts get the current account
+//! without starting the switcher each time.
+//!
+//! The protocol is JSON-RPC 2.0, one request per line and one response line
+//! back, over as many lines as the connection lasts:
+//!
+//! ```text
+//! {"jsonrpc": "2.0", "id": 1, "method": "current"}
+//! {"jsonrpc": "2.0", "id": 1, "result": {"current": "work"}}
+//! ```
+//!
+//! Methods: `current`, `list`, `switch` (`{"name": ...}`), `status` and
+//! `shutdown`. The accounts file is kept in memory and read again only when
+//! it changes. Requests are served one at a time, and a switch takes the
+//! state lock as any other would, so the daemon and the command line never
+//! interleave.
+//!
+//! Between requests, every few minutes, the daemon checks when the active
+//! account's login expires, and reports it through `[notify]` once it's
+//! within `notify.expiring-within`.
+
+use crate::config::{self, AccountsConfig};
+use crate::history::EventSource;
+use crate::jsonrpc;
+use crate::manager::{AccountManager, SwitchOutcome};
+use crate::tr;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::fs;
+use std::path::Path;
+use std::time::SystemTime;
+
+pub const SOCKET: &str = "daemon.sock";
+pub const LOG: &str = "daemon.log";
+
+/// What a request asks for.
+#[derive(Deserialize, Debug, PartialEq, Eq)]
+#[serde(tag = "method", content = "params", rename_all = "kebab-case")]
+pub enum Request {
+    Current,
+    List,
+    Switch { name: String },
+    Status,
+    Shutdown,
+}
+
+/// Answers one request line through `handle`. Returns the response line and
+/// whether the daemon should stop after sending it.
+pub fn respond(line: &str, handle: impl FnOnce(Request) -> Result<Value>) -> (String, bool) {
+    let (id, request) =
+        jsonrpc::request::<Request>(line, &["current", "list", "switch", "status", "shutdown"]);
+    let shutdown = request
+        .as_ref()
+        .is_ok_and(|request| *request == Request::Shutdown);
+    let outcome = request.and_then(|request| handle(request).map_err(jsonrpc::Error::from));
+    (jsonrpc::response(id, outcome), shutdown)
+}
+
+/// The accounts file as last read, and when it had last changed then.
+struct Cache {
+    modified: Option<SystemTime>,
+    config: Option<AccountsConfig>,
+}
+
+impl Cache {
+    fn config(&mut self, manager: &AccountManager) -> Result<&AccountsConfig> {
+        let path = manager.state_dir().join(config::FILE);
+        let modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok();
+        if self.config.is_none() || modified != self.modified {
+            self.config = Some(manager.accounts_config()?);
+            self.modified = modified;
+        }
+        Ok(self.config.as_ref().expect("just loaded"))
+    }
+}
+
+/// Answers `request` for `manager`.
+fn serve_request(
+    manager: &AccountManager,
+    cache: &mut Cache,
+    started_at: DateTime<Utc>,
+    served: u64,
+    request: Request,
+) -> Result<Value> {
+    Ok(match request {
+        Request::Current => json!({"current": cache.config(manager)?.current}),
+        Request::List => {
+            let config = cache.config(manager)?;
+            let accounts: Vec<Value> = config
+                .accounts
+                .keys()
+                .map(|name| {
+                    json!({
+                        "name": name,
+                        "current": config.current.as_deref() == Some(name.as_str()),
+                    })
+                })
+                .collect();
+            Value::Array(accounts)
+        }
+        Request::Switch { name } => {
+            let name = manager.resolve_target(&name)?;
+            let outcome = manager.switch_for(&name, None, EventSource::Daemon)?;
+            json!({
+                "account": name,
+                "cancelled": matches!(outcome, SwitchOutcome::Cancelled),
+            })
+        }
+        Request::Status => json!({
+            "pid": std::process::id(),
+            "started_at": started_at,
+            "requests": served,
+        }),
+        Request::Shutdown => Value::Null,
+    })
+}
+
+#[cfg(unix)]
+pub use unix::{call, run, start, status, stop};
+
+#[cfg(unix)]
+mod unix {
+    use super::*;
+    use std::io::{BufRead, BufReader, ErrorKind, Write};
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::os::unix::process::CommandExt;
+    use std::process::{Command, Stdio};
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    // A client that stops talking mustn't hold up everyone after it
+    const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+    const START_TIMEOUT: Duration = Duration::from_secs(5);
+    const POLL: Duration = Duration::from_millis(20);
+    // How often the active account's login is checked for its expiry
+    const EXPIRY_CHECK: Duration = Duration::from_secs(5 * 60);
+
+    /// Sends one request and returns its result, or `None` when no daemon
+    /// answers on `socket`.
+    pub fn call(socket: &Path, method: &str, params: Option<Value>) -> Result<Option<Value>> {
+        let mut stream = match UnixStream::connect(socket) {
+            Ok(stream) => stream,
+            Err(e) if matches!(e.kind(), ErrorKind::NotFound | ErrorKind::ConnectionRefused) => {
+                return Ok(None);
+            }
+            Err(e) => return Err(e).context("Failed to reach the daemon"),
+        };
+        stream.set_read_timeout(Some(CLIENT_TIMEOUT))?;
+        let mut request = json!({"jsonrpc": "2.0", "id": 1, "method": method});
+        if let Some(params) = params {
+            request["params"] = params;
+        }
+        writeln!(stream, "{}", request).context("Failed to write to the daemon")?;
+
+        let mut line = String::new();
+        BufReader::new(stream)
+            .read_line(&mut line)
+            .context("Failed to read from the daemon")?;
+        let mut response: Value =
+            serde_json::from_str(&line).context("The daemon's answer isn't JSON")?;
+        if let Some(error) = response.get("error") {
+            anyhow::bail!(
+                "{}",
+                error["message"].as_str().unwrap_or("The daemon failed")
+            );
+        }
+        Ok(Some(response["result"].take()))
+    }
+
+    /// Serves requests on `socket` until asked to shut down.
+    pub fn run(manager: &AccountManager, socket: &Path) -> Result<()> {
+        if call(socket, "status", None)?.is_some() {
+            anyhow::bail!(tr!("daemon-already-running"));
+        }
+        // Left by a daemon that didn't get to clean up
+        let _ = fs::remove_file(socket);
+        let listener = UnixListener::bind(socket)
+            .with_context(|| format!("Failed to listen on {}", socket.display()))?;
+        fs::set_permissions(socket, fs::Permissions::from_mode(0o600))?;
+
+        let started_at = Utc::now();
+        let mut cache = Cache {
+            modified: None,
+            config: None,
+        };
+        let mut served = 0;
+        // Connections come in on their own thread, so the login can be
+        // checked between them
+        let (connections, incoming) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                if connections.send(stream).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut reported = None;
+        let mut checked = Instant::now();
+        check_expiry(manager, &mut reported);
+        loop {
+            match incoming.recv_timeout(EXPIRY_CHECK.saturating_sub(checked.elapsed())) {
+                Ok(stream) => {
+                    let shutdown = serve_connection(stream, |request| {
+                        served += 1;
+                        serve_request(manager, &mut cache, started_at, served, request)
+                    });
+                    if shutdown {
+                        break;
+                    }
+                }
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            }
+            if checked.elapsed() >= EXPIRY_CHECK {
+                check_expiry(manager, &mut reported);
+                checked = Instant::now();
+            }
+        }
+        let _ = fs::remove_file(socket);
+        Ok(())
+    }
+
+    // A check that fails is the log's business: the daemon goes on serving
+    fn check_expiry(manager: &AccountManager, reported: &mut Option<(String, DateTime<Utc>)>) {
+        if let Err(e) = manager.check_expiry(reported) {
+            eprintln!("{:#}", e);
+        }
+    }
+
+    /// Answers the requests on one connection. Returns whether one of them
+    /// was to shut down.
+    fn serve_connection(
+        stream: UnixStream,
+        mut handle: impl FnMut(Request) -> Result<Value>,
+    ) -> bool {
+        let _ = stream.set_read_timeout(Some(CLIENT_TIMEOUT));
+        let Ok(mut writer) = stream.try_clone() else {
+            return false;
+        };
+        for line in BufReader::new(stream).lines() {
+            let Ok(line) = line else { break };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (response, shutdown) = respond(&line, &mut handle);
+            if writeln!(writer, "{}", response).is_err() || shutdown {
+                return shutdown;
+            }
+        }
+        false
+    }
+
+    /// Starts the daemon in the background, running this same program with
+    /// `args`, and waits until it answers.
+    pub fn start(socket: &Path, log: &Path, args: &[&std::ffi::OsStr]) -> Result<u32> {
+        if let Some(status) = call(socket, "status", None)? {
+            anyhow::bail!(tr!("daemon-already-running-pid", pid = status["pid"]));
+        }
+        let program = std::env::current_exe().context("Failed to find this program")?;
+        let log_file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log)
+            .with_context(|| format!("Failed to open {}", log.display()))?;
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(log_file)
+            // Out of the terminal's process group, so Ctrl-C there leaves it be
+            .process_group(0)
+            .spawn()
+            .context("Failed to start the daemon")?;
+
+        let started = Instant::now();
+        while started.elapsed() < START_TIMEOUT {
+            if call(socket, "status", None)?.is_some() {
+                return Ok(child.id());
+            }
+            if child.try_wait()?.is_some() {
+                break;
+            }
+            thread::sleep(POLL);
+        }
+        anyhow::bail!(tr!("daemon-start-failed", log = log.display()))
+    }
+
+    /// Asks the daemon to shut down and waits until it has. Returns whether
+    /// one was running.
+    pub fn stop(socket: &Path) -> Result<bool> {
+        if call(socket, "shutdown", None)?.is_none() {
+            return Ok(false);
+        }
+        let started = Instant::now();
+        while socket.exists() && started.elapsed() < START_TIMEOUT {
+            thread::sleep(POLL);
+        }
+        Ok(true)
+    }
+
+    /// What the running daemon says about itself, if one is.
+    pub fn status(socket: &Path) -> Result<Option<Value>> {
+        call(socket, "status", None)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use tempfile::TempDir;
+
+        #[test]
+        fn test_served_over_the_socket() -> Result<()> {
+            let dir = TempDir::new()?;
+            let socket = dir.path().join(SOCKET);
+            assert!(call(&socket, "current", None)?.is_none());
+
+            let listener = UnixListener::bind(&socket)?;
+            let server = thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let shutdown = serve_connection(stream.unwrap(), |request| match request {
+                        Request::Current => Ok(json!({"current": "work"})),
+                        Request::Switch { name } => anyhow::bail!("No account {}", name),
+                        _ => Ok(Value::Null),
+                    });
+                    if shutdown {
+                        break;
+                    }
+                }
+            });
+
+            assert_eq!(
+                call(&socket, "current", None)?,
+                Some(json!({"current": "work"}))
+            );
+            let error = call(&socket, "switch", Some(json!({"name": "nope"}))).unwrap_err();
+            assert_eq!(error.to_string(), "No account nope");
+            assert_eq!(call(&socket, "shutdown", None)?, Some(Value::Null));
+            server.join().unwrap();
+            Ok(())
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod other {
+    use super::*;
+
+    fn unsupported<T>() -> Result<T> {
+        anyhow::bail!(tr!("daemon-unsupported"))
+    }
+
+    pub fn call(_socket: &Path, _method: &str, _params: Option<Value>) -> Result<Option<Value>> {
+        unsupported()
+    }
+
+    pub fn run(_manager: &AccountManager, _socket: &Path) -> Result<()> {
+        unsupported()
+    }
+
+    pub fn start(_socket: &Path, _log: &Path, _args: &[&std::ffi::OsStr]) -> Result<u32> {
+        unsupported()
+    }
+
+    pub fn stop(_socket: &Path) -> Result<bool> {
+        unsupported()
+    }
+
+    pub fn status(_socket: &Path) -> Result<Option<Value>> {
+        unsupported()
+    }
+}
+
+#[cfg(not(unix))]
+pub use other::{call, run, start, status, stop};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jsonrpc::{
+        INVALID_PARAMS, INVALID_REQUEST, METHOD_NOT_FOUND, PARSE_ERROR, SERVER_ERROR,
+    };
+
+    fn answer(line: &str) -> Value {
+        let (response, _) = respond(line, |request| match request {
+            Request::Current => Ok(json!({"current": "work"})),
+            Request::Switch { name } => Ok(json!({"account": name})),
+            _ => Ok(Value::Null),
+        });
+        serde_json::from_str(&response).unwrap()
+    }
+
+    #[test]
+    fn test_requests_and_their_errors() {
+        assert_eq!(
+            answer(r#"{"jsonrpc": "2.0", "id": 7, "method": "current"}"#),
+            json!({"jsonrpc": "2.0", "id": 7, "result": {"current": "work"}})
+        );
+        assert_eq!(
+            answer(r#"{"jsonrpc": "2.0", "id": "a", "method": "switch", "params": {"name": "x"}}"#)
+                ["result"],
+            json!({"account": "x"})
+        );
+
+        let code = |line: &str| answer(line)["error"]["code"].as_i64();
+        assert_eq!(code("not json"), Some(PARSE_ERROR));
+        assert_eq!(
+            code(r#"{"jsonrpc": "1.0", "method": "current"}"#),
+            Some(INVALID_REQUEST)
+        );
+        assert_eq!(
+            code(r#"{"jsonrpc": "2.0", "method": "delete"}"#),
+            Some(METHOD_NOT_FOUND)
+        );
+        assert_eq!(
+            code(r#"{"jsonrpc": "2.0", "method": "switch"}"#),
+            Some(INVALID_PARAMS)
+        );
+
+        let (_, shutdown) = respond(r#"{"jsonrpc": "2.0", "method": "shutdown"}"#, |_| {
+            Ok(Value::Null)
+        });
+        assert!(shutdown);
+    }
+
+    #[test]
+    fn test_failures_carry_their_kind() {
+        let (response, _) = respond(
+            r#"{"jsonrpc": "2.0", "id": 1, "method": "switch", "params": {"name": "x"}}"#,
+            |_| Err(crate::error::AccountError::NotFound("x".to_string()).into()),
+        );
+        let response: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(response["error"]["code"], SERVER_ERROR);
+        assert_eq!(response["error"]["data"]["kind"], "not-found");
+    }
+}
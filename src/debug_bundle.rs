@@ -0,0 +1,288 @@
+//! `debug-bundle`: what a bug report about the store needs, in one
+//! `.tar.zst` the user attaches themselves. Nothing is sent anywhere.
+//!
+//! It holds the crash reports and the history journal, `accounts.json`, the
+//! `doctor` findings and what the machine is. Secrets are masked as in
+//! `export --redacted` (see [`redact`](crate::redact)), the emails and
+//! organizations accounts are signed in as are masked too, as are the
+//! settings holding secrets and any email address, account names that are
+//! one included, and the home directory is written `~`. Snapshots and
+//! credentials are never included.
+
+use crate::crash;
+use crate::redact::{MASK, Redaction};
+use crate::settings::{self, SECRET_KEYS};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+const FILE_PREFIX: &str = "claude-account-debug-";
+const FILE_SUFFIX: &str = ".tar.zst";
+const LEVEL: i32 = 3;
+// Fields naming who an account belongs to
+const PERSONAL_KEYS: &[&str] = &["email", "emailAddress", "organization", "organizationName"];
+
+/// What the bundle says about the machine and the build.
+#[derive(Serialize, Debug)]
+pub struct SystemInfo {
+    pub version: &'static str,
+    pub os: &'static str,
+    pub family: &'static str,
+    pub arch: &'static str,
+    /// The distribution or OS release, where it can be found
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release: Option<String>,
+    pub created_at: String,
+}
+
+impl SystemInfo {
+    pub fn collect(now: DateTime<Utc>) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            os: std::env::consts::OS,
+            family: std::env::consts::FAMILY,
+            arch: std::env::consts::ARCH,
+            release: release(),
+            created_at: now.to_rfc3339(),
+        }
+    }
+}
+
+fn release() -> Option<String> {
+    let os_release = fs::read_to_string("/etc/os-release").ok()?;
+    os_release.lines().find_map(|line| {
+        let value = line.strip_prefix("PRETTY_NAME=")?;
+        Some(value.trim_matches('"').to_string())
+    })
+}
+
+/// Where the bundle goes in `dir` when no path is given.
+pub fn default_path(dir: &Path, now: DateTime<Utc>) -> PathBuf {
+    dir.join(format!(
+        "{}{}{}",
+        FILE_PREFIX,
+        now.format("%Y%m%dT%H%M%SZ"),
+        FILE_SUFFIX
+    ))
+}
+
+/// `contents` of a file from the state directory as it goes in the bundle:
+/// secrets and who the accounts belong to masked, and `home` written `~`.
+/// A file that isn't JSON, such as a corrupt `accounts.json`, still has its
+/// tokens masked.
+pub fn anonymize(path: &Path, contents: Vec<u8>, home: Option<&Path>) -> Vec<u8> {
+    let contents = Redaction::default()
+        .file(path, contents)
+        .unwrap_or_default();
+    let text = String::from_utf8_lossy(&contents);
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "toml")
+    {
+        return scrub(&mask_settings(&text), home).into_bytes();
+    }
+    let text = match serde_json::from_str::<Value>(&text) {
+        Ok(mut value) => {
+            mask_personal(&mut value);
+            serde_json::to_string_pretty(&value).unwrap_or_else(|_| text.into_owned())
+        }
+        // Each line of a journal is a document of its own
+        Err(_) => text
+            .lines()
+            .map(|line| match serde_json::from_str::<Value>(line) {
+                Ok(mut value) => {
+                    mask_personal(&mut value);
+                    value.to_string()
+                }
+                Err(_) => line.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    };
+    scrub(&text, home).into_bytes()
+}
+
+/// `text` with tokens and email addresses masked and `home` written `~`,
+/// for what goes in the bundle without being read from a file.
+pub fn scrub(text: &str, home: Option<&Path>) -> String {
+    mask_addresses(&crash::redact(text, home))
+}
+
+// Every `local@domain.tld` in `text`, wherever it is: an account named
+// after its login shows up in keys, paths and messages as well as fields
+fn mask_addresses(text: &str) -> String {
+    let is_local = |c: char| c.is_ascii_alphanumeric() || "._%+-".contains(c);
+    let is_domain = |c: char| c.is_ascii_alphanumeric() || ".-".contains(c);
+
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find('@') {
+        let (before, after) = (&rest[..at], &rest[at + 1..]);
+        let local = before.len() - before.trim_end_matches(is_local).len();
+        let domain = after.len() - after.trim_start_matches(is_domain).len();
+        let host = after[..domain].trim_end_matches('.');
+        let valid = local > 0
+            && host
+                .split_once('.')
+                .is_some_and(|(name, tld)| !name.is_empty() && !tld.is_empty());
+        match valid {
+            true => {
+                out.push_str(&before[..before.len() - local]);
+                out.push_str(MASK);
+                rest = &after[host.len()..];
+            }
+            false => {
+                out.push_str(before);
+                out.push('@');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// The settings file with the settings holding secrets masked; one that
+// doesn't parse is left as the token masking made it
+fn mask_settings(text: &str) -> String {
+    let Ok(mut table) = text.parse::<toml::Table>() else {
+        return text.to_string();
+    };
+    for key in SECRET_KEYS {
+        if settings::get_key(&table, key).is_some() {
+            settings::set_key(&mut table, key, Some(toml::Value::from(MASK)));
+        }
+    }
+    toml::to_string(&table).unwrap_or_else(|_| text.to_string())
+}
+
+fn mask_personal(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                match PERSONAL_KEYS.contains(&key.as_str()) && value.is_string() {
+                    true => *value = Value::String(MASK.to_string()),
+                    false => mask_personal(value),
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(mask_personal),
+        _ => {}
+    }
+}
+
+/// Writes `entries` (a path within the bundle and its contents) to `path`.
+pub fn write(path: &Path, entries: &[(PathBuf, Vec<u8>)]) -> Result<()> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create debug bundle: {}", path.display()))?;
+    let encoder = zstd::Encoder::new(BufWriter::new(file), LEVEL)
+        .context("Failed to start compressing the debug bundle")?;
+    let mut builder = tar::Builder::new(encoder);
+    for (name, contents) in entries {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o600);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, name, contents.as_slice())
+            .with_context(|| format!("Failed to write archive entry: {}", name.display()))?;
+    }
+    builder
+        .into_inner()
+        .and_then(|encoder| encoder.finish())
+        .and_then(|mut file| file.flush())
+        .context("Failed to finish the debug bundle")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Read;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_anonymize_masks_secrets_people_and_home() {
+        let accounts = json!({
+            "current": "work",
+            "accounts": {
+                "work": {
+                    "path": "/home/me/.local/share/claude-account-switcher/work",
+                    "identity": {"email": "me@example.com", "plan": "max"},
+                    "apiKey": "sk-ant-REDACTED",
+                }
+            }
+        });
+        let home = Path::new("/home/me");
+        let out = anonymize(
+            Path::new("accounts.json"),
+            accounts.to_string().into_bytes(),
+            Some(home),
+        );
+        let out: Value = serde_json::from_slice(&out).unwrap();
+        let work = &out["accounts"]["work"];
+        assert_eq!(work["path"], "~/.local/share/claude-account-switcher/work");
+        assert_eq!(work["identity"]["email"], MASK);
+        assert_eq!(work["identity"]["plan"], "max");
+        assert_eq!(work["apiKey"], MASK);
+
+        // A journal line by line, and a file that isn't JSON at all
+        let journal = b"{\"account\":\"work\",\"email\":\"me@example.com\"}\nnot json\n".to_vec();
+        let out = String::from_utf8(anonymize(Path::new("history.jsonl"), journal, None)).unwrap();
+        assert_eq!(
+            out,
+            format!("{{\"account\":\"work\",\"email\":\"{}\"}}\nnot json", MASK)
+        );
+        let broken = b"{\"key\": \"sk-ant-REDACTED\",".to_vec();
+        let out = String::from_utf8(anonymize(Path::new("accounts.json"), broken, None)).unwrap();
+        assert!(!out.contains("abcdefghijklmnopqrstuvwxyz"));
+
+        // An account named after its login, in keys, values and paths
+        let accounts = json!({
+            "current": "me@example.com",
+            "accounts": {"me@example.com": {"path": "/store/me@example.com"}},
+        });
+        let out = String::from_utf8(anonymize(
+            Path::new("accounts.json"),
+            accounts.to_string().into_bytes(),
+            None,
+        ))
+        .unwrap();
+        assert!(!out.contains("me@example.com"), "{}", out);
+        assert!(out.contains(&format!("/store/{}", MASK)));
+        assert_eq!(mask_addresses("a@b, x@y.z."), format!("a@b, {}.", MASK));
+
+        let settings = b"mode = \"copy\"\n\n[notify]\nwebhook = \"https://hooks.slack.com/services/T0/B0/SECRET\"\n".to_vec();
+        let out = String::from_utf8(anonymize(Path::new("settings.toml"), settings, None)).unwrap();
+        assert!(!out.contains("SECRET"));
+        assert!(out.contains("mode = \"copy\""));
+    }
+
+    #[test]
+    fn test_write_bundle() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = default_path(dir.path(), Utc::now());
+        write(&path, &[(PathBuf::from("system.json"), b"{}".to_vec())])?;
+
+        let mut tar = Vec::new();
+        zstd::Decoder::new(File::open(&path)?)?.read_to_end(&mut tar)?;
+        let mut archive = tar::Archive::new(tar.as_slice());
+        let names: Vec<_> = archive
+            .entries()?
+            .map(|entry| entry.unwrap().path().unwrap().into_owned())
+            .collect();
+        assert_eq!(names, [PathBuf::from("system.json")]);
+        Ok(())
+    }
+}
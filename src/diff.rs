@@ -0,0 +1,219 @@
+//! `diff`: what differs between the live configuration and a snapshot, or
+//! between two snapshots, file by file, with unified diffs for text files on
+//! request.
+
+use serde::Serialize;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+/// Lines of context around each change, as `diff -u` shows.
+const CONTEXT: usize = 3;
+
+// Past this many line pairs the changed middle is shown as replaced
+// wholesale rather than compared line by line
+const MAX_COMPARED: usize = 4_000_000;
+
+/// How a file in the live configuration (or the second snapshot) compares
+/// to the snapshot.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum Change {
+    /// Only in the live configuration, or the second snapshot
+    Added,
+    /// Only in the (first) snapshot
+    Removed,
+    Modified,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct FileChange {
+    /// Relative to the configuration directory
+    pub path: PathBuf,
+    pub change: Change,
+    /// The unified diff, with `--content`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diff: Option<String>,
+}
+
+enum Edit<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// A unified diff from `old` to `new`, or an empty string when they're the
+/// same. The lines both share at the start and end are set aside before
+/// comparing, so the usual small edit in a large file stays cheap.
+pub fn unified(old: &str, new: &str, old_label: &str, new_label: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let edits = edits(&old, &new);
+    if edits.iter().all(|edit| matches!(edit, Edit::Same(_))) {
+        return String::new();
+    }
+
+    let mut out = format!("--- {}\n+++ {}\n", old_label, new_label);
+    for hunk in hunks(&edits) {
+        let (mut old_line, mut new_line) = (1, 1);
+        for edit in &edits[..hunk.start] {
+            match edit {
+                Edit::Same(_) => (old_line, new_line) = (old_line + 1, new_line + 1),
+                Edit::Removed(_) => old_line += 1,
+                Edit::Added(_) => new_line += 1,
+            }
+        }
+        let lines = &edits[hunk];
+        let old_count = lines
+            .iter()
+            .filter(|edit| !matches!(edit, Edit::Added(_)))
+            .count();
+        let new_count = lines
+            .iter()
+            .filter(|edit| !matches!(edit, Edit::Removed(_)))
+            .count();
+        // An empty side is numbered from the line before it
+        let start = |line: usize, count| if count == 0 { line - 1 } else { line };
+        let _ = writeln!(
+            out,
+            "@@ -{},{} +{},{} @@",
+            start(old_line, old_count),
+            old_count,
+            start(new_line, new_count),
+            new_count
+        );
+        for edit in lines {
+            let _ = match edit {
+                Edit::Same(line) => writeln!(out, " {}", line),
+                Edit::Removed(line) => writeln!(out, "-{}", line),
+                Edit::Added(line) => writeln!(out, "+{}", line),
+            };
+        }
+    }
+    out
+}
+
+fn edits<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Edit<'a>> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_mid, new_mid) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    let mut edits: Vec<Edit> = old[..prefix].iter().map(|line| Edit::Same(line)).collect();
+    if old_mid.len().saturating_mul(new_mid.len()) > MAX_COMPARED {
+        edits.extend(old_mid.iter().map(|line| Edit::Removed(line)));
+        edits.extend(new_mid.iter().map(|line| Edit::Added(line)));
+        edits.extend(
+            old[old.len() - suffix..]
+                .iter()
+                .map(|line| Edit::Same(line)),
+        );
+        return edits;
+    }
+
+    // Longest common subsequence of what's left, filled in from the end
+    let width = new_mid.len() + 1;
+    let mut lengths = vec![0u32; (old_mid.len() + 1) * width];
+    for i in (0..old_mid.len()).rev() {
+        for j in (0..new_mid.len()).rev() {
+            lengths[i * width + j] = if old_mid[i] == new_mid[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    while i < old_mid.len() || j < new_mid.len() {
+        if i < old_mid.len() && j < new_mid.len() && old_mid[i] == new_mid[j] {
+            edits.push(Edit::Same(old_mid[i]));
+            (i, j) = (i + 1, j + 1);
+        } else if i < old_mid.len()
+            && (j == new_mid.len() || lengths[(i + 1) * width + j] >= lengths[i * width + j + 1])
+        {
+            // Removals first, as diff prints them
+            edits.push(Edit::Removed(old_mid[i]));
+            i += 1;
+        } else {
+            edits.push(Edit::Added(new_mid[j]));
+            j += 1;
+        }
+    }
+    edits.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|line| Edit::Same(line)),
+    );
+    edits
+}
+
+/// The ranges of `edits` to print: each change with its context, merging
+/// changes whose context would overlap.
+fn hunks(edits: &[Edit]) -> Vec<std::ops::Range<usize>> {
+    let mut hunks: Vec<std::ops::Range<usize>> = Vec::new();
+    for (index, edit) in edits.iter().enumerate() {
+        if matches!(edit, Edit::Same(_)) {
+            continue;
+        }
+        let start = index.saturating_sub(CONTEXT);
+        let end = (index + 1 + CONTEXT).min(edits.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.end => last.end = end,
+            _ => hunks.push(start..end),
+        }
+    }
+    hunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        let old = "{\n  \"theme\": \"dark\",\n  \"verbose\": false\n}\n";
+        let new = "{\n  \"theme\": \"light\",\n  \"verbose\": false\n}\n";
+        assert_eq!(
+            unified(old, new, "a/settings.json", "b/settings.json"),
+            concat!(
+                "--- a/settings.json\n",
+                "+++ b/settings.json\n",
+                "@@ -1,4 +1,4 @@\n",
+                " {\n",
+                "-  \"theme\": \"dark\",\n",
+                "+  \"theme\": \"light\",\n",
+                "   \"verbose\": false\n",
+                " }\n",
+            )
+        );
+        assert_eq!(unified(old, old, "a", "b"), "");
+    }
+
+    #[test]
+    fn test_distant_changes_get_separate_hunks() {
+        let mut lines: Vec<String> = (1..=20).map(|n| n.to_string()).collect();
+        let old = lines.join("\n");
+        lines[1] = "two".to_string();
+        lines.remove(18);
+        let new = lines.join("\n");
+        let diff = unified(&old, &new, "a", "b");
+        assert_eq!(diff.matches("@@ -").count(), 2);
+        assert!(diff.contains("@@ -1,5 +1,5 @@\n 1\n-2\n+two\n"));
+        assert!(diff.contains("@@ -16,5 +16,4 @@\n 16\n 17\n 18\n-19\n 20\n"));
+    }
+
+    #[test]
+    fn test_added_file_is_all_additions() {
+        assert_eq!(
+            unified("", "a\nb\n", "a", "b"),
+            "--- a\n+++ b\n@@ -0,0 +1,2 @@\n+a\n+b\n"
+        );
+    }
+}
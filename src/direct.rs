@@ -0,0 +1,93 @@
+//! `env` and `shell`: an account used straight from the store, by pointing
+//! `CLAUDE_CONFIG_DIR` at its snapshot, so two terminals can each have an
+//! account of their own while `~/.claude` stays as it is.
+
+use crate::completions;
+use anyhow::{Context, Result};
+use clap_complete::Shell;
+use std::path::Path;
+
+/// What Claude Code takes its configuration directory from
+pub const VAR: &str = "CLAUDE_CONFIG_DIR";
+
+/// The line that sets `CLAUDE_CONFIG_DIR` to `dir` in `shell`, or the shell
+/// in `$SHELL` when not given.
+pub fn exports(shell: Option<Shell>, dir: &Path) -> Result<String> {
+    export(resolve_shell(shell)?, VAR, &dir.to_string_lossy())
+}
+
+/// `shell`, or the one in `$SHELL` when not given.
+pub fn resolve_shell(shell: Option<Shell>) -> Result<Shell> {
+    match shell {
+        Some(shell) => Ok(shell),
+        None => completions::detect_shell(std::env::var("SHELL").ok().as_deref())
+            .context("Could not detect your shell; pass it with --shell (bash, zsh, fish, ...)"),
+    }
+}
+
+/// The line that sets the variable `var` to `value` in `shell`.
+pub fn export(shell: Shell, var: &str, value: &str) -> Result<String> {
+    let line = match shell {
+        Shell::Bash | Shell::Zsh => format!("export {}='{}'\n", var, value.replace('\'', "'\\''")),
+        Shell::Fish => format!(
+            "set -gx {} '{}'\n",
+            var,
+            value.replace('\\', "\\\\").replace('\'', "\\'")
+        ),
+        Shell::PowerShell => format!("$env:{} = '{}'\n", var, value.replace('\'', "''")),
+        _ => anyhow::bail!("Exports are not available for {}", shell),
+    };
+    Ok(line)
+}
+
+/// The line that removes the variable `var` in `shell`.
+pub fn unset(shell: Shell, var: &str) -> Result<String> {
+    let line = match shell {
+        Shell::Bash | Shell::Zsh => format!("unset {}\n", var),
+        Shell::Fish => format!("set -e -g {}\n", var),
+        Shell::PowerShell => format!("Remove-Item Env:{} -ErrorAction SilentlyContinue\n", var),
+        _ => anyhow::bail!("Exports are not available for {}", shell),
+    };
+    Ok(line)
+}
+
+/// The shell `shell` starts: the user's own, from `$SHELL` (`%COMSPEC%` on
+/// Windows).
+pub fn interactive_shell() -> String {
+    let (var, fallback) = match cfg!(windows) {
+        true => ("COMSPEC", "cmd.exe"),
+        false => ("SHELL", "/bin/sh"),
+    };
+    std::env::var(var)
+        .ok()
+        .filter(|shell| !shell.is_empty())
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exports_quote_the_directory() {
+        let dir = Path::new("/home/me/it's here");
+        assert_eq!(
+            exports(Some(Shell::Bash), dir).unwrap(),
+            "export CLAUDE_CONFIG_DIR='/home/me/it'\\''s here'\n"
+        );
+        assert_eq!(
+            exports(Some(Shell::Fish), dir).unwrap(),
+            "set -gx CLAUDE_CONFIG_DIR '/home/me/it\\'s here'\n"
+        );
+        assert_eq!(
+            exports(Some(Shell::PowerShell), dir).unwrap(),
+            "$env:CLAUDE_CONFIG_DIR = '/home/me/it''s here'\n"
+        );
+        assert!(exports(Some(Shell::Elvish), dir).is_err());
+        assert_eq!(unset(Shell::Zsh, VAR).unwrap(), "unset CLAUDE_CONFIG_DIR\n");
+        assert_eq!(
+            unset(Shell::Fish, VAR).unwrap(),
+            "set -e -g CLAUDE_CONFIG_DIR\n"
+        );
+    }
+}
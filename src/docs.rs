@@ -0,0 +1,1125 @@
+//! `--help` examples and `docs man`: what each command is for, with
+//! examples and exit codes, taken from one table so the help and the man
+//! pages packagers ship never disagree.
+
+use crate::cli::Cli;
+use crate::completions::BIN_NAME;
+use crate::error::EXIT_CODES;
+use crate::tr;
+use anyhow::{Context, Result};
+use clap::{Arg, Command, CommandFactory};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+/// A command line worth showing, and what it does.
+pub struct Example {
+    pub args: &'static str,
+    pub what: &'static str,
+}
+
+const fn example(args: &'static str, what: &'static str) -> Example {
+    Example { args, what }
+}
+
+/// The examples of each subcommand, by name.
+pub const EXAMPLES: &[(&str, &[Example])] = &[
+    (
+        "save",
+        &[
+            example("save work", "Save the live login as 'work'"),
+            example(
+                "save work --overwrite",
+                "Save over 'work' although another account is active",
+            ),
+            example(
+                "save work --store /media/usb/claude",
+                "Keep the snapshot on another drive",
+            ),
+            example(
+                "--system save team",
+                "Provision 'team' in the store every user shares",
+            ),
+        ],
+    ),
+    (
+        "switch",
+        &[
+            example("switch personal", "Switch to 'personal'"),
+            example("switch -", "Switch back to the account used before"),
+            example("switch 2", "Switch to the second account in `list`"),
+            example(
+                "switch personal --auto-save",
+                "Save the current account's changes first",
+            ),
+            example(
+                "switch personal --preview",
+                "See what would change in ~/.claude, then decide",
+            ),
+        ],
+    ),
+    (
+        "list",
+        &[
+            example("list --long", "Show the login, plan and size of each"),
+            example("list --tag client-a", "Only the accounts tagged client-a"),
+            example(
+                "list --stale-days 30",
+                "Only the accounts saved over 30 days ago or about to expire",
+            ),
+            example(
+                "list --format csv > seats.csv",
+                "Export the list for a spreadsheet",
+            ),
+        ],
+    ),
+    (
+        "size",
+        &[
+            example(
+                "size",
+                "Show what each account and its versions take on disk",
+            ),
+            example("size work", "Only 'work'"),
+        ],
+    ),
+    (
+        "add",
+        &[example(
+            "add personal",
+            "Log in to a new account and save it as 'personal'",
+        )],
+    ),
+    (
+        "renew",
+        &[example(
+            "renew work",
+            "Sign 'work' in again and save the fresh login",
+        )],
+    ),
+    (
+        "logout",
+        &[
+            example(
+                "logout",
+                "Sign ~/.claude out, saving the current account first",
+            ),
+            example(
+                "logout --all --keep-settings",
+                "Clear ~/.claude except settings.json",
+            ),
+        ],
+    ),
+    (
+        "add-existing",
+        &[example(
+            "add-existing dots ~/dotfiles/claude",
+            "Use a Claude directory kept in dotfiles in place",
+        )],
+    ),
+    (
+        "adopt",
+        &[
+            example(
+                "adopt /media/usb/claude-backup --name laptop",
+                "Copy a snapshot directory in as 'laptop'",
+            ),
+            example(
+                "adopt --scan",
+                "Register every directory in the store no account uses",
+            ),
+        ],
+    ),
+    (
+        "delete",
+        &[
+            example("delete old-account", "Move 'old-account' to the trash"),
+            example(
+                "delete 'test-*' old-account --yes",
+                "Delete several accounts without asking",
+            ),
+        ],
+    ),
+    (
+        "protect",
+        &[
+            example(
+                "protect customer-prod",
+                "Ask for the typed name before switching to it",
+            ),
+            example("protect customer-prod --off", "Stop asking"),
+        ],
+    ),
+    (
+        "pin",
+        &[
+            example(
+                "pin fragile --claude-version 2.0.14",
+                "Only switch to 'fragile' under Claude Code 2.0.14",
+            ),
+            example("pin fragile --off", "Switch to it under any version"),
+        ],
+    ),
+    (
+        "lock",
+        &[example(
+            "lock golden",
+            "Keep 'golden' from being saved over or deleted",
+        )],
+    ),
+    (
+        "unlock",
+        &[example("unlock golden", "Let 'golden' be saved over again")],
+    ),
+    (
+        "token",
+        &[example(
+            "token work --copy",
+            "Copy the token of 'work', clearing it after 30 seconds",
+        )],
+    ),
+    (
+        "envfile",
+        &[example(
+            "envfile ci --format github-actions >> \"$GITHUB_ENV\"",
+            "Sign the rest of a GitHub Actions job in as 'ci'",
+        )],
+    ),
+    (
+        "archive",
+        &[example(
+            "archive old-client",
+            "Pack 'old-client' into one file until it's needed",
+        )],
+    ),
+    (
+        "unarchive",
+        &[example(
+            "unarchive old-client",
+            "Unpack 'old-client' ahead of switching to it",
+        )],
+    ),
+    (
+        "limit-hit",
+        &[
+            example("limit-hit", "Mark the active account as rate limited"),
+            example("limit-hit work --resets-in 90m", "Say when the limit lifts"),
+            example("limit-hit work --clear", "Take the mark off"),
+        ],
+    ),
+    (
+        "rotate",
+        &[
+            example(
+                "rotate",
+                "Switch to the next account that isn't limited or expired",
+            ),
+            example("rotate --group work", "Only among the 'work' accounts"),
+        ],
+    ),
+    (
+        "audit",
+        &[example(
+            "audit",
+            "List the files holding tokens, flagging exposed ones",
+        )],
+    ),
+    (
+        "suggest",
+        &[
+            example(
+                "suggest",
+                "Rank the accounts by how good a pick each is now",
+            ),
+            example(
+                "suggest --group work --online --switch",
+                "Check the logins online and switch to the best",
+            ),
+        ],
+    ),
+    (
+        "menu",
+        &[
+            example("menu", "Pick an account in rofi, dmenu or the like"),
+            example("menu --via dmenu", "Use dmenu"),
+        ],
+    ),
+    (
+        "note",
+        &[
+            example("note work Acme", "Note which client 'work' is for"),
+            example("note work --edit", "Write the note in $EDITOR"),
+        ],
+    ),
+    (
+        "tag",
+        &[
+            example("tag add work client-a billing", "Tag 'work'"),
+            example("tag remove work billing", "Take a tag off"),
+        ],
+    ),
+    (
+        "var",
+        &[
+            example(
+                "var set client HTTPS_PROXY=http://proxy.acme:8080",
+                "Export a proxy while 'client' is active",
+            ),
+            example("var unset client HTTPS_PROXY", "Stop exporting it"),
+        ],
+    ),
+    (
+        "alias",
+        &[
+            example(
+                "alias set w work-main",
+                "Switch to 'work-main' with `switch w`",
+            ),
+            example("alias remove w", "Forget the alias"),
+        ],
+    ),
+    (
+        "rename",
+        &[example("rename old-name new-name", "Rename an account")],
+    ),
+    (
+        "copy",
+        &[example(
+            "copy work work-experiment",
+            "Start a new account from a copy of 'work'",
+        )],
+    ),
+    (
+        "template",
+        &[
+            example(
+                "template save strict-mode --from work",
+                "Keep the settings of 'work' as a template",
+            ),
+            example(
+                "template apply strict-mode",
+                "Layer it over ~/.claude, keeping the login",
+            ),
+        ],
+    ),
+    (
+        "preset",
+        &[
+            example("preset export -o team.json", "Write this setup to a file"),
+            example(
+                "preset import team.json",
+                "Lay it over the setup on another machine",
+            ),
+        ],
+    ),
+    (
+        "profile",
+        &[
+            example(
+                "profile create fast --account personal --env ANTHROPIC_MODEL=claude-haiku-4-5",
+                "Name 'personal' with a faster model",
+            ),
+            example("profile use fast", "Switch to it and print its exports"),
+        ],
+    ),
+    (
+        "run",
+        &[example(
+            "run work -- claude",
+            "Run Claude Code as 'work' without switching",
+        )],
+    ),
+    (
+        "remote",
+        &[
+            example(
+                "remote --host me@devbox switch work",
+                "Switch the account on a dev server",
+            ),
+            example(
+                "remote --host devbox current",
+                "See which one it's on, as JSON",
+            ),
+        ],
+    ),
+    (
+        "env",
+        &[
+            example(
+                "env personal",
+                "Print the exports that use 'personal' in this terminal",
+            ),
+            example("env personal --shell fish", "For fish"),
+        ],
+    ),
+    (
+        "shell",
+        &[example(
+            "shell personal",
+            "Start a subshell using 'personal'; exit to leave",
+        )],
+    ),
+    (
+        "sessions",
+        &[example(
+            "sessions",
+            "List the terminals using an account through env or shell",
+        )],
+    ),
+    (
+        "mcp-serve",
+        &[example(
+            "mcp-serve",
+            "Serve the accounts as MCP tools on stdin and stdout",
+        )],
+    ),
+    (
+        "api",
+        &[example(
+            "api",
+            "Answer JSON-RPC requests on stdin and stdout",
+        )],
+    ),
+    (
+        "daemon",
+        &[
+            example("daemon start", "Start the daemon in the background"),
+            example("daemon status", "Say whether it's running"),
+        ],
+    ),
+    (
+        "service",
+        &[
+            example(
+                "service install --daemon",
+                "Run the daemon as a user service",
+            ),
+            example(
+                "service install --backup daily --keep 14",
+                "Back up every day, keeping 14",
+            ),
+        ],
+    ),
+    (
+        "prompt",
+        &[
+            example("prompt", "Print the active account for PS1"),
+            example("prompt {name}:{plan}", "With its plan, as work:max"),
+        ],
+    ),
+    (
+        "status",
+        &[
+            example("status", "Show the active account and the store"),
+            example("status --porcelain", "The account alone, for status bars"),
+        ],
+    ),
+    (
+        "restore",
+        &[example(
+            "restore personal --file settings.json",
+            "Copy one file from a snapshot into ~/.claude",
+        )],
+    ),
+    (
+        "cat",
+        &[example(
+            "cat work settings.json",
+            "Show the settings 'work' was saved with, secrets masked",
+        )],
+    ),
+    (
+        "ls",
+        &[example(
+            "ls work plugins",
+            "List the plugins directory of 'work'",
+        )],
+    ),
+    (
+        "merge",
+        &[
+            example(
+                "merge work personal --only settings.json,CLAUDE.md --dry-run",
+                "See which files would be copied",
+            ),
+            example(
+                "merge work personal --only settings.json,CLAUDE.md",
+                "Copy them from 'work' into 'personal'",
+            ),
+        ],
+    ),
+    (
+        "diff",
+        &[
+            example("diff", "What changed since the active account was saved"),
+            example(
+                "diff work personal --content",
+                "Compare two accounts line by line",
+            ),
+        ],
+    ),
+    (
+        "current",
+        &[
+            example("current", "Print the active account"),
+            example(
+                "current --verbose",
+                "With its save time, drift and token expiry",
+            ),
+        ],
+    ),
+    (
+        "exists",
+        &[example("exists work", "Exit with 0 when 'work' is saved")],
+    ),
+    (
+        "is-current",
+        &[example(
+            "is-current work",
+            "Exit with 0 when 'work' is active",
+        )],
+    ),
+    (
+        "info",
+        &[
+            example(
+                "info work",
+                "Show who 'work' is signed in as and where it lives",
+            ),
+            example("info work --json", "The same, for scripts"),
+        ],
+    ),
+    (
+        "history",
+        &[example(
+            "history --source auto-save -n 20",
+            "The last 20 automatic saves",
+        )],
+    ),
+    (
+        "undo",
+        &[example("undo", "Reverse the last switch, delete or rename")],
+    ),
+    (
+        "recover-live",
+        &[
+            example(
+                "recover-live --list",
+                "List the live directories kept, newest first",
+            ),
+            example("recover-live", "Put the newest back"),
+        ],
+    ),
+    (
+        "recover",
+        &[example(
+            "recover --rollback",
+            "Undo a switch that was killed halfway",
+        )],
+    ),
+    (
+        "checkpoint",
+        &[
+            example(
+                "checkpoint save before-upgrade",
+                "Copy ~/.claude before trying a new Claude Code version",
+            ),
+            example("checkpoint restore before-upgrade", "Put that copy back"),
+        ],
+    ),
+    (
+        "trash",
+        &[
+            example("trash list", "What has been deleted, most recent first"),
+            example("trash restore work", "Bring 'work' back"),
+        ],
+    ),
+    (
+        "backup",
+        &[
+            example(
+                "backup create -o /mnt/nas",
+                "Back up every account to /mnt/nas",
+            ),
+            example(
+                "backup restore /mnt/nas/claude-accounts-20240101T093000Z.tar.zst",
+                "Restore a backup",
+            ),
+        ],
+    ),
+    (
+        "sync",
+        &[
+            example(
+                "sync init --remote git@github.com:me/claude-accounts.git",
+                "Sync through a git repository",
+            ),
+            example("sync push", "Send what was saved or deleted here"),
+            example("sync pull", "Bring in what was saved or deleted elsewhere"),
+        ],
+    ),
+    (
+        "rollback",
+        &[example(
+            "rollback work --to 20240101T093000Z",
+            "Put back an earlier version of 'work'",
+        )],
+    ),
+    ("tui", &[example("tui", "Open the dashboard")]),
+    (
+        "share",
+        &[example(
+            "share service-bot --expires 24h -o service-bot.casbundle",
+            "Bundle 'service-bot' for a teammate for a day",
+        )],
+    ),
+    (
+        "export",
+        &[
+            example("export work -o work.tar.zst", "Write 'work' to an archive"),
+            example(
+                "export --all --encrypt -o accounts.tar.zst",
+                "Every account, encrypted",
+            ),
+        ],
+    ),
+    (
+        "import",
+        &[
+            example(
+                "import service-bot.casbundle --dry-run",
+                "See what a bundle would bring in",
+            ),
+            example(
+                "import accounts.tar.zst --on-conflict rename",
+                "Import an archive, renaming clashes",
+            ),
+        ],
+    ),
+    (
+        "import-from",
+        &[
+            example(
+                "import-from profiles --dry-run",
+                "See what ~/.claude-profiles would bring in",
+            ),
+            example(
+                "import-from cc-switch --on-conflict rename",
+                "Import cc-switch's Claude providers",
+            ),
+        ],
+    ),
+    (
+        "init",
+        &[example(
+            "init",
+            "Walk through the main settings on a first run",
+        )],
+    ),
+    (
+        "doctor",
+        &[example("doctor", "Look for problems and how to fix them")],
+    ),
+    (
+        "debug-bundle",
+        &[example(
+            "debug-bundle -o report.tar.zst",
+            "Gather a redacted bundle to attach to a bug report",
+        )],
+    ),
+    (
+        "repair",
+        &[example(
+            "repair --relink work=/media/usb/work",
+            "Point 'work' at where its snapshot is now",
+        )],
+    ),
+    (
+        "upgrade-config",
+        &[
+            example("upgrade-config --dry-run", "See what would be rewritten"),
+            example("upgrade-config", "Rewrite the files in the current format"),
+        ],
+    ),
+    (
+        "migrate",
+        &[
+            example(
+                "migrate --dry-run",
+                "See what upgrading accounts.json would change",
+            ),
+            example("migrate", "Upgrade it and adopt unlisted snapshots"),
+        ],
+    ),
+    (
+        "migrate-legacy",
+        &[
+            example("migrate-legacy", "Move accounts out of ~/.claude-accounts"),
+            example(
+                "migrate-legacy --from ~/backups/claude-accounts",
+                "From a copy kept elsewhere",
+            ),
+        ],
+    ),
+    (
+        "inspect",
+        &[example(
+            "inspect service-bot.casbundle",
+            "Look inside a bundle before importing it",
+        )],
+    ),
+    (
+        "find",
+        &[
+            example(
+                "find --email dev@example.com",
+                "Find the accounts signed in as an email",
+            ),
+            example(
+                "find --email @acme.com --grep github",
+                "Of a domain, with some text in their settings",
+            ),
+        ],
+    ),
+    (
+        "grep",
+        &[example(
+            "grep github --ignore-case",
+            "Find the accounts that mention github",
+        )],
+    ),
+    (
+        "checksum",
+        &[
+            example("checksum work > work.sha256", "Record checksums of 'work'"),
+            example("checksum work --check work.sha256", "Verify them later"),
+        ],
+    ),
+    (
+        "check",
+        &[example(
+            "check --all --online",
+            "Check every account's login with the server",
+        )],
+    ),
+    (
+        "verify",
+        &[example(
+            "verify --all",
+            "Check every snapshot against what was saved",
+        )],
+    ),
+    (
+        "gc",
+        &[
+            example("gc --dry-run", "See what unused stored files would go"),
+            example(
+                "gc --orphans",
+                "Move directories no account uses to the trash",
+            ),
+            example("gc --orphans --adopt", "Register them as accounts instead"),
+        ],
+    ),
+    (
+        "prune",
+        &[
+            example(
+                "prune --dry-run",
+                "See what the retention rules would remove",
+            ),
+            example(
+                "prune --keep-versions 2 --unused-after 90d",
+                "With other rules for this run",
+            ),
+        ],
+    ),
+    (
+        "config",
+        &[
+            example("config set mode symlink", "Switch by linking by default"),
+            example("config get mode", "Print a setting"),
+            example("config list", "Print every setting that isn't the default"),
+        ],
+    ),
+    (
+        "encrypt",
+        &[
+            example(
+                "encrypt enable",
+                "Encrypt every saved account with a passphrase",
+            ),
+            example("encrypt disable", "Decrypt them again"),
+        ],
+    ),
+    (
+        "bind",
+        &[example(
+            "bind client",
+            "Use 'client' in this directory and below",
+        )],
+    ),
+    (
+        "project",
+        &[
+            example(
+                "project set client --path ~/acme",
+                "Map a directory to 'client' without writing into it",
+            ),
+            example("project list", "Show the mapped directories"),
+        ],
+    ),
+    (
+        "wrapper",
+        &[example(
+            "wrapper bash",
+            "Print a claude function that switches to the bound account first",
+        )],
+    ),
+    (
+        "auto",
+        &[example(
+            "auto",
+            "Switch to the account bound to this directory",
+        )],
+    ),
+    (
+        "hook",
+        &[
+            example("hook bash", "Print a hook that runs auto on every cd"),
+            example("hook fish", "For fish"),
+        ],
+    ),
+    (
+        "completions",
+        &[example(
+            "completions --install",
+            "Install completions for your shell",
+        )],
+    ),
+    (
+        "docs",
+        &[example(
+            "docs man --out /usr/share/man/man1",
+            "Write the man pages",
+        )],
+    ),
+];
+
+fn examples(name: &str) -> &'static [Example] {
+    EXAMPLES
+        .iter()
+        .find(|(command, _)| *command == name)
+        .map_or(&[], |(_, examples)| examples)
+}
+
+/// The command line, with each subcommand's examples and the exit codes
+/// added to its `--help`.
+pub fn command() -> Command {
+    let mut command = Cli::command().after_long_help(after_help("", &[]));
+    let names: Vec<String> = command
+        .get_subcommands()
+        .map(|sub| sub.get_name().to_string())
+        .collect();
+    for name in names {
+        command = command.mut_subcommand(&name, |sub| {
+            sub.after_long_help(after_help(&name, examples(&name)))
+        });
+    }
+    command
+}
+
+fn after_help(name: &str, examples: &[Example]) -> String {
+    let mut text = String::new();
+    if !examples.is_empty() {
+        text.push_str("Examples:\n");
+        for example in examples {
+            let _ = writeln!(text, "  {} {}", BIN_NAME, example.args);
+            let _ = writeln!(text, "      {}", example.what);
+        }
+        text.push('\n');
+    }
+    text.push_str("Exit codes:\n");
+    for (code, _, meaning) in EXIT_CODES {
+        let _ = writeln!(text, "  {:<4}{}", code, meaning);
+    }
+    if name.is_empty() {
+        let _ = write!(
+            text,
+            "\nSee '{} <COMMAND> --help' for each command.",
+            BIN_NAME
+        );
+    }
+    text.trim_end().to_string()
+}
+
+/// `docs man`: the main page on stdout, or every page written into `out`.
+pub fn run_man(out: Option<&Path>) -> Result<()> {
+    let pages = man_pages();
+    let Some(out) = out else {
+        print!("{}", pages[0].1);
+        return Ok(());
+    };
+    fs::create_dir_all(out)
+        .with_context(|| tr!("failed-create-directory", path = out.display()))?;
+    for (file, page) in &pages {
+        let path = out.join(file);
+        fs::write(&path, page).with_context(|| tr!("failed-write-file", path = path.display()))?;
+    }
+    println!(
+        "{}",
+        tr!("man-written", count = pages.len(), path = out.display())
+    );
+    Ok(())
+}
+
+/// Each man page's file name and roff source: the main page first, then one
+/// for each subcommand.
+pub fn man_pages() -> Vec<(String, String)> {
+    let mut command = command().bin_name(BIN_NAME);
+    command.build();
+    let mut pages = vec![(format!("{}.1", BIN_NAME), page(&command, BIN_NAME, ""))];
+    for sub in command.get_subcommands().filter(|sub| !sub.is_hide_set()) {
+        let title = format!("{}-{}", BIN_NAME, sub.get_name());
+        pages.push((format!("{}.1", title), page(sub, &title, sub.get_name())));
+    }
+    pages
+}
+
+fn page(command: &Command, title: &str, name: &str) -> String {
+    let mut roff = String::new();
+    let version = command.get_version().unwrap_or(env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(
+        roff,
+        ".TH {} 1 \"\" \"{} {}\"",
+        title.to_uppercase(),
+        BIN_NAME,
+        version
+    );
+    let about = command.get_about().map(|about| about.to_string());
+    section(&mut roff, "NAME");
+    let _ = writeln!(
+        roff,
+        "{} \\- {}",
+        escape(title),
+        escape(about.as_deref().unwrap_or_default())
+    );
+
+    section(&mut roff, "SYNOPSIS");
+    let usage = command.clone().render_usage().to_string();
+    let usage = usage.trim_start_matches("Usage:").trim();
+    let _ = writeln!(roff, "\\fB{}\\fR", escape(usage));
+
+    if let Some(description) = command.get_long_about().or(command.get_about()) {
+        section(&mut roff, "DESCRIPTION");
+        paragraphs(&mut roff, &description.to_string());
+    }
+
+    let visible =
+        |arg: &&Arg| !arg.is_hide_set() && !["help", "version"].contains(&arg.get_id().as_str());
+    let positional: Vec<_> = command.get_positionals().filter(visible).collect();
+    if !positional.is_empty() {
+        section(&mut roff, "ARGUMENTS");
+        for arg in positional {
+            let value = arg.get_id().as_str().to_uppercase();
+            argument(&mut roff, &format!("\\fI<{}>\\fR", escape(&value)), arg);
+        }
+    }
+    // Global options are described once, on the main page
+    let options: Vec<_> = command
+        .get_arguments()
+        .filter(|arg| !arg.is_positional())
+        .filter(visible)
+        .filter(|arg| name.is_empty() || !arg.is_global_set())
+        .collect();
+    if !options.is_empty() {
+        section(&mut roff, "OPTIONS");
+        for arg in options {
+            argument(&mut roff, &flags(arg), arg);
+        }
+    }
+
+    let subcommands: Vec<_> = command
+        .get_subcommands()
+        .filter(|sub| !sub.is_hide_set())
+        .collect();
+    if !subcommands.is_empty() {
+        section(&mut roff, "COMMANDS");
+        for sub in subcommands {
+            let _ = writeln!(roff, ".TP\n\\fB{}\\fR", escape(sub.get_name()));
+            let about = sub.get_about().map(|about| about.to_string());
+            let _ = writeln!(roff, "{}", escape(about.as_deref().unwrap_or_default()));
+            if name.is_empty() {
+                let _ = writeln!(roff, "See \\fB{}\\-{}\\fR(1).", BIN_NAME, sub.get_name());
+            }
+        }
+    }
+
+    let examples = examples(name);
+    if !examples.is_empty() {
+        section(&mut roff, "EXAMPLES");
+        for example in examples {
+            let _ = writeln!(
+                roff,
+                ".TP\n\\fB{} {}\\fR\n{}",
+                BIN_NAME,
+                escape(example.args),
+                escape(example.what)
+            );
+        }
+    }
+
+    section(&mut roff, "EXIT STATUS");
+    for (code, kind, meaning) in EXIT_CODES {
+        let _ = writeln!(roff, ".TP\n\\fB{}\\fR", code);
+        match kind.is_empty() {
+            true => {
+                let _ = writeln!(roff, "{}", escape(meaning));
+            }
+            false => {
+                let _ = writeln!(roff, "{} (\\fI{}\\fR)", escape(meaning), escape(kind));
+            }
+        }
+    }
+    if !name.is_empty() {
+        section(&mut roff, "SEE ALSO");
+        let _ = writeln!(roff, "\\fB{}\\fR(1)", BIN_NAME);
+    }
+    roff
+}
+
+fn section(roff: &mut String, title: &str) {
+    let _ = writeln!(roff, ".SH {}", title);
+}
+
+fn paragraphs(roff: &mut String, text: &str) {
+    for (i, paragraph) in text.split("\n\n").enumerate() {
+        if i > 0 {
+            roff.push_str(".PP\n");
+        }
+        let _ = writeln!(roff, "{}", escape(paragraph.trim()));
+    }
+}
+
+// `-f, --format <FORMAT>` in bold and italics
+fn flags(arg: &Arg) -> String {
+    let mut names = Vec::new();
+    if let Some(short) = arg.get_short() {
+        names.push(format!("\\fB\\-{}\\fR", short));
+    }
+    if let Some(long) = arg.get_long() {
+        names.push(format!("\\fB\\-\\-{}\\fR", escape(long)));
+    }
+    let mut flags = names.join(", ");
+    if arg.get_action().takes_values() {
+        let value = arg
+            .get_value_names()
+            .and_then(|names| names.first())
+            .map_or_else(
+                || arg.get_id().as_str().to_uppercase(),
+                |name| name.to_string(),
+            );
+        let _ = write!(flags, " \\fI<{}>\\fR", escape(&value));
+    }
+    flags
+}
+
+fn argument(roff: &mut String, label: &str, arg: &Arg) {
+    let _ = writeln!(roff, ".TP\n{}", label);
+    let help = arg.get_long_help().or(arg.get_help());
+    if let Some(help) = help {
+        let _ = writeln!(roff, "{}", escape(&help.to_string()));
+    }
+    let values: Vec<_> = arg
+        .get_possible_values()
+        .into_iter()
+        .filter(|value| !value.is_hide_set())
+        .map(|value| value.get_name().to_string())
+        .collect();
+    if !values.is_empty() {
+        let _ = writeln!(roff, ".br\nOne of: {}", escape(&values.join(", ")));
+    }
+}
+
+// Text safe to put in roff: backslashes and dashes escaped, and no line
+// starting with what roff would take for a request
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\e")
+        .replace('-', "\\-")
+        .lines()
+        .map(|line| match line.starts_with(['.', '\'']) {
+            true => format!("\\&{}", line),
+            false => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_examples_name_real_commands() {
+        let command = Cli::command();
+        for (name, examples) in EXAMPLES {
+            assert!(command.find_subcommand(name).is_some(), "{}", name);
+            for example in *examples {
+                let args = example
+                    .args
+                    .split_once(" >")
+                    .map_or(example.args, |(args, _)| args);
+                let argv = std::iter::once(BIN_NAME)
+                    .chain(args.split(' ').map(|arg| arg.trim_matches('\'')));
+                assert!(
+                    Cli::command().try_get_matches_from(argv).is_ok(),
+                    "{}",
+                    example.args
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_every_command_has_examples() {
+        // The hidden ones are run by the shell hooks, not typed
+        for command in Cli::command()
+            .get_subcommands()
+            .filter(|c| !c.is_hide_set())
+        {
+            let name = command.get_name();
+            assert!(!examples(name).is_empty(), "{} has no examples", name);
+        }
+    }
+
+    #[test]
+    fn test_help_and_man_pages_show_examples_and_exit_codes() {
+        let mut command = command();
+        let help = command
+            .find_subcommand_mut("save")
+            .unwrap()
+            .render_long_help()
+            .to_string();
+        assert!(help.contains("claude-account-switcher save work --overwrite"));
+        assert!(help.contains("64  The command line isn't valid"));
+
+        let pages = man_pages();
+        assert_eq!(pages[0].0, "claude-account-switcher.1");
+        let (_, save) = pages
+            .iter()
+            .find(|(file, _)| file == "claude-account-switcher-save.1")
+            .unwrap();
+        assert!(save.starts_with(".TH CLAUDE-ACCOUNT-SWITCHER-SAVE 1"));
+        assert!(save.contains(".SH EXAMPLES"));
+        assert!(save.contains("\\fB\\-\\-overwrite\\fR"));
+        assert!(save.contains("already\\-exists"));
+        // Global options are only on the main page
+        assert!(!save.contains("\\-\\-json"));
+        assert!(pages[0].1.contains("\\-\\-json"));
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a-b \\ c"), "a\\-b \\e c");
+        assert_eq!(escape(".hidden\n'quoted"), "\\&.hidden\n\\&'quoted");
+    }
+}
@@ -0,0 +1,157 @@
+//! `doctor`: checks of the live configuration and the account store, each
+//! with a suggested fix that `--fix` can apply where it's safe to.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum Severity {
+    Ok,
+    Warning,
+    Problem,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Finding {
+    pub severity: Severity,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+    /// Whether `--fix` applied `fix`
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub fixed: bool,
+}
+
+impl Finding {
+    pub fn ok(message: String) -> Self {
+        Self::new(Severity::Ok, message)
+    }
+
+    pub fn warning(message: String) -> Self {
+        Self::new(Severity::Warning, message)
+    }
+
+    pub fn problem(message: String) -> Self {
+        Self::new(Severity::Problem, message)
+    }
+
+    fn new(severity: Severity, message: String) -> Self {
+        Self {
+            severity,
+            message,
+            fix: None,
+            fixed: false,
+        }
+    }
+
+    pub fn with_fix(mut self, fix: String) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+
+    /// Still needs attention: a problem that wasn't fixed.
+    pub fn is_outstanding(&self) -> bool {
+        self.severity == Severity::Problem && !self.fixed
+    }
+}
+
+/// Top-level directories in the store that no account's snapshot is in or
+/// under. Dot directories hold versions and staging areas, so they're left out.
+pub fn orphaned_dirs(store: &Path, tracked: &[&Path]) -> Result<Vec<PathBuf>> {
+    let mut orphans = Vec::new();
+    for entry in fs::read_dir(store)
+        .with_context(|| format!("Failed to read directory: {}", store.display()))?
+    {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() || entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+
+        let path = entry.path();
+        if !tracked.iter().any(|tracked| tracked.starts_with(&path)) {
+            orphans.push(path);
+        }
+    }
+    orphans.sort();
+    Ok(orphans)
+}
+
+/// Whether anyone but the owner can read or write `path`. Always false where
+/// permissions aren't Unix modes.
+pub fn is_too_open(path: &Path) -> Result<bool> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(path)
+            .with_context(|| format!("Failed to read permissions: {}", path.display()))?
+            .permissions()
+            .mode();
+        Ok(mode & 0o077 != 0)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        Ok(false)
+    }
+}
+
+pub fn make_private(path: &Path) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))
+            .with_context(|| format!("Failed to set permissions: {}", path.display()))?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_orphaned_dirs() -> Result<()> {
+        let dir = TempDir::new()?;
+        let store = dir.path();
+        for name in ["work", "team/alpha", "stray", ".versions"] {
+            fs::create_dir_all(store.join(name))?;
+        }
+        fs::write(store.join("notes.txt"), "")?;
+
+        let tracked = [store.join("work"), store.join("team/alpha")];
+        let tracked: Vec<&Path> = tracked.iter().map(PathBuf::as_path).collect();
+        assert_eq!(orphaned_dirs(store, &tracked)?, [store.join("stray")]);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_make_private() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new()?;
+        let path = dir.path().join(".credentials.json");
+        fs::write(&path, "{}")?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644))?;
+        assert!(is_too_open(&path)?);
+
+        make_private(&path)?;
+        assert!(!is_too_open(&path)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_only_unfixed_problems_are_outstanding() {
+        assert!(!Finding::warning("stale".into()).is_outstanding());
+        let mut finding = Finding::problem("missing".into());
+        assert!(finding.is_outstanding());
+        finding.fixed = true;
+        assert!(!finding.is_outstanding());
+    }
+}
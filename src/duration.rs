@@ -0,0 +1,80 @@
+use anyhow::Result;
+use chrono::Duration;
+
+/// Parses human-friendly durations such as `30m`, `24h`, `7d` or `2w`.
+pub fn parse_duration(input: &str) -> Result<Duration> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (amount, unit) = input.split_at(split);
+
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid duration '{}': expected e.g. 24h or 7d", input))?;
+
+    let duration = match unit {
+        "s" => Duration::try_seconds(amount),
+        "m" => Duration::try_minutes(amount),
+        "h" | "" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        _ => anyhow::bail!("Invalid duration unit '{}': use s, m, h, d or w", unit),
+    };
+
+    duration.ok_or_else(|| anyhow::anyhow!("Duration '{}' is out of range", input))
+}
+
+/// Formats a countdown compactly for status bars: `3d4h`, `2h05m`, `45m`.
+/// Anything under a minute, or negative, is `0m`.
+pub fn format_countdown(duration: Duration) -> String {
+    let minutes = duration.num_minutes().max(0);
+    let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
+    if days > 0 {
+        format!("{}d{}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h{:02}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration_units() -> Result<()> {
+        assert_eq!(parse_duration("45s")?, Duration::seconds(45));
+        assert_eq!(parse_duration("30m")?, Duration::minutes(30));
+        assert_eq!(parse_duration("24h")?, Duration::hours(24));
+        assert_eq!(parse_duration("7d")?, Duration::days(7));
+        assert_eq!(parse_duration("2w")?, Duration::weeks(2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_duration_defaults_to_hours() -> Result<()> {
+        assert_eq!(parse_duration("12")?, Duration::hours(12));
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_countdown() {
+        assert_eq!(
+            format_countdown(Duration::minutes(3 * 24 * 60 + 4 * 60 + 5)),
+            "3d4h"
+        );
+        assert_eq!(format_countdown(Duration::minutes(125)), "2h05m");
+        assert_eq!(format_countdown(Duration::seconds(45 * 60 + 59)), "45m");
+        assert_eq!(format_countdown(Duration::seconds(-30)), "0m");
+    }
+
+    #[test]
+    fn test_parse_duration_invalid() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("10y").is_err());
+        assert!(parse_duration("-5d").is_err());
+    }
+}
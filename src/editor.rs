@@ -0,0 +1,38 @@
+//! The user's editor, for changing a file by hand: `config edit` and
+//! `note --edit`.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::process::Command;
+
+pub struct Editor {
+    command: String,
+}
+
+impl Editor {
+    /// `$VISUAL`, then `$EDITOR`, then `vi` (`notepad` on Windows).
+    pub fn from_env() -> Self {
+        let command = ["VISUAL", "EDITOR"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|editor| !editor.trim().is_empty())
+            .unwrap_or_else(|| if cfg!(windows) { "notepad" } else { "vi" }.to_string());
+        Self { command }
+    }
+
+    pub fn program(&self) -> &str {
+        self.command.split_whitespace().next().unwrap_or("vi")
+    }
+
+    /// Opens `path` and waits for the editor to close. Returns false when it
+    /// exits with an error.
+    pub fn edit(&self, path: &Path) -> Result<bool> {
+        // `code --wait` and the like come with arguments of their own
+        let status = Command::new(self.program())
+            .args(self.command.split_whitespace().skip(1))
+            .arg(path)
+            .status()
+            .with_context(|| format!("Failed to start editor: {}", self.program()))?;
+        Ok(status.success())
+    }
+}
@@ -0,0 +1,174 @@
+//! At-rest encryption for the account store.
+//!
+//! When enabled, every file in a snapshot is sealed with a key derived from
+//! the store passphrase. The key file only holds the salt and a sealed check
+//! value used to tell a wrong passphrase apart from corrupted data.
+
+use crate::crypto::{self, StoreKey};
+use crate::store;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// Supplies the store passphrase non-interactively, e.g. for background re-saves.
+pub const PASSPHRASE_ENV: &str = "CLAUDE_ACCOUNT_STORE_PASSPHRASE";
+
+const CHECK_PLAINTEXT: &[u8] = b"claude-account-switcher";
+
+#[derive(Serialize, Deserialize)]
+struct KeyFile {
+    salt: String,
+    check: String,
+}
+
+pub struct Vault {
+    key: StoreKey,
+}
+
+impl Vault {
+    pub fn is_enabled(key_file: &Path) -> bool {
+        key_file.exists()
+    }
+
+    /// Sets up encryption with a new salt and writes the key file.
+    pub fn create(key_file: &Path, passphrase: &str) -> Result<Self> {
+        let salt = crypto::generate_salt()?;
+        let key = StoreKey::derive(passphrase, &salt)?;
+
+        let contents = KeyFile {
+            salt: to_hex(&salt),
+            check: to_hex(&key.seal(CHECK_PLAINTEXT)?),
+        };
+        let json =
+            serde_json::to_string_pretty(&contents).context("Failed to serialize key file")?;
+        fs::write(key_file, json).context("Failed to write key file")?;
+
+        Ok(Self { key })
+    }
+
+    pub fn unlock(key_file: &Path, passphrase: &str) -> Result<Self> {
+        let contents = fs::read_to_string(key_file).context("Failed to read key file")?;
+        let contents: KeyFile =
+            serde_json::from_str(&contents).context("Failed to parse key file")?;
+
+        let salt = from_hex(&contents.salt)?;
+        let key = StoreKey::derive(passphrase, &salt)?;
+        let check = key
+            .open(&from_hex(&contents.check)?)
+            .map_err(|_| anyhow::anyhow!("Wrong store passphrase"))?;
+        if check != CHECK_PLAINTEXT {
+            anyhow::bail!("Wrong store passphrase");
+        }
+
+        Ok(Self { key })
+    }
+
+    /// Seals `data`, leaving already sealed data untouched.
+    pub fn seal(&self, data: Vec<u8>) -> Result<Vec<u8>> {
+        if crypto::is_sealed(&data) {
+            return Ok(data);
+        }
+        self.key.seal(&data)
+    }
+
+    /// Opens sealed `data`, passing plaintext through.
+    pub fn open(&self, data: Vec<u8>) -> Result<Vec<u8>> {
+        if !crypto::is_sealed(&data) {
+            return Ok(data);
+        }
+        self.key.open(&data)
+    }
+
+    /// Seals every file below `dir` in place. Returns the number of files.
+    pub fn seal_tree(&self, dir: &Path) -> Result<u64> {
+        store::rewrite_tree(dir, |data| self.seal(data))
+    }
+
+    /// Opens every file below `dir` in place. Returns the number of files.
+    pub fn open_tree(&self, dir: &Path) -> Result<u64> {
+        store::rewrite_tree(dir, |data| self.open(data))
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>> {
+    if !hex.is_ascii() || !hex.len().is_multiple_of(2) {
+        anyhow::bail!("Key file contains invalid hex");
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|_| anyhow::anyhow!("Key file contains invalid hex"))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_create_and_unlock() -> Result<()> {
+        let dir = TempDir::new()?;
+        let key_file = dir.path().join("store-key.json");
+
+        let vault = Vault::create(&key_file, "passphrase")?;
+        assert!(Vault::is_enabled(&key_file));
+        let sealed = vault.seal(b"token".to_vec())?;
+
+        let unlocked = Vault::unlock(&key_file, "passphrase")?;
+        assert_eq!(unlocked.open(sealed)?, b"token");
+
+        let err = Vault::unlock(&key_file, "wrong").err().unwrap();
+        assert!(err.to_string().contains("Wrong store passphrase"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_seal_and_open_pass_through() -> Result<()> {
+        let dir = TempDir::new()?;
+        let vault = Vault::create(&dir.path().join("key.json"), "passphrase")?;
+
+        let sealed = vault.seal(b"token".to_vec())?;
+        assert_eq!(vault.seal(sealed.clone())?, sealed);
+        assert_eq!(vault.open(b"plain".to_vec())?, b"plain");
+        Ok(())
+    }
+
+    #[test]
+    fn test_seal_and_open_tree() -> Result<()> {
+        let dir = TempDir::new()?;
+        let vault = Vault::create(&dir.path().join("key.json"), "passphrase")?;
+        let account = dir.path().join("work");
+        fs::create_dir_all(account.join("nested"))?;
+        fs::write(account.join(".credentials.json"), "secret")?;
+        fs::write(account.join("nested/settings.json"), "{}")?;
+
+        assert_eq!(vault.seal_tree(&account)?, 2);
+        assert!(crypto::is_sealed(&fs::read(
+            account.join(".credentials.json")
+        )?));
+
+        assert_eq!(vault.open_tree(&account)?, 2);
+        assert_eq!(
+            fs::read_to_string(account.join(".credentials.json"))?,
+            "secret"
+        );
+        assert_eq!(fs::read_dir(&account)?.count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hex_roundtrip() -> Result<()> {
+        assert_eq!(from_hex(&to_hex(&[0, 15, 255]))?, vec![0, 15, 255]);
+        assert!(from_hex("abc").is_err());
+        assert!(from_hex("zz").is_err());
+        Ok(())
+    }
+}
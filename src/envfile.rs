@@ -0,0 +1,126 @@
+//! `envfile`: an account's login as environment variables, for SDK scripts
+//! and CI jobs that use the same identity without a Claude directory. Unlike
+//! `env`, which points Claude Code at a snapshot, this carries the secret
+//! itself, read from the store (and unsealed when it's encrypted).
+
+use clap::ValueEnum;
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// What Claude Code and the SDKs take an API key from
+pub const API_KEY_VAR: &str = "ANTHROPIC_API_KEY";
+/// What Claude Code takes a long-lived OAuth token from
+pub const OAUTH_TOKEN_VAR: &str = "CLAUDE_CODE_OAUTH_TOKEN";
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    /// `NAME="value"` lines, for `.env` files and `source`
+    #[default]
+    Dotenv,
+    /// `NAME=value` lines to append to `$GITHUB_ENV`
+    GithubActions,
+}
+
+/// The variable a secret of `kind` (see `AccountManager::show_token`) goes
+/// in.
+pub fn secret_var(kind: &str) -> &'static str {
+    match kind {
+        "api-key" => API_KEY_VAR,
+        _ => OAUTH_TOKEN_VAR,
+    }
+}
+
+/// The `env` block of a snapshot's `settings.json`: what Claude Code sets
+/// for the account, such as `ANTHROPIC_BASE_URL` for a proxy. Values that
+/// aren't strings, numbers or booleans are left out.
+pub fn settings_env(settings: &str) -> BTreeMap<String, String> {
+    let Ok(value) = serde_json::from_str::<Value>(settings) else {
+        return BTreeMap::new();
+    };
+    let Some(env) = value.get("env").and_then(Value::as_object) else {
+        return BTreeMap::new();
+    };
+    env.iter()
+        .filter_map(|(name, value)| {
+            let value = match value {
+                Value::String(value) => value.clone(),
+                Value::Number(_) | Value::Bool(_) => value.to_string(),
+                _ => return None,
+            };
+            Some((name.clone(), value))
+        })
+        .collect()
+}
+
+/// `vars` in `format`, one per line, in order. Names that aren't valid
+/// variable names are skipped, and so are values on several lines in
+/// `$GITHUB_ENV`, which would need a delimiter of their own.
+pub fn render(vars: &[(String, String)], format: Format) -> String {
+    let mut out = String::new();
+    for (name, value) in vars.iter().filter(|(name, _)| is_var_name(name)) {
+        match format {
+            Format::Dotenv => out.push_str(&format!("{}=\"{}\"\n", name, escape(value))),
+            Format::GithubActions if value.contains(['\n', '\r']) => {}
+            Format::GithubActions => out.push_str(&format!("{}={}\n", name, value)),
+        }
+    }
+    out
+}
+
+/// Whether `name` can be an environment variable's name in every shell.
+pub fn is_var_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// What a double-quoted dotenv value (and a POSIX shell) would otherwise expand
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' | '"' | '$' | '`' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_formats() {
+        let vars = vec![
+            (API_KEY_VAR.to_string(), "sk-ant-api03-x".to_string()),
+            ("NOTE".to_string(), "say \"$HI\"\nbye".to_string()),
+            ("not a name".to_string(), "x".to_string()),
+        ];
+        assert_eq!(
+            render(&vars, Format::Dotenv),
+            "ANTHROPIC_API_KEY=\"sk-ant-api03-x\"\nNOTE=\"say \\\"\\$HI\\\"\\nbye\"\n"
+        );
+        assert_eq!(
+            render(&vars, Format::GithubActions),
+            "ANTHROPIC_API_KEY=sk-ant-api03-x\n"
+        );
+    }
+
+    #[test]
+    fn test_settings_env() {
+        let env = settings_env(
+            r#"{"env": {"ANTHROPIC_BASE_URL": "https://proxy", "MAX_TOKENS": 8000, "LIST": []}}"#,
+        );
+        assert_eq!(env["ANTHROPIC_BASE_URL"], "https://proxy");
+        assert_eq!(env["MAX_TOKENS"], "8000");
+        assert!(!env.contains_key("LIST"));
+        assert!(settings_env("not json").is_empty());
+    }
+}
@@ -1,23 +1,222 @@
+//! The failures scripts can tell apart. Each maps to its own exit code and
+//! to a `kind` in the `--json` error; anything else exits with 1.
+
+use crate::tr;
 use std::fmt;
 
+/// Exit code for failures without a code of their own.
+pub const EXIT_FAILURE: u8 = 1;
+/// Exit code for arguments the command line doesn't accept.
+pub const EXIT_USAGE: u8 = 64;
+
+/// Every exit code, with its `kind` in the `--json` error and what it
+/// means, for `--help` and the man pages.
+pub const EXIT_CODES: &[(u8, &str, &str)] = &[
+    (0, "", "Success"),
+    (EXIT_FAILURE, "error", "Any other failure"),
+    (2, "not-found", "No account, alias or file by that name"),
+    (
+        3,
+        "already-exists",
+        "The name is taken, or differs only in case from one that is",
+    ),
+    (
+        4,
+        "dirty-state",
+        "The live configuration has unsaved changes",
+    ),
+    (5, "lock-held", "Another instance is changing accounts"),
+    (
+        6,
+        "no-configuration",
+        "There is no Claude configuration to save",
+    ),
+    (
+        7,
+        "confirmation-required",
+        "A confirmation was needed, with no terminal to ask on and no --yes",
+    ),
+    (8, "expired", "The shared bundle has expired"),
+    (
+        9,
+        "locked",
+        "The account is locked, and --force wasn't given",
+    ),
+    (
+        10,
+        "conflict",
+        "Another instance changed the accounts meanwhile; run the command again",
+    ),
+    (
+        11,
+        "version-mismatch",
+        "The account is pinned to a Claude Code version that isn't installed",
+    ),
+    (EXIT_USAGE, "usage", "The command line isn't valid"),
+];
+
 #[derive(Debug)]
 pub enum AccountError {
     NotFound(String),
     AlreadyExists(String),
     NoConfiguration,
+    BundleExpired(String),
+    CaseConflict(String, String),
+    /// The live configuration has changes the active account doesn't
+    UnsavedChanges(String, usize),
+    /// Another instance holds the state lock, and waiting wasn't allowed
+    LockHeld(String),
+    /// Another instance held the state lock for longer than we waited
+    LockTimeout(String, u64),
+    /// A confirmation with no terminal to ask on and no `--yes`
+    ConfirmationRequired,
+    /// The account is locked against changes, and `--force` wasn't given
+    Locked(String),
+    /// The accounts file was saved by another instance since it was loaded
+    Conflict,
+    /// The account is pinned to a Claude Code version (the second) other
+    /// than the installed one (the third)
+    VersionMismatch(String, String, String),
+}
+
+impl AccountError {
+    /// The exit code, stable across releases:
+    ///
+    /// | code | kind |
+    /// |------|------|
+    /// | 2 | `not-found` |
+    /// | 3 | `already-exists` |
+    /// | 4 | `dirty-state` |
+    /// | 5 | `lock-held` |
+    /// | 6 | `no-configuration` |
+    /// | 7 | `confirmation-required` |
+    /// | 8 | `expired` |
+    /// | 9 | `locked` |
+    /// | 10 | `conflict` |
+    /// | 11 | `version-mismatch` |
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::NotFound(_) => 2,
+            Self::AlreadyExists(_) | Self::CaseConflict(..) => 3,
+            Self::UnsavedChanges(..) => 4,
+            Self::LockHeld(_) | Self::LockTimeout(..) => 5,
+            Self::NoConfiguration => 6,
+            Self::ConfirmationRequired => 7,
+            Self::BundleExpired(_) => 8,
+            Self::Locked(_) => 9,
+            Self::Conflict => 10,
+            Self::VersionMismatch(..) => 11,
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::NotFound(_) => "not-found",
+            Self::AlreadyExists(_) | Self::CaseConflict(..) => "already-exists",
+            Self::UnsavedChanges(..) => "dirty-state",
+            Self::LockHeld(_) | Self::LockTimeout(..) => "lock-held",
+            Self::NoConfiguration => "no-configuration",
+            Self::ConfirmationRequired => "confirmation-required",
+            Self::BundleExpired(_) => "expired",
+            Self::Locked(_) => "locked",
+            Self::Conflict => "conflict",
+            Self::VersionMismatch(..) => "version-mismatch",
+        }
+    }
 }
 
 impl fmt::Display for AccountError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::NotFound(name) => write!(f, "Account '{}' not found", name),
-            Self::AlreadyExists(name) => write!(f, "Account '{}' already exists", name),
-            Self::NoConfiguration => write!(
-                f,
-                "No Claude Code configuration found. Please authenticate first with: claude-code auth"
+        let message = match self {
+            Self::NotFound(name) => tr!("error-not-found", name = name),
+            Self::AlreadyExists(name) => tr!("error-already-exists", name = name),
+            Self::NoConfiguration => tr!("error-no-configuration"),
+            Self::BundleExpired(expires_at) => tr!("error-bundle-expired", time = expires_at),
+            Self::CaseConflict(name, existing) => {
+                tr!("error-case-conflict", name = name, existing = existing)
+            }
+            Self::UnsavedChanges(name, count) => {
+                tr!("switch-unsaved", name = name, count = count)
+            }
+            Self::LockHeld(holder) => tr!("lock-held", holder = holder),
+            Self::LockTimeout(holder, seconds) => {
+                tr!("lock-timeout", holder = holder, seconds = seconds)
+            }
+            Self::ConfirmationRequired => tr!("confirm-needs-yes"),
+            Self::Locked(name) => tr!("error-locked", name = name),
+            Self::Conflict => tr!("error-conflict"),
+            Self::VersionMismatch(name, pinned, installed) => tr!(
+                "error-version-mismatch",
+                name = name,
+                pinned = pinned,
+                installed = installed
             ),
-        }
+        };
+        f.write_str(&message)
     }
 }
 
 impl std::error::Error for AccountError {}
+
+/// The `AccountError` behind `error`, however much context was added to it.
+pub fn classify(error: &anyhow::Error) -> Option<&AccountError> {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<AccountError>())
+}
+
+/// The exit code `error` ends the process with.
+pub fn exit_code(error: &anyhow::Error) -> u8 {
+    classify(error).map_or(EXIT_FAILURE, AccountError::exit_code)
+}
+
+/// The `kind` of `error` in the `--json` output.
+pub fn kind(error: &anyhow::Error) -> &'static str {
+    classify(error).map_or("error", AccountError::kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_exit_code_looks_through_context() {
+        let error = Err::<(), _>(AccountError::NotFound("work".to_string()))
+            .context("Failed to switch")
+            .unwrap_err();
+        assert_eq!(exit_code(&error), 2);
+        assert_eq!(kind(&error), "not-found");
+
+        let error = anyhow::anyhow!("disk full");
+        assert_eq!(exit_code(&error), EXIT_FAILURE);
+        assert_eq!(kind(&error), "error");
+    }
+
+    #[test]
+    fn test_exit_codes_are_all_documented() {
+        let name = || "work".to_string();
+        for error in [
+            AccountError::NotFound(name()),
+            AccountError::AlreadyExists(name()),
+            AccountError::NoConfiguration,
+            AccountError::BundleExpired(name()),
+            AccountError::CaseConflict(name(), name()),
+            AccountError::UnsavedChanges(name(), 1),
+            AccountError::LockHeld(name()),
+            AccountError::LockTimeout(name(), 1),
+            AccountError::ConfirmationRequired,
+            AccountError::Locked(name()),
+            AccountError::Conflict,
+            AccountError::VersionMismatch(name(), name(), name()),
+        ] {
+            assert!(
+                EXIT_CODES
+                    .iter()
+                    .any(|(code, kind, _)| *code == error.exit_code() && *kind == error.kind()),
+                "{}",
+                error.kind()
+            );
+        }
+    }
+}
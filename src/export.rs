@@ -0,0 +1,254 @@
+//! Portable archives of one or more accounts, for moving them between
+//! machines: a zstd-compressed tar holding each snapshot next to the metadata
+//! it had, optionally encrypted with a passphrase.
+
+use crate::archive::{append_file, safe_path_under};
+use crate::config::AccountMetadata;
+use crate::size::DirUsage;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+pub const EXTENSION: &str = "tar.zst";
+
+const FORMAT_VERSION: u32 = 1;
+const MANIFEST_ENTRY: &str = "export.json";
+const ACCOUNTS_PREFIX: &str = "accounts";
+const ZSTD_MAGIC: &[u8; 4] = b"\x28\xb5\x2f\xfd";
+
+#[derive(Serialize, Deserialize)]
+pub struct ExportManifest {
+    pub format_version: u32,
+    pub created_at: String,
+    pub accounts: Vec<ExportedAccount>,
+    /// Made with `--redacted`: no logins, and secrets masked
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub redacted: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ExportedAccount {
+    pub name: String,
+    /// As it was on the exporting machine; `path` is only informational
+    pub metadata: AccountMetadata,
+}
+
+impl ExportManifest {
+    pub fn new(created_at: DateTime<Utc>, accounts: Vec<ExportedAccount>) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            created_at: created_at.to_rfc3339(),
+            accounts,
+            redacted: false,
+        }
+    }
+}
+
+/// Files of one account: where they go inside the snapshot, and their contents.
+pub type AccountFiles = Vec<(PathBuf, Vec<u8>)>;
+
+/// Packs every account in `manifest`, asking `files` for each one's
+/// contents, and compresses the result.
+pub fn pack(
+    manifest: &ExportManifest,
+    mut files: impl FnMut(&ExportedAccount) -> Result<AccountFiles>,
+) -> Result<Vec<u8>> {
+    let mut builder = tar::Builder::new(Vec::new());
+
+    let manifest_json =
+        serde_json::to_vec_pretty(manifest).context("Failed to serialize export manifest")?;
+    append_file(&mut builder, Path::new(MANIFEST_ENTRY), &manifest_json)?;
+
+    // Accounts are stored by position: names may contain `/` and nest
+    for (index, account) in manifest.accounts.iter().enumerate() {
+        for (relative, contents) in files(account)? {
+            append_file(
+                &mut builder,
+                &account_prefix(index).join(relative),
+                &contents,
+            )?;
+        }
+    }
+
+    let tar = builder.into_inner().context("Failed to finalize archive")?;
+    zstd::encode_all(tar.as_slice(), 0).context("Failed to compress archive")
+}
+
+/// Whether `data` (already decrypted) is an export rather than a share bundle.
+pub fn is_export(data: &[u8]) -> bool {
+    data.starts_with(ZSTD_MAGIC)
+}
+
+/// A decompressed export, ready to be inspected or unpacked account by account.
+pub struct Export {
+    pub manifest: ExportManifest,
+    tar: Vec<u8>,
+}
+
+impl Export {
+    pub fn read(data: &[u8]) -> Result<Self> {
+        let tar = zstd::decode_all(data).context("Failed to decompress archive")?;
+
+        let mut archive = tar::Archive::new(tar.as_slice());
+        let mut manifest = None;
+        for entry in archive.entries().context("Failed to read archive")? {
+            let mut entry = entry.context("Failed to read archive entry")?;
+            if entry.path()?.as_ref() == Path::new(MANIFEST_ENTRY) {
+                manifest = Some(parse_manifest(&mut entry)?);
+                break;
+            }
+        }
+
+        Ok(Self {
+            manifest: manifest.context("Archive does not contain an export manifest")?,
+            tar,
+        })
+    }
+
+    /// What the account at `index` holds.
+    pub fn usage(&self, index: usize) -> Result<DirUsage> {
+        let prefix = account_prefix(index);
+        let mut usage = DirUsage::default();
+
+        let mut archive = tar::Archive::new(self.tar.as_slice());
+        for entry in archive.entries().context("Failed to read archive")? {
+            let entry = entry.context("Failed to read archive entry")?;
+            if entry.header().entry_type().is_file() && entry.path()?.starts_with(&prefix) {
+                usage.files += 1;
+                usage.bytes += entry.size();
+            }
+        }
+        Ok(usage)
+    }
+
+    /// Extracts the account at `index` into `dest`.
+    pub fn unpack(&self, index: usize, dest: &Path) -> Result<()> {
+        let prefix = account_prefix(index);
+        fs::create_dir_all(dest)
+            .with_context(|| format!("Failed to create directory: {}", dest.display()))?;
+
+        let mut archive = tar::Archive::new(self.tar.as_slice());
+        for entry in archive.entries().context("Failed to read archive")? {
+            let mut entry = entry.context("Failed to read archive entry")?;
+            let path = entry.path()?.into_owned();
+            if !path.starts_with(&prefix) {
+                continue;
+            }
+
+            let Some(relative) = safe_path_under(&path, &prefix) else {
+                anyhow::bail!("Archive contains an unsafe path: {}", path.display());
+            };
+            if relative.as_os_str().is_empty() {
+                continue;
+            }
+
+            let target = dest.join(&relative);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+            }
+            entry
+                .unpack(&target)
+                .with_context(|| format!("Failed to extract file: {}", target.display()))?;
+        }
+        Ok(())
+    }
+}
+
+fn account_prefix(index: usize) -> PathBuf {
+    Path::new(ACCOUNTS_PREFIX).join(index.to_string())
+}
+
+fn parse_manifest(entry: &mut impl Read) -> Result<ExportManifest> {
+    let mut contents = String::new();
+    entry
+        .read_to_string(&mut contents)
+        .context("Failed to read export manifest")?;
+
+    let manifest: ExportManifest =
+        serde_json::from_str(&contents).context("Failed to parse export manifest")?;
+
+    if manifest.format_version > FORMAT_VERSION {
+        anyhow::bail!(
+            "Export format version {} is newer than supported version {}",
+            manifest.format_version,
+            FORMAT_VERSION
+        );
+    }
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn account(name: &str) -> ExportedAccount {
+        ExportedAccount {
+            name: name.to_string(),
+            metadata: AccountMetadata {
+                saved_at: "2024-01-01T00:00:00+00:00".to_string(),
+                path: PathBuf::from("/home/me/.claude-switcher").join(name),
+                protected: name == "prod",
+                ..Default::default()
+            },
+        }
+    }
+
+    fn packed() -> Result<Vec<u8>> {
+        let manifest =
+            ExportManifest::new(Utc::now(), vec![account("client"), account("client/dev")]);
+        pack(&manifest, |account| {
+            Ok(vec![
+                (
+                    PathBuf::from("config.json"),
+                    account.name.clone().into_bytes(),
+                ),
+                (PathBuf::from("nested/file.txt"), b"content".to_vec()),
+            ])
+        })
+    }
+
+    #[test]
+    fn test_pack_and_unpack_each_account() -> Result<()> {
+        let data = packed()?;
+        assert!(is_export(&data));
+
+        let export = Export::read(&data)?;
+        assert_eq!(export.manifest.accounts.len(), 2);
+        assert_eq!(export.manifest.accounts[1].name, "client/dev");
+
+        // Nested names don't pick up each other's files
+        let dest = TempDir::new()?;
+        export.unpack(0, dest.path())?;
+        assert_eq!(
+            fs::read_to_string(dest.path().join("config.json"))?,
+            "client"
+        );
+        assert!(dest.path().join("nested/file.txt").exists());
+        assert!(!dest.path().join("dev").exists());
+
+        assert_eq!(
+            export.usage(1)?,
+            DirUsage {
+                files: 2,
+                bytes: 17
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_share_bundles_are_not_exports() -> Result<()> {
+        let dir = TempDir::new()?;
+        fs::write(dir.path().join("config.json"), "{}")?;
+        let manifest = crate::archive::ArchiveManifest::new("work", "", Utc::now(), None);
+
+        assert!(!is_export(&crate::archive::pack(&manifest, dir.path())?));
+        assert!(Export::read(b"not an archive").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+//! Claude Code state kept outside its directory, such as `~/.claude.json`
+//! with the signed-in account, that belongs to each account as much as what's
+//! inside it does.
+//!
+//! A snapshot keeps its copies in `.cas-paths`, so versions, the trash and
+//! exports carry them along with the rest: `home/` holds the ones in the home
+//! directory by their path from it, so they still fit when the snapshot is
+//! used on another machine, and `root/` the others by their absolute path. A
+//! snapshot without one was saved before they were captured, and a switch to
+//! it leaves them as they are.
+
+use crate::tr;
+use anyhow::Result;
+use std::path::{Component, Path, PathBuf};
+
+/// The directory in a snapshot the copies are kept in
+pub const DIR: &str = ".cas-paths";
+/// Captured unless `extra-paths` says otherwise, when the Claude directory is
+/// `~/.claude`: with `CLAUDE_CONFIG_DIR` it's kept inside instead
+pub const DEFAULT: &str = "~/.claude.json";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtraPath {
+    /// Where Claude Code keeps it
+    pub live: PathBuf,
+    /// Where a snapshot keeps it, from the snapshot's root
+    pub stored: PathBuf,
+}
+
+/// The paths `configured` names (`None` for the default), with `~` for
+/// `home`. They have to be absolute. Ones inside `claude_dir` are captured
+/// with it already, and left out.
+pub fn resolve(
+    home: &Path,
+    claude_dir: &Path,
+    configured: Option<&[String]>,
+) -> Result<Vec<ExtraPath>> {
+    let default = [DEFAULT.to_string()];
+    let configured = match configured {
+        Some(configured) => configured,
+        None if claude_dir == home.join(".claude") => &default,
+        None => &[],
+    };
+
+    let mut paths: Vec<ExtraPath> = Vec::new();
+    for entry in configured {
+        let path = Path::new(entry);
+        let live = match path.strip_prefix("~") {
+            Ok(rest) => home.join(rest),
+            Err(_) => path.to_path_buf(),
+        };
+        if !live.is_absolute() {
+            anyhow::bail!(tr!("extra-paths-not-absolute", path = entry));
+        }
+        if home.starts_with(&live) || claude_dir.starts_with(&live) {
+            anyhow::bail!(tr!("extra-paths-holds-home", path = entry));
+        }
+        if live.starts_with(claude_dir) || paths.iter().any(|known| known.live == live) {
+            continue;
+        }
+        let stored = match live.strip_prefix(home) {
+            Ok(relative) => in_home(relative),
+            Err(_) => Path::new(DIR).join("root").join(from_root(&live)),
+        };
+        paths.push(ExtraPath { live, stored });
+    }
+    Ok(paths)
+}
+
+/// Where a snapshot keeps `relative`, a path from the home directory.
+pub fn in_home(relative: impl AsRef<Path>) -> PathBuf {
+    Path::new(DIR).join("home").join(relative)
+}
+
+// An absolute path as one below `root/`, with a Windows drive as its first
+// directory (`C:\Tools` as `C/Tools`)
+fn from_root(path: &Path) -> PathBuf {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Prefix(prefix) => Some(
+                prefix
+                    .as_os_str()
+                    .to_string_lossy()
+                    .trim_start_matches(r"\\?\")
+                    .trim_end_matches(':')
+                    .to_string(),
+            ),
+            Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() -> Result<()> {
+        let home = Path::new("/home/me");
+        let claude_dir = home.join(".claude");
+        assert_eq!(
+            resolve(home, &claude_dir, None)?,
+            [ExtraPath {
+                live: home.join(".claude.json"),
+                stored: Path::new(".cas-paths/home/.claude.json").to_path_buf(),
+            }]
+        );
+        // Kept inside a directory Claude Code was pointed at
+        assert!(resolve(home, Path::new("/work/claude"), None)?.is_empty());
+
+        let configured = [
+            "~/.config/claude/".to_string(),
+            "/home/me/.config/claude".to_string(),
+            "~/.claude/settings.json".to_string(),
+            "/srv/app/.mcp.json".to_string(),
+        ];
+        let stored: Vec<_> = resolve(home, &claude_dir, Some(&configured))?
+            .into_iter()
+            .map(|path| path.stored)
+            .collect();
+        assert_eq!(
+            stored,
+            [
+                Path::new(".cas-paths/home/.config/claude"),
+                Path::new(".cas-paths/root/srv/app/.mcp.json"),
+            ]
+        );
+
+        for invalid in ["relative/.mcp.json", "~", "/home"] {
+            assert!(resolve(home, &claude_dir, Some(&[invalid.to_string()])).is_err());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+//! What a copy keeps besides the contents: symlinks stay symlinks, and files
+//! keep their mode and modification time. Credential files are made private
+//! whatever mode they had, so a restored login is never world-readable.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// The mode credential files get, owner read and write only
+#[cfg(unix)]
+pub const PRIVATE_MODE: u32 = 0o600;
+
+/// Gives `dst`, a copy of `src`, the modification time and (on Unix) the
+/// mode of `src`, or the private mode for a credential file.
+pub fn keep_metadata(src: &Path, dst: &Path) -> io::Result<()> {
+    let metadata = fs::metadata(src)?;
+    // The owner can set the time of a read-only file on Unix; Windows wants
+    // write access, which `make_writable` has left it
+    fs::File::options()
+        .read(true)
+        .write(cfg!(windows))
+        .open(dst)?
+        .set_modified(metadata.modified()?)?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = match crate::claude::is_credential_file(dst) {
+            true => PRIVATE_MODE,
+            false => metadata.permissions().mode() & 0o7777,
+        };
+        fs::set_permissions(dst, fs::Permissions::from_mode(mode))?;
+    }
+    Ok(())
+}
+
+/// Creates `dst` as a symlink with the same target as the symlink `src`,
+/// replacing whatever is at `dst`. Fails where symlinks can't be made
+/// (Windows without the privilege, for one).
+pub fn copy_symlink(src: &Path, dst: &Path) -> io::Result<()> {
+    let target = fs::read_link(src)?;
+    if fs::symlink_metadata(dst).is_ok() {
+        fs::remove_file(dst)?;
+    }
+    #[cfg(unix)]
+    return std::os::unix::fs::symlink(target, dst);
+    #[cfg(windows)]
+    return match fs::metadata(src) {
+        Ok(metadata) if metadata.is_dir() => std::os::windows::fs::symlink_dir(target, dst),
+        _ => std::os::windows::fs::symlink_file(target, dst),
+    };
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = target;
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use std::time::{Duration, SystemTime};
+    use tempfile::TempDir;
+
+    fn mode(path: &Path) -> u32 {
+        fs::metadata(path).unwrap().permissions().mode() & 0o7777
+    }
+
+    #[test]
+    fn test_keeps_mode_and_mtime() {
+        let dir = TempDir::new().unwrap();
+        let (src, dst) = (dir.path().join("run.sh"), dir.path().join("copy.sh"));
+        fs::write(&src, "#!/bin/sh\n").unwrap();
+        fs::write(&dst, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&src, fs::Permissions::from_mode(0o750)).unwrap();
+        let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        fs::File::options()
+            .write(true)
+            .open(&src)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+
+        keep_metadata(&src, &dst).unwrap();
+        assert_eq!(mode(&dst), 0o750);
+        assert_eq!(fs::metadata(&dst).unwrap().modified().unwrap(), mtime);
+    }
+
+    #[test]
+    fn test_credentials_made_private() {
+        let dir = TempDir::new().unwrap();
+        let src = dir.path().join("source.json");
+        let dst = dir.path().join(".credentials.json");
+        fs::write(&src, "{}").unwrap();
+        fs::write(&dst, "{}").unwrap();
+        fs::set_permissions(&src, fs::Permissions::from_mode(0o644)).unwrap();
+
+        keep_metadata(&src, &dst).unwrap();
+        assert_eq!(mode(&dst), PRIVATE_MODE);
+    }
+
+    #[test]
+    fn test_symlink_recreated_with_its_target() {
+        let dir = TempDir::new().unwrap();
+        let (src, dst) = (dir.path().join("link"), dir.path().join("copy"));
+        std::os::unix::fs::symlink("../shared/settings.json", &src).unwrap();
+        fs::write(&dst, "in the way").unwrap();
+
+        copy_symlink(&src, &dst).unwrap();
+        assert_eq!(
+            fs::read_link(&dst).unwrap(),
+            Path::new("../shared/settings.json")
+        );
+    }
+}
@@ -0,0 +1,168 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::{Mutex, mpsc};
+use std::thread;
+
+/// Per-worker read buffer; hashing never holds more than this of a file in memory.
+pub const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Computes the hex SHA-256 of a file by streaming it through a fixed-size buffer.
+pub fn hash_file(path: &Path) -> Result<String> {
+    let mut file =
+        File::open(path).with_context(|| format!("Failed to open file: {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .with_context(|| format!("Failed to read file: {}", path.display()))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hex(&hasher.finalize()))
+}
+
+/// The hex SHA-256 of data already in memory.
+pub fn hash_bytes(data: &[u8]) -> String {
+    hex(&Sha256::digest(data))
+}
+
+/// Lowercase hex of a digest (or any bytes).
+pub fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub fn default_jobs() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Runs `work` over `items` on at most `jobs` threads, handing each result to
+/// `sink` on the calling thread as soon as it's ready. Items are pulled from
+/// the iterator on demand and results are bounded, so memory use stays flat
+/// regardless of how many items there are. Results arrive in completion order.
+pub fn for_each_parallel<I, R, F, S>(items: I, jobs: usize, work: F, mut sink: S) -> Result<()>
+where
+    I: Iterator + Send,
+    I::Item: Send,
+    R: Send,
+    F: Fn(I::Item) -> R + Sync,
+    S: FnMut(R) -> Result<()>,
+{
+    let jobs = jobs.max(1);
+    let items = Mutex::new(items);
+
+    thread::scope(|scope| {
+        let (tx, rx) = mpsc::sync_channel(jobs);
+
+        for _ in 0..jobs {
+            let tx = tx.clone();
+            let items = &items;
+            let work = &work;
+            scope.spawn(move || {
+                loop {
+                    let next = items.lock().map(|mut items| items.next());
+                    let Ok(Some(item)) = next else {
+                        break;
+                    };
+                    // The receiver only goes away when the sink failed
+                    if tx.send(work(item)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(tx);
+
+        for result in rx {
+            sink(result)?;
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_hash_file_larger_than_buffer() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("big.bin");
+        fs::write(&path, vec![b'a'; BUFFER_SIZE * 2 + 17])?;
+
+        let expected: String = Sha256::digest(fs::read(&path)?)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+        assert_eq!(hash_file(&path)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash_missing_file() {
+        assert!(hash_file(Path::new("/nonexistent/file")).is_err());
+    }
+
+    #[test]
+    fn test_for_each_parallel_processes_everything() -> Result<()> {
+        let mut results = Vec::new();
+        for_each_parallel(
+            1..=100,
+            4,
+            |n| n * 2,
+            |n| {
+                results.push(n);
+                Ok(())
+            },
+        )?;
+
+        results.sort();
+        assert_eq!(results, (1..=100).map(|n| n * 2).collect::<Vec<_>>());
+        Ok(())
+    }
+
+    #[test]
+    fn test_for_each_parallel_stops_on_sink_error() {
+        let mut seen = 0;
+        let result = for_each_parallel(
+            0..10_000,
+            2,
+            |n| n,
+            |_| {
+                seen += 1;
+                if seen == 3 {
+                    anyhow::bail!("stop");
+                }
+                Ok(())
+            },
+        );
+
+        assert!(result.is_err());
+        assert_eq!(seen, 3);
+    }
+
+    #[test]
+    fn test_for_each_parallel_zero_jobs_runs_serially() -> Result<()> {
+        let mut count = 0;
+        for_each_parallel(
+            0..5,
+            0,
+            |n| n,
+            |_| {
+                count += 1;
+                Ok(())
+            },
+        )?;
+        assert_eq!(count, 5);
+        Ok(())
+    }
+}
@@ -0,0 +1,242 @@
+//! `check`: whether each saved login still works.
+//!
+//! Offline that's only what the token's expiry says. With `--online` the
+//! token is shown to the API's profile endpoint, which answers for a token
+//! that works and refuses one that was revoked (or has expired). Nothing is
+//! activated for it: the token is read from the snapshot, so the live
+//! configuration is never touched, and it isn't refreshed either. Claude
+//! Code does that itself the next time the account is used.
+
+use crate::tr;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use ureq::Agent;
+
+/// Points `--online` somewhere other than the Anthropic API, e.g. a proxy
+pub const API_URL_ENV: &str = "CLAUDE_ACCOUNT_API_URL";
+const DEFAULT_API_URL: &str = "https://api.anthropic.com";
+const PROFILE_PATH: &str = "/api/oauth/profile";
+// Asked for by the API for requests with an OAuth token
+const OAUTH_BETA: &str = "oauth-2025-04-20";
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Health {
+    /// The API accepted the token
+    Valid,
+    /// Not expired, which is all that's known without asking the API
+    Unexpired,
+    /// Past its expiry; Claude Code refreshes it when the account is next
+    /// used, unless the login was revoked meanwhile
+    Expired,
+    /// Refused by the API before its expiry: signed out, or revoked
+    Revoked,
+    /// No OAuth login in the snapshot (an API key, or nothing)
+    NoLogin,
+    /// The API answered with something else, or couldn't be reached
+    Unknown,
+}
+
+impl Health {
+    /// Whether the account can be used as it is.
+    pub fn is_ok(self) -> bool {
+        matches!(self, Health::Valid | Health::Unexpired)
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            Health::Valid => tr!("health-valid"),
+            Health::Unexpired => tr!("health-unexpired"),
+            Health::Expired => tr!("health-expired"),
+            Health::Revoked => tr!("health-revoked"),
+            Health::NoLogin => tr!("health-no-login"),
+            Health::Unknown => tr!("health-unknown"),
+        }
+    }
+}
+
+/// Something that shows a token to the API and returns the HTTP status it
+/// answered with.
+pub trait Pinger {
+    fn ping(&self, token: &str) -> Result<u16>;
+}
+
+/// The API's profile endpoint.
+pub struct ApiPinger {
+    agent: Agent,
+    url: String,
+}
+
+impl ApiPinger {
+    /// At `CLAUDE_ACCOUNT_API_URL`, or the Anthropic API.
+    pub fn from_env() -> Result<Self> {
+        let base = std::env::var(API_URL_ENV)
+            .ok()
+            .filter(|url| !url.is_empty())
+            .unwrap_or_else(|| DEFAULT_API_URL.to_string());
+        if !is_secure(&base) {
+            anyhow::bail!(tr!("health-insecure-url", url = &base, var = API_URL_ENV));
+        }
+        Ok(Self {
+            // Refusals are answers here, not errors
+            agent: Agent::config_builder()
+                .http_status_as_error(false)
+                .build()
+                .into(),
+            url: format!("{}{}", base.trim_end_matches('/'), PROFILE_PATH),
+        })
+    }
+}
+
+/// Whether a token can be sent to `url`: over https, or over plain http only
+/// to this machine (a local proxy), where it never crosses the network.
+fn is_secure(url: &str) -> bool {
+    if url.starts_with("https://") {
+        return true;
+    }
+    let Some(rest) = url.strip_prefix("http://") else {
+        return false;
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority.rsplit('@').next().unwrap_or(authority);
+    let host = match host.strip_prefix('[') {
+        Some(bracketed) => bracketed.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    };
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+impl Pinger for ApiPinger {
+    fn ping(&self, token: &str) -> Result<u16> {
+        tracing::debug!(url = %self.url, "checking token");
+        let response = self
+            .agent
+            .get(&self.url)
+            .header("Authorization", &format!("Bearer {}", token))
+            .header("anthropic-beta", OAUTH_BETA)
+            .call()
+            .with_context(|| tr!("health-unreachable", url = &self.url))?;
+        Ok(response.status().as_u16())
+    }
+}
+
+/// A login `list` points out: one expiring within `EXPIRING_WITHIN`, or one
+/// already past its expiry.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Expiry {
+    Soon,
+    Expired,
+}
+
+/// How close to its expiry a login has to be for `list` to point it out, in
+/// hours
+pub const EXPIRING_WITHIN: i64 = 24;
+
+impl Expiry {
+    /// What's to point out about a login expiring at `expires_at`: nothing
+    /// while it has time to spare, or without an expiry.
+    pub fn of(expires_at: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Option<Self> {
+        let left = expires_at? - now;
+        match left {
+            _ if left <= chrono::Duration::zero() => Some(Self::Expired),
+            _ if left <= chrono::Duration::hours(EXPIRING_WITHIN) => Some(Self::Soon),
+            _ => None,
+        }
+    }
+}
+
+/// How a login looks, from its access token and when that expires: worked
+/// out from the expiry alone without `pinger`, otherwise from what the API
+/// says. Also returns the status the API answered with.
+pub fn check(
+    token: Option<&str>,
+    expires_at: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+    pinger: Option<&dyn Pinger>,
+) -> Result<(Health, Option<u16>)> {
+    let Some(token) = token else {
+        return Ok((Health::NoLogin, None));
+    };
+    let expired = expires_at.is_some_and(|expires_at| expires_at <= now);
+    let Some(pinger) = pinger else {
+        return Ok((
+            match expired {
+                true => Health::Expired,
+                false => Health::Unexpired,
+            },
+            None,
+        ));
+    };
+
+    let status = pinger.ping(token)?;
+    let health = match status {
+        200..=299 => Health::Valid,
+        401 | 403 if expired => Health::Expired,
+        401 | 403 => Health::Revoked,
+        _ => Health::Unknown,
+    };
+    Ok((health, Some(status)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Answers(u16);
+
+    impl Pinger for Answers {
+        fn ping(&self, token: &str) -> Result<u16> {
+            assert_eq!(token, "sk-ant-oat01-token");
+            Ok(self.0)
+        }
+    }
+
+    #[test]
+    fn test_health_offline_and_online() -> Result<()> {
+        let now = DateTime::parse_from_rfc3339("2024-01-01T12:00:00Z")?.with_timezone(&Utc);
+        let token = Some("sk-ant-oat01-token");
+        let later = Some(now + chrono::Duration::hours(1));
+        let earlier = Some(now - chrono::Duration::hours(1));
+
+        assert_eq!(check(None, later, now, None)?, (Health::NoLogin, None));
+        assert_eq!(check(token, later, now, None)?.0, Health::Unexpired);
+        assert_eq!(check(token, earlier, now, None)?.0, Health::Expired);
+
+        let online = |status, expires_at| check(token, expires_at, now, Some(&Answers(status)));
+        assert_eq!(online(200, later)?, (Health::Valid, Some(200)));
+        assert_eq!(online(401, later)?.0, Health::Revoked);
+        assert_eq!(online(401, earlier)?.0, Health::Expired);
+        assert_eq!(online(500, later)?, (Health::Unknown, Some(500)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_tokens_only_go_over_plain_http_to_loopback() {
+        assert!(is_secure("https://api.anthropic.com"));
+        assert!(is_secure("http://localhost:8080"));
+        assert!(is_secure("http://127.0.0.1/api"));
+        assert!(is_secure("http://[::1]:8080"));
+        assert!(!is_secure("http://proxy.example.com"));
+        assert!(!is_secure("http://localhost.example.com"));
+        assert!(!is_secure("http://127.0.0.1.example.com"));
+        assert!(!is_secure("http://localhost@proxy.example.com"));
+        assert!(!is_secure("ftp://localhost"));
+    }
+
+    #[test]
+    fn test_expiry_points_out_logins_about_to_expire() -> Result<()> {
+        let now = DateTime::parse_from_rfc3339("2024-01-01T12:00:00Z")?.with_timezone(&Utc);
+        let hours = |hours| Some(now + chrono::Duration::hours(hours));
+
+        assert_eq!(Expiry::of(None, now), None);
+        assert_eq!(Expiry::of(hours(EXPIRING_WITHIN + 1), now), None);
+        assert_eq!(Expiry::of(hours(2), now), Some(Expiry::Soon));
+        assert_eq!(Expiry::of(hours(-2), now), Some(Expiry::Expired));
+        Ok(())
+    }
+}
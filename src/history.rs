@@ -0,0 +1,232 @@
+//! An append-only log of what touched each account, and what triggered it.
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+/// What triggered an event.
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum EventSource {
+    /// A command the user ran, directly or from the dashboard
+    Cli,
+    /// The save of the outgoing account that every switch starts with
+    AutoSave,
+    /// The background re-save scheduled by `resave-after`
+    Resave,
+    /// The `claude` shell wrapper switching to a project's bound account
+    Wrapper,
+    /// `auto`, usually run by the shell hook on changing directory
+    Auto,
+    /// `run` switching to an account for one command, and back afterwards
+    Run,
+    /// `sync pull` bringing in what was saved or deleted on another machine
+    Sync,
+    /// `rotate` moving on to the next account with quota left
+    Rotate,
+    /// `suggest --switch` picking the best account to use
+    Suggest,
+    /// The account picked from `menu`
+    Menu,
+    /// `profile use` switching to the profile's account
+    Profile,
+    /// A switch asked of the `daemon` over its socket
+    Daemon,
+    /// A switch through the `switch_account` tool of `mcp-serve`
+    Mcp,
+    /// A save or switch asked for over `api`
+    Api,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum EventKind {
+    Save,
+    Switch,
+    Delete,
+    Rename,
+    Copy,
+    Import,
+    Rollback,
+    Undo,
+    Restore,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    pub at: String,
+    pub kind: EventKind,
+    pub account: String,
+    pub source: EventSource,
+    /// The account a switch left, or the name a rename replaced
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous: Option<String>,
+    /// How many files were copied, for saves and switches that copied any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub files: Option<u64>,
+    /// The live backup a switch set the replaced live directory aside as,
+    /// which is what undoing it puts back
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub live_backup: Option<String>,
+}
+
+impl fmt::Display for EventSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = self.to_possible_value().expect("no skipped variants");
+        f.pad(value.get_name())
+    }
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(match self {
+            Self::Save => "save",
+            Self::Switch => "switch",
+            Self::Delete => "delete",
+            Self::Rename => "rename",
+            Self::Copy => "copy",
+            Self::Import => "import",
+            Self::Rollback => "rollback",
+            Self::Undo => "undo",
+            Self::Restore => "restore",
+        })
+    }
+}
+
+/// Appends one event as a JSON line.
+pub fn append(path: &Path, event: &Event) -> Result<()> {
+    let mut line = serde_json::to_string(event).context("Failed to serialize history event")?;
+    line.push('\n');
+
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .context("Failed to write history file")
+}
+
+/// Reads every event, oldest first. Lines that don't parse (say, from a
+/// newer version) are skipped.
+pub fn load(path: &Path) -> Result<Vec<Event>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = fs::read_to_string(path).context("Failed to read history file")?;
+    Ok(contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// A history file written out again in the current format.
+pub struct Rewritten {
+    pub contents: String,
+    pub events: usize,
+    /// Lines that didn't parse, carried over as they were
+    pub kept: usize,
+}
+
+/// Re-serializes every event in `contents`. Lines that don't parse may be
+/// from a newer version, so they're kept rather than dropped.
+pub fn rewrite(contents: &str) -> Result<Rewritten> {
+    let mut rewritten = Rewritten {
+        contents: String::with_capacity(contents.len()),
+        events: 0,
+        kept: 0,
+    };
+    for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+        match serde_json::from_str::<Event>(line) {
+            Ok(event) => {
+                let line =
+                    serde_json::to_string(&event).context("Failed to serialize history event")?;
+                rewritten.contents.push_str(&line);
+                rewritten.events += 1;
+            }
+            Err(_) => {
+                rewritten.contents.push_str(line);
+                rewritten.kept += 1;
+            }
+        }
+        rewritten.contents.push('\n');
+    }
+    Ok(rewritten)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn event(kind: EventKind, source: EventSource) -> Event {
+        Event {
+            at: "2024-01-01T00:00:00+00:00".to_string(),
+            kind,
+            account: "work".to_string(),
+            source,
+            previous: None,
+            files: None,
+            live_backup: None,
+        }
+    }
+
+    #[test]
+    fn test_append_and_load() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("history.jsonl");
+        assert!(load(&path)?.is_empty());
+
+        append(&path, &event(EventKind::Save, EventSource::Cli))?;
+        append(&path, &event(EventKind::Save, EventSource::AutoSave))?;
+
+        let events = load(&path)?;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].source, EventSource::AutoSave);
+        assert!(fs::read_to_string(&path)?.contains("\"auto-save\""));
+        Ok(())
+    }
+
+    #[test]
+    fn test_unreadable_lines_are_skipped() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("history.jsonl");
+        fs::write(&path, "garbage\n")?;
+        append(&path, &event(EventKind::Switch, EventSource::Wrapper))?;
+
+        assert_eq!(load(&path)?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rewrite_normalizes_events_and_keeps_the_rest() -> Result<()> {
+        let contents = concat!(
+            "{ \"at\": \"2024-01-01T00:00:00+00:00\", \"kind\": \"save\", ",
+            "\"account\": \"work\", \"source\": \"cli\" }\n",
+            "\n",
+            "{\"kind\": \"teleport\"}\n",
+        );
+        let rewritten = rewrite(contents)?;
+
+        assert_eq!((rewritten.events, rewritten.kept), (1, 1));
+        assert_eq!(
+            rewritten.contents,
+            format!(
+                "{}\n{{\"kind\": \"teleport\"}}\n",
+                serde_json::to_string(&event(EventKind::Save, EventSource::Cli))?
+            )
+        );
+        assert_eq!(rewrite(&rewritten.contents)?.contents, rewritten.contents);
+        Ok(())
+    }
+
+    #[test]
+    fn test_display_matches_cli_names() {
+        assert_eq!(EventSource::AutoSave.to_string(), "auto-save");
+        assert_eq!(EventKind::Rename.to_string(), "rename");
+    }
+}
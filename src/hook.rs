@@ -0,0 +1,210 @@
+//! `hook`: a shell snippet that runs `auto` whenever the working directory
+//! changes, so a project's bound account is active as soon as you `cd` into
+//! it (the way direnv loads an `.envrc`).
+//!
+//! Before each prompt it also runs `hook-env`, which exports the variables
+//! `var set` gave the active account and unsets those of the one before. The
+//! names it exported and a hash of what they were set to are kept in
+//! [`STATE_VAR`], so nothing is printed until the account or its variables
+//! change.
+
+use crate::completions::{self, BIN_NAME};
+use crate::config::AccountsConfig;
+use crate::direct;
+use crate::hashing;
+use crate::system;
+use anyhow::{Context, Result};
+use clap_complete::Shell;
+use std::collections::BTreeMap;
+
+/// What the shell keeps `hook-env`'s state in: the hash, then the names
+pub const STATE_VAR: &str = "_CLAUDE_ACCOUNT_SWITCHER_ENV";
+
+pub fn run(shell: Option<Shell>, warn_only: bool) -> Result<()> {
+    let shell = match shell {
+        Some(shell) => shell,
+        None => completions::detect_shell(std::env::var("SHELL").ok().as_deref())
+            .context("Could not detect your shell; pass it explicitly (bash, zsh, fish)")?,
+    };
+
+    print!("{}", script(shell, warn_only)?);
+    Ok(())
+}
+
+/// The hook for `shell`. It only calls out when the directory actually
+/// changed, and writes to stderr so command substitutions stay clean.
+pub fn script(shell: Shell, warn_only: bool) -> Result<String> {
+    let auto = if warn_only {
+        format!("{} auto --warn", BIN_NAME)
+    } else {
+        format!("{} auto", BIN_NAME)
+    };
+
+    let script = match shell {
+        // Bash has no directory-change hook, so compare on each prompt
+        Shell::Bash => format!(
+            "# Generated by `{bin} hook bash`\n\
+             _claude_account_switcher_hook() {{\n    \
+                 if [ \"$PWD\" != \"${{_claude_account_switcher_pwd-}}\" ]; then\n        \
+                     _claude_account_switcher_pwd=$PWD\n        \
+                     command {auto} >&2\n    \
+                 fi\n    \
+                 eval \"$(command {bin} hook-env bash)\"\n\
+             }}\n\
+             case \";${{PROMPT_COMMAND-}};\" in\n    \
+                 *\";_claude_account_switcher_hook;\"*) ;;\n    \
+                 *) PROMPT_COMMAND=\"_claude_account_switcher_hook${{PROMPT_COMMAND:+;$PROMPT_COMMAND}}\" ;;\n\
+             esac\n",
+            bin = BIN_NAME,
+        ),
+        Shell::Zsh => format!(
+            "# Generated by `{bin} hook zsh`\n\
+             _claude_account_switcher_hook() {{\n    \
+                 command {auto} >&2\n\
+             }}\n\
+             _claude_account_switcher_env() {{\n    \
+                 eval \"$(command {bin} hook-env zsh)\"\n\
+             }}\n\
+             autoload -Uz add-zsh-hook\n\
+             add-zsh-hook chpwd _claude_account_switcher_hook\n\
+             add-zsh-hook precmd _claude_account_switcher_env\n\
+             _claude_account_switcher_hook\n",
+            bin = BIN_NAME,
+        ),
+        Shell::Fish => format!(
+            "# Generated by `{bin} hook fish`\n\
+             function __claude_account_switcher_hook --on-variable PWD\n    \
+                 command {auto} >&2\n\
+             end\n\
+             function __claude_account_switcher_env --on-event fish_prompt\n    \
+                 command {bin} hook-env fish | source\n\
+             end\n\
+             __claude_account_switcher_hook\n",
+            bin = BIN_NAME,
+        ),
+        _ => anyhow::bail!("The directory hook is not available for {}", shell),
+    };
+    Ok(script)
+}
+
+/// Prints what brings the variables in `shell` up to date with the active
+/// account's. Prompts run it, so it reads the settings and `accounts.json`
+/// and nothing else, and prints nothing when those can't be read.
+pub fn run_env(shell: Shell) -> Result<()> {
+    let Some(contents) = system::accounts_contents() else {
+        return Ok(());
+    };
+    let state = std::env::var(STATE_VAR).ok();
+    print!("{}", env_updates(shell, &contents, state.as_deref())?);
+    Ok(())
+}
+
+/// The lines that take a shell whose [`STATE_VAR`] is `state` to the
+/// variables of the account active in the accounts file `contents`.
+pub fn env_updates(shell: Shell, contents: &str, state: Option<&str>) -> Result<String> {
+    let vars = serde_json::from_str::<AccountsConfig>(contents)
+        .ok()
+        .and_then(|config| {
+            let current = config.current?;
+            config.accounts.get(&current).map(|meta| meta.env.clone())
+        })
+        .unwrap_or_default();
+    let digest = digest(&vars);
+    let (old_digest, old_names) = match state.and_then(|state| state.split_once(':')) {
+        Some((digest, names)) => (Some(digest), names),
+        None => (None, ""),
+    };
+    if old_digest == digest.as_deref() {
+        return Ok(String::new());
+    }
+
+    let mut out = String::new();
+    for name in old_names.split(',').filter(|name| !name.is_empty()) {
+        if !vars.contains_key(name) {
+            out.push_str(&direct::unset(shell, name)?);
+        }
+    }
+    for (name, value) in &vars {
+        out.push_str(&direct::export(shell, name, value)?);
+    }
+    match digest {
+        Some(digest) => {
+            let names: Vec<&str> = vars.keys().map(String::as_str).collect();
+            let state = format!("{}:{}", digest, names.join(","));
+            out.push_str(&direct::export(shell, STATE_VAR, &state)?);
+        }
+        None => out.push_str(&direct::unset(shell, STATE_VAR)?),
+    }
+    Ok(out)
+}
+
+// `None` when there's nothing to export
+fn digest(vars: &BTreeMap<String, String>) -> Option<String> {
+    if vars.is_empty() {
+        return None;
+    }
+    let mut data = Vec::new();
+    for (name, value) in vars {
+        data.extend_from_slice(name.as_bytes());
+        data.push(0);
+        data.extend_from_slice(value.as_bytes());
+        data.push(0);
+    }
+    Some(hashing::hash_bytes(&data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hooks_run_auto_on_directory_change() -> Result<()> {
+        let bash = script(Shell::Bash, false)?;
+        assert!(bash.contains("command claude-account-switcher auto >&2"));
+        assert!(bash.contains("PROMPT_COMMAND=\"_claude_account_switcher_hook"));
+
+        let zsh = script(Shell::Zsh, true)?;
+        assert!(zsh.contains("add-zsh-hook chpwd _claude_account_switcher_hook"));
+        assert!(zsh.contains("auto --warn >&2"));
+
+        assert!(script(Shell::Fish, false)?.contains("--on-variable PWD"));
+        assert!(script(Shell::PowerShell, false).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_env_updates_follow_the_active_account() -> Result<()> {
+        let accounts = |current: &str| {
+            format!(
+                r#"{{"current": "{}", "accounts": {{
+                    "client": {{"path": "/c", "saved_at": "x",
+                        "env": {{"HTTPS_PROXY": "http://proxy:8080", "NO_PROXY": "localhost"}}}},
+                    "home": {{"path": "/h", "saved_at": "x"}}
+                }}}}"#,
+                current
+            )
+        };
+
+        let out = env_updates(Shell::Bash, &accounts("client"), None)?;
+        assert!(out.contains("export HTTPS_PROXY='http://proxy:8080'\n"));
+        assert!(out.contains("export NO_PROXY='localhost'\n"));
+        let state = out
+            .lines()
+            .find_map(|line| line.strip_prefix("export _CLAUDE_ACCOUNT_SWITCHER_ENV='"))
+            .map(|state| state.trim_end_matches('\''))
+            .expect("state is exported");
+        assert!(state.ends_with(":HTTPS_PROXY,NO_PROXY"));
+
+        // Nothing to do until the account changes
+        assert_eq!(
+            env_updates(Shell::Bash, &accounts("client"), Some(state))?,
+            ""
+        );
+        assert_eq!(
+            env_updates(Shell::Fish, &accounts("home"), Some(state))?,
+            "set -e -g HTTPS_PROXY\nset -e -g NO_PROXY\nset -e -g _CLAUDE_ACCOUNT_SWITCHER_ENV\n"
+        );
+        assert_eq!(env_updates(Shell::Zsh, &accounts("home"), None)?, "");
+        Ok(())
+    }
+}
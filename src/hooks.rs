@@ -0,0 +1,164 @@
+//! Commands from the `[hooks]` settings, run around saves, switches and
+//! deletes: restarting an MCP server, refreshing a status line. (The shell
+//! snippet that switches on `cd` is `hook`.)
+//!
+//! Each runs through the shell (`sh -c`, or `cmd /C` on Windows) with the
+//! accounts involved in its environment:
+//!
+//! - `CLAUDE_ACCOUNT_HOOK`: which hook it is (`post-switch`, ...)
+//! - `CLAUDE_ACCOUNT_NAME`: the account saved, switched to or deleted
+//! - `CLAUDE_ACCOUNT_OLD`, `CLAUDE_ACCOUNT_NEW`: for switches, the account
+//!   left (unset when there was none) and the one switched to
+//! - `CLAUDE_ACCOUNT_SOURCE`: what started it, as in `history`
+//!
+//! A `pre-` hook that fails stops what it runs before. The switcher holds
+//! its lock while hooks run, so they can read accounts but not change them.
+
+use crate::settings::HookSettings;
+use crate::tr;
+use anyhow::{Context, Result};
+use std::fmt;
+use std::io;
+use std::process::Command;
+
+pub const NAME_VAR: &str = "CLAUDE_ACCOUNT_NAME";
+pub const OLD_VAR: &str = "CLAUDE_ACCOUNT_OLD";
+pub const NEW_VAR: &str = "CLAUDE_ACCOUNT_NEW";
+pub const SOURCE_VAR: &str = "CLAUDE_ACCOUNT_SOURCE";
+const HOOK_VAR: &str = "CLAUDE_ACCOUNT_HOOK";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hook {
+    PreSave,
+    PostSave,
+    PreSwitch,
+    PostSwitch,
+    PostDelete,
+}
+
+impl Hook {
+    pub const ALL: [Hook; 5] = [
+        Self::PreSave,
+        Self::PostSave,
+        Self::PreSwitch,
+        Self::PostSwitch,
+        Self::PostDelete,
+    ];
+
+    pub fn is_pre(self) -> bool {
+        matches!(self, Self::PreSave | Self::PreSwitch)
+    }
+
+    fn command(self, hooks: &HookSettings) -> Option<&str> {
+        match self {
+            Self::PreSave => hooks.pre_save.as_deref(),
+            Self::PostSave => hooks.post_save.as_deref(),
+            Self::PreSwitch => hooks.pre_switch.as_deref(),
+            Self::PostSwitch => hooks.post_switch.as_deref(),
+            Self::PostDelete => hooks.post_delete.as_deref(),
+        }
+    }
+}
+
+impl fmt::Display for Hook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(match self {
+            Self::PreSave => "pre-save",
+            Self::PostSave => "post-save",
+            Self::PreSwitch => "pre-switch",
+            Self::PostSwitch => "post-switch",
+            Self::PostDelete => "post-delete",
+        })
+    }
+}
+
+/// Each hook `hooks` sets, with its command.
+pub fn commands(hooks: &HookSettings) -> Vec<(Hook, &str)> {
+    Hook::ALL
+        .into_iter()
+        .filter_map(|hook| Some((hook, hook.command(hooks)?)))
+        .collect()
+}
+
+/// Runs the command set for `hook`, if there is one, with `vars` added to
+/// its environment. It fails when the command does.
+pub fn run(hooks: &HookSettings, hook: Hook, vars: &[(&str, &str)]) -> Result<()> {
+    let Some(command) = hook
+        .command(hooks)
+        .filter(|command| !command.trim().is_empty())
+    else {
+        return Ok(());
+    };
+
+    tracing::info!(command, "{} hook", hook);
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    };
+    // Left over from a hook further up, which would be wrong here
+    for var in [NAME_VAR, OLD_VAR, NEW_VAR, SOURCE_VAR] {
+        shell.env_remove(var);
+    }
+    shell
+        .env(HOOK_VAR, hook.to_string())
+        .envs(vars.iter().copied());
+    // Keeps stdout for the switcher's own output
+    shell.stdout(io::stderr());
+
+    let status = shell
+        .status()
+        .with_context(|| tr!("hook-start-failed", hook = hook))?;
+    if !status.success() {
+        anyhow::bail!(match status.code() {
+            Some(code) => tr!("hook-failed", hook = hook, code = code),
+            None => tr!("hook-killed", hook = hook),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_hook_gets_the_accounts() -> Result<()> {
+        let dir = TempDir::new()?;
+        let out = dir.path().join("out");
+        let hooks = HookSettings {
+            post_switch: Some(format!(
+                "echo \"$CLAUDE_ACCOUNT_HOOK $CLAUDE_ACCOUNT_OLD $CLAUDE_ACCOUNT_NEW\" > {}",
+                out.display()
+            )),
+            ..HookSettings::default()
+        };
+
+        run(
+            &hooks,
+            Hook::PostSwitch,
+            &[(OLD_VAR, "work"), (NEW_VAR, "personal")],
+        )?;
+        assert_eq!(fs::read_to_string(&out)?, "post-switch work personal\n");
+        // Nothing is set for this one
+        run(&hooks, Hook::PreSwitch, &[])?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_failing_hook_is_an_error() {
+        let hooks = HookSettings {
+            pre_save: Some("exit 3".to_string()),
+            ..HookSettings::default()
+        };
+        let error = run(&hooks, Hook::PreSave, &[]).unwrap_err();
+        assert!(error.to_string().contains("pre-save"));
+        assert!(error.to_string().contains('3'));
+    }
+}
@@ -0,0 +1,4071 @@
+//! Message catalog for user-facing text.
+//!
+//! Machine-oriented output (bare account names from `current`, checksum lists,
+//! grep matches) is deliberately left untranslated so scripts keep working.
+//!
+//! English and Indonesian are built in. Packagers can ship other languages,
+//! or corrections, as `<language>.toml` files of `key = "text"` in a
+//! `claude-account-switcher/locale` directory under `$XDG_DATA_DIRS`, or in
+//! the directory `CLAUDE_ACCOUNT_LOCALE_DIR` names. A message missing from
+//! an installed catalog falls back to the built-in one.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Names a directory of installed catalogs, searched before the system ones.
+pub const CATALOG_DIR_VAR: &str = "CLAUDE_ACCOUNT_LOCALE_DIR";
+
+const LOCALE_VARS: &[&str] = &["CLAUDE_ACCOUNT_LANG", "LC_ALL", "LC_MESSAGES", "LANG"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Locale {
+    En,
+    Id,
+}
+
+impl Locale {
+    /// Picks the locale from `CLAUDE_ACCOUNT_LANG`, then the usual POSIX variables.
+    pub fn detect() -> Self {
+        requested_tag()
+            .and_then(|tag| Self::parse(&tag))
+            .unwrap_or(Self::En)
+    }
+
+    pub fn parse(tag: &str) -> Option<Self> {
+        match language(tag)?.as_str() {
+            "en" | "c" | "posix" => Some(Self::En),
+            "id" | "in" => Some(Self::Id),
+            _ => None,
+        }
+    }
+
+    fn catalog(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Self::En => EN,
+            Self::Id => ID,
+        }
+    }
+}
+
+// The value of the first locale variable that's set
+fn requested_tag() -> Option<String> {
+    LOCALE_VARS
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+}
+
+// The language of a tag such as `pt_BR.UTF-8`
+fn language(tag: &str) -> Option<String> {
+    let language = tag.split(['_', '-', '.', '@']).next()?.to_ascii_lowercase();
+    (!language.is_empty()).then_some(language)
+}
+
+/// Where installed catalogs are looked for, in order.
+fn catalog_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = std::env::var_os(CATALOG_DIR_VAR)
+        .map(PathBuf::from)
+        .into_iter()
+        .collect();
+    let data_dirs = std::env::var_os("XDG_DATA_DIRS")
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+    dirs.extend(
+        std::env::split_paths(&data_dirs).map(|dir| dir.join("claude-account-switcher/locale")),
+    );
+    dirs
+}
+
+/// The messages of the first catalog for `language` found in `dirs`. One
+/// that can't be read or parsed is skipped.
+fn load_catalog(dirs: &[PathBuf], language: &str) -> HashMap<String, String> {
+    dirs.iter()
+        .map(|dir| dir.join(format!("{}.toml", language)))
+        .find_map(|path| read_catalog(&path))
+        .unwrap_or_default()
+}
+
+fn read_catalog(path: &Path) -> Option<HashMap<String, String>> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    match toml::from_str(&contents) {
+        Ok(catalog) => Some(catalog),
+        Err(e) => {
+            tracing::warn!(path = %path.display(), error = %e, "unreadable catalog");
+            None
+        }
+    }
+}
+
+// The installed catalog for the requested language, if there is one
+fn installed() -> &'static HashMap<String, String> {
+    static INSTALLED: OnceLock<HashMap<String, String>> = OnceLock::new();
+    INSTALLED.get_or_init(|| match requested_tag().and_then(|tag| language(&tag)) {
+        Some(language) if !cfg!(test) => load_catalog(&catalog_dirs(), &language),
+        _ => HashMap::new(),
+    })
+}
+
+pub fn locale() -> Locale {
+    // Tests assert on English text regardless of the developer's environment
+    if cfg!(test) {
+        return Locale::En;
+    }
+
+    static LOCALE: OnceLock<Locale> = OnceLock::new();
+    *LOCALE.get_or_init(Locale::detect)
+}
+
+/// Looks up `key` in the installed catalog, then the active locale, falling
+/// back to English and then to the key itself.
+pub fn message(key: &'static str) -> &'static str {
+    match installed().get(key) {
+        Some(text) => text,
+        None => lookup(locale(), key),
+    }
+}
+
+fn lookup(locale: Locale, key: &'static str) -> &'static str {
+    [locale.catalog(), EN]
+        .iter()
+        .find_map(|catalog| {
+            catalog
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, text)| *text)
+        })
+        .unwrap_or(key)
+}
+
+/// Fills `{placeholder}` slots in the message for `key`.
+pub fn format(key: &'static str, args: &[(&str, String)]) -> String {
+    fill(message(key), args)
+}
+
+// In one pass over the template, so a value that itself looks like a slot
+// (a name with `{path}` in it, say) is left as it is
+fn fill(template: &str, args: &[(&str, String)]) -> String {
+    let mut text = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        text.push_str(&rest[..open]);
+        let slot = &rest[open + 1..];
+        let value = slot.find('}').and_then(|close| {
+            let (_, value) = args.iter().find(|(name, _)| *name == &slot[..close])?;
+            Some((value, close))
+        });
+        match value {
+            Some((value, close)) => {
+                text.push_str(value);
+                rest = &slot[close + 1..];
+            }
+            None => {
+                text.push('{');
+                rest = slot;
+            }
+        }
+    }
+    text.push_str(rest);
+    text
+}
+
+/// Translates a message: `tr!("account-saved", name = name)`.
+#[macro_export]
+macro_rules! tr {
+    ($key:literal) => {
+        $crate::i18n::format($key, &[])
+    };
+    ($key:literal, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::format($key, &[$((stringify!($name), $value.to_string())),+])
+    };
+}
+
+const EN: &[(&str, &str)] = &[
+    ("account-saved", "Saved account '{name}'"),
+    ("account-switched", "Switched to account '{name}'"),
+    ("account-deleted", "Deleted account '{name}'"),
+    (
+        "account-registered",
+        "Registered account '{name}' from {path}",
+    ),
+    (
+        "add-login-hint",
+        "Log in with the new account, then quit Claude Code to save it as '{name}'.",
+    ),
+    (
+        "add-login-failed",
+        "No new login to save: the login didn't finish or left no credentials",
+    ),
+    (
+        "add-unsaved-login",
+        "The current login isn't saved as an account; save it first so it isn't lost",
+    ),
+    (
+        "add-linked",
+        "The Claude directory is linked to the snapshot of '{name}'; switch to it with --mode copy first",
+    ),
+    ("add-done", "Added '{name}'; '{previous}' is active again"),
+    ("add-done-live", "Added '{name}', now the active account"),
+    (
+        "logout-unsaved",
+        "No account is active, so the live configuration isn't saved anywhere. Remove it anyway?",
+    ),
+    (
+        "logged-out",
+        "Signed out of '{name}' (saved); `switch -` signs back in",
+    ),
+    ("logged-out-untracked", "Signed out"),
+    (
+        "account-deleted-kept",
+        "Removed account '{name}' from the list; its files remain at {path}",
+    ),
+    ("account-renamed", "Renamed account '{old}' to '{new}'"),
+    ("account-copied", "Copied account '{source}' to '{name}'"),
+    (
+        "config-empty",
+        "No settings set; everything is at its default",
+    ),
+    ("config-invalid", "Invalid value for '{key}'"),
+    (
+        "config-comments-dropped",
+        "Note: settings.toml was rewritten without its comments",
+    ),
+    (
+        "config-env-overrides",
+        "Note: {var} is set and takes precedence over '{key}'",
+    ),
+    (
+        "config-editor-failed",
+        "{editor} exited with an error; settings not checked",
+    ),
+    ("template-saved", "Saved template '{name}' ({count} files)"),
+    (
+        "template-empty",
+        "Nothing to keep in template '{name}': no settings.json, CLAUDE.md or MCP servers",
+    ),
+    ("template-not-found", "Template '{name}' not found"),
+    (
+        "template-invalid-name",
+        "'{name}' can't be used as a template name",
+    ),
+    (
+        "template-applied",
+        "Applied template '{name}' to the live configuration; the login is unchanged",
+    ),
+    (
+        "template-applied-save",
+        "Run `claude-account-switcher save {account}` to keep it in '{account}'",
+    ),
+    ("template-deleted", "Deleted template '{name}'"),
+    ("no-templates", "No templates saved"),
+    ("account-imported", "Imported account '{name}'"),
+    (
+        "account-dir-not-found",
+        "Account directory not found: {path}",
+    ),
+    ("no-accounts", "No saved accounts found."),
+    ("no-active-account", "No active account"),
+    ("accounts-header", "Claude Code Accounts:"),
+    ("saved-at", "saved: {time}"),
+    ("group-summary-one", "{group} (1 account, {size})"),
+    ("group-summary-many", "{group} ({count} accounts, {size})"),
+    ("no-namespace", "(no namespace)"),
+    ("no-tag", "(no tag)"),
+    ("no-identity", "(not signed in)"),
+    ("no-tagged-accounts", "No accounts tagged {tags}"),
+    (
+        "no-stale-accounts",
+        "No accounts saved more than {days} days ago or with a login about to expire",
+    ),
+    ("tag-added", "Tagged '{name}' {tag}"),
+    ("tag-already", "'{name}' is already tagged {tag}"),
+    ("tag-removed", "Removed tag {tag} from '{name}'"),
+    ("tag-not-set", "'{name}' isn't tagged {tag}"),
+    ("alias-set", "'{alias}' now stands for '{name}'"),
+    ("alias-removed", "Removed alias '{alias}' (for '{name}')"),
+    ("alias-unknown", "No alias '{alias}'"),
+    ("limit-recorded", "'{name}' is rate limited until {time}"),
+    ("limit-cleared", "'{name}' has quota again"),
+    (
+        "rotate-unknown",
+        "Skipping '{name}' in the rotation: no such account",
+    ),
+    (
+        "rotate-skip-limited",
+        "Skipping '{name}': rate limited until {time}",
+    ),
+    (
+        "rotate-skip-expired",
+        "Skipping '{name}': its token expired {time}",
+    ),
+    ("rotate-empty", "No accounts to rotate through"),
+    ("suggest-available", "ready"),
+    ("suggest-limited", "rate limited, {left} left"),
+    (
+        "suggest-best",
+        "Best to use now: '{name}' (switch with `suggest --switch`)",
+    ),
+    (
+        "suggest-already-current",
+        "'{name}' is already active and the best pick",
+    ),
+    ("suggest-none", "No account can be used right now"),
+    ("label-state", "state"),
+    (
+        "rotate-none",
+        "No other account in the rotation has quota left",
+    ),
+    (
+        "limit-no-current",
+        "No account is active; name the one that hit the limit",
+    ),
+    (
+        "alias-number",
+        "'{alias}' can't be an alias: numbers pick accounts by their place in `list`",
+    ),
+    (
+        "alias-is-account",
+        "'{alias}' is already the name of an account",
+    ),
+    (
+        "tag-invalid",
+        "'{tag}' can't be a tag: tags can't be empty or have spaces or commas",
+    ),
+    (
+        "plain-account",
+        "number: {number}, name: {name}, current: {current}, saved: {time}",
+    ),
+    (
+        "plain-group",
+        "group: {group}, accounts: {count}, size: {size}",
+    ),
+    ("yes", "yes"),
+    ("no", "no"),
+    (
+        "delete-active-warning",
+        "Warning: '{name}' is currently active",
+    ),
+    (
+        "delete-confirm",
+        "Continue? This will clear your active session",
+    ),
+    ("delete-many", "Deleting {count} account(s): {names}"),
+    ("delete-many-confirm", "Delete them?"),
+    ("pattern-no-match", "No account matches '{pattern}'"),
+    ("bulk-item-failed", "{name}: {error}"),
+    ("bulk-summary", "{done} of {total} account(s) done"),
+    ("bulk-failed", "Failed for: {names}"),
+    ("cancelled", "Cancelled."),
+    (
+        "prune-unused",
+        "{count} account(s) not used within the retention period:",
+    ),
+    ("last-used", "last used: {time}"),
+    ("prune-confirm", "Delete these accounts?"),
+    ("prune-kept-accounts", "Kept the unused accounts."),
+    (
+        "prune-would-delete",
+        "Would delete {count} unused account(s)",
+    ),
+    (
+        "prune-would-remove-versions",
+        "Would remove {count} old version(s), freeing {size}",
+    ),
+    (
+        "prune-would-strip",
+        "Would strip {count} file(s) ({size}) from stored snapshots",
+    ),
+    ("prune-deleted", "Deleted {count} unused account(s)"),
+    (
+        "prune-removed-versions",
+        "Removed {count} old version(s), freeing {size}",
+    ),
+    (
+        "prune-stripped",
+        "Stripped {count} file(s) ({size}) from stored snapshots",
+    ),
+    ("prune-nothing", "Nothing to prune."),
+    (
+        "diff-no-current",
+        "No account is active; name the account to compare with.",
+    ),
+    ("diff-none", "~/.claude matches the snapshot of '{name}'."),
+    (
+        "diff-summary",
+        "{count} file(s) differ from the snapshot of '{name}'.",
+    ),
+    (
+        "diff-accounts-none",
+        "The snapshots of '{name}' and '{other}' match.",
+    ),
+    (
+        "diff-accounts-summary",
+        "{count} file(s) differ between '{name}' and '{other}'.",
+    ),
+    ("diff-added", "added: {path}"),
+    ("diff-removed", "removed: {path}"),
+    ("diff-modified", "modified: {path}"),
+    (
+        "gc-removed",
+        "Removed {count} unused stored file(s), freeing {size}",
+    ),
+    (
+        "gc-shared",
+        "Found {count} file(s) ({size}) already stored by another snapshot",
+    ),
+    (
+        "gc-dry-run",
+        "Would remove {count} unused stored file(s), freeing {size}",
+    ),
+    (
+        "gc-no-orphans",
+        "Every directory in the store belongs to an account",
+    ),
+    (
+        "gc-orphans",
+        "Directories in the store no account uses ({count}):",
+    ),
+    (
+        "gc-orphans-confirm",
+        "Move them to the trash? They can be brought back as accounts with `trash restore`.",
+    ),
+    (
+        "gc-adopt-confirm",
+        "Register them as accounts of their names?",
+    ),
+    ("gc-orphans-kept", "Left them where they are"),
+    ("gc-orphans-dry-run", "Dry run: nothing was moved"),
+    ("gc-orphan-trashed", "Moved {path} to the trash"),
+    (
+        "switch-unsaved",
+        "{count} file(s) in ~/.claude changed since '{name}' was saved (see `diff`). Switch with --auto-save to save them to '{name}' first, or --force to discard them.",
+    ),
+    (
+        "manifest-failed",
+        "Warning: could not record which files were saved: {error}",
+    ),
+    (
+        "run-no-current",
+        "No account is active to switch back to. Save the current configuration with `save <name>` first.",
+    ),
+    (
+        "no-previous",
+        "No account was active before this one; `switch -` needs an earlier switch",
+    ),
+    (
+        "run-restore-failed",
+        "Couldn't switch back to '{name}'; run `switch {name}` to restore it",
+    ),
+    ("info-current", "(current)"),
+    ("info-unknown", "unknown"),
+    ("info-never", "never"),
+    ("label-name", "name"),
+    ("label-current", "current"),
+    ("label-email", "email"),
+    ("label-organization", "organization"),
+    ("label-plan", "plan"),
+    ("label-saved", "saved"),
+    ("label-last-used", "last used"),
+    ("label-path", "path"),
+    ("label-protected", "protected"),
+    ("label-locked", "locked"),
+    ("label-external", "external"),
+    ("label-tags", "tags"),
+    ("label-switches", "switches"),
+    ("label-note", "note"),
+    ("label-token", "token"),
+    ("label-unsaved", "unsaved"),
+    ("label-expires", "expires"),
+    ("label-size", "size"),
+    ("label-mode", "mode"),
+    ("label-phase", "phase"),
+    ("label-time", "time"),
+    ("label-calls", "calls"),
+    ("label-speed", "speed"),
+    ("label-encrypted", "encrypted"),
+    ("label-flags", "flags"),
+    ("label-files", "files"),
+    ("label-number", "number"),
+    ("label-limited", "limited until"),
+    ("label-aliases", "aliases"),
+    ("label-account", "account"),
+    ("label-env", "environment"),
+    ("label-claude-version", "claude code"),
+    ("label-pinned", "pinned to"),
+    ("info-size", "{size} in {files} file(s)"),
+    ("info-token-expires", "{time} (in {left})"),
+    ("info-token-expired", "expired {time}"),
+    ("info-unsaved", "{count} file(s) changed"),
+    ("status-expires-in", "expires in {time}"),
+    ("status-expired", "expired"),
+    ("restore-file", "Restored {path} from '{name}' to {dest}"),
+    ("restore-missing", "'{name}' has no {path}"),
+    (
+        "file-bad-path",
+        "{path} isn't a path inside the Claude directory",
+    ),
+    (
+        "file-credentials",
+        "{path} holds a login, which only moves with its account; switch to the account to use it",
+    ),
+    ("merge-same-account", "Can't merge '{name}' into itself"),
+    ("merge-copied", "{path}: copied from '{source}' to '{dest}'"),
+    ("merge-same", "{path} is already the same in '{name}'"),
+    ("merge-kept", "{path}: kept the one in '{name}'"),
+    ("merge-would-copy", "{path}: would be copied to '{name}'"),
+    (
+        "merge-would-replace",
+        "{path}: would replace the one in '{name}'",
+    ),
+    (
+        "merge-confirm",
+        "{path} differs in '{dest}'. Replace it with the one from '{source}'?",
+    ),
+    ("merge-live", "Also wrote them into {path}"),
+    ("label-accounts", "accounts"),
+    ("label-store", "store"),
+    ("label-lock", "lock"),
+    ("label-sync", "sync"),
+    ("status-unlocked", "free"),
+    ("status-locked", "held by {holder}"),
+    ("status-synced", "up to date with {remote}"),
+    (
+        "status-sync-pending",
+        "{push} to push, {pull} to pull (as of the last sync)",
+    ),
+    ("status-sync-off", "not set up"),
+    (
+        "lock-held",
+        "Another claude-account-switcher is changing accounts ({holder}). Try again when it's done, or pass --wait to wait for it.",
+    ),
+    (
+        "lock-timeout",
+        "Gave up after {seconds}s waiting for another claude-account-switcher ({holder}); pass --wait to wait as long as it takes.",
+    ),
+    (
+        "lock-waiting",
+        "Waiting for another claude-account-switcher to finish ({holder})...",
+    ),
+    ("lock-holder-unknown", "process unknown"),
+    (
+        "capability-symlinks",
+        "Symlinks: available, so `--mode symlink` links the live directory",
+    ),
+    (
+        "capability-no-symlinks",
+        "Symlinks: not available; `--mode symlink` copies instead",
+    ),
+    (
+        "capability-keychain",
+        "Keychain: available for `credentials = \"keychain\"`",
+    ),
+    (
+        "capability-no-keychain",
+        "Keychain: not available; credentials stay in snapshot files",
+    ),
+    (
+        "capability-reflink",
+        "Reflink: available; unencrypted copies share blocks instead of duplicating them",
+    ),
+    (
+        "capability-no-reflink",
+        "Reflink: not supported by this filesystem; copies duplicate every file",
+    ),
+    ("capabilities-cached", "Probed just now; cached in {path}"),
+    (
+        "keychain-unavailable",
+        "The keychain isn't available here, so credentials stay in snapshot files. Run `doctor --capabilities` once it is.",
+    ),
+    ("upgrade-changed", "{path}: {contents}, {before} → {after}"),
+    ("upgrade-unchanged", "{path}: {contents}, already current"),
+    ("upgrade-backup", "The previous version is kept as {path}"),
+    ("upgrade-accounts", "accounts: {count}"),
+    ("upgrade-events", "events: {count}"),
+    (
+        "upgrade-events-kept",
+        "events: {count}, unreadable lines kept as they were: {kept}",
+    ),
+    ("upgrade-settings", "settings"),
+    (
+        "upgrade-settings-comments",
+        "settings (comments are dropped; the backup keeps them)",
+    ),
+    ("upgrade-nothing", "No state files to upgrade."),
+    ("upgrade-dry-run", "Dry run: nothing was written."),
+    (
+        "switch-recovered",
+        "Restored {path}, which an interrupted switch had left moved aside",
+    ),
+    ("doctor-ok", "ok"),
+    ("doctor-warning", "warning"),
+    ("doctor-problem", "problem"),
+    ("doctor-fix", "Fix: {fix}"),
+    ("doctor-fixed", "Fixed: {fix}"),
+    ("doctor-live-ok", "Live configuration at {path} is readable"),
+    ("doctor-live-missing", "No live configuration at {path}"),
+    (
+        "doctor-live-missing-fix",
+        "run `claude` and sign in, or switch to a saved account",
+    ),
+    (
+        "doctor-live-dangling",
+        "{path} links to a snapshot that no longer exists",
+    ),
+    (
+        "doctor-live-dangling-fix",
+        "switch to one of your accounts to replace the link",
+    ),
+    ("doctor-live-unreadable", "Can't read {path}: {error}"),
+    (
+        "doctor-live-unreadable-fix",
+        "make sure the directory belongs to you and is readable",
+    ),
+    ("doctor-config-ok", "Accounts file is valid"),
+    ("doctor-config-invalid", "{path} can't be read: {error}"),
+    (
+        "doctor-config-invalid-fix",
+        "move it aside to {path} and register the saved snapshots again",
+    ),
+    (
+        "doctor-account-missing",
+        "The snapshot of '{name}' is missing from {path}",
+    ),
+    (
+        "doctor-account-missing-fix",
+        "remove the account from the list",
+    ),
+    (
+        "doctor-credentials-expired",
+        "The saved login of '{name}' expired on {time}",
+    ),
+    (
+        "doctor-credentials-expired-fix",
+        "switch to '{name}', sign in again if Claude Code asks, then save it",
+    ),
+    (
+        "doctor-current-unknown",
+        "The active account '{name}' isn't in the list",
+    ),
+    (
+        "doctor-current-unknown-fix",
+        "forget it; the next save or switch sets a new one",
+    ),
+    (
+        "doctor-orphan",
+        "{path} is in the store but no account uses it",
+    ),
+    ("doctor-orphan-fix", "register it as account '{name}'"),
+    (
+        "doctor-orphan-name-fix",
+        "its name can't be an account's; adopt it with `adopt {path} --name <name>`",
+    ),
+    ("doctor-permissions", "{path} can be read by other users"),
+    ("doctor-permissions-fix", "restrict it to your user (0600)"),
+    ("doctor-healthy", "No problems need fixing."),
+    (
+        "doctor-outstanding",
+        "{count} problem(s) need attention; `doctor --fix` repairs the ones it can.",
+    ),
+    (
+        "crash-report-written",
+        "A crash report was saved to {path}; it has no credentials, so you can attach it to an issue.",
+    ),
+    (
+        "crash-report-failed",
+        "Could not save a crash report: {error}",
+    ),
+    (
+        "crash-recovery-snapshot",
+        "The snapshot of '{name}' may be incomplete. The live configuration is untouched; save it again, or run `claude-account-switcher rollback {name}` to go back to the previous save.",
+    ),
+    (
+        "crash-recovery-live",
+        "The live configuration may be only partly replaced. Run `claude-account-switcher switch {name}` to restore it.",
+    ),
+    (
+        "crash-recovery-version",
+        "The rollback of '{name}' was interrupted. Check `claude-account-switcher history {name}` and run `claude-account-switcher rollback {name}` again if the snapshot is missing.",
+    ),
+    (
+        "crash-recovery-config",
+        "The files were written but accounts.json may not reflect it. Check `claude-account-switcher list` before switching again.",
+    ),
+    (
+        "init-settings-exist",
+        "{path} already exists; change it with your answers?",
+    ),
+    (
+        "init-store",
+        "Accounts are kept in {path} (set XDG_DATA_HOME to move them).",
+    ),
+    (
+        "init-system",
+        "Keep accounts in the system-wide store in {path}, shared with other users, rather than your own?",
+    ),
+    ("init-claude-dir", "Claude Code's directory [{path}]: "),
+    (
+        "init-credentials-only",
+        "Switch only the login, with settings, projects and history shared by every account?",
+    ),
+    (
+        "init-keychain",
+        "Keep saved logins in the OS keychain rather than in files?",
+    ),
+    ("init-encrypt", "Encrypt saved accounts with a passphrase?"),
+    (
+        "init-first-account",
+        "Save the current login as (leave empty to skip): ",
+    ),
+    (
+        "init-done",
+        "Settings written to {path}; change them later with `config`.",
+    ),
+    ("migrate-schema", "accounts.json: schema {from} → {to}"),
+    (
+        "migrate-current",
+        "accounts.json: schema {version}, already current",
+    ),
+    ("account-adopted", "Adopted '{name}' from {path}"),
+    ("adopt-skipped", "Left {path} alone: {error}"),
+    ("adopt-copied", "Copied {path} into the store as '{name}'"),
+    (
+        "adopt-tracked",
+        "{path} is already the snapshot of '{name}'",
+    ),
+    (
+        "adopt-dir-taken",
+        "{path} already exists; pick another name with --name",
+    ),
+    (
+        "adopt-none",
+        "Every directory in the store belongs to an account.",
+    ),
+    (
+        "legacy-not-found",
+        "No accounts from an earlier version found in {path}",
+    ),
+    (
+        "legacy-snapshot-missing",
+        "Skipped '{name}': its snapshot is missing from {path}",
+    ),
+    ("legacy-migrated-one", "Moved 1 account from {path}"),
+    ("legacy-migrated-many", "Moved {count} accounts from {path}"),
+    (
+        "legacy-left-behind",
+        "Accounts that weren't moved are still listed in {path}",
+    ),
+    (
+        "legacy-hint",
+        "Accounts saved by an earlier version are in {path}; run `claude-account-switcher migrate-legacy` to move them here.",
+    ),
+    (
+        "no-versions",
+        "No earlier snapshots of '{name}' to roll back to",
+    ),
+    (
+        "version-not-found",
+        "No snapshot '{id}' of '{name}'; see `history {name}`",
+    ),
+    ("rolled-back", "Rolled '{name}' back to snapshot {id}"),
+    (
+        "rolled-back-live",
+        "Restored it into the live configuration, as it's the active account",
+    ),
+    (
+        "reserved-name",
+        "'{name}' is reserved for the switcher's own use",
+    ),
+    ("name-empty", "An account name can't be empty"),
+    ("name-too-long", "'{name}' is longer than {max} characters"),
+    (
+        "name-bad-char",
+        "'{name}' has '{char}' in it. Names can use letters, digits and . _ - @ +, with / between namespaces",
+    ),
+    (
+        "name-bad-part",
+        "'{name}' has a part that's empty or starts or ends with '.'",
+    ),
+    (
+        "name-device",
+        "'{name}' has '{part}' in it, which Windows keeps for a device",
+    ),
+    ("version-usage", "{files} files, {size}"),
+    ("account-usage", "{files} files, {size}"),
+    ("plain-account-usage", ", files: {files}, size: {size}"),
+    ("plain-account-tags", ", tags: {tags}"),
+    ("plain-account-aliases", ", aliases: {aliases}"),
+    ("plain-account-limited", ", rate limited for: {left}"),
+    ("account-limited", "(rate limited, {left} left)"),
+    ("plain-account-stale", ", stale: {days} days"),
+    ("account-stale", "(saved {days} days ago)"),
+    ("plain-account-expiring", ", login expires: {time}"),
+    ("account-expiring", "(login expires {time})"),
+    ("plain-account-expired", ", login expired: {time}"),
+    ("account-expired", "(login expired {time})"),
+    ("account-aliases", "alias {aliases}"),
+    ("plain-account-note", ", note: {note}"),
+    (
+        "note-none",
+        "'{name}' has no note. Add one with `note {name} <text>` or `--edit`",
+    ),
+    ("note-saved", "Saved the note of '{name}'"),
+    ("note-cleared", "Removed the note of '{name}'"),
+    (
+        "note-editor-failed",
+        "{editor} exited with an error; the note is unchanged",
+    ),
+    (
+        "size-account",
+        "{files} files, {size} (versions: {version_files} files, {version_size})",
+    ),
+    (
+        "plain-size",
+        "name: {name}, files: {files}, size: {size}, version files: {version_files}, version size: {version_size}",
+    ),
+    ("size-store", "Store total: {size} in {files} files"),
+    ("size-measuring", "Measuring snapshots"),
+    ("copy-progress", "Copying"),
+    (
+        "plain-version",
+        "id: {id}, saved: {time}, files: {files}, size: {size}",
+    ),
+    ("export-passphrase-prompt", "Archive passphrase: "),
+    ("export-passphrase-repeat", "Repeat archive passphrase: "),
+    ("export-created-one", "Exported account '{name}' to {path}"),
+    ("export-created-many", "Exported {count} accounts to {path}"),
+    (
+        "import-name-needs-single",
+        "--name only applies to archives holding one account; this one holds {count}",
+    ),
+    (
+        "import-conflict-hint",
+        "Pass --on-conflict rename, skip or replace to import it anyway",
+    ),
+    (
+        "import-skipped",
+        "Skipped '{name}': an account with that name already exists",
+    ),
+    (
+        "import-renamed",
+        "Account '{name}' already exists; importing as '{new_name}'",
+    ),
+    (
+        "import-replace-active",
+        "Can't replace '{name}' while it's the active account; switch to another account first",
+    ),
+    ("history-empty", "No history recorded yet"),
+    (
+        "plain-history",
+        "time: {time}, action: {action}, account: {account}, source: {source}",
+    ),
+    ("undo-nothing", "Nothing to undo"),
+    (
+        "undo-unsupported",
+        "The last change was a {action}, which undo can't reverse (see `rollback`)",
+    ),
+    (
+        "undo-switch-moved",
+        "'{name}' is no longer the active account, so its switch can't be undone",
+    ),
+    (
+        "undo-no-backup",
+        "The configuration the last switch replaced wasn't kept, so it can't be undone",
+    ),
+    (
+        "undo-live-backups-off",
+        "keep-live-backups is 0, so the configuration a switch replaces isn't kept and switches can't be undone",
+    ),
+    (
+        "live-backups-none",
+        "No live directory a switch replaced has been kept",
+    ),
+    (
+        "live-backup-not-found",
+        "No kept live directory '{id}'; see `recover-live --list`",
+    ),
+    (
+        "live-recovered",
+        "Put back the live directory from {id}; '{name}' is active again",
+    ),
+    (
+        "live-recovered-untracked",
+        "Put back the live directory from {id}, which wasn't saved as any account",
+    ),
+    (
+        "plain-live-backup",
+        "id: {id}, account: {name}, replaced: {time}",
+    ),
+    (
+        "undo-not-in-trash",
+        "'{name}' is not in the trash (see `trash list`)",
+    ),
+    ("undo-switched", "Back on account '{name}'"),
+    (
+        "undo-switched-none",
+        "Put back the configuration from before the switch",
+    ),
+    ("undo-deleted", "Restored account '{name}'"),
+    ("undo-renamed", "Renamed '{old}' back to '{new}'"),
+    ("trash-empty", "The trash is empty"),
+    (
+        "sessions-none",
+        "No terminal is using an account from the store",
+    ),
+    ("session-pid", "pid {pid}"),
+    ("session-since", "since {time}"),
+    (
+        "daemon-started",
+        "Daemon started (pid {pid}), listening on {path}",
+    ),
+    ("daemon-stopped", "Daemon stopped"),
+    (
+        "passphrase-unattended",
+        "The store is encrypted and there's no terminal to ask for its passphrase; set {var}",
+    ),
+    ("daemon-not-running", "The daemon isn't running"),
+    (
+        "daemon-running",
+        "Daemon running (pid {pid}, since {time}, {requests} request(s) served), listening on {path}",
+    ),
+    ("daemon-already-running", "The daemon is already running"),
+    (
+        "daemon-already-running-pid",
+        "The daemon is already running (pid {pid})",
+    ),
+    (
+        "daemon-start-failed",
+        "The daemon didn't start; see {log} for why",
+    ),
+    (
+        "daemon-unsupported",
+        "The daemon listens on a Unix socket, which isn't available here",
+    ),
+    (
+        "plain-session",
+        "account: {name}, from: {kind}, pid: {pid}, since: {time}",
+    ),
+    (
+        "session-conflict",
+        "Warning: '{name}' is in use by a terminal (`{kind}`, pid {pid}); both will refresh the same login, and one may be signed out",
+    ),
+    ("deleted-at", "deleted: {time}"),
+    (
+        "plain-trashed",
+        "name: {name}, deleted: {time}, files: {files}, size: {size}",
+    ),
+    ("trash-restored", "Restored account '{name}' from the trash"),
+    (
+        "trash-empty-confirm",
+        "Permanently remove everything in the trash?",
+    ),
+    (
+        "trash-emptied",
+        "Removed {count} deleted account(s) from the trash",
+    ),
+    (
+        "backup-created",
+        "Backed up {count} account(s) to {path} ({size})",
+    ),
+    ("backup-removed", "Removed old backup {path}"),
+    (
+        "backup-external-skipped",
+        "Not backed up, as their files aren't in the store: {names}",
+    ),
+    (
+        "backup-restore-confirm",
+        "Replace every saved account, the history and settings with the backup?",
+    ),
+    (
+        "backup-kept-current",
+        "The store as it was is backed up to {path}",
+    ),
+    (
+        "backup-restored",
+        "Restored {count} account(s) from the backup made {time}",
+    ),
+    (
+        "sync-needs-encryption",
+        "Only an encrypted store can sync, so credentials never leave this machine readable. Run `encrypt enable` first",
+    ),
+    ("sync-already-set-up", "Sync is already set up in {path}"),
+    (
+        "sync-set-up",
+        "Syncing through {remote}. Run `sync push` to send the accounts here",
+    ),
+    (
+        "sync-not-set-up",
+        "Sync isn't set up. Run `sync init --remote <git-url>` first",
+    ),
+    ("sync-sent", "Sent '{name}'"),
+    ("sync-removed-there", "Removed '{name}' from the repository"),
+    (
+        "sync-removed-here",
+        "Moved '{name}' to the trash, as it was deleted elsewhere",
+    ),
+    (
+        "sync-skipped-active",
+        "Left '{name}' as it is, as it's active here. Switch away and pull again to take the other machine's",
+    ),
+    (
+        "sync-skipped-external",
+        "Left '{name}' as it is, as it was registered with add-existing here",
+    ),
+    (
+        "sync-skipped-locked",
+        "Left '{name}' as it is, as it's locked here; pull with --force to replace it",
+    ),
+    (
+        "sync-skipped-locked-remove",
+        "Kept '{name}', as it's locked here; pull with --force to remove it as it was elsewhere",
+    ),
+    ("sync-up-to-date", "Already up to date"),
+    ("sync-passphrase-prompt", "Passphrase of the synced store: "),
+    (
+        "sync-wrong-passphrase",
+        "Couldn't unlock the synced store, which was set up on another machine",
+    ),
+    (
+        "sync-unsealed",
+        "Refusing to sync '{path}': it isn't encrypted",
+    ),
+    ("sync-git-failed", "git {command} failed: {output}"),
+    ("sync-git-missing", "Failed to run git. Is it installed?"),
+    (
+        "sync-bad-remote",
+        "'{remote}' names no bucket: use s3://bucket or s3://bucket/prefix",
+    ),
+    (
+        "sync-s3-credentials",
+        "Set AWS_ACCESS_KEY_ID and AWS_SECRET_ACCESS_KEY to sync through S3",
+    ),
+    (
+        "sync-conflict",
+        "Another machine pushed while this one was. Run `sync push` again",
+    ),
+    (
+        "sync-object-missing",
+        "'{key}' in the synced store is missing or damaged. Push from a machine with a good copy",
+    ),
+    (
+        "sync-unsafe-path",
+        "The synced store's index has a path outside the sync directory: {path}",
+    ),
+    ("sync-http-unreachable", "Couldn't reach {url}"),
+    (
+        "sync-http-failed",
+        "{method} {url} failed with {status}: {output}",
+    ),
+    ("plain-history-previous", ", previous: {name}"),
+    ("plain-history-files", ", files: {count}"),
+    ("history-previous", " from {name}"),
+    ("history-files", ", {count} files"),
+    ("binding-written", "Bound {path} to account '{name}'"),
+    (
+        "binding-unknown-account",
+        "{path} binds to account '{name}', which doesn't exist",
+    ),
+    (
+        "binding-mismatch",
+        "Warning: this project is bound to '{name}' but '{current}' is active",
+    ),
+    (
+        "binding-switching",
+        "Switching to '{name}' (bound by {path})",
+    ),
+    ("project-set", "Mapped {path} to account '{name}'"),
+    (
+        "project-unset",
+        "Removed the mapping of {path} (to '{name}')",
+    ),
+    ("project-unknown", "{path} isn't mapped to an account"),
+    (
+        "project-none",
+        "Nothing binds {path} to an account; map it with `project set` or `bind`",
+    ),
+    ("projects-none", "No directories are mapped to accounts"),
+    ("plain-project", "account: {name}, path: {path}"),
+    ("tui-title", " Claude accounts ({count}) "),
+    ("tui-col-account", "Account"),
+    ("tui-col-email", "Email"),
+    ("tui-col-plan", "Plan"),
+    ("tui-col-expires", "Token expires"),
+    ("tui-col-last-used", "Last used"),
+    ("tui-col-size", "Size"),
+    ("tui-never", "never"),
+    ("tui-expired", "expired"),
+    ("tui-details", "Saved {saved}, {files} files, token {token}"),
+    (
+        "tui-help",
+        "↑/↓ move  Enter switch  r rename  d delete  b back up active  q quit",
+    ),
+    ("tui-confirm-delete", "Delete '{name}' and its snapshot?"),
+    ("tui-rename", "New name for '{name}': "),
+    ("tui-no-active", "No active account to back up"),
+    ("tui-press-enter", "Press Enter to return to the dashboard"),
+    (
+        "account-protected",
+        "Switching to '{name}' now requires confirmation",
+    ),
+    (
+        "account-unprotected",
+        "'{name}' no longer requires confirmation",
+    ),
+    (
+        "account-pinned",
+        "'{name}' can now only be switched to with Claude Code {version}",
+    ),
+    (
+        "account-unpinned",
+        "'{name}' is no longer pinned to a Claude Code version",
+    ),
+    (
+        "pin-no-claude",
+        "Claude Code isn't installed, or didn't say its version; give one with --claude-version",
+    ),
+    (
+        "claude-version-unknown",
+        "'{name}' is pinned to Claude Code {version}, but the installed version couldn't be told",
+    ),
+    (
+        "claude-version-drift",
+        "'{name}' was saved with Claude Code {saved}, but {installed} is installed; its configuration may not be read the same way",
+    ),
+    (
+        "protected-warning",
+        "Warning: '{name}' is a protected account",
+    ),
+    ("protected-confirm", "Switch to it?"),
+    (
+        "account-locked",
+        "'{name}' is locked: saving over it, deleting and pruning it now need --force",
+    ),
+    ("account-unlocked", "'{name}' is no longer locked"),
+    (
+        "account-locked-not-saved",
+        "'{name}' is locked; its snapshot was left as it was",
+    ),
+    ("protected-type-name", "Type the account name to confirm: "),
+    (
+        "protected-name-mismatch",
+        "Typed name does not match '{name}'; not switching",
+    ),
+    ("pick-prompt", "Switch to [1-{count}, empty to cancel]: "),
+    ("pick-invalid", "No account matches '{answer}'"),
+    ("pick-no-match", "(no matching accounts)"),
+    ("pick-query", "{count}/{total} > "),
+    ("share-created", "Shared account '{name}' to {path}"),
+    ("share-passphrase", "Passphrase: {passphrase}"),
+    ("share-expires", "Expires:    {time}"),
+    ("passphrase-prompt", "Passphrase: "),
+    ("store-passphrase-prompt", "Store passphrase: "),
+    ("store-passphrase-repeat", "Repeat store passphrase: "),
+    ("passphrase-mismatch", "Passphrases don't match"),
+    ("passphrase-empty", "Passphrase must not be empty"),
+    (
+        "encryption-enabled",
+        "Encrypted {files} files in {accounts} accounts",
+    ),
+    (
+        "encryption-disabled",
+        "Decrypted {files} files in {accounts} accounts",
+    ),
+    (
+        "encryption-already-enabled",
+        "Encryption is already enabled",
+    ),
+    ("encryption-not-enabled", "Encryption is not enabled"),
+    (
+        "symlink-copying",
+        "Warning: {reason}, switching by copying instead",
+    ),
+    ("direct-encrypted", "the account store is encrypted"),
+    ("direct-keychain", "credentials are kept in the keychain"),
+    (
+        "direct-dedup",
+        "snapshots share files with each other (dedup)",
+    ),
+    ("direct-compressed", "the snapshot is stored compressed"),
+    (
+        "direct-credentials-scope",
+        "only the login is captured per account",
+    ),
+    (
+        "env-current",
+        "'{name}' is the active account, already in {path}; `env` and `shell` are for the others",
+    ),
+    (
+        "env-unusable",
+        "'{name}' can't be used from the store: {reason}",
+    ),
+    (
+        "shell-started",
+        "Starting a shell with account '{name}'; exit it to leave",
+    ),
+    (
+        "import-dry-run",
+        "Would import account '{name}' ({files} files, {size}); nothing was written",
+    ),
+    ("inspect-account", "Account:    {name}"),
+    ("inspect-saved", "Saved:      {time}"),
+    ("inspect-created", "Created:    {time}"),
+    ("inspect-expired", "Expired:    {time}"),
+    ("inspect-never-expires", "Expires:    never"),
+    ("inspect-files", "Files:      {files} ({size})"),
+    ("inspect-plan", "Plan:       {plan}"),
+    ("inspect-token", "Token:      {token}"),
+    ("inspect-token-expires", "Token ends: {time}"),
+    ("inspect-no-credentials", "Login:      none"),
+    (
+        "grep-skip-missing",
+        "Warning: skipping '{name}', account directory not found",
+    ),
+    ("no-matches", "No matches found."),
+    ("label-archived", "archived"),
+    (
+        "token-none",
+        "Account '{name}' has no OAuth token or API key saved",
+    ),
+    (
+        "token-copied",
+        "Copied the token of '{name}'; the clipboard is cleared in {seconds}s",
+    ),
+    (
+        "token-masked-hint",
+        "Masked; pass --reveal to print it whole, or --copy to put it on the clipboard.",
+    ),
+    (
+        "clipboard-unavailable",
+        "No clipboard tool found (pbcopy, wl-copy, xclip, xsel or clip)",
+    ),
+    ("plain-account-archived", ", archived"),
+    ("archive-already", "Account '{name}' is already archived"),
+    (
+        "archive-active",
+        "Account '{name}' is the active one; switch to another before archiving it",
+    ),
+    (
+        "archive-external",
+        "Account '{name}' was added with add-existing; its directory is managed elsewhere, so it isn't archived",
+    ),
+    (
+        "archive-nested",
+        "Other accounts' snapshots are inside '{name}'; archive or move them first",
+    ),
+    ("archive-not-archived", "Account '{name}' isn't archived"),
+    ("account-archived", "Archived account '{name}' ({size})"),
+    ("account-unarchived", "Unarchived account '{name}'"),
+    ("unarchiving", "Unarchiving account '{name}'..."),
+    (
+        "encryption-archived",
+        "Unarchive these accounts first: {names}",
+    ),
+    (
+        "checkpoint-bad-label",
+        "'{label}' can't name a checkpoint: it's empty or starts with '.'",
+    ),
+    (
+        "checkpoint-no-live",
+        "There's no live configuration at {path} to checkpoint",
+    ),
+    (
+        "checkpoint-exists",
+        "There's already a checkpoint '{label}'; pass --force to replace it",
+    ),
+    ("checkpoint-saved", "Saved checkpoint '{label}' ({size})"),
+    ("checkpoint-pruned", "Removed old checkpoint '{label}'"),
+    (
+        "checkpoint-not-found",
+        "No checkpoint '{label}'; see `checkpoint list`",
+    ),
+    (
+        "checkpoint-restored",
+        "Restored checkpoint '{label}'; the configuration it replaced is kept for `recover-live`",
+    ),
+    ("checkpoint-deleted", "Deleted checkpoint '{label}'"),
+    ("checkpoints-none", "No checkpoints saved"),
+    (
+        "plain-checkpoint",
+        "label: {label}, account: {name}, saved: {time}",
+    ),
+    (
+        "preview-archived",
+        "Account '{name}' is archived; unarchive it to preview the switch",
+    ),
+    (
+        "preview-heading",
+        "Switching to '{name}' changes ~/.claude:",
+    ),
+    ("preview-identity", "Signed in as: {from} -> {to}"),
+    (
+        "preview-identity-same",
+        "Signed in as: {identity} (unchanged)",
+    ),
+    ("preview-summary", "{count} file(s) change."),
+    ("preview-confirm", "Switch to '{name}'?"),
+    (
+        "var-invalid",
+        "'{var}' isn't NAME=VALUE with a valid variable name",
+    ),
+    ("var-set", "Set {var} for '{name}'"),
+    ("var-unset", "Removed {var} from '{name}'"),
+    ("var-not-set", "'{name}' has no variable {var}"),
+    (
+        "vars-none",
+        "'{name}' has no variables; add some with `var set`",
+    ),
+    (
+        "system-missing",
+        "The system store {path} doesn't exist; an administrator can create it for a group with `install -d -m 2750 -g GROUP {path}`",
+    ),
+    (
+        "system-read-only",
+        "Can't change the system store's accounts in {path}; ask an administrator to make this change",
+    ),
+    (
+        "system-access-invalid",
+        "The system store's access rules in {path} are invalid: {error}",
+    ),
+    (
+        "system-account-read-only",
+        "'{name}' is shared from the system store and only an administrator can save over it",
+    ),
+    (
+        "system-not-saved",
+        "'{name}' is shared from the system store; its changes were not saved",
+    ),
+    (
+        "system-copy-only",
+        "Accounts in the system store are only switched to by copying",
+    ),
+    ("interrupted", "Interrupted before it finished"),
+    (
+        "interrupted-save",
+        "The save of '{name}' started {time} didn't finish",
+    ),
+    (
+        "interrupted-switch",
+        "The switch to '{name}' started {time} didn't finish",
+    ),
+    (
+        "interrupted-hint",
+        "Run `claude-account-switcher recover --resume` to finish it, or `recover --rollback` to undo it",
+    ),
+    ("interrupted-resume-confirm", "Finish it now?"),
+    (
+        "interrupted-rollback-confirm",
+        "Undo what it had done instead?",
+    ),
+    (
+        "interrupted-left",
+        "Left as it is; `claude-account-switcher recover` deals with it later",
+    ),
+    ("recover-none", "No interrupted save or switch to recover"),
+    (
+        "recover-nothing-done",
+        "It hadn't changed anything yet; nothing to undo",
+    ),
+    (
+        "recover-save-kept",
+        "The snapshot of '{name}' may be incomplete and there's no earlier one to put back; check it with `verify {name}`",
+    ),
+    (
+        "recover-snapshot-restored",
+        "Put back the snapshot of '{name}' the save was replacing",
+    ),
+    (
+        "recover-snapshot-removed",
+        "Removed the incomplete snapshot of '{name}'",
+    ),
+    (
+        "recover-live-instead",
+        "The live configuration the switch replaced has been moved on; see `recover-live --list`",
+    ),
+    (
+        "recover-switched-back",
+        "Undid the switch; '{name}' is active again",
+    ),
+    ("recover-switched-back-none", "Undid the switch"),
+    (
+        "snapshot-archived",
+        "'{name}' is archived; unarchive it to look inside",
+    ),
+    ("snapshot-not-dir", "'{name}' has no directory {path}"),
+    ("plain-snapshot-entry", "path: {path}, size: {size}"),
+    (
+        "failed-determine-current-directory",
+        "Failed to determine the current directory",
+    ),
+    ("debug-bundle-written", "Wrote debug bundle: {path}"),
+    (
+        "debug-bundle-review",
+        "Secrets, emails and your home directory are masked; look it over before attaching it to a report.",
+    ),
+    ("man-written", "Wrote {count} man page(s) to {path}"),
+    (
+        "failed-clear-account-directory",
+        "Failed to clear account directory",
+    ),
+    (
+        "failed-clear-synced-account",
+        "Failed to clear synced account",
+    ),
+    (
+        "failed-copy-account-snapshot",
+        "Failed to copy account snapshot",
+    ),
+    (
+        "failed-copy-configuration-files",
+        "Failed to copy configuration files",
+    ),
+    (
+        "failed-copy-file-from-to",
+        "Failed to copy file from {from} to {to}",
+    ),
+    ("failed-copy-store-key", "Failed to copy the store key"),
+    ("failed-create", "Failed to create {path}"),
+    (
+        "failed-create-account-directory",
+        "Failed to create account directory",
+    ),
+    (
+        "failed-create-account-storage-directory",
+        "Failed to create account storage directory",
+    ),
+    (
+        "failed-create-claude-config-directory",
+        "Failed to create Claude config directory",
+    ),
+    (
+        "failed-create-configuration-directory",
+        "Failed to create configuration directory",
+    ),
+    (
+        "failed-create-directory",
+        "Failed to create directory: {path}",
+    ),
+    (
+        "failed-create-settings-directory",
+        "Failed to create settings directory",
+    ),
+    (
+        "failed-create-state-directory",
+        "Failed to create state directory",
+    ),
+    ("failed-create-symlink", "Failed to create symlink: {path}"),
+    (
+        "failed-create-synced-account",
+        "Failed to create synced account",
+    ),
+    (
+        "failed-create-template-directory",
+        "Failed to create template directory",
+    ),
+    (
+        "failed-create-trash-directory",
+        "Failed to create trash directory",
+    ),
+    (
+        "failed-create-versions-directory",
+        "Failed to create versions directory",
+    ),
+    ("failed-delete-template", "Failed to delete template"),
+    (
+        "failed-determine-home-directory",
+        "Failed to determine home directory",
+    ),
+    (
+        "failed-find-switchers-executable",
+        "Failed to find the switcher's executable",
+    ),
+    (
+        "failed-keep-current-snapshot",
+        "Failed to keep the current snapshot",
+    ),
+    (
+        "failed-keep-live-directory",
+        "Failed to keep the live directory",
+    ),
+    (
+        "failed-keep-nested-account",
+        "Failed to keep a nested account",
+    ),
+    (
+        "failed-keep-previous-snapshot",
+        "Failed to keep previous snapshot",
+    ),
+    ("failed-keep-shared-files", "Failed to keep shared files"),
+    (
+        "failed-locate-switcher-executable",
+        "Failed to locate the switcher executable",
+    ),
+    ("failed-move", "Failed to move {path}"),
+    (
+        "failed-move-account-directory-to-trash",
+        "Failed to move account directory to the trash",
+    ),
+    (
+        "failed-move-account-store-aside",
+        "Failed to move the account store aside",
+    ),
+    (
+        "failed-move-account-versions-to-trash",
+        "Failed to move account versions to the trash",
+    ),
+    (
+        "failed-move-accounts-file-aside",
+        "Failed to move the accounts file aside",
+    ),
+    (
+        "failed-move-current-configuration-aside",
+        "Failed to move the current configuration aside",
+    ),
+    (
+        "failed-move-earlier-configuration-into-place",
+        "Failed to move the earlier configuration into place",
+    ),
+    (
+        "failed-move-new-configuration-into-place",
+        "Failed to move the new configuration into place",
+    ),
+    (
+        "failed-open-checksum-list",
+        "Failed to open checksum list: {path}",
+    ),
+    ("failed-parse", "Failed to parse {path}"),
+    (
+        "failed-parse-template-file",
+        "Failed to parse template file: {path}",
+    ),
+    (
+        "failed-read-accounts-configuration-file",
+        "Failed to read accounts configuration file",
+    ),
+    ("failed-read-bundle", "Failed to read bundle: {path}"),
+    ("failed-read-claude-md", "Failed to read CLAUDE.md"),
+    (
+        "failed-read-credentials-file",
+        "Failed to read credentials file",
+    ),
+    ("failed-read-directory", "Failed to read directory: {path}"),
+    ("failed-read-file", "Failed to read file: {path}"),
+    ("failed-read-history-file", "Failed to read history file"),
+    ("failed-read-note", "Failed to read note"),
+    (
+        "failed-read-restored-state",
+        "Failed to read restored state",
+    ),
+    (
+        "failed-read-saved-claude-md",
+        "Failed to read saved CLAUDE.md",
+    ),
+    ("failed-read-settings-file", "Failed to read settings file"),
+    (
+        "failed-read-state-directory",
+        "Failed to read state directory",
+    ),
+    (
+        "failed-read-trashed-account",
+        "Failed to read trashed account",
+    ),
+    (
+        "failed-recreate-symlink",
+        "Failed to recreate symlink: {path}",
+    ),
+    ("failed-remove", "Failed to remove {path}"),
+    (
+        "failed-remove-account-symlink",
+        "Failed to remove account symlink",
+    ),
+    (
+        "failed-remove-directory",
+        "Failed to remove directory: {path}",
+    ),
+    ("failed-remove-file", "Failed to remove file: {path}"),
+    ("failed-remove-key-file", "Failed to remove key file"),
+    (
+        "failed-remove-old-accounts-file",
+        "Failed to remove old accounts file",
+    ),
+    (
+        "failed-remove-saved-claude-md",
+        "Failed to remove saved CLAUDE.md",
+    ),
+    (
+        "failed-remove-saved-credentials-file",
+        "Failed to remove saved credentials file",
+    ),
+    ("failed-remove-symlink", "Failed to remove symlink"),
+    (
+        "failed-remove-synced-account",
+        "Failed to remove synced account",
+    ),
+    (
+        "failed-rename-account-directory",
+        "Failed to rename account directory",
+    ),
+    ("failed-replace-template", "Failed to replace template"),
+    (
+        "failed-resolve-directory",
+        "Failed to resolve directory: {path}",
+    ),
+    ("failed-resolve-path", "Failed to resolve path: {path}"),
+    ("failed-restore", "Failed to restore: {path}"),
+    (
+        "failed-restore-account-configuration",
+        "Failed to restore account configuration",
+    ),
+    (
+        "failed-restore-account-directory",
+        "Failed to restore account directory",
+    ),
+    (
+        "failed-restore-account-store",
+        "Failed to restore the account store",
+    ),
+    (
+        "failed-restore-account-versions",
+        "Failed to restore account versions",
+    ),
+    (
+        "failed-restore-configuration-interrupted-switch-moved-aside",
+        "Failed to restore the configuration an interrupted switch moved aside",
+    ),
+    (
+        "failed-restore-credentials-file",
+        "Failed to restore credentials file",
+    ),
+    (
+        "failed-restore-files-outside-claude-directory",
+        "Failed to restore files outside the Claude directory",
+    ),
+    (
+        "extra-paths-not-absolute",
+        "Invalid extra-paths setting: {path} (expected an absolute path or one starting with ~)",
+    ),
+    (
+        "extra-paths-holds-home",
+        "Invalid extra-paths setting: {path} (it holds the home or Claude directory)",
+    ),
+    ("failed-restore-settings", "Failed to restore settings"),
+    ("failed-restore-snapshot", "Failed to restore snapshot"),
+    ("failed-serialize", "Failed to serialize {path}"),
+    (
+        "failed-serialize-mcp-servers",
+        "Failed to serialize MCP servers",
+    ),
+    ("failed-serialize-output", "Failed to serialize output"),
+    (
+        "failed-serialize-settings-json",
+        "Failed to serialize settings.json",
+    ),
+    (
+        "failed-start-background-re-save",
+        "Failed to start background re-save",
+    ),
+    (
+        "failed-update-account-symlink",
+        "Failed to update account symlink",
+    ),
+    ("failed-write", "Failed to write {path}"),
+    (
+        "failed-write-account-snapshot",
+        "Failed to write account snapshot",
+    ),
+    ("failed-write-archive", "Failed to write archive: {path}"),
+    ("failed-write-bundle", "Failed to write bundle: {path}"),
+    ("failed-write-claude-md", "Failed to write CLAUDE.md"),
+    ("failed-write-file", "Failed to write file: {path}"),
+    ("failed-write-note", "Failed to write note"),
+    (
+        "failed-write-saved-claude-md",
+        "Failed to write saved CLAUDE.md",
+    ),
+    (
+        "failed-write-settings-json",
+        "Failed to write settings.json",
+    ),
+    (
+        "failed-write-template-file",
+        "Failed to write template file: {path}",
+    ),
+    (
+        "hardlink-copying",
+        "Copying instead of hard-linking: {reason}",
+    ),
+    (
+        "hardlink-linked",
+        "Linked {count} file(s) from the snapshot",
+    ),
+    (
+        "entry-missing",
+        "'{name}' is broken: its snapshot {path} is missing; run `claude-account-switcher repair` to re-link or drop it",
+    ),
+    (
+        "entry-corrupt",
+        "'{name}' is broken: its entry in accounts.json can't be read ({error}); run `claude-account-switcher repair` to register its snapshot again or drop it",
+    ),
+    (
+        "switch-entry-missing",
+        "Can't switch to '{name}': its snapshot {path} is missing. Put the directory back, point the account at where it is now with `claude-account-switcher repair --relink {name}=DIR`, or drop it with `claude-account-switcher repair`.",
+    ),
+    (
+        "switch-entry-corrupt",
+        "Can't switch to '{name}': its entry in accounts.json can't be read ({error}). Run `claude-account-switcher repair` to register its snapshot again or drop it.",
+    ),
+    (
+        "doctor-entry-corrupt",
+        "The entry of '{name}' in accounts.json can't be read: {error}",
+    ),
+    (
+        "doctor-entry-corrupt-fix",
+        "run `claude-account-switcher repair` to register its snapshot again or drop it",
+    ),
+    ("repair-relink-invalid", "Expected NAME=DIR, got '{value}'"),
+    (
+        "repair-healthy",
+        "'{name}' isn't broken; there's nothing to re-link",
+    ),
+    (
+        "repair-confirm",
+        "Drop {names} from the list? Nothing was found to re-link them to; no files are removed.",
+    ),
+    ("repair-relinked", "Re-linked '{name}' to {path}"),
+    ("repair-would-relink", "Would re-link '{name}' to {path}"),
+    ("repair-dropped", "Dropped '{name}' from the list"),
+    ("repair-would-drop", "Would drop '{name}' from the list"),
+    (
+        "repair-nothing",
+        "Every account's entry and snapshot are in order.",
+    ),
+    (
+        "repair-unmounted",
+        "Left '{name}' alone: the drive it's kept on isn't mounted",
+    ),
+    (
+        "save-store-missing",
+        "{path} isn't there; if it's on a drive, mount it first",
+    ),
+    (
+        "save-store-kept",
+        "'{name}' is already kept in {path}; delete it and save it again to keep it somewhere else",
+    ),
+    (
+        "store-unmounted",
+        "'{name}' is kept in {path}, which isn't there; mount the drive it's on and try again",
+    ),
+    (
+        "switch-away-unmounted",
+        "'{name}' is kept in {path}, which isn't there, so its changes can't be saved. Mount the drive it's on, or pass --force to switch without saving them.",
+    ),
+    (
+        "store-unmounted-not-saved",
+        "'{name}' wasn't saved: {path}, where it's kept, isn't mounted",
+    ),
+    (
+        "entry-unmounted",
+        "'{name}' is kept in {path}, which isn't mounted; mount the drive to use it",
+    ),
+    (
+        "save-overwrite-confirm",
+        "'{name}' is saved already and isn't the active account. Save the live configuration over it?",
+    ),
+    (
+        "save-overwrite-needed",
+        "'{name}' isn't the active account; pass --overwrite to save the live configuration over it",
+    ),
+    (
+        "switch-unsaved-confirm",
+        "~/.claude has {count} change(s) not saved to '{name}'. Save them before switching?",
+    ),
+    (
+        "switch-save-failed",
+        "Failed to save '{name}' before switching, so ~/.claude was left as it is; pass --force to switch without saving it",
+    ),
+    ("format-list-only", "--format {format} is only for `list`"),
+    (
+        "format-not-grouped",
+        "--format {format} lists one account per row, so it can't be grouped",
+    ),
+    ("import-from-not-found", "Nothing to import at {path}"),
+    ("import-from-none", "No accounts found in {path}"),
+    ("import-from-would", "Would import '{name}'"),
+    (
+        "import-from-done",
+        "Imported {count} account(s) from {path}",
+    ),
+    (
+        "no-profiles",
+        "No profiles. Create one with `profile create`.",
+    ),
+    (
+        "profile-created",
+        "Created profile '{name}' for account '{account}'",
+    ),
+    (
+        "profile-exists",
+        "Profile '{name}' already exists; pass --force to replace it",
+    ),
+    ("profile-unknown", "No profile named '{name}'"),
+    ("profile-deleted", "Deleted profile '{name}'"),
+    (
+        "profile-written",
+        "Wrote the environment of profile '{name}' to {path}",
+    ),
+    (
+        "team-overlay-missing",
+        "Team overlay not found, so it wasn't applied: {path}",
+    ),
+    (
+        "team-overlay-failed",
+        "Couldn't apply the team overlay: {error}",
+    ),
+    (
+        "export-redacted",
+        "Redacted: left out {dropped} credential file(s) and masked {masked} secret(s)",
+    ),
+    (
+        "import-redacted",
+        "The archive was redacted: sign in again, and fill in the values shown as {mask}",
+    ),
+    ("audit-none", "No files holding tokens or keys were found."),
+    ("audit-world-readable", "readable by anyone"),
+    ("audit-group-readable", "readable by the group"),
+    ("audit-unencrypted", "unencrypted"),
+    (
+        "audit-flagged",
+        "{count} of {total} file(s) holding secrets are flagged",
+    ),
+    (
+        "service-installed",
+        "Installed the {service} service in {path}",
+    ),
+    ("service-removed", "Removed the {service} service"),
+    ("service-none-installed", "No services are installed."),
+    (
+        "service-unsupported",
+        "Services can only be installed with systemd (Linux) or launchd (macOS)",
+    ),
+    ("service-command-failed", "{command} failed"),
+    ("service-state-not-installed", "not installed"),
+    ("service-state-active", "running"),
+    ("service-state-inactive", "stopped"),
+    ("service-state-installed", "installed"),
+    ("menu-prompt", "Account"),
+    ("menu-current", "current"),
+    (
+        "menu-no-launcher",
+        "No menu to pick an account from: install one of {launchers}, or pass --command",
+    ),
+    ("menu-start-failed", "Failed to start the menu: {menu}"),
+    ("health-valid", "valid"),
+    ("health-unexpired", "not expired (not checked online)"),
+    (
+        "health-expired",
+        "expired; it's refreshed when the account is next used, unless revoked",
+    ),
+    (
+        "health-revoked",
+        "revoked or signed out; sign in again with `renew`",
+    ),
+    ("health-no-login", "no OAuth login saved"),
+    ("health-unknown", "unknown"),
+    ("health-unreachable", "Failed to reach {url}"),
+    (
+        "health-insecure-url",
+        "Refusing to send tokens to {url}: {var} has to be an https URL, or http only to this machine",
+    ),
+    (
+        "check-failed",
+        "{count} of {total} login(s) can't be used as they are",
+    ),
+    ("find-lines", "{count} matching line(s)"),
+    (
+        "copy-summary",
+        "Copied {files} files ({size}) in {duration}",
+    ),
+    ("timing-scanning", "scanning"),
+    ("timing-hashing", "hashing"),
+    ("timing-copying", "copying"),
+    ("timing-config", "accounts file"),
+    ("timing-total", "total"),
+    ("plain-timing", "{phase}: {time}, {calls} times"),
+    ("plain-timing-total", "total: {time}"),
+    (
+        "bench-header",
+        "Putting {files} files ({size}) from {from} in place beside {to}",
+    ),
+    ("bench-failed", "not possible: {error}"),
+    ("bench-speed", "{size}/s"),
+    ("copy-eta", "Copying {size}, estimated {duration}..."),
+    (
+        "resave-scheduled",
+        "Will re-save '{name}' in {duration} to capture refreshed credentials",
+    ),
+    (
+        "resave-failed",
+        "Warning: could not schedule re-save: {error}",
+    ),
+    (
+        "confirm-needs-yes",
+        "This needs confirming, but stdin isn't a terminal; pass --yes to go ahead",
+    ),
+    (
+        "hook-failed",
+        "The {hook} hook failed with exit code {code}",
+    ),
+    ("hook-killed", "The {hook} hook was stopped by a signal"),
+    ("hook-start-failed", "Failed to run the {hook} hook"),
+    ("hook-warning", "Warning: {error}"),
+    ("preset-exported", "Wrote the preset to {path}"),
+    (
+        "preset-imported",
+        "Imported the preset: {settings} setting(s), {aliases} alias(es), {tagged} tagged account(s), {templates} template(s)",
+    ),
+    (
+        "preset-account-missing",
+        "'{name}' isn't an account here; its aliases and tags in the preset were skipped",
+    ),
+    (
+        "preset-template-file",
+        "The template '{name}' in the preset has a file templates don't take: {file}",
+    ),
+    (
+        "preset-hooks",
+        "The preset sets hooks, commands run on every save, switch or delete:",
+    ),
+    (
+        "preset-hooks-confirm",
+        "Import the preset with these hooks?",
+    ),
+    ("notify-webhook-failed", "Couldn't notify {url}"),
+    (
+        "login-expiring",
+        "The login of '{name}' expires in {left}; sign in again before then",
+    ),
+    (
+        "login-expired",
+        "The login of '{name}' has expired; sign in again",
+    ),
+    (
+        "notify-desktop-unsupported",
+        "Desktop notifications aren't available on this platform",
+    ),
+    (
+        "notify-desktop-failed",
+        "Couldn't show a desktop notification with {program}",
+    ),
+    (
+        "symlink-fallback",
+        "Warning: symlinks aren't permitted here, switching by copying instead",
+    ),
+    ("checksum-summary", "{ok} file(s) OK, {failed} failed"),
+    (
+        "checksum-malformed",
+        "line {line}: not a checksum and a path in the snapshot",
+    ),
+    ("checksum-empty", "{path} has no checksums to check"),
+    (
+        "checksum-failed",
+        "{failed} file(s) in '{name}' failed verification",
+    ),
+    (
+        "verify-no-current",
+        "No account is active; name one or pass --all",
+    ),
+    ("verify-ok", "{name}: {files} file(s) OK"),
+    ("verify-missing", "{name}: {path} is missing"),
+    (
+        "verify-corrupt",
+        "{name}: {path} doesn't match what was saved",
+    ),
+    (
+        "verify-no-manifest",
+        "{name}: nothing to check against; save it again to record its files",
+    ),
+    (
+        "verify-linked",
+        "{name}: the live configuration is this snapshot, so there's nothing to check",
+    ),
+    (
+        "verify-repaired",
+        "{name}: saved again from the live configuration",
+    ),
+    (
+        "verify-not-current",
+        "{name}: only the current account can be repaired, from the live configuration",
+    ),
+    ("verify-failed", "{count} snapshot(s) are damaged"),
+    (
+        "completions-installed",
+        "Installed {shell} completions to {path}",
+    ),
+    (
+        "completions-restart",
+        "Restart your shell to load the completions.",
+    ),
+    (
+        "completions-zsh-fpath",
+        "Add this to your ~/.zshrc before compinit:\n  fpath=({dir} $fpath)",
+    ),
+    (
+        "sharing-violation",
+        "Files were locked by another program, most likely antivirus real-time scanning or the Windows search indexer.\nClose programs that may have the files open and try again. To stop this from recurring, exclude the account store from scanning (run PowerShell as administrator):\n  Add-MpPreference -ExclusionPath \"{dir}\"",
+    ),
+    ("error-not-found", "Account '{name}' not found"),
+    (
+        "renew-login-hint",
+        "Sign '{name}' in again; the login is saved when Claude Code exits",
+    ),
+    (
+        "renew-login-failed",
+        "The login didn't finish; '{name}' is active but wasn't saved",
+    ),
+    ("renewed", "Renewed '{name}'"),
+    (
+        "renew-reminder",
+        "'{name}' was saved {days} days ago and its login may have expired; run `renew {name}`",
+    ),
+    (
+        "error-locked",
+        "Account '{name}' is locked; pass --force or run `unlock` first",
+    ),
+    ("error-already-exists", "Account '{name}' already exists"),
+    (
+        "error-no-configuration",
+        "No Claude Code configuration found. Please authenticate first with: claude-code auth",
+    ),
+    (
+        "error-bundle-expired",
+        "Bundle expired at {time} and can no longer be imported",
+    ),
+    (
+        "error-case-conflict",
+        "Account '{name}' differs only by case from existing account '{existing}', which maps to the same directory on this filesystem",
+    ),
+    (
+        "error-conflict",
+        "The accounts file was changed by another command while this one ran, so nothing was saved over it; run it again",
+    ),
+    (
+        "error-version-mismatch",
+        "'{name}' is pinned to Claude Code {pinned}, but {installed} is installed; install that version, or lift the pin with `pin {name} --off`",
+    ),
+];
+
+const ID: &[(&str, &str)] = &[
+    ("account-saved", "Akun '{name}' disimpan"),
+    ("account-switched", "Beralih ke akun '{name}'"),
+    ("account-deleted", "Akun '{name}' dihapus"),
+    (
+        "account-registered",
+        "Akun '{name}' didaftarkan dari {path}",
+    ),
+    (
+        "add-login-hint",
+        "Masuk dengan akun baru, lalu tutup Claude Code untuk menyimpannya sebagai '{name}'.",
+    ),
+    (
+        "add-login-failed",
+        "Tidak ada login baru untuk disimpan: login tidak selesai atau tidak meninggalkan kredensial",
+    ),
+    (
+        "add-unsaved-login",
+        "Login saat ini belum disimpan sebagai akun; simpan dulu agar tidak hilang",
+    ),
+    (
+        "add-linked",
+        "Direktori Claude ditautkan ke snapshot '{name}'; beralih ke akun itu dengan --mode copy dulu",
+    ),
+    ("add-done", "'{name}' ditambahkan; '{previous}' aktif lagi"),
+    ("add-done-live", "'{name}' ditambahkan, sekarang akun aktif"),
+    (
+        "logout-unsaved",
+        "Tidak ada akun aktif, jadi konfigurasi aktif tidak tersimpan di mana pun. Tetap hapus?",
+    ),
+    (
+        "logged-out",
+        "Keluar dari '{name}' (tersimpan); `switch -` masuk kembali",
+    ),
+    ("logged-out-untracked", "Sudah keluar"),
+    (
+        "account-deleted-kept",
+        "Akun '{name}' dihapus dari daftar; berkasnya tetap ada di {path}",
+    ),
+    (
+        "account-renamed",
+        "Akun '{old}' diganti namanya menjadi '{new}'",
+    ),
+    ("account-copied", "Akun '{source}' disalin ke '{name}'"),
+    (
+        "config-empty",
+        "Belum ada pengaturan; semuanya memakai nilai bawaan",
+    ),
+    ("config-invalid", "Nilai untuk '{key}' tidak valid"),
+    (
+        "config-comments-dropped",
+        "Catatan: settings.toml ditulis ulang tanpa komentarnya",
+    ),
+    (
+        "config-env-overrides",
+        "Catatan: {var} diatur dan lebih diutamakan daripada '{key}'",
+    ),
+    (
+        "config-editor-failed",
+        "{editor} keluar dengan galat; pengaturan tidak diperiksa",
+    ),
+    (
+        "template-saved",
+        "Template '{name}' disimpan ({count} berkas)",
+    ),
+    (
+        "template-empty",
+        "Tidak ada yang bisa disimpan di template '{name}': tidak ada settings.json, CLAUDE.md, atau server MCP",
+    ),
+    ("template-not-found", "Template '{name}' tidak ditemukan"),
+    (
+        "template-invalid-name",
+        "'{name}' tidak bisa dipakai sebagai nama template",
+    ),
+    (
+        "template-applied",
+        "Template '{name}' diterapkan ke konfigurasi aktif; login tidak berubah",
+    ),
+    (
+        "template-applied-save",
+        "Jalankan `claude-account-switcher save {account}` untuk menyimpannya di '{account}'",
+    ),
+    ("template-deleted", "Template '{name}' dihapus"),
+    ("no-templates", "Belum ada template yang disimpan"),
+    ("account-imported", "Akun '{name}' diimpor"),
+    (
+        "account-dir-not-found",
+        "Direktori akun tidak ditemukan: {path}",
+    ),
+    ("no-accounts", "Tidak ada akun tersimpan."),
+    ("no-active-account", "Tidak ada akun aktif"),
+    ("accounts-header", "Akun Claude Code:"),
+    ("saved-at", "disimpan: {time}"),
+    ("group-summary-one", "{group} (1 akun, {size})"),
+    ("group-summary-many", "{group} ({count} akun, {size})"),
+    ("no-namespace", "(tanpa namespace)"),
+    ("no-tag", "(tanpa tag)"),
+    ("no-identity", "(belum masuk)"),
+    ("no-tagged-accounts", "Tidak ada akun dengan tag {tags}"),
+    (
+        "no-stale-accounts",
+        "Tidak ada akun yang disimpan lebih dari {days} hari lalu atau yang login-nya hampir kedaluwarsa",
+    ),
+    ("tag-added", "'{name}' diberi tag {tag}"),
+    ("tag-already", "'{name}' sudah memiliki tag {tag}"),
+    ("tag-removed", "Tag {tag} dihapus dari '{name}'"),
+    ("tag-not-set", "'{name}' tidak memiliki tag {tag}"),
+    ("alias-set", "'{alias}' sekarang mewakili '{name}'"),
+    ("alias-removed", "Alias '{alias}' (untuk '{name}') dihapus"),
+    ("alias-unknown", "Tidak ada alias '{alias}'"),
+    ("limit-recorded", "'{name}' terkena batas hingga {time}"),
+    ("limit-cleared", "'{name}' memiliki kuota lagi"),
+    (
+        "rotate-unknown",
+        "Melewati '{name}' dalam rotasi: akun tidak ada",
+    ),
+    (
+        "rotate-skip-limited",
+        "Melewati '{name}': terkena batas hingga {time}",
+    ),
+    (
+        "rotate-skip-expired",
+        "Melewati '{name}': tokennya kedaluwarsa {time}",
+    ),
+    ("rotate-empty", "Tidak ada akun untuk dirotasi"),
+    ("suggest-available", "siap"),
+    ("suggest-limited", "terkena batas, tersisa {left}"),
+    (
+        "suggest-best",
+        "Paling baik dipakai sekarang: '{name}' (beralih dengan `suggest --switch`)",
+    ),
+    (
+        "suggest-already-current",
+        "'{name}' sudah aktif dan merupakan pilihan terbaik",
+    ),
+    ("suggest-none", "Tidak ada akun yang bisa dipakai sekarang"),
+    ("label-state", "status"),
+    (
+        "rotate-none",
+        "Tidak ada akun lain dalam rotasi yang masih memiliki kuota",
+    ),
+    (
+        "limit-no-current",
+        "Tidak ada akun aktif; sebutkan akun yang terkena batas",
+    ),
+    (
+        "alias-number",
+        "'{alias}' tidak bisa menjadi alias: angka memilih akun menurut urutannya di `list`",
+    ),
+    (
+        "alias-is-account",
+        "'{alias}' sudah menjadi nama sebuah akun",
+    ),
+    (
+        "tag-invalid",
+        "'{tag}' tidak bisa menjadi tag: tag tidak boleh kosong atau berisi spasi atau koma",
+    ),
+    (
+        "plain-account",
+        "nomor: {number}, nama: {name}, aktif: {current}, disimpan: {time}",
+    ),
+    (
+        "plain-group",
+        "grup: {group}, jumlah akun: {count}, ukuran: {size}",
+    ),
+    ("yes", "ya"),
+    ("no", "tidak"),
+    ("delete-active-warning", "Peringatan: '{name}' sedang aktif"),
+    (
+        "delete-confirm",
+        "Lanjutkan? Sesi aktif Anda akan dikosongkan",
+    ),
+    ("delete-many", "Menghapus {count} akun: {names}"),
+    ("delete-many-confirm", "Hapus akun-akun ini?"),
+    (
+        "pattern-no-match",
+        "Tidak ada akun yang cocok dengan '{pattern}'",
+    ),
+    ("bulk-item-failed", "{name}: {error}"),
+    ("bulk-summary", "{done} dari {total} akun selesai"),
+    ("bulk-failed", "Gagal untuk: {names}"),
+    ("cancelled", "Dibatalkan."),
+    (
+        "prune-unused",
+        "{count} akun tidak dipakai dalam periode retensi:",
+    ),
+    ("last-used", "terakhir dipakai: {time}"),
+    ("prune-confirm", "Hapus akun-akun ini?"),
+    ("prune-kept-accounts", "Akun yang tidak dipakai dibiarkan."),
+    (
+        "prune-would-delete",
+        "Akan menghapus {count} akun yang tidak dipakai",
+    ),
+    (
+        "prune-would-remove-versions",
+        "Akan menghapus {count} versi lama, membebaskan {size}",
+    ),
+    (
+        "prune-would-strip",
+        "Akan membuang {count} berkas ({size}) dari snapshot tersimpan",
+    ),
+    ("prune-deleted", "{count} akun yang tidak dipakai dihapus"),
+    (
+        "prune-removed-versions",
+        "{count} versi lama dihapus, membebaskan {size}",
+    ),
+    (
+        "prune-stripped",
+        "{count} berkas ({size}) dibuang dari snapshot tersimpan",
+    ),
+    ("prune-nothing", "Tidak ada yang perlu dipangkas."),
+    (
+        "diff-no-current",
+        "Tidak ada akun aktif; sebutkan akun yang ingin dibandingkan.",
+    ),
+    ("diff-none", "~/.claude sama dengan snapshot '{name}'."),
+    (
+        "diff-summary",
+        "{count} berkas berbeda dari snapshot '{name}'.",
+    ),
+    (
+        "diff-accounts-none",
+        "Snapshot '{name}' dan '{other}' sama.",
+    ),
+    (
+        "diff-accounts-summary",
+        "{count} berkas berbeda antara '{name}' dan '{other}'.",
+    ),
+    ("diff-added", "ditambahkan: {path}"),
+    ("diff-removed", "dihapus: {path}"),
+    ("diff-modified", "diubah: {path}"),
+    (
+        "gc-removed",
+        "{count} berkas tersimpan yang tidak terpakai dihapus, {size} dibebaskan",
+    ),
+    (
+        "gc-shared",
+        "{count} berkas ({size}) ternyata sudah disimpan oleh snapshot lain",
+    ),
+    (
+        "gc-dry-run",
+        "Akan menghapus {count} berkas tersimpan yang tidak terpakai, membebaskan {size}",
+    ),
+    (
+        "gc-no-orphans",
+        "Setiap direktori di penyimpanan dimiliki sebuah akun",
+    ),
+    (
+        "gc-orphans",
+        "{count} direktori di penyimpanan tidak dipakai akun mana pun:",
+    ),
+    (
+        "gc-orphans-confirm",
+        "Pindahkan ke tempat sampah? Direktori itu bisa dikembalikan sebagai akun dengan `trash restore`.",
+    ),
+    (
+        "gc-adopt-confirm",
+        "Daftarkan sebagai akun dengan nama direktorinya?",
+    ),
+    ("gc-orphans-kept", "Dibiarkan di tempatnya"),
+    ("gc-orphans-dry-run", "Uji coba: tidak ada yang dipindahkan"),
+    ("gc-orphan-trashed", "{path} dipindahkan ke tempat sampah"),
+    (
+        "switch-unsaved",
+        "{count} berkas di ~/.claude berubah sejak '{name}' disimpan (lihat `diff`). Beralih dengan --auto-save untuk menyimpannya ke '{name}' terlebih dahulu, atau --force untuk membuangnya.",
+    ),
+    (
+        "manifest-failed",
+        "Peringatan: tidak dapat mencatat berkas yang disimpan: {error}",
+    ),
+    (
+        "run-no-current",
+        "Tidak ada akun aktif untuk kembali. Simpan konfigurasi saat ini dengan `save <nama>` terlebih dahulu.",
+    ),
+    (
+        "no-previous",
+        "Tidak ada akun yang aktif sebelum akun ini; `switch -` memerlukan perpindahan sebelumnya",
+    ),
+    (
+        "run-restore-failed",
+        "Gagal kembali ke '{name}'; jalankan `switch {name}` untuk memulihkannya",
+    ),
+    ("info-current", "(aktif)"),
+    ("info-unknown", "tidak diketahui"),
+    ("info-never", "belum pernah"),
+    ("label-name", "nama"),
+    ("label-current", "aktif"),
+    ("label-email", "email"),
+    ("label-organization", "organisasi"),
+    ("label-plan", "paket"),
+    ("label-saved", "disimpan"),
+    ("label-last-used", "terakhir dipakai"),
+    ("label-path", "lokasi"),
+    ("label-protected", "dilindungi"),
+    ("label-locked", "dikunci"),
+    ("label-external", "eksternal"),
+    ("label-tags", "tag"),
+    ("label-switches", "peralihan"),
+    ("label-note", "catatan"),
+    ("label-token", "token"),
+    ("label-unsaved", "belum disimpan"),
+    ("label-expires", "kedaluwarsa"),
+    ("label-size", "ukuran"),
+    ("label-mode", "mode"),
+    ("label-phase", "tahap"),
+    ("label-time", "waktu"),
+    ("label-calls", "kali"),
+    ("label-speed", "kecepatan"),
+    ("label-encrypted", "terenkripsi"),
+    ("label-flags", "tanda"),
+    ("label-files", "berkas"),
+    ("label-number", "nomor"),
+    ("label-limited", "dibatasi s/d"),
+    ("label-aliases", "alias"),
+    ("label-account", "akun"),
+    ("label-env", "lingkungan"),
+    ("label-claude-version", "claude code"),
+    ("label-pinned", "disematkan ke"),
+    ("info-size", "{size} dalam {files} berkas"),
+    ("info-token-expires", "{time} (dalam {left})"),
+    ("info-token-expired", "kedaluwarsa {time}"),
+    ("info-unsaved", "{count} berkas berubah"),
+    ("status-expires-in", "kedaluwarsa dalam {time}"),
+    ("status-expired", "kedaluwarsa"),
+    ("restore-file", "{path} dari '{name}' dipulihkan ke {dest}"),
+    ("restore-missing", "'{name}' tidak memiliki {path}"),
+    (
+        "file-bad-path",
+        "{path} bukan jalur di dalam direktori Claude",
+    ),
+    (
+        "file-credentials",
+        "{path} menyimpan login, yang hanya berpindah bersama akunnya; beralihlah ke akun itu untuk memakainya",
+    ),
+    (
+        "merge-same-account",
+        "Tidak bisa menggabungkan '{name}' ke dirinya sendiri",
+    ),
+    (
+        "merge-copied",
+        "{path}: disalin dari '{source}' ke '{dest}'",
+    ),
+    ("merge-same", "{path} sudah sama di '{name}'"),
+    ("merge-kept", "{path}: yang ada di '{name}' dipertahankan"),
+    ("merge-would-copy", "{path}: akan disalin ke '{name}'"),
+    (
+        "merge-would-replace",
+        "{path}: akan menggantikan yang ada di '{name}'",
+    ),
+    (
+        "merge-confirm",
+        "{path} berbeda di '{dest}'. Ganti dengan yang dari '{source}'?",
+    ),
+    ("merge-live", "Juga ditulis ke {path}"),
+    ("label-accounts", "akun"),
+    ("label-store", "penyimpanan"),
+    ("label-lock", "kunci"),
+    ("label-sync", "sinkron"),
+    ("status-unlocked", "bebas"),
+    ("status-locked", "dipegang oleh {holder}"),
+    ("status-synced", "mutakhir dengan {remote}"),
+    (
+        "status-sync-pending",
+        "{push} untuk push, {pull} untuk pull (per sinkronisasi terakhir)",
+    ),
+    ("status-sync-off", "belum disiapkan"),
+    (
+        "lock-held",
+        "claude-account-switcher lain sedang mengubah akun ({holder}). Coba lagi setelah selesai, atau gunakan --wait untuk menunggunya.",
+    ),
+    (
+        "lock-timeout",
+        "Berhenti menunggu claude-account-switcher lain setelah {seconds} detik ({holder}); gunakan --wait untuk menunggu sampai selesai.",
+    ),
+    (
+        "lock-waiting",
+        "Menunggu claude-account-switcher lain selesai ({holder})...",
+    ),
+    ("lock-holder-unknown", "proses tidak diketahui"),
+    (
+        "capability-symlinks",
+        "Symlink: tersedia, jadi `--mode symlink` menautkan direktori aktif",
+    ),
+    (
+        "capability-no-symlinks",
+        "Symlink: tidak tersedia; `--mode symlink` menyalin sebagai gantinya",
+    ),
+    (
+        "capability-keychain",
+        "Keychain: tersedia untuk `credentials = \"keychain\"`",
+    ),
+    (
+        "capability-no-keychain",
+        "Keychain: tidak tersedia; kredensial tetap di berkas snapshot",
+    ),
+    (
+        "capability-reflink",
+        "Reflink: tersedia; salinan tanpa enkripsi berbagi blok alih-alih menggandakannya",
+    ),
+    (
+        "capability-no-reflink",
+        "Reflink: tidak didukung sistem berkas ini; setiap berkas disalin penuh",
+    ),
+    (
+        "capabilities-cached",
+        "Baru saja diperiksa; disimpan di {path}",
+    ),
+    (
+        "keychain-unavailable",
+        "Keychain tidak tersedia di sini, jadi kredensial tetap di berkas snapshot. Jalankan `doctor --capabilities` setelah tersedia.",
+    ),
+    ("upgrade-changed", "{path}: {contents}, {before} → {after}"),
+    ("upgrade-unchanged", "{path}: {contents}, sudah terbaru"),
+    ("upgrade-backup", "Versi sebelumnya disimpan sebagai {path}"),
+    ("upgrade-accounts", "akun: {count}"),
+    ("upgrade-events", "peristiwa: {count}"),
+    (
+        "upgrade-events-kept",
+        "peristiwa: {count}, baris tak terbaca yang dibiarkan: {kept}",
+    ),
+    ("upgrade-settings", "pengaturan"),
+    (
+        "upgrade-settings-comments",
+        "pengaturan (komentar dihapus; cadangannya menyimpannya)",
+    ),
+    (
+        "upgrade-nothing",
+        "Tidak ada berkas status untuk ditingkatkan.",
+    ),
+    ("upgrade-dry-run", "Uji coba: tidak ada yang ditulis."),
+    (
+        "switch-recovered",
+        "{path} dipulihkan; sebelumnya dipindahkan oleh perpindahan yang terputus",
+    ),
+    ("doctor-ok", "ok"),
+    ("doctor-warning", "peringatan"),
+    ("doctor-problem", "masalah"),
+    ("doctor-fix", "Perbaikan: {fix}"),
+    ("doctor-fixed", "Diperbaiki: {fix}"),
+    ("doctor-live-ok", "Konfigurasi aktif di {path} dapat dibaca"),
+    (
+        "doctor-live-missing",
+        "Tidak ada konfigurasi aktif di {path}",
+    ),
+    (
+        "doctor-live-missing-fix",
+        "jalankan `claude` lalu masuk, atau pindah ke akun tersimpan",
+    ),
+    (
+        "doctor-live-dangling",
+        "{path} menaut ke snapshot yang sudah tidak ada",
+    ),
+    (
+        "doctor-live-dangling-fix",
+        "pindah ke salah satu akun untuk mengganti tautannya",
+    ),
+    (
+        "doctor-live-unreadable",
+        "Tidak dapat membaca {path}: {error}",
+    ),
+    (
+        "doctor-live-unreadable-fix",
+        "pastikan direktori itu milik Anda dan dapat dibaca",
+    ),
+    ("doctor-config-ok", "Berkas akun valid"),
+    (
+        "doctor-config-invalid",
+        "{path} tidak dapat dibaca: {error}",
+    ),
+    (
+        "doctor-config-invalid-fix",
+        "pindahkan ke {path} dan daftarkan ulang snapshot yang tersimpan",
+    ),
+    (
+        "doctor-account-missing",
+        "Snapshot '{name}' tidak ada di {path}",
+    ),
+    ("doctor-account-missing-fix", "hapus akun dari daftar"),
+    (
+        "doctor-credentials-expired",
+        "Login tersimpan '{name}' kedaluwarsa pada {time}",
+    ),
+    (
+        "doctor-credentials-expired-fix",
+        "pindah ke '{name}', masuk lagi jika Claude Code memintanya, lalu simpan",
+    ),
+    (
+        "doctor-current-unknown",
+        "Akun aktif '{name}' tidak ada di daftar",
+    ),
+    (
+        "doctor-current-unknown-fix",
+        "lupakan; simpan atau pindah berikutnya menetapkan yang baru",
+    ),
+    (
+        "doctor-orphan",
+        "{path} ada di penyimpanan tetapi tidak dipakai akun mana pun",
+    ),
+    ("doctor-orphan-fix", "daftarkan sebagai akun '{name}'"),
+    (
+        "doctor-orphan-name-fix",
+        "namanya tidak bisa dipakai akun; adopsi dengan `adopt {path} --name <nama>`",
+    ),
+    ("doctor-permissions", "{path} dapat dibaca pengguna lain"),
+    (
+        "doctor-permissions-fix",
+        "batasi hanya untuk pengguna Anda (0600)",
+    ),
+    ("doctor-healthy", "Tidak ada masalah yang perlu diperbaiki."),
+    (
+        "doctor-outstanding",
+        "{count} masalah perlu ditangani; `doctor --fix` memperbaiki yang bisa.",
+    ),
+    (
+        "crash-report-written",
+        "Laporan crash disimpan di {path}; isinya tanpa kredensial, jadi bisa dilampirkan ke issue.",
+    ),
+    (
+        "crash-report-failed",
+        "Gagal menyimpan laporan crash: {error}",
+    ),
+    (
+        "crash-recovery-snapshot",
+        "Snapshot '{name}' mungkin tidak lengkap. Konfigurasi aktif tidak berubah; simpan lagi, atau jalankan `claude-account-switcher rollback {name}` untuk kembali ke simpanan sebelumnya.",
+    ),
+    (
+        "crash-recovery-live",
+        "Konfigurasi aktif mungkin baru sebagian diganti. Jalankan `claude-account-switcher switch {name}` untuk memulihkannya.",
+    ),
+    (
+        "crash-recovery-version",
+        "Rollback '{name}' terputus. Periksa `claude-account-switcher history {name}` dan jalankan `claude-account-switcher rollback {name}` lagi jika snapshot-nya hilang.",
+    ),
+    (
+        "crash-recovery-config",
+        "File sudah ditulis tetapi accounts.json mungkin belum sesuai. Periksa `claude-account-switcher list` sebelum berpindah lagi.",
+    ),
+    (
+        "init-settings-exist",
+        "{path} sudah ada; ubah dengan jawaban Anda?",
+    ),
+    (
+        "init-store",
+        "Akun disimpan di {path} (atur XDG_DATA_HOME untuk memindahkannya).",
+    ),
+    (
+        "init-system",
+        "Simpan akun di penyimpanan sistem di {path}, dibagi dengan pengguna lain, bukan milik Anda sendiri?",
+    ),
+    ("init-claude-dir", "Direktori Claude Code [{path}]: "),
+    (
+        "init-credentials-only",
+        "Hanya tukar login, dengan pengaturan, proyek dan riwayat dipakai bersama semua akun?",
+    ),
+    (
+        "init-keychain",
+        "Simpan login di keychain OS, bukan di berkas?",
+    ),
+    (
+        "init-encrypt",
+        "Enkripsi akun tersimpan dengan frasa sandi?",
+    ),
+    (
+        "init-first-account",
+        "Simpan login saat ini sebagai (kosongkan untuk melewati): ",
+    ),
+    (
+        "init-done",
+        "Pengaturan ditulis ke {path}; ubah nanti dengan `config`.",
+    ),
+    ("migrate-schema", "accounts.json: skema {from} → {to}"),
+    (
+        "migrate-current",
+        "accounts.json: skema {version}, sudah terbaru",
+    ),
+    ("account-adopted", "'{name}' diambil dari {path}"),
+    ("adopt-skipped", "{path} dibiarkan: {error}"),
+    (
+        "adopt-copied",
+        "{path} disalin ke penyimpanan sebagai '{name}'",
+    ),
+    ("adopt-tracked", "{path} sudah menjadi snapshot '{name}'"),
+    (
+        "adopt-dir-taken",
+        "{path} sudah ada; pilih nama lain dengan --name",
+    ),
+    (
+        "adopt-none",
+        "Setiap direktori di penyimpanan sudah dimiliki akun.",
+    ),
+    (
+        "legacy-not-found",
+        "Tidak ada akun dari versi sebelumnya di {path}",
+    ),
+    (
+        "legacy-snapshot-missing",
+        "'{name}' dilewati: snapshot-nya tidak ada di {path}",
+    ),
+    ("legacy-migrated-one", "1 akun dipindahkan dari {path}"),
+    (
+        "legacy-migrated-many",
+        "{count} akun dipindahkan dari {path}",
+    ),
+    (
+        "legacy-left-behind",
+        "Akun yang tidak dipindahkan masih tercatat di {path}",
+    ),
+    (
+        "legacy-hint",
+        "Akun yang disimpan versi sebelumnya ada di {path}; jalankan `claude-account-switcher migrate-legacy` untuk memindahkannya ke sini.",
+    ),
+    (
+        "no-versions",
+        "Tidak ada snapshot sebelumnya dari '{name}' untuk dikembalikan",
+    ),
+    (
+        "version-not-found",
+        "Tidak ada snapshot '{id}' dari '{name}'; lihat `history {name}`",
+    ),
+    ("rolled-back", "'{name}' dikembalikan ke snapshot {id}"),
+    (
+        "rolled-back-live",
+        "Sudah dipulihkan ke konfigurasi aktif, karena ini akun yang aktif",
+    ),
+    (
+        "reserved-name",
+        "'{name}' dicadangkan untuk keperluan switcher sendiri",
+    ),
+    ("name-empty", "Nama akun tidak boleh kosong"),
+    ("name-too-long", "'{name}' lebih dari {max} karakter"),
+    (
+        "name-bad-char",
+        "'{name}' mengandung '{char}'. Nama boleh memakai huruf, angka dan . _ - @ +, dengan / di antara namespace",
+    ),
+    (
+        "name-bad-part",
+        "'{name}' punya bagian yang kosong atau diawali atau diakhiri '.'",
+    ),
+    (
+        "name-device",
+        "'{name}' mengandung '{part}', yang dipakai Windows untuk perangkat",
+    ),
+    ("version-usage", "{files} berkas, {size}"),
+    ("account-usage", "{files} berkas, {size}"),
+    ("plain-account-usage", ", berkas: {files}, ukuran: {size}"),
+    ("plain-account-tags", ", tag: {tags}"),
+    ("plain-account-aliases", ", alias: {aliases}"),
+    ("plain-account-limited", ", terkena batas selama: {left}"),
+    ("account-limited", "(terkena batas, tersisa {left})"),
+    ("plain-account-stale", ", usang: {days} hari"),
+    ("account-stale", "(disimpan {days} hari lalu)"),
+    ("plain-account-expiring", ", login kedaluwarsa: {time}"),
+    ("account-expiring", "(login kedaluwarsa {time})"),
+    ("plain-account-expired", ", login sudah kedaluwarsa: {time}"),
+    ("account-expired", "(login sudah kedaluwarsa {time})"),
+    ("account-aliases", "alias {aliases}"),
+    ("plain-account-note", ", catatan: {note}"),
+    (
+        "note-none",
+        "'{name}' belum punya catatan. Tambahkan dengan `note {name} <teks>` atau `--edit`",
+    ),
+    ("note-saved", "Catatan '{name}' disimpan"),
+    ("note-cleared", "Catatan '{name}' dihapus"),
+    (
+        "note-editor-failed",
+        "{editor} keluar dengan galat; catatan tidak diubah",
+    ),
+    (
+        "size-account",
+        "{files} berkas, {size} (versi: {version_files} berkas, {version_size})",
+    ),
+    (
+        "plain-size",
+        "nama: {name}, berkas: {files}, ukuran: {size}, berkas versi: {version_files}, ukuran versi: {version_size}",
+    ),
+    (
+        "size-store",
+        "Total penyimpanan: {size} dalam {files} berkas",
+    ),
+    ("size-measuring", "Mengukur snapshot"),
+    ("copy-progress", "Menyalin"),
+    (
+        "plain-version",
+        "id: {id}, disimpan: {time}, berkas: {files}, ukuran: {size}",
+    ),
+    ("export-passphrase-prompt", "Frasa sandi arsip: "),
+    ("export-passphrase-repeat", "Ulangi frasa sandi arsip: "),
+    ("export-created-one", "Akun '{name}' diekspor ke {path}"),
+    ("export-created-many", "{count} akun diekspor ke {path}"),
+    (
+        "import-name-needs-single",
+        "--name hanya berlaku untuk arsip berisi satu akun; arsip ini berisi {count}",
+    ),
+    (
+        "import-conflict-hint",
+        "Gunakan --on-conflict rename, skip atau replace untuk tetap mengimpornya",
+    ),
+    (
+        "import-skipped",
+        "'{name}' dilewati: akun dengan nama itu sudah ada",
+    ),
+    (
+        "import-renamed",
+        "Akun '{name}' sudah ada; diimpor sebagai '{new_name}'",
+    ),
+    (
+        "import-replace-active",
+        "Tidak bisa mengganti '{name}' selagi menjadi akun aktif; beralihlah ke akun lain dulu",
+    ),
+    ("history-empty", "Belum ada riwayat"),
+    (
+        "plain-history",
+        "waktu: {time}, aksi: {action}, akun: {account}, sumber: {source}",
+    ),
+    ("undo-nothing", "Tidak ada yang bisa dibatalkan"),
+    (
+        "undo-unsupported",
+        "Perubahan terakhir adalah {action}, yang tidak bisa dibatalkan (lihat `rollback`)",
+    ),
+    (
+        "undo-switch-moved",
+        "'{name}' bukan lagi akun aktif, jadi peralihannya tidak bisa dibatalkan",
+    ),
+    (
+        "undo-no-backup",
+        "Konfigurasi yang diganti peralihan terakhir tidak disimpan, jadi tidak bisa dibatalkan",
+    ),
+    (
+        "undo-live-backups-off",
+        "keep-live-backups bernilai 0, jadi konfigurasi yang diganti peralihan tidak disimpan dan peralihan tidak bisa dibatalkan",
+    ),
+    (
+        "live-backups-none",
+        "Tidak ada direktori live yang diganti peralihan yang disimpan",
+    ),
+    (
+        "live-backup-not-found",
+        "Tidak ada direktori live tersimpan '{id}'; lihat `recover-live --list`",
+    ),
+    (
+        "live-recovered",
+        "Direktori live dari {id} dikembalikan; '{name}' aktif lagi",
+    ),
+    (
+        "live-recovered-untracked",
+        "Direktori live dari {id} dikembalikan, yang tidak disimpan sebagai akun mana pun",
+    ),
+    (
+        "plain-live-backup",
+        "id: {id}, akun: {name}, diganti: {time}",
+    ),
+    (
+        "undo-not-in-trash",
+        "'{name}' tidak ada di tempat sampah (lihat `trash list`)",
+    ),
+    ("undo-switched", "Kembali ke akun '{name}'"),
+    (
+        "undo-switched-none",
+        "Konfigurasi sebelum peralihan dikembalikan",
+    ),
+    ("undo-deleted", "Akun '{name}' dipulihkan"),
+    (
+        "undo-renamed",
+        "'{old}' diganti namanya kembali menjadi '{new}'",
+    ),
+    ("trash-empty", "Tempat sampah kosong"),
+    (
+        "sessions-none",
+        "Tidak ada terminal yang memakai akun langsung dari penyimpanan",
+    ),
+    ("session-pid", "pid {pid}"),
+    ("session-since", "sejak {time}"),
+    (
+        "daemon-started",
+        "Daemon dimulai (pid {pid}), mendengarkan di {path}",
+    ),
+    ("daemon-stopped", "Daemon dihentikan"),
+    (
+        "passphrase-unattended",
+        "Penyimpanan terenkripsi dan tidak ada terminal untuk menanyakan frasa sandinya; setel {var}",
+    ),
+    ("daemon-not-running", "Daemon tidak berjalan"),
+    (
+        "daemon-running",
+        "Daemon berjalan (pid {pid}, sejak {time}, {requests} permintaan dilayani), mendengarkan di {path}",
+    ),
+    ("daemon-already-running", "Daemon sudah berjalan"),
+    (
+        "daemon-already-running-pid",
+        "Daemon sudah berjalan (pid {pid})",
+    ),
+    (
+        "daemon-start-failed",
+        "Daemon tidak dapat dimulai; lihat {log} untuk penyebabnya",
+    ),
+    (
+        "daemon-unsupported",
+        "Daemon mendengarkan di soket Unix, yang tidak tersedia di sini",
+    ),
+    (
+        "plain-session",
+        "akun: {name}, dari: {kind}, pid: {pid}, sejak: {time}",
+    ),
+    (
+        "session-conflict",
+        "Peringatan: '{name}' sedang dipakai sebuah terminal (`{kind}`, pid {pid}); keduanya akan menyegarkan login yang sama, dan salah satunya bisa keluar",
+    ),
+    ("deleted-at", "dihapus: {time}"),
+    (
+        "plain-trashed",
+        "nama: {name}, dihapus: {time}, berkas: {files}, ukuran: {size}",
+    ),
+    (
+        "trash-restored",
+        "Akun '{name}' dipulihkan dari tempat sampah",
+    ),
+    (
+        "trash-empty-confirm",
+        "Hapus permanen semua yang ada di tempat sampah?",
+    ),
+    (
+        "trash-emptied",
+        "{count} akun terhapus dibuang dari tempat sampah",
+    ),
+    (
+        "backup-created",
+        "{count} akun dicadangkan ke {path} ({size})",
+    ),
+    ("backup-removed", "Cadangan lama {path} dihapus"),
+    (
+        "backup-external-skipped",
+        "Tidak dicadangkan, karena berkasnya tidak ada di penyimpanan: {names}",
+    ),
+    (
+        "backup-restore-confirm",
+        "Ganti semua akun tersimpan, riwayat, dan pengaturan dengan cadangan ini?",
+    ),
+    (
+        "backup-kept-current",
+        "Penyimpanan sebelumnya dicadangkan ke {path}",
+    ),
+    (
+        "backup-restored",
+        "{count} akun dipulihkan dari cadangan yang dibuat {time}",
+    ),
+    (
+        "sync-needs-encryption",
+        "Hanya penyimpanan terenkripsi yang bisa disinkronkan, agar kredensial tidak pernah keluar dari mesin ini dalam bentuk terbaca. Jalankan `encrypt enable` dulu",
+    ),
+    (
+        "sync-already-set-up",
+        "Sinkronisasi sudah disiapkan di {path}",
+    ),
+    (
+        "sync-set-up",
+        "Sinkronisasi melalui {remote}. Jalankan `sync push` untuk mengirim akun di sini",
+    ),
+    (
+        "sync-not-set-up",
+        "Sinkronisasi belum disiapkan. Jalankan `sync init --remote <git-url>` dulu",
+    ),
+    ("sync-sent", "'{name}' dikirim"),
+    ("sync-removed-there", "'{name}' dihapus dari repositori"),
+    (
+        "sync-removed-here",
+        "'{name}' dipindahkan ke tempat sampah, karena sudah dihapus di tempat lain",
+    ),
+    (
+        "sync-skipped-active",
+        "'{name}' dibiarkan, karena sedang aktif di sini. Beralih ke akun lain lalu pull lagi untuk mengambil versi dari mesin lain",
+    ),
+    (
+        "sync-skipped-external",
+        "'{name}' dibiarkan, karena di sini didaftarkan dengan add-existing",
+    ),
+    (
+        "sync-skipped-locked",
+        "'{name}' dibiarkan, karena dikunci di sini; tarik dengan --force untuk menggantinya",
+    ),
+    (
+        "sync-skipped-locked-remove",
+        "'{name}' disimpan, karena dikunci di sini; tarik dengan --force untuk menghapusnya seperti di tempat lain",
+    ),
+    ("sync-up-to-date", "Sudah terbaru"),
+    (
+        "sync-passphrase-prompt",
+        "Frasa sandi penyimpanan yang disinkronkan: ",
+    ),
+    (
+        "sync-wrong-passphrase",
+        "Tidak bisa membuka penyimpanan yang disinkronkan, yang disiapkan di mesin lain",
+    ),
+    (
+        "sync-unsealed",
+        "Menolak menyinkronkan '{path}': berkas tidak terenkripsi",
+    ),
+    ("sync-git-failed", "git {command} gagal: {output}"),
+    (
+        "sync-git-missing",
+        "Gagal menjalankan git. Apakah sudah terpasang?",
+    ),
+    (
+        "sync-bad-remote",
+        "'{remote}' tidak menyebut bucket: gunakan s3://bucket atau s3://bucket/prefix",
+    ),
+    (
+        "sync-s3-credentials",
+        "Atur AWS_ACCESS_KEY_ID dan AWS_SECRET_ACCESS_KEY untuk sinkronisasi melalui S3",
+    ),
+    (
+        "sync-conflict",
+        "Mesin lain melakukan push pada saat yang sama. Jalankan `sync push` lagi",
+    ),
+    (
+        "sync-object-missing",
+        "'{key}' di penyimpanan yang disinkronkan hilang atau rusak. Lakukan push dari mesin dengan salinan yang baik",
+    ),
+    (
+        "sync-unsafe-path",
+        "Indeks penyimpanan yang disinkronkan memiliki path di luar direktori sinkronisasi: {path}",
+    ),
+    ("sync-http-unreachable", "Tidak bisa menghubungi {url}"),
+    (
+        "sync-http-failed",
+        "{method} {url} gagal dengan {status}: {output}",
+    ),
+    ("plain-history-previous", ", sebelumnya: {name}"),
+    ("plain-history-files", ", berkas: {count}"),
+    ("history-previous", " dari {name}"),
+    ("history-files", ", {count} berkas"),
+    ("binding-written", "{path} diikat ke akun '{name}'"),
+    (
+        "binding-unknown-account",
+        "{path} mengikat ke akun '{name}', yang tidak ada",
+    ),
+    (
+        "binding-mismatch",
+        "Peringatan: proyek ini terikat ke '{name}' tetapi '{current}' yang aktif",
+    ),
+    (
+        "binding-switching",
+        "Beralih ke '{name}' (diikat oleh {path})",
+    ),
+    ("project-set", "{path} dipetakan ke akun '{name}'"),
+    ("project-unset", "Pemetaan {path} (ke '{name}') dihapus"),
+    ("project-unknown", "{path} tidak dipetakan ke akun mana pun"),
+    (
+        "project-none",
+        "Tidak ada yang mengikat {path} ke sebuah akun; petakan dengan `project set` atau `bind`",
+    ),
+    (
+        "projects-none",
+        "Tidak ada direktori yang dipetakan ke akun",
+    ),
+    ("plain-project", "akun: {name}, jalur: {path}"),
+    ("tui-title", " Akun Claude ({count}) "),
+    ("tui-col-account", "Akun"),
+    ("tui-col-email", "Email"),
+    ("tui-col-plan", "Paket"),
+    ("tui-col-expires", "Token berakhir"),
+    ("tui-col-last-used", "Terakhir dipakai"),
+    ("tui-col-size", "Ukuran"),
+    ("tui-never", "belum pernah"),
+    ("tui-expired", "kedaluwarsa"),
+    (
+        "tui-details",
+        "Disimpan {saved}, {files} berkas, token {token}",
+    ),
+    (
+        "tui-help",
+        "↑/↓ pindah  Enter beralih  r ganti nama  d hapus  b cadangkan akun aktif  q keluar",
+    ),
+    ("tui-confirm-delete", "Hapus '{name}' beserta snapshot-nya?"),
+    ("tui-rename", "Nama baru untuk '{name}': "),
+    ("tui-no-active", "Tidak ada akun aktif untuk dicadangkan"),
+    ("tui-press-enter", "Tekan Enter untuk kembali ke dasbor"),
+    (
+        "account-protected",
+        "Beralih ke '{name}' kini memerlukan konfirmasi",
+    ),
+    (
+        "account-unprotected",
+        "'{name}' tidak lagi memerlukan konfirmasi",
+    ),
+    (
+        "account-pinned",
+        "'{name}' kini hanya bisa dipakai dengan Claude Code {version}",
+    ),
+    (
+        "account-unpinned",
+        "'{name}' tidak lagi disematkan ke versi Claude Code",
+    ),
+    (
+        "pin-no-claude",
+        "Claude Code tidak terpasang, atau tidak menyebutkan versinya; berikan dengan --claude-version",
+    ),
+    (
+        "claude-version-unknown",
+        "'{name}' disematkan ke Claude Code {version}, tetapi versi yang terpasang tidak dapat diketahui",
+    ),
+    (
+        "claude-version-drift",
+        "'{name}' disimpan dengan Claude Code {saved}, tetapi yang terpasang {installed}; konfigurasinya mungkin tidak terbaca dengan cara yang sama",
+    ),
+    (
+        "protected-warning",
+        "Peringatan: '{name}' adalah akun yang dilindungi",
+    ),
+    ("protected-confirm", "Beralih ke akun ini?"),
+    (
+        "account-locked",
+        "'{name}' dikunci: menyimpan di atasnya, menghapus, dan memangkasnya kini memerlukan --force",
+    ),
+    ("account-unlocked", "'{name}' tidak lagi dikunci"),
+    (
+        "account-locked-not-saved",
+        "'{name}' dikunci; snapshot-nya dibiarkan seperti semula",
+    ),
+    ("protected-type-name", "Ketik nama akun untuk konfirmasi: "),
+    (
+        "protected-name-mismatch",
+        "Nama yang diketik tidak cocok dengan '{name}'; batal beralih",
+    ),
+    (
+        "pick-prompt",
+        "Beralih ke [1-{count}, kosongkan untuk batal]: ",
+    ),
+    (
+        "pick-invalid",
+        "Tidak ada akun yang cocok dengan '{answer}'",
+    ),
+    ("pick-no-match", "(tidak ada akun yang cocok)"),
+    ("pick-query", "{count}/{total} > "),
+    ("share-created", "Akun '{name}' dibagikan ke {path}"),
+    ("share-passphrase", "Frasa sandi: {passphrase}"),
+    ("share-expires", "Kedaluwarsa: {time}"),
+    ("passphrase-prompt", "Frasa sandi: "),
+    ("store-passphrase-prompt", "Frasa sandi penyimpanan: "),
+    (
+        "store-passphrase-repeat",
+        "Ulangi frasa sandi penyimpanan: ",
+    ),
+    ("passphrase-mismatch", "Frasa sandi tidak cocok"),
+    ("passphrase-empty", "Frasa sandi tidak boleh kosong"),
+    (
+        "encryption-enabled",
+        "{files} berkas di {accounts} akun dienkripsi",
+    ),
+    (
+        "encryption-disabled",
+        "{files} berkas di {accounts} akun didekripsi",
+    ),
+    ("encryption-already-enabled", "Enkripsi sudah aktif"),
+    ("encryption-not-enabled", "Enkripsi belum aktif"),
+    (
+        "symlink-copying",
+        "Peringatan: {reason}, beralih dengan menyalin",
+    ),
+    ("direct-encrypted", "penyimpanan akun terenkripsi"),
+    ("direct-keychain", "kredensial disimpan di keychain"),
+    (
+        "direct-dedup",
+        "snapshot berbagi berkas satu sama lain (dedup)",
+    ),
+    ("direct-compressed", "snapshot disimpan terkompresi"),
+    (
+        "direct-credentials-scope",
+        "hanya login yang disimpan per akun",
+    ),
+    (
+        "env-current",
+        "'{name}' adalah akun aktif, sudah ada di {path}; `env` dan `shell` untuk akun lainnya",
+    ),
+    (
+        "env-unusable",
+        "'{name}' tidak dapat dipakai langsung dari penyimpanan: {reason}",
+    ),
+    (
+        "shell-started",
+        "Memulai shell dengan akun '{name}'; keluar untuk meninggalkannya",
+    ),
+    (
+        "import-dry-run",
+        "Akun '{name}' akan diimpor ({files} berkas, {size}); tidak ada yang ditulis",
+    ),
+    ("inspect-account", "Akun:        {name}"),
+    ("inspect-saved", "Disimpan:    {time}"),
+    ("inspect-created", "Dibuat:      {time}"),
+    ("inspect-expired", "Kedaluwarsa: {time} (sudah lewat)"),
+    ("inspect-never-expires", "Kedaluwarsa: tidak pernah"),
+    ("inspect-files", "Berkas:      {files} ({size})"),
+    ("inspect-plan", "Paket:       {plan}"),
+    ("inspect-token", "Token:       {token}"),
+    ("inspect-token-expires", "Token habis: {time}"),
+    ("inspect-no-credentials", "Login:       tidak ada"),
+    (
+        "grep-skip-missing",
+        "Peringatan: '{name}' dilewati, direktori akun tidak ditemukan",
+    ),
+    ("no-matches", "Tidak ada yang cocok."),
+    ("label-archived", "diarsipkan"),
+    (
+        "token-none",
+        "Akun '{name}' tidak menyimpan token OAuth atau kunci API",
+    ),
+    (
+        "token-copied",
+        "Token '{name}' disalin; papan klip dikosongkan dalam {seconds} detik",
+    ),
+    (
+        "token-masked-hint",
+        "Disamarkan; gunakan --reveal untuk mencetaknya utuh, atau --copy untuk menyalinnya ke papan klip.",
+    ),
+    (
+        "clipboard-unavailable",
+        "Alat papan klip tidak ditemukan (pbcopy, wl-copy, xclip, xsel, atau clip)",
+    ),
+    ("plain-account-archived", ", diarsipkan"),
+    ("archive-already", "Akun '{name}' sudah diarsipkan"),
+    (
+        "archive-active",
+        "Akun '{name}' sedang aktif; beralih ke akun lain sebelum mengarsipkannya",
+    ),
+    (
+        "archive-external",
+        "Akun '{name}' ditambahkan dengan add-existing; direktorinya dikelola di tempat lain, jadi tidak diarsipkan",
+    ),
+    (
+        "archive-nested",
+        "Snapshot akun lain ada di dalam '{name}'; arsipkan atau pindahkan dulu",
+    ),
+    ("archive-not-archived", "Akun '{name}' tidak diarsipkan"),
+    ("account-archived", "Akun '{name}' diarsipkan ({size})"),
+    ("account-unarchived", "Akun '{name}' dikeluarkan dari arsip"),
+    ("unarchiving", "Mengeluarkan akun '{name}' dari arsip..."),
+    (
+        "encryption-archived",
+        "Keluarkan akun-akun ini dari arsip dulu: {names}",
+    ),
+    (
+        "checkpoint-bad-label",
+        "'{label}' tidak bisa menjadi nama checkpoint: kosong atau diawali '.'",
+    ),
+    (
+        "checkpoint-no-live",
+        "Tidak ada konfigurasi live di {path} untuk dijadikan checkpoint",
+    ),
+    (
+        "checkpoint-exists",
+        "Checkpoint '{label}' sudah ada; gunakan --force untuk menggantinya",
+    ),
+    ("checkpoint-saved", "Checkpoint '{label}' disimpan ({size})"),
+    ("checkpoint-pruned", "Checkpoint lama '{label}' dihapus"),
+    (
+        "checkpoint-not-found",
+        "Tidak ada checkpoint '{label}'; lihat `checkpoint list`",
+    ),
+    (
+        "checkpoint-restored",
+        "Checkpoint '{label}' dipulihkan; konfigurasi yang digantinya disimpan untuk `recover-live`",
+    ),
+    ("checkpoint-deleted", "Checkpoint '{label}' dihapus"),
+    ("checkpoints-none", "Belum ada checkpoint yang disimpan"),
+    (
+        "plain-checkpoint",
+        "label: {label}, akun: {name}, disimpan: {time}",
+    ),
+    (
+        "preview-archived",
+        "Akun '{name}' diarsipkan; keluarkan dari arsip untuk melihat pratinjau peralihan",
+    ),
+    ("preview-heading", "Beralih ke '{name}' mengubah ~/.claude:"),
+    ("preview-identity", "Masuk sebagai: {from} -> {to}"),
+    (
+        "preview-identity-same",
+        "Masuk sebagai: {identity} (tidak berubah)",
+    ),
+    ("preview-summary", "{count} berkas berubah."),
+    ("preview-confirm", "Beralih ke '{name}'?"),
+    (
+        "var-invalid",
+        "'{var}' bukan NAMA=NILAI dengan nama variabel yang valid",
+    ),
+    ("var-set", "{var} diatur untuk '{name}'"),
+    ("var-unset", "{var} dihapus dari '{name}'"),
+    ("var-not-set", "'{name}' tidak punya variabel {var}"),
+    (
+        "vars-none",
+        "'{name}' tidak punya variabel; tambahkan dengan `var set`",
+    ),
+    (
+        "system-missing",
+        "Penyimpanan sistem {path} tidak ada; administrator dapat membuatnya untuk sebuah grup dengan `install -d -m 2750 -g GROUP {path}`",
+    ),
+    (
+        "system-read-only",
+        "Tidak dapat mengubah akun penyimpanan sistem di {path}; minta administrator untuk melakukan perubahan ini",
+    ),
+    (
+        "system-access-invalid",
+        "Aturan akses penyimpanan sistem di {path} tidak valid: {error}",
+    ),
+    (
+        "system-account-read-only",
+        "'{name}' dibagikan dari penyimpanan sistem dan hanya administrator yang dapat menyimpan di atasnya",
+    ),
+    (
+        "system-not-saved",
+        "'{name}' dibagikan dari penyimpanan sistem; perubahannya tidak disimpan",
+    ),
+    (
+        "system-copy-only",
+        "Akun di penyimpanan sistem hanya dapat diaktifkan dengan menyalin",
+    ),
+    ("interrupted", "Terputus sebelum selesai"),
+    (
+        "interrupted-save",
+        "Penyimpanan '{name}' yang dimulai {time} tidak selesai",
+    ),
+    (
+        "interrupted-switch",
+        "Perpindahan ke '{name}' yang dimulai {time} tidak selesai",
+    ),
+    (
+        "interrupted-hint",
+        "Jalankan `claude-account-switcher recover --resume` untuk menyelesaikannya, atau `recover --rollback` untuk membatalkannya",
+    ),
+    ("interrupted-resume-confirm", "Selesaikan sekarang?"),
+    (
+        "interrupted-rollback-confirm",
+        "Batalkan yang sudah dilakukannya saja?",
+    ),
+    (
+        "interrupted-left",
+        "Dibiarkan apa adanya; `claude-account-switcher recover` dapat menanganinya nanti",
+    ),
+    (
+        "recover-none",
+        "Tidak ada penyimpanan atau perpindahan terputus untuk dipulihkan",
+    ),
+    (
+        "recover-nothing-done",
+        "Belum ada yang diubah; tidak ada yang perlu dibatalkan",
+    ),
+    (
+        "recover-save-kept",
+        "Snapshot '{name}' mungkin tidak lengkap dan tidak ada snapshot sebelumnya untuk dikembalikan; periksa dengan `verify {name}`",
+    ),
+    (
+        "recover-snapshot-restored",
+        "Snapshot '{name}' yang sedang digantikan telah dikembalikan",
+    ),
+    (
+        "recover-snapshot-removed",
+        "Snapshot '{name}' yang tidak lengkap telah dihapus",
+    ),
+    (
+        "recover-live-instead",
+        "Konfigurasi live yang digantikan perpindahan itu sudah dipindahkan; lihat `recover-live --list`",
+    ),
+    (
+        "recover-switched-back",
+        "Perpindahan dibatalkan; '{name}' aktif kembali",
+    ),
+    ("recover-switched-back-none", "Perpindahan dibatalkan"),
+    (
+        "snapshot-archived",
+        "'{name}' diarsipkan; keluarkan dari arsip untuk melihat isinya",
+    ),
+    (
+        "snapshot-not-dir",
+        "'{name}' tidak memiliki direktori {path}",
+    ),
+    ("plain-snapshot-entry", "path: {path}, ukuran: {size}"),
+    (
+        "failed-determine-current-directory",
+        "Gagal menentukan direktori saat ini",
+    ),
+    ("debug-bundle-written", "Bundel debug ditulis: {path}"),
+    (
+        "debug-bundle-review",
+        "Rahasia, email, dan direktori home Anda disamarkan; periksa dulu sebelum melampirkannya ke laporan.",
+    ),
+    ("man-written", "{count} halaman man ditulis ke {path}"),
+    (
+        "failed-clear-account-directory",
+        "Gagal mengosongkan direktori akun",
+    ),
+    (
+        "failed-clear-synced-account",
+        "Gagal mengosongkan akun yang disinkronkan",
+    ),
+    (
+        "failed-copy-account-snapshot",
+        "Gagal menyalin snapshot akun",
+    ),
+    (
+        "failed-copy-configuration-files",
+        "Gagal menyalin berkas konfigurasi",
+    ),
+    (
+        "failed-copy-file-from-to",
+        "Gagal menyalin berkas dari {from} ke {to}",
+    ),
+    ("failed-copy-store-key", "Gagal menyalin kunci penyimpanan"),
+    ("failed-create", "Gagal membuat {path}"),
+    (
+        "failed-create-account-directory",
+        "Gagal membuat direktori akun",
+    ),
+    (
+        "failed-create-account-storage-directory",
+        "Gagal membuat direktori penyimpanan akun",
+    ),
+    (
+        "failed-create-claude-config-directory",
+        "Gagal membuat direktori konfigurasi Claude",
+    ),
+    (
+        "failed-create-configuration-directory",
+        "Gagal membuat direktori konfigurasi",
+    ),
+    ("failed-create-directory", "Gagal membuat direktori: {path}"),
+    (
+        "failed-create-settings-directory",
+        "Gagal membuat direktori pengaturan",
+    ),
+    (
+        "failed-create-state-directory",
+        "Gagal membuat direktori state",
+    ),
+    ("failed-create-symlink", "Gagal membuat symlink: {path}"),
+    (
+        "failed-create-synced-account",
+        "Gagal membuat akun yang disinkronkan",
+    ),
+    (
+        "failed-create-template-directory",
+        "Gagal membuat direktori templat",
+    ),
+    (
+        "failed-create-trash-directory",
+        "Gagal membuat direktori tempat sampah",
+    ),
+    (
+        "failed-create-versions-directory",
+        "Gagal membuat direktori versi",
+    ),
+    ("failed-delete-template", "Gagal menghapus templat"),
+    (
+        "failed-determine-home-directory",
+        "Gagal menentukan direktori home",
+    ),
+    (
+        "failed-find-switchers-executable",
+        "Gagal menemukan executable switcher",
+    ),
+    (
+        "failed-keep-current-snapshot",
+        "Gagal menyimpan snapshot saat ini",
+    ),
+    (
+        "failed-keep-live-directory",
+        "Gagal menyimpan direktori aktif",
+    ),
+    (
+        "failed-keep-nested-account",
+        "Gagal mempertahankan akun bersarang",
+    ),
+    (
+        "failed-keep-previous-snapshot",
+        "Gagal menyimpan snapshot sebelumnya",
+    ),
+    (
+        "failed-keep-shared-files",
+        "Gagal mempertahankan berkas bersama",
+    ),
+    (
+        "failed-locate-switcher-executable",
+        "Gagal menemukan executable switcher",
+    ),
+    ("failed-move", "Gagal memindahkan {path}"),
+    (
+        "failed-move-account-directory-to-trash",
+        "Gagal memindahkan direktori akun ke tempat sampah",
+    ),
+    (
+        "failed-move-account-store-aside",
+        "Gagal memindahkan penyimpanan akun ke samping",
+    ),
+    (
+        "failed-move-account-versions-to-trash",
+        "Gagal memindahkan versi akun ke tempat sampah",
+    ),
+    (
+        "failed-move-accounts-file-aside",
+        "Gagal memindahkan berkas akun ke samping",
+    ),
+    (
+        "failed-move-current-configuration-aside",
+        "Gagal memindahkan konfigurasi saat ini ke samping",
+    ),
+    (
+        "failed-move-earlier-configuration-into-place",
+        "Gagal mengembalikan konfigurasi sebelumnya ke tempatnya",
+    ),
+    (
+        "failed-move-new-configuration-into-place",
+        "Gagal memindahkan konfigurasi baru ke tempatnya",
+    ),
+    (
+        "failed-open-checksum-list",
+        "Gagal membuka daftar checksum: {path}",
+    ),
+    ("failed-parse", "Gagal mengurai {path}"),
+    (
+        "failed-parse-template-file",
+        "Gagal mengurai berkas templat: {path}",
+    ),
+    (
+        "failed-read-accounts-configuration-file",
+        "Gagal membaca berkas konfigurasi akun",
+    ),
+    ("failed-read-bundle", "Gagal membaca bundle: {path}"),
+    ("failed-read-claude-md", "Gagal membaca CLAUDE.md"),
+    (
+        "failed-read-credentials-file",
+        "Gagal membaca berkas kredensial",
+    ),
+    ("failed-read-directory", "Gagal membaca direktori: {path}"),
+    ("failed-read-file", "Gagal membaca berkas: {path}"),
+    ("failed-read-history-file", "Gagal membaca berkas riwayat"),
+    ("failed-read-note", "Gagal membaca catatan"),
+    (
+        "failed-read-restored-state",
+        "Gagal membaca state yang dipulihkan",
+    ),
+    (
+        "failed-read-saved-claude-md",
+        "Gagal membaca CLAUDE.md yang disimpan",
+    ),
+    (
+        "failed-read-settings-file",
+        "Gagal membaca berkas pengaturan",
+    ),
+    (
+        "failed-read-state-directory",
+        "Gagal membaca direktori state",
+    ),
+    (
+        "failed-read-trashed-account",
+        "Gagal membaca akun di tempat sampah",
+    ),
+    (
+        "failed-recreate-symlink",
+        "Gagal membuat ulang symlink: {path}",
+    ),
+    ("failed-remove", "Gagal menghapus {path}"),
+    (
+        "failed-remove-account-symlink",
+        "Gagal menghapus symlink akun",
+    ),
+    (
+        "failed-remove-directory",
+        "Gagal menghapus direktori: {path}",
+    ),
+    ("failed-remove-file", "Gagal menghapus berkas: {path}"),
+    ("failed-remove-key-file", "Gagal menghapus berkas kunci"),
+    (
+        "failed-remove-old-accounts-file",
+        "Gagal menghapus berkas akun lama",
+    ),
+    (
+        "failed-remove-saved-claude-md",
+        "Gagal menghapus CLAUDE.md yang disimpan",
+    ),
+    (
+        "failed-remove-saved-credentials-file",
+        "Gagal menghapus berkas kredensial yang disimpan",
+    ),
+    ("failed-remove-symlink", "Gagal menghapus symlink"),
+    (
+        "failed-remove-synced-account",
+        "Gagal menghapus akun yang disinkronkan",
+    ),
+    (
+        "failed-rename-account-directory",
+        "Gagal mengganti nama direktori akun",
+    ),
+    ("failed-replace-template", "Gagal mengganti templat"),
+    (
+        "failed-resolve-directory",
+        "Gagal menentukan direktori: {path}",
+    ),
+    ("failed-resolve-path", "Gagal menentukan path: {path}"),
+    ("failed-restore", "Gagal memulihkan: {path}"),
+    (
+        "failed-restore-account-configuration",
+        "Gagal memulihkan konfigurasi akun",
+    ),
+    (
+        "failed-restore-account-directory",
+        "Gagal memulihkan direktori akun",
+    ),
+    (
+        "failed-restore-account-store",
+        "Gagal memulihkan penyimpanan akun",
+    ),
+    (
+        "failed-restore-account-versions",
+        "Gagal memulihkan versi akun",
+    ),
+    (
+        "failed-restore-configuration-interrupted-switch-moved-aside",
+        "Gagal memulihkan konfigurasi yang dipindahkan oleh peralihan yang terputus",
+    ),
+    (
+        "failed-restore-credentials-file",
+        "Gagal memulihkan berkas kredensial",
+    ),
+    (
+        "failed-restore-files-outside-claude-directory",
+        "Gagal memulihkan berkas di luar direktori Claude",
+    ),
+    (
+        "extra-paths-not-absolute",
+        "Pengaturan extra-paths tidak valid: {path} (seharusnya path absolut atau yang diawali ~)",
+    ),
+    (
+        "extra-paths-holds-home",
+        "Pengaturan extra-paths tidak valid: {path} (berisi direktori home atau direktori Claude)",
+    ),
+    ("failed-restore-settings", "Gagal memulihkan pengaturan"),
+    ("failed-restore-snapshot", "Gagal memulihkan snapshot"),
+    ("failed-serialize", "Gagal menyerialkan {path}"),
+    (
+        "failed-serialize-mcp-servers",
+        "Gagal menyerialkan server MCP",
+    ),
+    ("failed-serialize-output", "Gagal menyerialkan keluaran"),
+    (
+        "failed-serialize-settings-json",
+        "Gagal menyerialkan settings.json",
+    ),
+    (
+        "failed-start-background-re-save",
+        "Gagal memulai penyimpanan ulang di latar belakang",
+    ),
+    (
+        "failed-update-account-symlink",
+        "Gagal memperbarui symlink akun",
+    ),
+    ("failed-write", "Gagal menulis {path}"),
+    (
+        "failed-write-account-snapshot",
+        "Gagal menulis snapshot akun",
+    ),
+    ("failed-write-archive", "Gagal menulis arsip: {path}"),
+    ("failed-write-bundle", "Gagal menulis bundle: {path}"),
+    ("failed-write-claude-md", "Gagal menulis CLAUDE.md"),
+    ("failed-write-file", "Gagal menulis berkas: {path}"),
+    ("failed-write-note", "Gagal menulis catatan"),
+    (
+        "failed-write-saved-claude-md",
+        "Gagal menulis CLAUDE.md yang disimpan",
+    ),
+    ("failed-write-settings-json", "Gagal menulis settings.json"),
+    (
+        "failed-write-template-file",
+        "Gagal menulis berkas templat: {path}",
+    ),
+    (
+        "hardlink-copying",
+        "Menyalin alih-alih membuat hard link: {reason}",
+    ),
+    ("hardlink-linked", "{count} berkas ditautkan dari snapshot"),
+    (
+        "entry-missing",
+        "'{name}' rusak: snapshot-nya {path} tidak ada; jalankan `claude-account-switcher repair` untuk menautkan ulang atau membuangnya",
+    ),
+    (
+        "entry-corrupt",
+        "'{name}' rusak: entrinya di accounts.json tidak dapat dibaca ({error}); jalankan `claude-account-switcher repair` untuk mendaftarkan ulang snapshot-nya atau membuangnya",
+    ),
+    (
+        "switch-entry-missing",
+        "Tidak dapat beralih ke '{name}': snapshot-nya {path} tidak ada. Kembalikan direktorinya, arahkan akun ke lokasinya sekarang dengan `claude-account-switcher repair --relink {name}=DIR`, atau buang dengan `claude-account-switcher repair`.",
+    ),
+    (
+        "switch-entry-corrupt",
+        "Tidak dapat beralih ke '{name}': entrinya di accounts.json tidak dapat dibaca ({error}). Jalankan `claude-account-switcher repair` untuk mendaftarkan ulang snapshot-nya atau membuangnya.",
+    ),
+    (
+        "doctor-entry-corrupt",
+        "Entri '{name}' di accounts.json tidak dapat dibaca: {error}",
+    ),
+    (
+        "doctor-entry-corrupt-fix",
+        "jalankan `claude-account-switcher repair` untuk mendaftarkan ulang snapshot-nya atau membuangnya",
+    ),
+    (
+        "repair-relink-invalid",
+        "Diharapkan NAME=DIR, didapat '{value}'",
+    ),
+    (
+        "repair-healthy",
+        "'{name}' tidak rusak; tidak ada yang perlu ditautkan ulang",
+    ),
+    (
+        "repair-confirm",
+        "Buang {names} dari daftar? Tidak ada yang dapat ditautkan ulang; tidak ada berkas yang dihapus.",
+    ),
+    ("repair-relinked", "'{name}' ditautkan ulang ke {path}"),
+    (
+        "repair-would-relink",
+        "Akan menautkan ulang '{name}' ke {path}",
+    ),
+    ("repair-dropped", "'{name}' dibuang dari daftar"),
+    ("repair-would-drop", "Akan membuang '{name}' dari daftar"),
+    (
+        "repair-nothing",
+        "Entri dan snapshot setiap akun dalam keadaan baik.",
+    ),
+    (
+        "repair-unmounted",
+        "'{name}' dibiarkan: drive tempatnya disimpan tidak terpasang",
+    ),
+    (
+        "save-store-missing",
+        "{path} tidak ada; jika berada di sebuah drive, pasang dulu drive tersebut",
+    ),
+    (
+        "save-store-kept",
+        "'{name}' sudah disimpan di {path}; hapus lalu simpan lagi untuk menyimpannya di tempat lain",
+    ),
+    (
+        "store-unmounted",
+        "'{name}' disimpan di {path}, yang tidak ada; pasang drive tempatnya lalu coba lagi",
+    ),
+    (
+        "switch-away-unmounted",
+        "'{name}' disimpan di {path}, yang tidak ada, sehingga perubahannya tidak dapat disimpan. Pasang drive tempatnya, atau berikan --force untuk beralih tanpa menyimpannya.",
+    ),
+    (
+        "store-unmounted-not-saved",
+        "'{name}' tidak disimpan: {path}, tempatnya disimpan, tidak terpasang",
+    ),
+    (
+        "entry-unmounted",
+        "'{name}' disimpan di {path}, yang tidak terpasang; pasang drive untuk memakainya",
+    ),
+    (
+        "save-overwrite-confirm",
+        "'{name}' sudah tersimpan dan bukan akun aktif. Simpan konfigurasi aktif di atasnya?",
+    ),
+    (
+        "save-overwrite-needed",
+        "'{name}' bukan akun aktif; berikan --overwrite untuk menyimpan konfigurasi aktif di atasnya",
+    ),
+    (
+        "switch-unsaved-confirm",
+        "~/.claude punya {count} perubahan yang belum disimpan ke '{name}'. Simpan sebelum beralih?",
+    ),
+    (
+        "switch-save-failed",
+        "Gagal menyimpan '{name}' sebelum beralih, jadi ~/.claude dibiarkan apa adanya; berikan --force untuk beralih tanpa menyimpannya",
+    ),
+    ("format-list-only", "--format {format} hanya untuk `list`"),
+    (
+        "format-not-grouped",
+        "--format {format} menampilkan satu akun per baris, jadi tidak bisa dikelompokkan",
+    ),
+    (
+        "import-from-not-found",
+        "Tidak ada yang bisa diimpor di {path}",
+    ),
+    ("import-from-none", "Tidak ada akun di {path}"),
+    ("import-from-would", "Akan mengimpor '{name}'"),
+    ("import-from-done", "{count} akun diimpor dari {path}"),
+    (
+        "no-profiles",
+        "Tidak ada profil. Buat dengan `profile create`.",
+    ),
+    (
+        "profile-created",
+        "Profil '{name}' dibuat untuk akun '{account}'",
+    ),
+    (
+        "profile-exists",
+        "Profil '{name}' sudah ada; pakai --force untuk menggantinya",
+    ),
+    ("profile-unknown", "Tidak ada profil bernama '{name}'"),
+    ("profile-deleted", "Profil '{name}' dihapus"),
+    (
+        "profile-written",
+        "Lingkungan profil '{name}' ditulis ke {path}",
+    ),
+    (
+        "team-overlay-missing",
+        "Overlay tim tidak ditemukan, jadi tidak diterapkan: {path}",
+    ),
+    (
+        "team-overlay-failed",
+        "Gagal menerapkan overlay tim: {error}",
+    ),
+    (
+        "export-redacted",
+        "Disamarkan: {dropped} berkas kredensial tidak disertakan dan {masked} rahasia ditutupi",
+    ),
+    (
+        "import-redacted",
+        "Arsip ini disamarkan: masuk lagi, dan isi nilai yang tertulis {mask}",
+    ),
+    ("audit-none", "Tidak ada berkas berisi token atau kunci."),
+    ("audit-world-readable", "bisa dibaca siapa saja"),
+    ("audit-group-readable", "bisa dibaca grup"),
+    ("audit-unencrypted", "tidak terenkripsi"),
+    (
+        "audit-flagged",
+        "{count} dari {total} berkas berisi rahasia ditandai",
+    ),
+    ("service-installed", "Layanan {service} dipasang di {path}"),
+    ("service-removed", "Layanan {service} dihapus"),
+    (
+        "service-none-installed",
+        "Tidak ada layanan yang terpasang.",
+    ),
+    (
+        "service-unsupported",
+        "Layanan hanya bisa dipasang dengan systemd (Linux) atau launchd (macOS)",
+    ),
+    ("service-command-failed", "{command} gagal"),
+    ("service-state-not-installed", "tidak terpasang"),
+    ("service-state-active", "berjalan"),
+    ("service-state-inactive", "berhenti"),
+    ("service-state-installed", "terpasang"),
+    ("menu-prompt", "Akun"),
+    ("menu-current", "aktif"),
+    (
+        "menu-no-launcher",
+        "Tidak ada menu untuk memilih akun: pasang salah satu dari {launchers}, atau berikan --command",
+    ),
+    ("menu-start-failed", "Gagal menjalankan menu: {menu}"),
+    ("health-valid", "valid"),
+    (
+        "health-unexpired",
+        "belum kedaluwarsa (tidak diperiksa online)",
+    ),
+    (
+        "health-expired",
+        "kedaluwarsa; diperbarui saat akun dipakai lagi, kecuali sudah dicabut",
+    ),
+    (
+        "health-revoked",
+        "dicabut atau sudah keluar; masuk lagi dengan `renew`",
+    ),
+    ("health-no-login", "tidak ada login OAuth tersimpan"),
+    ("health-unknown", "tidak diketahui"),
+    ("health-unreachable", "Gagal menghubungi {url}"),
+    (
+        "health-insecure-url",
+        "Menolak mengirim token ke {url}: {var} harus URL https, atau http hanya ke mesin ini",
+    ),
+    (
+        "check-failed",
+        "{count} dari {total} login tidak bisa dipakai apa adanya",
+    ),
+    ("find-lines", "{count} baris cocok"),
+    (
+        "copy-summary",
+        "{files} berkas ({size}) disalin dalam {duration}",
+    ),
+    ("timing-scanning", "memindai"),
+    ("timing-hashing", "menghitung hash"),
+    ("timing-copying", "menyalin"),
+    ("timing-config", "berkas akun"),
+    ("timing-total", "total"),
+    ("plain-timing", "{phase}: {time}, {calls} kali"),
+    ("plain-timing-total", "total: {time}"),
+    (
+        "bench-header",
+        "Menempatkan {files} berkas ({size}) dari {from} di samping {to}",
+    ),
+    ("bench-failed", "tidak bisa: {error}"),
+    ("bench-speed", "{size}/d"),
+    ("copy-eta", "Menyalin {size}, perkiraan {duration}..."),
+    (
+        "resave-scheduled",
+        "'{name}' akan disimpan ulang dalam {duration} untuk menangkap kredensial yang diperbarui",
+    ),
+    (
+        "resave-failed",
+        "Peringatan: tidak dapat menjadwalkan penyimpanan ulang: {error}",
+    ),
+    (
+        "confirm-needs-yes",
+        "Perlu konfirmasi, tetapi stdin bukan terminal; gunakan --yes untuk melanjutkan",
+    ),
+    ("hook-failed", "Hook {hook} gagal dengan kode keluar {code}"),
+    ("hook-killed", "Hook {hook} dihentikan oleh sinyal"),
+    ("hook-start-failed", "Gagal menjalankan hook {hook}"),
+    ("hook-warning", "Peringatan: {error}"),
+    ("preset-exported", "Preset ditulis ke {path}"),
+    (
+        "preset-imported",
+        "Preset diimpor: {settings} pengaturan, {aliases} alias, {tagged} akun bertag, {templates} template",
+    ),
+    (
+        "preset-account-missing",
+        "'{name}' bukan akun di sini; alias dan tagnya dalam preset dilewati",
+    ),
+    (
+        "preset-template-file",
+        "Template '{name}' dalam preset memiliki berkas yang tidak diterima template: {file}",
+    ),
+    (
+        "preset-hooks",
+        "Preset ini mengatur hook, perintah yang dijalankan pada setiap simpan, ganti, atau hapus:",
+    ),
+    ("preset-hooks-confirm", "Impor preset dengan hook ini?"),
+    ("notify-webhook-failed", "Tidak dapat memberi tahu {url}"),
+    (
+        "login-expiring",
+        "Login '{name}' berakhir dalam {left}; masuk lagi sebelum itu",
+    ),
+    ("login-expired", "Login '{name}' telah berakhir; masuk lagi"),
+    (
+        "notify-desktop-unsupported",
+        "Notifikasi desktop tidak tersedia di platform ini",
+    ),
+    (
+        "notify-desktop-failed",
+        "Tidak dapat menampilkan notifikasi desktop dengan {program}",
+    ),
+    (
+        "symlink-fallback",
+        "Peringatan: symlink tidak diizinkan di sini, beralih dengan menyalin",
+    ),
+    ("checksum-summary", "{ok} berkas OK, {failed} gagal"),
+    (
+        "checksum-malformed",
+        "baris {line}: bukan checksum dan path di dalam snapshot",
+    ),
+    (
+        "checksum-empty",
+        "{path} tidak memiliki checksum untuk diperiksa",
+    ),
+    (
+        "checksum-failed",
+        "{failed} berkas di '{name}' gagal diverifikasi",
+    ),
+    (
+        "verify-no-current",
+        "Tidak ada akun aktif; sebutkan satu atau gunakan --all",
+    ),
+    ("verify-ok", "{name}: {files} berkas OK"),
+    ("verify-missing", "{name}: {path} hilang"),
+    (
+        "verify-corrupt",
+        "{name}: {path} tidak sesuai dengan yang disimpan",
+    ),
+    (
+        "verify-no-manifest",
+        "{name}: tidak ada pembanding; simpan lagi untuk mencatat berkasnya",
+    ),
+    (
+        "verify-linked",
+        "{name}: konfigurasi aktif adalah snapshot ini, jadi tidak ada yang diperiksa",
+    ),
+    (
+        "verify-repaired",
+        "{name}: disimpan lagi dari konfigurasi aktif",
+    ),
+    (
+        "verify-not-current",
+        "{name}: hanya akun aktif yang bisa diperbaiki, dari konfigurasi aktif",
+    ),
+    ("verify-failed", "{count} snapshot rusak"),
+    (
+        "completions-installed",
+        "Pelengkapan {shell} dipasang di {path}",
+    ),
+    (
+        "completions-restart",
+        "Mulai ulang shell Anda untuk memuat pelengkapan.",
+    ),
+    (
+        "completions-zsh-fpath",
+        "Tambahkan ini ke ~/.zshrc sebelum compinit:\n  fpath=({dir} $fpath)",
+    ),
+    (
+        "sharing-violation",
+        "Berkas sedang dikunci oleh program lain, kemungkinan besar pemindaian antivirus atau pengindeks pencarian Windows.\nTutup program yang mungkin membuka berkas tersebut lalu coba lagi. Agar tidak terulang, kecualikan penyimpanan akun dari pemindaian (jalankan PowerShell sebagai administrator):\n  Add-MpPreference -ExclusionPath \"{dir}\"",
+    ),
+    ("error-not-found", "Akun '{name}' tidak ditemukan"),
+    (
+        "renew-login-hint",
+        "Masuk lagi ke '{name}'; login disimpan saat Claude Code keluar",
+    ),
+    (
+        "renew-login-failed",
+        "Login tidak selesai; '{name}' aktif tetapi tidak disimpan",
+    ),
+    ("renewed", "'{name}' diperbarui"),
+    (
+        "renew-reminder",
+        "'{name}' disimpan {days} hari lalu dan login-nya mungkin sudah kedaluwarsa; jalankan `renew {name}`",
+    ),
+    (
+        "error-locked",
+        "Akun '{name}' dikunci; gunakan --force atau jalankan `unlock` dulu",
+    ),
+    ("error-already-exists", "Akun '{name}' sudah ada"),
+    (
+        "error-no-configuration",
+        "Konfigurasi Claude Code tidak ditemukan. Silakan autentikasi terlebih dahulu dengan: claude-code auth",
+    ),
+    (
+        "error-bundle-expired",
+        "Bundel kedaluwarsa pada {time} dan tidak dapat diimpor lagi",
+    ),
+    (
+        "error-case-conflict",
+        "Akun '{name}' hanya berbeda huruf besar/kecil dari akun '{existing}', yang memakai direktori yang sama pada sistem berkas ini",
+    ),
+    (
+        "error-conflict",
+        "Berkas akun diubah oleh perintah lain selama perintah ini berjalan, jadi tidak ada yang disimpan di atasnya; jalankan lagi",
+    ),
+    (
+        "error-version-mismatch",
+        "'{name}' disematkan ke Claude Code {pinned}, tetapi yang terpasang {installed}; pasang versi itu, atau lepaskan dengan `pin {name} --off`",
+    ),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholders(text: &str) -> Vec<&str> {
+        let mut found: Vec<_> = text
+            .split('{')
+            .skip(1)
+            .filter_map(|rest| rest.split_once('}').map(|(name, _)| name))
+            .collect();
+        found.sort();
+        found
+    }
+
+    #[test]
+    fn test_parse_locale() {
+        assert_eq!(Locale::parse("id_ID.UTF-8"), Some(Locale::Id));
+        assert_eq!(Locale::parse("en_US.UTF-8"), Some(Locale::En));
+        assert_eq!(Locale::parse("C"), Some(Locale::En));
+        assert_eq!(Locale::parse("fr_FR"), None);
+    }
+
+    #[test]
+    fn test_catalogs_have_matching_keys_and_placeholders() {
+        for (key, text) in EN {
+            let translated = ID
+                .iter()
+                .find(|(k, _)| k == key)
+                .unwrap_or_else(|| panic!("missing Indonesian message: {}", key));
+            assert_eq!(placeholders(text), placeholders(translated.1), "{}", key);
+        }
+        assert_eq!(EN.len(), ID.len());
+    }
+
+    #[test]
+    fn test_lookup_falls_back() {
+        assert_eq!(
+            lookup(Locale::Id, "no-accounts"),
+            "Tidak ada akun tersimpan."
+        );
+        assert_eq!(
+            lookup(Locale::En, "no-accounts"),
+            "No saved accounts found."
+        );
+        assert_eq!(lookup(Locale::Id, "unknown-key"), "unknown-key");
+    }
+
+    #[test]
+    fn test_installed_catalogs_are_found_in_order() -> std::io::Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let (first, second) = (dir.path().join("first"), dir.path().join("second"));
+        std::fs::create_dir_all(&first)?;
+        std::fs::create_dir_all(&second)?;
+        std::fs::write(first.join("fr.toml"), "not = [valid")?;
+        std::fs::write(second.join("fr.toml"), "no-accounts = \"Aucun compte.\"\n")?;
+        let dirs = [first, second];
+
+        let catalog = load_catalog(&dirs, "fr");
+        assert_eq!(catalog["no-accounts"], "Aucun compte.");
+        assert!(load_catalog(&dirs, "de").is_empty());
+        assert_eq!(language("pt_BR.UTF-8").as_deref(), Some("pt"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_tr_macro_fills_placeholders() {
+        assert_eq!(
+            crate::tr!("account-renamed", old = "a", new = "b"),
+            "Renamed account 'a' to 'b'"
+        );
+        assert_eq!(crate::tr!("cancelled"), "Cancelled.");
+        // What's filled in isn't filled in again
+        assert_eq!(
+            crate::tr!("account-renamed", old = "{new}", new = "b"),
+            "Renamed account '{new}' to 'b'"
+        );
+        assert_eq!(
+            fill("{a} {b} {c} {", &[("a", "{b}".into())]),
+            "{b} {b} {c} {"
+        );
+    }
+}
@@ -0,0 +1,242 @@
+//! `import-from`: the accounts other switchers keep, read so they can be
+//! brought into the store, leaving the other tool's files as they are.
+//!
+//! - `profiles` is a directory with one Claude directory per account, as
+//!   the shell-script switchers lay out (`~/.claude-profiles/<name>`). Each
+//!   is either what `CLAUDE_CONFIG_DIR` points at, with `.claude.json`
+//!   inside, or holds a `.claude` directory with `.claude.json` next to it.
+//!   One named in a way accounts can't be is imported under a slug of it.
+//! - `cc-switch` is the JSON file of cc-switch (`~/.cc-switch/config.json`),
+//!   whose Claude providers are each a `settings.json`, usually with an API
+//!   key and base URL in its `env`.
+
+use crate::names;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CLAUDE_DIR: &str = ".claude";
+const CLAUDE_JSON: &str = ".claude.json";
+// What a directory needs one of to be taken for a Claude directory
+const MARKERS: &[&str] = &[
+    ".credentials.json",
+    "settings.json",
+    CLAUDE_JSON,
+    "CLAUDE.md",
+];
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tool {
+    /// One Claude directory per account, as in ~/.claude-profiles/<name>
+    Profiles,
+    /// The providers in cc-switch's ~/.cc-switch/config.json
+    CcSwitch,
+}
+
+impl Tool {
+    /// Where the tool keeps its accounts unless told otherwise.
+    pub fn default_path(self, home: &Path) -> PathBuf {
+        match self {
+            Tool::Profiles => home.join(".claude-profiles"),
+            Tool::CcSwitch => home.join(".cc-switch").join("config.json"),
+        }
+    }
+}
+
+/// What an account found is made of.
+#[derive(Debug, PartialEq)]
+pub enum Snapshot {
+    /// A Claude directory, and the `.claude.json` kept beside it when it
+    /// isn't inside
+    Dir {
+        claude_dir: PathBuf,
+        claude_json: Option<PathBuf>,
+    },
+    /// The contents of a `settings.json`
+    Settings(Value),
+}
+
+/// An account found in another tool's files.
+#[derive(Debug, PartialEq)]
+pub struct Found {
+    /// The name to import it under
+    pub name: String,
+    pub snapshot: Snapshot,
+}
+
+/// The accounts `tool` keeps at `path`, by name.
+pub fn scan(tool: Tool, path: &Path) -> Result<Vec<Found>> {
+    let mut found = match tool {
+        Tool::Profiles => scan_profiles(path)?,
+        Tool::CcSwitch => scan_cc_switch(path)?,
+    };
+    found.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(found)
+}
+
+fn scan_profiles(root: &Path) -> Result<Vec<Found>> {
+    let entries =
+        fs::read_dir(root).with_context(|| format!("Failed to read {}", root.display()))?;
+    let mut found = Vec::new();
+    for entry in entries {
+        let entry = entry.with_context(|| format!("Failed to read {}", root.display()))?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let dir = entry.path();
+        if name.starts_with('.') || !dir.is_dir() {
+            continue;
+        }
+        // A directory named "My Work" is imported as "my-work"
+        let name = match names::validate(&name) {
+            Ok(()) => name,
+            Err(_) => slug(&name),
+        };
+        let nested = dir.join(CLAUDE_DIR);
+        let (claude_dir, claude_json) = match nested.is_dir() {
+            true => {
+                let claude_json = Some(dir.join(CLAUDE_JSON)).filter(|path| path.is_file());
+                (nested, claude_json)
+            }
+            false => (dir, None),
+        };
+        let is_claude = claude_json.is_some()
+            || MARKERS
+                .iter()
+                .any(|marker| claude_dir.join(marker).exists());
+        if is_claude {
+            found.push(Found {
+                name,
+                snapshot: Snapshot::Dir {
+                    claude_dir,
+                    claude_json,
+                },
+            });
+        }
+    }
+    Ok(found)
+}
+
+fn scan_cc_switch(path: &Path) -> Result<Vec<Found>> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let document: Value = serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    // Later versions keep each app's providers under its name
+    let providers = document
+        .pointer("/claude/providers")
+        .or_else(|| document.get("providers"))
+        .and_then(Value::as_object)
+        .with_context(|| format!("No Claude providers in {}", path.display()))?;
+
+    Ok(providers
+        .iter()
+        .filter_map(|(id, provider)| {
+            let settings = provider.get("settingsConfig")?.clone();
+            let name = provider
+                .get("name")
+                .and_then(Value::as_str)
+                .map(slug)
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| slug(id));
+            Some(Found {
+                name,
+                snapshot: Snapshot::Settings(settings),
+            })
+        })
+        .collect())
+}
+
+// A display name ("Claude Official") as an account name ("claude-official")
+fn slug(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars() {
+        match c.is_ascii_alphanumeric() {
+            true => slug.push(c.to_ascii_lowercase()),
+            false if !slug.is_empty() && !slug.ends_with('-') => slug.push('-'),
+            false => {}
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_profiles_in_both_layouts() -> Result<()> {
+        let dir = TempDir::new()?;
+        let root = dir.path();
+        fs::create_dir_all(root.join("work"))?;
+        fs::write(root.join("work/.credentials.json"), "{}")?;
+        fs::create_dir_all(root.join("personal/.claude"))?;
+        fs::write(root.join("personal/.claude.json"), "{}")?;
+        fs::create_dir_all(root.join("empty"))?;
+        fs::create_dir_all(root.join(".git"))?;
+        fs::create_dir_all(root.join("My Work"))?;
+        fs::write(root.join("My Work/settings.json"), "{}")?;
+
+        assert_eq!(
+            scan(Tool::Profiles, root)?,
+            [
+                Found {
+                    name: "my-work".to_string(),
+                    snapshot: Snapshot::Dir {
+                        claude_dir: root.join("My Work"),
+                        claude_json: None,
+                    },
+                },
+                Found {
+                    name: "personal".to_string(),
+                    snapshot: Snapshot::Dir {
+                        claude_dir: root.join("personal/.claude"),
+                        claude_json: Some(root.join("personal/.claude.json")),
+                    },
+                },
+                Found {
+                    name: "work".to_string(),
+                    snapshot: Snapshot::Dir {
+                        claude_dir: root.join("work"),
+                        claude_json: None,
+                    },
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cc_switch_providers() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("config.json");
+        let settings = json!({"env": {"ANTHROPIC_BASE_URL": "https://example.com"}});
+        fs::write(
+            &path,
+            json!({
+                "version": 2,
+                "claude": {
+                    "providers": {
+                        "a1": {"name": "Claude Official", "settingsConfig": {}},
+                        "b2": {"name": "Zhipu GLM (CN)", "settingsConfig": settings},
+                        "c3": {"name": "Broken"},
+                    },
+                    "current": "a1",
+                },
+            })
+            .to_string(),
+        )?;
+
+        let found = scan(Tool::CcSwitch, &path)?;
+        let names: Vec<_> = found.iter().map(|found| found.name.as_str()).collect();
+        assert_eq!(names, ["claude-official", "zhipu-glm-cn"]);
+        assert_eq!(found[1].snapshot, Snapshot::Settings(settings));
+
+        // The layout of earlier versions
+        fs::write(&path, r#"{"providers": {"x": {"settingsConfig": {}}}}"#)?;
+        assert_eq!(scan(Tool::CcSwitch, &path)?[0].name, "x");
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+//! `init`: a first run through the choices that otherwise only show up in
+//! the README, where the store is and how much of `~/.claude` an account
+//! takes, ending with the current login saved as the first account.
+//!
+//! The answers go into `settings.toml` as `config set` would write them, so
+//! running it again later only changes what's answered differently; a no
+//! takes out what an earlier yes wrote.
+
+use crate::configure;
+use crate::manager::AccountManager;
+use crate::names;
+use crate::output::OutputFormat;
+use crate::paths::Paths;
+use crate::prompt::{Prompter, StdinPrompter};
+use crate::settings::Settings;
+use crate::system;
+use crate::tr;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// What the answers amount to.
+#[derive(Debug, Default, PartialEq)]
+pub struct Choices {
+    /// Settings to write, by key, or to remove when `None`
+    pub settings: Vec<(&'static str, Option<toml::Value>)>,
+    /// Whether to encrypt the store, which asks for its passphrase
+    pub encrypt: bool,
+    /// The name to save the current login as
+    pub first_account: Option<String>,
+}
+
+/// Asks the questions. `system_dir` is where the system store would be and
+/// `claude_dir` the Claude directory, as things stand; `has_login` tells
+/// whether the one chosen holds a login to save.
+pub fn ask(
+    prompter: &dyn Prompter,
+    system_dir: &Path,
+    claude_dir: &Path,
+    has_login: impl Fn(&Path) -> bool,
+) -> Result<Choices> {
+    let mut choices = Choices::default();
+    let mut choose = |key, yes: bool, value: toml::Value| {
+        choices.settings.push((key, yes.then_some(value)));
+    };
+
+    let system = prompter.confirm(&tr!("init-system", path = system_dir.display()), false)?;
+    choose("system", system, toml::Value::Boolean(true));
+
+    let answer = prompter.input(&tr!("init-claude-dir", path = claude_dir.display()))?;
+    let chosen = match answer.is_empty() {
+        true => claude_dir.to_path_buf(),
+        false => PathBuf::from(&answer),
+    };
+    if chosen != claude_dir {
+        choose("claude-dir", true, toml::Value::String(answer));
+    }
+    let credentials_only = prompter.confirm(&tr!("init-credentials-only"), false)?;
+    choose(
+        "capture-scope",
+        credentials_only,
+        toml::Value::String("credentials".into()),
+    );
+    let keychain = prompter.confirm(&tr!("init-keychain"), false)?;
+    choose(
+        "credentials",
+        keychain,
+        toml::Value::String("keychain".into()),
+    );
+    choices.encrypt = prompter.confirm(&tr!("init-encrypt"), false)?;
+
+    if has_login(&chosen) {
+        let name = prompter.input(&tr!("init-first-account"))?;
+        if !name.is_empty() {
+            names::validate(&name)?;
+            choices.first_account = Some(name);
+        }
+    }
+    Ok(choices)
+}
+
+/// Runs the walk-through on the terminal and carries out the answers.
+pub fn run(claude_dir: Option<&Path>, format: OutputFormat) -> Result<()> {
+    let paths = Paths::current()?;
+    let prompter = StdinPrompter;
+    if paths.settings_file.exists()
+        && !prompter.confirm(
+            &tr!("init-settings-exist", path = paths.settings_file.display()),
+            false,
+        )?
+    {
+        eprintln!("{}", tr!("cancelled"));
+        return Ok(());
+    }
+
+    eprintln!("{}", tr!("init-store", path = paths.switcher_dir.display()));
+    let settings = Settings::load(&paths.settings_file)?;
+    let system_dir = system::root(&settings, true).unwrap_or_default();
+    let live = paths.claude_config_dir(claude_dir, settings.claude_dir.as_deref());
+    let choices = ask(&prompter, &system_dir, &live, |dir| {
+        paths.claude_config_dir(claude_dir, Some(dir)).is_dir()
+    })?;
+
+    for (key, value) in &choices.settings {
+        configure::update(&paths.settings_file, key, value.clone())?;
+    }
+    // Built after the settings are written, so it works by them
+    let manager = AccountManager::new(claude_dir)?.with_format(format);
+    if choices.encrypt {
+        manager.enable_encryption()?;
+    }
+    if let Some(name) = &choices.first_account {
+        manager.save_account(name)?;
+    }
+    eprintln!("{}", tr!("init-done", path = paths.settings_file.display()));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::ScriptedPrompter;
+
+    #[test]
+    fn test_answers_become_settings() -> Result<()> {
+        let system_dir = Path::new(system::DEFAULT_DIR);
+        let prompter = ScriptedPrompter::new(["n", "", "y", "n", "y", "work"]);
+        let choices = ask(&prompter, system_dir, Path::new("/home/me/.claude"), |_| {
+            true
+        })?;
+        assert_eq!(
+            choices,
+            Choices {
+                settings: vec![
+                    ("system", None),
+                    (
+                        "capture-scope",
+                        Some(toml::Value::String("credentials".into()))
+                    ),
+                    ("credentials", None),
+                ],
+                encrypt: true,
+                first_account: Some("work".to_string()),
+            }
+        );
+
+        // Nothing to save in the directory chosen, so it isn't asked
+        let prompter = ScriptedPrompter::new(["y", "/srv/claude", "n", "y", "n"]);
+        let choices = ask(
+            &prompter,
+            system_dir,
+            Path::new("/home/me/.claude"),
+            |dir| dir != Path::new("/srv/claude"),
+        )?;
+        assert_eq!(
+            choices.settings,
+            vec![
+                ("system", Some(toml::Value::Boolean(true))),
+                (
+                    "claude-dir",
+                    Some(toml::Value::String("/srv/claude".into()))
+                ),
+                ("capture-scope", None),
+                ("credentials", Some(toml::Value::String("keychain".into()))),
+            ]
+        );
+        assert!(choices.first_account.is_none());
+
+        let prompter = ScriptedPrompter::new(["n", "", "n", "n", "n", "../evil"]);
+        assert!(
+            ask(&prompter, system_dir, Path::new("/home/me/.claude"), |_| {
+                true
+            })
+            .is_err()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,321 @@
+//! Saves and switches that are stopped before they finish.
+//!
+//! While one runs, Ctrl-C (and `SIGTERM` or `SIGHUP`) only asks it to stop:
+//! the copy in progress gives up at the next file and the operation backs
+//! out the way it does after any other failure, leaving `~/.claude` and the
+//! snapshots as they were. A second Ctrl-C stops the process at once.
+//!
+//! Each operation also leaves a marker in the state directory,
+//! `operation.json`, saying what it is and which step it has reached, and
+//! removes it when it's done. One still there on the next run (the process
+//! was killed, the machine went down, it panicked) is offered to be resumed
+//! or rolled back, and `recover` does either.
+
+use crate::crash::Step;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub const FILE: &str = "operation.json";
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    // The outermost operation's marker, with where it's written
+    static MARKER: RefCell<Option<(PathBuf, Marker)>> = const { RefCell::new(None) };
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Operation {
+    Save,
+    Switch,
+}
+
+/// What `recover` does about an interrupted operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Recovery {
+    /// Carry it out after all
+    Resume,
+    /// Undo what it had done
+    Rollback,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Marker {
+    pub operation: Operation,
+    /// The account saved or switched to
+    pub account: String,
+    /// The account active when a switch started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub step: Step,
+    /// The account `step` was for: the outgoing one while a switch saves it
+    pub working_on: String,
+    /// How to back out of the snapshot being written, once it can be
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undo: Option<Undo>,
+}
+
+/// A snapshot being written over, and the version the one it replaces was
+/// moved to (none for an account saved for the first time).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Undo {
+    pub snapshot: PathBuf,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kept: Option<PathBuf>,
+}
+
+impl Marker {
+    pub fn new(
+        operation: Operation,
+        account: &str,
+        from: Option<&str>,
+        started_at: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            operation,
+            account: account.to_string(),
+            from: from.map(str::to_string),
+            started_at,
+            step: Step::Starting,
+            working_on: account.to_string(),
+            undo: None,
+        }
+    }
+
+    /// The account whose snapshot was being written when it stopped, if one
+    /// was.
+    pub fn writing_snapshot(&self) -> Option<&str> {
+        let saving = match self.step {
+            Step::KeepingVersion | Step::CopyingSnapshot => true,
+            // A save's last step; a switch's is its own
+            Step::UpdatingConfig => {
+                self.operation == Operation::Save || self.working_on != self.account
+            }
+            _ => false,
+        };
+        saving.then_some(self.working_on.as_str())
+    }
+
+    /// Whether it stopped once a switch had started replacing the live
+    /// directory.
+    pub fn replacing_live(&self) -> bool {
+        self.operation == Operation::Switch
+            && self.working_on == self.account
+            && matches!(
+                self.step,
+                Step::SwappingLive | Step::RestoringCredentials | Step::UpdatingConfig
+            )
+    }
+}
+
+/// The marker an operation left in `state_dir`, if one did.
+pub fn load(state_dir: &Path) -> Result<Option<Marker>> {
+    let path = state_dir.join(FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = fs::read_to_string(&path).context("Failed to read interrupted operation")?;
+    serde_json::from_str(&contents)
+        .map(Some)
+        .context("Failed to parse interrupted operation")
+}
+
+pub fn clear(state_dir: &Path) -> Result<()> {
+    let path = state_dir.join(FILE);
+    match fs::remove_file(&path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+            Err(e).context("Failed to remove interrupted operation")
+        }
+        _ => Ok(()),
+    }
+}
+
+fn write(path: &Path, marker: &Marker) -> Result<()> {
+    let contents = serde_json::to_string_pretty(marker).context("Failed to serialize operation")?;
+    fs::write(path, contents).context("Failed to write operation")
+}
+
+/// Keeps the marker, and the signal handlers, until dropped.
+#[must_use]
+pub struct Guard {
+    outermost: bool,
+    _signals: Option<signals::Handlers>,
+}
+
+/// Writes `marker` into `state_dir` and catches Ctrl-C until the guard is
+/// dropped. Within another operation (the save a switch starts with) the
+/// outer one's marker is kept instead.
+pub fn begin(state_dir: &Path, marker: Marker) -> Result<Guard> {
+    if MARKER.with_borrow(Option::is_some) {
+        return Ok(Guard {
+            outermost: false,
+            _signals: None,
+        });
+    }
+    let path = state_dir.join(FILE);
+    write(&path, &marker)?;
+    INTERRUPTED.store(false, Ordering::SeqCst);
+    let signals = signals::install();
+    MARKER.with_borrow_mut(|current| *current = Some((path, marker)));
+    Ok(Guard {
+        outermost: true,
+        _signals: Some(signals),
+    })
+}
+
+/// Records that the operation reached `step` on `account`. Called by
+/// `crash::step`; nothing happens outside an operation.
+pub fn note(step: Step, account: &str) {
+    MARKER.with_borrow_mut(|current| {
+        if let Some((path, marker)) = current {
+            marker.step = step;
+            marker.working_on = account.to_string();
+            // The marker only helps afterwards; the operation goes on
+            let _ = write(path, marker);
+        }
+    });
+}
+
+/// Records that the save in progress is writing `snapshot`, and that
+/// removing it and putting `kept` back undoes it.
+pub fn undoable(snapshot: &Path, kept: Option<&Path>) {
+    MARKER.with_borrow_mut(|current| {
+        if let Some((path, marker)) = current {
+            marker.undo = Some(Undo {
+                snapshot: snapshot.to_path_buf(),
+                kept: kept.map(Path::to_path_buf),
+            });
+            let _ = write(path, marker);
+        }
+    });
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        if !self.outermost {
+            return;
+        }
+        let marker = MARKER.with_borrow_mut(Option::take);
+        // A panic leaves it behind for the next run, as a kill would
+        if let Some((path, _)) = marker
+            && !std::thread::panicking()
+        {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+/// Fails once Ctrl-C has been pressed during the operation, for the steps
+/// that can still back out to check between files.
+pub fn check() -> Result<()> {
+    match INTERRUPTED.load(Ordering::SeqCst) {
+        true => anyhow::bail!(crate::tr!("interrupted")),
+        false => Ok(()),
+    }
+}
+
+#[cfg(unix)]
+mod signals {
+    use super::INTERRUPTED;
+    use std::sync::atomic::Ordering;
+
+    const CAUGHT: [libc::c_int; 3] = [libc::SIGINT, libc::SIGTERM, libc::SIGHUP];
+
+    extern "C" fn interrupt(_: libc::c_int) {
+        if INTERRUPTED.swap(true, Ordering::SeqCst) {
+            // SAFETY: _exit is async-signal-safe
+            unsafe { libc::_exit(130) };
+        }
+    }
+
+    /// Puts the previous handlers back when dropped.
+    pub struct Handlers {
+        previous: Vec<(libc::c_int, libc::sigaction)>,
+    }
+
+    pub fn install() -> Handlers {
+        let mut previous = Vec::new();
+        for signal in CAUGHT {
+            // SAFETY: sigaction is plain data, and the handler only touches
+            // an atomic and calls _exit
+            unsafe {
+                let mut action: libc::sigaction = std::mem::zeroed();
+                action.sa_sigaction = interrupt as extern "C" fn(libc::c_int) as libc::sighandler_t;
+                action.sa_flags = libc::SA_RESTART;
+                libc::sigemptyset(&mut action.sa_mask);
+
+                let mut old: libc::sigaction = std::mem::zeroed();
+                if libc::sigaction(signal, &action, &mut old) == 0 {
+                    previous.push((signal, old));
+                }
+            }
+        }
+        Handlers { previous }
+    }
+
+    impl Drop for Handlers {
+        fn drop(&mut self) {
+            for (signal, old) in &self.previous {
+                // SAFETY: `old` came from sigaction
+                unsafe { libc::sigaction(*signal, old, std::ptr::null_mut()) };
+            }
+        }
+    }
+}
+
+// Windows stops the process at Ctrl-C; the marker is left for the next run
+#[cfg(not(unix))]
+mod signals {
+    pub struct Handlers;
+
+    pub fn install() -> Handlers {
+        Handlers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_marker_follows_the_steps() -> Result<()> {
+        let dir = TempDir::new()?;
+        let marker = Marker::new(Operation::Switch, "work", Some("home"), Utc::now());
+        {
+            let _guard = begin(dir.path(), marker)?;
+            // The save a switch starts with keeps the switch's marker
+            let _inner = begin(
+                dir.path(),
+                Marker::new(Operation::Save, "home", None, Utc::now()),
+            )?;
+            note(Step::CopyingSnapshot, "home");
+            undoable(&dir.path().join("home"), None);
+            let stopped = load(dir.path())?.expect("marker is written");
+            assert_eq!(stopped.operation, Operation::Switch);
+            assert_eq!(stopped.writing_snapshot(), Some("home"));
+            assert_eq!(
+                stopped.undo.as_ref().unwrap().snapshot,
+                dir.path().join("home")
+            );
+            assert!(!stopped.replacing_live());
+
+            note(Step::SwappingLive, "work");
+            let stopped = load(dir.path())?.expect("marker is written");
+            assert_eq!(stopped.writing_snapshot(), None);
+            assert!(stopped.replacing_live());
+        }
+        assert_eq!(load(dir.path())?, None);
+        // Outside an operation there's nothing to note
+        note(Step::UpdatingConfig, "work");
+        assert_eq!(load(dir.path())?, None);
+        Ok(())
+    }
+}
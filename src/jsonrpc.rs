@@ -0,0 +1,102 @@
+//! The JSON-RPC 2.0 framing shared by the `daemon` socket and the
+//! `mcp-serve` stdio server: one message per line, each response repeating
+//! the id of its request.
+
+use crate::output::ErrorJson;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+// JSON-RPC's own error codes, and the one it leaves for applications
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const SERVER_ERROR: i64 = -32000;
+
+/// A request, or a notification when it has no id.
+#[derive(Deserialize)]
+pub struct Message {
+    jsonrpc: String,
+    #[serde(default)]
+    pub id: Option<Value>,
+    pub method: String,
+    #[serde(default)]
+    pub params: Option<Value>,
+}
+
+#[derive(Serialize)]
+pub struct Error {
+    pub code: i64,
+    pub message: String,
+    /// For a failed command, what `--json` prints for one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<ErrorJson>,
+}
+
+impl Error {
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    pub fn unknown_method(method: &str) -> Self {
+        Self::new(METHOD_NOT_FOUND, format!("Unknown method: {}", method))
+    }
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(error: anyhow::Error) -> Self {
+        Self {
+            code: SERVER_ERROR,
+            message: error.to_string(),
+            data: Some(ErrorJson::new(&error)),
+        }
+    }
+}
+
+/// Parses one line into a message.
+pub fn read(line: &str) -> Result<Message, Error> {
+    let message: Message =
+        serde_json::from_str(line).map_err(|e| Error::new(PARSE_ERROR, e.to_string()))?;
+    if message.jsonrpc != "2.0" {
+        return Err(Error::new(INVALID_REQUEST, "jsonrpc must be \"2.0\""));
+    }
+    Ok(message)
+}
+
+/// Parses one line into the request `T` it makes, an enum tagged by
+/// `method` with its `params` as content, along with the id for the
+/// response. Only the names in `methods` are looked for, so any other is
+/// reported as unknown rather than as bad params.
+pub fn request<T: DeserializeOwned>(
+    line: &str,
+    methods: &[&str],
+) -> (Option<Value>, Result<T, Error>) {
+    let message = match read(line) {
+        Ok(message) => message,
+        Err(error) => return (None, Err(error)),
+    };
+    if !methods.contains(&message.method.as_str()) {
+        return (message.id, Err(Error::unknown_method(&message.method)));
+    }
+    let mut request = json!({"method": message.method});
+    if let Some(params) = message.params {
+        request["params"] = params;
+    }
+    let request =
+        serde_json::from_value(request).map_err(|e| Error::new(INVALID_PARAMS, e.to_string()));
+    (message.id, request)
+}
+
+/// The response line to the request with `id`.
+pub fn response(id: Option<Value>, outcome: Result<Value, Error>) -> String {
+    match outcome {
+        Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+        Err(error) => json!({"jsonrpc": "2.0", "id": id, "error": error}),
+    }
+    .to_string()
+}
@@ -0,0 +1,119 @@
+//! Manage multiple Claude Code CLI accounts.
+//!
+//! The binary is a thin wrapper around these modules; embedders can use
+//! [`store::SnapshotStore`] and [`manager::AccountManager`] directly. The
+//! manager's data methods return what they did rather than printing it:
+//!
+//! ```no_run
+//! use claude_account_switcher::manager::{AccountManager, SwitchOutcome};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let manager = AccountManager::new(None)?
+//!     .with_quiet(true)
+//!     .with_assume_yes(true);
+//! for account in manager.account_summaries()? {
+//!     println!("{}{}", account.name, if account.current { " *" } else { "" });
+//! }
+//! if let SwitchOutcome::Switched(Some(copied)) = manager.switch("work", None)? {
+//!     println!("copied {} files", copied.usage.files);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Failures that callers may want to handle are
+//! [`error::AccountError`]s in the error chain; see [`error::classify`].
+
+pub mod api;
+pub mod archive;
+pub mod audit;
+pub mod backup;
+pub mod banner;
+pub mod bench;
+pub mod binding;
+pub mod capabilities;
+pub mod capture;
+pub mod checkpoints;
+pub mod claude;
+pub mod claude_version;
+pub mod cli;
+pub mod clipboard;
+pub mod clock;
+pub mod cloud;
+pub mod codec;
+pub mod completions;
+pub mod compression;
+pub mod config;
+pub mod configure;
+pub mod crash;
+pub mod credstore;
+pub mod crypto;
+pub mod daemon;
+pub mod debug_bundle;
+pub mod diff;
+pub mod direct;
+pub mod docs;
+pub mod doctor;
+pub mod duration;
+pub mod editor;
+pub mod encryption;
+pub mod envfile;
+pub mod error;
+pub mod export;
+pub mod extra_paths;
+pub mod fidelity;
+pub mod hashing;
+pub mod health;
+pub mod history;
+pub mod hook;
+pub mod hooks;
+pub mod i18n;
+pub mod import_from;
+pub mod init;
+pub mod interrupt;
+pub mod jsonrpc;
+pub mod list_cache;
+pub mod live_backups;
+pub mod loader;
+pub mod lock;
+pub mod logging;
+pub mod manager;
+pub mod manifest;
+pub mod mcp;
+pub mod menu;
+pub mod names;
+pub mod notify;
+pub mod objects;
+pub mod output;
+pub mod overlay;
+pub mod paths;
+pub mod platform;
+pub mod preset;
+pub mod profile;
+pub mod progress;
+pub mod prompt;
+pub mod quota;
+pub mod redact;
+pub mod remote;
+pub mod retry;
+pub mod runner;
+pub mod search;
+pub mod segment;
+pub mod service;
+pub mod sessions;
+pub mod settings;
+pub mod size;
+pub mod stats;
+pub mod storage;
+pub mod store;
+pub mod suggest;
+pub mod sync;
+pub mod system;
+pub mod table;
+pub mod template;
+pub mod timings;
+pub mod trash;
+pub mod tui;
+pub mod ui;
+pub mod versions;
+pub mod wrapper;
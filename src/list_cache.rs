@@ -0,0 +1,195 @@
+//! What `list` shows that means walking a snapshot or reading its
+//! credentials: its size and when its login expires. It's kept in the state
+//! directory so that with dozens of accounts `list` reads one small file, and
+//! an entry is trusted only while its snapshot looks unchanged.
+//!
+//! A snapshot looks unchanged while it's at the same path, was saved at the
+//! same time, and neither it nor a directory at its top level has been
+//! modified since. Claude writing deeper into a snapshot used in place (with
+//! `env` or `shell`) doesn't show up that way, so entries are also measured
+//! again once they're a day old.
+
+use crate::config::AccountsConfig;
+use crate::size::DirUsage;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+pub const FILE: &str = "list-cache.json";
+
+const MAX_AGE_HOURS: i64 = 24;
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct ListCache {
+    /// The version that wrote it; another version starts over
+    pub version: String,
+    #[serde(default)]
+    pub accounts: BTreeMap<String, Entry>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub stamp: Stamp,
+    pub measured_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<DirUsage>,
+    /// When the login expires, once it's been read: `Some(None)` when the
+    /// snapshot has no expiring login
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<Option<DateTime<Utc>>>,
+}
+
+/// What an entry was taken from.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Stamp {
+    pub path: PathBuf,
+    pub saved_at: String,
+    /// The latest modification time of the snapshot and the directories at
+    /// its top level, in nanoseconds since the epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<u128>,
+}
+
+impl Stamp {
+    pub fn of(path: &Path, saved_at: &str) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            saved_at: saved_at.to_string(),
+            modified: modified(path),
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<u128> {
+    let nanos = |metadata: fs::Metadata| {
+        let modified = metadata.modified().ok()?;
+        Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos())
+    };
+    let mut latest = nanos(fs::metadata(path).ok()?)?;
+    for entry in fs::read_dir(path).ok()?.flatten() {
+        if let Ok(metadata) = entry.metadata()
+            && metadata.is_dir()
+            && let Some(modified) = nanos(metadata)
+        {
+            latest = latest.max(modified);
+        }
+    }
+    Some(latest)
+}
+
+impl ListCache {
+    /// The cache in `dir`, left out when it's missing, unreadable or was
+    /// written by another version, without the entries too old to trust.
+    pub fn load(dir: &Path, now: DateTime<Utc>) -> Self {
+        let mut cache = fs::read_to_string(dir.join(FILE))
+            .ok()
+            .and_then(|contents| serde_json::from_str::<Self>(&contents).ok())
+            .filter(|cache| cache.version == env!("CARGO_PKG_VERSION"))
+            .unwrap_or_else(|| Self {
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                accounts: BTreeMap::new(),
+            });
+        cache
+            .accounts
+            .retain(|_, entry| now - entry.measured_at < Duration::hours(MAX_AGE_HOURS));
+        cache
+    }
+
+    /// `name`'s entry if it was taken from `stamp`.
+    pub fn get(&self, name: &str, stamp: &Stamp) -> Option<&Entry> {
+        self.accounts
+            .get(name)
+            .filter(|entry| entry.stamp == *stamp)
+    }
+
+    /// `name`'s entry for `stamp`, empty when what's cached was taken from
+    /// anything else.
+    pub fn entry(&mut self, name: &str, stamp: Stamp, now: DateTime<Utc>) -> &mut Entry {
+        let entry = self
+            .accounts
+            .entry(name.to_string())
+            .or_insert_with(|| Entry {
+                stamp: stamp.clone(),
+                measured_at: now,
+                usage: None,
+                expires_at: None,
+            });
+        if entry.stamp != stamp {
+            *entry = Entry {
+                stamp,
+                measured_at: now,
+                usage: None,
+                expires_at: None,
+            };
+        }
+        entry
+    }
+
+    /// Drops the entries of accounts `config` no longer has as they were:
+    /// removed, renamed, moved or saved again. Returns whether any went.
+    pub fn retain(&mut self, config: &AccountsConfig) -> bool {
+        let before = self.accounts.len();
+        self.accounts.retain(|name, entry| {
+            config.accounts.get(name).is_some_and(|meta| {
+                meta.path == entry.stamp.path && meta.saved_at == entry.stamp.saved_at
+            })
+        });
+        self.accounts.len() != before
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        let contents =
+            serde_json::to_string_pretty(self).context("Failed to serialize the list cache")?;
+        fs::write(dir.join(FILE), contents).context("Failed to write the list cache")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_entries_follow_the_snapshot() -> Result<()> {
+        let dir = TempDir::new()?;
+        let snapshot = dir.path().join("work");
+        fs::create_dir_all(snapshot.join("projects"))?;
+        let now = Utc::now();
+
+        let mut cache = ListCache::load(dir.path(), now);
+        let stamp = Stamp::of(&snapshot, "2024-01-01T00:00:00Z");
+        cache.entry("work", stamp.clone(), now).usage = Some(DirUsage {
+            files: 2,
+            bytes: 10,
+        });
+        cache.save(dir.path())?;
+
+        let cache = ListCache::load(dir.path(), now);
+        let entry = cache.get("work", &stamp).expect("entry is kept");
+        assert_eq!(
+            entry.usage,
+            Some(DirUsage {
+                files: 2,
+                bytes: 10
+            })
+        );
+        assert!(
+            cache
+                .get("work", &Stamp::of(&snapshot, "2024-02-01T00:00:00Z"))
+                .is_none()
+        );
+
+        // A directory at the top level changing is a change to the snapshot
+        let mut later = stamp.clone();
+        later.modified = stamp.modified.map(|modified| modified + 1);
+        assert!(cache.get("work", &later).is_none());
+
+        let stale = ListCache::load(dir.path(), now + Duration::hours(MAX_AGE_HOURS));
+        assert!(stale.accounts.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+//! The live directories that switches replaced, so one that held more than
+//! the snapshots did (changes a save missed, or never captured) can be put
+//! back with `recover-live`.
+//!
+//! They're kept beside the live directory, in `~/.claude.cas-last-live`, so
+//! setting one aside is a rename on the same filesystem. Each is named for
+//! when it was replaced and the account that was active in it:
+//! `20240101T093000Z_work`, or just the time when none was.
+
+use crate::platform;
+use crate::retry::retry;
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Appended to the live directory's path for the directory they're kept in
+pub const SUFFIX: &str = ".cas-last-live";
+/// How many are kept unless `keep-live-backups` says otherwise
+pub const DEFAULT_KEEP: usize = 3;
+
+const ID_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+const ID_LEN: usize = "20240101T093000Z".len();
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LiveBackup {
+    pub id: String,
+    /// The account that was active in it, when one was
+    pub account: Option<String>,
+    pub path: PathBuf,
+}
+
+impl LiveBackup {
+    /// When it was set aside, recovered from the id.
+    pub fn replaced_at(&self) -> Option<DateTime<Utc>> {
+        let stamp = self.id.get(..ID_LEN)?;
+        NaiveDateTime::parse_from_str(stamp, ID_FORMAT)
+            .ok()
+            .map(|time| time.and_utc())
+    }
+
+    // Two set aside within the same second get `-2`, `-3`, ...
+    fn sequence(&self) -> u32 {
+        self.id
+            .get(ID_LEN..)
+            .and_then(|rest| rest.strip_prefix('-'))
+            .and_then(|rest| rest.parse().ok())
+            .unwrap_or(1)
+    }
+}
+
+/// Every backup in `area`, newest first. A symlink counts: it's what the
+/// live directory was in symlink mode.
+pub fn list(area: &Path) -> Result<Vec<LiveBackup>> {
+    if !area.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(area).context("Failed to read live backups")? {
+        let entry = entry.context("Failed to read live backups")?;
+        let file_type = entry.file_type()?;
+        if !file_type.is_dir() && !file_type.is_symlink() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let (id, account) = match name.split_once('_') {
+            Some((id, account)) => (id.to_string(), Some(unescape(account))),
+            None => (name, None),
+        };
+        backups.push(LiveBackup {
+            id,
+            account,
+            path: entry.path(),
+        });
+    }
+    backups.sort_by(|a, b| {
+        (b.replaced_at(), b.sequence())
+            .cmp(&(a.replaced_at(), a.sequence()))
+            .then_with(|| b.id.cmp(&a.id))
+    });
+    Ok(backups)
+}
+
+/// Picks an unused path in `area` for the live directory `account` was
+/// active in, set aside at `at`.
+pub fn allocate(area: &Path, at: DateTime<Utc>, account: Option<&str>) -> Result<PathBuf> {
+    fs::create_dir_all(area).context("Failed to create live backups directory")?;
+    let stamp = at.format(ID_FORMAT).to_string();
+    // After the last of the same second, so it still sorts as the newest
+    let last = list(area)?
+        .iter()
+        .filter(|backup| backup.id.starts_with(&stamp))
+        .map(LiveBackup::sequence)
+        .max();
+    let id = match last {
+        Some(last) => format!("{}-{}", stamp, last + 1),
+        None => stamp,
+    };
+    Ok(area.join(match account {
+        Some(account) => format!("{}_{}", id, escape(account)),
+        None => id,
+    }))
+}
+
+/// The id `list` gives the backup at `path`.
+pub fn id(path: &Path) -> Option<String> {
+    let name = path.file_name()?.to_string_lossy();
+    Some(match name.split_once('_') {
+        Some((id, _)) => id.to_string(),
+        None => name.into_owned(),
+    })
+}
+
+/// Removes all but the newest `keep`.
+pub fn prune(area: &Path, keep: usize) -> Result<()> {
+    for backup in list(area)?.into_iter().skip(keep) {
+        let path = &backup.path;
+        match path.is_symlink() {
+            true => platform::remove_symlink(path),
+            false => retry(|| fs::remove_dir_all(path)),
+        }
+        .context("Failed to remove old live backup")?;
+    }
+    Ok(())
+}
+
+// Account names may contain `/`
+fn escape(account: &str) -> String {
+    account.replace('%', "%25").replace('/', "%2F")
+}
+
+fn unescape(account: &str) -> String {
+    account.replace("%2F", "/").replace("%25", "%")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_newest_first_and_pruned() -> Result<()> {
+        let dir = TempDir::new()?;
+        let area = dir.path().join("area");
+        let at = |second| Utc.with_ymd_and_hms(2024, 1, 1, 9, 30, second).unwrap();
+
+        for (second, account) in [(0, Some("work")), (5, None), (5, Some("client/dev"))] {
+            fs::create_dir_all(allocate(&area, at(second), account)?)?;
+        }
+        let backups = list(&area)?;
+        let summary: Vec<_> = backups
+            .iter()
+            .map(|backup| (backup.id.as_str(), backup.account.as_deref()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("20240101T093005Z-2", Some("client/dev")),
+                ("20240101T093005Z", None),
+                ("20240101T093000Z", Some("work")),
+            ]
+        );
+        assert_eq!(backups[0].replaced_at(), Some(at(5)));
+
+        // Numbered past the newest even when an earlier number is free
+        fs::remove_dir_all(&backups[1].path)?;
+        let path = allocate(&area, at(5), None)?;
+        assert!(path.ends_with("20240101T093005Z-3"));
+
+        prune(&area, 1)?;
+        assert_eq!(list(&area)?.len(), 1);
+        Ok(())
+    }
+}
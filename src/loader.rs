@@ -0,0 +1,57 @@
+//! Background reads of the slower parts of an account summary, so a
+//! dashboard can draw the account list straight from the config and fill in
+//! the rest as it arrives, even with large snapshots on a slow disk.
+
+use crate::manager::{AccountLogin, AccountManager, AccountSummary};
+use crate::size::{self, DirUsage};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::thread::{self, Scope};
+
+/// One detail read for the named account.
+#[derive(Debug)]
+pub enum Loaded {
+    Login(String, AccountLogin),
+    Usage(String, DirUsage),
+}
+
+/// Starts reading login details and sizes for `accounts`, in order, and
+/// returns the channel they arrive on. Dropping the receiver stops both
+/// readers once they finish the account they're on.
+///
+/// Login details are a couple of small files per account and need the
+/// manager, so they're read on a thread in `scope`. Sizes walk whole
+/// snapshots and run detached instead, so quitting never waits on a walk.
+pub fn spawn<'scope, 'env>(
+    scope: &'scope Scope<'scope, 'env>,
+    manager: &'env AccountManager,
+    accounts: &[AccountSummary],
+) -> Receiver<Loaded> {
+    let (tx, rx) = mpsc::channel();
+    let accounts: Vec<(String, PathBuf)> = accounts
+        .iter()
+        .map(|account| (account.name.clone(), account.path.clone()))
+        .collect();
+
+    let usage_tx = tx.clone();
+    let paths = accounts.clone();
+    thread::spawn(move || {
+        for (name, path) in paths {
+            let usage = size::dir_usage(&path).unwrap_or_default();
+            if usage_tx.send(Loaded::Usage(name, usage)).is_err() {
+                break;
+            }
+        }
+    });
+
+    scope.spawn(move || {
+        for (name, path) in accounts {
+            let login = manager.account_login(&name, &path);
+            if tx.send(Loaded::Login(name, login)).is_err() {
+                break;
+            }
+        }
+    });
+
+    rx
+}
@@ -0,0 +1,143 @@
+//! An advisory lock on the state directory, held while a command changes
+//! accounts, so two instances (say, two terminals and a prompt hook) can't
+//! interleave their writes.
+
+use crate::crash;
+use crate::error::AccountError;
+use crate::paths;
+use crate::tr;
+use anyhow::{Context, Result};
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::Write;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const FILE: &str = "lock";
+
+const POLL: Duration = Duration::from_millis(100);
+
+/// How long to wait when another instance holds the lock.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Wait {
+    Never,
+    For(Duration),
+    Forever,
+}
+
+impl Default for Wait {
+    /// Long enough for any ordinary switch to finish.
+    fn default() -> Self {
+        Self::For(Duration::from_secs(30))
+    }
+}
+
+/// Released when dropped, or when the process exits however it exits.
+#[derive(Debug)]
+pub struct StateLock {
+    _file: File,
+}
+
+/// Takes the lock on `path`, waiting as `wait` allows.
+pub fn acquire(path: &Path, wait: Wait) -> Result<StateLock> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .read(true)
+        .write(true)
+        .open(path)
+        .with_context(|| format!("Failed to open lock file: {}", path.display()))?;
+
+    let started = Instant::now();
+    let mut announced = false;
+    loop {
+        match file.try_lock() {
+            Ok(()) => break,
+            Err(TryLockError::WouldBlock) => {}
+            Err(TryLockError::Error(e)) => {
+                return Err(e).with_context(|| format!("Failed to lock {}", path.display()));
+            }
+        }
+
+        let holder = holder(path);
+        match wait {
+            Wait::Never => return Err(AccountError::LockHeld(holder).into()),
+            Wait::For(limit) if started.elapsed() >= limit => {
+                return Err(AccountError::LockTimeout(holder, limit.as_secs()).into());
+            }
+            _ => {}
+        }
+        if !announced {
+            eprintln!("{}", tr!("lock-waiting", holder = holder));
+            announced = true;
+        }
+        thread::sleep(POLL);
+    }
+
+    // Who holds it, for the message another instance shows while it waits
+    let args = crash::redact_args(std::env::args().skip(1), paths::home_dir().as_deref());
+    file.set_len(0)
+        .and_then(|()| writeln!(file, "pid {}: {}", std::process::id(), args.join(" ")))
+        .context("Failed to write lock file")?;
+    Ok(StateLock { _file: file })
+}
+
+/// Who holds the lock on `path`, or `None` when it's free (or has never been
+/// taken). Only looks: a free lock is let go again at once.
+pub fn held_by(path: &Path) -> Option<String> {
+    let file = File::open(path).ok()?;
+    match file.try_lock_shared() {
+        Err(TryLockError::WouldBlock) => Some(holder(path)),
+        _ => None,
+    }
+}
+
+/// What the lock file says about the instance holding it. Windows doesn't
+/// let it be read while locked.
+fn holder(path: &Path) -> String {
+    fs::read_to_string(path)
+        .ok()
+        .map(|contents| contents.trim().to_string())
+        .filter(|holder| !holder.is_empty())
+        .unwrap_or_else(|| tr!("lock-holder-unknown"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_second_lock_is_refused_until_released() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join(FILE);
+
+        let held = acquire(&path, Wait::Never)?;
+        let error = acquire(&path, Wait::Never).unwrap_err().to_string();
+        if cfg!(unix) {
+            assert!(error.contains(&format!("pid {}", std::process::id())));
+        }
+        assert!(acquire(&path, Wait::For(Duration::from_millis(150))).is_err());
+
+        assert!(held_by(&path).is_some());
+        drop(held);
+        assert_eq!(held_by(&path), None);
+        acquire(&path, Wait::Never)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_waiting_picks_up_a_released_lock() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join(FILE);
+        let held = acquire(&path, Wait::Never)?;
+
+        let releaser = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(200));
+            drop(held);
+        });
+        acquire(&path, Wait::Forever)?;
+        releaser.join().unwrap();
+        Ok(())
+    }
+}
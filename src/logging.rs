@@ -0,0 +1,46 @@
+//! Diagnostic logging on stderr, for finding out what a save, switch or
+//! delete did to the files. Nothing is logged unless asked for: `-v` logs
+//! each step (a snapshot kept, the live directory moved aside), `-vv` each
+//! file copied or removed as well, and `-vvv` the files skipped too.
+
+use std::io::{self, IsTerminal};
+use tracing::Level;
+use tracing_subscriber::fmt::time;
+
+/// The most detailed level logged for `-v` given `verbose` times.
+pub fn level(verbose: u8) -> Option<Level> {
+    match verbose {
+        0 => None,
+        1 => Some(Level::INFO),
+        2 => Some(Level::DEBUG),
+        _ => Some(Level::TRACE),
+    }
+}
+
+/// Starts logging at the level `verbose` asks for, if any.
+pub fn init(verbose: u8) {
+    let Some(level) = level(verbose) else {
+        return;
+    };
+    // Time since start rather than the clock, to see where a switch is slow
+    let _ = tracing_subscriber::fmt()
+        .with_max_level(level)
+        .with_writer(io::stderr)
+        .with_ansi(io::stderr().is_terminal())
+        .with_target(false)
+        .with_timer(time::uptime())
+        .try_init();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_level_per_verbosity() {
+        assert_eq!(level(0), None);
+        assert_eq!(level(1), Some(Level::INFO));
+        assert_eq!(level(2), Some(Level::DEBUG));
+        assert_eq!(level(5), Some(Level::TRACE));
+    }
+}
@@ -1,26 +1,683 @@
-mod cli;
-mod config;
-mod error;
-mod manager;
-
 use anyhow::Result;
-use clap::Parser;
-use cli::{Cli, Commands};
-use manager::AccountManager;
-
-fn main() -> Result<()> {
-    let cli = Cli::parse();
-    let manager = AccountManager::new()?;
-
-    match cli.command {
-        Some(Commands::Save { name }) => manager.save_account(&name),
-        Some(Commands::Switch { name }) => manager.switch_account(&name),
-        Some(Commands::List) => manager.list_accounts(),
-        Some(Commands::Delete { name }) => manager.delete_account(&name),
-        Some(Commands::Rename { old_name, new_name }) => {
+use clap::FromArgMatches;
+use claude_account_switcher::cli::{
+    AddArgs, AddExistingArgs, AdoptArgs, AliasAction, AliasArgs, ArchiveArgs, AutoArgs,
+    BackupAction, BackupArgs, BenchArgs, BindArgs, CatArgs, CheckArgs, CheckpointAction,
+    CheckpointArgs, ChecksumArgs, ClearClipboardArgs, Cli, Commands, CompletionsArgs, ConfigArgs,
+    CopyArgs, DaemonAction, DaemonArgs, DebugBundleArgs, DeleteArgs, DiffArgs, DocsAction,
+    DocsArgs, DoctorArgs, EncryptAction, EncryptArgs, EnsureBoundArgs, EnvArgs, EnvfileArgs,
+    ExistsArgs, ExportArgs, FindArgs, Format, GcArgs, GrepArgs, HistoryArgs, HookArgs, HookEnvArgs,
+    ImportArgs, ImportFromArgs, InfoArgs, InspectArgs, IsCurrentArgs, LimitHitArgs, ListArgs,
+    ListSort, LockArgs, LogoutArgs, LsArgs, MenuArgs, MergeArgs, MigrateArgs, MigrateLegacyArgs,
+    NoteArgs, PinArgs, PresetAction, PresetArgs, ProfileAction, ProfileArgs, ProjectAction,
+    ProjectArgs, PromptArgs, ProtectArgs, PruneArgs, RecoverArgs, RecoverLiveArgs, RemoteArgs,
+    RenameArgs, RenewArgs, RepairArgs, ResaveArgs, RestoreArgs, RollbackArgs, RotateArgs, RunArgs,
+    SaveArgs, ServiceAction, ServiceArgs, ShareArgs, ShellArgs, SizeArgs, StatusArgs, SuggestArgs,
+    SwitchArgs, SyncAction, SyncArgs, TagAction, TagArgs, TemplateAction, TemplateArgs, TokenArgs,
+    TrashAction, TrashArgs, UnarchiveArgs, UnlockArgs, UpgradeConfigArgs, VarAction, VarArgs,
+    VerifyArgs, WrapperArgs,
+};
+use claude_account_switcher::history::EventSource;
+use claude_account_switcher::interrupt::Recovery;
+use claude_account_switcher::manager::{AccountManager, ListFilter, Unsaved};
+use claude_account_switcher::output::{
+    self, ColorChoice, ErrorJson, OutputFormat, TimingsJson, Tone,
+};
+use claude_account_switcher::paths::Paths;
+use claude_account_switcher::prompt::Unattended;
+use claude_account_switcher::settings::Settings;
+use claude_account_switcher::{
+    binding, clipboard, completions, configure, crash, docs, error, hook, init, lock, logging,
+    remote, retry, segment, timings, tui, wrapper,
+};
+use std::process::ExitCode;
+use std::time::Duration;
+
+// The whole command line is built before anything is parsed, which takes more
+// stack in a debug build than the main thread has on Windows (1 MB)
+const STACK_SIZE: usize = 8 << 20;
+// What Rust exits with after a panic, left to the crash report to explain
+const EXIT_PANIC: u8 = 101;
+
+fn main() -> ExitCode {
+    std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(start)
+        .map_or(ExitCode::FAILURE, |thread| {
+            thread.join().unwrap_or(ExitCode::from(EXIT_PANIC))
+        })
+}
+
+fn start() -> ExitCode {
+    // Parsed with the command `docs` adds examples to, so `--help` shows them
+    let parsed = docs::command()
+        .try_get_matches()
+        .and_then(|matches| Cli::from_arg_matches(&matches));
+    let cli = match parsed {
+        Ok(cli) => cli,
+        // `--help` and `--version` end up here too, and aren't failures
+        Err(e) => {
+            let _ = e.print();
+            return ExitCode::from(if e.use_stderr() { error::EXIT_USAGE } else { 0 });
+        }
+    };
+    let format = if cli.json {
+        OutputFormat::Json
+    } else if cli.plain {
+        OutputFormat::Plain
+    } else {
+        cli.format
+            .map_or_else(OutputFormat::default, Format::output)
+    };
+    logging::init(cli.verbose);
+    output::init_color(cli.color.unwrap_or_else(|| match format {
+        OutputFormat::Plain => ColorChoice::Never,
+        _ => configured_color().unwrap_or_default(),
+    }));
+
+    if cli.timings {
+        timings::enable();
+    }
+
+    let result = run(cli, format);
+    // However the command ended, to see what a failing one spent its time on
+    if let Some((phases, total)) = timings::report() {
+        let timings = TimingsJson::new(&phases, total);
+        match format {
+            OutputFormat::Json => {
+                let _ = output::eprint_json(&timings);
+            }
+            _ => {
+                for line in format.timings(&timings) {
+                    eprintln!("{}", line);
+                }
+            }
+        }
+    }
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        // Scripts branch on the exit code, and with `--json` on the kind
+        Err(e) => {
+            if format.is_json() {
+                let _ = output::eprint_json(&ErrorJson::new(&e));
+            } else {
+                eprintln!("{} {:?}", output::paint_stderr("Error:", Tone::Error), e);
+            }
+            ExitCode::from(error::exit_code(&e))
+        }
+    }
+}
+
+/// The `color` setting. Read ahead of everything else, so a settings file
+/// that doesn't load is left for the command to report.
+fn configured_color() -> Option<ColorChoice> {
+    let paths = Paths::current().ok()?;
+    let settings = Settings::load(&paths.settings_file).ok()?;
+    settings.with_env(std::env::vars()).ok()?.color
+}
+
+/// The login `add` and `renew` run: `claude /login` unless one was given.
+fn login_command(login: Vec<String>) -> Vec<String> {
+    match login.is_empty() {
+        true => vec!["claude".to_string(), "/login".to_string()],
+        false => login,
+    }
+}
+
+fn run(cli: Cli, format: OutputFormat) -> Result<()> {
+    let document = cli.document()?;
+    // Completions don't touch the account store, so skip creating it
+    if let Some(Commands::Completions(CompletionsArgs { shell, install })) = cli.command {
+        return completions::run(shell, install);
+    }
+    if let Some(Commands::Docs(DocsArgs {
+        action: DocsAction::Man { out },
+    })) = &cli.command
+    {
+        return docs::run_man(out.as_deref());
+    }
+    if let Some(Commands::ClearClipboard(ClearClipboardArgs { after, digest })) = &cli.command {
+        return clipboard::clear(Duration::from_secs(*after), digest);
+    }
+    // The store it works on is on the other machine
+    if let Some(Commands::Remote(RemoteArgs {
+        host,
+        program,
+        command,
+    })) = &cli.command
+    {
+        std::process::exit(remote::run(host, program, command, cli.yes)?);
+    }
+    if let Some(Commands::Wrapper(WrapperArgs { shell, warn })) = cli.command {
+        return wrapper::run(shell, warn);
+    }
+    // Prompts run this before every command, so it skips the manager setup
+    // (and the directories that creates) entirely
+    if let Some(Commands::Prompt(PromptArgs { template })) = &cli.command {
+        segment::run(template);
+        return Ok(());
+    }
+    if let Some(Commands::Hook(HookArgs { shell, warn })) = cli.command {
+        return hook::run(shell, warn);
+    }
+    if let Some(Commands::HookEnv(HookEnvArgs { shell })) = cli.command {
+        return hook::run_env(shell);
+    }
+    // The hook runs on every `cd`; outside a bound project there's nothing
+    // to load
+    if let Some(Commands::Auto(..) | Commands::EnsureBound(..)) = cli.command
+        && binding::nearest(&std::env::current_dir()?, &binding::projects())?.is_none()
+    {
+        return Ok(());
+    }
+
+    // Sets up the manager itself once the settings are written
+    if let Some(Commands::Init) = cli.command {
+        return init::run(cli.claude_dir.as_deref(), format);
+    }
+    // Works on the settings file alone, so a broken one can still be fixed
+    if let Some(Commands::Config(ConfigArgs { action })) = cli.command {
+        if !configure::run(action, format)? {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    let lock_wait = if cli.wait {
+        lock::Wait::Forever
+    } else if cli.no_wait {
+        lock::Wait::Never
+    } else {
+        lock::Wait::default()
+    };
+    let mut manager = AccountManager::open(cli.claude_dir.as_deref(), cli.system)?
+        .with_format(format)
+        .with_lock_wait(lock_wait)
+        .with_assume_yes(cli.yes)
+        .with_quiet(cli.quiet);
+    crash::install(manager.state_dir().join("crashes"));
+    // Keep stdout for the command's own output
+    if let Some(
+        Commands::Run(..)
+        | Commands::Shell(..)
+        | Commands::McpServe
+        | Commands::Api
+        | Commands::Profile(ProfileArgs {
+            action: ProfileAction::Use { write: false, .. },
+        }),
+    ) = &cli.command
+    {
+        manager = manager.with_status_to_stderr(true);
+    }
+    // Their stdin is a protocol or nothing, not the user
+    if let Some(
+        Commands::McpServe
+        | Commands::Api
+        | Commands::Daemon(DaemonArgs {
+            action: DaemonAction::Run,
+        }),
+    ) = &cli.command
+    {
+        manager = manager.with_prompter(Unattended);
+    }
+    // Run from a hotkey with `--yes`, nothing is left to ask: what would still
+    // need an answer (the store's passphrase) fails instead of waiting on a
+    // terminal that isn't there
+    if cli.yes && matches!(cli.command, Some(Commands::Menu(..))) {
+        manager = manager.with_prompter(Unattended);
+    }
+    // Only an explicit switch stops for unsaved changes; the hooks, `run` and
+    // the dashboard save them as before
+    if let Some(Commands::Switch(SwitchArgs {
+        auto_save,
+        no_autosave,
+        force,
+        ..
+    })) = &cli.command
+    {
+        manager = manager.with_unsaved(if *force {
+            Unsaved::Discard
+        } else if *auto_save {
+            Unsaved::Save
+        } else {
+            Unsaved::Refuse
+        });
+        manager = manager.with_autosave(match (auto_save, no_autosave) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        });
+    }
+    if let Some(
+        Commands::Save(SaveArgs { force: true, .. })
+        | Commands::Delete(DeleteArgs { force: true, .. })
+        | Commands::Prune(PruneArgs { force: true, .. })
+        | Commands::Merge(MergeArgs { force: true, .. })
+        | Commands::Import(ImportArgs { force: true, .. })
+        | Commands::ImportFrom(ImportFromArgs { force: true, .. })
+        | Commands::Sync(SyncArgs {
+            action: SyncAction::Pull { force: true },
+        })
+        | Commands::Checkpoint(CheckpointArgs {
+            action: CheckpointAction::Save { force: true, .. },
+        }),
+    ) = &cli.command
+    {
+        manager = manager.with_force(true);
+    }
+    if let Some(Commands::Save(SaveArgs {
+        overwrite: true, ..
+    })) = &cli.command
+    {
+        manager = manager.with_overwrite(true);
+    }
+    if let Some(
+        Commands::Save(SaveArgs { capture, .. }) | Commands::Switch(SwitchArgs { capture, .. }),
+    ) = &cli.command
+    {
+        manager = manager.with_capture_overrides(&capture.include, &capture.exclude)?;
+    }
+    // Before anything else works on a half-copied `~/.claude`
+    if !matches!(cli.command, Some(Commands::Recover(..))) {
+        manager.offer_recovery()?;
+    }
+
+    let result = match cli.command {
+        Some(Commands::Save(SaveArgs { name, store, .. })) => {
+            manager.save_account_in(&name, store.as_deref())
+        }
+        Some(Commands::Switch(SwitchArgs {
+            name,
+            here,
+            mode,
+            preview,
+            ..
+        })) => match name {
+            Some(name) if preview => manager.preview_switch(&name, mode),
+            Some(name) => manager.switch_account(&name, mode),
+            None if here => manager.switch_here(&std::env::current_dir()?, mode),
+            None => manager.switch_interactive(mode),
+        },
+        Some(Commands::List(ListArgs {
+            group_by,
+            tags,
+            sort,
+            recent,
+            long,
+            columns,
+            stale_days,
+        })) => {
+            let sort = if recent { ListSort::LastUsed } else { sort };
+            let filter = ListFilter {
+                tags: &tags,
+                stale_days,
+            };
+            manager.list_accounts(group_by, filter, sort, long, &columns, document)
+        }
+        Some(Commands::Size(SizeArgs { name, jobs })) => manager.show_sizes(name.as_deref(), jobs),
+        Some(Commands::Add(AddArgs { name, login })) => {
+            manager.add_account(&name, &login_command(login))
+        }
+        Some(Commands::Renew(RenewArgs { name, login })) => {
+            manager.renew(&name, &login_command(login))
+        }
+        Some(Commands::Logout(LogoutArgs { all, keep_settings })) => {
+            manager.logout(all, keep_settings)
+        }
+        Some(Commands::AddExisting(AddExistingArgs { name, path })) => {
+            manager.add_existing(&name, &path)
+        }
+        // Without a path, clap has made sure `--scan` was given
+        Some(Commands::Adopt(AdoptArgs { path, name, .. })) => match path {
+            Some(path) => manager.adopt(&path, name.as_deref()),
+            None => manager.adopt_all(),
+        },
+        Some(Commands::Delete(DeleteArgs {
+            names, keep_data, ..
+        })) => manager.delete_accounts(&names, keep_data),
+        Some(Commands::Protect(ProtectArgs { name, off })) => manager.set_protected(&name, !off),
+        Some(Commands::Pin(PinArgs {
+            name,
+            claude_version,
+            off,
+        })) => manager.pin(&name, claude_version.as_deref(), off),
+        Some(Commands::Lock(LockArgs { name })) => manager.set_locked(&name, true),
+        Some(Commands::Unlock(UnlockArgs { name })) => manager.set_locked(&name, false),
+        Some(Commands::Token(TokenArgs {
+            name,
+            reveal,
+            copy,
+            clear_after,
+        })) => manager.show_token(
+            &name,
+            reveal,
+            copy.then(|| Duration::from_secs(clear_after)),
+        ),
+        Some(Commands::Envfile(EnvfileArgs { name, format })) => {
+            manager.print_envfile(&name, format)
+        }
+        Some(Commands::Archive(ArchiveArgs { name })) => manager.archive_account(&name),
+        Some(Commands::Unarchive(UnarchiveArgs { name })) => manager.unarchive_account(&name),
+        Some(Commands::LimitHit(LimitHitArgs {
+            name,
+            resets_in,
+            clear,
+        })) => manager.limit_hit(name.as_deref(), resets_in.as_deref(), clear),
+        Some(Commands::Rotate(RotateArgs { group })) => manager.rotate(group.as_deref()),
+        Some(Commands::Audit) => manager.audit(),
+        Some(Commands::Suggest(SuggestArgs {
+            group,
+            online,
+            switch,
+        })) => manager.suggest(group.as_deref(), online, switch),
+        Some(Commands::Menu(MenuArgs { via, command })) => manager.menu(via, command.as_deref()),
+        Some(Commands::Note(NoteArgs {
+            name,
+            text,
+            edit,
+            clear,
+        })) => manager.note_account(&name, text.as_deref(), edit, clear),
+        Some(Commands::Tag(TagArgs { action })) => match action {
+            TagAction::Add { name, tags } => manager.tag_account(&name, &tags, true),
+            TagAction::Remove { name, tags } => manager.tag_account(&name, &tags, false),
+        },
+        Some(Commands::Var(VarArgs { action })) => match action {
+            VarAction::Set { name, vars } => manager.set_vars(&name, &vars),
+            VarAction::Unset { name, vars } => manager.unset_vars(&name, &vars),
+            VarAction::List { name } => manager.list_vars(&name),
+        },
+        Some(Commands::Alias(AliasArgs { action })) => match action {
+            AliasAction::Set { alias, name } => manager.set_alias(&alias, &name),
+            AliasAction::Remove { alias } => manager.remove_alias(&alias),
+        },
+        Some(Commands::Copy(CopyArgs { source, name })) => manager.copy_account(&source, &name),
+        Some(Commands::Rename(RenameArgs { old_name, new_name })) => {
             manager.rename_account(&old_name, &new_name)
         }
-        Some(Commands::Current) => manager.show_current(),
+        Some(Commands::Run(RunArgs { name, command })) => match manager.run_as(&name, &command) {
+            Ok(code) => std::process::exit(code),
+            Err(e) => Err(e),
+        },
+        Some(Commands::Env(EnvArgs { name, shell })) => manager.print_env(&name, shell),
+        Some(Commands::Sessions) => manager.list_sessions(),
+        Some(Commands::McpServe) => manager.mcp_serve(),
+        Some(Commands::Api) => manager.api_serve(),
+        Some(Commands::Daemon(DaemonArgs { action })) => match action {
+            DaemonAction::Start => manager.daemon_start(),
+            DaemonAction::Stop => manager.daemon_stop(),
+            DaemonAction::Status => manager.daemon_status(),
+            DaemonAction::Run => manager.daemon_run(),
+        },
+        Some(Commands::Service(ServiceArgs { action })) => match action {
+            ServiceAction::Install {
+                daemon,
+                backup,
+                keep,
+            } => manager.service_install(daemon, backup, keep),
+            ServiceAction::Uninstall { daemon, backup } => {
+                manager.service_uninstall(daemon, backup)
+            }
+            ServiceAction::Status => manager.service_status(),
+        },
+        Some(Commands::Shell(ShellArgs { name })) => match manager.shell_as(&name) {
+            Ok(code) => std::process::exit(code),
+            Err(e) => Err(e),
+        },
+        Some(Commands::Status(StatusArgs {
+            porcelain,
+            when_stale_only,
+        })) => manager.status(porcelain, when_stale_only),
+        Some(Commands::Diff(DiffArgs {
+            name,
+            other,
+            content,
+        })) => {
+            let same = match (name, other) {
+                (Some(name), Some(other)) => manager.diff_accounts(&name, &other, content),
+                (name, _) => manager.diff_live(name.as_deref(), content),
+            };
+            match same {
+                // Like diff(1), differences exit with 1
+                Ok(false) => std::process::exit(1),
+                result => result.map(drop),
+            }
+        }
+        Some(Commands::Cat(CatArgs { name, path, reveal })) => {
+            manager.cat_snapshot(&name, &path, reveal)
+        }
+        Some(Commands::Ls(LsArgs { name, subdir })) => {
+            manager.list_snapshot(&name, subdir.as_deref())
+        }
+        Some(Commands::Merge(MergeArgs {
+            source,
+            dest,
+            only,
+            live,
+            dry_run,
+            ..
+        })) => manager.merge_files(&source, &dest, &only, live, dry_run),
+        Some(Commands::Restore(RestoreArgs { name, files, to })) => {
+            manager.restore_files(&name, &files, to.as_deref())
+        }
+        Some(Commands::Current) => manager.show_current(cli.verbose > 0),
+        // Answered by the exit status alone, like test(1)
+        Some(Commands::Exists(ExistsArgs { name })) => match manager.account_exists(&name) {
+            Ok(false) => std::process::exit(1),
+            result => result.map(drop),
+        },
+        Some(Commands::IsCurrent(IsCurrentArgs { name })) => match manager.is_current(&name) {
+            Ok(false) => std::process::exit(1),
+            result => result.map(drop),
+        },
+        Some(Commands::Info(InfoArgs { name })) => manager.show_info(&name),
+        Some(Commands::Tui) => tui::run(&manager),
+        Some(Commands::History(HistoryArgs {
+            name: Some(name),
+            limit,
+            ..
+        })) => manager.show_versions(&name, limit),
+        Some(Commands::History(HistoryArgs {
+            name: None,
+            source,
+            account,
+            limit,
+        })) => manager.show_history(source, account.as_deref(), limit),
+        Some(Commands::Rollback(RollbackArgs { name, to })) => {
+            manager.rollback(&name, to.as_deref())
+        }
+        Some(Commands::Undo) => manager.undo(),
+        Some(Commands::RecoverLive(RecoverLiveArgs { id, list })) => match list {
+            true => manager.list_live_backups(),
+            false => manager.recover_live(id.as_deref()),
+        },
+        Some(Commands::Recover(RecoverArgs { resume, rollback })) => manager.recover(if resume {
+            Some(Recovery::Resume)
+        } else if rollback {
+            Some(Recovery::Rollback)
+        } else {
+            None
+        }),
+        Some(Commands::Checkpoint(CheckpointArgs { action })) => match action {
+            CheckpointAction::Save { label, .. } => manager.save_checkpoint(&label),
+            CheckpointAction::Restore { label } => manager.restore_checkpoint(&label),
+            CheckpointAction::List => manager.list_checkpoints(),
+            CheckpointAction::Delete { label } => manager.delete_checkpoint(&label),
+        },
+        Some(Commands::Trash(TrashArgs { action })) => match action {
+            TrashAction::List => manager.list_trash(),
+            TrashAction::Restore { name } => manager.restore_trashed(&name),
+            TrashAction::Empty { older_than } => manager.empty_trash(older_than.as_deref()),
+        },
+        Some(Commands::Backup(BackupArgs { action })) => match action {
+            BackupAction::Create { output } => manager.create_backup(output.as_deref()),
+            BackupAction::Restore { file } => manager.restore_backup(&file),
+            BackupAction::Auto { keep, output } => {
+                manager.auto_backup(output.as_deref(), keep as usize)
+            }
+        },
+        Some(Commands::Sync(SyncArgs { action })) => match action {
+            SyncAction::Init { remote } => manager.sync_init(&remote),
+            SyncAction::Push => manager.sync_push(),
+            SyncAction::Pull { .. } => manager.sync_pull(),
+        },
+        Some(Commands::Share(ShareArgs {
+            name,
+            expires,
+            output,
+        })) => manager.share_account(&name, &expires, output.as_deref()),
+        Some(Commands::Export(ExportArgs {
+            names,
+            all: _,
+            output,
+            encrypt,
+            passphrase,
+            redacted,
+        })) => manager.export_accounts(
+            &names,
+            output.as_deref(),
+            encrypt,
+            passphrase.as_deref(),
+            redacted,
+        ),
+        Some(Commands::ImportFrom(ImportFromArgs {
+            tool,
+            path,
+            dry_run,
+            on_conflict,
+            ..
+        })) => manager.import_from(tool, path.as_deref(), dry_run, on_conflict),
+        Some(Commands::Import(ImportArgs {
+            file,
+            name,
+            passphrase,
+            dry_run,
+            on_conflict,
+            ..
+        })) => manager.import_account(
+            &file,
+            name.as_deref(),
+            passphrase.as_deref(),
+            dry_run,
+            on_conflict,
+        ),
+        Some(Commands::Doctor(DoctorArgs {
+            capabilities: true, ..
+        })) => manager.show_capabilities(),
+        Some(Commands::Doctor(DoctorArgs { fix, .. })) => match manager.doctor(fix) {
+            // The findings are already printed; only the exit status is left
+            Ok(false) => std::process::exit(1),
+            result => result.map(drop),
+        },
+        Some(Commands::DebugBundle(DebugBundleArgs { output })) => {
+            manager.debug_bundle(output.as_deref())
+        }
+        Some(Commands::UpgradeConfig(UpgradeConfigArgs { dry_run })) => {
+            manager.upgrade_config(dry_run)
+        }
+        Some(Commands::Repair(RepairArgs { relink, dry_run })) => manager.repair(&relink, dry_run),
+        Some(Commands::Migrate(MigrateArgs { dry_run })) => manager.migrate(dry_run),
+        Some(Commands::MigrateLegacy(MigrateLegacyArgs { from })) => {
+            manager.migrate_legacy(from.as_deref())
+        }
+        Some(Commands::Inspect(InspectArgs { file, passphrase })) => {
+            manager.inspect_bundle(&file, passphrase.as_deref())
+        }
+        Some(Commands::Find(FindArgs { email, grep })) => {
+            manager.find_accounts(email.as_deref(), grep.as_deref())
+        }
+        Some(Commands::Grep(GrepArgs {
+            pattern,
+            account,
+            ignore_case,
+        })) => manager.grep_accounts(&pattern, account.as_deref(), ignore_case),
+        Some(Commands::Checksum(ChecksumArgs { name, check, jobs })) => {
+            manager.checksum_account(&name, check.as_deref(), jobs)
+        }
+        Some(Commands::Verify(VerifyArgs { names, all, repair })) => {
+            manager.verify(&names, all, repair)
+        }
+        Some(Commands::Check(CheckArgs { names, all, online })) => {
+            manager.check_logins(&names, all, online)
+        }
+        Some(Commands::Gc(GcArgs {
+            dry_run,
+            orphans: true,
+            adopt,
+        })) => manager.collect_orphans(adopt, dry_run),
+        Some(Commands::Gc(GcArgs { dry_run, .. })) => manager.collect_garbage(dry_run),
+        Some(Commands::Prune(PruneArgs {
+            dry_run,
+            keep_versions,
+            unused_after,
+            strip,
+            ..
+        })) => manager.prune(keep_versions, unused_after.as_deref(), &strip, dry_run),
+        Some(Commands::Template(TemplateArgs { action })) => match action {
+            TemplateAction::Save { name, from } => manager.save_template(&name, from.as_deref()),
+            TemplateAction::Apply { name } => manager.apply_template(&name),
+            TemplateAction::List => manager.list_templates(),
+            TemplateAction::Delete { name } => manager.delete_template(&name),
+        },
+        Some(Commands::Preset(PresetArgs { action })) => match action {
+            PresetAction::Export { output } => manager.export_preset(output.as_deref()),
+            PresetAction::Import { file } => manager.import_preset(&file),
+        },
+        Some(Commands::Profile(ProfileArgs { action })) => match action {
+            ProfileAction::Create {
+                name,
+                account,
+                env,
+                force,
+            } => manager.create_profile(&name, &account, &env, force),
+            ProfileAction::Use { name, shell, write } => manager.use_profile(&name, shell, write),
+            ProfileAction::List => manager.list_profiles(),
+            ProfileAction::Delete { name } => manager.delete_profile(&name),
+        },
+        Some(Commands::Encrypt(EncryptArgs { action })) => match action {
+            EncryptAction::Enable => manager.enable_encryption(),
+            EncryptAction::Disable => manager.disable_encryption(),
+        },
+        Some(Commands::Bench(BenchArgs { files, file_size })) => manager.bench(files, file_size),
+        Some(Commands::Resave(ResaveArgs { name, after })) => {
+            manager.resave_if_current(&name, Duration::from_secs(after))
+        }
+        Some(Commands::Bind(BindArgs { name })) => manager.bind(&std::env::current_dir()?, &name),
+        Some(Commands::Project(ProjectArgs { action })) => match action {
+            ProjectAction::Set { name, path } => {
+                manager.set_project(&path.map_or_else(std::env::current_dir, Ok)?, &name)
+            }
+            ProjectAction::Unset { path } => {
+                manager.unset_project(&path.map_or_else(std::env::current_dir, Ok)?)
+            }
+            ProjectAction::List => manager.list_projects(),
+        },
+        Some(Commands::EnsureBound(EnsureBoundArgs { warn })) => {
+            manager.ensure_bound(&std::env::current_dir()?, warn, EventSource::Wrapper)
+        }
+        Some(Commands::Auto(AutoArgs { warn })) => {
+            manager.ensure_bound(&std::env::current_dir()?, warn, EventSource::Auto)
+        }
+        Some(
+            Commands::Completions(..)
+            | Commands::Docs(..)
+            | Commands::Wrapper(..)
+            | Commands::Hook(..)
+            | Commands::HookEnv(..)
+            | Commands::Prompt(..)
+            | Commands::ClearClipboard(..)
+            | Commands::Remote(..)
+            | Commands::Config(..)
+            | Commands::Init,
+        ) => {
+            unreachable!("handled before manager setup")
+        }
         None => manager.show_current_if_any(),
+    };
+
+    if let Err(e) = &result
+        && retry::caused_by_sharing_violation(e)
+    {
+        eprintln!("{}", retry::sharing_violation_guidance(manager.data_dir()));
     }
+
+    result
 }
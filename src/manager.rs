@@ -1,563 +1,15642 @@
-use crate::config::{AccountMetadata, AccountsConfig};
+use crate::api;
+use crate::archive::{self, ArchiveManifest};
+use crate::audit::{self, Secret};
+use crate::backup::{self, BackupManifest};
+use crate::banner;
+use crate::bench;
+use crate::binding;
+use crate::capabilities::{self, Capabilities};
+use crate::capture::CapturePolicy;
+use crate::checkpoints::{self, Checkpoint};
+use crate::claude::{self, Identity};
+use crate::claude_version;
+use crate::cli::{GroupBy, ListSort, OnConflict};
+use crate::clipboard;
+use crate::clock::{Clock, SystemClock};
+use crate::codec::Codec;
+use crate::compression;
+use crate::config::{self, AccountIdentity, AccountMetadata, AccountsConfig, EntryHealth};
+use crate::crash::{self, Step};
+use crate::credstore::{self, CredentialStore, Keychain};
+use crate::crypto;
+use crate::daemon;
+use crate::debug_bundle;
+use crate::diff::{self, Change, FileChange};
+use crate::direct;
+use crate::doctor::{self, Finding};
+use crate::duration::{format_countdown, parse_duration};
+use crate::editor::Editor;
+use crate::encryption::{self, Vault};
+use crate::envfile;
 use crate::error::AccountError;
+use crate::export::{self, AccountFiles, Export, ExportManifest, ExportedAccount};
+use crate::extra_paths::{self, ExtraPath};
+use crate::hashing;
+use crate::health;
+use crate::history::{self, Event, EventKind, EventSource};
+use crate::hooks::{self, Hook};
+use crate::import_from::{self, Snapshot, Tool};
+use crate::interrupt::{self, Marker, Operation, Recovery, Undo};
+use crate::list_cache::{ListCache, Stamp};
+use crate::live_backups;
+use crate::lock::{self, StateLock};
+use crate::manifest::{self, FileHash, Manifest};
+use crate::mcp;
+use crate::menu::{Launcher, Menu};
+use crate::names;
+use crate::notify::{self, Notification};
+use crate::objects;
+use crate::output::{
+    self, AccountInfo, AccountJson, AccountRow, ActionJson, BackupJson, BenchJson, BulkFailureJson,
+    BulkJson, CheckJson, CheckpointJson, ChecksumCheckJson, ChecksumFailureJson, ChecksumJson,
+    Column, CurrentDetails, CurrentJson, DebugBundleJson, DiffJson, Document, EntryKind, EnvJson,
+    EnvfileJson, FileUpgrade, FoundJson, GcJson, GrepJson, LiveBackupJson, MigrateJson, NoteJson,
+    OrphanJson, OrphansJson, OutputFormat, Overview, ProfileJson, ProjectJson, PruneJson,
+    RepairJson, ServiceJson, SizeJson, SizeRow, SnapshotEntryJson, Status, StoreUsageJson,
+    SuggestionJson, SwitchPreviewJson, SyncPending, TokenJson, TrashRow, VerifyJson, VersionRow,
+};
+use crate::overlay;
+use crate::paths::{self, Paths};
+use crate::platform;
+use crate::preset::{self, Preset};
+use crate::profile::{self, Profile};
+use crate::progress::Progress;
+use crate::prompt::{Prompter, StdinPrompter};
+use crate::quota;
+use crate::redact::{self, Redaction};
+use crate::retry::retry;
+use crate::runner;
+use crate::search;
+use crate::service::{self, Invocation, Job, Schedule, System};
+use crate::sessions::{self, Session, SessionKind};
+use crate::settings::{CaptureScope, CredentialStorage, NotifyEvent, Settings, SwitchMode};
+use crate::size::{self, DirUsage};
+use crate::stats::{self, Transfer, TransferStats};
+use crate::storage::{self, LocalStorage, Storage};
+use crate::store;
+use crate::suggest::{self, Candidate};
+use crate::sync::{self, SyncBackend, SyncState, SyncedAccount, SyncedAccounts};
+use crate::system::{self, Activation};
+use crate::template;
+use crate::timings::{self, Phase};
+use crate::tr;
+use crate::trash;
+use crate::ui::Entry;
+use crate::versions;
 use anyhow::{Context, Result};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use clap_complete::Shell;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ffi::OsStr;
+use std::fmt;
 use std::fs;
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex, OnceLock, Weak};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{debug, info, trace};
 
+const CREDENTIALS_FILE: &str = ".credentials.json";
+// `status --when-stale-only` shows up this long before the token expires
+const STALE_WITHIN_SECS: i64 = 60 * 60;
+const LEGACY_ACCOUNTS_FILE: &str = "accounts.json";
+// Beside the live directory while a switch swaps it
+const STAGED_SUFFIX: &str = ".cas-staged";
+const PREVIOUS_SUFFIX: &str = ".cas-previous";
+// The paths outside it the switch replaced, until they go with the live backup
+const PREVIOUS_PATHS_SUFFIX: &str = ".cas-previous-paths";
+// Where earlier versions kept the live directory a switch replaced, for `undo`;
+// now in `live_backups`
+const UNDO_SUFFIX: &str = ".cas-undo";
+// A note being written with `note --edit`, in the state directory
+const NOTE_EDIT_FILE: &str = "NOTE_EDITMSG";
+
+/// Everything the dashboard shows about one account. The first fields come
+/// straight from the config; `login` and `usage` mean reading the snapshot,
+/// so they start out empty and are filled in by the [`loader`](crate::loader).
+#[derive(Clone, Debug)]
+pub struct AccountSummary {
+    pub name: String,
+    pub current: bool,
+    pub protected: bool,
+    pub external: bool,
+    pub saved_at: String,
+    pub last_used: Option<String>,
+    pub path: PathBuf,
+    pub login: Option<AccountLogin>,
+    pub usage: Option<DirUsage>,
+}
+
+/// The login details saved in a snapshot.
+#[derive(Clone, Debug, Default)]
+pub struct AccountLogin {
+    pub identity: Option<Identity>,
+    pub email: Option<String>,
+    pub organization: Option<String>,
+}
+
+impl AccountLogin {
+    /// The parts worth keeping in the account's metadata, if any were read.
+    pub fn to_identity(&self) -> Option<AccountIdentity> {
+        let identity = AccountIdentity {
+            email: self.email.clone(),
+            organization: self.organization.clone(),
+            plan: self
+                .identity
+                .as_ref()
+                .and_then(|identity| identity.plan.clone()),
+        };
+        (!identity.is_empty()).then_some(identity)
+    }
+}
+
+/// What a switch does when the live configuration has changed since the
+/// outgoing account was last saved or switched to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Unsaved {
+    /// Save the changes to the outgoing account first
+    #[default]
+    Save,
+    /// Stop, so nothing is saved over the snapshot without being asked for
+    Refuse,
+    /// Switch without saving, losing the changes
+    Discard,
+}
+
+/// What [`AccountManager::switch`] did.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwitchOutcome {
+    /// The account is live; what was copied into place, unless it was linked
+    Switched(Option<Transfer>),
+    /// The account is protected and the confirmation was declined
+    Cancelled,
+}
+
+/// Which accounts [`AccountManager::list_accounts`] shows.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ListFilter<'a> {
+    /// Only those with every one of these tags
+    pub tags: &'a [String],
+    /// Only those needing attention: saved more than this many days ago, or
+    /// with a login about to expire
+    pub stale_days: Option<u32>,
+}
+
+/// Saves, switches and lists accounts. Commands like `switch_account` print
+/// their results for the CLI; embedders can call the ones that return data
+/// instead ([`save`](Self::save), [`switch`](Self::switch),
+/// [`current_account`](Self::current_account),
+/// [`account_summaries`](Self::account_summaries)) with status messages
+/// turned off by [`with_quiet`](Self::with_quiet).
 pub struct AccountManager {
     claude_config_dir: PathBuf,
     switcher_dir: PathBuf,
+    state_dir: PathBuf,
     accounts_file: PathBuf,
+    // The system store, in system mode: the accounts file is shared, and
+    // which account is active is kept in the state directory
+    system: Option<PathBuf>,
+    // The accounts of the system store this user may see, when not all
+    grant: Option<system::Grant>,
+    settings_file: PathBuf,
+    // Where versions before the XDG layout kept everything
+    legacy_dir: PathBuf,
+    settings: Settings,
+    capabilities: Capabilities,
+    capture: CapturePolicy,
+    // Outside the Claude directory, but each account's own
+    extra_paths: Vec<ExtraPath>,
+    format: OutputFormat,
+    clock: Box<dyn Clock>,
+    prompter: Box<dyn Prompter>,
+    // Where snapshots are copied, moved and removed
+    storage: Box<dyn Storage>,
+    // `--yes`: every confirmation counts as answered yes
+    assume_yes: bool,
+    credstore: Box<dyn CredentialStore>,
+    // Unlocked on first use so the passphrase is asked for at most once
+    vault: OnceLock<Vault>,
+    // The installed Claude Code's version, asked for when first needed
+    claude_version: OnceLock<Option<String>>,
+    lock_wait: lock::Wait,
+    unsaved: Unsaved,
+    // `switch --autosave` or `--no-autosave`, over the `autosave` setting
+    autosave: Option<bool>,
+    // `--force`: locked accounts can be saved over, deleted and pruned
+    force: bool,
+    // `save --overwrite`: an account that isn't active can be saved over
+    overwrite: bool,
+    // Status messages go to stderr, keeping stdout for a command's output
+    status_to_stderr: bool,
+    // No status messages at all, for embedders
+    quiet: bool,
+    // Shared by nested calls, released when the outermost one returns
+    held_lock: Mutex<Weak<StateLock>>,
 }
 
 impl AccountManager {
-    pub fn new() -> Result<Self> {
-        let home = dirs::home_dir().context("Failed to determine home directory")?;
+    /// Sets up the manager for the current user. `claude_dir` is where
+    /// Claude Code's configuration lives, when given rather than found.
+    pub fn new(claude_dir: Option<&Path>) -> Result<Self> {
+        Self::open(claude_dir, false)
+    }
 
-        let claude_config_dir = home.join(".claude");
+    /// `new`, with the system store when `use_system` (`--system`) or the
+    /// `system` setting ask for it.
+    pub fn open(claude_dir: Option<&Path>, use_system: bool) -> Result<Self> {
+        let paths = Paths::current()?;
+        let settings = Settings::load(&paths.settings_file)?.with_env(std::env::vars())?;
+        let paths = match system::root(&settings, use_system) {
+            Some(root) => {
+                system::check(&root)?;
+                paths.system(&root)
+            }
+            None => paths,
+        };
+        let claude_config_dir = paths.claude_config_dir(claude_dir, settings.claude_dir.as_deref());
+        // Swapping only the login leaves `.claude.json` shared, unless asked
+        let configured = match settings.capture_scope {
+            CaptureScope::Full => settings.extra_paths.as_deref(),
+            CaptureScope::Credentials => Some(settings.extra_paths.as_deref().unwrap_or_default()),
+        };
+        let extra_paths = extra_paths::resolve(paths.home(), &claude_config_dir, configured)?;
+        let Paths {
+            switcher_dir,
+            state_dir,
+            accounts_file,
+            system,
+            settings_file,
+            legacy_dir,
+            ..
+        } = paths;
 
-        // XDG Base Directory compliant paths
-        let state_dir = home.join(".local/state/claude-account-switcher");
-        let switcher_dir = home.join(".local/share/claude-account-switcher");
-        let accounts_file = state_dir.join("accounts.json");
+        fs::create_dir_all(&state_dir).with_context(|| tr!("failed-create-state-directory"))?;
+        match &system {
+            Some(root) => system::create_store(root, &switcher_dir)?,
+            None => fs::create_dir_all(&switcher_dir)
+                .with_context(|| tr!("failed-create-account-storage-directory"))?,
+        }
+        let grant = match &system {
+            Some(root) => system::grant(root)?,
+            None => None,
+        };
 
-        fs::create_dir_all(&state_dir).context("Failed to create state directory")?;
-        fs::create_dir_all(&switcher_dir).context("Failed to create account storage directory")?;
+        let keychain =
+            Keychain::for_store(&switcher_dir, system.is_none() && !paths::is_sandboxed());
+        let capabilities = Capabilities::load_or_probe(&state_dir, &keychain, Utc::now());
+        let assume_yes = !settings.confirm();
+        if settings.credentials == CredentialStorage::Keychain && !capabilities.keychain {
+            output::warn(tr!("keychain-unavailable"));
+        }
 
         Ok(Self {
             claude_config_dir,
             switcher_dir,
+            state_dir,
             accounts_file,
+            system,
+            grant,
+            settings_file,
+            legacy_dir,
+            capture: settings
+                .capture_policy(&settings.capture.include, &settings.capture.exclude)?,
+            extra_paths,
+            settings,
+            capabilities,
+            format: OutputFormat::default(),
+            clock: Box::new(SystemClock),
+            prompter: Box::new(StdinPrompter),
+            storage: Box::new(LocalStorage),
+            assume_yes,
+            credstore: Box::new(keychain),
+            vault: OnceLock::new(),
+            claude_version: OnceLock::new(),
+            lock_wait: lock::Wait::default(),
+            unsaved: Unsaved::default(),
+            autosave: None,
+            force: false,
+            overwrite: false,
+            status_to_stderr: false,
+            quiet: false,
+            held_lock: Mutex::new(Weak::new()),
         })
     }
 
-    fn load_config(&self) -> Result<AccountsConfig> {
-        AccountsConfig::load(&self.accounts_file)
+    pub fn with_format(mut self, format: OutputFormat) -> Self {
+        self.format = format;
+        self
     }
 
-    fn save_config(&self, config: &AccountsConfig) -> Result<()> {
-        config.save(&self.accounts_file)
+    /// Replaces the configured include or exclude patterns with the given ones
+    /// when they're not empty.
+    pub fn with_capture_overrides(
+        mut self,
+        include: &[String],
+        exclude: &[String],
+    ) -> Result<Self> {
+        let include = if include.is_empty() {
+            &self.settings.capture.include
+        } else {
+            include
+        };
+        let exclude = if exclude.is_empty() {
+            &self.settings.capture.exclude
+        } else {
+            exclude
+        };
+
+        self.capture = self.settings.capture_policy(include, exclude)?;
+        Ok(self)
     }
 
-    pub fn save_account(&self, name: &str) -> Result<()> {
-        if !self.claude_config_dir.exists() {
-            return Err(AccountError::NoConfiguration.into());
-        }
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
 
-        let mut config = self.load_config()?;
-        let account_dir = self.switcher_dir.join(name);
+    pub fn with_prompter(mut self, prompter: impl Prompter + 'static) -> Self {
+        self.prompter = Box::new(prompter);
+        self
+    }
 
-        fs::create_dir_all(&account_dir).context("Failed to create account directory")?;
+    pub fn with_storage(mut self, storage: impl Storage + 'static) -> Self {
+        self.storage = Box::new(storage);
+        self
+    }
 
-        self.copy_dir_recursive(&self.claude_config_dir, &account_dir)
-            .context("Failed to copy configuration files")?;
+    /// Takes every confirmation as answered yes, for scripts.
+    /// `--yes`; with the `confirm` setting off, questions are answered yes
+    /// either way.
+    pub fn with_assume_yes(mut self, assume_yes: bool) -> Self {
+        self.assume_yes = assume_yes || !self.settings.confirm();
+        self
+    }
 
-        config.add_account(
-            name.to_string(),
-            AccountMetadata {
-                saved_at: Utc::now().to_rfc3339(),
-                path: account_dir,
-            },
-        );
-        config.current = Some(name.to_string());
+    pub fn with_status_to_stderr(mut self, status_to_stderr: bool) -> Self {
+        self.status_to_stderr = status_to_stderr;
+        self
+    }
 
-        self.save_config(&config)?;
-        println!("Saved account '{}'", name);
+    /// Leaves out the progress and confirmation messages ("Switched to
+    /// ..."), for callers that report results themselves. Warnings and
+    /// prompts still go to stderr.
+    pub fn with_quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
 
-        Ok(())
+    pub fn with_lock_wait(mut self, wait: lock::Wait) -> Self {
+        self.lock_wait = wait;
+        self
     }
 
-    pub fn switch_account(&self, name: &str) -> Result<()> {
-        let mut config = self.load_config()?;
+    pub fn with_unsaved(mut self, unsaved: Unsaved) -> Self {
+        self.unsaved = unsaved;
+        self
+    }
 
-        let account_meta = config
-            .get_account(name)
-            .ok_or_else(|| AccountError::NotFound(name.to_string()))?
-            .clone();
+    /// Whether switches save the outgoing account when nothing changed in
+    /// it, in place of the `autosave` setting and its debounce.
+    pub fn with_autosave(mut self, autosave: Option<bool>) -> Self {
+        self.autosave = autosave;
+        self
+    }
 
-        // Save current state if it exists
-        if let Some(current) = &config.current
-            && self.claude_config_dir.exists()
-        {
-            let _ = self.save_account(current);
+    /// Lets `save`, `delete` and `prune` change locked accounts, and skips
+    /// their confirmations.
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    /// Lets `save` go over an account that isn't the active one without
+    /// asking.
+    pub fn with_overwrite(mut self, overwrite: bool) -> Self {
+        self.overwrite = overwrite;
+        self
+    }
+
+    pub fn with_credential_store(mut self, credstore: impl CredentialStore + 'static) -> Self {
+        self.credstore = Box::new(credstore);
+        self
+    }
+
+    fn installed_claude_version(&self) -> Option<&str> {
+        self.claude_version
+            .get_or_init(|| claude_version::installed(&self.state_dir))
+            .as_deref()
+    }
+
+    /// Prints a progress or confirmation message. With `--json` these go to
+    /// stderr so stdout carries only the JSON document.
+    fn say(&self, message: impl fmt::Display) {
+        if self.quiet {
+            return;
+        }
+        if self.format.is_json() || self.status_to_stderr {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
         }
+    }
 
-        // Validate account directory exists
-        if !account_meta.path.exists() {
-            anyhow::bail!(
-                "Account directory not found: {}",
-                account_meta.path.display()
-            );
+    /// Runs the `hook` command from the settings. A `pre-` hook that fails
+    /// is an error, stopping what it comes before; a `post-` one only warns,
+    /// as what it follows is done by then.
+    fn run_hook(&self, hook: Hook, vars: &[(&str, &str)]) -> Result<()> {
+        match hooks::run(&self.settings.hooks, hook, vars) {
+            Err(e) if !hook.is_pre() => {
+                output::warn(tr!("hook-warning", error = format!("{:#}", e)));
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    /// Reports `event` to the webhook and desktop `[notify]` sets, if it
+    /// reports that event; `message` is what the desktop shows. Failures
+    /// only warn.
+    fn notify(
+        &self,
+        event: NotifyEvent,
+        account: &str,
+        previous: Option<&str>,
+        expires_at: Option<DateTime<Utc>>,
+        source: EventSource,
+        message: &str,
+    ) {
+        let settings = &self.settings.notify;
+        if !notify::reports(settings, event, source) {
+            return;
+        }
+        let mut sent = Vec::new();
+        if let Some(url) = &settings.webhook {
+            sent.push(notify::post(
+                url,
+                &Notification {
+                    event,
+                    account,
+                    previous,
+                    source,
+                    expires_at: expires_at.map(|expires_at| expires_at.to_rfc3339()),
+                    host: notify::host(),
+                    user: notify::user(),
+                    at: self.clock.now().to_rfc3339(),
+                },
+            ));
+        }
+        if settings.desktop {
+            sent.push(notify::show(message));
         }
+        for e in sent.into_iter().filter_map(Result::err) {
+            output::warn(tr!("hook-warning", error = format!("{:#}", e)));
+        }
+    }
 
-        // Clear and recreate config directory
-        if self.claude_config_dir.exists() {
-            fs::remove_dir_all(&self.claude_config_dir)
-                .context("Failed to remove current configuration")?;
+    /// Asks `question`, unless `--yes`, or the `--force` of the command
+    /// asking, already answered it. `default` is the answer to an empty
+    /// reply.
+    fn confirm(&self, question: &str, default: bool) -> Result<bool> {
+        if self.assume_yes || self.force {
+            return Ok(true);
         }
+        self.prompter.confirm(question, default)
+    }
 
-        fs::create_dir_all(&self.claude_config_dir)
-            .context("Failed to create configuration directory")?;
+    pub fn data_dir(&self) -> &Path {
+        &self.switcher_dir
+    }
 
-        // Restore account configuration
-        self.copy_dir_recursive(&account_meta.path, &self.claude_config_dir)
-            .context("Failed to restore account configuration")?;
+    pub fn state_dir(&self) -> &Path {
+        &self.state_dir
+    }
 
-        config.current = Some(name.to_string());
-        self.save_config(&config)?;
+    fn load_config(&self) -> Result<AccountsConfig> {
+        timings::time(Phase::Config, || {
+            let mut config = AccountsConfig::load(&self.accounts_file)?;
+            if let Some(grant) = &self.grant {
+                system::restrict(&mut config, grant);
+            }
+            if self.system.is_some() {
+                Activation::load(&self.state_dir.join(system::ACTIVE_FILE))?.apply(&mut config);
+            }
+            Ok(config)
+        })
+    }
 
-        println!("Switched to account '{}'", name);
+    /// Writes `config` back, unless the file was saved by someone else since
+    /// it was loaded: the lock keeps other commands out, but not a hook or
+    /// an older version that runs without it, and what they saved would be
+    /// lost. `config` takes the revision it's saved as, so it can be saved
+    /// again.
+    fn save_config(&self, config: &mut AccountsConfig) -> Result<()> {
+        if config::stored_revision(&self.accounts_file)? != config.revision {
+            return Err(AccountError::Conflict.into());
+        }
+        config.revision += 1;
+        timings::time(Phase::Config, || match self.system {
+            Some(_) => {
+                Activation::of(config).save(&self.state_dir.join(system::ACTIVE_FILE))?;
+                system::save_shared(&self.accounts_file, config, self.grant.as_ref())?;
+                // Not rewritten when only this user's bookkeeping changed
+                config.revision = config::stored_revision(&self.accounts_file)?;
+                anyhow::Ok(())
+            }
+            None => config.save(&self.accounts_file),
+        })?;
+        // What `list` cached about accounts that are gone or saved again
+        let mut cache = ListCache::load(&self.state_dir, self.clock.now());
+        if cache.retain(config) {
+            let _ = cache.save(&self.state_dir);
+        }
         Ok(())
     }
 
-    pub fn list_accounts(&self) -> Result<()> {
-        let config = self.load_config()?;
+    /// The saved accounts and which one is active, as stored.
+    pub fn accounts_config(&self) -> Result<AccountsConfig> {
+        self.load_config()
+    }
 
-        if config.is_empty() {
-            println!("No saved accounts found.");
-            return Ok(());
-        }
+    /// The active account, if one is.
+    pub fn current_account(&self) -> Result<Option<String>> {
+        Ok(self.load_config()?.current)
+    }
 
-        println!("Claude Code Accounts:");
-        println!("{}", "-".repeat(60));
+    /// Saves the live configuration as `name`, returning what was copied
+    /// (nothing when it was saved into a linked snapshot).
+    pub fn save(&self, name: &str) -> Result<Option<Transfer>> {
+        self.save_for(name, EventSource::Cli)
+    }
 
-        let current = config.current.as_deref();
-        let mut accounts: Vec<_> = config.accounts.iter().collect();
-        accounts.sort_by_key(|(name, _)| *name);
+    /// `save`, recorded in the history as coming from `source`.
+    pub fn save_for(&self, name: &str, source: EventSource) -> Result<Option<Transfer>> {
+        let _lock = self.lock_state()?;
+        self.save_from(name, source)
+    }
 
-        for (name, meta) in accounts {
-            let marker = if Some(name.as_str()) == current {
-                "*"
-            } else {
-                " "
-            };
-            let saved_at = meta.saved_at.get(..19).unwrap_or(&meta.saved_at);
-            println!("{} {:<20} (saved: {})", marker, name, saved_at);
-        }
-        println!();
+    /// Makes `name` the live account, saving the outgoing one first as
+    /// [`with_unsaved`](Self::with_unsaved) says.
+    pub fn switch(&self, name: &str, mode: Option<SwitchMode>) -> Result<SwitchOutcome> {
+        self.switch_for(name, mode, EventSource::Cli)
+    }
 
+    /// `switch`, recorded in the history as coming from `source`.
+    pub fn switch_for(
+        &self,
+        name: &str,
+        mode: Option<SwitchMode>,
+        source: EventSource,
+    ) -> Result<SwitchOutcome> {
+        let _lock = self.lock_state()?;
+        Ok(match self.switch_from(name, mode, source)? {
+            Some(transfer) => SwitchOutcome::Switched(transfer),
+            None => SwitchOutcome::Cancelled,
+        })
+    }
+
+    pub fn save_account(&self, name: &str) -> Result<()> {
+        self.save_account_in(name, None)
+    }
+
+    /// `save_account`, keeping the snapshot of a new account in `store`, a
+    /// directory outside the data directory such as an external drive.
+    /// Later saves and switches use it there.
+    pub fn save_account_in(&self, name: &str, store: Option<&Path>) -> Result<()> {
+        let store = match store {
+            Some(store) if !store.is_dir() => {
+                anyhow::bail!(tr!("save-store-missing", path = store.display()))
+            }
+            Some(store) => Some(
+                fs::canonicalize(store)
+                    .with_context(|| tr!("failed-resolve-path", path = store.display()))?,
+            ),
+            None => None,
+        };
+        let cancelled = !self.confirm_overwrite(name)?;
+        let transfer = match cancelled {
+            true => {
+                self.say(tr!("cancelled"));
+                None
+            }
+            false => {
+                let _lock = self.lock_state()?;
+                self.save_in(name, EventSource::Cli, store.as_deref())?
+            }
+        };
+        if self.format.is_json() {
+            output::print_json(&ActionJson {
+                action: "save",
+                account: name,
+                cancelled,
+                copied: transfer.map(Into::into),
+            })?;
+        }
         Ok(())
     }
 
-    pub fn delete_account(&self, name: &str) -> Result<()> {
-        let mut config = self.load_config()?;
+    /// Whether `save` may go over `name`. The live login is the active
+    /// account's, so saving it over another one is more often a slip than
+    /// meant: it takes `--overwrite`, or a yes when someone can answer.
+    fn confirm_overwrite(&self, name: &str) -> Result<bool> {
+        let config = self.load_config()?;
+        if !config.accounts.contains_key(name)
+            || config.current.as_deref() == Some(name)
+            || self.overwrite
+        {
+            return Ok(true);
+        }
+        if !self.prompter.can_ask() && !self.assume_yes && !self.force {
+            return Err(
+                anyhow::Error::from(AccountError::AlreadyExists(name.to_string()))
+                    .context(tr!("save-overwrite-needed", name = name)),
+            );
+        }
+        self.confirm(&tr!("save-overwrite-confirm", name = name), false)
+    }
 
-        let account_meta = config
-            .get_account(name)
-            .ok_or_else(|| AccountError::NotFound(name.to_string()))?
-            .clone();
+    /// Saves the live configuration as `name`, returning what was copied.
+    fn save_from(&self, name: &str, source: EventSource) -> Result<Option<Transfer>> {
+        self.save_in(name, source, None)
+    }
 
-        // Check if it's the current account
-        if config.current.as_deref() == Some(name) {
-            eprintln!("Warning: '{}' is currently active", name);
-            eprint!("Continue? This will clear your active session (y/N): ");
-            io::stdout().flush()?;
+    /// `save_from`, keeping a new account's snapshot in `store` rather than
+    /// the data directory.
+    fn save_in(
+        &self,
+        name: &str,
+        source: EventSource,
+        store: Option<&Path>,
+    ) -> Result<Option<Transfer>> {
+        if !self.storage.exists(&self.claude_config_dir) {
+            return Err(AccountError::NoConfiguration.into());
+        }
 
-            let mut input = String::new();
-            io::stdin().read_line(&mut input)?;
+        let _operation = crash::begin("save", name);
+        let mut config = self.load_config()?;
+        // Unpacked first, so what's saved over it is kept as a version
+        self.unarchive(&mut config, name)?;
+        names::validate(name)?;
+        self.ensure_no_case_conflict(&config, name, None)?;
+        let source_name = source.to_string();
+        let hook_vars = [
+            (hooks::NAME_VAR, name),
+            (hooks::SOURCE_VAR, source_name.as_str()),
+        ];
+        self.run_hook(Hook::PreSave, &hook_vars)?;
 
-            if !input.trim().eq_ignore_ascii_case("y") {
-                println!("Cancelled.");
-                return Ok(());
+        // External snapshots are saved back to where they live, as are those
+        // kept elsewhere with `--store`
+        let previous = config.get_account(name);
+        let elsewhere = match (previous, store) {
+            (Some(meta), Some(store)) if meta.path != store.join(name) => {
+                anyhow::bail!(tr!(
+                    "save-store-kept",
+                    name = name,
+                    path = meta.path.display()
+                ))
             }
-            config.current = None;
+            (_, Some(store)) => {
+                store::claim(store)?;
+                Some(store)
+            }
+            (Some(meta), None) => self.custom_store(meta),
+            (None, None) => None,
+        };
+        if let Some(store) = elsewhere.filter(|store| !store::is_mounted(store)) {
+            return Err(self.unmounted(name, store));
+        }
+        let external = previous.is_some_and(|meta| meta.external);
+        let account_dir = match (previous, elsewhere) {
+            (Some(meta), _) if external => meta.path.clone(),
+            (_, Some(store)) => store.join(name),
+            _ => self.switcher_dir.join(name),
+        };
+        let protected = previous.is_some_and(|meta| meta.protected);
+        let locked = previous.is_some_and(|meta| meta.locked);
+        if locked && !self.force {
+            // Switches and re-saves go ahead, leaving the snapshot as it is
+            if matches!(source, EventSource::AutoSave | EventSource::Resave) {
+                self.say(tr!("account-locked-not-saved", name = name));
+                return Ok(None);
+            }
+            return Err(AccountError::Locked(name.to_string()).into());
         }
+        if previous.is_some_and(|meta| self.is_read_only(meta)) {
+            if matches!(source, EventSource::AutoSave | EventSource::Resave) {
+                self.say(tr!("system-not-saved", name = name));
+                return Ok(None);
+            }
+            anyhow::bail!(tr!("system-account-read-only", name = name));
+        }
+        let _marker = interrupt::begin(
+            &self.state_dir,
+            Marker::new(Operation::Save, name, None, self.clock.now()),
+        )?;
+        let last_used = previous.and_then(|meta| meta.last_used.clone());
+        let switch_count = previous.map_or(0, |meta| meta.switch_count);
+        let tags = previous.map(|meta| meta.tags.clone()).unwrap_or_default();
+        let note = previous.and_then(|meta| meta.note.clone());
+        let limited_until = previous.and_then(|meta| meta.limited_until.clone());
+        let env = previous.map(|meta| meta.env.clone()).unwrap_or_default();
+        let pinned_version = previous.and_then(|meta| meta.pinned_version.clone());
+        let previous_saved_at = previous.map(|meta| meta.saved_at.clone());
 
-        // Remove directory
-        if account_meta.path.exists() {
-            fs::remove_dir_all(&account_meta.path).context("Failed to remove account directory")?;
+        // In symlink mode the live directory may already be this snapshot
+        let transfer = if platform::same_location(&self.claude_config_dir, &account_dir) {
+            self.save_extra_paths(&account_dir, Codec::Plain)?;
+            // Written by a version that put it there in this mode too
+            self.strip_banner(&account_dir)?;
+            None
+        } else {
+            // What the snapshot holds, when it's known. External snapshots
+            // may have been changed by other tooling, so they're copied whole
+            let manifest_path = manifest::path(&self.state_dir, name);
+            let saved = match external {
+                true => None,
+                false => Manifest::load(&manifest_path)?,
+            };
+            let mut kept = None;
+            // Versions and shared objects are in the data directory, which a
+            // snapshot kept elsewhere is meant to stay out of
+            let in_store = !external && elsewhere.is_none();
+            let nested = nested_snapshots(&config, &account_dir);
+            if let Some(saved_at) = previous_saved_at.filter(|_| in_store) {
+                crash::step(Step::KeepingVersion);
+                kept = self.keep_version(name, &account_dir, &saved_at, &nested)?;
+            }
+            let base = kept.clone().unwrap_or_else(|| account_dir.clone());
+            // Half a snapshot is worse than the one it was replacing
+            let undoable = in_store && (kept.is_some() || previous.is_none());
+            if undoable {
+                interrupt::undoable(&account_dir, kept.as_deref());
+            }
+            crash::step(Step::CopyingSnapshot);
+            let copied = (|| {
+                self.storage
+                    .create_dir_all(&account_dir)
+                    .with_context(|| tr!("failed-create-account-directory"))?;
+                // Files managed by other tooling are left readable
+                let codec = if external {
+                    Codec::Plain
+                } else {
+                    self.store_codec(true)?
+                };
+                let (transfer, manifest) = match saved.filter(|_| self.storage.is_dir(&base)) {
+                    Some(saved) => self
+                        .save_changes(&account_dir, &base, &saved, codec)
+                        .map(|(transfer, manifest)| (transfer, Some(manifest))),
+                    None => self
+                        .timed_copy(&self.claude_config_dir, &account_dir, &self.capture, codec)
+                        .map(|transfer| (transfer, None)),
+                }
+                .with_context(|| tr!("failed-copy-configuration-files"))?;
+                self.save_extra_paths(&account_dir, codec)?;
+                if self.uses_keychain() {
+                    self.move_credentials_to_keychain(name, &account_dir)?;
+                }
+                self.strip_banner(&account_dir)?;
+                if self.dedup_enabled() && in_store {
+                    self.intern_snapshot(&account_dir)?;
+                }
+                anyhow::Ok((transfer, manifest))
+            })();
+            let (transfer, manifest) = match copied {
+                Ok(copied) => copied,
+                Err(e) if undoable => {
+                    if let Some(kept) = &kept {
+                        let _ = self.move_nested(&account_dir, kept, &nested);
+                    }
+                    self.discard(&account_dir);
+                    if let Some(kept) = &kept {
+                        let _ = retry(|| self.storage.rename(kept, &account_dir));
+                    }
+                    return Err(e);
+                }
+                Err(e) => return Err(e),
+            };
+            // For every user of the system store, whoever saved it
+            if self.system.is_some() && !external {
+                system::share(&account_dir)?;
+            }
+            self.record_manifest(
+                name,
+                match manifest {
+                    Some(manifest) => self.finish_manifest(manifest),
+                    None => self.live_manifest(None),
+                },
+            );
+            Some(transfer)
+        };
+
+        let identity = self.account_login(name, &account_dir).to_identity();
+        config.add_account(
+            name.to_string(),
+            AccountMetadata {
+                saved_at: self.clock.now().to_rfc3339(),
+                path: account_dir,
+                external,
+                protected,
+                locked,
+                last_used,
+                identity,
+                tags,
+                note,
+                switch_count,
+                limited_until,
+                env,
+                claude_version: self.installed_claude_version().map(str::to_string),
+                pinned_version,
+                ..Default::default()
+            },
+        );
+        config.current = Some(name.to_string());
+
+        crash::step(Step::UpdatingConfig);
+        self.save_config(&mut config)?;
+        self.record_details(EventKind::Save, name, source, None, transfer)?;
+        let saved = tr!("account-saved", name = name);
+        self.say(&saved);
+        if let Some(transfer) = transfer {
+            self.say(stats::summary(transfer.usage, transfer.elapsed));
         }
+        self.run_hook(Hook::PostSave, &hook_vars)?;
+        self.notify(NotifyEvent::Save, name, None, None, source, &saved);
 
-        config.remove_account(name);
-        self.save_config(&config)?;
+        Ok(transfer)
+    }
 
-        println!("Deleted account '{}'", name);
-        Ok(())
+    /// Whether `meta`'s snapshot is shared from the system store and this
+    /// user can't save over it.
+    fn is_read_only(&self, meta: &AccountMetadata) -> bool {
+        self.system.is_some() && !system::writable(&meta.path)
     }
 
-    pub fn rename_account(&self, old_name: &str, new_name: &str) -> Result<()> {
+    /// The directory other than the data directory that `meta`'s snapshot is
+    /// kept in, since `save --store` put it there.
+    fn custom_store<'a>(&self, meta: &'a AccountMetadata) -> Option<&'a Path> {
+        if meta.external || meta.path.starts_with(&self.switcher_dir) {
+            return None;
+        }
+        // A namespaced account is further down than the store it's kept in
+        meta.path
+            .ancestors()
+            .skip(1)
+            .find(|dir| store::is_mounted(dir))
+            .or(meta.path.parent())
+    }
+
+    fn unmounted(&self, name: &str, store: &Path) -> anyhow::Error {
+        anyhow::anyhow!(tr!("store-unmounted", name = name, path = store.display()))
+    }
+
+    /// Registers a directory maintained elsewhere as an account. Nothing is
+    /// copied: switching reads from `path` and saving writes back to it.
+    pub fn add_existing(&self, name: &str, path: &Path) -> Result<()> {
+        let _lock = self.lock_state()?;
         let mut config = self.load_config()?;
 
-        if !config.accounts.contains_key(old_name) {
-            return Err(AccountError::NotFound(old_name.to_string()).into());
+        if config.accounts.contains_key(name) {
+            return Err(AccountError::AlreadyExists(name.to_string()).into());
         }
+        names::validate(name)?;
+        self.ensure_no_case_conflict(&config, name, None)?;
 
-        if config.accounts.contains_key(new_name) {
-            return Err(AccountError::AlreadyExists(new_name.to_string()).into());
+        if !path.is_dir() {
+            anyhow::bail!(tr!("account-dir-not-found", path = path.display()));
         }
+        let path = fs::canonicalize(path)
+            .with_context(|| tr!("failed-resolve-path", path = path.display()))?;
 
-        let account_meta = config
-            .get_account(old_name)
-            .ok_or_else(|| AccountError::NotFound(old_name.to_string()))?
-            .clone();
+        config.add_account(
+            name.to_string(),
+            AccountMetadata {
+                saved_at: self.clock.now().to_rfc3339(),
+                path: path.clone(),
+                external: true,
+                identity: self.account_login(name, &path).to_identity(),
+                ..Default::default()
+            },
+        );
+        self.save_config(&mut config)?;
 
-        // Rename directory
-        let new_dir = self.switcher_dir.join(new_name);
-        fs::rename(&account_meta.path, &new_dir).context("Failed to rename account directory")?;
+        self.say(tr!(
+            "account-registered",
+            name = name,
+            path = path.display()
+        ));
+        Ok(())
+    }
 
-        // Update configuration using the config method
-        config.rename_account(old_name, new_name.to_string())?;
+    pub fn switch_account(&self, name: &str, mode: Option<SwitchMode>) -> Result<()> {
+        let name = &self.resolve_target(name)?;
+        let outcome = self.switch(name, mode)?;
+        if self.format.is_json() {
+            let copied = match outcome {
+                SwitchOutcome::Switched(transfer) => transfer,
+                SwitchOutcome::Cancelled => None,
+            };
+            output::print_json(&ActionJson {
+                action: "switch",
+                account: name,
+                cancelled: outcome == SwitchOutcome::Cancelled,
+                copied: copied.map(Into::into),
+            })?;
+        }
+        Ok(())
+    }
 
-        // Update the path in the renamed account metadata
-        if let Some(meta) = config.accounts.get_mut(new_name) {
-            meta.path = new_dir;
+    /// Shows what switching to `name` would change in the live
+    /// configuration (the files, who it's signed in as and how its settings
+    /// differ) and switches once confirmed. With --json only the preview is
+    /// printed.
+    pub fn preview_switch(&self, name: &str, mode: Option<SwitchMode>) -> Result<()> {
+        let name = &self.resolve_target(name)?;
+        let config = self.load_config()?;
+        let meta = config
+            .get_account(name)
+            .ok_or_else(|| AccountError::NotFound(name.clone()))?;
+        if meta.archive.is_some() {
+            anyhow::bail!(tr!("preview-archived", name = name));
+        }
+
+        // Compared the other way round: what the live directory becomes
+        let mut changes = self.live_changes(name, &meta.path, false)?;
+        for change in &mut changes {
+            change.change = match change.change {
+                Change::Added => Change::Removed,
+                Change::Removed => Change::Added,
+                Change::Modified => Change::Modified,
+            };
+            if is_settings_file(&change.path) {
+                let text = |data: Option<Vec<u8>>| {
+                    data.map(|data| {
+                        serde_json::from_slice::<serde_json::Value>(&data)
+                            .ok()
+                            .and_then(|value| serde_json::to_string_pretty(&value).ok())
+                            .unwrap_or_else(|| String::from_utf8_lossy(&data).into_owned())
+                    })
+                    .unwrap_or_default()
+                };
+                let live = match change.change {
+                    Change::Added => None,
+                    _ => self.read_live(&change.path)?,
+                };
+                let target = self.read_stored_bytes(&meta.path.join(&change.path))?;
+                let diff = diff::unified(
+                    &text(live),
+                    &text(target),
+                    &format!("live/{}", change.path.display()),
+                    &format!("{}/{}", name, change.path.display()),
+                );
+                change.diff = (!diff.is_empty()).then_some(diff);
+            }
         }
+        let from = self.live_login().to_identity();
+        let to = meta
+            .identity
+            .clone()
+            .or_else(|| self.account_login(name, &meta.path).to_identity());
 
-        self.save_config(&config)?;
-        println!("Renamed account '{}' to '{}'", old_name, new_name);
+        if self.format.is_json() {
+            return output::print_json(&SwitchPreviewJson {
+                account: name,
+                changes: &changes,
+                identity_from: from.as_ref(),
+                identity_to: to.as_ref(),
+            });
+        }
+        let who = |identity: &Option<AccountIdentity>| {
+            identity
+                .as_ref()
+                .map_or_else(|| tr!("info-unknown"), ToString::to_string)
+        };
+        println!("{}", tr!("preview-heading", name = name));
+        for change in &changes {
+            println!("{}", self.format.file_change(change));
+        }
+        match from == to {
+            true => println!("{}", tr!("preview-identity-same", identity = who(&to))),
+            false => println!(
+                "{}",
+                tr!("preview-identity", from = who(&from), to = who(&to))
+            ),
+        }
+        for diff in changes.iter().filter_map(|change| change.diff.as_ref()) {
+            print!("{}", diff);
+        }
+        println!("{}", tr!("preview-summary", count = changes.len()));
 
-        Ok(())
+        match self.confirm(&tr!("preview-confirm", name = name), true)? {
+            true => self.switch_account(name, mode),
+            false => {
+                self.say(tr!("cancelled"));
+                Ok(())
+            }
+        }
     }
 
-    pub fn show_current(&self) -> Result<()> {
+    /// Who the live configuration is signed in as, read as `account_login`
+    /// reads a snapshot.
+    fn live_login(&self) -> AccountLogin {
+        let in_home = extra_paths::in_home(template::CLAUDE_JSON);
+        let claude_json = std::iter::once(self.claude_config_dir.join(template::CLAUDE_JSON))
+            .chain(
+                self.extra_paths
+                    .iter()
+                    .filter(|path| path.stored == in_home)
+                    .map(|path| path.live.clone()),
+            )
+            .find_map(|path| fs::read_to_string(path).ok());
+        AccountLogin {
+            identity: fs::read_to_string(self.claude_config_dir.join(CREDENTIALS_FILE))
+                .ok()
+                .and_then(|credentials| claude::read_identity(&credentials)),
+            email: claude_json.as_deref().and_then(claude::read_email),
+            organization: claude_json.as_deref().and_then(claude::read_organization),
+        }
+    }
+
+    /// The account `name` stands for: see `AccountsConfig::resolve`, and
+    /// for `-` the account that was active before the current one. Anything
+    /// else is left as it is, to be reported as not found.
+    pub fn resolve_target(&self, name: &str) -> Result<String> {
         let config = self.load_config()?;
-        match config.current {
-            Some(name) => println!("{}", name),
-            None => println!("No active account"),
+        if name != names::PREVIOUS {
+            return Ok(config.resolve(name).unwrap_or(name).to_string());
         }
-        Ok(())
+        config
+            .previous
+            .filter(|previous| config.accounts.contains_key(previous))
+            .ok_or_else(|| anyhow::anyhow!(tr!("no-previous")))
     }
 
-    pub fn show_current_if_any(&self) -> Result<()> {
+    /// The accounts `args` name, in order and each once: what
+    /// `resolve_target` takes, and globs (`test-*`) for every account they
+    /// match. A glob that matches nothing is an error.
+    pub fn expand_names(&self, args: &[String]) -> Result<Vec<String>> {
         let config = self.load_config()?;
-        if let Some(name) = config.current {
-            println!("{}", name);
+        let mut expanded: Vec<String> = Vec::new();
+        for arg in args {
+            let matched = match names::is_pattern(arg) {
+                true => {
+                    let mut matched = names::matching(arg, config.accounts.keys());
+                    matched.sort();
+                    matched
+                }
+                false => vec![self.resolve_target(arg)?],
+            };
+            if matched.is_empty() {
+                anyhow::bail!(tr!("pattern-no-match", pattern = arg));
+            }
+            for name in matched {
+                if !expanded.contains(&name) {
+                    expanded.push(name);
+                }
+            }
         }
-        Ok(())
+        Ok(expanded)
     }
 
-    fn copy_dir_recursive(&self, src: &Path, dst: &Path) -> Result<()> {
-        for entry in fs::read_dir(src)
-            .with_context(|| format!("Failed to read directory: {}", src.display()))?
-        {
-            let entry = entry?;
-            let file_type = entry.file_type()?;
-            let src_path = entry.path();
-            let dst_path = dst.join(entry.file_name());
+    /// Runs `op` on each of `names`, carrying on past the ones that fail,
+    /// then reports how many succeeded and fails naming the others. A single
+    /// name runs just as it would on its own.
+    fn for_each_account(
+        &self,
+        names: &[String],
+        mut op: impl FnMut(&str) -> Result<()>,
+    ) -> Result<()> {
+        if let [name] = names {
+            return op(name);
+        }
 
-            if file_type.is_dir() {
-                fs::create_dir_all(&dst_path).with_context(|| {
-                    format!("Failed to create directory: {}", dst_path.display())
-                })?;
-                self.copy_dir_recursive(&src_path, &dst_path)?;
-            } else if file_type.is_file() {
-                fs::copy(&src_path, &dst_path).with_context(|| {
-                    format!(
-                        "Failed to copy file from {} to {}",
-                        src_path.display(),
-                        dst_path.display()
-                    )
-                })?;
+        let mut report = BulkJson::default();
+        for name in names {
+            match op(name) {
+                Ok(()) => report.succeeded.push(name.clone()),
+                Err(e) => {
+                    output::warn(tr!(
+                        "bulk-item-failed",
+                        name = name,
+                        error = format!("{:#}", e)
+                    ));
+                    report.failed.push(BulkFailureJson {
+                        name: name.clone(),
+                        error: format!("{:#}", e),
+                    });
+                }
             }
         }
+
+        match self.format.is_json() {
+            true => output::print_json(&report)?,
+            false => self.say(tr!(
+                "bulk-summary",
+                done = report.succeeded.len(),
+                total = names.len()
+            )),
+        }
+        if !report.failed.is_empty() {
+            let failed: Vec<&str> = report.failed.iter().map(|f| f.name.as_str()).collect();
+            anyhow::bail!(tr!("bulk-failed", names = failed.join(", ")));
+        }
         Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs;
-    use tempfile::TempDir;
+    /// Makes `name` the live account. Returns `None` when the user backed out
+    /// of a protected switch, otherwise what was restored by copying.
+    fn switch_from(
+        &self,
+        name: &str,
+        mode: Option<SwitchMode>,
+        source: EventSource,
+    ) -> Result<Option<Option<Transfer>>> {
+        let config = self.load_config()?;
+        match self.account_health(&config, name) {
+            Some(EntryHealth::Unmounted(store)) => return Err(self.unmounted(name, &store)),
+            Some(EntryHealth::Missing(path)) => {
+                anyhow::bail!(tr!(
+                    "switch-entry-missing",
+                    name = name,
+                    path = path.display()
+                ))
+            }
+            Some(EntryHealth::Corrupt(error)) => {
+                anyhow::bail!(tr!("switch-entry-corrupt", name = name, error = error))
+            }
+            _ => {}
+        }
+        let account_meta = config
+            .get_account(name)
+            .ok_or_else(|| AccountError::NotFound(name.to_string()))?;
 
-    struct TestSetup {
-        _temp_dir: TempDir,
-        manager: AccountManager,
-        claude_config_dir: PathBuf,
+        if account_meta.protected && !self.confirm_protected(name)? {
+            self.say(tr!("cancelled"));
+            return Ok(None);
+        }
+        self.switch_to(name, mode, source).map(Some)
     }
 
-    impl TestSetup {
-        fn new() -> Result<Self> {
-            let temp_dir = TempDir::new()?;
-            let temp_path = temp_dir.path();
-
-            let claude_config_dir = temp_path.join(".claude");
-            let switcher_dir = temp_path.join(".local/share/claude-account-switcher");
-            let state_dir = temp_path.join(".local/state/claude-account-switcher");
-            let accounts_file = state_dir.join("accounts.json");
+    /// The switch itself, once any confirmation is out of the way.
+    fn switch_to(
+        &self,
+        name: &str,
+        mode: Option<SwitchMode>,
+        source: EventSource,
+    ) -> Result<Option<Transfer>> {
+        self.switch_with(name, mode, source, self.unsaved)
+    }
 
-            fs::create_dir_all(&state_dir)?;
-            fs::create_dir_all(&switcher_dir)?;
+    /// `switch_to`, with `unsaved` in place of the policy the manager was
+    /// given for the outgoing account's changes.
+    fn switch_with(
+        &self,
+        name: &str,
+        mode: Option<SwitchMode>,
+        source: EventSource,
+        unsaved: Unsaved,
+    ) -> Result<Option<Transfer>> {
+        let _operation = crash::begin("switch", name);
+        self.recover_interrupted_switch()?;
+        let mut config = self.load_config()?;
+        if config
+            .get_account(name)
+            .is_some_and(|meta| meta.archive.is_some())
+        {
+            self.say(tr!("unarchiving", name = name));
+            self.unarchive(&mut config, name)?;
+        }
 
-            let manager = AccountManager {
+        let account_meta = config
+            .get_account(name)
+            .ok_or_else(|| AccountError::NotFound(name.to_string()))?
+            .clone();
+        self.check_claude_version(name, &account_meta)?;
+        // Only a warning: the switch goes ahead, as the terminal may be done
+        // with the account without having closed
+        for session in sessions::live(&self.sessions_file()).unwrap_or_default() {
+            if session.account == name {
+                output::warn(tr!(
+                    "session-conflict",
+                    name = name,
+                    kind = session.kind,
+                    pid = session.pid
+                ));
+            }
+        }
+
+        let previous = config.current.clone();
+        let source_name = source.to_string();
+        let mut hook_vars = vec![
+            (hooks::NAME_VAR, name),
+            (hooks::NEW_VAR, name),
+            (hooks::SOURCE_VAR, source_name.as_str()),
+        ];
+        if let Some(previous) = &previous {
+            hook_vars.push((hooks::OLD_VAR, previous));
+        }
+        self.run_hook(Hook::PreSwitch, &hook_vars)?;
+        let _marker = interrupt::begin(
+            &self.state_dir,
+            Marker::new(
+                Operation::Switch,
+                name,
+                previous.as_deref(),
+                self.clock.now(),
+            ),
+        )?;
+
+        // Save current state if it exists
+        if let Some(current) = &config.current
+            && self.claude_config_dir.exists()
+        {
+            // A locked account's changes aren't kept anyway, nor are those of
+            // one this user can't write to in the system store
+            let locked = config
+                .get_account(current)
+                .is_some_and(|meta| meta.locked || self.is_read_only(meta));
+            let unmounted = config
+                .get_account(current)
+                .and_then(|meta| self.custom_store(meta))
+                .filter(|store| !store::is_mounted(store));
+            let unsaved = match unmounted {
+                Some(store) if !locked => match unsaved {
+                    Unsaved::Refuse => anyhow::bail!(tr!(
+                        "switch-away-unmounted",
+                        name = current,
+                        path = store.display()
+                    )),
+                    Unsaved::Save => {
+                        output::warn(tr!(
+                            "store-unmounted-not-saved",
+                            name = current,
+                            path = store.display()
+                        ));
+                        Unsaved::Discard
+                    }
+                    Unsaved::Discard => Unsaved::Discard,
+                },
+                _ => unsaved,
+            };
+            let mut known_changes = None;
+            if unsaved == Unsaved::Refuse && !locked {
+                let changes = self.unsaved_changes(&config, current)?;
+                // Offered when someone can answer; otherwise it stops
+                let save = !changes.is_empty()
+                    && (self.assume_yes
+                        || self.prompter.can_ask()
+                            && self.confirm(
+                                &tr!(
+                                    "switch-unsaved-confirm",
+                                    name = current,
+                                    count = changes.len()
+                                ),
+                                true,
+                            )?);
+                if !changes.is_empty() && !save {
+                    return Err(AccountError::UnsavedChanges(current.clone(), changes.len()).into());
+                }
+                known_changes = Some(changes);
+            }
+            if unsaved != Unsaved::Discard
+                && self.autosaves(&config, current, known_changes.as_ref())?
+            {
+                // Nothing is replaced when the changes can't be kept
+                self.save_from(current, EventSource::AutoSave)
+                    .with_context(|| tr!("switch-save-failed", name = current))?;
+            }
+            // A save stopped by Ctrl-C has backed out; so does the switch
+            interrupt::check()?;
+            // As the save left it, with the outgoing account's new snapshot
+            config = self.load_config()?;
+        }
+
+        let (transfer, live_backup) = self.activate(name, &account_meta.path, mode)?;
+        if transfer.is_some() {
+            self.record_manifest(name, self.live_manifest(None));
+        }
+
+        // Switching to the active account again keeps the way back
+        if previous.as_deref() != Some(name) {
+            config.previous = previous.clone();
+        }
+        config.current = Some(name.to_string());
+        if let Some(meta) = config.accounts.get_mut(name) {
+            meta.last_used = Some(self.clock.now().to_rfc3339());
+            meta.switch_count += 1;
+        }
+        crash::step(Step::UpdatingConfig);
+        self.save_config(&mut config)?;
+        history::append(
+            &self.history_file(),
+            &Event {
+                live_backup,
+                ..self.event(
+                    EventKind::Switch,
+                    name,
+                    source,
+                    previous.as_deref(),
+                    transfer,
+                )
+            },
+        )?;
+
+        let switched = tr!("account-switched", name = name);
+        self.say(&switched);
+        if let Some(transfer) = transfer {
+            self.say(stats::summary(transfer.usage, transfer.elapsed));
+        }
+
+        self.run_hook(Hook::PostSwitch, &hook_vars)?;
+        self.notify(
+            NotifyEvent::Switch,
+            name,
+            previous.as_deref(),
+            None,
+            source,
+            &switched,
+        );
+
+        // Claude Code refreshes the token on first use; capture that too
+        if let Err(e) = self.schedule_resave(name) {
+            output::warn(tr!("resave-failed", error = format!("{:#}", e)));
+        }
+        Ok(transfer)
+    }
+
+    /// The live directory when it isn't Claude Code's default, so a command
+    /// run from here can be pointed at it.
+    fn moved_claude_dir(&self) -> Option<&Path> {
+        // Claude Code's own default, which a sandbox doesn't move
+        let default = dirs::home_dir().map(|home| home.join(".claude"));
+        Some(self.claude_config_dir.as_path()).filter(|dir| default.as_deref() != Some(*dir))
+    }
+
+    /// Runs `command` with `name` live, then switches back to the account
+    /// that was active, however the command ends. Returns its exit code.
+    ///
+    /// The state lock is held throughout, so nothing else can switch in the
+    /// meantime and have its change undone by the switch back.
+    pub fn run_as(&self, name: &str, command: &[String]) -> Result<i32> {
+        let _lock = self.lock_state()?;
+        let config = self.load_config()?;
+        let account_meta = config
+            .get_account(name)
+            .ok_or_else(|| AccountError::NotFound(name.to_string()))?;
+
+        let previous = match config.current.as_deref() {
+            Some(current) if current == name => {
+                return runner::run(command, self.moved_claude_dir());
+            }
+            Some(current) => current.to_string(),
+            // Without an account to return to, the live configuration would
+            // be lost when it's swapped out
+            None => anyhow::bail!(tr!("run-no-current")),
+        };
+        if account_meta.protected && !self.confirm_protected(name)? {
+            self.say(tr!("cancelled"));
+            return Ok(1);
+        }
+
+        self.switch_to(name, None, EventSource::Run)?;
+        let status = runner::run(command, self.moved_claude_dir());
+        // The way back is where the user already was, so it isn't confirmed
+        self.switch_to(&previous, None, EventSource::Run)
+            .with_context(|| tr!("run-restore-failed", name = previous))?;
+        status
+    }
+
+    /// The snapshot of `name`, checked for Claude Code to use where it is.
+    /// The account active in the live configuration is refused: two copies
+    /// of one login would each refresh its token and lose the other's.
+    fn direct_dir(&self, name: &str) -> Result<(String, PathBuf)> {
+        let name = self.resolve_target(name)?;
+        let config = self.load_config()?;
+        let account_meta = config
+            .get_account(&name)
+            .ok_or_else(|| AccountError::NotFound(name.clone()))?;
+        if config.current.as_deref() == Some(name.as_str()) {
+            anyhow::bail!(tr!(
+                "env-current",
+                name = name,
+                path = self.claude_config_dir.display()
+            ));
+        }
+        if let Some(reason) = self.direct_use_blocker(&account_meta.path) {
+            anyhow::bail!(tr!("env-unusable", name = name, reason = reason));
+        }
+        // Claude Code changes the files in place, past what was recorded;
+        // the next switch records them again
+        manifest::remove(&self.state_dir, &name)?;
+        Ok((name, account_meta.path.clone()))
+    }
+
+    /// Prints the exports that point Claude Code at the snapshot of `name`,
+    /// for `shell` (the one in `$SHELL` if not given) to evaluate.
+    pub fn print_env(&self, name: &str, shell: Option<Shell>) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let (name, dir) = self.direct_dir(name)?;
+        if let Some(pid) = sessions::terminal_id() {
+            self.register_session(&name, SessionKind::Env, pid)?;
+        }
+        if self.format.is_json() {
+            return output::print_json(&EnvJson {
+                account: &name,
+                claude_config_dir: &dir,
+            });
+        }
+        print!("{}", direct::exports(shell, &dir)?);
+        Ok(())
+    }
+
+    /// Starts the user's shell with Claude Code pointed at the snapshot of
+    /// `name`, returning its exit code once it's left.
+    pub fn shell_as(&self, name: &str) -> Result<i32> {
+        let pid = std::process::id();
+        let (name, dir) = {
+            let _lock = self.lock_state()?;
+            let (name, dir) = self.direct_dir(name)?;
+            self.register_session(&name, SessionKind::Shell, pid)?;
+            (name, dir)
+        };
+        self.say(tr!("shell-started", name = name));
+        let status = runner::run(&[direct::interactive_shell()], Some(&dir));
+        let _lock = self.lock_state()?;
+        sessions::unregister(&self.sessions_file(), pid)?;
+        status
+    }
+
+    fn register_session(&self, name: &str, kind: SessionKind, pid: u32) -> Result<()> {
+        sessions::register(
+            &self.sessions_file(),
+            Session {
+                account: name.to_string(),
+                kind,
+                pid,
+                started_at: self.clock.now(),
+            },
+        )
+    }
+
+    fn daemon_socket(&self) -> PathBuf {
+        self.state_dir.join(daemon::SOCKET)
+    }
+
+    /// Starts the daemon in the background, on the same Claude directory.
+    pub fn daemon_start(&self) -> Result<()> {
+        let args = [
+            OsStr::new("daemon"),
+            OsStr::new("run"),
+            OsStr::new("--claude-dir"),
+            self.claude_config_dir.as_os_str(),
+        ];
+        let socket = self.daemon_socket();
+        let pid = daemon::start(&socket, &self.state_dir.join(daemon::LOG), &args)?;
+        self.say(tr!("daemon-started", pid = pid, path = socket.display()));
+        Ok(())
+    }
+
+    pub fn daemon_stop(&self) -> Result<()> {
+        match daemon::stop(&self.daemon_socket())? {
+            true => self.say(tr!("daemon-stopped")),
+            false => self.say(tr!("daemon-not-running")),
+        }
+        Ok(())
+    }
+
+    pub fn daemon_status(&self) -> Result<()> {
+        let socket = self.daemon_socket();
+        let status = daemon::status(&socket)?;
+        if self.format.is_json() {
+            return output::print_json(
+                &status.unwrap_or_else(|| serde_json::json!({"running": false})),
+            );
+        }
+        let Some(status) = status else {
+            println!("{}", tr!("daemon-not-running"));
+            return Ok(());
+        };
+        let since = serde_json::from_value::<DateTime<Utc>>(status["started_at"].clone())
+            .map(|at| at.format("%Y-%m-%d %H:%M UTC").to_string())
+            .unwrap_or_default();
+        println!(
+            "{}",
+            tr!(
+                "daemon-running",
+                pid = status["pid"],
+                time = since,
+                requests = status["requests"],
+                path = socket.display()
+            )
+        );
+        Ok(())
+    }
+
+    /// Installs and starts the daemon, backups on `backup`'s schedule (keeping
+    /// `keep`), or both, as user services (see [`service`]). Installing one
+    /// again replaces it.
+    pub fn service_install(&self, daemon: bool, backup: Option<Schedule>, keep: u64) -> Result<()> {
+        let system = System::current()?;
+        let dir =
+            system.dir(&paths::home_dir().with_context(|| tr!("failed-determine-home-directory"))?);
+        let invocation = Invocation {
+            exe: std::env::current_exe()
+                .with_context(|| tr!("failed-find-switchers-executable"))?,
+            claude_dir: self.claude_config_dir.clone(),
+            env: service::ENV_VARS
+                .iter()
+                .filter_map(|key| {
+                    let value = std::env::var(key).ok().filter(|value| !value.is_empty())?;
+                    Some((key.to_string(), value))
+                })
+                .collect(),
+        };
+
+        let backup = backup.map(|schedule| Job::Backup { schedule, keep });
+        let jobs = daemon.then_some(Job::Daemon).into_iter().chain(backup);
+        fs::create_dir_all(&dir)
+            .with_context(|| tr!("failed-create-directory", path = dir.display()))?;
+        for job in jobs {
+            for file in system.files(job, &invocation) {
+                let path = dir.join(&file.name);
+                fs::write(&path, file.contents)
+                    .with_context(|| tr!("failed-write", path = path.display()))?;
+            }
+            system.enable(&dir, job)?;
+            self.say(tr!(
+                "service-installed",
+                service = job.name(),
+                path = dir.display()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Stops and removes the daemon's service, the backups' or (with neither)
+    /// both. Ones that aren't installed are left alone.
+    pub fn service_uninstall(&self, daemon: bool, backup: bool) -> Result<()> {
+        let system = System::current()?;
+        let dir =
+            system.dir(&paths::home_dir().with_context(|| tr!("failed-determine-home-directory"))?);
+        let all = !daemon && !backup;
+        let jobs = service::ALL.into_iter().filter(|job| match job {
+            Job::Daemon => all || daemon,
+            Job::Backup { .. } => all || backup,
+        });
+
+        let mut removed = false;
+        for job in jobs {
+            let paths: Vec<PathBuf> = system
+                .file_names(job)
+                .iter()
+                .map(|name| dir.join(name))
+                .filter(|path| path.exists())
+                .collect();
+            if paths.is_empty() {
+                continue;
+            }
+            // Whatever is installed goes, even if it wasn't running
+            if let Err(e) = system.disable(&dir, job) {
+                output::warn(format!("{:#}", e));
+            }
+            for path in &paths {
+                fs::remove_file(path)
+                    .with_context(|| tr!("failed-remove", path = path.display()))?;
+            }
+            removed = true;
+            self.say(tr!("service-removed", service = job.name()));
+        }
+        match removed {
+            true => system.reload(),
+            false => {
+                self.say(tr!("service-none-installed"));
+                Ok(())
+            }
+        }
+    }
+
+    /// Says which services are installed and whether they're running.
+    pub fn service_status(&self) -> Result<()> {
+        let system = System::current()?;
+        let dir =
+            system.dir(&paths::home_dir().with_context(|| tr!("failed-determine-home-directory"))?);
+        let services: Vec<ServiceJson> = service::ALL
+            .into_iter()
+            .map(|job| {
+                let path = dir.join(&system.file_names(job)[0]);
+                let installed = path.exists();
+                ServiceJson {
+                    service: job.name(),
+                    installed,
+                    active: installed.then(|| system.is_active(job)).flatten(),
+                    path,
+                }
+            })
+            .collect();
+        match self.format.is_json() {
+            true => output::print_json(&services),
+            false => {
+                for service in &services {
+                    println!("{}", self.format.service(service));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Serves the daemon's socket in the foreground, for `daemon start` or
+    /// a service manager.
+    pub fn daemon_run(&self) -> Result<()> {
+        daemon::run(self, &self.daemon_socket())
+    }
+
+    /// Serves the MCP tools on stdin and stdout until stdin ends.
+    pub fn mcp_serve(&self) -> Result<()> {
+        mcp::serve(io::stdin().lock(), io::stdout(), |tool, arguments| {
+            Some(match tool {
+                "list_accounts" => self.mcp_list_accounts(),
+                "current_account" => self
+                    .current_account()
+                    .map(|current| serde_json::json!({ "current": current }).to_string()),
+                "switch_account" => {
+                    mcp::argument(arguments, "name").and_then(|name| self.mcp_switch(name))
+                }
+                _ => return None,
+            })
+        })
+    }
+
+    /// Answers `api` requests on stdin and stdout until stdin ends.
+    pub fn api_serve(&self) -> Result<()> {
+        api::serve(io::stdin().lock(), io::stdout(), |request| {
+            let action = |action, account: &str, outcome: SwitchOutcome| {
+                let (cancelled, copied) = match outcome {
+                    SwitchOutcome::Switched(transfer) => (false, transfer.map(Into::into)),
+                    SwitchOutcome::Cancelled => (true, None),
+                };
+                serde_json::to_value(ActionJson {
+                    action,
+                    account,
+                    cancelled,
+                    copied,
+                })
+                .with_context(|| tr!("failed-serialize-output"))
+            };
+            match request {
+                api::Request::Current => {
+                    Ok(serde_json::json!({ "current": self.current_account()? }))
+                }
+                api::Request::List => Ok(serde_json::Value::Array(self.account_list()?)),
+                api::Request::Save { name } => {
+                    let transfer = self.save_for(&name, EventSource::Api)?;
+                    action("save", &name, SwitchOutcome::Switched(transfer))
+                }
+                api::Request::Switch { name } => {
+                    let name = self.resolve_target(&name)?;
+                    let outcome = self.switch_for(&name, None, EventSource::Api)?;
+                    action("switch", &name, outcome)
+                }
+                api::Request::Delete { name, keep_data } => {
+                    let name = self.resolve_target(&name)?;
+                    self.delete_account(&name, keep_data)?;
+                    Ok(serde_json::json!({ "account": name }))
+                }
+                api::Request::Rename { from, to } => {
+                    self.rename_account(&self.resolve_target(&from)?, &to)?;
+                    Ok(serde_json::json!({ "account": to }))
+                }
+            }
+        })
+    }
+
+    fn mcp_list_accounts(&self) -> Result<String> {
+        serde_json::to_string_pretty(&self.account_list()?)
+            .with_context(|| tr!("failed-serialize-output"))
+    }
+
+    /// The accounts, as `mcp-serve` and `api` list them.
+    fn account_list(&self) -> Result<Vec<serde_json::Value>> {
+        let accounts: Vec<_> = self
+            .account_summaries()?
+            .into_iter()
+            .map(|account| {
+                serde_json::json!({
+                    "name": account.name,
+                    "current": account.current,
+                    "protected": account.protected,
+                    "saved_at": account.saved_at,
+                    "last_used": account.last_used,
+                })
+            })
+            .collect();
+        Ok(accounts)
+    }
+
+    fn mcp_switch(&self, name: &str) -> Result<String> {
+        let name = self.resolve_target(name)?;
+        Ok(match self.switch_for(&name, None, EventSource::Mcp)? {
+            SwitchOutcome::Switched(_) => tr!("account-switched", name = name),
+            SwitchOutcome::Cancelled => tr!("cancelled"),
+        })
+    }
+
+    /// Lists the terminals using an account from the store.
+    pub fn list_sessions(&self) -> Result<()> {
+        let sessions = sessions::live(&self.sessions_file())?;
+        if self.format.is_json() {
+            return output::print_json(&sessions);
+        }
+        if sessions.is_empty() {
+            println!("{}", tr!("sessions-none"));
+            return Ok(());
+        }
+        for session in &sessions {
+            println!("{}", self.format.session(session));
+        }
+        Ok(())
+    }
+
+    /// Signs in to a new account through `login` (`claude /login` unless
+    /// given) and saves it as `name`. The current account is saved first and
+    /// put back afterwards, whether the login worked or not; without one, the
+    /// new account stays live.
+    pub fn add_account(&self, name: &str, login: &[String]) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let config = self.load_config()?;
+        if config.accounts.contains_key(name) {
+            return Err(AccountError::AlreadyExists(name.to_string()).into());
+        }
+        names::validate(name)?;
+        self.ensure_no_case_conflict(&config, name, None)?;
+
+        let live_credentials = || {
+            claude::CREDENTIAL_FILES
+                .iter()
+                .map(|file| self.claude_config_dir.join(file))
+                .filter(|path| path.is_file())
+                .collect::<Vec<_>>()
+        };
+        let previous = match config.current.clone() {
+            Some(current) => {
+                let meta = config
+                    .get_account(&current)
+                    .ok_or_else(|| AccountError::NotFound(current.clone()))?;
+                // Clearing the login would clear it in the snapshot too
+                if platform::same_location(&self.claude_config_dir, &meta.path) {
+                    anyhow::bail!(tr!("add-linked", name = current));
+                }
+                if self.claude_config_dir.exists() {
+                    self.save_from(&current, EventSource::AutoSave)?;
+                }
+                Some((current, meta.path.clone()))
+            }
+            None if !live_credentials().is_empty() => anyhow::bail!(tr!("add-unsaved-login")),
+            None => None,
+        };
+
+        for path in live_credentials() {
+            fs::remove_file(&path)
+                .with_context(|| tr!("failed-remove-file", path = path.display()))?;
+        }
+        self.say(tr!("add-login-hint", name = name));
+        let status = runner::run(login, self.moved_claude_dir());
+        let saved = match status {
+            Ok(0) if !live_credentials().is_empty() => self.save_from(name, EventSource::Cli),
+            Ok(_) => Err(anyhow::anyhow!(tr!("add-login-failed"))),
+            Err(e) => Err(e),
+        };
+
+        if let Some((previous, path)) = &previous {
+            self.activate(previous, path, None)
+                .map(|(transfer, _)| transfer)
+                .with_context(|| tr!("run-restore-failed", name = previous))?;
+            self.record_manifest(previous, self.live_manifest(None));
+            let mut config = self.load_config()?;
+            config.current = Some(previous.clone());
+            self.save_config(&mut config)?;
+        }
+        saved?;
+
+        match &previous {
+            Some((previous, _)) => self.say(tr!("add-done", name = name, previous = previous)),
+            None => self.say(tr!("add-done-live", name = name)),
+        }
+        Ok(())
+    }
+
+    /// Signs `name` in again: switches to it, runs `login` (`claude /login`
+    /// unless given) and saves the login it leaves. The account stays active.
+    pub fn renew(&self, name: &str, login: &[String]) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let name = self.resolve_target(name)?;
+        let config = self.load_config()?;
+        let meta = config
+            .get_account(&name)
+            .ok_or_else(|| AccountError::NotFound(name.clone()))?;
+        // Checked first, so the switch isn't for nothing
+        if meta.locked && !self.force {
+            return Err(AccountError::Locked(name).into());
+        }
+
+        if config.current.as_deref() != Some(name.as_str())
+            && self.switch_from(&name, None, EventSource::Cli)?.is_none()
+        {
+            return Ok(());
+        }
+        self.say(tr!("renew-login-hint", name = name));
+        if runner::run(login, self.moved_claude_dir())? != 0 {
+            anyhow::bail!(tr!("renew-login-failed", name = name));
+        }
+        self.save_from(&name, EventSource::Cli)?;
+        self.say(tr!("renewed", name = name));
+        Ok(())
+    }
+
+    /// Warns about the accounts among `names` saved longer ago than
+    /// `remind-after`, suggesting `renew`.
+    /// `config.health(name)`, telling a snapshot whose drive isn't mounted
+    /// from one that's gone.
+    fn account_health(&self, config: &AccountsConfig, name: &str) -> Option<EntryHealth> {
+        let health = config.health(name)?;
+        let store = config
+            .get_account(name)
+            .and_then(|meta| self.custom_store(meta));
+        Some(match (health, store) {
+            (EntryHealth::Missing(_), Some(store)) if !store::is_mounted(store) => {
+                EntryHealth::Unmounted(store.to_path_buf())
+            }
+            (health, _) => health,
+        })
+    }
+
+    /// The accounts that can't be used, as [`account_health`](Self::account_health) has it.
+    fn unhealthy(&self, config: &AccountsConfig) -> Vec<(String, EntryHealth)> {
+        config
+            .unhealthy()
+            .into_iter()
+            .filter_map(|(name, _)| Some((name.clone(), self.account_health(config, &name)?)))
+            .collect()
+    }
+
+    /// Warns about each account that can't be used, and what `repair` can
+    /// do about it.
+    fn warn_unhealthy(&self, config: &AccountsConfig) {
+        for (name, health) in self.unhealthy(config) {
+            match health {
+                EntryHealth::Unmounted(store) => {
+                    output::warn(tr!("entry-unmounted", name = name, path = store.display()))
+                }
+                EntryHealth::Missing(path) => {
+                    output::warn(tr!("entry-missing", name = name, path = path.display()))
+                }
+                EntryHealth::Corrupt(error) => {
+                    output::warn(tr!("entry-corrupt", name = name, error = error))
+                }
+                EntryHealth::Healthy => {}
+            }
+        }
+    }
+
+    fn remind_stale<'a>(
+        &self,
+        config: &AccountsConfig,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> Result<()> {
+        let Some(after) = self.settings.remind_after()? else {
+            return Ok(());
+        };
+        let now = self.clock.now();
+        for name in names {
+            let Some(saved_at) = config
+                .get_account(name)
+                .and_then(|meta| DateTime::parse_from_rfc3339(&meta.saved_at).ok())
+            else {
+                continue;
+            };
+            let age = now - saved_at.with_timezone(&Utc);
+            if age > after {
+                output::warn(tr!("renew-reminder", name = name, days = age.num_days()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Signs the live configuration out, saving the current account first:
+    /// its credential files are removed, or with `all` the whole Claude
+    /// directory, except `settings.json` with `keep_settings`. Afterwards no
+    /// account is active, and `switch -` goes back to the one that was.
+    pub fn logout(&self, all: bool, keep_settings: bool) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let mut config = self.load_config()?;
+        let live = &self.claude_config_dir;
+        let linked = config
+            .current
+            .as_deref()
+            .and_then(|current| config.get_account(current))
+            .is_some_and(|meta| platform::same_location(live, &meta.path));
+
+        match &config.current {
+            // Linked, the live directory already is the snapshot
+            Some(current) if live.exists() && !linked => {
+                self.save_from(current, EventSource::AutoSave)?;
+                config = self.load_config()?;
+            }
+            Some(_) => {}
+            None if live.exists() && !self.confirm(&tr!("logout-unsaved"), false)? => {
+                self.say(tr!("cancelled"));
+                return Ok(());
+            }
+            None => {}
+        }
+
+        if linked {
+            // Removing files would remove them from the snapshot
+            platform::remove_symlink(live).with_context(|| tr!("failed-remove-symlink"))?;
+        } else if all && keep_settings && live.is_dir() {
+            for entry in fs::read_dir(live)
+                .with_context(|| tr!("failed-read-directory", path = live.display()))?
+            {
+                let path = entry?.path();
+                if path.file_name() == Some(claude::SETTINGS_FILE.as_ref()) {
+                    continue;
+                }
+                if path.is_dir() && !path.is_symlink() {
+                    retry(|| fs::remove_dir_all(&path))
+                } else {
+                    fs::remove_file(&path)
+                }
+                .with_context(|| tr!("failed-remove", path = path.display()))?;
+            }
+        } else if all && live.exists() {
+            // Kept like a live directory a switch replaced, for `recover-live`
+            self.set_live_aside(live, config.current.as_deref())
+                .with_context(|| tr!("failed-remove-directory", path = live.display()))?;
+        } else {
+            for file in claude::CREDENTIAL_FILES {
+                let path = live.join(file);
+                if path.is_file() {
+                    fs::remove_file(&path)
+                        .with_context(|| tr!("failed-remove-file", path = path.display()))?;
+                }
+            }
+        }
+
+        let current = config.current.take();
+        if current.is_some() {
+            config.previous = current.clone();
+        }
+        self.save_config(&mut config)?;
+        match current {
+            Some(name) => self.say(tr!("logged-out", name = name)),
+            None => self.say(tr!("logged-out-untracked")),
+        }
+        Ok(())
+    }
+
+    /// Makes the live configuration `account_dir`'s snapshot of `name`, by
+    /// linking or copying, and returns what was copied and the live backup
+    /// the configuration it replaced was kept as.
+    ///
+    /// The new configuration is built beside the live one and renamed into
+    /// place, and the one it replaces is only removed once everything has
+    /// succeeded. Until then any failure puts the previous one back.
+    fn activate(
+        &self,
+        name: &str,
+        account_dir: &Path,
+        mode: Option<SwitchMode>,
+    ) -> Result<(Option<Transfer>, Option<String>)> {
+        // Validate account directory exists
+        if !self.storage.exists(account_dir) {
+            anyhow::bail!(tr!("account-dir-not-found", path = account_dir.display()));
+        }
+
+        // The account whose live directory this replaces
+        let replaced = self.load_config()?.current;
+        crash::step(Step::RestoringLive);
+        // A link would hand the shared snapshot to Claude Code to write
+        let mode = match (&self.system, mode) {
+            (Some(_), Some(mode)) if mode != SwitchMode::Copy => {
+                anyhow::bail!(tr!("system-copy-only"))
+            }
+            (Some(_), _) => SwitchMode::Copy,
+            (None, mode) => mode.unwrap_or(self.settings.mode),
+        };
+        let link = (mode == SwitchMode::Symlink)
+            .then(|| self.stage_live_link(account_dir))
+            .flatten();
+        let hardlink = mode == SwitchMode::Hardlink && self.can_hardlink(account_dir);
+        let (staged, transfer) = match link {
+            Some(link) => (link, None),
+            None => {
+                let staged = self.live_sibling(STAGED_SUFFIX);
+                let transfer = match hardlink {
+                    true => self.stage_live_hardlinks(account_dir, &staged),
+                    false => self.stage_live_copy(account_dir, &staged),
+                };
+                if transfer.is_err() {
+                    self.discard(&staged);
+                }
+                (staged, Some(transfer?))
+            }
+        };
+
+        crash::step(Step::SwappingLive);
+        let previous = self.swap_live(&staged)?;
+        let mut carried = Vec::new();
+        if let Err(e) = self.finish_activation(name, account_dir, previous.as_deref(), &mut carried)
+        {
+            self.restore_previous_live(previous.as_deref(), &carried);
+            return Err(e);
+        }
+        let mut live_backup = None;
+        if let Some(previous) = &previous {
+            match self.set_live_aside(previous, replaced.as_deref()) {
+                Ok(id) => live_backup = id,
+                // Cleared by the next switch if it can't be removed now
+                Err(_) => self.discard(previous),
+            }
+        }
+        // Where earlier versions kept the one replaced live directory
+        self.discard(&self.live_sibling(UNDO_SUFFIX));
+        // With no live directory replaced there's no backup to keep them in
+        self.discard(&self.live_sibling(PREVIOUS_PATHS_SUFFIX));
+
+        Ok((transfer, live_backup))
+    }
+
+    /// The steps of a switch that run against the new live configuration:
+    /// bringing over the files the capture policy leaves shared, then the
+    /// keychain credentials, the banner and the paths kept outside it.
+    fn finish_activation(
+        &self,
+        name: &str,
+        account_dir: &Path,
+        previous: Option<&Path>,
+        carried: &mut Vec<(PathBuf, PathBuf)>,
+    ) -> Result<()> {
+        // Nothing is shared out of a linked snapshot, as before it was swapped
+        if let Some(previous) = previous.filter(|previous| !self.storage.is_symlink(previous))
+            && !self.capture.is_empty()
+        {
+            self.carry_uncaptured(previous, &self.claude_config_dir, Path::new(""), carried)
+                .with_context(|| tr!("failed-keep-shared-files"))?;
+        }
+        if self.uses_keychain() {
+            crash::step(Step::RestoringCredentials);
+            self.restore_keychain_credentials(name)?;
+        }
+        self.restore_extra_paths(account_dir, true)
+            .with_context(|| tr!("failed-restore-files-outside-claude-directory"))?;
+        // Before the banner, which a shared `CLAUDE.md` would otherwise lose
+        if let Some(overlay) = self.team_overlay() {
+            self.apply_team_overlay(&overlay);
+        }
+        if let Some(template) = &self.settings.banner {
+            self.write_banner(&banner::render(template, name))?;
+        }
+        Ok(())
+    }
+
+    /// The configured team overlay, with `~` for the home directory.
+    fn team_overlay(&self) -> Option<PathBuf> {
+        let dir = self.settings.team_overlay.as_deref()?;
+        match (dir.strip_prefix("~"), paths::home_dir()) {
+            (Ok(rest), Some(home)) => Some(home.join(rest)),
+            _ => Some(dir.to_path_buf()),
+        }
+    }
+
+    /// Lays the team overlay over the live configuration. The switch has
+    /// happened by now, so one missing or broken is only warned about.
+    fn apply_team_overlay(&self, overlay: &Path) {
+        if !overlay.is_dir() {
+            output::warn(tr!("team-overlay-missing", path = overlay.display()));
+            return;
+        }
+        // The `.claude.json` each account keeps, else the one Claude Code
+        // reads from the Claude directory
+        let claude_json = self
+            .extra_paths
+            .iter()
+            .map(|path| &path.live)
+            .find(|live| live.file_name() == Some(template::CLAUDE_JSON.as_ref()))
+            .cloned()
+            .unwrap_or_else(|| self.claude_config_dir.join(template::CLAUDE_JSON));
+        match overlay::apply(overlay, &self.claude_config_dir, &claude_json) {
+            Ok(count) => info!(path = %overlay.display(), count, "team overlay applied"),
+            Err(e) => output::warn(tr!("team-overlay-failed", error = format!("{:#}", e))),
+        }
+    }
+
+    /// Puts an earlier snapshot of `name` back: the newest one, or the one
+    /// `to` names. The snapshot it replaces is kept as a version in turn, so
+    /// a rollback can itself be undone.
+    pub fn rollback(&self, name: &str, to: Option<&str>) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let _operation = crash::begin("rollback", name);
+        let mut config = self.load_config()?;
+        let account_meta = config
+            .get_account(name)
+            .ok_or_else(|| AccountError::NotFound(name.to_string()))?
+            .clone();
+
+        let available = versions::list(&self.switcher_dir, name)?;
+        let version = match to {
+            Some(id) => available.iter().find(|version| version.id == id),
+            None => available.first(),
+        };
+        let Some(version) = version else {
+            anyhow::bail!(match to {
+                Some(id) => tr!("version-not-found", id = id, name = name),
+                None => tr!("no-versions", name = name),
+            });
+        };
+
+        crash::step(Step::RestoringVersion);
+        let mut replaced = None;
+        if account_meta.path.exists() {
+            let kept = versions::allocate(&self.switcher_dir, name, &account_meta.saved_at)?;
+            retry(|| fs::rename(&account_meta.path, &kept))
+                .with_context(|| tr!("failed-keep-current-snapshot"))?;
+            if self.uses_keychain() {
+                self.keep_keychain_credentials(name, &kept)?;
+            }
+            replaced = Some(kept);
+        }
+        retry(|| fs::rename(&version.path, &account_meta.path))
+            .with_context(|| tr!("failed-restore-snapshot"))?;
+        if let Some(replaced) = &replaced {
+            let nested = nested_snapshots(&config, &account_meta.path);
+            self.move_nested(replaced, &account_meta.path, &nested)?;
+        }
+        if self.uses_keychain() {
+            self.restore_kept_credentials(name, &account_meta.path)?;
+        }
+
+        let saved_at = version
+            .saved_at()
+            .map_or_else(|| account_meta.saved_at.clone(), |time| time.to_rfc3339());
+        if let Some(meta) = config.accounts.get_mut(name) {
+            meta.saved_at = saved_at;
+        }
+        crash::step(Step::UpdatingConfig);
+        self.save_config(&mut config)?;
+        self.record(EventKind::Rollback, name, EventSource::Cli)?;
+        self.say(tr!("rolled-back", name = name, id = version.id));
+
+        // Otherwise the next switch would save the live session over it
+        if config.current.as_deref() == Some(name) {
+            self.recover_interrupted_switch()?;
+            if self.activate(name, &account_meta.path, None)?.0.is_some() {
+                self.record_manifest(name, self.live_manifest(None));
+            }
+            self.say(tr!("rolled-back-live"));
+        } else {
+            // Recorded again when it's next switched to
+            manifest::remove(&self.state_dir, name)?;
+        }
+        Ok(())
+    }
+
+    /// Lists the versions `rollback` can return `name` to, newest first.
+    pub fn show_versions(&self, name: &str, limit: Option<usize>) -> Result<()> {
+        let config = self.load_config()?;
+        if !config.accounts.contains_key(name) {
+            return Err(AccountError::NotFound(name.to_string()).into());
+        }
+
+        let mut rows = Vec::new();
+        for version in versions::list(&self.switcher_dir, name)?
+            .into_iter()
+            .take(limit.unwrap_or(usize::MAX))
+        {
+            let usage = size::dir_usage(&version.path)?;
+            rows.push(VersionRow {
+                saved_at: version.saved_at().map(|time| time.to_rfc3339()),
+                files: usage.files,
+                bytes: usage.bytes,
+                id: version.id,
+            });
+        }
+
+        if self.format.is_json() {
+            return output::print_json(&rows);
+        }
+        if rows.is_empty() {
+            println!("{}", tr!("no-versions", name = name));
+            return Ok(());
+        }
+        for row in &rows {
+            println!("{}", self.format.version(row));
+        }
+        Ok(())
+    }
+
+    /// Marks an account as requiring confirmation before it's switched to, or
+    /// lifts that requirement.
+    pub fn set_protected(&self, name: &str, protected: bool) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let mut config = self.load_config()?;
+        let meta = config
+            .accounts
+            .get_mut(name)
+            .ok_or_else(|| AccountError::NotFound(name.to_string()))?;
+        meta.protected = protected;
+        self.save_config(&mut config)?;
+
+        let message = if protected {
+            tr!("account-protected", name = name)
+        } else {
+            tr!("account-unprotected", name = name)
+        };
+        self.say(message);
+        Ok(())
+    }
+
+    /// Pins an account to a Claude Code `version`, by default the installed
+    /// one, or with `off` lifts the pin.
+    pub fn pin(&self, name: &str, version: Option<&str>, off: bool) -> Result<()> {
+        let pin = match (version, off) {
+            (_, true) => None,
+            (Some(version), false) => Some(version.to_string()),
+            (None, false) => match self.installed_claude_version() {
+                Some(installed) => Some(installed.to_string()),
+                None => anyhow::bail!(tr!("pin-no-claude")),
+            },
+        };
+        let _lock = self.lock_state()?;
+        let mut config = self.load_config()?;
+        let meta = config
+            .accounts
+            .get_mut(name)
+            .ok_or_else(|| AccountError::NotFound(name.to_string()))?;
+        meta.pinned_version = pin.clone();
+        self.save_config(&mut config)?;
+
+        self.say(match pin {
+            Some(version) => tr!("account-pinned", name = name, version = version),
+            None => tr!("account-unpinned", name = name),
+        });
+        Ok(())
+    }
+
+    /// Fails when `meta` is pinned to a Claude Code version other than the
+    /// installed one, and warns when its snapshot was saved under a release
+    /// whose configuration format may differ.
+    fn check_claude_version(&self, name: &str, meta: &AccountMetadata) -> Result<()> {
+        if meta.claude_version.is_none() && meta.pinned_version.is_none() {
+            return Ok(());
+        }
+        let Some(installed) = self.installed_claude_version() else {
+            if let Some(pinned) = &meta.pinned_version {
+                output::warn(tr!("claude-version-unknown", name = name, version = pinned));
+            }
+            return Ok(());
+        };
+        match (&meta.pinned_version, &meta.claude_version) {
+            (Some(pinned), _) if !claude_version::satisfies(installed, pinned) => {
+                return Err(AccountError::VersionMismatch(
+                    name.to_string(),
+                    pinned.clone(),
+                    installed.to_string(),
+                )
+                .into());
+            }
+            (None, Some(saved)) if claude_version::drifted(saved, installed) => {
+                output::warn(tr!(
+                    "claude-version-drift",
+                    name = name,
+                    saved = saved,
+                    installed = installed
+                ));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Locks an account against being saved over, deleted or pruned, or
+    /// unlocks it again.
+    pub fn set_locked(&self, name: &str, locked: bool) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let mut config = self.load_config()?;
+        let meta = config
+            .accounts
+            .get_mut(name)
+            .ok_or_else(|| AccountError::NotFound(name.to_string()))?;
+        meta.locked = locked;
+        self.save_config(&mut config)?;
+
+        let message = match locked {
+            true => tr!("account-locked", name = name),
+            false => tr!("account-unlocked", name = name),
+        };
+        self.say(message);
+        Ok(())
+    }
+
+    /// Prints the OAuth token `name` is signed in with, or the API key it
+    /// uses instead, masked unless `reveal`. With `copy` it goes to the
+    /// clipboard instead, to be cleared again after that long.
+    pub fn show_token(&self, name: &str, reveal: bool, copy: Option<Duration>) -> Result<()> {
+        let name = &self.resolve_target(name)?;
+        let config = self.load_config()?;
+        let meta = config
+            .get_account(name)
+            .ok_or_else(|| AccountError::NotFound(name.to_string()))?;
+        let (kind, token) = self
+            .account_token(&config, name, &meta.path)?
+            .ok_or_else(|| anyhow::anyhow!(tr!("token-none", name = name)))?;
+
+        if let Some(clear_after) = copy {
+            clipboard::copy(&token)?;
+            clipboard::clear_later(&token, clear_after)?;
+        }
+        let shown = match reveal {
+            true => token.clone(),
+            false => claude::redact(&token),
+        };
+        if self.format.is_json() {
+            return output::print_json(&TokenJson {
+                name,
+                kind,
+                token: shown,
+                masked: !reveal,
+                copied: copy.is_some(),
+            });
+        }
+        match copy {
+            Some(clear_after) => self.say(tr!(
+                "token-copied",
+                name = name,
+                seconds = clear_after.as_secs()
+            )),
+            None => {
+                println!("{}", shown);
+                if !reveal {
+                    self.say(tr!("token-masked-hint"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Prints the environment that signs SDK scripts and CI jobs in as `name`
+    /// without a Claude directory, in `format`: the `env` block of its
+    /// `settings.json`, its variables from `var set`, then its OAuth token or
+    /// API key.
+    pub fn print_envfile(&self, name: &str, format: envfile::Format) -> Result<()> {
+        let name = &self.resolve_target(name)?;
+        let vars = self.envfile_vars(name)?;
+        if self.format.is_json() {
+            return output::print_json(&EnvfileJson {
+                account: name,
+                variables: vars.into_iter().collect(),
+            });
+        }
+        print!("{}", envfile::render(&vars, format));
+        Ok(())
+    }
+
+    fn envfile_vars(&self, name: &str) -> Result<Vec<(String, String)>> {
+        let config = self.load_config()?;
+        let meta = config
+            .get_account(name)
+            .ok_or_else(|| AccountError::NotFound(name.to_string()))?;
+        let (kind, token) = self
+            .account_token(&config, name, &meta.path)?
+            .ok_or_else(|| anyhow::anyhow!(tr!("token-none", name = name)))?;
+        let secret = envfile::secret_var(kind);
+        let settings = self
+            .read_stored(&meta.path.join("settings.json"))?
+            .unwrap_or_default();
+        let mut env = envfile::settings_env(&settings);
+        env.extend(meta.env.clone());
+        let mut vars: Vec<_> = env.into_iter().filter(|(var, _)| var != secret).collect();
+        vars.push((secret.to_string(), token));
+        Ok(vars)
+    }
+
+    /// The kind and value of the secret `name` uses: its OAuth access token,
+    /// from the live credentials while it's active, or else the API key in
+    /// its `.claude.json`.
+    fn account_token(
+        &self,
+        config: &AccountsConfig,
+        name: &str,
+        account_dir: &Path,
+    ) -> Result<Option<(&'static str, String)>> {
+        let credentials = match config.current.as_deref() == Some(name) {
+            true => fs::read_to_string(self.claude_config_dir.join(CREDENTIALS_FILE)).ok(),
+            false => None,
+        };
+        let credentials = match credentials {
+            Some(credentials) => Some(credentials),
+            None => self.saved_credentials(name, account_dir)?,
+        };
+        if let Some(token) = credentials.as_deref().and_then(claude::read_access_token) {
+            return Ok(Some(("oauth", token)));
+        }
+        for path in [
+            account_dir.join(template::CLAUDE_JSON),
+            account_dir.join(extra_paths::in_home(template::CLAUDE_JSON)),
+        ] {
+            if let Some(key) = self
+                .read_stored(&path)?
+                .as_deref()
+                .and_then(claude::read_api_key)
+            {
+                return Ok(Some(("api-key", key)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Packs the snapshot of `name` into one file in the store's
+    /// `.archived` directory, sealed with the store key when the store is
+    /// encrypted, and removes the snapshot directory. `unarchive`, or a
+    /// switch to it, unpacks it again.
+    pub fn archive_account(&self, name: &str) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let mut config = self.load_config()?;
+        let meta = config
+            .get_account(name)
+            .ok_or_else(|| AccountError::NotFound(name.to_string()))?
+            .clone();
+        if meta.archive.is_some() {
+            anyhow::bail!(tr!("archive-already", name = name));
+        }
+        if meta.locked && !self.force {
+            return Err(AccountError::Locked(name.to_string()).into());
+        }
+        if config.current.as_deref() == Some(name) {
+            anyhow::bail!(tr!("archive-active", name = name));
+        }
+        // Directories registered with add-existing aren't ours to remove
+        if meta.external {
+            anyhow::bail!(tr!("archive-external", name = name));
+        }
+        if !nested_snapshots(&config, &meta.path).is_empty() {
+            anyhow::bail!(tr!("archive-nested", name = name));
+        }
+        if !self.storage.is_dir(&meta.path) {
+            anyhow::bail!(tr!("account-dir-not-found", path = meta.path.display()));
+        }
+
+        let manifest = ArchiveManifest::new(name, &meta.saved_at, self.clock.now(), None);
+        let data = archive::pack_cold(&manifest, &meta.path)?;
+        let data = match self.encryption_enabled() {
+            true => self.vault()?.seal(data)?,
+            false => data,
+        };
+        let path = archive::cold_path(&self.switcher_dir, name);
+        if let Some(parent) = path.parent() {
+            self.storage
+                .create_dir_all(parent)
+                .with_context(|| tr!("failed-create-directory", path = parent.display()))?;
+        }
+        let bytes = data.len() as u64;
+        self.storage
+            .write(&path, &data)
+            .with_context(|| tr!("failed-write-file", path = path.display()))?;
+
+        // Recorded before the directory goes, so an interruption leaves both
+        if let Some(entry) = config.accounts.get_mut(name) {
+            entry.archive = Some(path.clone());
+        }
+        self.save_config(&mut config)?;
+        info!(name, path = %path.display(), bytes, "archive account");
+        self.storage
+            .remove_dir_all(&meta.path)
+            .with_context(|| tr!("failed-remove-directory", path = meta.path.display()))?;
+        self.say(tr!(
+            "account-archived",
+            name = name,
+            size = size::format_bytes(bytes)
+        ));
+        Ok(())
+    }
+
+    /// Unpacks the snapshot `archive_account` packed for `name`.
+    pub fn unarchive_account(&self, name: &str) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let mut config = self.load_config()?;
+        let meta = config
+            .get_account(name)
+            .ok_or_else(|| AccountError::NotFound(name.to_string()))?;
+        if meta.archive.is_none() {
+            anyhow::bail!(tr!("archive-not-archived", name = name));
+        }
+        self.unarchive(&mut config, name)?;
+        self.say(tr!("account-unarchived", name = name));
+        Ok(())
+    }
+
+    /// Unpacks `name`'s archive back into its snapshot directory and saves
+    /// `config` without it. Nothing happens to an account that isn't
+    /// archived.
+    fn unarchive(&self, config: &mut AccountsConfig, name: &str) -> Result<()> {
+        let Some(meta) = config.accounts.get_mut(name) else {
+            return Ok(());
+        };
+        let Some(path) = meta.archive.clone() else {
+            return Ok(());
+        };
+        let data = self
+            .storage
+            .read(&path)
+            .with_context(|| tr!("failed-read-file", path = path.display()))?;
+        let data = match crypto::is_sealed(&data) {
+            true => self.vault()?.open(data)?,
+            false => data,
+        };
+        // Left over from an interrupted archive or unarchive
+        if self.storage.exists(&meta.path) {
+            self.storage
+                .remove_dir_all(&meta.path)
+                .with_context(|| tr!("failed-remove-directory", path = meta.path.display()))?;
+        }
+        if let Err(e) = archive::unpack_cold(data, &meta.path) {
+            let _ = self.storage.remove_dir_all(&meta.path);
+            return Err(e);
+        }
+        info!(name, path = %path.display(), "unarchive account");
+        meta.archive = None;
+        self.save_config(config)?;
+        self.storage
+            .remove_file(&path)
+            .with_context(|| tr!("failed-remove-file", path = path.display()))
+    }
+
+    /// Records that `name`, or the active account, ran into a rate limit
+    /// lifting after `resets_in` (a usage window by default), or with
+    /// `clear` that it has quota again.
+    pub fn limit_hit(
+        &self,
+        name: Option<&str>,
+        resets_in: Option<&str>,
+        clear: bool,
+    ) -> Result<()> {
+        let resets_in = resets_in.map(parse_duration).transpose()?;
+        let _lock = self.lock_state()?;
+        let mut config = self.load_config()?;
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => config
+                .current
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!(tr!("limit-no-current")))?,
+        };
+        let meta = config
+            .accounts
+            .get_mut(&name)
+            .ok_or_else(|| AccountError::NotFound(name.clone()))?;
+
+        if clear {
+            meta.limited_until = None;
+            self.save_config(&mut config)?;
+            self.say(tr!("limit-cleared", name = name));
+            return Ok(());
+        }
+        let until = quota::lifts_at(self.clock.now(), resets_in);
+        meta.limited_until = Some(until.to_rfc3339());
+        self.save_config(&mut config)?;
+        self.say(tr!(
+            "limit-recorded",
+            name = name,
+            time = until.format("%Y-%m-%d %H:%M UTC")
+        ));
+        Ok(())
+    }
+
+    /// Switches to the next account after the active one in the rotation:
+    /// the `rotation` setting, or every account by name. With `group`, only
+    /// accounts tagged with it or in its namespace take part. Accounts that
+    /// are rate limited or whose token has expired are passed over.
+    pub fn rotate(&self, group: Option<&str>) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let config = self.load_config()?;
+        let order = match self.settings.rotation.as_slice() {
+            [] => {
+                let mut names: Vec<&str> = config.accounts.keys().map(String::as_str).collect();
+                names.sort();
+                names
+            }
+            rotation => rotation.iter().map(String::as_str).collect(),
+        };
+        let mut accounts = Vec::new();
+        for name in order {
+            match config.accounts.get_key_value(name) {
+                Some((name, meta)) => accounts.push((name.as_str(), meta)),
+                None => self.say(tr!("rotate-unknown", name = name)),
+            }
+        }
+        accounts.retain(|(name, meta)| group.is_none_or(|group| in_group(group, name, meta)));
+        if accounts.is_empty() {
+            anyhow::bail!(tr!("rotate-empty"));
+        }
+
+        let current = config.current.as_deref();
+        let start = current
+            .and_then(|current| accounts.iter().position(|(name, _)| *name == current))
+            .map_or(0, |i| i + 1);
+        let now = self.clock.now();
+        for i in 0..accounts.len() {
+            let (name, meta) = accounts[(start + i) % accounts.len()];
+            if Some(name) == current {
+                continue;
+            }
+            if let Some(until) = quota::active(meta.limited_until.as_deref(), now) {
+                let time = until.format("%Y-%m-%d %H:%M UTC");
+                self.say(tr!("rotate-skip-limited", name = name, time = time));
+                continue;
+            }
+            let expires_at = self
+                .account_login(name, &meta.path)
+                .identity
+                .and_then(|identity| identity.expires_at);
+            if let Some(expires_at) = expires_at.filter(|expires_at| *expires_at < now) {
+                let time = expires_at.format("%Y-%m-%d %H:%M UTC");
+                self.say(tr!("rotate-skip-expired", name = name, time = time));
+                continue;
+            }
+
+            let switched = self.switch_from(name, None, EventSource::Rotate)?;
+            if self.format.is_json() {
+                output::print_json(&ActionJson {
+                    action: "rotate",
+                    account: name,
+                    cancelled: switched.is_none(),
+                    copied: switched.flatten().map(Into::into),
+                })?;
+            }
+            return Ok(());
+        }
+        anyhow::bail!(tr!("rotate-none"))
+    }
+
+    /// Ranks the accounts (those in `group`, when given) by how good a pick
+    /// each is now, see [`suggest`], and with `switch` makes the best live.
+    pub fn suggest(&self, group: Option<&str>, online: bool, switch: bool) -> Result<()> {
+        let _lock = switch.then(|| self.lock_state()).transpose()?;
+        let pinger = online.then(health::ApiPinger::from_env).transpose()?;
+        let ranked = self.ranked_accounts(
+            group,
+            pinger.as_ref().map(|pinger| pinger as &dyn health::Pinger),
+        )?;
+        if ranked.is_empty() {
+            anyhow::bail!(tr!("rotate-empty"));
+        }
+
+        let suggestions: Vec<SuggestionJson> = ranked
+            .iter()
+            .map(|candidate| SuggestionJson {
+                name: &candidate.name,
+                available: candidate.available(),
+                plan: candidate.plan.as_deref(),
+                health: candidate.health,
+                limited_until: candidate.limited_until,
+                last_used: candidate.last_used,
+            })
+            .collect();
+        match self.format.is_json() {
+            true => output::print_json(&suggestions)?,
+            false => {
+                for line in self.format.suggestions(&suggestions, self.clock.now()) {
+                    println!("{}", line);
+                }
+            }
+        }
+
+        let Some(best) = ranked.first().filter(|best| best.available()) else {
+            anyhow::bail!(tr!("suggest-none"));
+        };
+        if !switch {
+            self.say(tr!("suggest-best", name = best.name));
+            return Ok(());
+        }
+        match self.load_config()?.current.as_deref() == Some(best.name.as_str()) {
+            true => self.say(tr!("suggest-already-current", name = best.name)),
+            false => {
+                self.switch_from(&best.name, None, EventSource::Suggest)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The accounts in `group` (all without one), best pick first.
+    pub fn ranked_accounts(
+        &self,
+        group: Option<&str>,
+        pinger: Option<&dyn health::Pinger>,
+    ) -> Result<Vec<Candidate>> {
+        let config = self.load_config()?;
+        let now = self.clock.now();
+        let mut candidates = Vec::new();
+        for check in self.login_checks(&[], true, pinger)? {
+            let Some(meta) = config.get_account(&check.name) else {
+                continue;
+            };
+            if group.is_some_and(|group| !in_group(group, &check.name, meta)) {
+                continue;
+            }
+            let plan = meta
+                .identity
+                .as_ref()
+                .and_then(|identity| identity.plan.clone())
+                .or_else(|| {
+                    let login = self.account_login(&check.name, &meta.path);
+                    login.identity.and_then(|identity| identity.plan)
+                });
+            candidates.push(Candidate {
+                plan,
+                health: check.health,
+                limited_until: quota::active(meta.limited_until.as_deref(), now),
+                // The current account is in use as we speak
+                last_used: match config.current.as_deref() == Some(check.name.as_str()) {
+                    true => Some(now),
+                    false => meta
+                        .last_used
+                        .as_deref()
+                        .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+                        .map(|time| time.with_timezone(&Utc)),
+                },
+                name: check.name,
+            });
+        }
+        suggest::rank(&mut candidates);
+        Ok(candidates)
+    }
+
+    /// Lists accounts from the config alone, sorted by name, without
+    /// touching any snapshot; the login and size are left for the caller to
+    /// fill in.
+    pub fn account_summaries(&self) -> Result<Vec<AccountSummary>> {
+        let config = self.load_config()?;
+        // Unlock now, so the passphrase prompt comes before a dashboard
+        // takes over the terminal rather than from a background read
+        if self.encryption_enabled() {
+            self.vault()?;
+        }
+
+        let mut summaries: Vec<AccountSummary> = config
+            .accounts
+            .iter()
+            .map(|(name, meta)| AccountSummary {
+                name: name.clone(),
+                current: config.current.as_deref() == Some(name),
+                protected: meta.protected,
+                external: meta.external,
+                saved_at: meta.saved_at.clone(),
+                last_used: meta.last_used.clone(),
+                path: meta.path.clone(),
+                login: None,
+                usage: None,
+            })
+            .collect();
+        summaries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(summaries)
+    }
+
+    /// Reads who an account is logged in as. Unreadable files leave the
+    /// details empty; the dashboard shows what it can.
+    pub fn account_login(&self, name: &str, account_dir: &Path) -> AccountLogin {
+        // Inside the snapshot when it was saved with `CLAUDE_CONFIG_DIR`
+        let claude_json = [
+            account_dir.join(template::CLAUDE_JSON),
+            account_dir.join(extra_paths::in_home(template::CLAUDE_JSON)),
+        ]
+        .iter()
+        .find_map(|path| self.read_stored(path).ok().flatten());
+        AccountLogin {
+            identity: self
+                .saved_credentials(name, account_dir)
+                .ok()
+                .flatten()
+                .and_then(|credentials| claude::read_identity(&credentials)),
+            email: claude_json.as_deref().and_then(claude::read_email),
+            organization: claude_json.as_deref().and_then(claude::read_organization),
+        }
+    }
+
+    /// Binds `dir` (and everything below it) to an account.
+    pub fn bind(&self, dir: &Path, name: &str) -> Result<()> {
+        let config = self.load_config()?;
+        if config.get_account(name).is_none() {
+            return Err(AccountError::NotFound(name.to_string()).into());
+        }
+
+        let path = binding::write(dir, name)?;
+        self.say(tr!("binding-written", name = name, path = path.display()));
+        Ok(())
+    }
+
+    /// Makes sure the account bound to `dir` is active, switching to it or,
+    /// with `warn_only`, just saying so. Used by the `claude` shell wrapper
+    /// and by `auto` from the shell's directory hook, which runs it on every
+    /// `cd`, so it returns early when there's nothing to do.
+    pub fn ensure_bound(&self, dir: &Path, warn_only: bool, source: EventSource) -> Result<()> {
+        let config = self.load_config()?;
+        let Some(binding) = binding::nearest(dir, &config.projects)? else {
+            return Ok(());
+        };
+
+        if config.current.as_deref() == Some(binding.account.as_str()) {
+            return Ok(());
+        }
+        if config.get_account(&binding.account).is_none() {
+            anyhow::bail!(tr!(
+                "binding-unknown-account",
+                name = binding.account,
+                path = binding.source.display()
+            ));
+        }
+
+        let current = config.current.as_deref().unwrap_or("-");
+        if warn_only {
+            output::warn(tr!(
+                "binding-mismatch",
+                name = binding.account,
+                current = current
+            ));
+            return Ok(());
+        }
+
+        eprintln!(
+            "{}",
+            tr!(
+                "binding-switching",
+                name = binding.account,
+                path = binding.source.display()
+            )
+        );
+        let _lock = self.lock_state()?;
+        self.switch_from(&binding.account, None, source)?;
+        Ok(())
+    }
+
+    /// Switches to the account bound to `dir`, by a `.claude-account` file or
+    /// a `project set` mapping, whichever is nearer.
+    pub fn switch_here(&self, dir: &Path, mode: Option<SwitchMode>) -> Result<()> {
+        let config = self.load_config()?;
+        let Some(binding) = binding::nearest(dir, &config.projects)? else {
+            anyhow::bail!(tr!("project-none", path = dir.display()));
+        };
+        if config.get_account(&binding.account).is_none() {
+            anyhow::bail!(tr!(
+                "binding-unknown-account",
+                name = binding.account,
+                path = binding.source.display()
+            ));
+        }
+        self.switch_account(&binding.account, mode)
+    }
+
+    /// Maps `dir` (and everything below it) to the account `name` in the
+    /// accounts file, leaving the directory itself untouched.
+    pub fn set_project(&self, dir: &Path, name: &str) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let mut config = self.load_config()?;
+        if config.get_account(name).is_none() {
+            return Err(AccountError::NotFound(name.to_string()).into());
+        }
+        let dir = fs::canonicalize(dir)
+            .with_context(|| tr!("failed-resolve-directory", path = dir.display()))?;
+
+        config.projects.insert(dir.clone(), name.to_string());
+        self.save_config(&mut config)?;
+        self.say(tr!("project-set", name = name, path = dir.display()));
+        Ok(())
+    }
+
+    pub fn unset_project(&self, dir: &Path) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let mut config = self.load_config()?;
+        let dir = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+        let Some(name) = config.projects.remove(&dir) else {
+            anyhow::bail!(tr!("project-unknown", path = dir.display()));
+        };
+        self.save_config(&mut config)?;
+        self.say(tr!("project-unset", name = name, path = dir.display()));
+        Ok(())
+    }
+
+    pub fn list_projects(&self) -> Result<()> {
+        let config = self.load_config()?;
+        if self.format.is_json() {
+            let projects: Vec<ProjectJson> = config
+                .projects
+                .iter()
+                .map(|(path, account)| ProjectJson { path, account })
+                .collect();
+            return output::print_json(&projects);
+        }
+        if config.projects.is_empty() {
+            println!("{}", tr!("projects-none"));
+            return Ok(());
+        }
+        for (path, account) in &config.projects {
+            println!("{}", self.format.project(path, account));
+        }
+        Ok(())
+    }
+
+    /// Prints logged events, oldest first, optionally narrowed down to one
+    /// source or account and to the most recent `limit`.
+    pub fn show_history(
+        &self,
+        source: Option<EventSource>,
+        account: Option<&str>,
+        limit: Option<usize>,
+    ) -> Result<()> {
+        let events: Vec<Event> = history::load(&self.history_file())?
+            .into_iter()
+            .filter(|event| source.is_none_or(|source| event.source == source))
+            .filter(|event| account.is_none_or(|account| event.account == account))
+            .collect();
+        let skip = limit.map_or(0, |limit| events.len().saturating_sub(limit));
+
+        if self.format.is_json() {
+            return output::print_json(&&events[skip..]);
+        }
+        if events.is_empty() {
+            println!("{}", tr!("history-empty"));
+            return Ok(());
+        }
+
+        for event in &events[skip..] {
+            println!("{}", self.format.history(event));
+        }
+        Ok(())
+    }
+
+    /// Lets the user pick the account to switch to, for `switch` without a name.
+    pub fn switch_interactive(&self, mode: Option<SwitchMode>) -> Result<()> {
+        let config = self.load_config()?;
+        if config.is_empty() {
+            self.say(tr!("no-accounts"));
+            return Ok(());
+        }
+
+        let mut entries: Vec<Entry> = config
+            .accounts
+            .iter()
+            .map(|(name, meta)| Entry {
+                name: name.clone(),
+                saved_at: meta.saved_at.clone(),
+                current: config.current.as_deref() == Some(name),
+            })
+            .collect();
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+        match self.prompter.pick(&entries)? {
+            Some(index) => self.switch_account(&entries[index].name, mode),
+            None => {
+                self.say(tr!("cancelled"));
+                Ok(())
+            }
+        }
+    }
+
+    /// Shows the accounts in a launcher such as rofi (see [`menu`]) and
+    /// switches to the one picked. The current one is marked, and each shows
+    /// the email it's logged in with when that's known.
+    pub fn menu(&self, via: Option<Launcher>, command: Option<&str>) -> Result<()> {
+        let config = self.load_config()?;
+        if config.is_empty() {
+            anyhow::bail!(tr!("no-accounts"));
+        }
+        let menu = Menu::resolve(via, command)?;
+
+        let mut names: Vec<&String> = config.accounts.keys().collect();
+        names.sort();
+        let lines: Vec<String> = names
+            .iter()
+            .map(|name| {
+                let meta = &config.accounts[name.as_str()];
+                let mut line = name.to_string();
+                if let Some(email) = meta.identity.as_ref().and_then(|id| id.email.as_deref()) {
+                    line.push_str(&format!("  {}", email));
+                }
+                if config.current.as_deref() == Some(name.as_str()) {
+                    line.push_str(&format!("  ({})", tr!("menu-current")));
+                }
+                line
+            })
+            .collect();
+
+        let Some(index) = menu.pick(&tr!("menu-prompt"), &lines)? else {
+            self.say(tr!("cancelled"));
+            return Ok(());
+        };
+        let _lock = self.lock_state()?;
+        self.switch_from(names[index], None, EventSource::Menu)?;
+        Ok(())
+    }
+
+    /// The rows of a table of `columns`, with what only some columns need
+    /// looked up for those.
+    fn table_rows<'a>(
+        &self,
+        config: &'a AccountsConfig,
+        accounts: Vec<(&'a String, &'a AccountMetadata)>,
+        columns: &[Column],
+        usages: &BTreeMap<&str, DirUsage>,
+        attention: &BTreeMap<&str, Attention>,
+    ) -> Result<Vec<AccountRow<'a>>> {
+        let unsaved = match (&config.current, columns.contains(&Column::Unsaved)) {
+            (Some(current), true) if self.claude_config_dir.exists() => {
+                Some(self.unsaved_changes(config, current)?.len())
+            }
+            _ => None,
+        };
+        let numbers = numbers(config);
+        let now = self.clock.now();
+        Ok(accounts
+            .into_iter()
+            .map(|(name, meta)| {
+                let number = numbers[name.as_str()];
+                let usage = usages.get(name.as_str());
+                let row = account_row(name, meta, config, number, usage, true, now);
+                let row = attention[name.as_str()].applied_to(row);
+                AccountRow {
+                    unsaved: unsaved.filter(|_| row.current),
+                    ..row
+                }
+            })
+            .collect())
+    }
+
+    /// What each of `accounts` takes up: from the list cache where its
+    /// snapshot is unchanged, and measured (then cached) where it isn't.
+    fn cached_usages<'a>(
+        &self,
+        config: &AccountsConfig,
+        accounts: &[(&'a String, &'a AccountMetadata)],
+    ) -> Result<BTreeMap<&'a str, DirUsage>> {
+        let now = self.clock.now();
+        let mut cache = ListCache::load(&self.state_dir, now);
+        let mut usages = BTreeMap::new();
+        let (mut missing, mut stamps) = (Vec::new(), Vec::new());
+        // An archived account has no snapshot to measure
+        for (name, meta) in accounts.iter().filter(|(_, meta)| meta.archive.is_none()) {
+            let stamp = Stamp::of(&meta.path, &meta.saved_at);
+            match cache.get(name, &stamp).and_then(|entry| entry.usage) {
+                Some(usage) => {
+                    usages.insert(name.as_str(), usage);
+                }
+                None => {
+                    missing.push((*name, *meta));
+                    stamps.push(stamp);
+                }
+            }
+        }
+        if missing.is_empty() {
+            return Ok(usages);
+        }
+
+        let measured = self.measure_accounts(config, &missing, false, hashing::default_jobs())?;
+        for (((name, _), stamp), (usage, _)) in missing.into_iter().zip(stamps).zip(measured) {
+            cache.entry(name, stamp, now).usage = Some(usage);
+            usages.insert(name.as_str(), usage);
+        }
+        // An unwritable cache only means measuring again next time
+        let _ = cache.save(&self.state_dir);
+        Ok(usages)
+    }
+
+    /// What about each of `accounts` `list` points out: a snapshot saved
+    /// longer ago than `stale_days`, or `remind-after` without it, and a
+    /// login about to expire.
+    fn attention<'a>(
+        &self,
+        accounts: &[(&'a String, &'a AccountMetadata)],
+        stale_days: Option<u32>,
+    ) -> Result<BTreeMap<&'a str, Attention>> {
+        let stale_after = match stale_days {
+            Some(days) => Some(chrono::Duration::days(days.into())),
+            None => self.settings.remind_after()?,
+        };
+        let now = self.clock.now();
+        let expiries = self.cached_expiries(accounts);
+        Ok(accounts
+            .iter()
+            .map(|(name, meta)| {
+                let expires_at = expiries[name.as_str()];
+                let age = DateTime::parse_from_rfc3339(&meta.saved_at)
+                    .ok()
+                    .map(|saved_at| now - saved_at.with_timezone(&Utc));
+                let stale_days = match (age, stale_after) {
+                    (Some(age), Some(after)) if age > after => Some(age.num_days()),
+                    _ => None,
+                };
+                let attention = Attention {
+                    expires_at,
+                    stale_days,
+                    expiry: health::Expiry::of(expires_at, now),
+                };
+                (name.as_str(), attention)
+            })
+            .collect())
+    }
+
+    /// When the login of each of `accounts` expires, read from its
+    /// credentials where the list cache doesn't have it.
+    fn cached_expiries<'a>(
+        &self,
+        accounts: &[(&'a String, &'a AccountMetadata)],
+    ) -> BTreeMap<&'a str, Option<DateTime<Utc>>> {
+        let now = self.clock.now();
+        let mut cache = ListCache::load(&self.state_dir, now);
+        let mut changed = false;
+        let expiries = accounts
+            .iter()
+            .map(|(name, meta)| {
+                let stamp = Stamp::of(&meta.path, &meta.saved_at);
+                let entry = cache.entry(name, stamp, now);
+                let expires_at = *entry.expires_at.get_or_insert_with(|| {
+                    changed = true;
+                    self.account_login(name, &meta.path)
+                        .identity
+                        .and_then(|identity| identity.expires_at)
+                });
+                (name.as_str(), expires_at)
+            })
+            .collect();
+        if changed {
+            let _ = cache.save(&self.state_dir);
+        }
+        expiries
+    }
+
+    /// Waits for `delay`, then re-saves `name` unless another account became
+    /// active in the meantime.
+    pub fn resave_if_current(&self, name: &str, delay: Duration) -> Result<()> {
+        thread::sleep(delay);
+
+        let _lock = self.lock_state()?;
+        let config = self.load_config()?;
+        if config.current.as_deref() != Some(name) {
+            return Ok(());
+        }
+        self.save_from(name, EventSource::Resave)?;
+        Ok(())
+    }
+
+    /// Lists the accounts `filter` lets through in `sort` order. With `long`
+    /// (or sorted by size) each one shows what its snapshot takes up,
+    /// measured in parallel. Snapshots older than `remind-after` (or
+    /// `--stale-days`) and logins about to expire are pointed out.
+    pub fn list_accounts(
+        &self,
+        group_by: Option<GroupBy>,
+        filter: ListFilter,
+        sort: ListSort,
+        long: bool,
+        columns: &[Column],
+        document: Option<Document>,
+    ) -> Result<()> {
+        // Grouped, `--long` adds to each account's line instead
+        let columns = match columns.is_empty() {
+            true if document.is_some() => Column::DOCUMENT,
+            true if long && group_by.is_none() => Column::LONG,
+            _ => columns,
+        };
+        let config = self.load_config()?;
+        self.warn_unhealthy(&config);
+        let mut accounts: Vec<_> = config
+            .accounts
+            .iter()
+            .filter(|(_, meta)| filter.tags.iter().all(|tag| meta.tags.contains(tag)))
+            .collect();
+        accounts.sort_by_key(|(name, _)| *name);
+        let attention = self.attention(&accounts, filter.stale_days)?;
+        if filter.stale_days.is_some() {
+            let now = self.clock.now();
+            accounts.retain(|(name, meta)| {
+                let row = account_row(name, meta, &config, 0, None, false, now);
+                attention[name.as_str()].applied_to(row).needs_attention()
+            });
+        }
+        // Groups show what their members take up together
+        let measure = long
+            || group_by.is_some()
+            || sort == ListSort::Size
+            || columns.iter().any(|c| c.needs_usage());
+        let usages: BTreeMap<&str, DirUsage> = match measure {
+            true => self.cached_usages(&config, &accounts)?,
+            false => BTreeMap::new(),
+        };
+        sort_accounts(&mut accounts, sort, &usages);
+
+        let names: Vec<&str> = accounts.iter().map(|(name, _)| name.as_str()).collect();
+        if self.format.is_json() {
+            self.list_accounts_json(&config, &accounts, group_by, &usages, &attention)?;
+            return self.remind_stale(&config, names);
+        }
+        if let Some(document) = document {
+            let rows = self.table_rows(&config, accounts, columns, &usages, &attention)?;
+            for line in document.render(columns, &rows) {
+                println!("{}", line);
+            }
+            return self.remind_stale(&config, names);
+        }
+        if config.is_empty() {
+            println!("{}", tr!("no-accounts"));
+            if self.legacy_dir.join(LEGACY_ACCOUNTS_FILE).is_file() {
+                println!("{}", tr!("legacy-hint", path = self.legacy_dir.display()));
+            }
+            return Ok(());
+        }
+        if accounts.is_empty() {
+            match filter.stale_days {
+                Some(days) => println!("{}", tr!("no-stale-accounts", days = days)),
+                None => println!(
+                    "{}",
+                    tr!("no-tagged-accounts", tags = filter.tags.join(", "))
+                ),
+            }
+            return Ok(());
+        }
+
+        for line in self.format.header() {
+            println!("{}", line);
+        }
+
+        let numbers = numbers(&config);
+        let now = self.clock.now();
+        match group_by {
+            Some(group_by) => {
+                // An account with several tags is under each of them
+                let mut groups: BTreeMap<String, Vec<_>> = BTreeMap::new();
+                for (name, meta) in accounts {
+                    for group in group_keys(group_by, name, meta) {
+                        groups.entry(group).or_default().push((name, meta));
+                    }
+                }
+
+                for (group, members) in groups {
+                    let mut usage = DirUsage::default();
+                    for (name, _) in &members {
+                        usage += usages.get(name.as_str()).copied().unwrap_or_default();
+                    }
+
+                    let label = match group_by {
+                        _ if !group.is_empty() => group,
+                        GroupBy::Namespace => tr!("no-namespace"),
+                        GroupBy::Tag => tr!("no-tag"),
+                        GroupBy::Identity => tr!("no-identity"),
+                    };
+                    println!(
+                        "{}",
+                        self.format
+                            .group(&label, members.len(), &size::format_bytes(usage.bytes))
+                    );
+
+                    let last = members.len() - 1;
+                    for (i, (name, meta)) in members.into_iter().enumerate() {
+                        let number = numbers[name.as_str()];
+                        let usage = usages.get(name.as_str());
+                        let row = account_row(name, meta, &config, number, usage, long, now);
+                        let row = attention[name.as_str()].applied_to(row);
+                        println!("{}", self.format.group_member(&row, i == last));
+                    }
+                }
+            }
+            None if !columns.is_empty() => {
+                let rows = self.table_rows(&config, accounts, columns, &usages, &attention)?;
+                for line in self.format.account_table(columns, &rows) {
+                    println!("{}", line);
+                }
+            }
+            None => {
+                for (name, meta) in accounts {
+                    let number = numbers[name.as_str()];
+                    let usage = usages.get(name.as_str());
+                    let row = account_row(name, meta, &config, number, usage, long, now);
+                    let row = attention[name.as_str()].applied_to(row);
+                    println!("{}", self.format.account(&row));
+                }
+            }
+        }
+        println!();
+
+        self.remind_stale(&config, names)
+    }
+
+    fn list_accounts_json(
+        &self,
+        config: &AccountsConfig,
+        accounts: &[(&String, &AccountMetadata)],
+        group_by: Option<GroupBy>,
+        usages: &BTreeMap<&str, DirUsage>,
+        attention: &BTreeMap<&str, Attention>,
+    ) -> Result<()> {
+        let mut accounts: Vec<_> = accounts
+            .iter()
+            .flat_map(|(name, meta)| {
+                let groups = match group_by {
+                    Some(group_by) => group_keys(group_by, name, meta)
+                        .into_iter()
+                        .map(|group| Some(group).filter(|group| !group.is_empty()))
+                        .collect(),
+                    None => vec![None],
+                };
+                groups.into_iter().map(move |group| (*name, *meta, group))
+            })
+            .collect();
+        // Grouped, in the order asked for within each group
+        accounts.sort_by(|a, b| a.2.cmp(&b.2));
+
+        let numbers = numbers(config);
+        let now = self.clock.now();
+        let accounts: Vec<AccountJson> = accounts
+            .iter()
+            .map(|(name, meta, group)| AccountJson {
+                number: numbers[name.as_str()],
+                name,
+                aliases: config.aliases_of(name),
+                current: config.current.as_deref() == Some(name.as_str()),
+                saved_at: &meta.saved_at,
+                last_used: meta.last_used.as_deref(),
+                switch_count: meta.switch_count,
+                protected: meta.protected,
+                locked: meta.locked,
+                external: meta.external,
+                group: group.as_deref(),
+                identity: meta.identity.as_ref(),
+                tags: meta.tags.iter().map(String::as_str).collect(),
+                limited_until: quota::active(meta.limited_until.as_deref(), now)
+                    .map(|until| until.to_rfc3339()),
+                archived: meta.archive.is_some(),
+                files: usages.get(name.as_str()).map(|usage| usage.files),
+                bytes: usages.get(name.as_str()).map(|usage| usage.bytes),
+                expires_at: attention[name.as_str()]
+                    .expires_at
+                    .map(|at| at.to_rfc3339()),
+                stale_days: attention[name.as_str()].stale_days,
+                expiry: attention[name.as_str()].expiry,
+            })
+            .collect();
+        output::print_json(&accounts)
+    }
+
+    /// Shows the note of `name`, or replaces it: with `text`, with what's
+    /// written in the editor for `edit`, or with none for `clear`.
+    pub fn note_account(
+        &self,
+        name: &str,
+        text: Option<&str>,
+        edit: bool,
+        clear: bool,
+    ) -> Result<()> {
+        let config = self.load_config()?;
+        let meta = config
+            .get_account(name)
+            .ok_or_else(|| AccountError::NotFound(name.to_string()))?;
+
+        let note = if clear {
+            None
+        } else if edit {
+            Some(self.edit_note(meta.note.as_deref().unwrap_or_default())?)
+        } else if let Some(text) = text {
+            Some(text.to_string())
+        } else {
+            if self.format.is_json() {
+                return output::print_json(&NoteJson {
+                    name,
+                    note: meta.note.as_deref(),
+                });
+            }
+            match &meta.note {
+                Some(note) => println!("{}", note),
+                None => self.say(tr!("note-none", name = name)),
+            }
+            return Ok(());
+        };
+        let note = note
+            .map(|note| note.trim().to_string())
+            .filter(|note| !note.is_empty());
+
+        // The editor may have been open a while; the lock is only needed now
+        let _lock = self.lock_state()?;
+        let mut config = self.load_config()?;
+        let meta = config
+            .accounts
+            .get_mut(name)
+            .ok_or_else(|| AccountError::NotFound(name.to_string()))?;
+        let message = match &note {
+            Some(_) => tr!("note-saved", name = name),
+            None => tr!("note-cleared", name = name),
+        };
+        meta.note = note;
+        self.save_config(&mut config)?;
+        self.say(message);
+        Ok(())
+    }
+
+    /// Opens `current` in the editor and returns what was saved.
+    fn edit_note(&self, current: &str) -> Result<String> {
+        let path = self.state_dir.join(NOTE_EDIT_FILE);
+        fs::write(&path, format!("{}\n", current)).with_context(|| tr!("failed-write-note"))?;
+        let editor = Editor::from_env();
+        let edited = editor.edit(&path);
+        let note = fs::read_to_string(&path).with_context(|| tr!("failed-read-note"));
+        let _ = fs::remove_file(&path);
+        if !edited? {
+            anyhow::bail!(tr!("note-editor-failed", editor = editor.program()));
+        }
+        note
+    }
+
+    /// Adds `tags` to the account `name`, or removes them with `add` false.
+    pub fn tag_account(&self, name: &str, tags: &[String], add: bool) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let mut config = self.load_config()?;
+        let meta = config
+            .accounts
+            .get_mut(name)
+            .ok_or_else(|| AccountError::NotFound(name.to_string()))?;
+
+        for tag in tags {
+            if add {
+                if tag.is_empty() || tag.contains(|c: char| c.is_whitespace() || c == ',') {
+                    anyhow::bail!(tr!("tag-invalid", tag = tag));
+                }
+                if !meta.tags.insert(tag.clone()) {
+                    self.say(tr!("tag-already", name = name, tag = tag));
+                    continue;
+                }
+                self.say(tr!("tag-added", name = name, tag = tag));
+            } else if meta.tags.remove(tag) {
+                self.say(tr!("tag-removed", name = name, tag = tag));
+            } else {
+                self.say(tr!("tag-not-set", name = name, tag = tag));
+            }
+        }
+        self.save_config(&mut config)
+    }
+
+    /// Gives `name` the variables in `vars` (`NAME=VALUE`), for the shell
+    /// hook to export while it's active.
+    pub fn set_vars(&self, name: &str, vars: &[String]) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let mut config = self.load_config()?;
+        let meta = config
+            .accounts
+            .get_mut(name)
+            .ok_or_else(|| AccountError::NotFound(name.to_string()))?;
+
+        let mut parsed = Vec::new();
+        for var in vars {
+            match var.split_once('=') {
+                Some((var, value)) if envfile::is_var_name(var) => parsed.push((var, value)),
+                _ => anyhow::bail!(tr!("var-invalid", var = var)),
+            }
+        }
+        for (var, value) in parsed {
+            meta.env.insert(var.to_string(), value.to_string());
+            self.say(tr!("var-set", name = name, var = var));
+        }
+        self.save_config(&mut config)
+    }
+
+    pub fn unset_vars(&self, name: &str, vars: &[String]) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let mut config = self.load_config()?;
+        let meta = config
+            .accounts
+            .get_mut(name)
+            .ok_or_else(|| AccountError::NotFound(name.to_string()))?;
+        for var in vars {
+            match meta.env.remove(var) {
+                Some(_) => self.say(tr!("var-unset", name = name, var = var)),
+                None => self.say(tr!("var-not-set", name = name, var = var)),
+            }
+        }
+        self.save_config(&mut config)
+    }
+
+    /// Prints the variables of `name`, `NAME=VALUE` a line.
+    pub fn list_vars(&self, name: &str) -> Result<()> {
+        let name = &self.resolve_target(name)?;
+        let config = self.load_config()?;
+        let meta = config
+            .get_account(name)
+            .ok_or_else(|| AccountError::NotFound(name.to_string()))?;
+        if self.format.is_json() {
+            return output::print_json(&meta.env);
+        }
+        if meta.env.is_empty() {
+            println!("{}", tr!("vars-none", name = name));
+        }
+        for (var, value) in &meta.env {
+            println!("{}={}", var, value);
+        }
+        Ok(())
+    }
+
+    /// Makes `alias` stand for the account `name`, in place of whatever it
+    /// stood for before.
+    pub fn set_alias(&self, alias: &str, name: &str) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let mut config = self.load_config()?;
+        if !config.accounts.contains_key(name) {
+            return Err(AccountError::NotFound(name.to_string()).into());
+        }
+        names::validate(alias)?;
+        // Either would be shadowed when resolving
+        if alias.bytes().all(|b| b.is_ascii_digit()) {
+            anyhow::bail!(tr!("alias-number", alias = alias));
+        }
+        if config.accounts.contains_key(alias) {
+            anyhow::bail!(tr!("alias-is-account", alias = alias));
+        }
+
+        config.aliases.insert(alias.to_string(), name.to_string());
+        self.save_config(&mut config)?;
+        self.say(tr!("alias-set", alias = alias, name = name));
+        Ok(())
+    }
+
+    pub fn remove_alias(&self, alias: &str) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let mut config = self.load_config()?;
+        let Some(name) = config.aliases.remove(alias) else {
+            anyhow::bail!(tr!("alias-unknown", alias = alias));
+        };
+        self.save_config(&mut config)?;
+        self.say(tr!("alias-removed", alias = alias, name = name));
+        Ok(())
+    }
+
+    /// Measures how fast this machine puts `files` files of `file_kib` KiB
+    /// in place each way a switch can: from the store to beside the Claude
+    /// directory, as the filesystems of the two are what decides it.
+    pub fn bench(&self, files: usize, file_kib: u64) -> Result<()> {
+        let scratch = format!(".bench-{}", std::process::id());
+        let store = self.switcher_dir.join(&scratch);
+        let beside = self
+            .claude_config_dir
+            .parent()
+            .unwrap_or(&self.switcher_dir);
+        let target = beside.join(format!(".claude{}", scratch));
+        let file_size = file_kib * 1024;
+        let bytes = file_size * files as u64;
+        self.say(tr!(
+            "bench-header",
+            files = files,
+            size = size::format_bytes(bytes),
+            from = self.switcher_dir.display(),
+            to = self.claude_config_dir.display()
+        ));
+
+        let results: Vec<BenchJson> = bench::run(&store, &target, files, file_size)?
+            .iter()
+            .map(|measurement| BenchJson::new(measurement, files, bytes))
+            .collect();
+        if self.format.is_json() {
+            return output::print_json(&results);
+        }
+        for line in self.format.bench(&results) {
+            println!("{}", line);
+        }
+        Ok(())
+    }
+
+    /// Reports the space each account's snapshot and versions take, largest
+    /// first, then the whole store with files shared by `dedup` counted once.
+    pub fn show_sizes(&self, name: Option<&str>, jobs: usize) -> Result<()> {
+        let config = self.load_config()?;
+        let accounts: Vec<_> = match name {
+            Some(name) => vec![
+                config
+                    .accounts
+                    .get_key_value(name)
+                    .ok_or_else(|| AccountError::NotFound(name.to_string()))?,
+            ],
+            None => config.accounts.iter().collect(),
+        };
+        let usages = self.measure_accounts(&config, &accounts, true, jobs)?;
+
+        let mut rows: Vec<SizeRow> = accounts
+            .iter()
+            .zip(usages)
+            .map(|((name, meta), (usage, versions))| SizeRow {
+                name: name.to_string(),
+                external: meta.external,
+                files: usage.files,
+                bytes: usage.bytes,
+                version_files: versions.files,
+                version_bytes: versions.bytes,
+            })
+            .collect();
+        rows.sort_by(|a, b| {
+            b.total_bytes()
+                .cmp(&a.total_bytes())
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        let store = match name {
+            Some(_) => None,
+            None => Some(size::disk_usage(&self.switcher_dir)?),
+        };
+
+        if self.format.is_json() {
+            return output::print_json(&SizeJson {
+                accounts: rows,
+                store: store.map(|store| StoreUsageJson {
+                    files: store.files,
+                    bytes: store.bytes,
+                }),
+            });
+        }
+        if rows.is_empty() {
+            println!("{}", tr!("no-accounts"));
+            return Ok(());
+        }
+        for row in &rows {
+            println!("{}", self.format.size(row));
+        }
+        if let Some(store) = store {
+            println!();
+            println!(
+                "{}",
+                tr!(
+                    "size-store",
+                    files = store.files,
+                    size = size::format_bytes(store.bytes)
+                )
+            );
+        }
+        Ok(())
+    }
+
+    /// Measures the snapshots of `accounts`, and with `versions` their
+    /// versions too, on up to `jobs` threads, in the order given. A snapshot
+    /// doesn't count the snapshots of nested account names inside it.
+    fn measure_accounts(
+        &self,
+        config: &AccountsConfig,
+        accounts: &[(&String, &AccountMetadata)],
+        versions: bool,
+        jobs: usize,
+    ) -> Result<Vec<(DirUsage, DirUsage)>> {
+        let mut dirs = Vec::new();
+        for (index, (name, meta)) in accounts.iter().enumerate() {
+            let nested = nested_snapshots(config, &meta.path);
+            dirs.push((index, false, meta.path.clone(), nested));
+            if versions {
+                let path = versions::account_dir(&self.switcher_dir, name);
+                dirs.push((index, true, path, Vec::new()));
+            }
+        }
+
+        let mut usages = vec![(DirUsage::default(), DirUsage::default()); accounts.len()];
+        let mut progress = Progress::new(tr!("size-measuring"), dirs.len(), self.format.is_json());
+        hashing::for_each_parallel(
+            dirs.into_iter(),
+            jobs,
+            |(index, version, path, nested)| {
+                (index, version, size::dir_usage_skipping(&path, &nested))
+            },
+            |(index, version, usage)| {
+                let usage = usage?;
+                if version {
+                    usages[index].1 = usage;
+                } else {
+                    usages[index].0 = usage;
+                }
+                progress.advance();
+                Ok(())
+            },
+        )?;
+        Ok(usages)
+    }
+
+    /// Removes the accounts `args` name (see `expand_names`), asking first
+    /// when it's more than one or a glob picked them.
+    pub fn delete_accounts(&self, args: &[String], keep_data: bool) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let names = self.expand_names(args)?;
+        // A glob can match more than was meant, even when it's only one
+        if names.len() > 1 || args.iter().any(|arg| names::is_pattern(arg)) {
+            self.say(tr!(
+                "delete-many",
+                count = names.len(),
+                names = names.join(", ")
+            ));
+            if !self.confirm(&tr!("delete-many-confirm"), false)? {
+                self.say(tr!("cancelled"));
+                return Ok(());
+            }
+        }
+        self.for_each_account(&names, |name| self.delete_account(name, keep_data))
+    }
+
+    /// Removes an account. With `keep_data` only the metadata goes: the
+    /// snapshot directory stays in place for other tooling or later cleanup.
+    pub fn delete_account(&self, name: &str, keep_data: bool) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let mut config = self.load_config()?;
+
+        let account_meta = config
+            .get_account(name)
+            .ok_or_else(|| AccountError::NotFound(name.to_string()))?
+            .clone();
+        if account_meta.locked && !self.force {
+            return Err(AccountError::Locked(name.to_string()).into());
+        }
+        // What's in the trash is a snapshot `undo` can put back as it is
+        let account_meta = match account_meta.archive {
+            Some(_) if !keep_data => {
+                self.unarchive(&mut config, name)?;
+                config.accounts[name].clone()
+            }
+            _ => account_meta,
+        };
+
+        // Check if it's the current account
+        if config.current.as_deref() == Some(name) {
+            output::warn(tr!("delete-active-warning", name = name));
+            if !self.confirm(&tr!("delete-confirm"), false)? {
+                self.say(tr!("cancelled"));
+                return Ok(());
+            }
+            config.current = None;
+        }
+
+        // Directories registered with add-existing aren't ours to delete, and
+        // one kept elsewhere with `--store` stays out of the trash
+        if keep_data || account_meta.external || self.custom_store(&account_meta).is_some() {
+            let entry = self.trash_entry(name)?;
+            self.trash_details(&entry, name, &account_meta)?;
+            config.remove_account(name);
+            self.save_config(&mut config)?;
+            self.record(EventKind::Delete, name, EventSource::Cli)?;
+
+            self.say(tr!(
+                "account-deleted-kept",
+                name = name,
+                path = account_meta.path.display()
+            ));
+            return self.run_hook(Hook::PostDelete, &[(hooks::NAME_VAR, name)]);
+        }
+
+        self.remove_account_files(name, &account_meta, &config)?;
+        config.remove_account(name);
+        self.save_config(&mut config)?;
+        self.record(EventKind::Delete, name, EventSource::Cli)?;
+
+        self.say(tr!("account-deleted", name = name));
+        self.run_hook(Hook::PostDelete, &[(hooks::NAME_VAR, name)])
+    }
+
+    /// Moves what the store keeps for `name` to the trash, for `undo`: its
+    /// snapshot, versions and keychain credentials. The manifest goes, and
+    /// the snapshots of accounts nested under this one stay where they are.
+    fn remove_account_files(
+        &self,
+        name: &str,
+        meta: &AccountMetadata,
+        config: &AccountsConfig,
+    ) -> Result<()> {
+        let linked = platform::same_location(&self.claude_config_dir, &meta.path);
+        let entry = self.trash_entry(name)?;
+        // Before anything's moved in, so the entry can't be taken for an
+        // unreadable one and purged with the snapshot in it
+        self.trash_details(&entry, name, meta)?;
+
+        let snapshot = trash::snapshot_dir(&entry);
+        // The trash is in the data directory, which a snapshot kept
+        // elsewhere with `--store` is meant to stay out of, so it's left
+        // where it is
+        if self.storage.exists(&meta.path) && self.custom_store(meta).is_none() {
+            info!(from = %meta.path.display(), to = %snapshot.display(), "move snapshot to trash");
+            if let Err(e) = retry(|| self.storage.rename(&meta.path, &snapshot)) {
+                let _ = trash::remove(&entry);
+                return Err(e).with_context(|| tr!("failed-move-account-directory-to-trash"));
+            }
+            self.move_nested(&snapshot, &meta.path, &nested_snapshots(config, &meta.path))?;
+        }
+        self.storage
+            .create_dir_all(&snapshot)
+            .with_context(|| tr!("failed-create-trash-directory"))?;
+        if linked {
+            info!(path = %self.claude_config_dir.display(), "remove link");
+            self.storage
+                .remove_symlink(&self.claude_config_dir)
+                .with_context(|| tr!("failed-remove-account-symlink"))?;
+        }
+
+        let versions = versions::account_dir(&self.switcher_dir, name);
+        if self.storage.exists(&versions) {
+            retry(|| self.storage.rename(&versions, &trash::versions_dir(&entry)))
+                .with_context(|| tr!("failed-move-account-versions-to-trash"))?;
+        }
+        if self.uses_keychain() {
+            self.keep_keychain_credentials(name, &snapshot)?;
+        }
+        manifest::remove(&self.state_dir, name)?;
+        if self.uses_keychain() {
+            self.credstore.remove(name)?;
+        }
+        Ok(())
+    }
+
+    /// A new place in the trash for `name`, once what has been there longer
+    /// than `keep-trash` is gone.
+    fn trash_entry(&self, name: &str) -> Result<PathBuf> {
+        let now = self.clock.now();
+        let purged = trash::purge(&self.switcher_dir, Some(now - self.settings.keep_trash()?))?;
+        if purged > 0 {
+            info!(purged, "purge trash");
+        }
+        trash::allocate(&self.switcher_dir, name, now)
+    }
+
+    /// Records `meta` in the trash, so `undo` can register `name` again.
+    fn trash_details(&self, entry: &Path, name: &str, meta: &AccountMetadata) -> Result<()> {
+        trash::write(
+            entry,
+            &trash::Trashed {
+                name: name.to_string(),
+                deleted_at: self.clock.now().to_rfc3339(),
+                meta: meta.clone(),
+            },
+        )
+    }
+
+    /// Reverses the most recent switch, delete or rename. Saves made by the
+    /// switcher itself in the meantime (the background re-save) don't count.
+    pub fn undo(&self) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let events = history::load(&self.history_file())?;
+        let last = events.iter().rev().find(|event| {
+            !(event.kind == EventKind::Save
+                && matches!(event.source, EventSource::AutoSave | EventSource::Resave))
+        });
+        let Some(event) = last.filter(|event| event.kind != EventKind::Undo) else {
+            anyhow::bail!(tr!("undo-nothing"));
+        };
+
+        match event.kind {
+            EventKind::Switch => self.undo_switch(event)?,
+            EventKind::Delete => {
+                self.restore_from_trash(&event.account)?;
+                self.say(tr!("undo-deleted", name = event.account));
+            }
+            EventKind::Rename => {
+                let Some(old_name) = &event.previous else {
+                    anyhow::bail!(tr!("undo-unsupported", action = event.kind));
+                };
+                self.rename_files(&event.account, old_name)?;
+                self.say(tr!("undo-renamed", old = event.account, new = old_name));
+            }
+            kind => anyhow::bail!(tr!("undo-unsupported", action = kind)),
+        }
+        self.record_details(
+            EventKind::Undo,
+            &event.account,
+            EventSource::Cli,
+            event.previous.as_deref(),
+            None,
+        )
+    }
+
+    /// Puts back the live directory `event` replaced, and the account that
+    /// was active with it.
+    fn undo_switch(&self, event: &Event) -> Result<()> {
+        let mut config = self.load_config()?;
+        if config.current.as_deref() != Some(event.account.as_str()) {
+            anyhow::bail!(tr!("undo-switch-moved", name = event.account));
+        }
+        if self.keep_live_backups() == 0 {
+            anyhow::bail!(tr!("undo-live-backups-off"));
+        }
+        let backups = live_backups::list(&self.live_backups_dir())?;
+        // Switches recorded before the id was kept took the newest
+        let backup = match &event.live_backup {
+            Some(id) => backups.into_iter().find(|backup| &backup.id == id),
+            None => backups.into_iter().next(),
+        };
+        let Some(backup) = backup else {
+            anyhow::bail!(tr!("undo-no-backup"));
+        };
+
+        // What was done since under the account switched to is kept in it
+        if self.unsaved != Unsaved::Discard {
+            self.save_from(&event.account, EventSource::AutoSave)?;
+            config = self.load_config()?;
+        }
+        let _operation = crash::begin("undo", &event.account);
+        self.recover_interrupted_switch()?;
+        crash::step(Step::SwappingLive);
+        let live = &self.claude_config_dir;
+        let previous = self.live_sibling(PREVIOUS_SUFFIX);
+        if live.exists() || live.is_symlink() {
+            retry(|| fs::rename(live, &previous))
+                .with_context(|| tr!("failed-move-current-configuration-aside"))?;
+        }
+        if let Err(e) = retry(|| fs::rename(&backup.path, live)) {
+            let _ = retry(|| fs::rename(&previous, live));
+            return Err(e).with_context(|| tr!("failed-move-earlier-configuration-into-place"));
+        }
+        self.restore_kept_paths()?;
+        if self
+            .set_live_aside(&previous, Some(&event.account))
+            .is_err()
+        {
+            self.discard(&previous);
+        }
+        self.discard(&self.live_sibling(PREVIOUS_PATHS_SUFFIX));
+
+        crash::step(Step::UpdatingConfig);
+        if config.current != event.previous {
+            config.previous = config.current.take();
+        }
+        config.current = event.previous.clone();
+        self.save_config(&mut config)?;
+        match &event.previous {
+            Some(name) => self.say(tr!("undo-switched", name = name)),
+            None => self.say(tr!("undo-switched-none")),
+        }
+        Ok(())
+    }
+
+    /// Puts a live directory a switch replaced back in place: the newest,
+    /// or the one `id` names. The account that was active in it is active
+    /// again, and the live directory it replaces is kept in turn.
+    pub fn recover_live(&self, id: Option<&str>) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let backups = live_backups::list(&self.live_backups_dir())?;
+        let backup = match id {
+            Some(id) => backups.iter().find(|backup| backup.id == id),
+            None => backups.first(),
+        };
+        let Some(backup) = backup else {
+            anyhow::bail!(match id {
+                Some(id) => tr!("live-backup-not-found", id = id),
+                None => tr!("live-backups-none"),
+            });
+        };
+
+        let _operation = crash::begin("recover-live", backup.account.as_deref().unwrap_or("-"));
+        self.recover_interrupted_switch()?;
+        let mut config = self.load_config()?;
+        crash::step(Step::SwappingLive);
+        let live = &self.claude_config_dir;
+        let previous = self.live_sibling(PREVIOUS_SUFFIX);
+        let replacing = live.exists() || live.is_symlink();
+        if replacing {
+            retry(|| fs::rename(live, &previous))
+                .with_context(|| tr!("failed-move-current-configuration-aside"))?;
+        }
+        if let Err(e) = retry(|| fs::rename(&backup.path, live)) {
+            let _ = retry(|| fs::rename(&previous, live));
+            return Err(e).with_context(|| tr!("failed-move-earlier-configuration-into-place"));
+        }
+        self.restore_kept_paths()?;
+        if replacing
+            && self
+                .set_live_aside(&previous, config.current.as_deref())
+                .is_err()
+        {
+            self.discard(&previous);
+        }
+        self.discard(&self.live_sibling(PREVIOUS_PATHS_SUFFIX));
+
+        crash::step(Step::UpdatingConfig);
+        let account = backup
+            .account
+            .clone()
+            .filter(|account| config.accounts.contains_key(account));
+        if config.current != account {
+            config.previous = config.current.take();
+            config.current = account.clone();
+        }
+        self.save_config(&mut config)?;
+        match &account {
+            Some(account) => {
+                // What it holds is compared with the snapshot in full on the
+                // next save
+                manifest::remove(&self.state_dir, account)?;
+                self.say(tr!("live-recovered", id = backup.id, name = account));
+                self.say(tr!("template-applied-save", account = account));
+            }
+            None => self.say(tr!("live-recovered-untracked", id = backup.id)),
+        }
+        Ok(())
+    }
+
+    /// Lists the live directories `recover-live` can put back, newest first.
+    pub fn list_live_backups(&self) -> Result<()> {
+        let backups = live_backups::list(&self.live_backups_dir())?;
+        if self.format.is_json() {
+            let rows: Vec<LiveBackupJson> = backups
+                .iter()
+                .map(|backup| LiveBackupJson {
+                    id: &backup.id,
+                    account: backup.account.as_deref(),
+                    replaced_at: backup.replaced_at(),
+                    path: &backup.path,
+                })
+                .collect();
+            return output::print_json(&rows);
+        }
+        if backups.is_empty() {
+            println!("{}", tr!("live-backups-none"));
+            return Ok(());
+        }
+        for backup in &backups {
+            println!("{}", self.format.live_backup(backup));
+        }
+        Ok(())
+    }
+
+    /// Offers to resume or roll back the save or switch an earlier run left
+    /// unfinished, when someone can answer, and otherwise only says so. One
+    /// another instance is still carrying out is left to it.
+    pub fn offer_recovery(&self) -> Result<()> {
+        let Some(marker) = interrupt::load(&self.state_dir)? else {
+            return Ok(());
+        };
+        if lock::held_by(&self.state_dir.join(lock::FILE)).is_some() {
+            return Ok(());
+        }
+        if !self.prompter.can_ask() {
+            output::warn(interrupted_message(&marker));
+            eprintln!("{}", tr!("interrupted-hint"));
+            return Ok(());
+        }
+        eprintln!("{}", interrupted_message(&marker));
+        let how = if self
+            .prompter
+            .confirm(&tr!("interrupted-resume-confirm"), true)?
+        {
+            Recovery::Resume
+        } else if self
+            .prompter
+            .confirm(&tr!("interrupted-rollback-confirm"), false)?
+        {
+            Recovery::Rollback
+        } else {
+            eprintln!("{}", tr!("interrupted-left"));
+            return Ok(());
+        };
+        self.recover(Some(how))
+    }
+
+    /// Deals with the save or switch an earlier run left unfinished: `how`
+    /// says whether to carry it out after all or undo what it had done, and
+    /// without it the user is asked. A snapshot it was halfway through
+    /// writing is put back either way, and written again by a resume.
+    pub fn recover(&self, how: Option<Recovery>) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let Some(marker) = interrupt::load(&self.state_dir)? else {
+            self.say(tr!("recover-none"));
+            return Ok(());
+        };
+        let how = match how {
+            Some(how) => how,
+            None => {
+                self.say(interrupted_message(&marker));
+                match self.confirm(&tr!("interrupted-resume-confirm"), true)? {
+                    true => Recovery::Resume,
+                    false if self.confirm(&tr!("interrupted-rollback-confirm"), false)? => {
+                        Recovery::Rollback
+                    }
+                    false => {
+                        self.say(tr!("cancelled"));
+                        return Ok(());
+                    }
+                }
+            }
+        };
+
+        if let Some(account) = marker.writing_snapshot() {
+            match &marker.undo {
+                Some(undo) => self.undo_interrupted_save(account, undo)?,
+                None if how == Recovery::Rollback => {
+                    output::warn(tr!("recover-save-kept", name = account));
+                }
+                None => {}
+            }
+            // What it holds is compared in full on the next save
+            manifest::remove(&self.state_dir, account)?;
+        }
+        match (how, marker.operation) {
+            (Recovery::Resume, Operation::Save) => {
+                self.save_from(&marker.account, EventSource::Cli)?;
+            }
+            // The outgoing account was saved before its snapshot went live
+            (Recovery::Resume, Operation::Switch) if marker.replacing_live() => {
+                self.switch_with(&marker.account, None, EventSource::Cli, Unsaved::Discard)?;
+            }
+            (Recovery::Resume, Operation::Switch) => {
+                self.switch_to(&marker.account, None, EventSource::Cli)?;
+            }
+            (Recovery::Rollback, Operation::Switch) if marker.replacing_live() => {
+                self.undo_interrupted_switch(marker.from.as_deref())?;
+            }
+            (Recovery::Rollback, _) if marker.writing_snapshot().is_none() => {
+                self.say(tr!("recover-nothing-done"));
+            }
+            (Recovery::Rollback, _) => {}
+        }
+        interrupt::clear(&self.state_dir)?;
+        if self.format.is_json() {
+            output::print_json(&ActionJson {
+                action: match how {
+                    Recovery::Resume => "resume",
+                    Recovery::Rollback => "rollback",
+                },
+                account: &marker.account,
+                cancelled: false,
+                copied: None,
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Puts back the snapshot of `account` an interrupted save was writing
+    /// over, as the save itself does when it fails.
+    fn undo_interrupted_save(&self, account: &str, undo: &Undo) -> Result<()> {
+        self.discard(&undo.snapshot);
+        match &undo.kept {
+            Some(kept) => {
+                retry(|| fs::rename(kept, &undo.snapshot))
+                    .with_context(|| tr!("failed-restore-snapshot"))?;
+                if self.uses_keychain() {
+                    self.restore_kept_credentials(account, &undo.snapshot)?;
+                }
+                self.say(tr!("recover-snapshot-restored", name = account));
+            }
+            None => self.say(tr!("recover-snapshot-removed", name = account)),
+        }
+        Ok(())
+    }
+
+    /// Puts back the live directory an interrupted switch was replacing,
+    /// with `from` active in it again. Once it's no longer beside the new
+    /// one, `from`'s snapshot (saved as the switch began) takes its place.
+    fn undo_interrupted_switch(&self, from: Option<&str>) -> Result<()> {
+        let live = &self.claude_config_dir;
+        let previous = self.live_sibling(PREVIOUS_SUFFIX);
+        self.discard(&self.live_sibling(STAGED_SUFFIX));
+        if self.storage.exists(&previous) || self.storage.is_symlink(&previous) {
+            // The shared files already carried over go back with it
+            if !self.storage.is_symlink(&previous)
+                && self.storage.is_dir(live)
+                && !self.storage.is_symlink(live)
+                && !self.capture.is_empty()
+            {
+                self.carry_uncaptured(live, &previous, Path::new(""), &mut Vec::new())
+                    .with_context(|| tr!("failed-keep-shared-files"))?;
+            }
+            self.discard(live);
+            retry(|| self.storage.rename(&previous, live)).with_context(|| {
+                tr!("failed-restore-configuration-interrupted-switch-moved-aside")
+            })?;
+        } else {
+            let Some(from) = from else {
+                anyhow::bail!(tr!("recover-live-instead"));
+            };
+            let config = self.load_config()?;
+            let meta = config
+                .get_account(from)
+                .ok_or_else(|| AccountError::NotFound(from.to_string()))?;
+            if self.activate(from, &meta.path, None)?.0.is_some() {
+                self.record_manifest(from, self.live_manifest(None));
+            }
+        }
+
+        let mut config = self.load_config()?;
+        if config.current.as_deref() != from {
+            config.current = from.map(str::to_string);
+            self.save_config(&mut config)?;
+        }
+        match from {
+            Some(from) => self.say(tr!("recover-switched-back", name = from)),
+            None => self.say(tr!("recover-switched-back-none")),
+        }
+        Ok(())
+    }
+
+    /// Moves `dir`, the live directory `account` was active in, in with the
+    /// live backups, dropping the oldest past `keep-live-backups`, and
+    /// returns the id it's kept under. The paths outside it that were
+    /// replaced with it go along, in its `.cas-paths` as in a snapshot. With
+    /// none to be kept they're removed.
+    fn set_live_aside(&self, dir: &Path, account: Option<&str>) -> Result<Option<String>> {
+        let paths = self.live_sibling(PREVIOUS_PATHS_SUFFIX);
+        let keep = self.keep_live_backups();
+        if keep == 0 {
+            self.discard(dir);
+            self.discard(&paths);
+            return Ok(None);
+        }
+        let area = self.live_backups_dir();
+        let backup = live_backups::allocate(&area, self.clock.now(), account)?;
+        info!(from = %dir.display(), to = %backup.display(), "keep live directory");
+        retry(|| fs::rename(dir, &backup)).with_context(|| tr!("failed-keep-live-directory"))?;
+        // A linked live directory is a snapshot, which has its own
+        if paths.is_dir() && !backup.is_symlink() {
+            let _ =
+                retry(|| fs::rename(paths.join(extra_paths::DIR), backup.join(extra_paths::DIR)));
+        }
+        self.discard(&paths);
+        live_backups::prune(&area, keep)?;
+        Ok(live_backups::id(&backup))
+    }
+
+    fn keep_live_backups(&self) -> usize {
+        self.settings
+            .keep_live_backups
+            .unwrap_or(live_backups::DEFAULT_KEEP)
+    }
+
+    fn live_backups_dir(&self) -> PathBuf {
+        self.live_sibling(live_backups::SUFFIX)
+    }
+
+    /// Copies the live directory as the checkpoint `label`, replacing one of
+    /// that label only with --force, and drops the oldest past
+    /// `keep-checkpoints`.
+    pub fn save_checkpoint(&self, label: &str) -> Result<()> {
+        checkpoints::validate(label)?;
+        let _lock = self.lock_state()?;
+        let live = &self.claude_config_dir;
+        if !self.storage.exists(live) {
+            anyhow::bail!(tr!("checkpoint-no-live", path = live.display()));
+        }
+        let area = self.checkpoints_dir();
+        if checkpoints::find(&area, label)?.is_some() && !self.force {
+            anyhow::bail!(tr!("checkpoint-exists", label = label));
+        }
+
+        let config = self.load_config()?;
+        let (incoming, copy) = checkpoints::incoming(&area)?;
+        let usage = match self.copy_dir_recursive(
+            live,
+            &copy,
+            Path::new(""),
+            &CapturePolicy::default(),
+            Codec::Plain,
+            None,
+        ) {
+            Ok(usage) => usage,
+            Err(e) => {
+                self.discard(&incoming);
+                return Err(e);
+            }
+        };
+        checkpoints::commit(
+            &area,
+            &incoming,
+            Checkpoint {
+                label: label.to_string(),
+                created_at: self.clock.now(),
+                account: config.current.clone(),
+                path: PathBuf::new(),
+            },
+        )?;
+        self.say(tr!(
+            "checkpoint-saved",
+            label = label,
+            size = size::format_bytes(usage.bytes)
+        ));
+
+        let keep = self
+            .settings
+            .keep_checkpoints
+            .unwrap_or(checkpoints::DEFAULT_KEEP);
+        // The one just taken is always kept
+        for removed in checkpoints::prune(&area, keep.max(1))? {
+            self.say(tr!("checkpoint-pruned", label = removed));
+        }
+        Ok(())
+    }
+
+    /// Puts the checkpoint `label` in place of the live directory, which is
+    /// kept with the live backups for `recover-live`. The account that was
+    /// active when it was taken is active again, if it still exists.
+    pub fn restore_checkpoint(&self, label: &str) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let Some(checkpoint) = checkpoints::find(&self.checkpoints_dir(), label)? else {
+            anyhow::bail!(tr!("checkpoint-not-found", label = label));
+        };
+
+        let _operation = crash::begin("checkpoint-restore", label);
+        self.recover_interrupted_switch()?;
+        let mut config = self.load_config()?;
+        let restoring = self.live_sibling(checkpoints::RESTORING_SUFFIX);
+        self.discard(&restoring);
+        self.storage
+            .create_dir_all(&restoring)
+            .with_context(|| tr!("failed-create-directory", path = restoring.display()))?;
+        if let Err(e) = self.copy_dir_recursive(
+            &checkpoint.live(),
+            &restoring,
+            Path::new(""),
+            &CapturePolicy::default(),
+            Codec::Plain,
+            None,
+        ) {
+            self.discard(&restoring);
+            return Err(e);
+        }
+
+        crash::step(Step::SwappingLive);
+        let live = &self.claude_config_dir;
+        let previous = self.live_sibling(PREVIOUS_SUFFIX);
+        let replacing = live.exists() || live.is_symlink();
+        if replacing {
+            retry(|| fs::rename(live, &previous))
+                .with_context(|| tr!("failed-move-current-configuration-aside"))?;
+        }
+        if let Err(e) = retry(|| fs::rename(&restoring, live)) {
+            let _ = retry(|| fs::rename(&previous, live));
+            self.discard(&restoring);
+            return Err(e).with_context(|| tr!("failed-move-earlier-configuration-into-place"));
+        }
+        if replacing
+            && self
+                .set_live_aside(&previous, config.current.as_deref())
+                .is_err()
+        {
+            self.discard(&previous);
+        }
+
+        crash::step(Step::UpdatingConfig);
+        let account = checkpoint
+            .account
+            .clone()
+            .filter(|account| config.accounts.contains_key(account));
+        if config.current != account {
+            config.previous = config.current.take();
+            config.current = account.clone();
+        }
+        self.save_config(&mut config)?;
+        if let Some(account) = &account {
+            // What it holds is compared with the snapshot in full on the next
+            // save
+            manifest::remove(&self.state_dir, account)?;
+        }
+        self.say(tr!("checkpoint-restored", label = label));
+        Ok(())
+    }
+
+    /// Lists the checkpoints, newest first.
+    pub fn list_checkpoints(&self) -> Result<()> {
+        let checkpoints = checkpoints::list(&self.checkpoints_dir())?;
+        if self.format.is_json() {
+            let rows: Vec<CheckpointJson> = checkpoints
+                .iter()
+                .map(|checkpoint| CheckpointJson {
+                    label: &checkpoint.label,
+                    account: checkpoint.account.as_deref(),
+                    created_at: checkpoint.created_at,
+                    path: &checkpoint.path,
+                })
+                .collect();
+            return output::print_json(&rows);
+        }
+        if checkpoints.is_empty() {
+            println!("{}", tr!("checkpoints-none"));
+            return Ok(());
+        }
+        for checkpoint in &checkpoints {
+            println!("{}", self.format.checkpoint(checkpoint));
+        }
+        Ok(())
+    }
+
+    pub fn delete_checkpoint(&self, label: &str) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let Some(checkpoint) = checkpoints::find(&self.checkpoints_dir(), label)? else {
+            anyhow::bail!(tr!("checkpoint-not-found", label = label));
+        };
+        checkpoints::remove(&checkpoint.path)?;
+        self.say(tr!("checkpoint-deleted", label = label));
+        Ok(())
+    }
+
+    fn checkpoints_dir(&self) -> PathBuf {
+        self.live_sibling(checkpoints::SUFFIX)
+    }
+
+    /// Brings the most recent delete of `name` back out of the trash.
+    fn restore_from_trash(&self, name: &str) -> Result<()> {
+        let Some(trash::Entry {
+            path: entry,
+            trashed,
+        }) = trash::latest(&self.switcher_dir, name)?
+        else {
+            anyhow::bail!(tr!("undo-not-in-trash", name = name));
+        };
+        let mut config = self.load_config()?;
+        if config.accounts.contains_key(name) {
+            return Err(AccountError::AlreadyExists(name.to_string()).into());
+        }
+        self.ensure_no_case_conflict(&config, name, None)?;
+
+        // Forgotten with --keep-data or external, the snapshot was left alone
+        let snapshot = trash::snapshot_dir(&entry);
+        let path = &trashed.meta.path;
+        if snapshot.exists() && path.exists() {
+            // Only the accounts nested under it were left behind
+            for entry in
+                fs::read_dir(&snapshot).with_context(|| tr!("failed-read-trashed-account"))?
+            {
+                let entry = entry?;
+                retry(|| fs::rename(entry.path(), path.join(entry.file_name())))
+                    .with_context(|| tr!("failed-restore-account-directory"))?;
+            }
+        } else if snapshot.exists() {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| tr!("failed-create-account-directory"))?;
+            }
+            retry(|| fs::rename(&snapshot, path))
+                .with_context(|| tr!("failed-restore-account-directory"))?;
+        }
+        let versions = trash::versions_dir(&entry);
+        if versions.exists() {
+            let dest = versions::account_dir(&self.switcher_dir, name);
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| tr!("failed-create-versions-directory"))?;
+            }
+            retry(|| fs::rename(&versions, &dest))
+                .with_context(|| tr!("failed-restore-account-versions"))?;
+        }
+        if self.uses_keychain() && !trashed.meta.external {
+            self.restore_kept_credentials(name, path)?;
+        }
+
+        config.add_account(name.to_string(), trashed.meta);
+        self.save_config(&mut config)?;
+        trash::remove(&entry)
+    }
+
+    /// Lists what's in the trash, most recently deleted first.
+    pub fn list_trash(&self) -> Result<()> {
+        let rows: Vec<TrashRow> = trash::list(&self.switcher_dir)?
+            .into_iter()
+            .map(|entry| {
+                let usage = size::dir_usage(&trash::snapshot_dir(&entry.path)).unwrap_or_default();
+                TrashRow {
+                    name: entry.trashed.name,
+                    deleted_at: entry.trashed.deleted_at,
+                    path: entry.trashed.meta.path,
+                    files: usage.files,
+                    bytes: usage.bytes,
+                }
+            })
+            .collect();
+
+        if self.format.is_json() {
+            return output::print_json(&rows);
+        }
+        if rows.is_empty() {
+            println!("{}", tr!("trash-empty"));
+            return Ok(());
+        }
+        for row in &rows {
+            println!("{}", self.format.trash_entry(row));
+        }
+        Ok(())
+    }
+
+    /// Registers the most recently deleted account `name` again.
+    pub fn restore_trashed(&self, name: &str) -> Result<()> {
+        let _lock = self.lock_state()?;
+        self.restore_from_trash(name)?;
+        self.record(EventKind::Restore, name, EventSource::Cli)?;
+        self.say(tr!("trash-restored", name = name));
+        Ok(())
+    }
+
+    /// Removes what has been in the trash for longer than `older_than`, or
+    /// everything.
+    pub fn empty_trash(&self, older_than: Option<&str>) -> Result<()> {
+        let before = older_than
+            .map(|value| parse_duration(value).map(|age| self.clock.now() - age))
+            .transpose()?;
+        if before.is_none() && !self.confirm(&tr!("trash-empty-confirm"), false)? {
+            self.say(tr!("cancelled"));
+            return Ok(());
+        }
+
+        let _lock = self.lock_state()?;
+        let purged = trash::purge(&self.switcher_dir, before)?;
+        self.say(tr!("trash-emptied", count = purged));
+        Ok(())
+    }
+
+    /// Writes a backup of every account, the history and the settings to
+    /// `output`, or the backups directory.
+    pub fn create_backup(&self, output: Option<&Path>) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let dir = output.map_or_else(|| self.state_dir.join(backup::DIR), Path::to_path_buf);
+        let written = self.write_backup(&dir)?;
+        self.report_backup(&written)
+    }
+
+    /// Like [`create_backup`](Self::create_backup), then deletes all but the
+    /// `keep` newest backups there, for running from cron or a timer.
+    pub fn auto_backup(&self, output: Option<&Path>, keep: usize) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let dir = output.map_or_else(|| self.state_dir.join(backup::DIR), Path::to_path_buf);
+        let mut written = self.write_backup(&dir)?;
+        written.removed = backup::rotate(&dir, keep)?;
+        self.report_backup(&written)
+    }
+
+    fn report_backup(&self, written: &BackupJson) -> Result<()> {
+        if self.format.is_json() {
+            return output::print_json(written);
+        }
+        self.say(tr!(
+            "backup-created",
+            path = written.path.display(),
+            count = written.accounts,
+            size = size::format_bytes(written.bytes)
+        ));
+        for path in &written.removed {
+            self.say(tr!("backup-removed", path = path.display()));
+        }
+        Ok(())
+    }
+
+    /// Writes a backup of the whole store into `dir`. Credentials kept in
+    /// the keychain go in sealed like the snapshots; accounts registered
+    /// with `add-existing` live elsewhere and are left out.
+    fn write_backup(&self, dir: &Path) -> Result<BackupJson> {
+        let config = self.load_config()?;
+        let mut extra = Vec::new();
+        let mut external = Vec::new();
+        for (name, meta) in &config.accounts {
+            if meta.external {
+                external.push(name.as_str());
+                continue;
+            }
+            if !self.uses_keychain() {
+                continue;
+            }
+            let Ok(relative) = meta.path.strip_prefix(&self.switcher_dir) else {
+                continue;
+            };
+            if let Some(credentials) = self.credstore.load(name)? {
+                let sealed = self.store_codec(true)?.apply(credentials.into_bytes())?;
+                extra.push((relative.join(CREDENTIALS_FILE), sealed));
+            }
+        }
+        if !external.is_empty() {
+            external.sort();
+            eprintln!(
+                "{}",
+                tr!("backup-external-skipped", names = external.join(", "))
+            );
+        }
+
+        let now = self.clock.now();
+        let mut accounts: Vec<String> = config.accounts.keys().cloned().collect();
+        accounts.sort();
+        let manifest = BackupManifest::new(now, accounts, &self.switcher_dir);
+        let path = backup::path_in(dir, now);
+        let sources = backup::Sources {
+            store_dir: &self.switcher_dir,
+            state_dir: &self.state_dir,
+            settings_file: &self.settings_file,
+            extra,
+        };
+        let bytes = backup::create(&path, &manifest, &sources)?;
+        info!(path = %path.display(), bytes, "write backup");
+        Ok(BackupJson {
+            path,
+            accounts: manifest.accounts.len(),
+            bytes,
+            removed: Vec::new(),
+        })
+    }
+
+    /// Replaces every account, the history and the settings with those in
+    /// the backup `file`. What's there now is backed up first, and the live
+    /// configuration is left alone.
+    pub fn restore_backup(&self, file: &Path) -> Result<()> {
+        if !self.confirm(&tr!("backup-restore-confirm"), false)? {
+            self.say(tr!("cancelled"));
+            return Ok(());
+        }
+        let _lock = self.lock_state()?;
+
+        // Unpacked in full first, so a damaged backup replaces nothing
+        let staging = self.state_dir.join(backup::STAGING_DIR);
+        self.discard(&staging);
+        let extracted = match backup::extract(file, &staging) {
+            Ok(extracted) => extracted,
+            Err(e) => {
+                self.discard(&staging);
+                return Err(e);
+            }
+        };
+        if !self.load_config()?.accounts.is_empty() {
+            let written = self.write_backup(&self.state_dir.join(backup::DIR))?;
+            self.say(tr!("backup-kept-current", path = written.path.display()));
+        }
+
+        let mut replaced = self.switcher_dir.clone().into_os_string();
+        replaced.push(PREVIOUS_SUFFIX);
+        let replaced = PathBuf::from(replaced);
+        self.discard(&replaced);
+        retry(|| fs::rename(&self.switcher_dir, &replaced))
+            .with_context(|| tr!("failed-move-account-store-aside"))?;
+        if let Err(e) = self.move_dir(&extracted.store_dir, &self.switcher_dir) {
+            let _ = retry(|| fs::rename(&replaced, &self.switcher_dir));
+            return Err(e).with_context(|| tr!("failed-restore-account-store"));
+        }
+        self.discard(&replaced);
+
+        for entry in
+            fs::read_dir(&self.state_dir).with_context(|| tr!("failed-read-state-directory"))?
+        {
+            let entry = entry?;
+            let name = entry.file_name();
+            if [lock::FILE, backup::DIR, backup::STAGING_DIR]
+                .iter()
+                .any(|kept| name == *kept)
+            {
+                continue;
+            }
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                retry(|| fs::remove_dir_all(&path))
+            } else {
+                retry(|| fs::remove_file(&path))
+            }
+            .with_context(|| tr!("failed-remove", path = path.display()))?;
+        }
+        for entry in
+            fs::read_dir(&extracted.state_dir).with_context(|| tr!("failed-read-restored-state"))?
+        {
+            let entry = entry?;
+            let dest = self.state_dir.join(entry.file_name());
+            retry(|| fs::rename(entry.path(), &dest))
+                .with_context(|| tr!("failed-restore", path = dest.display()))?;
+        }
+        if let Some(settings) = &extracted.settings_file {
+            if let Some(parent) = self.settings_file.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| tr!("failed-create-settings-directory"))?;
+            }
+            fs::copy(settings, &self.settings_file)
+                .with_context(|| tr!("failed-restore-settings"))?;
+        }
+        self.discard(&staging);
+
+        // Made on another machine, or before the store moved
+        let mut config = self.load_config()?;
+        for (name, meta) in &mut config.accounts {
+            if let Ok(relative) = meta.path.strip_prefix(&extracted.manifest.store_dir) {
+                meta.path = self.switcher_dir.join(relative);
+            }
+            if self.uses_keychain() && !meta.external {
+                self.restore_kept_credentials(name, &meta.path)?;
+            }
+        }
+        self.save_config(&mut config)?;
+
+        self.say(tr!(
+            "backup-restored",
+            count = config.accounts.len(),
+            time = extracted.manifest.created_at
+        ));
+        Ok(())
+    }
+
+    /// Clones the repository at `remote` to sync the store through. The
+    /// store has to be encrypted first.
+    pub fn sync_init(&self, remote: &str) -> Result<()> {
+        self.ensure_sync_encrypted()?;
+        let _lock = self.lock_state()?;
+        let repo = self.state_dir.join(sync::DIR);
+        if repo.exists() {
+            anyhow::bail!(tr!("sync-already-set-up", path = repo.display()));
+        }
+
+        sync::backend(remote, &self.state_dir)?.init(&repo)?;
+        SyncState {
+            remote: remote.to_string(),
+            synced: BTreeSet::new(),
+        }
+        .save(&self.state_dir.join(sync::STATE_FILE))?;
+        self.say(tr!("sync-set-up", remote = remote));
+        Ok(())
+    }
+
+    /// Sends what was saved or deleted here since the last sync to the
+    /// repository. Newer saves made elsewhere are left for `sync_pull`.
+    pub fn sync_push(&self) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let (repo, mut state, backend) = self.sync_start()?;
+        let config = self.load_config()?;
+        let mut remote = SyncedAccounts::load(&repo)?;
+        let local = synced_saves(&config);
+
+        let repo_key = repo.join(sync::KEY_FILE);
+        if !repo_key.exists() {
+            fs::copy(self.key_file(), &repo_key).with_context(|| tr!("failed-copy-store-key"))?;
+        }
+        let repo_vault = self.repo_vault(&repo)?;
+        let repo_vault = match &repo_vault {
+            Some(vault) => vault,
+            None => self.vault()?,
+        };
+
+        let mut changed = 0;
+        for change in sync::plan(&local, &remote.saved_at(), &state.synced) {
+            match change {
+                sync::Action::Upload(name) => {
+                    let meta = &config.accounts[&name];
+                    self.upload_account(&repo, repo_vault, &config, &name)?;
+                    remote.accounts.insert(
+                        name.clone(),
+                        SyncedAccount {
+                            saved_at: meta.saved_at.clone(),
+                            protected: meta.protected,
+                            identity: meta.identity.clone(),
+                        },
+                    );
+                    self.say(tr!("sync-sent", name = name));
+                }
+                sync::Action::RemoveRemote(name) => {
+                    let dir = sync::account_dir(&repo, &name);
+                    if dir.exists() {
+                        retry(|| fs::remove_dir_all(&dir))
+                            .with_context(|| tr!("failed-remove-synced-account"))?;
+                    }
+                    remote.accounts.remove(&name);
+                    self.say(tr!("sync-removed-there", name = name));
+                }
+                sync::Action::Download(_) | sync::Action::RemoveLocal(_) => continue,
+            }
+            changed += 1;
+        }
+        remote.save(&repo)?;
+        backend.publish(&repo, &format!("Sync {} account(s)", changed))?;
+
+        state.synced = sync::next_synced(local.keys(), remote.accounts.keys(), &state.synced);
+        state.save(&self.state_dir.join(sync::STATE_FILE))?;
+        if changed == 0 {
+            self.say(tr!("sync-up-to-date"));
+        }
+        Ok(())
+    }
+
+    /// Brings in what was saved or deleted elsewhere since the last sync.
+    /// The active account is left as it is: the live session here is newer
+    /// than anything saved of it.
+    pub fn sync_pull(&self) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let (repo, mut state, _) = self.sync_start()?;
+        let mut config = self.load_config()?;
+        let remote = SyncedAccounts::load(&repo)?;
+        let local = synced_saves(&config);
+        let repo_vault = self.repo_vault(&repo)?;
+        let repo_vault = match &repo_vault {
+            Some(vault) => vault,
+            None => self.vault()?,
+        };
+
+        let mut changed = 0;
+        for change in sync::plan(&local, &remote.saved_at(), &state.synced) {
+            match change {
+                sync::Action::Download(name) => {
+                    let existing = config.accounts.get(&name).cloned();
+                    if config.current.as_deref() == Some(name.as_str()) {
+                        eprintln!("{}", tr!("sync-skipped-active", name = name));
+                        continue;
+                    }
+                    if existing.as_ref().is_some_and(|meta| meta.external) {
+                        eprintln!("{}", tr!("sync-skipped-external", name = name));
+                        continue;
+                    }
+                    if existing.as_ref().is_some_and(|meta| meta.locked) && !self.force {
+                        eprintln!("{}", tr!("sync-skipped-locked", name = name));
+                        continue;
+                    }
+                    if let Some(meta) = &existing {
+                        let nested = nested_snapshots(&config, &meta.path);
+                        self.keep_version(&name, &meta.path, &meta.saved_at, &nested)?;
+                    }
+
+                    let synced = &remote.accounts[&name];
+                    let metadata = AccountMetadata {
+                        saved_at: synced.saved_at.clone(),
+                        path: PathBuf::new(),
+                        protected: synced.protected,
+                        locked: existing.as_ref().is_some_and(|meta| meta.locked),
+                        last_used: existing.as_ref().and_then(|meta| meta.last_used.clone()),
+                        switch_count: existing.as_ref().map_or(0, |meta| meta.switch_count),
+                        identity: synced.identity.clone(),
+                        // Tags, notes and limits are this machine's own
+                        tags: existing
+                            .as_ref()
+                            .map(|meta| meta.tags.clone())
+                            .unwrap_or_default(),
+                        limited_until: existing
+                            .as_ref()
+                            .and_then(|meta| meta.limited_until.clone()),
+                        // So is the environment, as proxies differ by network
+                        env: existing
+                            .as_ref()
+                            .map(|meta| meta.env.clone())
+                            .unwrap_or_default(),
+                        note: existing.and_then(|meta| meta.note),
+                        ..Default::default()
+                    };
+                    let source = sync::account_dir(&repo, &name);
+                    self.install_import(&mut config, &name, metadata, EventSource::Sync, |dest| {
+                        unpack_synced(&source, dest, repo_vault)
+                    })?;
+                }
+                sync::Action::RemoveLocal(name) => {
+                    if config.current.as_deref() == Some(name.as_str()) {
+                        eprintln!("{}", tr!("sync-skipped-active", name = name));
+                        continue;
+                    }
+                    let meta = config.accounts[&name].clone();
+                    if meta.locked && !self.force {
+                        eprintln!("{}", tr!("sync-skipped-locked-remove", name = name));
+                        continue;
+                    }
+                    self.remove_account_files(&name, &meta, &config)?;
+                    config.remove_account(&name);
+                    self.save_config(&mut config)?;
+                    self.record(EventKind::Delete, &name, EventSource::Sync)?;
+                    self.say(tr!("sync-removed-here", name = name));
+                }
+                sync::Action::Upload(_) | sync::Action::RemoveRemote(_) => continue,
+            }
+            changed += 1;
+        }
+
+        let local = synced_saves(&config);
+        state.synced = sync::next_synced(local.keys(), remote.accounts.keys(), &state.synced);
+        state.save(&self.state_dir.join(sync::STATE_FILE))?;
+        if changed == 0 {
+            self.say(tr!("sync-up-to-date"));
+        }
+        Ok(())
+    }
+
+    // Snapshots leave the machine sealed, so without a store key there's
+    // nothing to seal them with
+    fn ensure_sync_encrypted(&self) -> Result<()> {
+        if !self.encryption_enabled() {
+            anyhow::bail!(tr!("sync-needs-encryption"));
+        }
+        Ok(())
+    }
+
+    /// The clone, brought up to date, what the last sync left, and how to
+    /// reach the remote.
+    fn sync_start(&self) -> Result<(PathBuf, SyncState, Box<dyn SyncBackend>)> {
+        self.ensure_sync_encrypted()?;
+        let repo = self.state_dir.join(sync::DIR);
+        let Some(state) = SyncState::load(&self.state_dir.join(sync::STATE_FILE))? else {
+            anyhow::bail!(tr!("sync-not-set-up"));
+        };
+        if !repo.is_dir() {
+            anyhow::bail!(tr!("sync-not-set-up"));
+        }
+        let backend = sync::backend(&state.remote, &self.state_dir)?;
+        backend.fetch(&repo)?;
+        Ok((repo, state, backend))
+    }
+
+    /// The key the repository's snapshots are sealed with, when it isn't
+    /// this store's own: set up on another machine, with a salt of its own.
+    fn repo_vault(&self, repo: &Path) -> Result<Option<Vault>> {
+        let repo_key = repo.join(sync::KEY_FILE);
+        if !repo_key.exists() || fs::read(&repo_key).ok() == fs::read(self.key_file()).ok() {
+            return Ok(None);
+        }
+        let passphrase = match std::env::var(encryption::PASSPHRASE_ENV) {
+            Ok(passphrase) => passphrase,
+            Err(_) => self.prompter.passphrase(&tr!("sync-passphrase-prompt"))?,
+        };
+        Vault::unlock(&repo_key, &passphrase)
+            .map(Some)
+            .context(tr!("sync-wrong-passphrase"))
+    }
+
+    /// Writes the snapshot of `name` into the clone, each file opened with
+    /// this store's key and sealed with the repository's. Keychain
+    /// credentials go along as a sealed credentials file.
+    fn upload_account(
+        &self,
+        repo: &Path,
+        repo_vault: &Vault,
+        config: &AccountsConfig,
+        name: &str,
+    ) -> Result<()> {
+        let meta = &config.accounts[name];
+        let dest = sync::account_dir(repo, name);
+        if dest.exists() {
+            retry(|| fs::remove_dir_all(&dest))
+                .with_context(|| tr!("failed-clear-synced-account"))?;
+        }
+        fs::create_dir_all(&dest).with_context(|| tr!("failed-create-synced-account"))?;
+
+        let nested = nested_snapshots(config, &meta.path);
+        for entry in store::walk_dir(&meta.path).skip_dirs(&nested) {
+            let entry = entry?;
+            let data = self.read_stored_bytes(&entry.path)?.unwrap_or_default();
+            let target = dest.join(&entry.relative_path);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent).with_context(|| tr!("failed-create-synced-account"))?;
+            }
+            platform::write_private(&target, &repo_vault.seal(data)?)
+                .with_context(|| tr!("failed-write-file", path = target.display()))?;
+        }
+        if self.uses_keychain()
+            && let Some(credentials) = self.credstore.load(name)?
+        {
+            let sealed = repo_vault.seal(credentials.into_bytes())?;
+            platform::write_private(&dest.join(CREDENTIALS_FILE), &sealed)?;
+        }
+        Ok(())
+    }
+
+    pub fn rename_account(&self, old_name: &str, new_name: &str) -> Result<()> {
+        let _lock = self.lock_state()?;
+        self.rename_files(old_name, new_name)?;
+        self.record_details(
+            EventKind::Rename,
+            new_name,
+            EventSource::Cli,
+            Some(old_name),
+            None,
+        )?;
+        self.say(tr!("account-renamed", old = old_name, new = new_name));
+        Ok(())
+    }
+
+    /// Moves everything kept for `old_name` over to `new_name`.
+    fn rename_files(&self, old_name: &str, new_name: &str) -> Result<()> {
+        let mut config = self.load_config()?;
+
+        if !config.accounts.contains_key(old_name) {
+            return Err(AccountError::NotFound(old_name.to_string()).into());
+        }
+
+        if config.accounts.contains_key(new_name) {
+            return Err(AccountError::AlreadyExists(new_name.to_string()).into());
+        }
+
+        // Changing only the case of a name is fine; colliding with another account isn't
+        names::validate(new_name)?;
+        self.ensure_no_case_conflict(&config, new_name, Some(old_name))?;
+
+        let account_meta = config
+            .get_account(old_name)
+            .ok_or_else(|| AccountError::NotFound(old_name.to_string()))?
+            .clone();
+
+        // Rename directory; external ones stay where they are
+        let linked = platform::same_location(&self.claude_config_dir, &account_meta.path);
+        let archive = match &account_meta.archive {
+            Some(from) => {
+                let to = archive::cold_path(&self.switcher_dir, new_name);
+                retry(|| self.storage.rename(from, &to))
+                    .with_context(|| tr!("failed-rename-account-directory"))?;
+                Some(to)
+            }
+            None => None,
+        };
+        let new_dir = if account_meta.archive.is_some() {
+            self.custom_store(&account_meta)
+                .unwrap_or(&self.switcher_dir)
+                .join(new_name)
+        } else if account_meta.external {
+            account_meta.path.clone()
+        } else {
+            // One kept elsewhere with `--store` stays there
+            let store = self.custom_store(&account_meta);
+            if let Some(store) = store.filter(|store| !store::is_mounted(store)) {
+                return Err(self.unmounted(old_name, store));
+            }
+            let new_dir = store.unwrap_or(&self.switcher_dir).join(new_name);
+            retry(|| fs::rename(&account_meta.path, &new_dir))
+                .with_context(|| tr!("failed-rename-account-directory"))?;
+            new_dir
+        };
+
+        if linked && !account_meta.external {
+            let staged = self.stage_link(&new_dir)?;
+            retry(|| fs::rename(&staged, &self.claude_config_dir))
+                .with_context(|| tr!("failed-update-account-symlink"))?;
+        }
+
+        versions::rename(&self.switcher_dir, old_name, new_name)?;
+        manifest::rename(&self.state_dir, old_name, new_name)?;
+        if self.uses_keychain() {
+            credstore::rename(self.credstore.as_ref(), old_name, new_name)?;
+        }
+
+        // Update configuration using the config method
+        config.rename_account(old_name, new_name.to_string())?;
+
+        // Update the path in the renamed account metadata
+        if let Some(meta) = config.accounts.get_mut(new_name) {
+            meta.path = new_dir;
+            meta.archive = archive;
+        }
+
+        self.save_config(&mut config)
+    }
+
+    /// Duplicates the stored snapshot of `source` and its details as `name`,
+    /// leaving the live configuration alone, so changes made since `source`
+    /// was last saved aren't in the copy. It starts without versions. An
+    /// external source is copied into the store, the way a save would.
+    pub fn copy_account(&self, source: &str, name: &str) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let mut config = self.load_config()?;
+
+        let meta = config
+            .get_account(source)
+            .ok_or_else(|| AccountError::NotFound(source.to_string()))?
+            .clone();
+        if config.accounts.contains_key(name) {
+            return Err(AccountError::AlreadyExists(name.to_string()).into());
+        }
+        names::validate(name)?;
+        self.ensure_no_case_conflict(&config, name, None)?;
+        if !meta.path.exists() {
+            anyhow::bail!(tr!("account-dir-not-found", path = meta.path.display()));
+        }
+
+        let account_dir = self.switcher_dir.join(name);
+        if let Err(e) = self.copy_snapshot(&config, &meta, &account_dir) {
+            let _ = fs::remove_dir_all(&account_dir);
+            return Err(e.context(tr!("failed-copy-account-snapshot")));
+        }
+        if self.uses_keychain()
+            && let Some(credentials) = self.credstore.load(source)?
+        {
+            self.credstore.store(name, &credentials)?;
+        }
+
+        config.add_account(
+            name.to_string(),
+            AccountMetadata {
+                path: account_dir,
+                external: false,
+                last_used: None,
+                switch_count: 0,
+                ..meta
+            },
+        );
+        self.save_config(&mut config)?;
+        self.record(EventKind::Copy, name, EventSource::Cli)?;
+        self.say(tr!("account-copied", source = source, name = name));
+        Ok(())
+    }
+
+    /// Copies the files of a snapshot as they're stored, as clones where the
+    /// filesystem can make them. The snapshots of nested account names
+    /// inside it aren't part of it.
+    fn copy_snapshot(
+        &self,
+        config: &AccountsConfig,
+        meta: &AccountMetadata,
+        account_dir: &Path,
+    ) -> Result<()> {
+        let codec = if meta.external {
+            self.store_codec(true)?
+        } else {
+            Codec::Plain
+        };
+        fs::create_dir_all(account_dir).with_context(|| tr!("failed-create-account-directory"))?;
+        for entry in store::walk_dir(&meta.path).skip_dirs(&nested_snapshots(config, &meta.path)) {
+            let entry = entry?;
+            let dst = account_dir.join(&entry.relative_path);
+            if let Some(parent) = dst.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| tr!("failed-create-directory", path = parent.display()))?;
+            }
+            if meta.external {
+                copy_file(&*self.storage, &entry.path, &dst, codec)?;
+            } else {
+                take_unchanged(&entry.path, &dst, self.dedup_enabled())?;
+            }
+        }
+        if meta.external && self.dedup_enabled() {
+            self.intern_snapshot(account_dir)?;
+        }
+        Ok(())
+    }
+
+    /// Keeps the settings of the live configuration, or of the snapshot of
+    /// `from`, as the template `name`, replacing any template of that name.
+    /// Credentials and the login in `.claude.json` are left out.
+    pub fn save_template(&self, name: &str, from: Option<&str>) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let dir = self.template_dir(name)?;
+        let source = match from {
+            Some(account) => Some(
+                self.load_config()?
+                    .get_account(account)
+                    .ok_or_else(|| AccountError::NotFound(account.to_string()))?
+                    .path
+                    .clone(),
+            ),
+            None => None,
+        };
+        let read = |relative: &str| match &source {
+            Some(account_dir) => self.read_stored_bytes(&account_dir.join(relative)),
+            None if self.claude_config_dir.join(relative).is_file() => {
+                self.read_live(Path::new(relative))
+            }
+            None => Ok(None),
+        };
+
+        let mut files = Vec::new();
+        for file in template::FILES {
+            let Some(mut data) = read(file)? else {
+                continue;
+            };
+            // A snapshot the live directory links to still has the banner
+            if *file == banner::FILE
+                && let Ok(contents) = std::str::from_utf8(&data)
+            {
+                data = banner::strip(contents).into_bytes();
+                if data.is_empty() {
+                    continue;
+                }
+            }
+            files.push((*file, data));
+        }
+        let servers = read(template::CLAUDE_JSON)?
+            .and_then(|data| serde_json::from_slice::<serde_json::Value>(&data).ok())
+            .and_then(|mut value| {
+                value
+                    .get_mut(template::MCP_KEY)
+                    .map(serde_json::Value::take)
+            })
+            .filter(|servers| {
+                servers
+                    .as_object()
+                    .is_some_and(|servers| !servers.is_empty())
+            });
+        if let Some(servers) = servers {
+            let mcp = serde_json::json!({ template::MCP_KEY: servers });
+            let data = serde_json::to_vec_pretty(&mcp)
+                .with_context(|| tr!("failed-serialize-mcp-servers"))?;
+            files.push((template::MCP_FILE, data));
+        }
+        if files.is_empty() {
+            anyhow::bail!(tr!("template-empty", name = name));
+        }
+
+        if dir.exists() {
+            fs::remove_dir_all(&dir).with_context(|| tr!("failed-replace-template"))?;
+        }
+        fs::create_dir_all(&dir).with_context(|| tr!("failed-create-template-directory"))?;
+        let codec = self.store_codec(true)?;
+        for (file, data) in &files {
+            fs::write(dir.join(file), codec.apply(data.clone())?)
+                .with_context(|| tr!("failed-write-template-file", path = file))?;
+        }
+        self.say(tr!("template-saved", name = name, count = files.len()));
+        Ok(())
+    }
+
+    /// Layers the template `name` over the live configuration: its
+    /// `settings.json` and MCP servers are merged into what's there, key by
+    /// key, and its `CLAUDE.md` takes the place of the live one. The login
+    /// isn't touched, and the active account's snapshot only takes the
+    /// changes when it's next saved.
+    pub fn apply_template(&self, name: &str) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let dir = self.template_dir(name)?;
+        if !dir.is_dir() {
+            anyhow::bail!(tr!("template-not-found", name = name));
+        }
+        fs::create_dir_all(&self.claude_config_dir)
+            .with_context(|| tr!("failed-create-claude-config-directory"))?;
+
+        let layer_json = |template_file: &str, live_file: &str| -> Result<()> {
+            let Some(data) = self.read_stored_bytes(&dir.join(template_file))? else {
+                return Ok(());
+            };
+            let layer: serde_json::Value = serde_json::from_slice(&data)
+                .with_context(|| tr!("failed-parse-template-file", path = template_file))?;
+            let path = self.claude_config_dir.join(live_file);
+            let mut live = if path.exists() {
+                let contents = fs::read(&path)
+                    .with_context(|| tr!("failed-read-file", path = path.display()))?;
+                serde_json::from_slice(&contents)
+                    .with_context(|| tr!("failed-parse", path = path.display()))?
+            } else {
+                serde_json::json!({})
+            };
+            template::merge(&mut live, layer);
+            let contents = serde_json::to_string_pretty(&live)
+                .with_context(|| tr!("failed-serialize", path = live_file))?;
+            fs::write(&path, contents + "\n")
+                .with_context(|| tr!("failed-write-file", path = path.display()))
+        };
+        layer_json("settings.json", "settings.json")?;
+        layer_json(template::MCP_FILE, template::CLAUDE_JSON)?;
+
+        let config = self.load_config()?;
+        if let Some(data) = self.read_stored_bytes(&dir.join(banner::FILE))? {
+            fs::write(self.claude_config_dir.join(banner::FILE), data)
+                .with_context(|| tr!("failed-write-claude-md"))?;
+            if let (Some(template), Some(current)) = (&self.settings.banner, &config.current) {
+                self.write_banner(&banner::render(template, current))?;
+            }
+        }
+
+        self.say(tr!("template-applied", name = name));
+        if let Some(current) = &config.current {
+            self.say(tr!("template-applied-save", account = current));
+        }
+        Ok(())
+    }
+
+    pub fn list_templates(&self) -> Result<()> {
+        let names = template::list(&self.switcher_dir)?;
+        if self.format.is_json() {
+            return output::print_json(&names);
+        }
+        if names.is_empty() {
+            println!("{}", tr!("no-templates"));
+        }
+        for name in &names {
+            println!("{}", name);
+        }
+        Ok(())
+    }
+
+    pub fn delete_template(&self, name: &str) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let dir = self.template_dir(name)?;
+        if !dir.is_dir() {
+            anyhow::bail!(tr!("template-not-found", name = name));
+        }
+        fs::remove_dir_all(&dir).with_context(|| tr!("failed-delete-template"))?;
+        self.say(tr!("template-deleted", name = name));
+        Ok(())
+    }
+
+    /// Writes this setup as a preset (see `preset`) to `output`, or prints
+    /// it without one.
+    pub fn export_preset(&self, output: Option<&Path>) -> Result<()> {
+        let config = self.load_config()?;
+        let mut preset = Preset::new(self.clock.now().to_rfc3339());
+        // The file, not what CLAUDE_ACCOUNT_* variables make of it here
+        preset.settings = preset::shareable(&Settings::load(&self.settings_file)?)?;
+        preset.aliases = config.aliases.clone();
+        preset.tags = config
+            .accounts
+            .iter()
+            .filter(|(_, meta)| !meta.tags.is_empty())
+            .map(|(name, meta)| (name.clone(), meta.tags.clone()))
+            .collect();
+        for name in template::list(&self.switcher_dir)? {
+            let dir = self.template_dir(&name)?;
+            let mut files = BTreeMap::new();
+            for file in template::FILES.iter().chain([&template::MCP_FILE]) {
+                let path = dir.join(file);
+                if let Some(data) = self.read_stored_bytes(&path)? {
+                    let text = String::from_utf8(data)
+                        .with_context(|| tr!("failed-read-file", path = path.display()))?;
+                    files.insert(file.to_string(), text);
+                }
+            }
+            preset.templates.insert(name, files);
+        }
+
+        let contents = serde_json::to_string_pretty(&preset)
+            .with_context(|| tr!("failed-serialize", path = "preset"))?;
+        let Some(path) = output else {
+            println!("{}", contents);
+            return Ok(());
+        };
+        fs::write(path, contents + "\n")
+            .with_context(|| tr!("failed-write-file", path = path.display()))?;
+        self.say(tr!("preset-exported", path = path.display()));
+        Ok(())
+    }
+
+    /// Lays the preset in `file` over this setup. Everything in it is
+    /// checked before anything is written; aliases and tags of accounts
+    /// that aren't here are skipped with a warning.
+    pub fn import_preset(&self, file: &Path) -> Result<()> {
+        let contents = fs::read_to_string(file)
+            .with_context(|| tr!("failed-read-file", path = file.display()))?;
+        let preset = Preset::parse(&contents)?;
+        let _lock = self.lock_state()?;
+
+        let mut templates = Vec::new();
+        for (name, files) in &preset.templates {
+            let known = |file: &&String| {
+                template::FILES.contains(&file.as_str()) || *file == template::MCP_FILE
+            };
+            if let Some(file) = files.keys().find(|file| !known(file)) {
+                anyhow::bail!(tr!("preset-template-file", name = name, file = file));
+            }
+            templates.push((self.template_dir(name)?, files));
+        }
+        for alias in preset.aliases.keys() {
+            names::validate(alias)?;
+        }
+        let layer = preset::shareable(&preset.settings)?;
+        let (settings, setting_count) =
+            preset::merge_settings(&Settings::load(&self.settings_file)?, &layer)?;
+        settings.validate()?;
+        // Commands from someone else's file, run on every switch from now on
+        let hooks = hooks::commands(&layer.hooks);
+        if !hooks.is_empty() {
+            output::warn(tr!("preset-hooks"));
+            for (hook, command) in hooks {
+                eprintln!("  {}: {}", hook, command);
+            }
+            if !self.confirm(&tr!("preset-hooks-confirm"), false)? {
+                self.say(tr!("cancelled"));
+                return Ok(());
+            }
+        }
+
+        let mut config = self.load_config()?;
+        let mut missing = BTreeSet::new();
+        let mut alias_count = 0;
+        for (alias, name) in &preset.aliases {
+            if !config.accounts.contains_key(name) {
+                missing.insert(name);
+            } else if config.accounts.contains_key(alias) {
+                output::warn(tr!("alias-is-account", alias = alias));
+            } else {
+                config.aliases.insert(alias.clone(), name.clone());
+                alias_count += 1;
+            }
+        }
+        let mut tagged = 0;
+        for (name, tags) in &preset.tags {
+            match config.accounts.get_mut(name) {
+                Some(meta) => {
+                    meta.tags.extend(tags.iter().cloned());
+                    tagged += 1;
+                }
+                None => {
+                    missing.insert(name);
+                }
+            }
+        }
+        for name in missing {
+            output::warn(tr!("preset-account-missing", name = name));
+        }
+
+        self.save_config(&mut config)?;
+        settings.save(&self.settings_file)?;
+        let codec = self.store_codec(true)?;
+        for (dir, files) in &templates {
+            if dir.exists() {
+                fs::remove_dir_all(dir).with_context(|| tr!("failed-replace-template"))?;
+            }
+            fs::create_dir_all(dir).with_context(|| tr!("failed-create-template-directory"))?;
+            for (file, text) in *files {
+                fs::write(dir.join(file), codec.apply(text.clone().into_bytes())?)
+                    .with_context(|| tr!("failed-write-template-file", path = file))?;
+            }
+        }
+        self.say(tr!(
+            "preset-imported",
+            settings = setting_count,
+            aliases = alias_count,
+            tagged = tagged,
+            templates = templates.len()
+        ));
+        Ok(())
+    }
+
+    /// Where the template `name` is kept. Names that would lead out of the
+    /// templates directory are refused.
+    fn template_dir(&self, name: &str) -> Result<PathBuf> {
+        if name.is_empty() || name == "." || name == ".." {
+            anyhow::bail!(tr!("template-invalid-name", name = name));
+        }
+        Ok(template::dir(&self.switcher_dir, name))
+    }
+
+    /// Names `account` (or an alias of it) together with `env`, given as
+    /// `KEY=VALUE`.
+    pub fn create_profile(
+        &self,
+        name: &str,
+        account: &str,
+        env: &[String],
+        force: bool,
+    ) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let mut config = self.load_config()?;
+        names::validate(name)?;
+        let account = config
+            .resolve(account)
+            .ok_or_else(|| AccountError::NotFound(account.to_string()))?
+            .to_string();
+        if config.profiles.contains_key(name) && !force {
+            anyhow::bail!(tr!("profile-exists", name = name));
+        }
+        let env = env
+            .iter()
+            .map(|assignment| profile::parse_env(assignment))
+            .collect::<Result<_>>()?;
+
+        config.profiles.insert(
+            name.to_string(),
+            Profile {
+                account: account.clone(),
+                env,
+            },
+        );
+        self.save_config(&mut config)?;
+        self.say(tr!("profile-created", name = name, account = account));
+        Ok(())
+    }
+
+    /// Switches to the account of the profile `name`, then prints the
+    /// exports of its variables for `shell`, or with `write` merges them
+    /// into the `env` of the live `settings.json`. Those are kept by the
+    /// account once it's saved, as a template's settings are.
+    pub fn use_profile(&self, name: &str, shell: Option<Shell>, write: bool) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let config = self.load_config()?;
+        let profile = config
+            .profiles
+            .get(name)
+            .ok_or_else(|| anyhow::anyhow!(tr!("profile-unknown", name = name)))?;
+        // Known before switching, so a shell that can't be told apart
+        // doesn't leave the switch half done
+        let shell = match write {
+            true => None,
+            false => Some(direct::resolve_shell(shell)?),
+        };
+        if self
+            .switch_from(&profile.account, None, EventSource::Profile)?
+            .is_none()
+        {
+            return Ok(());
+        }
+
+        if write {
+            let path = self.claude_config_dir.join(claude::SETTINGS_FILE);
+            let mut settings = match path.exists() {
+                true => {
+                    let contents = fs::read(&path)
+                        .with_context(|| tr!("failed-read-file", path = path.display()))?;
+                    serde_json::from_slice(&contents)
+                        .with_context(|| tr!("failed-parse", path = path.display()))?
+                }
+                false => serde_json::json!({}),
+            };
+            template::merge(&mut settings, serde_json::json!({ "env": profile.env }));
+            let contents = serde_json::to_string_pretty(&settings)
+                .with_context(|| tr!("failed-serialize-settings-json"))?;
+            fs::write(&path, contents + "\n")
+                .with_context(|| tr!("failed-write-file", path = path.display()))?;
+            self.say(tr!("profile-written", name = name, path = path.display()));
+        }
+        if self.format.is_json() {
+            return output::print_json(&ProfileJson { name, profile });
+        }
+        if let Some(shell) = shell {
+            print!("{}", profile.exports(shell)?);
+        }
+        Ok(())
+    }
+
+    pub fn list_profiles(&self) -> Result<()> {
+        let config = self.load_config()?;
+        let profiles: Vec<ProfileJson> = config
+            .profiles
+            .iter()
+            .map(|(name, profile)| ProfileJson { name, profile })
+            .collect();
+        if self.format.is_json() {
+            return output::print_json(&profiles);
+        }
+        if profiles.is_empty() {
+            println!("{}", tr!("no-profiles"));
+        }
+        for line in self.format.profiles(&profiles) {
+            println!("{}", line);
+        }
+        Ok(())
+    }
+
+    pub fn delete_profile(&self, name: &str) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let mut config = self.load_config()?;
+        if config.profiles.remove(name).is_none() {
+            anyhow::bail!(tr!("profile-unknown", name = name));
+        }
+        self.save_config(&mut config)?;
+        self.say(tr!("profile-deleted", name = name));
+        Ok(())
+    }
+
+    pub fn share_account(&self, name: &str, expires: &str, output: Option<&Path>) -> Result<()> {
+        let config = self.load_config()?;
+
+        let account_meta = config
+            .get_account(name)
+            .ok_or_else(|| AccountError::NotFound(name.to_string()))?;
+
+        if !account_meta.path.exists() {
+            anyhow::bail!(tr!(
+                "account-dir-not-found",
+                path = account_meta.path.display()
+            ));
+        }
+
+        let now = self.clock.now();
+        let expires_at = now + parse_duration(expires)?;
+        let manifest = ArchiveManifest::new(name, &account_meta.saved_at, now, Some(expires_at));
+        let codec = self.store_codec(false)?;
+        let data = archive::pack_with(&manifest, &account_meta.path, |path| {
+            codec.apply(fs::read(path)?)
+        })?;
+
+        // Every bundle gets its own passphrase so it can be handed out separately
+        let passphrase = crypto::generate_passphrase()?;
+        let bundle = crypto::encrypt(&data, &passphrase)?;
+
+        let output = output
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from(format!("{}.casbundle", name)));
+        fs::write(&output, bundle)
+            .with_context(|| tr!("failed-write-bundle", path = output.display()))?;
+
+        self.say(tr!("share-created", name = name, path = output.display()));
+        self.say(tr!("share-passphrase", passphrase = passphrase));
+        self.say(tr!(
+            "share-expires",
+            time = expires_at.format("%Y-%m-%dT%H:%M:%S")
+        ));
+
+        Ok(())
+    }
+
+    /// Writes the accounts `args` name (see `expand_names`), or every account
+    /// when it's empty, to a portable archive.
+    pub fn export_accounts(
+        &self,
+        args: &[String],
+        output: Option<&Path>,
+        encrypt: bool,
+        passphrase: Option<&str>,
+        redacted: bool,
+    ) -> Result<()> {
+        let config = self.load_config()?;
+
+        let mut accounts: Vec<ExportedAccount> = match args.is_empty() {
+            false => {
+                let mut accounts = Vec::new();
+                for name in self.expand_names(args)? {
+                    let meta = config
+                        .get_account(&name)
+                        .ok_or_else(|| AccountError::NotFound(name.clone()))?;
+                    accounts.push(ExportedAccount {
+                        name,
+                        metadata: meta.clone(),
+                    });
+                }
+                accounts
+            }
+            true => config
+                .accounts
+                .iter()
+                .map(|(name, meta)| ExportedAccount {
+                    name: name.clone(),
+                    metadata: meta.clone(),
+                })
+                .collect(),
+        };
+        // Named for the account when it's the only one asked for
+        let name = match (args, accounts.as_slice()) {
+            ([arg], [account]) if !names::is_pattern(arg) => Some(account.name.clone()),
+            _ => None,
+        };
+        let name = name.as_deref();
+        if accounts.is_empty() {
+            self.say(tr!("no-accounts"));
+            return Ok(());
+        }
+        accounts.sort_by(|a, b| a.name.cmp(&b.name));
+
+        if let Some(missing) = accounts
+            .iter()
+            .find(|account| !account.metadata.path.exists())
+        {
+            anyhow::bail!(tr!(
+                "account-dir-not-found",
+                path = missing.metadata.path.display()
+            ));
+        }
+
+        // Ask up front rather than after packing everything
+        let passphrase = match (encrypt, passphrase) {
+            (false, _) => None,
+            (true, Some(passphrase)) => Some(passphrase.to_string()),
+            (true, None) => Some(self.new_passphrase(
+                &tr!("export-passphrase-prompt"),
+                &tr!("export-passphrase-repeat"),
+            )?),
+        };
+
+        let codec = self.store_codec(false)?;
+        if redacted {
+            // Who it was logged in as goes along with the login
+            for account in &mut accounts {
+                account.metadata.identity = None;
+            }
+        }
+        let mut manifest = ExportManifest::new(self.clock.now(), accounts);
+        manifest.redacted = redacted;
+        let mut redaction = Redaction::default();
+        let data = export::pack(&manifest, |account| {
+            self.export_files(account, codec, redacted.then_some(&mut redaction))
+        })?;
+        let data = match &passphrase {
+            Some(passphrase) => crypto::encrypt(&data, passphrase)?,
+            None => data,
+        };
+
+        let output = output.map(Path::to_path_buf).unwrap_or_else(|| {
+            let stem = name.map_or_else(
+                || "claude-accounts".to_string(),
+                |name| name.replace('/', "-"),
+            );
+            PathBuf::from(format!("{}.{}", stem, export::EXTENSION))
+        });
+        fs::write(&output, data)
+            .with_context(|| tr!("failed-write-archive", path = output.display()))?;
+
+        match name {
+            Some(name) => self.say(tr!(
+                "export-created-one",
+                name = name,
+                path = output.display()
+            )),
+            None => self.say(tr!(
+                "export-created-many",
+                count = manifest.accounts.len(),
+                path = output.display()
+            )),
+        }
+        if redacted {
+            self.say(tr!(
+                "export-redacted",
+                dropped = redaction.dropped,
+                masked = redaction.masked
+            ));
+        }
+        Ok(())
+    }
+
+    /// Imports a bundle made by `share`, or every account in an archive made
+    /// by `export`. Names already taken are handled as `on_conflict` says.
+    pub fn import_account(
+        &self,
+        file: &Path,
+        name: Option<&str>,
+        passphrase: Option<&str>,
+        dry_run: bool,
+        on_conflict: Option<OnConflict>,
+    ) -> Result<()> {
+        let data = self.read_bundle(file, passphrase)?;
+        let _lock = (!dry_run).then(|| self.lock_state()).transpose()?;
+        if export::is_export(&data) {
+            return self.import_export(&Export::read(&data)?, name, dry_run, on_conflict);
+        }
+
+        let manifest = archive::read_manifest(&data)?;
+        if manifest.is_expired(self.clock.now())? {
+            return Err(
+                AccountError::BundleExpired(manifest.expires_at.unwrap_or_default()).into(),
+            );
+        }
+
+        let mut config = self.load_config()?;
+        let Some(name) = self.import_name(&config, name.unwrap_or(&manifest.name), on_conflict)?
+        else {
+            return Ok(());
+        };
+
+        if dry_run {
+            let usage = archive::inspect(&data)?.usage;
+            self.say(tr!(
+                "import-dry-run",
+                name = name,
+                files = usage.files,
+                size = size::format_bytes(usage.bytes)
+            ));
+            return Ok(());
+        }
+
+        self.install_import(
+            &mut config,
+            &name,
+            AccountMetadata {
+                saved_at: manifest.saved_at,
+                path: PathBuf::new(),
+                ..Default::default()
+            },
+            EventSource::Cli,
+            |account_dir| archive::unpack(&data, account_dir).map(drop),
+        )
+    }
+
+    fn import_export(
+        &self,
+        export: &Export,
+        name: Option<&str>,
+        dry_run: bool,
+        on_conflict: Option<OnConflict>,
+    ) -> Result<()> {
+        let accounts = &export.manifest.accounts;
+        if name.is_some() && accounts.len() != 1 {
+            anyhow::bail!(tr!("import-name-needs-single", count = accounts.len()));
+        }
+
+        let mut config = self.load_config()?;
+        for (index, account) in accounts.iter().enumerate() {
+            let Some(target) =
+                self.import_name(&config, name.unwrap_or(&account.name), on_conflict)?
+            else {
+                continue;
+            };
+
+            if dry_run {
+                let usage = export.usage(index)?;
+                self.say(tr!(
+                    "import-dry-run",
+                    name = target,
+                    files = usage.files,
+                    size = size::format_bytes(usage.bytes)
+                ));
+                continue;
+            }
+
+            // Keep what describes the account; where it lived and when it
+            // was last used only mean something on the exporting machine
+            let metadata = AccountMetadata {
+                external: false,
+                last_used: None,
+                switch_count: 0,
+                identity: None,
+                ..account.metadata.clone()
+            };
+            self.install_import(
+                &mut config,
+                &target,
+                metadata,
+                EventSource::Cli,
+                |account_dir| export.unpack(index, account_dir),
+            )?;
+        }
+        if export.manifest.redacted && !dry_run {
+            output::warn(tr!("import-redacted", mask = redact::MASK));
+        }
+        Ok(())
+    }
+
+    /// Picks the name to import `name` under, or `None` when it should be
+    /// skipped.
+    fn import_name(
+        &self,
+        config: &AccountsConfig,
+        name: &str,
+        on_conflict: Option<OnConflict>,
+    ) -> Result<Option<String>> {
+        let taken = |candidate: &str| {
+            config.accounts.contains_key(candidate)
+                || self
+                    .ensure_no_case_conflict(config, candidate, None)
+                    .is_err()
+        };
+        if !taken(name) {
+            return Ok(Some(name.to_string()));
+        }
+
+        match on_conflict {
+            None if config.accounts.contains_key(name) => {
+                eprintln!("{}", tr!("import-conflict-hint"));
+                Err(AccountError::AlreadyExists(name.to_string()).into())
+            }
+            Some(OnConflict::Skip) => {
+                self.say(tr!("import-skipped", name = name));
+                Ok(None)
+            }
+            Some(OnConflict::Rename) => {
+                let renamed = (2..)
+                    .map(|n| format!("{}-{}", name, n))
+                    .find(|candidate| !taken(candidate))
+                    .expect("some suffix is free");
+                self.say(tr!("import-renamed", name = name, new_name = renamed));
+                Ok(Some(renamed))
+            }
+            Some(OnConflict::Replace) if config.accounts.contains_key(name) => {
+                // The live session would be saved over it on the next switch
+                if config.current.as_deref() == Some(name) {
+                    anyhow::bail!(tr!("import-replace-active", name = name));
+                }
+                Ok(Some(name.to_string()))
+            }
+            // Only a differently cased name clashes, and that isn't this account
+            _ => {
+                self.ensure_no_case_conflict(config, name, None)?;
+                Ok(Some(name.to_string()))
+            }
+        }
+    }
+
+    /// Writes an imported snapshot into the store as `name` with `unpack`,
+    /// then registers it with `metadata`, replacing any account of that name.
+    fn install_import(
+        &self,
+        config: &mut AccountsConfig,
+        name: &str,
+        metadata: AccountMetadata,
+        source: EventSource,
+        unpack: impl FnOnce(&Path) -> Result<()>,
+    ) -> Result<()> {
+        names::validate(name)?;
+        if config.accounts.get(name).is_some_and(|meta| meta.locked) && !self.force {
+            return Err(AccountError::Locked(name.to_string()).into());
+        }
+        let account_dir = self.switcher_dir.join(name);
+        // Unpacked next to where it goes, under a name no account can have
+        let staged = account_dir.with_file_name(format!(
+            ".importing-{}",
+            account_dir
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+        ));
+        self.discard(&staged);
+        if let Err(e) = unpack(&staged) {
+            self.discard(&staged);
+            return Err(e.context(tr!("failed-write-account-snapshot")));
+        }
+        // Leftover directories are untracked, so they're safe to replace; an
+        // account being replaced only loses its copy in the store, never an
+        // external directory, nor the snapshots of accounts nested in it
+        if account_dir.exists() {
+            let nested = nested_snapshots(config, &account_dir);
+            self.move_nested(&account_dir, &staged, &nested)?;
+            retry(|| fs::remove_dir_all(&account_dir))
+                .with_context(|| tr!("failed-clear-account-directory"))?;
+        }
+        retry(|| fs::rename(&staged, &account_dir))
+            .with_context(|| tr!("failed-write-account-snapshot"))?;
+        manifest::remove(&self.state_dir, name)?;
+        if self.uses_keychain() {
+            let saved = account_dir.join(CREDENTIALS_FILE);
+            if saved.exists() {
+                let credentials = fs::read_to_string(&saved)
+                    .with_context(|| tr!("failed-read-credentials-file"))?;
+                self.credstore.store(name, &credentials)?;
+                fs::remove_file(&saved)
+                    .with_context(|| tr!("failed-remove-saved-credentials-file"))?;
+            } else {
+                self.credstore.remove(name)?;
+            }
+        }
+        // Stored the way `save` would have copied it
+        let codec = self.store_codec(true)?;
+        if !matches!(codec, Codec::Plain) {
+            store::rewrite_tree(&account_dir, |data| codec.apply(data))?;
+        }
+        if self.dedup_enabled() {
+            self.intern_snapshot(&account_dir)?;
+        }
+
+        // An export carries the identity along; anything else is read from
+        // what was unpacked
+        let identity = metadata
+            .identity
+            .clone()
+            .or_else(|| self.account_login(name, &account_dir).to_identity());
+        config.add_account(
+            name.to_string(),
+            AccountMetadata {
+                path: account_dir,
+                identity,
+                ..metadata
+            },
+        );
+        self.save_config(config)?;
+        self.record(EventKind::Import, name, source)?;
+        self.say(tr!("account-imported", name = name));
+        Ok(())
+    }
+
+    /// Probes the machine again, refreshing the cache, and reports which
+    /// features are active and what they fall back to.
+    pub fn show_capabilities(&self) -> Result<()> {
+        let probed =
+            Capabilities::probe(&self.state_dir, self.credstore.as_ref(), self.clock.now());
+        let cache = self.state_dir.join(capabilities::FILE);
+        probed.save(&cache)?;
+        if self.format.is_json() {
+            return output::print_json(&probed);
+        }
+
+        let finding = |available: bool, yes: String, no: String| {
+            if available {
+                Finding::ok(yes)
+            } else {
+                Finding::warning(no)
+            }
+        };
+        let findings = [
+            finding(
+                probed.symlinks,
+                tr!("capability-symlinks"),
+                tr!("capability-no-symlinks"),
+            ),
+            finding(
+                probed.keychain,
+                tr!("capability-keychain"),
+                tr!("capability-no-keychain"),
+            ),
+            finding(
+                probed.reflink,
+                tr!("capability-reflink"),
+                tr!("capability-no-reflink"),
+            ),
+        ];
+        for finding in &findings {
+            for line in self.format.finding(finding) {
+                println!("{}", line);
+            }
+        }
+        self.say(tr!("capabilities-cached", path = cache.display()));
+        Ok(())
+    }
+
+    /// Rewrites the accounts file, history and settings in the current
+    /// format, keeping each old file as `.bak`. Everything is read and
+    /// validated before anything is written, and `dry_run` writes nothing.
+    pub fn upgrade_config(&self, dry_run: bool) -> Result<()> {
+        let _lock = (!dry_run).then(|| self.lock_state()).transpose()?;
+        let mut files = Vec::new();
+
+        if self.accounts_file.exists() {
+            let before = fs::read_to_string(&self.accounts_file)
+                .with_context(|| tr!("failed-read-accounts-configuration-file"))?;
+            let config = self.load_config()?;
+            files.push((
+                self.accounts_file.clone(),
+                before,
+                config.to_json()?,
+                tr!("upgrade-accounts", count = config.accounts.len()),
+            ));
+        }
+
+        let history_file = self.history_file();
+        if history_file.exists() {
+            let before = fs::read_to_string(&history_file)
+                .with_context(|| tr!("failed-read-history-file"))?;
+            let rewritten = history::rewrite(&before)?;
+            let contents = if rewritten.kept > 0 {
+                tr!(
+                    "upgrade-events-kept",
+                    count = rewritten.events,
+                    kept = rewritten.kept
+                )
+            } else {
+                tr!("upgrade-events", count = rewritten.events)
+            };
+            files.push((history_file, before, rewritten.contents, contents));
+        }
+
+        if self.settings_file.exists() {
+            let before = fs::read_to_string(&self.settings_file)
+                .with_context(|| tr!("failed-read-settings-file"))?;
+            let settings = Settings::load(&self.settings_file)?;
+            settings.validate()?;
+            let contents = if before
+                .lines()
+                .any(|line| line.trim_start().starts_with('#'))
+            {
+                tr!("upgrade-settings-comments")
+            } else {
+                tr!("upgrade-settings")
+            };
+            files.push((
+                self.settings_file.clone(),
+                before,
+                settings.to_toml()?,
+                contents,
+            ));
+        }
+
+        let mut upgrades = Vec::new();
+        for (path, before, after, contents) in files {
+            let changed = before != after;
+            let backup = changed.then(|| {
+                path.with_extension(match path.extension() {
+                    Some(extension) => format!("{}.bak", extension.to_string_lossy()),
+                    None => "bak".to_string(),
+                })
+            });
+            if let Some(backup) = backup.as_ref().filter(|_| !dry_run) {
+                fs::write(backup, &before)
+                    .with_context(|| tr!("failed-write", path = backup.display()))?;
+                fs::write(&path, &after)
+                    .with_context(|| tr!("failed-write", path = path.display()))?;
+            }
+            upgrades.push(FileUpgrade {
+                path,
+                contents,
+                before_bytes: before.len() as u64,
+                after_bytes: after.len() as u64,
+                changed,
+                backup: backup.filter(|_| !dry_run),
+            });
+        }
+
+        if self.format.is_json() {
+            return output::print_json(&upgrades);
+        }
+        if upgrades.is_empty() {
+            println!("{}", tr!("upgrade-nothing"));
+        }
+        for upgrade in &upgrades {
+            for line in self.format.file_upgrade(upgrade) {
+                println!("{}", line);
+            }
+        }
+        if dry_run && upgrades.iter().any(|upgrade| upgrade.changed) {
+            self.say(tr!("upgrade-dry-run"));
+        }
+        Ok(())
+    }
+
+    /// Registers an account for a snapshot directory in the store that
+    /// `accounts.json` doesn't list, saved when the directory last changed.
+    fn orphan_metadata(&self, name: &str, orphan: PathBuf, now: DateTime<Utc>) -> AccountMetadata {
+        let saved_at = fs::metadata(&orphan)
+            .and_then(|metadata| metadata.modified())
+            .map_or_else(|_| now, DateTime::<Utc>::from);
+        let identity = self.account_login(name, &orphan).to_identity();
+        AccountMetadata {
+            saved_at: saved_at.to_rfc3339(),
+            path: orphan,
+            identity,
+            ..Default::default()
+        }
+    }
+
+    /// The directories in the store that no account in `config` uses.
+    fn orphaned_dirs(&self, config: &AccountsConfig) -> Result<Vec<PathBuf>> {
+        if !self.switcher_dir.is_dir() {
+            return Ok(Vec::new());
+        }
+        let tracked: Vec<&Path> = config
+            .accounts
+            .values()
+            .map(|meta| meta.path.as_path())
+            .collect();
+        doctor::orphaned_dirs(&self.switcher_dir, &tracked)
+    }
+
+    /// Registers each directory in the store that no account uses as an
+    /// account of its name, returning the names adopted and the directories
+    /// skipped because their names can't be used.
+    fn adopt_untracked(&self, config: &mut AccountsConfig) -> Result<(Vec<String>, Vec<PathBuf>)> {
+        let mut adopted = Vec::new();
+        let mut skipped = Vec::new();
+        let now = self.clock.now();
+        for orphan in self.orphaned_dirs(config)? {
+            let name = match self.orphan_name(config, &orphan) {
+                Ok(name) => name,
+                Err(e) => {
+                    self.say(tr!(
+                        "adopt-skipped",
+                        path = orphan.display(),
+                        error = format!("{:#}", e)
+                    ));
+                    skipped.push(orphan);
+                    continue;
+                }
+            };
+            self.say(tr!("account-adopted", name = name, path = orphan.display()));
+            let metadata = self.orphan_metadata(&name, orphan, now);
+            config.add_account(name.clone(), metadata);
+            adopted.push(name);
+        }
+        Ok((adopted, skipped))
+    }
+
+    /// The name a directory in the store no account uses is adopted under:
+    /// its own, when that's one a new account could have.
+    fn orphan_name(&self, config: &AccountsConfig, orphan: &Path) -> Result<String> {
+        let name = orphan
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        names::validate(&name)?;
+        self.ensure_no_case_conflict(config, &name, None)?;
+        Ok(name)
+    }
+
+    /// `adopt --scan`: adopts every directory in the store no account uses.
+    pub fn adopt_all(&self) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let mut config = self.load_config()?;
+        let (adopted, _) = self.adopt_untracked(&mut config)?;
+        if adopted.is_empty() {
+            self.say(tr!("adopt-none"));
+            return Ok(());
+        }
+        self.save_config(&mut config)
+    }
+
+    /// Registers the snapshot directory at `path` as the account `name`
+    /// (by default the directory's name, or its place in the store). One in
+    /// the store is used where it is; one anywhere else is copied in, as a
+    /// save would, and left as it was.
+    pub fn adopt(&self, path: &Path, name: Option<&str>) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let mut config = self.load_config()?;
+        if !path.is_dir() {
+            anyhow::bail!(tr!("account-dir-not-found", path = path.display()));
+        }
+        let path = fs::canonicalize(path)
+            .with_context(|| tr!("failed-resolve-path", path = path.display()))?;
+        if let Some((owner, _)) = config
+            .accounts
+            .iter()
+            .find(|(_, meta)| platform::same_location(&meta.path, &path))
+        {
+            anyhow::bail!(tr!("adopt-tracked", path = path.display(), name = owner));
+        }
+
+        // Dot directories in the store hold versions and the trash, which
+        // clean themselves up, so what's in them is copied out instead
+        let store =
+            fs::canonicalize(&self.switcher_dir).unwrap_or_else(|_| self.switcher_dir.clone());
+        let in_store = path
+            .strip_prefix(&store)
+            .ok()
+            .filter(|relative| {
+                relative.components().next().is_some()
+                    && !relative
+                        .components()
+                        .any(|part| part.as_os_str().to_string_lossy().starts_with('.'))
+            })
+            .map(Path::to_path_buf);
+        let name = match (name, &in_store) {
+            (Some(name), _) => name.to_string(),
+            (None, Some(relative)) => relative
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            (None, None) => path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        };
+        if config.accounts.contains_key(&name) {
+            return Err(AccountError::AlreadyExists(name).into());
+        }
+        names::validate(&name)?;
+        self.ensure_no_case_conflict(&config, &name, None)?;
+
+        let metadata = match in_store {
+            Some(_) => {
+                self.say(tr!("account-adopted", name = name, path = path.display()));
+                self.orphan_metadata(&name, path, self.clock.now())
+            }
+            None => {
+                let account_dir = self.switcher_dir.join(&name);
+                if account_dir.exists() {
+                    anyhow::bail!(tr!("adopt-dir-taken", path = account_dir.display()));
+                }
+                fs::create_dir_all(&account_dir)
+                    .with_context(|| tr!("failed-create-account-directory"))?;
+                let codec = self.store_codec(true)?;
+                let transfer = self
+                    .timed_copy(&path, &account_dir, &self.capture, codec)
+                    .with_context(|| tr!("failed-copy-configuration-files"))?;
+                if self.uses_keychain() {
+                    self.move_credentials_to_keychain(&name, &account_dir)?;
+                }
+                self.strip_banner(&account_dir)?;
+                if self.dedup_enabled() {
+                    self.intern_snapshot(&account_dir)?;
+                }
+                self.say(tr!("adopt-copied", path = path.display(), name = name));
+                self.say(stats::summary(transfer.usage, transfer.elapsed));
+                AccountMetadata {
+                    saved_at: self.clock.now().to_rfc3339(),
+                    ..self.orphan_metadata(&name, account_dir, self.clock.now())
+                }
+            }
+        };
+        config.add_account(name, metadata);
+        self.save_config(&mut config)
+    }
+
+    /// Brings `accounts.json` up to the current schema version, and adopts
+    /// the snapshot directories in the store it has lost track of. The old
+    /// file is kept as `.bak`, and `dry_run` writes nothing.
+    pub fn migrate(&self, dry_run: bool) -> Result<()> {
+        let _lock = (!dry_run).then(|| self.lock_state()).transpose()?;
+        let before = match self.accounts_file.exists() {
+            true => Some(
+                fs::read_to_string(&self.accounts_file)
+                    .with_context(|| tr!("failed-read-accounts-configuration-file"))?,
+            ),
+            false => None,
+        };
+        let from_version = match &before {
+            Some(contents) => config::stored_version(contents)?,
+            None => config::SCHEMA_VERSION,
+        };
+        let mut config = self.load_config()?;
+        let (adopted, skipped) = self.adopt_untracked(&mut config)?;
+
+        let changed = from_version != config::SCHEMA_VERSION || !adopted.is_empty();
+        let mut backup = None;
+        if changed && !dry_run {
+            if let Some(before) = &before {
+                let path = self.accounts_file.with_extension("json.bak");
+                fs::write(&path, before)
+                    .with_context(|| tr!("failed-write", path = path.display()))?;
+                backup = Some(path);
+            }
+            self.save_config(&mut config)?;
+        }
+
+        if self.format.is_json() {
+            return output::print_json(&MigrateJson {
+                from_version,
+                to_version: config::SCHEMA_VERSION,
+                adopted,
+                skipped,
+                backup,
+                dry_run,
+            });
+        }
+        match from_version == config::SCHEMA_VERSION {
+            true => println!("{}", tr!("migrate-current", version = from_version)),
+            false => println!(
+                "{}",
+                tr!(
+                    "migrate-schema",
+                    from = from_version,
+                    to = config::SCHEMA_VERSION
+                )
+            ),
+        }
+        if let Some(backup) = &backup {
+            self.say(tr!("upgrade-backup", path = backup.display()));
+        }
+        if dry_run && changed {
+            self.say(tr!("upgrade-dry-run"));
+        }
+        Ok(())
+    }
+
+    /// Moves the snapshots of an install from before the XDG layout
+    /// (`~/.claude-accounts` unless `from` is given) into the store and
+    /// registers them. Names already taken here are skipped and stay listed
+    /// in the old location; once nothing is left there it's removed.
+    pub fn migrate_legacy(&self, from: Option<&Path>) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let from = from.unwrap_or(&self.legacy_dir);
+        let legacy_file = from.join(LEGACY_ACCOUNTS_FILE);
+        if !legacy_file.is_file() {
+            anyhow::bail!(tr!("legacy-not-found", path = from.display()));
+        }
+
+        let mut legacy = AccountsConfig::load(&legacy_file)?;
+        let mut config = self.load_config()?;
+        let mut names: Vec<_> = legacy.accounts.keys().cloned().collect();
+        names.sort();
+
+        let mut migrated = 0;
+        for name in names {
+            if self
+                .import_name(&config, &name, Some(OnConflict::Skip))?
+                .is_none()
+            {
+                continue;
+            }
+
+            // The recorded path wins, but a copied or moved old directory
+            // still has each snapshot under its name
+            let meta = legacy.accounts[&name].clone();
+            let source = if meta.path.is_dir() {
+                meta.path.clone()
+            } else {
+                from.join(&name)
+            };
+            if !source.is_dir() {
+                eprintln!(
+                    "{}",
+                    tr!(
+                        "legacy-snapshot-missing",
+                        name = name,
+                        path = source.display()
+                    )
+                );
+                continue;
+            }
+
+            if legacy.current.as_deref() == Some(name.as_str()) && config.current.is_none() {
+                config.current = Some(name.clone());
+            }
+            if meta.external {
+                names::validate(&name)?;
+                config.add_account(name.clone(), meta);
+                self.save_config(&mut config)?;
+                self.record(EventKind::Import, &name, EventSource::Cli)?;
+                self.say(tr!("account-imported", name = name));
+            } else {
+                self.install_import(&mut config, &name, meta, EventSource::Cli, |dest| {
+                    self.move_dir(&source, dest)
+                })?;
+            }
+
+            legacy.remove_account(&name);
+            if legacy.current.as_deref() == Some(name.as_str()) {
+                legacy.current = None;
+            }
+            migrated += 1;
+        }
+
+        if legacy.is_empty() {
+            fs::remove_file(&legacy_file)
+                .with_context(|| tr!("failed-remove-old-accounts-file"))?;
+            // Anything else the user kept there is left alone
+            let _ = fs::remove_dir(from);
+        } else {
+            legacy.save(&legacy_file)?;
+        }
+
+        if migrated == 1 {
+            self.say(tr!("legacy-migrated-one", path = from.display()));
+        } else {
+            self.say(tr!(
+                "legacy-migrated-many",
+                count = migrated,
+                path = from.display()
+            ));
+        }
+        if !legacy.is_empty() {
+            self.say(tr!("legacy-left-behind", path = legacy_file.display()));
+        }
+        Ok(())
+    }
+
+    /// Brings in the accounts `tool` keeps (at `path`, or where it keeps
+    /// them by default), copying them into the store as `import` would. The
+    /// other tool's files are left as they are.
+    pub fn import_from(
+        &self,
+        tool: Tool,
+        path: Option<&Path>,
+        dry_run: bool,
+        on_conflict: Option<OnConflict>,
+    ) -> Result<()> {
+        let _lock = (!dry_run).then(|| self.lock_state()).transpose()?;
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => tool.default_path(
+                &paths::home_dir().with_context(|| tr!("failed-determine-home-directory"))?,
+            ),
+        };
+        if !path.exists() {
+            anyhow::bail!(tr!("import-from-not-found", path = path.display()));
+        }
+        let found = import_from::scan(tool, &path)?;
+        if found.is_empty() {
+            anyhow::bail!(tr!("import-from-none", path = path.display()));
+        }
+
+        let mut config = self.load_config()?;
+        // A clash stops the import before any of it is written
+        if on_conflict.is_none() {
+            for found in &found {
+                self.import_name(&config, &found.name, None)?;
+            }
+        }
+        let mut imported = 0;
+        for found in found {
+            let Some(name) = self.import_name(&config, &found.name, on_conflict)? else {
+                continue;
+            };
+            if dry_run {
+                self.say(tr!("import-from-would", name = name));
+                continue;
+            }
+            let now = self.clock.now();
+            let metadata = AccountMetadata {
+                saved_at: now.to_rfc3339(),
+                ..self.orphan_metadata(&name, self.switcher_dir.join(&name), now)
+            };
+            self.install_import(&mut config, &name, metadata, EventSource::Cli, |dest| {
+                fs::create_dir_all(dest).with_context(|| tr!("failed-create-account-directory"))?;
+                match &found.snapshot {
+                    Snapshot::Dir {
+                        claude_dir,
+                        claude_json,
+                    } => {
+                        self.timed_copy(claude_dir, dest, &self.capture, Codec::Plain)?;
+                        self.place_claude_json(dest, claude_json.as_deref())
+                    }
+                    Snapshot::Settings(settings) => {
+                        let contents = serde_json::to_string_pretty(settings)
+                            .with_context(|| tr!("failed-serialize-settings-json"))?;
+                        fs::write(dest.join(claude::SETTINGS_FILE), contents + "\n")
+                            .with_context(|| tr!("failed-write-settings-json"))
+                    }
+                }
+            })?;
+            imported += 1;
+        }
+        if dry_run {
+            self.say(tr!("upgrade-dry-run"));
+        } else {
+            self.say(tr!(
+                "import-from-done",
+                count = imported,
+                path = path.display()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Puts the `.claude.json` of an imported Claude directory, inside it at
+    /// `dest` or at `beside`, where a snapshot here keeps it.
+    fn place_claude_json(&self, dest: &Path, beside: Option<&Path>) -> Result<()> {
+        let inside = dest.join(template::CLAUDE_JSON);
+        let Some(source) = beside
+            .map(Path::to_path_buf)
+            .or_else(|| inside.is_file().then(|| inside.clone()))
+        else {
+            return Ok(());
+        };
+        // Beside the Claude directory unless Claude Code reads it from inside
+        let stored = self
+            .extra_paths
+            .iter()
+            .find(|path| path.live.file_name() == Some(template::CLAUDE_JSON.as_ref()))
+            .map(|path| dest.join(&path.stored))
+            .unwrap_or(inside.clone());
+        if stored == source {
+            return Ok(());
+        }
+        if let Some(parent) = stored.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| tr!("failed-create-directory", path = parent.display()))?;
+        }
+        match source == inside {
+            true => fs::rename(&source, &stored),
+            false => fs::copy(&source, &stored).map(|_| ()),
+        }
+        .with_context(|| tr!("failed-write-file", path = stored.display()))
+    }
+
+    /// Shows what a bundle contains without importing it.
+    pub fn inspect_bundle(&self, file: &Path, passphrase: Option<&str>) -> Result<()> {
+        let summary = archive::inspect(&self.read_bundle(file, passphrase)?)?;
+        let manifest = &summary.manifest;
+
+        self.say(tr!("inspect-account", name = manifest.name));
+        self.say(tr!("inspect-saved", time = short_time(&manifest.saved_at)));
+        self.say(tr!(
+            "inspect-created",
+            time = short_time(&manifest.created_at)
+        ));
+        match &manifest.expires_at {
+            Some(time) if manifest.is_expired(self.clock.now())? => {
+                self.say(tr!("inspect-expired", time = short_time(time)))
+            }
+            Some(time) => self.say(tr!("share-expires", time = short_time(time))),
+            None => self.say(tr!("inspect-never-expires")),
+        }
+        self.say(tr!(
+            "inspect-files",
+            files = summary.usage.files,
+            size = size::format_bytes(summary.usage.bytes)
+        ));
+
+        let Some(identity) = summary.identity else {
+            self.say(tr!("inspect-no-credentials"));
+            return Ok(());
+        };
+        if let Some(plan) = identity.plan {
+            self.say(tr!("inspect-plan", plan = plan));
+        }
+        if let Some(token) = identity.token {
+            self.say(tr!("inspect-token", token = token));
+        }
+        if let Some(expires_at) = identity.expires_at {
+            self.say(tr!(
+                "inspect-token-expires",
+                time = expires_at.format("%Y-%m-%dT%H:%M:%S")
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn grep_accounts(
+        &self,
+        pattern: &str,
+        account: Option<&str>,
+        ignore_case: bool,
+    ) -> Result<()> {
+        let config = self.load_config()?;
+
+        let mut accounts: Vec<_> = match account {
+            Some(name) => {
+                let meta = config
+                    .get_account(name)
+                    .ok_or_else(|| AccountError::NotFound(name.to_string()))?;
+                vec![(name, meta)]
+            }
+            None => config
+                .accounts
+                .iter()
+                .map(|(name, meta)| (name.as_str(), meta))
+                .collect(),
+        };
+        accounts.sort_by_key(|(name, _)| *name);
+
+        let codec = self.store_codec(false)?;
+        let mut total = 0;
+        let mut found_json = Vec::new();
+        for (name, meta) in accounts {
+            if !meta.path.exists() {
+                output::warn(tr!("grep-skip-missing", name = name));
+                continue;
+            }
+
+            let nested = nested_snapshots(&config, &meta.path);
+            let matches =
+                search::search_dir_with(&meta.path, pattern, ignore_case, &nested, |path| {
+                    codec.apply(fs::read(path)?)
+                })?;
+            for found in matches {
+                total += 1;
+                if self.format.is_json() {
+                    found_json.push(GrepJson {
+                        account: name,
+                        path: found.path,
+                        line_number: found.line_number,
+                        line: found.line,
+                    });
+                    continue;
+                }
+                println!(
+                    "{}: {}:{}: {}",
+                    name,
+                    found.path.display(),
+                    found.line_number,
+                    found.line
+                );
+            }
+        }
+
+        if self.format.is_json() {
+            return output::print_json(&found_json);
+        }
+        if total == 0 {
+            println!("{}", tr!("no-matches"));
+        }
+        Ok(())
+    }
+
+    /// Lists the accounts `found_accounts` finds.
+    pub fn find_accounts(&self, email: Option<&str>, text: Option<&str>) -> Result<()> {
+        let found = self.found_accounts(email, text)?;
+        if self.format.is_json() {
+            return output::print_json(&found);
+        }
+        if found.is_empty() {
+            println!("{}", tr!("no-matches"));
+        }
+        for account in &found {
+            println!("{}", self.format.found(account));
+        }
+        Ok(())
+    }
+
+    /// The accounts signed in with an email containing `email` and whose
+    /// settings files hold `text` (both ignoring case), whichever are given.
+    /// The email is the one recorded on save, or else read from the snapshot.
+    pub fn found_accounts(
+        &self,
+        email: Option<&str>,
+        text: Option<&str>,
+    ) -> Result<Vec<FoundJson>> {
+        let config = self.load_config()?;
+        let mut accounts: Vec<_> = config.accounts.iter().collect();
+        accounts.sort_by_key(|(name, _)| *name);
+        let email = email.map(str::to_lowercase);
+        let codec = self.store_codec(false)?;
+
+        let mut found = Vec::new();
+        for (name, meta) in accounts {
+            let saved = meta.identity.clone().unwrap_or_default();
+            let login = (saved.email.is_none() || saved.organization.is_none())
+                .then(|| self.account_login(name, &meta.path));
+            let from_login = |field: fn(&AccountLogin) -> &Option<String>| {
+                login.as_ref().and_then(|login| field(login).clone())
+            };
+            let found_email = saved.email.or_else(|| from_login(|login| &login.email));
+            let organization = saved
+                .organization
+                .or_else(|| from_login(|login| &login.organization));
+
+            if let Some(email) = &email
+                && !found_email
+                    .as_deref()
+                    .is_some_and(|found| found.to_lowercase().contains(email))
+            {
+                continue;
+            }
+            let matching_lines = match text {
+                Some(_) if !meta.path.exists() => {
+                    output::warn(tr!("grep-skip-missing", name = name));
+                    continue;
+                }
+                Some(text) => {
+                    let nested = nested_snapshots(&config, &meta.path);
+                    let matches =
+                        search::search_dir_with(&meta.path, text, true, &nested, |path| {
+                            codec.apply(fs::read(path)?)
+                        })?;
+                    if matches.is_empty() {
+                        continue;
+                    }
+                    Some(matches.len())
+                }
+                None => None,
+            };
+            found.push(FoundJson {
+                name: name.clone(),
+                email: found_email,
+                organization,
+                matching_lines,
+            });
+        }
+        Ok(found)
+    }
+
+    pub fn checksum_account(&self, name: &str, check: Option<&Path>, jobs: usize) -> Result<()> {
+        let config = self.load_config()?;
+        let account_meta = config
+            .get_account(name)
+            .ok_or_else(|| AccountError::NotFound(name.to_string()))?;
+
+        if !account_meta.path.exists() {
+            anyhow::bail!(tr!(
+                "account-dir-not-found",
+                path = account_meta.path.display()
+            ));
+        }
+
+        let Some(check) = check else {
+            let mut listed = Vec::new();
+            // Same layout as sha256sum, so the output can be fed back with --check
+            hashing::for_each_parallel(
+                store::walk_dir(&account_meta.path)
+                    .skip_dirs(&nested_snapshots(&config, &account_meta.path)),
+                jobs,
+                |entry| -> Result<_> {
+                    let entry = entry?;
+                    Ok((entry.hash()?, entry.relative_path))
+                },
+                |result| {
+                    let (sha256, path) = result?;
+                    match self.format.is_json() {
+                        true => listed.push(ChecksumJson { path, sha256 }),
+                        false => println!("{}  {}", sha256, path.display()),
+                    }
+                    Ok(())
+                },
+            )?;
+            return match self.format.is_json() {
+                true => output::print_json(&listed),
+                false => Ok(()),
+            };
+        };
+
+        let list = fs::read_to_string(check)
+            .with_context(|| tr!("failed-open-checksum-list", path = check.display()))?;
+        // A line that can't be read as one is a failure, not something to
+        // pass over: a list cut short or edited by hand mustn't pass
+        let mut expected = Vec::new();
+        let mut result = ChecksumCheckJson::default();
+        for (number, line) in list.lines().enumerate() {
+            if line.is_empty() {
+                continue;
+            }
+            match checksum_line(line) {
+                Some(entry) => expected.push(entry),
+                None => result.malformed.push(number + 1),
+            }
+        }
+        if expected.is_empty() && result.malformed.is_empty() {
+            anyhow::bail!(tr!("checksum-empty", path = check.display()));
+        }
+        if !self.format.is_json() {
+            for line in &result.malformed {
+                println!("{}", tr!("checksum-malformed", line = line));
+            }
+        }
+
+        hashing::for_each_parallel(
+            expected.into_iter(),
+            jobs,
+            |(hash, path)| {
+                let full = account_meta.path.join(&path);
+                let status = if !full.is_file() {
+                    "MISSING"
+                } else if hashing::hash_file(&full).is_ok_and(|actual| actual == hash) {
+                    "OK"
+                } else {
+                    "FAILED"
+                };
+                (path, status)
+            },
+            |(path, status)| {
+                if status == "OK" {
+                    result.ok += 1;
+                } else {
+                    if !self.format.is_json() {
+                        println!("{}: {}", path.display(), status);
+                    }
+                    result.failed.push(ChecksumFailureJson { path, status });
+                }
+                Ok(())
+            },
+        )?;
+
+        let failed = result.failed.len() + result.malformed.len();
+        match self.format.is_json() {
+            true => output::print_json(&result)?,
+            false => println!(
+                "{}",
+                tr!("checksum-summary", ok = result.ok, failed = failed)
+            ),
+        }
+        if failed > 0 {
+            anyhow::bail!(tr!("checksum-failed", failed = failed, name = name));
+        }
+        Ok(())
+    }
+
+    /// Checks the snapshots of the accounts `args` name (see `expand_names`),
+    /// of every account with `all`, or of the current one, against its
+    /// manifest: each file recorded when it was saved must still be there
+    /// with the same contents. With `repair`, a damaged snapshot of the
+    /// current account is saved again in full.
+    pub fn verify(&self, args: &[String], all: bool, repair: bool) -> Result<()> {
+        let _lock = repair.then(|| self.lock_state()).transpose()?;
+        let config = self.load_config()?;
+        let names = self.targets(&config, args, all)?;
+
+        // Asked for once up front, so a wrong passphrase fails here rather
+        // than passing for damage in every sealed file
+        if self.encryption_enabled() {
+            self.vault()?;
+        }
+
+        let mut damaged = 0;
+        let mut found = Vec::new();
+        for name in &names {
+            let account_meta = config
+                .get_account(name)
+                .ok_or_else(|| AccountError::NotFound(name.to_string()))?;
+            let Some(problems) = self.snapshot_problems(name, account_meta)? else {
+                continue;
+            };
+            if problems.is_empty() {
+                continue;
+            }
+            if !self.format.is_json() {
+                for problem in &problems {
+                    println!("{}", problem);
+                }
+            }
+            let mut repaired = false;
+            if !repair {
+                damaged += 1;
+            } else if config.current.as_deref() == Some(name.as_str()) {
+                // Without the manifest nothing is taken as unchanged
+                manifest::remove(&self.state_dir, name)?;
+                self.save_from(name, EventSource::Cli)?;
+                self.say(tr!("verify-repaired", name = name));
+                repaired = true;
+            } else {
+                damaged += 1;
+                self.say(tr!("verify-not-current", name = name));
+            }
+            found.push(VerifyJson {
+                name,
+                problems,
+                repaired,
+            });
+        }
+        if self.format.is_json() {
+            output::print_json(&found)?;
+        }
+
+        if damaged > 0 {
+            anyhow::bail!(tr!("verify-failed", count = damaged));
+        }
+        Ok(())
+    }
+
+    /// The accounts `args` name (see `expand_names`), every account with
+    /// `all`, or else the current one, for commands that check accounts.
+    fn targets(&self, config: &AccountsConfig, args: &[String], all: bool) -> Result<Vec<String>> {
+        Ok(match (all, args.is_empty()) {
+            (true, _) => config.accounts.keys().cloned().collect(),
+            (false, false) => self.expand_names(args)?,
+            (false, true) => vec![
+                config
+                    .current
+                    .clone()
+                    .ok_or_else(|| anyhow::anyhow!(tr!("verify-no-current")))?,
+            ],
+        })
+    }
+
+    /// Checks the logins of the accounts `targets` picks, asking the API
+    /// with `online` (see [`health`]), and fails when any of them can't be
+    /// used as it is.
+    pub fn check_logins(&self, args: &[String], all: bool, online: bool) -> Result<()> {
+        let pinger = online.then(health::ApiPinger::from_env).transpose()?;
+        let checks = self.login_checks(
+            args,
+            all,
+            pinger.as_ref().map(|pinger| pinger as &dyn health::Pinger),
+        )?;
+        let failed = checks.iter().filter(|check| !check.health.is_ok()).count();
+        match self.format.is_json() {
+            true => output::print_json(&checks)?,
+            false => {
+                for check in &checks {
+                    println!("{}", self.format.check(check));
+                }
+            }
+        }
+        if failed > 0 {
+            anyhow::bail!(tr!("check-failed", count = failed, total = checks.len()));
+        }
+        Ok(())
+    }
+
+    /// Lists the files in the store and the live configuration that hold
+    /// tokens or keys (see [`audit`]). Fails when any of them is flagged.
+    pub fn audit(&self) -> Result<()> {
+        let secrets = self.secrets()?;
+        let flagged = secrets
+            .iter()
+            .filter(|secret| !secret.flags.is_empty())
+            .count();
+        match self.format.is_json() {
+            true => output::print_json(&secrets)?,
+            false if secrets.is_empty() => println!("{}", tr!("audit-none")),
+            false => {
+                for line in self.format.secrets(&secrets) {
+                    println!("{}", line);
+                }
+            }
+        }
+        if flagged > 0 {
+            anyhow::bail!(tr!("audit-flagged", count = flagged, total = secrets.len()));
+        }
+        Ok(())
+    }
+
+    /// The files holding secrets, by path: in the store, then in the live
+    /// Claude directory and the paths kept outside it.
+    pub fn secrets(&self) -> Result<Vec<Secret>> {
+        let mut secrets = audit::scan(&self.switcher_dir, audit::Location::Store)?;
+        let mut live = audit::scan(&self.claude_config_dir, audit::Location::Live)?;
+        for path in &self.extra_paths {
+            live.extend(audit::scan(&path.live, audit::Location::Live)?);
+        }
+        secrets.sort_by(|a, b| a.path.cmp(&b.path));
+        live.sort_by(|a, b| a.path.cmp(&b.path));
+        secrets.extend(live);
+        Ok(secrets)
+    }
+
+    /// How each login looks, through `pinger` when given. The current
+    /// account's is read from the live configuration, whose token Claude Code
+    /// keeps refreshed; the others' from their snapshots.
+    pub fn login_checks(
+        &self,
+        args: &[String],
+        all: bool,
+        pinger: Option<&dyn health::Pinger>,
+    ) -> Result<Vec<CheckJson>> {
+        let config = self.load_config()?;
+        let mut names = self.targets(&config, args, all)?;
+        names.sort();
+        let now = self.clock.now();
+
+        let mut checks = Vec::new();
+        for name in names {
+            let meta = config
+                .get_account(&name)
+                .ok_or_else(|| AccountError::NotFound(name.clone()))?;
+            let credentials = match config.current.as_deref() == Some(name.as_str()) {
+                true => fs::read_to_string(self.claude_config_dir.join(CREDENTIALS_FILE)).ok(),
+                false => None,
+            };
+            let credentials = match credentials {
+                Some(credentials) => Some(credentials),
+                None => self.saved_credentials(&name, &meta.path)?,
+            };
+            let token = credentials.as_deref().and_then(claude::read_access_token);
+            let expires_at = credentials
+                .as_deref()
+                .and_then(claude::read_identity)
+                .and_then(|identity| identity.expires_at);
+
+            let (health, http_status, error) =
+                match health::check(token.as_deref(), expires_at, now, pinger) {
+                    Ok((health, status)) => (health, status, None),
+                    Err(e) => (health::Health::Unknown, None, Some(format!("{:#}", e))),
+                };
+            checks.push(CheckJson {
+                name,
+                health,
+                expires_at,
+                http_status,
+                error,
+            });
+        }
+        Ok(checks)
+    }
+
+    /// What's wrong with the snapshot of `name`, one line per file, or
+    /// `None` when there's nothing to check it against.
+    fn snapshot_problems(
+        &self,
+        name: &str,
+        account_meta: &AccountMetadata,
+    ) -> Result<Option<Vec<String>>> {
+        if platform::same_location(&self.claude_config_dir, &account_meta.path) {
+            self.say(tr!("verify-linked", name = name));
+            return Ok(None);
+        }
+        let Some(saved) = Manifest::load(&manifest::path(&self.state_dir, name))? else {
+            self.say(tr!("verify-no-manifest", name = name));
+            return Ok(None);
+        };
+
+        let mut problems = Vec::new();
+        for (path, file) in &saved.files {
+            let stored = account_meta.path.join(path);
+            if !stored.is_file() {
+                problems.push(tr!("verify-missing", name = name, path = path.display()));
+                continue;
+            }
+            // Anything that won't decrypt or decompress is as damaged as a
+            // file with the wrong contents
+            let matches = self.read_stored_bytes(&stored).is_ok_and(|data| {
+                data.is_some_and(|data| hashing::hash_bytes(&data) == file.sha256)
+            });
+            if !matches {
+                problems.push(tr!("verify-corrupt", name = name, path = path.display()));
+            }
+        }
+        if problems.is_empty() {
+            self.say(tr!("verify-ok", name = name, files = saved.files.len()));
+        }
+        Ok(Some(problems))
+    }
+
+    pub fn show_current(&self, verbose: bool) -> Result<()> {
+        let config = self.load_config()?;
+        let current = config
+            .current
+            .as_deref()
+            .and_then(|name| Some((name, config.get_account(name)?)));
+        let identity = current.and_then(|(_, meta)| meta.identity.as_ref());
+        let details = match current {
+            Some((name, meta)) if verbose => Some(self.current_details(&config, name, meta)?),
+            _ => None,
+        };
+        if self.format.is_json() {
+            return output::print_json(&CurrentJson {
+                current: config.current.as_deref(),
+                identity,
+                details,
+            });
+        }
+        match &config.current {
+            Some(name) => println!("{}", self.format.current(name, identity)),
+            None => println!("{}", tr!("no-active-account")),
+        }
+        for line in details
+            .iter()
+            .flat_map(|details| self.format.current_details(details, self.clock.now()))
+        {
+            println!("{}", line);
+        }
+        Ok(())
+    }
+
+    /// When the active account `name` was saved, what in the live
+    /// configuration drifted from it since and when its token expires.
+    fn current_details<'a>(
+        &self,
+        config: &AccountsConfig,
+        name: &str,
+        meta: &'a AccountMetadata,
+    ) -> Result<CurrentDetails<'a>> {
+        let changes = self.unsaved_changes(config, name)?;
+        // Saved by an earlier version, there's no manifest to compare with
+        // and modification times are all there is to go by
+        let drifted = match manifest::path(&self.state_dir, name).exists() {
+            true => !changes.is_empty(),
+            false => self.live_changed_since(meta)?,
+        };
+        let expires_at = self.live_expiry();
+        let now = self.clock.now();
+        Ok(CurrentDetails {
+            saved_at: &meta.saved_at,
+            last_used: meta.last_used.as_deref(),
+            drifted,
+            changes: changes.into_iter().collect(),
+            expires_at,
+            expires_in: expires_at.map(|expires_at| (expires_at - now).num_seconds()),
+        })
+    }
+
+    /// When the live login expires, if it says.
+    fn live_expiry(&self) -> Option<DateTime<Utc>> {
+        fs::read_to_string(self.claude_config_dir.join(CREDENTIALS_FILE))
+            .ok()
+            .and_then(|credentials| claude::read_identity(&credentials))
+            .and_then(|identity| identity.expires_at)
+    }
+
+    /// For the daemon: reports the active account as `expiring` once its
+    /// login expires within `notify.expiring-within`. `reported` is the
+    /// account and expiry last reported, so each login is reported once
+    /// rather than on every check; a renewed one has a new expiry.
+    pub fn check_expiry(&self, reported: &mut Option<(String, DateTime<Utc>)>) -> Result<()> {
+        let within = self.settings.notify.expiring_within()?;
+        let config = self.load_config()?;
+        let (Some(name), Some(expires_at)) = (config.current, self.live_expiry()) else {
+            return Ok(());
+        };
+        let left = expires_at - self.clock.now();
+        let login = Some((name.clone(), expires_at));
+        if left > within || *reported == login {
+            return Ok(());
+        }
+        let message = match left > chrono::Duration::zero() {
+            true => tr!("login-expiring", name = name, left = format_countdown(left)),
+            false => tr!("login-expired", name = name),
+        };
+        self.notify(
+            NotifyEvent::Expiring,
+            &name,
+            None,
+            Some(expires_at),
+            EventSource::Daemon,
+            &message,
+        );
+        *reported = login;
+        Ok(())
+    }
+
+    /// Reports the active account's plan, how long its token has left and
+    /// whether the live configuration has unsaved changes. Prints nothing
+    /// when no account is active or, with `stale_only`, when nothing needs
+    /// attention, so a status bar segment can simply disappear.
+    pub fn status(&self, porcelain: bool, stale_only: bool) -> Result<()> {
+        let config = self.load_config()?;
+        let status = self.account_status(&config)?;
+        // Status bars poll these, so they stay cheap and quiet
+        if porcelain || stale_only {
+            let Some(status) = status else {
+                return Ok(());
+            };
+            if stale_only && !status.stale {
+                return Ok(());
+            }
+            if self.format.is_json() {
+                return output::print_json(&status);
+            }
+            match porcelain {
+                true => println!("{}", status.porcelain()),
+                false => {
+                    for line in self.format.status(&status) {
+                        println!("{}", line);
+                    }
+                }
+            }
+            return Ok(());
+        }
+
+        let overview = Overview {
+            status,
+            account_count: config.accounts.len(),
+            store_bytes: size::disk_usage(&self.switcher_dir)?.bytes,
+            locked_by: lock::held_by(&self.state_dir.join(lock::FILE)),
+            sync: self.sync_pending(&config)?,
+        };
+        match self.format.is_json() {
+            true => output::print_json(&overview)?,
+            false => {
+                for line in self.format.overview(&overview) {
+                    println!("{}", line);
+                }
+            }
+        }
+        self.remind_stale(&config, config.current.as_deref())
+    }
+
+    /// The active account's plan, token expiry and whether it has unsaved
+    /// changes; `None` without an active account.
+    fn account_status(&self, config: &AccountsConfig) -> Result<Option<Status>> {
+        let Some(name) = config.current.as_deref() else {
+            return Ok(None);
+        };
+        let meta = config.get_account(name);
+        let now = self.clock.now();
+
+        let identity = fs::read_to_string(self.claude_config_dir.join(CREDENTIALS_FILE))
+            .ok()
+            .and_then(|credentials| claude::read_identity(&credentials));
+        let plan = identity
+            .as_ref()
+            .and_then(|identity| identity.plan.clone())
+            .or_else(|| meta.and_then(|meta| meta.identity.as_ref()?.plan.clone()));
+        let expires_at = identity.and_then(|identity| identity.expires_at);
+        let expires_in = expires_at.map(|expires_at| (expires_at - now).num_seconds());
+        let dirty = match meta {
+            Some(meta) => self.live_changed_since(meta)?,
+            None => false,
+        };
+
+        Ok(Some(Status {
+            account: name.to_string(),
+            plan,
+            expires_at,
+            expires_in,
+            dirty,
+            stale: dirty || expires_in.is_some_and(|seconds| seconds < STALE_WITHIN_SECS),
+        }))
+    }
+
+    /// What `sync push` and `sync pull` have to do, against the clone as it
+    /// was last fetched; nothing is fetched. `None` when sync isn't set up.
+    fn sync_pending(&self, config: &AccountsConfig) -> Result<Option<SyncPending>> {
+        let repo = self.state_dir.join(sync::DIR);
+        let Some(state) = SyncState::load(&self.state_dir.join(sync::STATE_FILE))? else {
+            return Ok(None);
+        };
+        if !repo.is_dir() {
+            return Ok(None);
+        }
+        let remote = SyncedAccounts::load(&repo)?;
+        let changes = sync::plan(&synced_saves(config), &remote.saved_at(), &state.synced);
+        let to_push = changes
+            .iter()
+            .filter(|change| {
+                matches!(
+                    change,
+                    sync::Action::Upload(_) | sync::Action::RemoveRemote(_)
+                )
+            })
+            .count();
+        Ok(Some(SyncPending {
+            remote: state.remote,
+            to_push,
+            to_pull: changes.len() - to_push,
+        }))
+    }
+
+    /// Whether a captured file in the live configuration was modified after
+    /// the account was last saved or switched to. Modification times make it
+    /// cheap enough to poll from a status bar.
+    fn live_changed_since(&self, meta: &AccountMetadata) -> Result<bool> {
+        if platform::same_location(&self.claude_config_dir, &meta.path) {
+            // Linked: the live directory is the snapshot
+            return Ok(false);
+        }
+
+        let since = [Some(meta.saved_at.as_str()), meta.last_used.as_deref()]
+            .into_iter()
+            .flatten()
+            .filter_map(|time| DateTime::parse_from_rfc3339(time).ok())
+            .max();
+        let Some(since) = since else {
+            return Ok(false);
+        };
+        self.changed_after(&self.claude_config_dir, Path::new(""), since.into())
+    }
+
+    fn changed_after(&self, dir: &Path, relative: &Path, since: SystemTime) -> Result<bool> {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return Ok(false);
+        };
+        for entry in entries {
+            let entry = entry?;
+            let relative = relative.join(entry.file_name());
+            let file_type = entry.file_type()?;
+            let changed = if file_type.is_dir() {
+                !self.capture.skips_dir(&relative)
+                    && self.changed_after(&entry.path(), &relative, since)?
+            } else {
+                file_type.is_file()
+                    && self.capture.captures(&relative)
+                    && relative != Path::new(banner::FILE)
+                    && entry.metadata()?.modified()? > since
+            };
+            if changed {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Compares the live configuration with `name`'s snapshot (the active
+    /// account's by default) and lists the files added, removed or modified
+    /// since it was saved. With `content`, JSON files get a unified diff;
+    /// credential files never do. Returns whether the two are the same.
+    /// Copies single files out of the snapshot of `name`, into the live
+    /// configuration or to `to`, without switching. `to` is taken the way
+    /// cp(1) takes its target: a directory the files go into (keeping their
+    /// relative paths), or for a single file, the path to write it to.
+    pub fn restore_files(&self, name: &str, files: &[PathBuf], to: Option<&Path>) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let name = &self.resolve_target(name)?;
+        let config = self.load_config()?;
+        let meta = config
+            .get_account(name)
+            .ok_or_else(|| AccountError::NotFound(name.to_string()))?;
+
+        files
+            .iter()
+            .try_for_each(|file| ensure_settings_file(file))?;
+
+        let live = to.is_none();
+        let target = to.unwrap_or(&self.claude_config_dir);
+        let into_dir = live || files.len() > 1 || target.is_dir();
+        let nested = nested_snapshots(&config, &meta.path);
+        for file in files {
+            // Another account's snapshot isn't part of this one
+            let stored = match nested.iter().any(|nested| file.starts_with(nested)) {
+                true => None,
+                false => self.read_stored_bytes(&meta.path.join(file))?,
+            };
+            let Some(mut data) = stored else {
+                anyhow::bail!(tr!("restore-missing", path = file.display(), name = name));
+            };
+            // A snapshot the live directory links to still has the banner
+            if file == Path::new(banner::FILE)
+                && let Ok(contents) = std::str::from_utf8(&data)
+            {
+                data = banner::strip(contents).into_bytes();
+            }
+
+            let dest = match into_dir {
+                true => target.join(file),
+                false => target.to_path_buf(),
+            };
+            if let Some(parent) = dest.parent()
+                && !parent.as_os_str().is_empty()
+            {
+                fs::create_dir_all(parent)
+                    .with_context(|| tr!("failed-create", path = parent.display()))?;
+            }
+            fs::write(&dest, data)
+                .with_context(|| tr!("failed-write-file", path = dest.display()))?;
+            if live
+                && file == Path::new(banner::FILE)
+                && let (Some(template), Some(current)) = (&self.settings.banner, &config.current)
+            {
+                self.write_banner(&banner::render(template, current))?;
+            }
+            self.say(tr!(
+                "restore-file",
+                path = file.display(),
+                name = name,
+                dest = dest.display()
+            ));
+        }
+        if live && let Some(current) = &config.current {
+            self.say(tr!("template-applied-save", account = current));
+        }
+        Ok(())
+    }
+
+    /// The snapshot of `name` and the path within it `path` names, for
+    /// looking inside without switching.
+    fn snapshot_path(&self, name: &str, path: &Path) -> Result<(String, PathBuf)> {
+        let name = self.resolve_target(name)?;
+        let config = self.load_config()?;
+        let meta = config
+            .get_account(&name)
+            .ok_or_else(|| AccountError::NotFound(name.clone()))?;
+        if meta.archive.is_some() {
+            anyhow::bail!(tr!("snapshot-archived", name = name));
+        }
+        if !path
+            .components()
+            .all(|component| matches!(component, std::path::Component::Normal(_)))
+        {
+            anyhow::bail!(tr!("file-bad-path", path = path.display()));
+        }
+        Ok((name, meta.path.join(path)))
+    }
+
+    /// Prints the file at `path` in the snapshot of `name`, decrypted and
+    /// with JSON pretty-printed. Secrets are masked unless `reveal`.
+    pub fn cat_snapshot(&self, name: &str, path: &Path, reveal: bool) -> Result<()> {
+        let (name, file) = self.snapshot_path(name, path)?;
+        let data = match self.read_stored_bytes(&file)? {
+            Some(data) => data,
+            // Kept in the keychain rather than the snapshot
+            None if path == Path::new(CREDENTIALS_FILE) && self.uses_keychain() => self
+                .credstore
+                .load(&name)?
+                .map(String::into_bytes)
+                .ok_or_else(|| {
+                    anyhow::anyhow!(tr!("restore-missing", path = path.display(), name = name))
+                })?,
+            None => anyhow::bail!(tr!("restore-missing", path = path.display(), name = name)),
+        };
+        io::stdout()
+            .write_all(&redact::show(path, data, reveal))
+            .context("Failed to write output")
+    }
+
+    /// Lists the directory `subdir` (its root by default) of the snapshot
+    /// of `name`, directories first.
+    pub fn list_snapshot(&self, name: &str, subdir: Option<&Path>) -> Result<()> {
+        let (name, dir) = self.snapshot_path(name, subdir.unwrap_or(Path::new("")))?;
+        if !dir.is_dir() {
+            anyhow::bail!(tr!(
+                "snapshot-not-dir",
+                path = subdir.unwrap_or(Path::new(".")).display(),
+                name = name
+            ));
+        }
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&dir)
+            .with_context(|| tr!("failed-read-directory", path = dir.display()))?
+        {
+            let entry =
+                entry.with_context(|| tr!("failed-read-directory", path = dir.display()))?;
+            let file_type = entry.file_type()?;
+            let (kind, size) = if file_type.is_symlink() {
+                (EntryKind::Symlink, None)
+            } else if file_type.is_dir() {
+                (EntryKind::Dir, None)
+            } else {
+                (EntryKind::File, Some(entry.metadata()?.len()))
+            };
+            entries.push(SnapshotEntryJson {
+                name: entry.file_name().to_string_lossy().into_owned(),
+                kind,
+                size,
+            });
+        }
+        entries.sort_by(|a, b| {
+            (a.kind != EntryKind::Dir, &a.name).cmp(&(b.kind != EntryKind::Dir, &b.name))
+        });
+        if self.format.is_json() {
+            return output::print_json(&entries);
+        }
+        for entry in &entries {
+            println!("{}", self.format.snapshot_entry(entry));
+        }
+        Ok(())
+    }
+
+    /// Copies the files `only` from the snapshot of `source` into that of
+    /// `dest`, asking before replacing one that differs, and with `live` (or
+    /// when `dest` is active, which its next save would otherwise undo) into
+    /// the live configuration too. A dry run says what would be copied.
+    pub fn merge_files(
+        &self,
+        source: &str,
+        dest: &str,
+        only: &[PathBuf],
+        live: bool,
+        dry_run: bool,
+    ) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let source = &self.resolve_target(source)?;
+        let dest = &self.resolve_target(dest)?;
+        if source == dest {
+            anyhow::bail!(tr!("merge-same-account", name = source));
+        }
+        let mut config = self.load_config()?;
+        let meta = |name: &str| {
+            config
+                .get_account(name)
+                .ok_or_else(|| AccountError::NotFound(name.to_string()))
+        };
+        let source_dir = meta(source)?.path.clone();
+        let dest_meta = meta(dest)?;
+        if dest_meta.locked && !self.force {
+            return Err(AccountError::Locked(dest.to_string()).into());
+        }
+        let dest_dir = dest_meta.path.clone();
+        let dest_saved_at = dest_meta.saved_at.clone();
+        // Kept out of the data directory, as a save keeps them
+        let mut keep_version = !dest_meta.external;
+        only.iter()
+            .try_for_each(|file| ensure_settings_file(file))?;
+        let live = live || config.current.as_deref() == Some(dest.as_str());
+
+        let codec = self.store_codec(true)?;
+        let mut merged = Vec::new();
+        for file in only {
+            let Some(data) = self.read_stored_bytes(&source_dir.join(file))? else {
+                anyhow::bail!(tr!("restore-missing", path = file.display(), name = source));
+            };
+            let path = file.display();
+            let target = dest_dir.join(file);
+            let replacing = match self.read_stored_bytes(&target)? {
+                Some(existing) if existing == data => {
+                    self.say(tr!("merge-same", path = path, name = dest));
+                    continue;
+                }
+                existing => existing.is_some(),
+            };
+            if dry_run {
+                self.say(match replacing {
+                    true => tr!("merge-would-replace", path = path, name = dest),
+                    false => tr!("merge-would-copy", path = path, name = dest),
+                });
+                continue;
+            }
+            let question = tr!("merge-confirm", path = path, dest = dest, source = source);
+            if replacing && !self.confirm(&question, false)? {
+                self.say(tr!("merge-kept", path = path, name = dest));
+                continue;
+            }
+
+            if std::mem::take(&mut keep_version) {
+                let nested = nested_snapshots(&config, &dest_dir);
+                self.keep_version_copy(dest, &dest_dir, &dest_saved_at, &nested)?;
+            }
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| tr!("failed-create-account-directory"))?;
+            }
+            // Shared by `dedup`, the old file is unlinked rather than written
+            // through
+            if target.is_file() {
+                platform::make_writable(&target)?;
+                retry(|| fs::remove_file(&target))?;
+            }
+            let stored = codec.apply(data.clone())?;
+            retry(|| fs::write(&target, &stored))
+                .with_context(|| tr!("failed-write-file", path = target.display()))?;
+            if live {
+                let live_path = self.claude_config_dir.join(file);
+                if let Some(parent) = live_path.parent() {
+                    fs::create_dir_all(parent)
+                        .with_context(|| tr!("failed-create-claude-config-directory"))?;
+                }
+                fs::write(&live_path, &data)
+                    .with_context(|| tr!("failed-write-file", path = live_path.display()))?;
+            }
+            self.say(tr!(
+                "merge-copied",
+                path = path,
+                source = source,
+                dest = dest
+            ));
+            merged.push(file);
+        }
+        if merged.is_empty() {
+            return Ok(());
+        }
+
+        if live
+            && merged.iter().any(|file| *file == Path::new(banner::FILE))
+            && let (Some(template), Some(current)) = (&self.settings.banner, &config.current)
+        {
+            self.write_banner(&banner::render(template, current))?;
+        }
+        // A newer save, for `sync` to send
+        if let Some(meta) = config.accounts.get_mut(dest) {
+            meta.saved_at = self.clock.now().to_rfc3339();
+        }
+        self.save_config(&mut config)?;
+        if config.current.as_deref() != Some(dest.as_str()) {
+            // Recorded again when it's next switched to
+            manifest::remove(&self.state_dir, dest)?;
+        }
+        if live {
+            self.say(tr!("merge-live", path = self.claude_config_dir.display()));
+        }
+        Ok(())
+    }
+
+    pub fn diff_live(&self, name: Option<&str>, content: bool) -> Result<bool> {
+        let config = self.load_config()?;
+        let name = match name.or(config.current.as_deref()) {
+            Some(name) => name,
+            None => anyhow::bail!(tr!("diff-no-current")),
+        };
+        let meta = config
+            .get_account(name)
+            .ok_or_else(|| AccountError::NotFound(name.to_string()))?;
+
+        // Linked, the live directory is the snapshot
+        let changes = if platform::same_location(&self.claude_config_dir, &meta.path) {
+            Vec::new()
+        } else {
+            self.live_changes(name, &meta.path, content)?
+        };
+        let json = DiffJson {
+            account: name,
+            other: None,
+            changes: &changes,
+        };
+        self.print_changes(
+            &json,
+            tr!("diff-none", name = name),
+            tr!("diff-summary", count = changes.len(), name = name),
+        )
+    }
+
+    /// Compares the snapshots of two accounts, as `diff_live` compares the
+    /// live configuration with one: files only `other` has are added. JSON
+    /// files are compared by what they hold, so formatting and key order
+    /// don't count, and their diffs are of the pretty-printed documents.
+    pub fn diff_accounts(&self, name: &str, other: &str, content: bool) -> Result<bool> {
+        let name = &self.resolve_target(name)?;
+        let other = &self.resolve_target(other)?;
+        let changes = self.account_changes(name, other, content)?;
+
+        let json = DiffJson {
+            account: name,
+            other: Some(other),
+            changes: &changes,
+        };
+        self.print_changes(
+            &json,
+            tr!("diff-accounts-none", name = name, other = other),
+            tr!(
+                "diff-accounts-summary",
+                count = changes.len(),
+                name = name,
+                other = other
+            ),
+        )
+    }
+
+    fn account_changes(&self, name: &str, other: &str, content: bool) -> Result<Vec<FileChange>> {
+        let config = self.load_config()?;
+        let meta = |name: &str| {
+            config
+                .get_account(name)
+                .ok_or_else(|| AccountError::NotFound(name.to_string()))
+        };
+        let (old_dir, new_dir) = (&meta(name)?.path, &meta(other)?.path);
+        let (old_files, old_login) = self.stored_files(name, old_dir)?;
+        let (new_files, new_login) = self.stored_files(other, new_dir)?;
+
+        let mut changes = Vec::new();
+        for path in old_files.union(&new_files) {
+            let old_data = match old_files.contains(path) {
+                true => self.read_stored_file(old_dir, old_login.as_deref(), path)?,
+                false => None,
+            };
+            let new_data = match new_files.contains(path) {
+                true => self.read_stored_file(new_dir, new_login.as_deref(), path)?,
+                false => None,
+            };
+            let is_json = path
+                .extension()
+                .is_some_and(|extension| extension == "json");
+            // Pretty-printed, a JSON document's keys come out sorted
+            let text = |data: &Option<Vec<u8>>| {
+                let data = data.as_deref()?;
+                Some(
+                    serde_json::from_slice::<serde_json::Value>(data)
+                        .ok()
+                        .filter(|_| is_json)
+                        .and_then(|value| serde_json::to_string_pretty(&value).ok())
+                        .unwrap_or_else(|| String::from_utf8_lossy(data).into_owned()),
+                )
+            };
+            let (old_text, new_text) = (text(&old_data), text(&new_data));
+            let change = match (&old_data, &new_data) {
+                (None, Some(_)) => Change::Added,
+                (Some(_), None) => Change::Removed,
+                (Some(old), Some(new)) if old != new && (!is_json || old_text != new_text) => {
+                    Change::Modified
+                }
+                _ => continue,
+            };
+
+            let diff = (content && !claude::is_credential_file(path)).then(|| {
+                diff::unified(
+                    old_text.as_deref().unwrap_or_default(),
+                    new_text.as_deref().unwrap_or_default(),
+                    &format!("{}/{}", name, path.display()),
+                    &format!("{}/{}", other, path.display()),
+                )
+            });
+            changes.push(FileChange {
+                path: path.clone(),
+                change,
+                diff,
+            });
+        }
+        Ok(changes)
+    }
+
+    /// Prints what `diff` found, returning whether nothing differs.
+    fn print_changes(&self, json: &DiffJson, none: String, summary: String) -> Result<bool> {
+        let same = json.changes.is_empty();
+        if self.format.is_json() {
+            output::print_json(json)?;
+            return Ok(same);
+        }
+        if same {
+            println!("{}", none);
+            return Ok(true);
+        }
+        for change in json.changes {
+            println!("{}", self.format.file_change(change));
+            if let Some(diff) = &change.diff {
+                print!("{}", diff);
+            }
+        }
+        println!("{}", summary);
+        Ok(false)
+    }
+
+    /// The captured files of the snapshot of `name`, with the login kept for
+    /// it in the keychain, which then counts as one of them.
+    fn stored_files(&self, name: &str, dir: &Path) -> Result<(BTreeSet<PathBuf>, Option<String>)> {
+        let mut files = self.captured_files(dir)?;
+        let nested = nested_snapshots(&self.load_config()?, dir);
+        files.retain(|file| !nested.iter().any(|nested| file.starts_with(nested)));
+        let keychain_credentials = if self.uses_keychain() {
+            self.credstore.load(name)?
+        } else {
+            None
+        };
+        if keychain_credentials.is_some() {
+            files.insert(PathBuf::from(CREDENTIALS_FILE));
+        }
+        Ok((files, keychain_credentials))
+    }
+
+    /// A file of the snapshot in `dir`, as `stored_files` lists them.
+    fn read_stored_file(
+        &self,
+        dir: &Path,
+        keychain_credentials: Option<&str>,
+        path: &Path,
+    ) -> Result<Option<Vec<u8>>> {
+        match keychain_credentials {
+            Some(credentials) if path == Path::new(CREDENTIALS_FILE) => {
+                Ok(Some(credentials.as_bytes().to_vec()))
+            }
+            _ => self.read_stored_bytes(&dir.join(path)),
+        }
+    }
+
+    fn live_changes(
+        &self,
+        name: &str,
+        account_dir: &Path,
+        content: bool,
+    ) -> Result<Vec<FileChange>> {
+        let live = self.captured_files(&self.claude_config_dir)?;
+        // Kept in the keychain, the saved credentials aren't in the snapshot
+        let (saved, keychain_credentials) = self.stored_files(name, account_dir)?;
+
+        let mut changes = Vec::new();
+        for path in live.union(&saved) {
+            let live_data = match live.contains(path) {
+                true => self.read_live(path)?,
+                false => None,
+            };
+            let saved_data = match saved.contains(path) {
+                true => {
+                    self.read_stored_file(account_dir, keychain_credentials.as_deref(), path)?
+                }
+                false => None,
+            };
+            let change = match (&live_data, &saved_data) {
+                (Some(_), None) => Change::Added,
+                (None, Some(_)) => Change::Removed,
+                (Some(live), Some(saved)) if live != saved => Change::Modified,
+                _ => continue,
+            };
+
+            let is_json = path
+                .extension()
+                .is_some_and(|extension| extension == "json");
+            let diff = (content && is_json && !claude::is_credential_file(path)).then(|| {
+                let text = |data: &Option<Vec<u8>>| {
+                    data.as_deref()
+                        .map(|data| String::from_utf8_lossy(data).into_owned())
+                        .unwrap_or_default()
+                };
+                diff::unified(
+                    &text(&saved_data),
+                    &text(&live_data),
+                    &format!("{}/{}", name, path.display()),
+                    &format!("live/{}", path.display()),
+                )
+            });
+            changes.push(FileChange {
+                path: path.clone(),
+                change,
+                diff,
+            });
+        }
+        Ok(changes)
+    }
+
+    /// Shows who an account is signed in as and where its snapshot lives.
+    /// Accounts saved before identities were recorded are read from the
+    /// snapshot instead.
+    pub fn show_info(&self, name: &str) -> Result<()> {
+        let name = &self.resolve_target(name)?;
+        let config = self.load_config()?;
+        let meta = config
+            .get_account(name)
+            .ok_or_else(|| AccountError::NotFound(name.to_string()))?;
+        let login = self.account_login(name, &meta.path);
+        let current = config.current.as_deref() == Some(name.as_str());
+        // The live token is the one Claude Code refreshes
+        let live_login = current
+            .then(|| fs::read_to_string(self.claude_config_dir.join(CREDENTIALS_FILE)).ok())
+            .flatten()
+            .and_then(|credentials| claude::read_identity(&credentials));
+        let token_expires_at = live_login
+            .or(login.identity.clone())
+            .and_then(|identity| identity.expires_at);
+        let identity = meta.identity.clone().or_else(|| login.to_identity());
+        let usage = size::dir_usage(&meta.path).unwrap_or_default();
+        let unsaved_changes = match current {
+            true => Some(self.unsaved_changes(&config, name)?.len()),
+            false => None,
+        };
+
+        let info = AccountInfo {
+            name,
+            current,
+            identity,
+            saved_at: &meta.saved_at,
+            last_used: meta.last_used.as_deref(),
+            switch_count: meta.switch_count,
+            path: &meta.path,
+            protected: meta.protected,
+            locked: meta.locked,
+            external: meta.external,
+            tags: meta.tags.iter().map(String::as_str).collect(),
+            aliases: config.aliases_of(name),
+            note: meta.note.as_deref(),
+            files: usage.files,
+            size_bytes: usage.bytes,
+            token_expires_at,
+            unsaved_changes,
+            limited_until: meta.limited_until.as_deref(),
+            claude_version: meta.claude_version.as_deref(),
+            pinned_version: meta.pinned_version.as_deref(),
+        };
+        if self.format.is_json() {
+            return output::print_json(&info);
+        }
+        for line in self.format.account_info(&info, self.clock.now()) {
+            println!("{}", line);
+        }
+        Ok(())
+    }
+
+    /// Whether an account is saved as `name`, for `exists`.
+    pub fn account_exists(&self, name: &str) -> Result<bool> {
+        Ok(self.load_config()?.accounts.contains_key(name))
+    }
+
+    /// Whether `name` is the active account, for `is-current`.
+    pub fn is_current(&self, name: &str) -> Result<bool> {
+        Ok(self.load_config()?.current.as_deref() == Some(name))
+    }
+
+    pub fn show_current_if_any(&self) -> Result<()> {
+        if self.format.is_json() {
+            return self.show_current(false);
+        }
+        let config = self.load_config()?;
+        if let Some(name) = config.current {
+            println!("{}", name);
+        }
+        Ok(())
+    }
+
+    /// Checks the live configuration and the account store, printing each
+    /// finding with a fix. With `fix` the safe repairs are made. Returns
+    /// whether everything is healthy once that's done.
+    pub fn doctor(&self, fix: bool) -> Result<bool> {
+        let findings = self.doctor_findings(fix)?;
+        let outstanding = findings
+            .iter()
+            .filter(|finding| finding.is_outstanding())
+            .count();
+        if self.format.is_json() {
+            output::print_json(&findings)?;
+        } else {
+            for finding in &findings {
+                for line in self.format.finding(finding) {
+                    println!("{}", line);
+                }
+            }
+            if outstanding > 0 {
+                self.say(tr!("doctor-outstanding", count = outstanding));
+            } else {
+                self.say(tr!("doctor-healthy"));
+            }
+        }
+        Ok(outstanding == 0)
+    }
+
+    /// Writes a redacted `.tar.zst` for a bug report to `output`, or to the
+    /// current directory: the crash reports, history, accounts.json,
+    /// settings, `doctor` findings and what this machine is. See
+    /// [`debug_bundle`] for what's masked.
+    pub fn debug_bundle(&self, output: Option<&Path>) -> Result<()> {
+        let now = self.clock.now();
+        let path = match output {
+            Some(path) => path.to_path_buf(),
+            None => debug_bundle::default_path(
+                &std::env::current_dir()
+                    .with_context(|| tr!("failed-determine-current-directory"))?,
+                now,
+            ),
+        };
+        let home = paths::home_dir();
+        let home = home.as_deref();
+
+        let mut entries = vec![(
+            PathBuf::from("system.json"),
+            serde_json::to_vec_pretty(&debug_bundle::SystemInfo::collect(now))?,
+        )];
+        let doctor = serde_json::to_string_pretty(&self.doctor_findings(false)?)?;
+        entries.push((
+            PathBuf::from("doctor.json"),
+            debug_bundle::scrub(&doctor, home).into_bytes(),
+        ));
+        let crashes = self.state_dir.join("crashes");
+        let mut files = vec![
+            self.accounts_file.clone(),
+            self.history_file(),
+            self.settings_file.clone(),
+            self.state_dir.join(capabilities::FILE),
+        ];
+        if let Ok(reports) = fs::read_dir(&crashes) {
+            let mut reports: Vec<_> = reports.flatten().map(|entry| entry.path()).collect();
+            reports.sort();
+            files.extend(reports);
+        }
+        for file in files {
+            let Ok(contents) = fs::read(&file) else {
+                continue;
+            };
+            let name = match file.strip_prefix(&crashes) {
+                Ok(report) => Path::new("crashes").join(report),
+                Err(_) => PathBuf::from(file.file_name().unwrap_or_default()),
+            };
+            let contents = debug_bundle::anonymize(&name, contents, home);
+            entries.push((name, contents));
+        }
+
+        debug_bundle::write(&path, &entries)?;
+        info!(path = %path.display(), files = entries.len(), "write debug bundle");
+        if self.format.is_json() {
+            return output::print_json(&DebugBundleJson {
+                files: entries.into_iter().map(|(name, _)| name).collect(),
+                path,
+            });
+        }
+        self.say(tr!("debug-bundle-written", path = path.display()));
+        self.say(tr!("debug-bundle-review"));
+        Ok(())
+    }
+
+    /// What `doctor` finds, making the safe repairs with `fix`.
+    fn doctor_findings(&self, fix: bool) -> Result<Vec<Finding>> {
+        let _lock = fix.then(|| self.lock_state()).transpose()?;
+        let mut findings = Vec::new();
+        let live = &self.claude_config_dir;
+
+        findings.push(match fs::read_dir(live) {
+            Ok(_) => Finding::ok(tr!("doctor-live-ok", path = live.display())),
+            Err(_) if live.is_symlink() => {
+                Finding::problem(tr!("doctor-live-dangling", path = live.display()))
+                    .with_fix(tr!("doctor-live-dangling-fix"))
+            }
+            Err(_) if !live.exists() => {
+                Finding::warning(tr!("doctor-live-missing", path = live.display()))
+                    .with_fix(tr!("doctor-live-missing-fix"))
+            }
+            Err(e) => Finding::problem(tr!(
+                "doctor-live-unreadable",
+                path = live.display(),
+                error = e
+            ))
+            .with_fix(tr!("doctor-live-unreadable-fix")),
+        });
+
+        let (mut config, config_valid) = match self.load_config() {
+            Ok(config) => {
+                findings.push(Finding::ok(tr!("doctor-config-ok")));
+                (config, true)
+            }
+            Err(e) => {
+                let aside = self.accounts_file.with_extension("json.broken");
+                let mut finding = Finding::problem(tr!(
+                    "doctor-config-invalid",
+                    path = self.accounts_file.display(),
+                    error = format!("{:#}", e)
+                ))
+                .with_fix(tr!("doctor-config-invalid-fix", path = aside.display()));
+                // The snapshots are untouched, so the orphan check below
+                // registers them again
+                if fix {
+                    retry(|| fs::rename(&self.accounts_file, &aside))
+                        .with_context(|| tr!("failed-move-accounts-file-aside"))?;
+                    finding.fixed = true;
+                }
+                findings.push(finding);
+                (AccountsConfig::default(), fix)
+            }
+        };
+        let mut changed = false;
+
+        let mut names: Vec<_> = config.accounts.keys().cloned().collect();
+        names.sort();
+        let mut private_dirs = vec![live.clone()];
+        let now = self.clock.now();
+        for name in names {
+            let meta = config.accounts[&name].clone();
+            if !meta.path.is_dir() {
+                let mut finding = Finding::problem(tr!(
+                    "doctor-account-missing",
+                    name = name,
+                    path = meta.path.display()
+                ))
+                .with_fix(tr!("doctor-account-missing-fix"));
+                if fix {
+                    config.remove_account(&name);
+                    changed = true;
+                    finding.fixed = true;
+                }
+                findings.push(finding);
+                continue;
+            }
+            // A linked live directory is this snapshot, already on the list,
+            // and the system store's are for its group to read
+            if !platform::same_location(live, &meta.path) && self.system.is_none() {
+                private_dirs.push(meta.path.clone());
+            }
+
+            let expires_at = self
+                .account_login(&name, &meta.path)
+                .identity
+                .and_then(|identity| identity.expires_at);
+            if let Some(expires_at) = expires_at.filter(|expires_at| *expires_at < now) {
+                findings.push(
+                    Finding::warning(tr!(
+                        "doctor-credentials-expired",
+                        name = name,
+                        time = expires_at.format("%Y-%m-%d %H:%M UTC")
+                    ))
+                    .with_fix(tr!("doctor-credentials-expired-fix", name = name)),
+                );
+            }
+        }
+
+        for (name, broken) in &config.broken {
+            findings.push(
+                Finding::problem(tr!(
+                    "doctor-entry-corrupt",
+                    name = name,
+                    error = broken.error
+                ))
+                .with_fix(tr!("doctor-entry-corrupt-fix")),
+            );
+        }
+
+        if let Some(current) = config
+            .current
+            .clone()
+            .filter(|current| !config.accounts.contains_key(current))
+        {
+            let mut finding = Finding::problem(tr!("doctor-current-unknown", name = current))
+                .with_fix(tr!("doctor-current-unknown-fix"));
+            if fix {
+                config.current = None;
+                changed = true;
+                finding.fixed = true;
+            }
+            findings.push(finding);
+        }
+
+        if config_valid {
+            let tracked: Vec<&Path> = config
+                .accounts
+                .values()
+                .map(|meta| meta.path.as_path())
+                .collect();
+            let orphans = doctor::orphaned_dirs(&self.switcher_dir, &tracked)?;
+            for orphan in orphans {
+                let finding = Finding::warning(tr!("doctor-orphan", path = orphan.display()));
+                // A name no account could have is left for `adopt --name`
+                let Ok(name) = self.orphan_name(&config, &orphan) else {
+                    findings.push(
+                        finding.with_fix(tr!("doctor-orphan-name-fix", path = orphan.display())),
+                    );
+                    continue;
+                };
+                let mut finding = finding.with_fix(tr!("doctor-orphan-fix", name = name));
+                if fix {
+                    let metadata = self.orphan_metadata(&name, orphan, now);
+                    config.add_account(name, metadata);
+                    changed = true;
+                    finding.fixed = true;
+                }
+                findings.push(finding);
+            }
+        }
+
+        for dir in private_dirs.iter().filter(|dir| dir.is_dir()) {
+            for entry in store::walk_dir(dir) {
+                let entry = entry?;
+                if !claude::is_credential_file(&entry.path) || !doctor::is_too_open(&entry.path)? {
+                    continue;
+                }
+                let mut finding =
+                    Finding::problem(tr!("doctor-permissions", path = entry.path.display()))
+                        .with_fix(tr!("doctor-permissions-fix"));
+                if fix {
+                    doctor::make_private(&entry.path)?;
+                    finding.fixed = true;
+                }
+                findings.push(finding);
+            }
+        }
+
+        if changed {
+            self.save_config(&mut config)?;
+        }
+        Ok(findings)
+    }
+
+    /// Mends the accounts that can't be used. One given a directory in
+    /// `relink` (`NAME=DIR`), or whose snapshot is still where the store
+    /// keeps it, is pointed there; a corrupt entry is registered again from
+    /// the snapshot, losing its tags and note. The rest are dropped from the
+    /// list once confirmed. No snapshot is touched either way.
+    pub fn repair(&self, relink: &[String], dry_run: bool) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let mut config = self.load_config()?;
+
+        let mut targets = BTreeMap::new();
+        for assignment in relink {
+            let Some((name, dir)) = assignment.split_once('=') else {
+                anyhow::bail!(tr!("repair-relink-invalid", value = assignment));
+            };
+            match config.health(name) {
+                None => return Err(AccountError::NotFound(name.to_string()).into()),
+                Some(EntryHealth::Healthy) => anyhow::bail!(tr!("repair-healthy", name = name)),
+                Some(_) => {}
+            }
+            let dir = Path::new(dir);
+            if !dir.is_dir() {
+                anyhow::bail!(tr!("account-dir-not-found", path = dir.display()));
+            }
+            let dir = fs::canonicalize(dir)
+                .with_context(|| tr!("failed-resolve-path", path = dir.display()))?;
+            targets.insert(name.to_string(), dir);
+        }
+
+        let mut result = RepairJson {
+            dry_run,
+            ..Default::default()
+        };
+        for (name, health) in self.unhealthy(&config) {
+            let stored = self.switcher_dir.join(&name);
+            match targets.remove(&name) {
+                Some(dir) => {
+                    result.relinked.insert(name, dir);
+                }
+                // Its drive may only be unplugged
+                None if matches!(health, EntryHealth::Unmounted(_)) => result.unmounted.push(name),
+                None if stored.is_dir() => {
+                    result.relinked.insert(name, stored);
+                }
+                None => result.dropped.push(name),
+            }
+        }
+
+        if !dry_run {
+            let names = result.dropped.join(", ");
+            if !result.dropped.is_empty()
+                && !self.confirm(&tr!("repair-confirm", names = names), false)?
+            {
+                self.say(tr!("cancelled"));
+                return Ok(());
+            }
+            let store = fs::canonicalize(&self.switcher_dir).unwrap_or(self.switcher_dir.clone());
+            let now = self.clock.now();
+            for (name, dir) in &result.relinked {
+                let mut meta = match config.get_account(name) {
+                    Some(meta) => meta.clone(),
+                    None => self.orphan_metadata(name, dir.clone(), now),
+                };
+                // A directory outside the store isn't ours to move or delete
+                meta.external |= !dir.starts_with(&store) && !dir.starts_with(&self.switcher_dir);
+                meta.path = dir.clone();
+                config.broken.remove(name);
+                config.add_account(name.clone(), meta);
+            }
+            for name in &result.dropped {
+                config.remove_account(name);
+                if config.current.as_deref() == Some(name.as_str()) {
+                    config.current = None;
+                }
+            }
+            if !result.relinked.is_empty() || !result.dropped.is_empty() {
+                self.save_config(&mut config)?;
+            }
+        }
+
+        if self.format.is_json() {
+            return output::print_json(&result);
+        }
+        for (name, dir) in &result.relinked {
+            self.say(match dry_run {
+                true => tr!("repair-would-relink", name = name, path = dir.display()),
+                false => tr!("repair-relinked", name = name, path = dir.display()),
+            });
+        }
+        for name in &result.unmounted {
+            self.say(tr!("repair-unmounted", name = name));
+        }
+        for name in &result.dropped {
+            self.say(match dry_run {
+                true => tr!("repair-would-drop", name = name),
+                false => tr!("repair-dropped", name = name),
+            });
+        }
+        if result.relinked.is_empty() && result.dropped.is_empty() && result.unmounted.is_empty() {
+            self.say(tr!("repair-nothing"));
+        }
+        Ok(())
+    }
+
+    fn ensure_no_case_conflict(
+        &self,
+        config: &AccountsConfig,
+        name: &str,
+        allowed: Option<&str>,
+    ) -> Result<()> {
+        let Some(existing) = config.find_case_conflict(name) else {
+            return Ok(());
+        };
+
+        // Only probe the filesystem when there's actually a potential clash
+        if Some(existing) != allowed && platform::is_case_insensitive(&self.switcher_dir)? {
+            return Err(AccountError::CaseConflict(name.to_string(), existing.to_string()).into());
+        }
+        Ok(())
+    }
+
+    /// The files of `account` as they go into an export, decrypted from the
+    /// store and through `redaction` for a redacted one. Credentials kept in
+    /// the keychain travel with them; the importing machine stores them its
+    /// own way.
+    fn export_files(
+        &self,
+        account: &ExportedAccount,
+        codec: Codec,
+        redaction: Option<&mut Redaction>,
+    ) -> Result<AccountFiles> {
+        let mut files = Vec::new();
+        let nested = nested_snapshots(&self.load_config()?, &account.metadata.path);
+        for entry in store::walk_dir(&account.metadata.path).skip_dirs(&nested) {
+            let entry = entry?;
+            let contents = fs::read(&entry.path)
+                .with_context(|| tr!("failed-read-file", path = entry.path.display()))?;
+            files.push((entry.relative_path, codec.apply(contents)?));
+        }
+
+        if let Some(redaction) = redaction {
+            let files = files
+                .into_iter()
+                .filter_map(|(path, contents)| {
+                    let contents = redaction.file(&path, contents)?;
+                    Some((path, contents))
+                })
+                .collect();
+            return Ok(files);
+        }
+        if self.uses_keychain()
+            && let Some(credentials) = self.credstore.load(&account.name)?
+        {
+            files.retain(|(path, _)| path != Path::new(CREDENTIALS_FILE));
+            files.push((PathBuf::from(CREDENTIALS_FILE), credentials.into_bytes()));
+        }
+        Ok(files)
+    }
+
+    /// Asks for a new passphrase, then again to catch typos.
+    fn new_passphrase(&self, prompt: &str, repeat: &str) -> Result<String> {
+        let passphrase = self.prompter.passphrase(prompt)?;
+        if passphrase != self.prompter.passphrase(repeat)? {
+            anyhow::bail!(tr!("passphrase-mismatch"));
+        }
+        if passphrase.is_empty() {
+            anyhow::bail!(tr!("passphrase-empty"));
+        }
+        Ok(passphrase)
+    }
+
+    /// Reads a bundle, decrypting it when needed.
+    fn read_bundle(&self, file: &Path, passphrase: Option<&str>) -> Result<Vec<u8>> {
+        let data =
+            fs::read(file).with_context(|| tr!("failed-read-bundle", path = file.display()))?;
+
+        if !crypto::is_encrypted(&data) {
+            return Ok(data);
+        }
+        let passphrase = match passphrase {
+            Some(passphrase) => passphrase.to_string(),
+            None => self.prompter.passphrase(&tr!("passphrase-prompt"))?,
+        };
+        crypto::decrypt(&data, &passphrase)
+    }
+
+    pub fn enable_encryption(&self) -> Result<()> {
+        let _lock = self.lock_state()?;
+        let key_file = self.key_file();
+        if Vault::is_enabled(&key_file) {
+            anyhow::bail!(tr!("encryption-already-enabled"));
+        }
+        self.ensure_none_archived()?;
+
+        let passphrase = match std::env::var(encryption::PASSPHRASE_ENV) {
+            Ok(passphrase) if passphrase.is_empty() => anyhow::bail!(tr!("passphrase-empty")),
+            Ok(passphrase) => passphrase,
+            Err(_) => self.new_passphrase(
+                &tr!("store-passphrase-prompt"),
+                &tr!("store-passphrase-repeat"),
+            )?,
+        };
+
+        // Sealing skips files that already are, so rerunning after a failure is safe
+        let vault = Vault::create(&key_file, &passphrase)?;
+        let (accounts, files) = self.rewrite_accounts(|dir| vault.seal_tree(dir))?;
+        let _ = self.vault.set(vault);
+
+        self.say(tr!(
+            "encryption-enabled",
+            files = files,
+            accounts = accounts
+        ));
+        Ok(())
+    }
+
+    pub fn disable_encryption(&self) -> Result<()> {
+        let _lock = self.lock_state()?;
+        if !self.encryption_enabled() {
+            anyhow::bail!(tr!("encryption-not-enabled"));
+        }
+        self.ensure_none_archived()?;
+
+        // The key file goes last so an interrupted run can be resumed
+        let vault = self.vault()?;
+        let (accounts, files) = self.rewrite_accounts(|dir| vault.open_tree(dir))?;
+        fs::remove_file(self.key_file()).with_context(|| tr!("failed-remove-key-file"))?;
+
+        self.say(tr!(
+            "encryption-disabled",
+            files = files,
+            accounts = accounts
+        ));
+        Ok(())
+    }
+
+    /// Fails unless every snapshot is unpacked, for what rewrites them all:
+    /// an archive holds its files as they were stored when it was made.
+    fn ensure_none_archived(&self) -> Result<()> {
+        let config = self.load_config()?;
+        let mut archived: Vec<&str> = config
+            .accounts
+            .iter()
+            .filter(|(_, meta)| meta.archive.is_some())
+            .map(|(name, _)| name.as_str())
+            .collect();
+        if archived.is_empty() {
+            return Ok(());
+        }
+        archived.sort();
+        anyhow::bail!(tr!("encryption-archived", names = archived.join(", ")))
+    }
+
+    fn rewrite_accounts(&self, rewrite: impl Fn(&Path) -> Result<u64>) -> Result<(usize, u64)> {
+        let config = self.load_config()?;
+        let mut accounts = 0;
+        let mut files = 0;
+        for (name, meta) in &config.accounts {
+            if meta.path.is_dir() && !meta.external {
+                files += rewrite(&meta.path)?;
+                accounts += 1;
+            }
+            for version in versions::list(&self.switcher_dir, name)? {
+                files += rewrite(&version.path)?;
+            }
+        }
+        Ok((accounts, files))
+    }
+
+    /// Takes the state lock for a command that changes anything, or joins
+    /// the one an outer call already holds.
+    fn lock_state(&self) -> Result<Arc<StateLock>> {
+        let mut held = self.held_lock.lock().unwrap();
+        if let Some(lock) = held.upgrade() {
+            return Ok(lock);
+        }
+
+        let lock = Arc::new(lock::acquire(
+            &self.state_dir.join(lock::FILE),
+            self.lock_wait,
+        )?);
+        *held = Arc::downgrade(&lock);
+        Ok(lock)
+    }
+
+    fn history_file(&self) -> PathBuf {
+        self.state_dir.join("history.jsonl")
+    }
+
+    fn sessions_file(&self) -> PathBuf {
+        self.state_dir.join(sessions::FILE)
+    }
+
+    fn record(&self, kind: EventKind, account: &str, source: EventSource) -> Result<()> {
+        self.record_details(kind, account, source, None, None)
+    }
+
+    /// Records an event with the account it followed and the number of
+    /// files it copied, where there are any.
+    fn record_details(
+        &self,
+        kind: EventKind,
+        account: &str,
+        source: EventSource,
+        previous: Option<&str>,
+        transfer: Option<Transfer>,
+    ) -> Result<()> {
+        history::append(
+            &self.history_file(),
+            &self.event(kind, account, source, previous, transfer),
+        )
+    }
+
+    fn event(
+        &self,
+        kind: EventKind,
+        account: &str,
+        source: EventSource,
+        previous: Option<&str>,
+        transfer: Option<Transfer>,
+    ) -> Event {
+        Event {
+            at: self.clock.now().to_rfc3339(),
+            kind,
+            account: account.to_string(),
+            source,
+            previous: previous.map(str::to_string),
+            files: transfer.map(|transfer| transfer.usage.files),
+            live_backup: None,
+        }
+    }
+
+    fn key_file(&self) -> PathBuf {
+        self.state_dir.join("store-key.json")
+    }
+
+    /// Whether credentials go to the keychain: configured, and the keychain
+    /// answered the capability probe. Otherwise they stay in the snapshot.
+    fn uses_keychain(&self) -> bool {
+        self.settings.credentials == CredentialStorage::Keychain && self.capabilities.keychain
+    }
+
+    fn encryption_enabled(&self) -> bool {
+        Vault::is_enabled(&self.key_file())
+    }
+
+    /// Whether saved files are linked to shared objects. Sealed files never
+    /// match, and link counts are only read on Unix.
+    fn dedup_enabled(&self) -> bool {
+        self.settings.dedup && cfg!(unix) && !self.encryption_enabled()
+    }
+
+    fn objects_dir(&self) -> PathBuf {
+        self.switcher_dir.join(objects::DIR)
+    }
+
+    /// Links every file in a snapshot that isn't linked yet to the object
+    /// with its contents, returning the files that matched one already.
+    /// Credentials stay the snapshot's own.
+    fn intern_snapshot(&self, dir: &Path) -> Result<DirUsage> {
+        let mut shared = DirUsage::default();
+        for entry in store::walk_dir(dir) {
+            let entry = entry?;
+            if claude::is_credential_file(&entry.relative_path) || objects::is_linked(&entry.path)?
+            {
+                continue;
+            }
+            if objects::intern(&self.objects_dir(), &entry.path, &entry.hash()?)? {
+                shared.files += 1;
+                shared.bytes += entry.size;
+            }
+        }
+        Ok(shared)
+    }
+
+    /// Stores the files of snapshots saved before `dedup` was on once, then
+    /// removes the stored objects nothing links to any more.
+    pub fn collect_garbage(&self, dry_run: bool) -> Result<()> {
+        let _lock = (!dry_run).then(|| self.lock_state()).transpose()?;
+        let mut shared = DirUsage::default();
+        if self.dedup_enabled() && !dry_run {
+            let config = self.load_config()?;
+            for (name, meta) in &config.accounts {
+                // Claude Code writes to a linked snapshot in place
+                if meta.path.is_dir()
+                    && !meta.external
+                    && self.custom_store(meta).is_none()
+                    && !platform::same_location(&self.claude_config_dir, &meta.path)
+                {
+                    shared += self.intern_snapshot(&meta.path)?;
+                }
+                for version in versions::list(&self.switcher_dir, name)? {
+                    shared += self.intern_snapshot(&version.path)?;
+                }
+            }
+        }
+        let collected = objects::gc(&self.objects_dir(), dry_run)?;
+
+        if self.format.is_json() {
+            return output::print_json(&GcJson {
+                shared_files: shared.files,
+                shared_bytes: shared.bytes,
+                removed_objects: collected.objects,
+                removed_bytes: collected.bytes,
+                dry_run,
+            });
+        }
+        if shared.files > 0 {
+            self.say(tr!(
+                "gc-shared",
+                count = shared.files,
+                size = size::format_bytes(shared.bytes)
+            ));
+        }
+        let size = size::format_bytes(collected.bytes);
+        self.say(if dry_run {
+            tr!("gc-dry-run", count = collected.objects, size = size)
+        } else {
+            tr!("gc-removed", count = collected.objects, size = size)
+        });
+        Ok(())
+    }
+
+    /// `gc --orphans`: lists the directories in the store that no account
+    /// uses, left by a crash or a rename that failed halfway, and once
+    /// confirmed moves them to the trash, or with `adopt` registers them as
+    /// accounts of their names instead.
+    pub fn collect_orphans(&self, adopt: bool, dry_run: bool) -> Result<()> {
+        let _lock = (!dry_run).then(|| self.lock_state()).transpose()?;
+        let mut config = self.load_config()?;
+        let mut result = OrphansJson {
+            orphans: Vec::new(),
+            deleted: Vec::new(),
+            adopted: Vec::new(),
+            dry_run,
+        };
+        for path in self.orphaned_dirs(&config)? {
+            let usage = size::dir_usage(&path)?;
+            result.orphans.push(OrphanJson {
+                path,
+                files: usage.files,
+                bytes: usage.bytes,
+            });
+        }
+
+        if result.orphans.is_empty() {
+            return match self.format.is_json() {
+                true => output::print_json(&result),
+                false => {
+                    self.say(tr!("gc-no-orphans"));
+                    Ok(())
+                }
+            };
+        }
+        self.say(tr!("gc-orphans", count = result.orphans.len()));
+        for orphan in &result.orphans {
+            self.say(format!(
+                "  {} ({})",
+                orphan.path.display(),
+                size::format_bytes(orphan.bytes)
+            ));
+        }
+
+        let question = match adopt {
+            true => tr!("gc-adopt-confirm"),
+            false => tr!("gc-orphans-confirm"),
+        };
+        if !dry_run && !self.confirm(&question, false)? {
+            self.say(tr!("gc-orphans-kept"));
+        } else if dry_run {
+            self.say(tr!("gc-orphans-dry-run"));
+        } else if adopt {
+            let (adopted, _) = self.adopt_untracked(&mut config)?;
+            if !adopted.is_empty() {
+                self.save_config(&mut config)?;
+            }
+            result.adopted = adopted;
+        } else {
+            let now = self.clock.now();
+            for orphan in &result.orphans {
+                let name = orphan
+                    .path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default();
+                // The details read from it are what `trash restore` registers
+                let meta = self.orphan_metadata(&name, orphan.path.clone(), now);
+                let entry = self.trash_entry(&name)?;
+                self.trash_details(&entry, &name, &meta)?;
+                if let Err(e) = retry(|| fs::rename(&orphan.path, trash::snapshot_dir(&entry))) {
+                    let _ = trash::remove(&entry);
+                    return Err(e).with_context(|| tr!("failed-move-account-directory-to-trash"));
+                }
+                self.say(tr!("gc-orphan-trashed", path = orphan.path.display()));
+                result.deleted.push(orphan.path.clone());
+            }
+        }
+
+        if self.format.is_json() {
+            output::print_json(&result)?;
+        }
+        Ok(())
+    }
+
+    /// Applies the retention rules, each taken from the settings unless
+    /// given: accounts unused for `unused_after` are deleted once confirmed,
+    /// versions past `keep_versions` go, and files matching `strip` are
+    /// removed from every stored snapshot and version.
+    pub fn prune(
+        &self,
+        keep_versions: Option<usize>,
+        unused_after: Option<&str>,
+        strip: &[String],
+        dry_run: bool,
+    ) -> Result<()> {
+        let keep = keep_versions
+            .or(self.settings.keep_versions)
+            .unwrap_or(versions::DEFAULT_KEEP);
+        let unused_after = match unused_after {
+            Some(value) => Some(parse_duration(value)?),
+            None => self.settings.prune.unused_after()?,
+        };
+        let strip = match strip {
+            [] => &self.settings.prune.strip[..],
+            strip => strip,
+        };
+        // Everything it doesn't capture is stripped
+        let strip = CapturePolicy::new(&[] as &[String], strip)?;
+
+        let _lock = (!dry_run).then(|| self.lock_state()).transpose()?;
+        let mut config = self.load_config()?;
+        let mut result = PruneJson {
+            deleted_accounts: Vec::new(),
+            removed_versions: 0,
+            version_bytes: 0,
+            stripped_files: 0,
+            stripped_bytes: 0,
+            dry_run,
+        };
+
+        if let Some(unused_after) = unused_after {
+            let unused = self.unused_accounts(&config, self.clock.now() - unused_after);
+            if !unused.is_empty() {
+                self.say(tr!("prune-unused", count = unused.len()));
+                for (name, last_used) in &unused {
+                    self.say(format!(
+                        "  {} ({})",
+                        name,
+                        tr!("last-used", time = last_used)
+                    ));
+                }
+            }
+            if !unused.is_empty() && !dry_run && !self.confirm(&tr!("prune-confirm"), false)? {
+                self.say(tr!("prune-kept-accounts"));
+            } else {
+                for (name, _) in unused {
+                    if !dry_run {
+                        let meta = config.accounts[&name].clone();
+                        if !meta.external {
+                            self.remove_account_files(&name, &meta, &config)?;
+                        }
+                        config.remove_account(&name);
+                        self.record(EventKind::Delete, &name, EventSource::Cli)?;
+                    }
+                    result.deleted_accounts.push(name);
+                }
+                if !dry_run && !result.deleted_accounts.is_empty() {
+                    self.save_config(&mut config)?;
+                    for name in &result.deleted_accounts {
+                        self.run_hook(Hook::PostDelete, &[(hooks::NAME_VAR, name)])?;
+                    }
+                }
+            }
+        }
+
+        for (name, meta) in &config.accounts {
+            if (dry_run && result.deleted_accounts.contains(name)) || (meta.locked && !self.force) {
+                continue;
+            }
+            let all = versions::list(&self.switcher_dir, name)?;
+            for version in all.iter().skip(keep) {
+                let usage = size::dir_usage(&version.path)?;
+                result.removed_versions += 1;
+                result.version_bytes += usage.bytes;
+            }
+            if !dry_run {
+                versions::prune(&self.switcher_dir, name, keep)?;
+            }
+
+            if strip.is_empty() || meta.external {
+                continue;
+            }
+            // A linked snapshot is the live configuration
+            let mut dirs: Vec<PathBuf> = all.into_iter().take(keep).map(|v| v.path).collect();
+            if !platform::same_location(&self.claude_config_dir, &meta.path) {
+                dirs.push(meta.path.clone());
+            }
+            let nested = nested_snapshots(&config, &meta.path);
+            for dir in dirs {
+                let (stripped, removed) = strip_files(&dir, &strip, &nested, dry_run)?;
+                result.stripped_files += stripped.files;
+                result.stripped_bytes += stripped.bytes;
+                // Gone from the snapshot, so `verify` doesn't look for them
+                if dir == meta.path && !removed.is_empty() {
+                    let path = manifest::path(&self.state_dir, name);
+                    if let Some(mut saved) = Manifest::load(&path)? {
+                        saved.files.retain(|file, _| !removed.contains(file));
+                        saved.save(&path)?;
+                    }
+                }
+            }
+        }
+
+        if self.format.is_json() {
+            output::print_json(&result)?;
+        } else {
+            self.report_prune(&result);
+        }
+        // Removed files may have been the last links to stored objects
+        if self.dedup_enabled() && !dry_run {
+            objects::gc(&self.objects_dir(), false)?;
+        }
+        Ok(())
+    }
+
+    /// The accounts not switched to (or saved, if never switched to) since
+    /// `cutoff`, with when that was. The active account and protected ones
+    /// are never among them, nor locked ones without `--force`.
+    fn unused_accounts(
+        &self,
+        config: &AccountsConfig,
+        cutoff: DateTime<Utc>,
+    ) -> Vec<(String, String)> {
+        config
+            .accounts
+            .iter()
+            .filter(|(name, meta)| {
+                config.current.as_deref() != Some(name.as_str())
+                    && !meta.protected
+                    && (!meta.locked || self.force)
+            })
+            .filter_map(|(name, meta)| {
+                let last_used = meta.last_used.as_deref().unwrap_or(&meta.saved_at);
+                let at = DateTime::parse_from_rfc3339(last_used).ok()?;
+                (at < cutoff).then(|| {
+                    (
+                        name.clone(),
+                        last_used.get(..19).unwrap_or(last_used).to_string(),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    fn report_prune(&self, result: &PruneJson) {
+        let versions = size::format_bytes(result.version_bytes);
+        let stripped = size::format_bytes(result.stripped_bytes);
+        let count = result.deleted_accounts.len();
+        if result.dry_run {
+            if count > 0 {
+                self.say(tr!("prune-would-delete", count = count));
+            }
+            if result.removed_versions > 0 {
+                self.say(tr!(
+                    "prune-would-remove-versions",
+                    count = result.removed_versions,
+                    size = versions
+                ));
+            }
+            if result.stripped_files > 0 {
+                self.say(tr!(
+                    "prune-would-strip",
+                    count = result.stripped_files,
+                    size = stripped
+                ));
+            }
+        } else {
+            if count > 0 {
+                self.say(tr!("prune-deleted", count = count));
+            }
+            if result.removed_versions > 0 {
+                self.say(tr!(
+                    "prune-removed-versions",
+                    count = result.removed_versions,
+                    size = versions
+                ));
+            }
+            if result.stripped_files > 0 {
+                self.say(tr!(
+                    "prune-stripped",
+                    count = result.stripped_files,
+                    size = stripped
+                ));
+            }
+        }
+        if count == 0 && result.removed_versions == 0 && result.stripped_files == 0 {
+            self.say(tr!("prune-nothing"));
+        }
+    }
+
+    fn vault(&self) -> Result<&Vault> {
+        if let Some(vault) = self.vault.get() {
+            return Ok(vault);
+        }
+
+        let passphrase = match std::env::var(encryption::PASSPHRASE_ENV) {
+            Ok(passphrase) => passphrase,
+            Err(_) => self.prompter.passphrase(&tr!("store-passphrase-prompt"))?,
+        };
+        let vault = Vault::unlock(&self.key_file(), &passphrase)?;
+        Ok(self.vault.get_or_init(|| vault))
+    }
+
+    /// How files are transformed when copied into (`sealing`) or out of the
+    /// store. Coming out, files compressed earlier are decompressed whatever
+    /// the setting is now.
+    fn store_codec(&self, sealing: bool) -> Result<Codec<'_>> {
+        let vault = if self.encryption_enabled() {
+            Some(self.vault()?)
+        } else {
+            None
+        };
+        if !sealing {
+            return Ok(Codec::Load { vault });
+        }
+
+        let level = self.settings.compression_level()?;
+        if vault.is_none() && level.is_none() {
+            return Ok(Codec::Plain);
+        }
+        Ok(Codec::Store { vault, level })
+    }
+
+    /// Why Claude Code can't use the snapshot in `account_dir` where it is,
+    /// through a symlink or `CLAUDE_CONFIG_DIR`, if it can't.
+    fn direct_use_blocker(&self, account_dir: &Path) -> Option<String> {
+        if let Some(reason) = self.link_blocker(account_dir) {
+            return Some(reason);
+        }
+        // The snapshot has no credentials file to use
+        if self.uses_keychain() {
+            return Some(tr!("direct-keychain"));
+        }
+        // The snapshot holds only the login, and nothing else would be shared
+        if self.settings.capture_scope == CaptureScope::Credentials {
+            return Some(tr!("direct-credentials-scope"));
+        }
+        None
+    }
+
+    /// Why the files of the snapshot in `account_dir` can't be linked into
+    /// the live directory as they're stored, when they can't.
+    fn link_blocker(&self, account_dir: &Path) -> Option<String> {
+        // Claude Code can't read sealed files, so they have to be copied out
+        if self.encryption_enabled() {
+            return Some(tr!("direct-encrypted"));
+        }
+        // Claude Code would write through the links into other snapshots
+        if self.dedup_enabled() {
+            return Some(tr!("direct-dedup"));
+        }
+        // Nor can it read compressed ones, which may be left from before
+        // `compress` was turned off
+        if self.settings.compress.is_some() || holds_compressed(account_dir) {
+            return Some(tr!("direct-compressed"));
+        }
+        None
+    }
+
+    /// Whether `--mode hardlink` can link the snapshot in `account_dir`,
+    /// warning when it has to copy instead.
+    fn can_hardlink(&self, account_dir: &Path) -> bool {
+        match self.link_blocker(account_dir) {
+            Some(reason) => {
+                output::warn(tr!("hardlink-copying", reason = reason));
+                false
+            }
+            None => true,
+        }
+    }
+
+    /// Creates the symlink that links the live directory to `account_dir`,
+    /// or returns `None` when the switch has to copy instead.
+    fn stage_live_link(&self, account_dir: &Path) -> Option<PathBuf> {
+        if let Some(reason) = self.direct_use_blocker(account_dir) {
+            output::warn(tr!("symlink-copying", reason = reason));
+            return None;
+        }
+
+        // Symlinks need elevated rights or developer mode on Windows
+        info!(to = %account_dir.display(), "link");
+        let staged = if self.capabilities.symlinks {
+            self.stage_link(account_dir).ok()
+        } else {
+            None
+        };
+        if staged.is_none() {
+            output::warn(tr!("symlink-fallback"));
+        }
+        staged
+    }
+
+    /// What goes from the snapshot in `account_dir` into the live directory:
+    /// not the paths kept from outside it, which go back to where they came
+    /// from instead, nor the snapshots of accounts nested in it.
+    fn snapshot_policy(&self, account_dir: &Path) -> Result<CapturePolicy> {
+        let config = self.load_config()?;
+        let mut exclude = vec![format!("/{}/", extra_paths::DIR)];
+        exclude.extend(
+            nested_snapshots(&config, account_dir)
+                .iter()
+                .map(|nested| format!("/{}/", slashed(nested))),
+        );
+        CapturePolicy::new(&[], &exclude)
+    }
+
+    /// Copies the snapshot in `account_dir` to `staged`, decrypting it.
+    fn stage_live_copy(&self, account_dir: &Path, staged: &Path) -> Result<Transfer> {
+        // Left over from an interrupted switch
+        self.discard(staged);
+        self.storage
+            .create_dir_all(staged)
+            .with_context(|| tr!("failed-create-configuration-directory"))?;
+        let policy = self.snapshot_policy(account_dir)?;
+        let transfer = self
+            .timed_copy(account_dir, staged, &policy, self.store_codec(false)?)
+            .with_context(|| tr!("failed-restore-account-configuration"))?;
+        // Copied from files the store's group can read
+        if self.system.is_some() {
+            system::make_private(staged)?;
+        }
+        Ok(transfer)
+    }
+
+    /// Fills `staged` with hard links to the files of the snapshot in
+    /// `account_dir`. Those Claude Code rewrites (see
+    /// [`claude::is_rewritten`]) are copied instead, so writing them doesn't
+    /// change the snapshot, as are any a link can't be made to, such as on
+    /// another filesystem. What's returned counts only the copies.
+    fn stage_live_hardlinks(&self, account_dir: &Path, staged: &Path) -> Result<Transfer> {
+        self.discard(staged);
+        self.storage
+            .create_dir_all(staged)
+            .with_context(|| tr!("failed-create-configuration-directory"))?;
+        let policy = self.snapshot_policy(account_dir)?;
+        let started = Instant::now();
+        let mut files = Vec::new();
+        timings::time(Phase::Scanning, || {
+            plan_copy(
+                &*self.storage,
+                account_dir,
+                staged,
+                Path::new(""),
+                &policy,
+                &mut files,
+            )
+        })?;
+
+        let mut usage = DirUsage::default();
+        let mut linked = 0;
+        let copying = timings::Timer::start(Phase::Copying);
+        for planned in files {
+            interrupt::check()?;
+            let (src, dst) = (&planned.src, &planned.dst);
+            if planned.symlink {
+                self.storage
+                    .copy_symlink(src, dst)
+                    .with_context(|| tr!("failed-recreate-symlink", path = dst.display()))?;
+                continue;
+            }
+            if !claude::is_rewritten(&planned.relative) && self.storage.hard_link(src, dst).is_ok()
+            {
+                debug!(from = %src.display(), to = %dst.display(), "hard link");
+                linked += 1;
+                continue;
+            }
+            usage.bytes +=
+                copy_file(&*self.storage, src, dst, Codec::Plain).with_context(|| {
+                    tr!(
+                        "failed-copy-file-from-to",
+                        from = src.display(),
+                        to = dst.display()
+                    )
+                })?;
+            usage.files += 1;
+        }
+        drop(copying);
+        self.say(tr!("hardlink-linked", count = linked));
+        Ok(Transfer {
+            usage,
+            elapsed: started.elapsed(),
+        })
+    }
+
+    /// Moves the live configuration aside and `staged` into its place,
+    /// returning where the previous one went. Either both renames happen or
+    /// neither does.
+    fn swap_live(&self, staged: &Path) -> Result<Option<PathBuf>> {
+        let live = &self.claude_config_dir;
+        let previous = if self.storage.exists(live) || self.storage.is_symlink(live) {
+            let previous = self.live_sibling(PREVIOUS_SUFFIX);
+            info!(from = %live.display(), to = %previous.display(), "move live aside");
+            if let Err(e) = retry(|| self.storage.rename(live, &previous)) {
+                self.discard(staged);
+                return Err(e).with_context(|| tr!("failed-move-current-configuration-aside"));
+            }
+            Some(previous)
+        } else {
+            None
+        };
+
+        info!(from = %staged.display(), to = %live.display(), "move staged into place");
+        if let Err(e) = retry(|| self.storage.rename(staged, live)) {
+            self.discard(staged);
+            if let Some(previous) = &previous {
+                let _ = retry(|| self.storage.rename(previous, live));
+            }
+            return Err(e).with_context(|| tr!("failed-move-new-configuration-into-place"));
+        }
+        Ok(previous)
+    }
+
+    /// Undoes a swap whose switch failed afterwards, putting shared files
+    /// back where they were carried from.
+    fn restore_previous_live(&self, previous: Option<&Path>, carried: &[(PathBuf, PathBuf)]) {
+        info!("put the previous live configuration back");
+        for (from, to) in carried.iter().rev() {
+            let _ = retry(|| self.storage.rename(to, from));
+        }
+
+        let live = &self.claude_config_dir;
+        let failed = self.live_sibling(STAGED_SUFFIX);
+        self.discard(&failed);
+        if retry(|| self.storage.rename(live, &failed)).is_ok() {
+            self.discard(&failed);
+        }
+        if let Some(previous) = previous {
+            let _ = retry(|| self.storage.rename(previous, live));
+        }
+        self.put_back_extra_paths();
+    }
+
+    /// Cleans up after a switch that was killed partway. A live directory
+    /// still moved aside is put back; when the new one did make it into
+    /// place, the shared files it hadn't received yet are carried over.
+    fn recover_interrupted_switch(&self) -> Result<()> {
+        let live = &self.claude_config_dir;
+        let previous = self.live_sibling(PREVIOUS_SUFFIX);
+        self.discard(&self.live_sibling(STAGED_SUFFIX));
+        if !self.storage.exists(&previous) && !self.storage.is_symlink(&previous) {
+            return Ok(());
+        }
+
+        if !self.storage.exists(live) && !self.storage.is_symlink(live) {
+            retry(|| self.storage.rename(&previous, live)).with_context(|| {
+                tr!("failed-restore-configuration-interrupted-switch-moved-aside")
+            })?;
+            self.put_back_extra_paths();
+            eprintln!("{}", tr!("switch-recovered", path = live.display()));
+            return Ok(());
+        }
+        if !self.storage.is_symlink(&previous)
+            && self.storage.is_dir(live)
+            && !self.storage.is_symlink(live)
+            && !self.capture.is_empty()
+        {
+            self.carry_uncaptured(&previous, live, Path::new(""), &mut Vec::new())
+                .with_context(|| tr!("failed-keep-shared-files"))?;
+        }
+        self.discard(&previous);
+        Ok(())
+    }
+
+    /// Moves what the capture policy doesn't cover from `from` into `to`,
+    /// leaving anything already present in `to` alone. Each move is noted in
+    /// `carried` so it can be undone.
+    fn carry_uncaptured(
+        &self,
+        from: &Path,
+        to: &Path,
+        relative: &Path,
+        carried: &mut Vec<(PathBuf, PathBuf)>,
+    ) -> Result<()> {
+        for entry in self
+            .storage
+            .read_dir(from)
+            .with_context(|| tr!("failed-read-directory", path = from.display()))?
+        {
+            let path = from.join(&entry.name);
+            let dest = to.join(&entry.name);
+            let relative = relative.join(&entry.name);
+
+            let shared = if entry.kind == storage::Kind::Dir {
+                if !self.capture.skips_dir(&relative) {
+                    self.carry_uncaptured(&path, &dest, &relative, carried)?;
+                    continue;
+                }
+                true
+            } else {
+                !self.capture.captures(&relative)
+            };
+            if shared && !self.storage.exists(&dest) && !self.storage.is_symlink(&dest) {
+                self.storage
+                    .create_dir_all(to)
+                    .with_context(|| tr!("failed-create-directory", path = to.display()))?;
+                debug!(from = %path.display(), to = %dest.display(), "carry shared");
+                retry(|| self.storage.rename(&path, &dest))
+                    .with_context(|| tr!("failed-move", path = path.display()))?;
+                carried.push((path, dest));
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the snapshot a save is about to replace aside as a version, then
+    /// drops versions beyond `keep-versions`. Returns where it went.
+    fn keep_version(
+        &self,
+        name: &str,
+        account_dir: &Path,
+        saved_at: &str,
+        nested: &[PathBuf],
+    ) -> Result<Option<PathBuf>> {
+        let keep = self
+            .settings
+            .keep_versions
+            .unwrap_or(versions::DEFAULT_KEEP);
+        if keep == 0 || !self.storage.is_dir(account_dir) {
+            return Ok(None);
+        }
+
+        let version = versions::allocate(&self.switcher_dir, name, saved_at)?;
+        info!(from = %account_dir.display(), to = %version.display(), "keep version");
+        retry(|| self.storage.rename(account_dir, &version))
+            .with_context(|| tr!("failed-keep-previous-snapshot"))?;
+        self.move_nested(&version, account_dir, nested)?;
+        if self.uses_keychain() {
+            self.keep_keychain_credentials(name, &version)?;
+        }
+        versions::prune(&self.switcher_dir, name, keep)?;
+        Ok(Some(version))
+    }
+
+    /// Keeps a copy of the snapshot of `name` as a version, as `keep_version`
+    /// does, for a change made to it where it is.
+    fn keep_version_copy(
+        &self,
+        name: &str,
+        account_dir: &Path,
+        saved_at: &str,
+        nested: &[PathBuf],
+    ) -> Result<()> {
+        let keep = self
+            .settings
+            .keep_versions
+            .unwrap_or(versions::DEFAULT_KEEP);
+        if keep == 0 || !self.storage.is_dir(account_dir) {
+            return Ok(());
+        }
+
+        let version = versions::allocate(&self.switcher_dir, name, saved_at)?;
+        info!(from = %account_dir.display(), to = %version.display(), "keep version copy");
+        for entry in store::walk_dir(account_dir).skip_dirs(nested) {
+            let entry = entry?;
+            let dst = version.join(&entry.relative_path);
+            if let Some(parent) = dst.parent() {
+                self.storage
+                    .create_dir_all(parent)
+                    .with_context(|| tr!("failed-keep-previous-snapshot"))?;
+            }
+            take_unchanged(&entry.path, &dst, self.dedup_enabled())?;
+        }
+        if self.uses_keychain() {
+            self.keep_keychain_credentials(name, &version)?;
+        }
+        versions::prune(&self.switcher_dir, name, keep)?;
+        Ok(())
+    }
+
+    /// Moves the `nested` snapshots in `from` to the same places in `to`:
+    /// when a snapshot is moved as a whole, those of the accounts nested in
+    /// it aren't part of it and stay where they were.
+    fn move_nested(&self, from: &Path, to: &Path, nested: &[PathBuf]) -> Result<()> {
+        for nested in nested {
+            let (src, dest) = (from.join(nested), to.join(nested));
+            // Moved along with one it's nested in
+            if !self.storage.exists(&src) {
+                continue;
+            }
+            if let Some(parent) = dest.parent() {
+                self.storage
+                    .create_dir_all(parent)
+                    .with_context(|| tr!("failed-create-account-directory"))?;
+            }
+            retry(|| self.storage.rename(&src, &dest))
+                .with_context(|| tr!("failed-keep-nested-account"))?;
+        }
+        Ok(())
+    }
+
+    // The keychain holds only the latest credentials, so a version keeps its
+    // own copy in the snapshot, sealed like any other file
+    fn keep_keychain_credentials(&self, name: &str, version_dir: &Path) -> Result<()> {
+        if let Some(credentials) = self.credstore.load(name)? {
+            let sealed = self.store_codec(true)?.apply(credentials.into_bytes())?;
+            platform::write_private(&version_dir.join(CREDENTIALS_FILE), &sealed)?;
+        }
+        Ok(())
+    }
+
+    fn restore_kept_credentials(&self, name: &str, account_dir: &Path) -> Result<()> {
+        let saved = account_dir.join(CREDENTIALS_FILE);
+        if let Some(credentials) = self.read_stored(&saved)? {
+            self.credstore.store(name, &credentials)?;
+            self.storage
+                .remove_file(&saved)
+                .with_context(|| tr!("failed-remove-saved-credentials-file"))?;
+        }
+        Ok(())
+    }
+
+    /// Asks before switching to a protected account: a yes/no question, then
+    /// the account name typed out in full. `--yes` answers both.
+    fn confirm_protected(&self, name: &str) -> Result<bool> {
+        output::warn(tr!("protected-warning", name = name));
+        if self.assume_yes {
+            return Ok(true);
+        }
+        if !self.confirm(&tr!("protected-confirm"), false)? {
+            return Ok(false);
+        }
+
+        let typed = self.prompter.input(&tr!("protected-type-name"))?;
+        if typed.trim() != name {
+            anyhow::bail!(tr!("protected-name-mismatch", name = name));
+        }
+        Ok(true)
+    }
+
+    /// Hands the live credentials to the keychain and drops the copy that was
+    /// just written to the snapshot.
+    fn move_credentials_to_keychain(&self, name: &str, account_dir: &Path) -> Result<()> {
+        let live = self.claude_config_dir.join(CREDENTIALS_FILE);
+        if !self.storage.exists(&live) {
+            return Ok(());
+        }
+
+        let credentials = self
+            .storage
+            .read(&live)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| Ok(String::from_utf8(data)?))
+            .with_context(|| tr!("failed-read-credentials-file"))?;
+        self.credstore.store(name, &credentials)?;
+
+        let saved = account_dir.join(CREDENTIALS_FILE);
+        if self.storage.exists(&saved) {
+            self.storage
+                .remove_file(&saved)
+                .with_context(|| tr!("failed-remove-saved-credentials-file"))?;
+        }
+        Ok(())
+    }
+
+    /// Copies the paths kept outside the Claude directory into the snapshot
+    /// in `account_dir`, in place of the ones saved before. One that doesn't
+    /// exist is left out, so the switch back removes it.
+    fn save_extra_paths(&self, account_dir: &Path, codec: Codec) -> Result<()> {
+        if self.extra_paths.is_empty() {
+            return Ok(());
+        }
+
+        let stored_dir = account_dir.join(extra_paths::DIR);
+        self.discard(&stored_dir);
+        self.storage
+            .create_dir_all(&stored_dir)
+            .with_context(|| tr!("failed-create-directory", path = stored_dir.display()))?;
+        for path in &self.extra_paths {
+            let stored = account_dir.join(&path.stored);
+            self.copy_extra_path(&path.live, &stored, codec)?;
+        }
+        Ok(())
+    }
+
+    /// Puts the paths `account_dir`'s snapshot kept outside the Claude
+    /// directory back, removing the ones it didn't have. The ones they
+    /// replace are copied beside the live directory first, for the live
+    /// backup (or for putting back when the switch fails). The copies are
+    /// decrypted when they're in the `store`d form.
+    fn restore_extra_paths(&self, account_dir: &Path, stored: bool) -> Result<()> {
+        // Saved before they were captured
+        if self.extra_paths.is_empty() || !account_dir.join(extra_paths::DIR).is_dir() {
+            return Ok(());
+        }
+
+        let codec = match stored {
+            true => self.store_codec(false)?,
+            false => Codec::Plain,
+        };
+
+        let kept = self.live_sibling(PREVIOUS_PATHS_SUFFIX);
+        self.discard(&kept);
+        for path in &self.extra_paths {
+            info!(path = %path.live.display(), "restore");
+            self.copy_extra_path(&path.live, &kept.join(&path.stored), Codec::Plain)?;
+            remove_extra_path(&path.live)?;
+            self.copy_extra_path(&account_dir.join(&path.stored), &path.live, codec)?;
+            if self.system.is_some() && path.live.exists() {
+                system::make_private(&path.live)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Puts back the paths outside the Claude directory a switch that didn't
+    /// go through had replaced. They're left beside the live directory when
+    /// one can't be.
+    fn put_back_extra_paths(&self) {
+        let kept = self.live_sibling(PREVIOUS_PATHS_SUFFIX);
+        if !kept.is_dir() {
+            return;
+        }
+        let mut put_back = true;
+        for path in &self.extra_paths {
+            let saved = kept.join(&path.stored);
+            if !saved.exists() {
+                continue;
+            }
+            put_back &= remove_extra_path(&path.live).is_ok()
+                && self
+                    .copy_extra_path(&saved, &path.live, Codec::Plain)
+                    .is_ok();
+        }
+        if put_back {
+            self.discard(&kept);
+        }
+    }
+
+    /// Puts back the paths outside the Claude directory kept with the live
+    /// backup just moved into place, the ones they replace going with the
+    /// live directory it replaced.
+    fn restore_kept_paths(&self) -> Result<()> {
+        let live = &self.claude_config_dir;
+        if !live.join(extra_paths::DIR).is_dir() {
+            return Ok(());
+        }
+        self.restore_extra_paths(live, false)
+            .with_context(|| tr!("failed-restore-files-outside-claude-directory"))?;
+        self.discard(&live.join(extra_paths::DIR));
+        Ok(())
+    }
+
+    /// Copies the file or directory at `src` to `dst`, when there is one.
+    fn copy_extra_path(&self, src: &Path, dst: &Path, codec: Codec) -> Result<()> {
+        if src.is_dir() {
+            fs::create_dir_all(dst)
+                .with_context(|| tr!("failed-create-directory", path = dst.display()))?;
+            self.copy_dir_recursive(
+                src,
+                dst,
+                Path::new(""),
+                &CapturePolicy::default(),
+                codec,
+                None,
+            )?;
+        } else if src.is_file() {
+            if let Some(parent) = dst.parent() {
+                fs::create_dir_all(parent)
+                    .with_context(|| tr!("failed-create-directory", path = parent.display()))?;
+            }
+            copy_file(&*self.storage, src, dst, codec).with_context(|| {
+                tr!(
+                    "failed-copy-file-from-to",
+                    from = src.display(),
+                    to = dst.display()
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    fn saved_credentials(&self, name: &str, account_dir: &Path) -> Result<Option<String>> {
+        if self.uses_keychain()
+            && let Some(credentials) = self.credstore.load(name)?
+        {
+            return Ok(Some(credentials));
+        }
+        self.read_stored(&account_dir.join(CREDENTIALS_FILE))
+    }
+
+    /// Reads a text file from a snapshot, decrypting it if needed.
+    fn read_stored(&self, path: &Path) -> Result<Option<String>> {
+        Ok(self
+            .read_stored_bytes(path)?
+            .and_then(|data| String::from_utf8(data).ok()))
+    }
+
+    fn read_stored_bytes(&self, path: &Path) -> Result<Option<Vec<u8>>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let data =
+            fs::read(path).with_context(|| tr!("failed-read-file", path = path.display()))?;
+        let data = if crypto::is_sealed(&data) {
+            self.vault()?.open(data)?
+        } else {
+            data
+        };
+        Ok(Some(compression::decompress(data)?))
+    }
+
+    /// Reads a file from the live configuration as a snapshot would hold
+    /// it, without the switch-time banner. A `CLAUDE.md` holding nothing but
+    /// the banner was made by the switch, so it counts as absent.
+    fn read_live(&self, relative: &Path) -> Result<Option<Vec<u8>>> {
+        let path = self.claude_config_dir.join(relative);
+        let data =
+            fs::read(&path).with_context(|| tr!("failed-read-file", path = path.display()))?;
+        if relative != Path::new(banner::FILE) {
+            return Ok(Some(data));
+        }
+        Ok(match String::from_utf8(data) {
+            Ok(contents) => {
+                let stripped = banner::strip(&contents);
+                if stripped.is_empty() && stripped != contents {
+                    None
+                } else {
+                    Some(stripped.into_bytes())
+                }
+            }
+            Err(e) => Some(e.into_bytes()),
+        })
+    }
+
+    /// Hashes the captured live files, as a snapshot would hold them. Files
+    /// `known` has with the same size and modification time aren't read.
+    fn live_manifest(&self, known: Option<&Manifest>) -> Result<Manifest> {
+        let captured = timings::time(Phase::Scanning, || {
+            self.captured_files(&self.claude_config_dir)
+        })?;
+        let live = &self.claude_config_dir;
+        let mut manifest = Manifest::default();
+        timings::time(Phase::Hashing, || {
+            hashing::for_each_parallel(
+                captured.into_iter().filter(|path| {
+                    !claude::is_credential_file(path) && path != Path::new(banner::FILE)
+                }),
+                hashing::default_jobs(),
+                |path| {
+                    let hash = live_file_hash(
+                        &live.join(&path),
+                        known.and_then(|known| known.files.get(&path)),
+                    );
+                    (path, hash)
+                },
+                |(path, hash)| {
+                    manifest.files.insert(path, hash?);
+                    Ok(())
+                },
+            )
+        })?;
+        self.finish_manifest(manifest)
+    }
+
+    /// Adds `CLAUDE.md` to a manifest of the other live files, hashed
+    /// without the banner, as it's saved.
+    fn finish_manifest(&self, mut manifest: Manifest) -> Result<Manifest> {
+        let banner_file = Path::new(banner::FILE);
+        let path = self.claude_config_dir.join(banner_file);
+        if !self.capture.captures(banner_file) || !path.is_file() {
+            return Ok(manifest);
+        }
+        let hashed = SystemTime::now();
+        let metadata = fs::metadata(&path).with_context(|| tr!("failed-read-claude-md"))?;
+        if let Some(data) = self.read_live(banner_file)? {
+            manifest.files.insert(
+                banner_file.to_path_buf(),
+                FileHash {
+                    sha256: hashing::hash_bytes(&data),
+                    size: metadata.len(),
+                    modified: metadata
+                        .modified()
+                        .with_context(|| tr!("failed-read-claude-md"))?,
+                    hashed: Some(hashed),
+                },
+            );
+        }
+        Ok(manifest)
+    }
+
+    /// Keeps `manifest` as what the live configuration holds now that it
+    /// matches the snapshot of `name`. A manifest that can't be written is dropped
+    /// instead, so the next switch is saved as it was before manifests.
+    fn record_manifest(&self, name: &str, manifest: Result<Manifest>) {
+        let path = manifest::path(&self.state_dir, name);
+        if let Err(e) = manifest.and_then(|manifest| manifest.save(&path)) {
+            let _ = fs::remove_file(&path);
+            output::warn(tr!("manifest-failed", error = format!("{:#}", e)));
+        }
+    }
+
+    /// Brings the snapshot in `account_dir` up to date with the live
+    /// configuration, copying only the files that changed since `saved` was
+    /// recorded. The others are taken from `base`: the snapshot itself when
+    /// it's updated in place, or the version it was just moved aside to.
+    /// Returns what was copied and the manifest of the new snapshot, still
+    /// without `CLAUDE.md`.
+    fn save_changes(
+        &self,
+        account_dir: &Path,
+        base: &Path,
+        saved: &Manifest,
+        codec: Codec,
+    ) -> Result<(Transfer, Manifest)> {
+        let started = Instant::now();
+        let in_place = base == account_dir;
+        let captured = timings::time(Phase::Scanning, || {
+            self.captured_files(&self.claude_config_dir)
+        })?;
+        let mut manifest = Manifest::default();
+        let mut usage = DirUsage::default();
+
+        for path in &captured {
+            let live = self.claude_config_dir.join(path);
+            let dst = account_dir.join(path);
+            let metadata = fs::metadata(&live)
+                .with_context(|| tr!("failed-read-file", path = live.display()))?;
+            // Credentials and CLAUDE.md are never in the manifest, so
+            // they're always copied
+            let unchanged = match saved.files.get(path) {
+                Some(file) if self.storage.is_file(&base.join(path)) => {
+                    unchanged_file(&live, file, &metadata)?
+                }
+                _ => None,
+            };
+
+            if let Some(parent) = dst.parent() {
+                self.storage
+                    .create_dir_all(parent)
+                    .with_context(|| tr!("failed-create-directory", path = parent.display()))?;
+            }
+            if let Some(file) = unchanged {
+                if !in_place {
+                    timings::time(Phase::Copying, || {
+                        take_unchanged(&base.join(path), &dst, self.dedup_enabled())
+                    })?;
+                }
+                manifest.files.insert(path.clone(), file);
+                continue;
+            }
+
+            usage.bytes += timings::time(Phase::Copying, || {
+                copy_file(&*self.storage, &live, &dst, codec)
+            })
+            .with_context(|| {
+                tr!(
+                    "failed-copy-file-from-to",
+                    from = live.display(),
+                    to = dst.display()
+                )
+            })?;
+            usage.files += 1;
+            if !claude::is_credential_file(path) && path != Path::new(banner::FILE) {
+                let hash = timings::time(Phase::Hashing, || live_file_hash(&live, None))?;
+                manifest.files.insert(path.clone(), hash);
+            }
+        }
+
+        for path in self.captured_symlinks(&self.claude_config_dir)? {
+            let dst = account_dir.join(&path);
+            if let Some(parent) = dst.parent() {
+                self.storage
+                    .create_dir_all(parent)
+                    .with_context(|| tr!("failed-create-directory", path = parent.display()))?;
+            }
+            self.storage
+                .copy_symlink(&self.claude_config_dir.join(&path), &dst)
+                .with_context(|| tr!("failed-recreate-symlink", path = dst.display()))?;
+        }
+
+        // Only what was saved before is removed; anything else in the
+        // directory isn't this snapshot's to delete
+        if in_place {
+            for path in saved.files.keys().filter(|path| !captured.contains(*path)) {
+                let dst = account_dir.join(path);
+                if self.storage.is_file(&dst) {
+                    self.storage
+                        .remove_file(&dst)
+                        .with_context(|| tr!("failed-remove-file", path = dst.display()))?;
+                }
+            }
+        }
+
+        Ok((
+            Transfer {
+                usage,
+                elapsed: started.elapsed(),
+            },
+            manifest,
+        ))
+    }
+
+    /// Whether a switch saves the outgoing account `name` when it isn't
+    /// told to discard its changes. Changes always are. Even with nothing
+    /// changed a save used to keep whatever the checks can't see, so it
+    /// still happens unless `--no-autosave` or the `autosave` setting say
+    /// not to, or `name` was saved or switched to within the debounce;
+    /// `--autosave` always saves. `changes`, when already known, saves
+    /// working them out again.
+    fn autosaves(
+        &self,
+        config: &AccountsConfig,
+        name: &str,
+        changes: Option<&BTreeSet<PathBuf>>,
+    ) -> Result<bool> {
+        if self.autosave == Some(true) {
+            return Ok(true);
+        }
+        let Some(meta) = config.get_account(name) else {
+            return Ok(true);
+        };
+        let latest = [Some(meta.saved_at.as_str()), meta.last_used.as_deref()]
+            .into_iter()
+            .flatten()
+            .filter_map(|at| DateTime::parse_from_rfc3339(at).ok())
+            .max();
+        // Without a manifest there's no telling what changed
+        let changed = match changes {
+            _ if !manifest::path(&self.state_dir, name).exists() => true,
+            Some(changes) => !changes.is_empty(),
+            None => !self.unsaved_changes(config, name)?.is_empty(),
+        } || self.login_or_extra_paths_differ(meta)?;
+        if changed {
+            return Ok(true);
+        }
+        if !self.autosave.unwrap_or(self.settings.autosave()) {
+            tracing::info!(name, "nothing changed, autosave off");
+            return Ok(false);
+        }
+        let recent = latest.is_some_and(|at| {
+            self.clock.now() - at.with_timezone(&Utc)
+                < self.settings.autosave_debounce().unwrap_or_default()
+        });
+        if recent {
+            tracing::info!(name, "nothing changed since a moment ago, not saved again");
+        }
+        Ok(!recent)
+    }
+
+    /// Whether the live login, which Claude Code refreshes on first use, or
+    /// a path outside the Claude directory differs from what `meta`'s
+    /// snapshot holds; the manifest leaves them out. What can't be compared
+    /// file by file counts as different.
+    fn login_or_extra_paths_differ(&self, meta: &AccountMetadata) -> Result<bool> {
+        if self.uses_keychain() {
+            return Ok(true);
+        }
+        let login = (
+            self.claude_config_dir.join(CREDENTIALS_FILE),
+            meta.path.join(CREDENTIALS_FILE),
+        );
+        let extra = self
+            .extra_paths
+            .iter()
+            .map(|path| (path.live.clone(), meta.path.join(&path.stored)));
+        for (live, stored) in std::iter::once(login).chain(extra) {
+            if live.is_dir() || stored.is_dir() {
+                return Ok(true);
+            }
+            if fs::read(&live).ok() != self.read_stored_bytes(&stored)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// The live files that changed since `name` was last saved or switched
+    /// to. Without a manifest (saved by an earlier version) nothing counts
+    /// as changed.
+    fn unsaved_changes(&self, config: &AccountsConfig, name: &str) -> Result<BTreeSet<PathBuf>> {
+        let Some(meta) = config.get_account(name) else {
+            return Ok(BTreeSet::new());
+        };
+        // Linked, the live directory is the snapshot
+        if platform::same_location(&self.claude_config_dir, &meta.path) {
+            return Ok(BTreeSet::new());
+        }
+        match Manifest::load(&manifest::path(&self.state_dir, name))? {
+            Some(saved) => Ok(saved.changes(&self.live_manifest(Some(&saved))?)),
+            None => Ok(BTreeSet::new()),
+        }
+    }
+
+    /// The files below `dir` the capture policy covers, relative to it.
+    fn captured_files(&self, dir: &Path) -> Result<BTreeSet<PathBuf>> {
+        let mut files = BTreeSet::new();
+        self.collect_captured(dir, Path::new(""), false, &mut files)?;
+        Ok(files)
+    }
+
+    /// The symlinks below `dir` that are captured, which `captured_files`
+    /// leaves out.
+    fn captured_symlinks(&self, dir: &Path) -> Result<BTreeSet<PathBuf>> {
+        let mut links = BTreeSet::new();
+        self.collect_captured(dir, Path::new(""), true, &mut links)?;
+        Ok(links)
+    }
+
+    fn collect_captured(
+        &self,
+        dir: &Path,
+        relative: &Path,
+        symlinks: bool,
+        files: &mut BTreeSet<PathBuf>,
+    ) -> Result<()> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => {
+                return Err(e).with_context(|| tr!("failed-read-directory", path = dir.display()));
+            }
+        };
+        for entry in entries {
+            let entry = entry?;
+            let relative = relative.join(entry.file_name());
+            // What a snapshot keeps from outside the Claude directory
+            if relative == Path::new(extra_paths::DIR) {
+                continue;
+            }
+            let file_type = entry.file_type()?;
+            let wanted = match symlinks {
+                true => file_type.is_symlink(),
+                false => file_type.is_file(),
+            };
+            if file_type.is_dir() {
+                if !self.capture.skips_dir(&relative) {
+                    self.collect_captured(&entry.path(), &relative, symlinks, files)?;
+                }
+            } else if wanted && self.capture.captures(&relative) {
+                files.insert(relative);
+            }
+        }
+        Ok(())
+    }
+
+    /// Puts `text` at the top of the live `CLAUDE.md`. Not when the live
+    /// directory is a link: the file is the snapshot's own then, and the
+    /// banner would be saved with it.
+    fn write_banner(&self, text: &str) -> Result<()> {
+        if self.storage.is_symlink(&self.claude_config_dir) {
+            return Ok(());
+        }
+        let path = self.claude_config_dir.join(banner::FILE);
+        let contents = if self.storage.exists(&path) {
+            let data = self
+                .storage
+                .read(&path)
+                .with_context(|| tr!("failed-read-claude-md"))?;
+            String::from_utf8(data).with_context(|| tr!("failed-read-claude-md"))?
+        } else {
+            String::new()
+        };
+        self.storage
+            .write_replacing(&path, banner::insert(&contents, text).as_bytes())
+            .with_context(|| tr!("failed-write-claude-md"))
+    }
+
+    /// Keeps the switch-time banner out of the saved snapshot.
+    fn strip_banner(&self, account_dir: &Path) -> Result<()> {
+        let path = account_dir.join(banner::FILE);
+        if !self.storage.exists(&path) {
+            return Ok(());
+        }
+
+        let data = self
+            .storage
+            .read(&path)
+            .with_context(|| tr!("failed-read-saved-claude-md"))?;
+        let sealed = crypto::is_sealed(&data);
+        let data = if sealed {
+            self.vault()?.open(data)?
+        } else {
+            data
+        };
+        let compressed = compression::is_compressed(&data);
+        let Ok(contents) = String::from_utf8(compression::decompress(data)?) else {
+            return Ok(());
+        };
+
+        let stripped = banner::strip(&contents);
+        if stripped == contents {
+            return Ok(());
+        }
+        // The file only existed to carry the banner
+        if stripped.is_empty() {
+            return self
+                .storage
+                .remove_file(&path)
+                .with_context(|| tr!("failed-remove-saved-claude-md"));
+        }
+
+        let data = match self.settings.compression_level()? {
+            Some(level) if compressed => compression::compress(stripped.into_bytes(), level)?,
+            _ => stripped.into_bytes(),
+        };
+        let data = if sealed {
+            self.vault()?.seal(data)?
+        } else {
+            data
+        };
+        self.storage
+            .write(&path, &data)
+            .with_context(|| tr!("failed-write-saved-claude-md"))
+    }
+
+    fn restore_keychain_credentials(&self, name: &str) -> Result<()> {
+        // Accounts saved before the keychain was enabled still carry their own file
+        let Some(credentials) = self.credstore.load(name)? else {
+            return Ok(());
+        };
+
+        platform::write_private(
+            &self.claude_config_dir.join(CREDENTIALS_FILE),
+            credentials.as_bytes(),
+        )
+        .with_context(|| tr!("failed-restore-credentials-file"))
+    }
+
+    /// A path next to the live directory, so renames between the two stay
+    /// on one filesystem.
+    fn live_sibling(&self, suffix: &str) -> PathBuf {
+        let mut path = self.claude_config_dir.clone().into_os_string();
+        path.push(suffix);
+        PathBuf::from(path)
+    }
+
+    /// Creates a symlink to `target` next to the live directory, ready to be
+    /// renamed over it.
+    fn stage_link(&self, target: &Path) -> Result<PathBuf> {
+        let staged = self.live_sibling(".cas-link");
+
+        // Left over from an interrupted switch
+        let _ = platform::remove_symlink(&staged);
+        platform::symlink_dir(target, &staged)
+            .with_context(|| tr!("failed-create-symlink", path = staged.display()))?;
+        Ok(staged)
+    }
+
+    /// Starts a detached `resave` process when `resave-after` is configured,
+    /// so the switch itself returns immediately.
+    fn schedule_resave(&self, name: &str) -> Result<()> {
+        let Some(delay) = self.settings.resave_delay()? else {
+            return Ok(());
+        };
+
+        let exe =
+            std::env::current_exe().with_context(|| tr!("failed-locate-switcher-executable"))?;
+        Command::new(exe)
+            .args([
+                "resave",
+                "--after",
+                &delay.as_secs().to_string(),
+                "--",
+                name,
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .with_context(|| tr!("failed-start-background-re-save"))?;
+
+        self.say(tr!(
+            "resave-scheduled",
+            name = name,
+            duration = stats::format_duration(delay)
+        ));
+        Ok(())
+    }
+
+    /// Copies `src` into `dst`, showing an estimate beforehand when the rolling
+    /// throughput suggests it'll take a while, and the files and bytes copied
+    /// so far while it runs.
+    fn timed_copy(
+        &self,
+        src: &Path,
+        dst: &Path,
+        policy: &CapturePolicy,
+        codec: Codec,
+    ) -> Result<Transfer> {
+        let stats_file = self.state_dir.join("stats.json");
+        let mut stats = TransferStats::load(&stats_file);
+
+        let (mut pending, mut files) = (0, 0);
+        for (relative, size) in
+            timings::time(Phase::Scanning, || storage::files(&*self.storage, src))?
+        {
+            if policy.captures(&relative) {
+                pending += size;
+                files += 1;
+            }
+        }
+        if let Some(eta) = stats.estimate(pending) {
+            eprintln!(
+                "{}",
+                tr!(
+                    "copy-eta",
+                    size = size::format_bytes(pending),
+                    duration = stats::format_duration(eta)
+                )
+            );
+        }
+
+        let quiet = self.quiet || self.format.is_json();
+        let mut progress = Progress::new(tr!("copy-progress"), files, quiet).with_bytes(pending);
+        let started = Instant::now();
+        let usage =
+            self.copy_dir_recursive(src, dst, Path::new(""), policy, codec, Some(&mut progress))?;
+        let elapsed = started.elapsed();
+        drop(progress);
+
+        stats.record(usage.bytes, elapsed);
+        let _ = stats.save(&stats_file);
+
+        Ok(Transfer { usage, elapsed })
+    }
+
+    /// Removes a leftover of a switch: a symlink itself, never what it points
+    /// to, or a directory. Failures are left for the next switch to clear.
+    fn discard(&self, path: &Path) {
+        let storage = &*self.storage;
+        if storage.is_symlink(path) || storage.exists(path) {
+            debug!(path = %path.display(), "remove");
+        }
+        if storage.is_symlink(path) {
+            let _ = storage.remove_symlink(path);
+        } else if storage.exists(path) {
+            let _ = retry(|| storage.remove_dir_all(path));
+        }
+    }
+
+    /// Moves a directory tree, copying it when it lives on another filesystem.
+    fn move_dir(&self, src: &Path, dst: &Path) -> Result<()> {
+        info!(from = %src.display(), to = %dst.display(), "move");
+        if retry(|| self.storage.rename(src, dst)).is_ok() {
+            return Ok(());
+        }
+
+        self.storage
+            .create_dir_all(dst)
+            .with_context(|| tr!("failed-create-directory", path = dst.display()))?;
+        self.copy_dir_recursive(
+            src,
+            dst,
+            Path::new(""),
+            &CapturePolicy::default(),
+            Codec::Plain,
+            None,
+        )?;
+        retry(|| self.storage.remove_dir_all(src))
+            .with_context(|| tr!("failed-remove-directory", path = src.display()))
+    }
+
+    /// Copies the files under `src` that `policy` captures; `relative` is the
+    /// position of `src` within the tree being copied. Each file copied
+    /// advances `progress`.
+    ///
+    /// The tree is walked and its directories created first, then the files
+    /// are copied on a thread per core: on SSDs and network filesystems a
+    /// large configuration is mostly spent waiting for each file in turn.
+    fn copy_dir_recursive(
+        &self,
+        src: &Path,
+        dst: &Path,
+        relative: &Path,
+        policy: &CapturePolicy,
+        codec: Codec,
+        mut progress: Option<&mut Progress>,
+    ) -> Result<DirUsage> {
+        let mut files = Vec::new();
+        timings::time(Phase::Scanning, || {
+            plan_copy(&*self.storage, src, dst, relative, policy, &mut files)
+        })?;
+
+        let mut usage = DirUsage::default();
+        timings::time(Phase::Copying, || {
+            hashing::for_each_parallel(
+                files.into_iter(),
+                hashing::default_jobs(),
+                |planned: PlannedCopy| {
+                    let (src_path, dst_path) = (&planned.src, &planned.dst);
+                    if planned.symlink {
+                        debug!(from = %src_path.display(), to = %dst_path.display(), "link");
+                        return self
+                            .storage
+                            .copy_symlink(src_path, dst_path)
+                            .map(|()| None)
+                            .with_context(|| {
+                                tr!("failed-recreate-symlink", path = dst_path.display())
+                            });
+                    }
+                    debug!(from = %src_path.display(), to = %dst_path.display(), "copy");
+                    copy_file(&*self.storage, src_path, dst_path, codec)
+                        .map(Some)
+                        .with_context(|| {
+                            tr!(
+                                "failed-copy-file-from-to",
+                                from = src_path.display(),
+                                to = dst_path.display()
+                            )
+                        })
+                },
+                |bytes| {
+                    interrupt::check()?;
+                    // Symlinks aren't counted, as `dir_usage` doesn't count them
+                    let Some(bytes) = bytes? else {
+                        return Ok(());
+                    };
+                    usage.bytes += bytes;
+                    usage.files += 1;
+                    if let Some(progress) = progress.as_deref_mut() {
+                        progress.advance_by(bytes);
+                    }
+                    Ok(())
+                },
+            )
+        })?;
+        Ok(usage)
+    }
+}
+
+/// What `list` points out about an account, see
+/// [`AccountManager::attention`].
+struct Attention {
+    expires_at: Option<DateTime<Utc>>,
+    stale_days: Option<i64>,
+    expiry: Option<health::Expiry>,
+}
+
+impl Attention {
+    fn applied_to<'a>(&self, row: AccountRow<'a>) -> AccountRow<'a> {
+        AccountRow {
+            expires_at: self.expires_at,
+            stale_days: self.stale_days,
+            expiry: self.expiry,
+            ..row
+        }
+    }
+}
+
+/// A file or symlink `copy_dir_recursive` copies.
+struct PlannedCopy {
+    src: PathBuf,
+    dst: PathBuf,
+    /// Where it is in the tree being copied
+    relative: PathBuf,
+    symlink: bool,
+}
+
+/// Creates the directories of `src` that `policy` keeps under `dst`, and
+/// lists the files and symlinks to copy into them, for `copy_dir_recursive`.
+fn plan_copy(
+    storage: &dyn Storage,
+    src: &Path,
+    dst: &Path,
+    relative: &Path,
+    policy: &CapturePolicy,
+    files: &mut Vec<PlannedCopy>,
+) -> Result<()> {
+    for entry in storage
+        .read_dir(src)
+        .with_context(|| tr!("failed-read-directory", path = src.display()))?
+    {
+        let src_path = src.join(&entry.name);
+        let dst_path = platform::long_path(&dst.join(&entry.name)).into_owned();
+        let relative = relative.join(&entry.name);
+
+        if entry.kind == storage::Kind::Dir {
+            if policy.skips_dir(&relative) {
+                trace!(path = %relative.display(), "skip directory");
+                continue;
+            }
+            // With includes, directories only appear once something in them is captured
+            if !policy.has_includes() {
+                storage
+                    .create_dir_all(&dst_path)
+                    .with_context(|| tr!("failed-create-directory", path = dst_path.display()))?;
+            }
+            plan_copy(storage, &src_path, &dst_path, &relative, policy, files)?;
+        } else {
+            if !policy.captures(&relative) {
+                trace!(path = %relative.display(), "skip file");
+                continue;
+            }
+            if policy.has_includes() {
+                storage
+                    .create_dir_all(dst)
+                    .with_context(|| tr!("failed-create-directory", path = dst.display()))?;
+            }
+            files.push(PlannedCopy {
+                src: src_path,
+                dst: dst_path,
+                relative,
+                symlink: entry.kind == storage::Kind::Symlink,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Copies one file, passing its contents through `codec`, with its mode and
+/// modification time (see `fidelity`). Returns the plaintext size, so
+/// statistics don't depend on whether the store is encrypted.
+///
+/// A file already at `dst` is replaced rather than written over, since it
+/// may share its contents with other snapshots.
+fn copy_file(storage: &dyn Storage, src: &Path, dst: &Path, codec: Codec) -> Result<u64> {
+    if storage.is_file(dst) {
+        retry(|| storage.remove_file(dst))?;
+    }
+    if codec.keeps_in(storage, src)? {
+        return Ok(retry(|| storage.copy(src, dst))?);
+    }
+
+    let data = retry(|| storage.read(src))?;
+    let original = data.len();
+    let data = codec.apply(data)?;
+    retry(|| storage.write(dst, &data))?;
+    storage.keep_metadata(src, dst)?;
+
+    // The plaintext is what went in, or what came out
+    Ok(match codec {
+        Codec::Load { .. } => data.len(),
+        _ => original,
+    } as u64)
+}
+
+/// Refuses a path `restore` or `merge` can't copy: one leaving the Claude
+/// directory, or a credentials file, since swapping the login alone would
+/// leave the rest signed in as another account.
+fn ensure_settings_file(file: &Path) -> Result<()> {
+    if !file
+        .components()
+        .all(|component| matches!(component, std::path::Component::Normal(_)))
+    {
+        anyhow::bail!(tr!("file-bad-path", path = file.display()));
+    }
+    if claude::is_credential_file(file) {
+        anyhow::bail!(tr!("file-credentials", path = file.display()));
+    }
+    Ok(())
+}
+
+/// Each account's `saved_at`, for the accounts `sync` takes part in: the
+/// ones in the store, not those registered with `add-existing`.
+fn synced_saves(config: &AccountsConfig) -> BTreeMap<String, String> {
+    config
+        .accounts
+        .iter()
+        .filter(|(_, meta)| !meta.external)
+        .map(|(name, meta)| (name.clone(), meta.saved_at.clone()))
+        .collect()
+}
+
+/// Writes the synced snapshot in `source` to `dest` opened, refusing any
+/// file that isn't sealed.
+fn unpack_synced(source: &Path, dest: &Path, vault: &Vault) -> Result<()> {
+    fs::create_dir_all(dest).with_context(|| tr!("failed-create-account-directory"))?;
+    for entry in store::walk_dir(source) {
+        let entry = entry?;
+        let data = fs::read(&entry.path)
+            .with_context(|| tr!("failed-read-file", path = entry.path.display()))?;
+        if !crypto::is_sealed(&data) {
+            anyhow::bail!(tr!("sync-unsealed", path = entry.relative_path.display()));
+        }
+        let target = dest.join(&entry.relative_path);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).with_context(|| tr!("failed-create-account-directory"))?;
+        }
+        platform::write_private(&target, &vault.open(data)?)
+            .with_context(|| tr!("failed-write-file", path = target.display()))?;
+    }
+    Ok(())
+}
+
+/// Whether a switch preview shows how `path` changes: Claude Code's settings
+/// files, `settings.json` and `settings.local.json`.
+/// What an interrupted operation was, for the user to choose what to do
+/// about it.
+fn interrupted_message(marker: &Marker) -> String {
+    let time = marker.started_at.format("%Y-%m-%d %H:%M UTC");
+    match marker.operation {
+        Operation::Save => tr!("interrupted-save", name = marker.account, time = time),
+        Operation::Switch => tr!("interrupted-switch", name = marker.account, time = time),
+    }
+}
+
+fn is_settings_file(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with("settings") && name.ends_with(".json"))
+}
+
+/// The snapshots of other accounts inside `dir`, relative to it, as nested
+/// account names (`client` and `client/dev`) put them.
+fn nested_snapshots(config: &AccountsConfig, dir: &Path) -> Vec<PathBuf> {
+    config
+        .accounts
+        .values()
+        .filter_map(|other| other.path.strip_prefix(dir).ok())
+        .filter(|relative| !relative.as_os_str().is_empty())
+        .map(Path::to_path_buf)
+        .collect()
+}
+
+/// `path` with `/` between its parts, as capture patterns have them.
+fn slashed(path: &Path) -> String {
+    path.components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// A `sha256sum` line: the hash and, two spaces on, a path relative to the
+/// snapshot. `None` for anything else, a path that would lead out of the
+/// snapshot included.
+fn checksum_line(line: &str) -> Option<(String, PathBuf)> {
+    let (hash, path) = line.split_once("  ")?;
+    if hash.len() != 64 || !hash.bytes().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let path = archive::safe_path_under(Path::new(path), Path::new(""))?;
+    (!path.as_os_str().is_empty()).then(|| (hash.to_ascii_lowercase(), path))
+}
+
+/// Removes the files in `dir` that `strip` doesn't capture, apart from
+/// credentials and the `nested` snapshots, then the directories that leaves
+/// empty. Returns what they took up and the paths removed; with `dry_run`
+/// they're only counted.
+fn strip_files(
+    dir: &Path,
+    strip: &CapturePolicy,
+    nested: &[PathBuf],
+    dry_run: bool,
+) -> Result<(DirUsage, BTreeSet<PathBuf>)> {
+    let mut stripped = DirUsage::default();
+    let mut removed = BTreeSet::new();
+    let mut parents = BTreeSet::new();
+    for entry in store::walk_dir(dir).skip_dirs(nested) {
+        let entry = entry?;
+        if strip.captures(&entry.relative_path) || claude::is_credential_file(&entry.relative_path)
+        {
+            continue;
+        }
+        if !dry_run {
+            retry(|| fs::remove_file(&entry.path))
+                .with_context(|| tr!("failed-remove-file", path = entry.path.display()))?;
+            parents.extend(
+                entry
+                    .relative_path
+                    .ancestors()
+                    .skip(1)
+                    .map(Path::to_path_buf),
+            );
+            removed.insert(entry.relative_path);
+        }
+        stripped.files += 1;
+        stripped.bytes += entry.size;
+    }
+
+    // Deepest first, so a parent is only tried once its children are gone
+    for parent in parents
+        .iter()
+        .rev()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        let _ = fs::remove_dir(dir.join(parent));
+    }
+    Ok((stripped, removed))
+}
+
+/// Whether any file in a snapshot is stored compressed. An unreadable one
+/// counts too, so a switch copies rather than link to what it can't check.
+fn holds_compressed(dir: &Path) -> bool {
+    let load = Codec::Load { vault: None };
+    store::walk_dir(dir).any(|entry| match entry {
+        Ok(entry) => !load.keeps(&entry.path).unwrap_or(false),
+        Err(_) => true,
+    })
+}
+
+/// The manifest entry for a live file: `known` when the file still has its
+/// size and modification time, otherwise a fresh hash. The metadata is read
+/// first, so a change made while hashing is noticed next time.
+fn live_file_hash(path: &Path, known: Option<&FileHash>) -> Result<FileHash> {
+    let hashed = SystemTime::now();
+    let metadata =
+        fs::metadata(path).with_context(|| tr!("failed-read-file", path = path.display()))?;
+    if let Some(known) = known.filter(|known| known.matches(&metadata)) {
+        return Ok(known.clone());
+    }
+    Ok(FileHash {
+        sha256: hashing::hash_file(path)?,
+        size: metadata.len(),
+        modified: metadata
+            .modified()
+            .with_context(|| tr!("failed-read-file", path = path.display()))?,
+        hashed: Some(hashed),
+    })
+}
+
+/// Removes the file or directory at `path`, when there is one.
+fn remove_extra_path(path: &Path) -> Result<()> {
+    if path.is_dir() && !path.is_symlink() {
+        retry(|| fs::remove_dir_all(path))
+    } else if path.exists() || path.is_symlink() {
+        platform::make_writable(path)?;
+        retry(|| fs::remove_file(path))
+    } else {
+        Ok(())
+    }
+    .with_context(|| tr!("failed-remove", path = path.display()))
+}
+
+/// The manifest entry for a live file that still holds what `saved` says
+/// it did, or `None` when it changed. A file whose size and modification
+/// time can't be trusted on their own is hashed again to tell.
+fn unchanged_file(
+    live: &Path,
+    saved: &FileHash,
+    metadata: &fs::Metadata,
+) -> Result<Option<FileHash>> {
+    if !saved.same_metadata(metadata) {
+        return Ok(None);
+    }
+    if !saved.is_racy() {
+        return Ok(Some(saved.clone()));
+    }
+    let fresh = timings::time(Phase::Hashing, || live_file_hash(live, None))?;
+    Ok((fresh.sha256 == saved.sha256).then_some(fresh))
+}
+
+/// Puts an unchanged file from the previous snapshot into the new one as it
+/// is stored, sealed or not: a clone where the filesystem can make one, or
+/// a copy. A hard link only to a stored object, when `link` says they're
+/// shared anyway; otherwise a linked live directory would write through it into
+/// the version.
+fn take_unchanged(src: &Path, dst: &Path, link: bool) -> Result<()> {
+    if link && objects::is_linked(src)? && fs::hard_link(src, dst).is_ok() {
+        return Ok(());
+    }
+    if platform::clone_file(src, dst).is_ok() {
+        return Ok(());
+    }
+    retry(|| fs::copy(src, dst)).map(drop).with_context(|| {
+        tr!(
+            "failed-copy-file-from-to",
+            from = src.display(),
+            to = dst.display()
+        )
+    })
+}
+
+// Drops fractional seconds and the offset, like the list output does
+fn short_time(timestamp: &str) -> &str {
+    timestamp.get(..19).unwrap_or(timestamp)
+}
+
+fn account_row<'a>(
+    name: &'a str,
+    meta: &'a AccountMetadata,
+    config: &'a AccountsConfig,
+    number: usize,
+    usage: Option<&DirUsage>,
+    long: bool,
+    now: DateTime<Utc>,
+) -> AccountRow<'a> {
+    AccountRow {
+        number,
+        name,
+        aliases: config.aliases_of(name),
+        current: config.current.as_deref() == Some(name),
+        saved_at: &meta.saved_at,
+        identity: meta.identity.as_ref(),
+        tags: meta.tags.iter().map(String::as_str).collect(),
+        note: meta.note.as_deref().filter(|_| long),
+        limited_for: quota::active(meta.limited_until.as_deref(), now).map(|until| until - now),
+        archived: meta.archive.is_some(),
+        usage: usage.copied(),
+        last_used: meta.last_used.as_deref(),
+        unsaved: None,
+        expires_at: None,
+        stale_days: None,
+        expiry: None,
+    }
+}
+
+/// Each account's number in `list`, whatever the sort.
+fn numbers(config: &AccountsConfig) -> HashMap<&str, usize> {
+    config
+        .accounts
+        .keys()
+        .map(|name| (name.as_str(), config.number_of(name).unwrap_or_default()))
+        .collect()
+}
+
+/// Puts `accounts`, sorted by name, in `sort` order. Ties stay in name
+/// order.
+fn sort_accounts(
+    accounts: &mut [(&String, &AccountMetadata)],
+    sort: ListSort,
+    usages: &BTreeMap<&str, DirUsage>,
+) {
+    let time = |value: &str| DateTime::parse_from_rfc3339(value).ok();
+    match sort {
+        ListSort::Name => {}
+        ListSort::Saved => accounts.sort_by_key(|(_, meta)| Reverse(time(&meta.saved_at))),
+        ListSort::LastUsed => {
+            accounts.sort_by_key(|(_, meta)| Reverse(meta.last_used.as_deref().and_then(time)))
+        }
+        ListSort::Size => accounts.sort_by_key(|(name, _)| {
+            Reverse(usages.get(name.as_str()).map_or(0, |usage| usage.bytes))
+        }),
+    }
+}
+
+/// Whether `name` is tagged `group` or in the namespace of that name.
+fn in_group(group: &str, name: &str, meta: &AccountMetadata) -> bool {
+    meta.tags.contains(group)
+        || name
+            .strip_prefix(group)
+            .is_some_and(|rest| rest.starts_with(names::SEPARATOR))
+}
+
+/// The groups `name` goes in, an empty one standing for accounts with no
+/// namespace or no tag.
+fn group_keys(group_by: GroupBy, name: &str, meta: &AccountMetadata) -> Vec<String> {
+    match group_by {
+        GroupBy::Namespace => vec![
+            name.rsplit_once('/')
+                .map(|(namespace, _)| namespace.to_string())
+                .unwrap_or_default(),
+        ],
+        GroupBy::Tag if meta.tags.is_empty() => vec![String::new()],
+        GroupBy::Tag => meta.tags.iter().cloned().collect(),
+        GroupBy::Identity => vec![
+            meta.identity
+                .as_ref()
+                .and_then(|identity| identity.email.clone())
+                .unwrap_or_default(),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use crate::credstore::MemoryStore;
+    use crate::hook;
+    use crate::loader::{self, Loaded};
+    use crate::prompt::ScriptedPrompter;
+    use chrono::{DateTime, Utc};
+    use std::fs;
+    use tempfile::TempDir;
+
+    struct TestSetup {
+        _temp_dir: TempDir,
+        manager: AccountManager,
+        claude_config_dir: PathBuf,
+    }
+
+    impl TestSetup {
+        fn new() -> Result<Self> {
+            let temp_dir = TempDir::new()?;
+            let temp_path = temp_dir.path();
+
+            let claude_config_dir = temp_path.join(".claude");
+            let switcher_dir = temp_path.join(".local/share/claude-account-switcher");
+            let state_dir = temp_path.join(".local/state/claude-account-switcher");
+            let accounts_file = state_dir.join("accounts.json");
+
+            fs::create_dir_all(&state_dir)?;
+            fs::create_dir_all(&switcher_dir)?;
+
+            let manager = AccountManager {
                 claude_config_dir: claude_config_dir.clone(),
                 switcher_dir,
+                state_dir,
                 accounts_file,
+                system: None,
+                grant: None,
+                settings_file: temp_path.join(".config/claude-account-switcher/settings.toml"),
+                legacy_dir: temp_path.join(".claude-accounts"),
+                settings: Settings::default(),
+                capabilities: Capabilities::assumed(Utc::now()),
+                capture: CapturePolicy::default(),
+                extra_paths: Vec::new(),
+                format: OutputFormat::default(),
+                clock: Box::new(SystemClock),
+                // Tests that reach a prompt must script its answer
+                prompter: Box::new(ScriptedPrompter::new(Vec::<String>::new())),
+                storage: Box::new(LocalStorage),
+                assume_yes: false,
+                credstore: Box::new(MemoryStore::default()),
+                vault: OnceLock::new(),
+                // The `claude` on the test machine's PATH isn't asked
+                claude_version: OnceLock::from(None),
+                lock_wait: lock::Wait::Never,
+                unsaved: Unsaved::default(),
+                autosave: None,
+                force: false,
+                overwrite: false,
+                status_to_stderr: false,
+                quiet: false,
+                held_lock: Mutex::new(Weak::new()),
+            };
+
+            Ok(Self {
+                _temp_dir: temp_dir,
+                manager,
+                claude_config_dir,
+            })
+        }
+
+        fn create_mock_claude_config(&self) -> Result<()> {
+            fs::create_dir_all(&self.claude_config_dir)?;
+            fs::write(
+                self.claude_config_dir.join("config.json"),
+                r#"{"api_key": "test_key"}"#,
+            )?;
+            fs::write(
+                self.claude_config_dir.join("session.json"),
+                r#"{"session": "test_session"}"#,
+            )?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_save_account_no_configuration() {
+        let setup = TestSetup::new().unwrap();
+        let result = setup.manager.save_account("test");
+
+        assert!(result.is_err());
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("No Claude Code configuration found"));
+    }
+
+    #[test]
+    fn test_save_account_success() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+
+        let result = setup.manager.save_account("test_account");
+        assert!(result.is_ok());
+
+        let config = setup.manager.load_config().unwrap();
+        assert!(config.get_account("test_account").is_some());
+        assert_eq!(config.current, Some("test_account".to_string()));
+
+        let account_dir = setup.manager.switcher_dir.join("test_account");
+        assert!(account_dir.exists());
+        assert!(account_dir.join("config.json").exists());
+        assert!(account_dir.join("session.json").exists());
+    }
+
+    #[test]
+    fn test_save_multiple_accounts() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+
+        setup.manager.save_account("account1").unwrap();
+        setup.manager.save_account("account2").unwrap();
+        setup.manager.save_account("account3").unwrap();
+
+        let config = setup.manager.load_config().unwrap();
+        assert_eq!(config.accounts.len(), 3);
+        assert!(config.get_account("account1").is_some());
+        assert!(config.get_account("account2").is_some());
+        assert!(config.get_account("account3").is_some());
+    }
+
+    #[test]
+    fn test_resave_mirrors_the_live_configuration() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        let live = setup.claude_config_dir.clone();
+        let saved = setup.manager.switcher_dir.join("work");
+        setup.manager.save_account("work")?;
+
+        // Saving only the changes, from what the manifest says it holds
+        fs::remove_file(live.join("session.json"))?;
+        setup.manager.save_account("work")?;
+        assert!(!saved.join("session.json").exists());
+
+        // And copying it all, when the manifest is gone
+        fs::write(live.join("extra.json"), "{}")?;
+        setup.manager.save_account("work")?;
+        fs::remove_file(live.join("extra.json"))?;
+        fs::remove_file(manifest::path(&setup.manager.state_dir, "work"))?;
+        setup.manager.save_account("work")?;
+        assert!(!saved.join("extra.json").exists());
+        assert!(saved.join("config.json").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_switch_account_not_found() {
+        let setup = TestSetup::new().unwrap();
+        let result = setup.manager.switch_account("nonexistent", None);
+
+        assert!(result.is_err());
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("not found"));
+    }
+
+    #[test]
+    fn test_switch_account_success() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+
+        setup.manager.save_account("account1").unwrap();
+
+        fs::write(
+            setup.claude_config_dir.join("config.json"),
+            r#"{"api_key": "modified_key"}"#,
+        )
+        .unwrap();
+
+        setup.manager.save_account("account2").unwrap();
+
+        setup.manager.switch_account("account1", None).unwrap();
+
+        let config = setup.manager.load_config().unwrap();
+        assert_eq!(config.current, Some("account1".to_string()));
+
+        let content = fs::read_to_string(setup.claude_config_dir.join("config.json")).unwrap();
+        assert!(content.contains("test_key"));
+    }
+
+    #[test]
+    fn test_resave_if_current_captures_refreshed_files() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("work").unwrap();
+
+        fs::write(setup.claude_config_dir.join("config.json"), "refreshed").unwrap();
+        setup
+            .manager
+            .resave_if_current("work", Duration::ZERO)
+            .unwrap();
+
+        let saved = setup.manager.switcher_dir.join("work/config.json");
+        assert_eq!(fs::read_to_string(saved).unwrap(), "refreshed");
+    }
+
+    #[test]
+    fn test_resave_if_current_skips_inactive_account() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("work").unwrap();
+        setup.manager.save_account("personal").unwrap();
+
+        fs::write(setup.claude_config_dir.join("config.json"), "personal data").unwrap();
+        setup
+            .manager
+            .resave_if_current("work", Duration::ZERO)
+            .unwrap();
+
+        let saved = setup.manager.switcher_dir.join("work/config.json");
+        assert!(fs::read_to_string(saved).unwrap().contains("test_key"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_switch_symlink_mode_links_live_dir() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("account1")?;
+        fs::write(setup.claude_config_dir.join("config.json"), "second")?;
+        setup.manager.save_account("account2")?;
+
+        let store = &setup.manager.switcher_dir;
+        setup
+            .manager
+            .switch_account("account1", Some(SwitchMode::Symlink))?;
+        assert!(setup.claude_config_dir.is_symlink());
+        assert_eq!(
+            fs::read_link(&setup.claude_config_dir)?,
+            store.join("account1")
+        );
+
+        setup
+            .manager
+            .switch_account("account2", Some(SwitchMode::Symlink))?;
+        assert_eq!(
+            fs::read_link(&setup.claude_config_dir)?,
+            store.join("account2")
+        );
+        let content = fs::read_to_string(setup.claude_config_dir.join("config.json"))?;
+        assert_eq!(content, "second");
+
+        // The previous account wasn't overwritten by saving it onto itself
+        let first = fs::read_to_string(store.join("account1/config.json"))?;
+        assert!(first.contains("test_key"));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_banner_stays_out_of_linked_snapshots() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        fs::write(setup.claude_config_dir.join(banner::FILE), "memory\n")?;
+        setup.manager.save_account("work")?;
+        setup.manager.save_account("personal")?;
+        setup.manager.settings.banner = Some("Account: {name}".to_string());
+
+        setup
+            .manager
+            .switch_account("work", Some(SwitchMode::Symlink))?;
+        let memory = setup.manager.switcher_dir.join("work").join(banner::FILE);
+        assert_eq!(fs::read_to_string(&memory)?, "memory\n");
+
+        // One an older version put there goes with the next save
+        fs::write(&memory, banner::insert("memory\n", "Account: work"))?;
+        setup.manager.save_account("work")?;
+        assert_eq!(fs::read_to_string(&memory)?, "memory\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_copies_go_through_storage() -> Result<()> {
+        let setup = TestSetup::new()?;
+        let storage = crate::storage::MemoryStorage::default();
+        let (live, snapshot) = (Path::new("/mem/.claude"), Path::new("/mem/store/work"));
+        storage.create_dir_all(&live.join("projects"))?;
+        storage.write(&live.join("settings.json"), b"{}")?;
+        storage.write(&live.join("projects/a.jsonl"), b"line\n")?;
+        let manager = setup.manager.with_storage(storage);
+
+        manager.storage.create_dir_all(snapshot)?;
+        let transfer =
+            manager.timed_copy(live, snapshot, &CapturePolicy::default(), Codec::Plain)?;
+        assert_eq!((transfer.usage.files, transfer.usage.bytes), (2, 7));
+        assert_eq!(
+            manager.storage.read(&snapshot.join("projects/a.jsonl"))?,
+            b"line\n"
+        );
+        let memory = snapshot.join(banner::FILE);
+        manager.storage.write(
+            &memory,
+            banner::insert("memory\n", "Account: work").as_bytes(),
+        )?;
+        manager.strip_banner(snapshot)?;
+        assert_eq!(manager.storage.read(&memory)?, b"memory\n");
+
+        // Hard links aren't possible, so the files are copied
+        let staged = Path::new("/mem/.claude.staged");
+        let transfer = manager.stage_live_hardlinks(snapshot, staged)?;
+        assert_eq!(transfer.usage.files, 3);
+        manager.move_dir(staged, Path::new("/mem/moved"))?;
+        assert!(
+            manager
+                .storage
+                .is_file(Path::new("/mem/moved/settings.json"))
+        );
+        manager.discard(Path::new("/mem/moved"));
+        assert!(!manager.storage.exists(Path::new("/mem/moved")));
+        assert!(!Path::new("/mem").exists());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_switch_hardlink_mode_links_files_claude_only_reads() -> Result<()> {
+        use std::os::unix::fs::MetadataExt;
+
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        fs::write(setup.claude_config_dir.join("history.jsonl"), "{}\n")?;
+        setup.manager.save_account("work")?;
+        fs::write(setup.claude_config_dir.join("config.json"), "other")?;
+        setup.manager.save_account("other")?;
+
+        setup
+            .manager
+            .switch_account("work", Some(SwitchMode::Hardlink))?;
+        let live = &setup.claude_config_dir;
+        let snapshot = setup.manager.switcher_dir.join("work");
+        let inode = |path: &Path| -> Result<u64> { Ok(fs::metadata(path)?.ino()) };
+        assert!(!live.is_symlink());
+        assert_eq!(
+            inode(&live.join("config.json"))?,
+            inode(&snapshot.join("config.json"))?
+        );
+        assert_ne!(
+            inode(&live.join("history.jsonl"))?,
+            inode(&snapshot.join("history.jsonl"))?
+        );
+
+        // Appending to the history leaves the snapshot as it was saved
+        fs::write(live.join("history.jsonl"), "{}\n{}\n")?;
+        assert_eq!(fs::read_to_string(snapshot.join("history.jsonl"))?, "{}\n");
+
+        // Saving copies the linked files onto themselves without losing them
+        setup.manager.save_account("work")?;
+        assert!(fs::read_to_string(snapshot.join("config.json"))?.contains("test_key"));
+        assert_eq!(
+            fs::read_to_string(snapshot.join("history.jsonl"))?,
+            "{}\n{}\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_overlay_and_banner_leave_hard_linked_snapshot_alone() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        let live = &setup.claude_config_dir;
+        fs::create_dir_all(live.join("agents"))?;
+        fs::write(live.join("agents/a.md"), "saved agent")?;
+        fs::write(live.join("CLAUDE.md"), "saved memory\n")?;
+        setup.manager.save_account("work")?;
+        setup.manager.save_account("other")?;
+        setup
+            .manager
+            .switch_account("work", Some(SwitchMode::Hardlink))?;
+
+        let overlay = setup._temp_dir.path().join("overlay");
+        fs::create_dir_all(overlay.join("agents"))?;
+        fs::write(overlay.join("agents/a.md"), "team agent")?;
+        setup.manager.apply_team_overlay(&overlay);
+        setup.manager.write_banner("ACTIVE ACCOUNT: work")?;
+        assert_eq!(fs::read_to_string(live.join("agents/a.md"))?, "team agent");
+        assert!(fs::read_to_string(live.join("CLAUDE.md"))?.contains("ACTIVE ACCOUNT"));
+
+        let snapshot = setup.manager.switcher_dir.join("work");
+        assert_eq!(
+            fs::read_to_string(snapshot.join("agents/a.md"))?,
+            "saved agent"
+        );
+        assert_eq!(
+            fs::read_to_string(snapshot.join("CLAUDE.md"))?,
+            "saved memory\n"
+        );
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_save_while_linked_keeps_snapshot() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+        setup
+            .manager
+            .switch_account("work", Some(SwitchMode::Symlink))?;
+
+        setup.manager.save_account("work")?;
+        let content = fs::read_to_string(setup.manager.switcher_dir.join("work/config.json"))?;
+        assert!(content.contains("test_key"));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_copy_mode_replaces_symlink() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+        setup
+            .manager
+            .switch_account("work", Some(SwitchMode::Symlink))?;
+
+        setup
+            .manager
+            .switch_account("work", Some(SwitchMode::Copy))?;
+        assert!(!setup.claude_config_dir.is_symlink());
+        assert!(setup.claude_config_dir.join("config.json").is_file());
+        assert!(
+            setup
+                .manager
+                .switcher_dir
+                .join("work/config.json")
+                .is_file()
+        );
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_rename_relinks_live_dir() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+        setup
+            .manager
+            .switch_account("work", Some(SwitchMode::Symlink))?;
+
+        setup.manager.rename_account("work", "job")?;
+        assert_eq!(
+            fs::read_link(&setup.claude_config_dir)?,
+            setup.manager.switcher_dir.join("job")
+        );
+        assert!(setup.claude_config_dir.join("config.json").is_file());
+        Ok(())
+    }
+
+    #[test]
+    fn test_switch_account_directory_not_found() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("test_account").unwrap();
+
+        // Remove both the account directory and the claude config directory
+        // to prevent auto-save from recreating the account
+        let account_dir = setup.manager.switcher_dir.join("test_account");
+        fs::remove_dir_all(&account_dir).unwrap();
+        fs::remove_dir_all(&setup.claude_config_dir).unwrap();
+
+        let result = setup.manager.switch_account("test_account", None);
+        assert!(result.is_err());
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("is missing"));
+        assert!(err.contains("repair --relink test_account=DIR"));
+    }
+
+    #[test]
+    fn test_repair_relinks_or_drops_broken_accounts() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        for name in ["work", "moved", "gone", "bad"] {
+            setup.manager.save_account(name)?;
+        }
+        let store = setup.manager.switcher_dir.clone();
+        let elsewhere = setup._temp_dir.path().join("elsewhere");
+        fs::rename(store.join("moved"), &elsewhere)?;
+        fs::remove_dir_all(store.join("gone"))?;
+        let accounts_file = setup.manager.accounts_file.clone();
+        let mut document: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&accounts_file)?)?;
+        document["accounts"]["bad"]["saved_at"] = 5.into();
+        fs::write(&accounts_file, document.to_string())?;
+
+        // Listing and switching to the others still work
+        setup.manager.list_accounts(
+            None,
+            ListFilter::default(),
+            ListSort::Name,
+            false,
+            &[],
+            None,
+        )?;
+        setup.manager.switch_account("work", None)?;
+        let error = setup.manager.switch_account("bad", None).unwrap_err();
+        assert!(error.to_string().contains("can't be read"));
+
+        let relink = [format!("moved={}", elsewhere.display())];
+        setup.manager.repair(&relink, true)?;
+        assert_eq!(setup.manager.load_config()?.unhealthy().len(), 3);
+
+        let manager = setup.manager.with_prompter(ScriptedPrompter::new(["y"]));
+        manager.repair(&relink, false)?;
+        let config = manager.load_config()?;
+        assert!(config.unhealthy().is_empty());
+        assert_eq!(config.accounts["moved"].path, fs::canonicalize(&elsewhere)?);
+        assert!(config.accounts["moved"].external);
+        // Registered again from its snapshot, which is still in the store
+        assert_eq!(config.accounts["bad"].path, store.join("bad"));
+        assert!(!config.accounts.contains_key("gone"));
+        manager.switch_account("bad", None)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_namespaced_accounts_are_not_taken_for_another_store() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.manager.assume_yes = true;
+        setup.manager.unsaved = Unsaved::Refuse;
+        setup.create_mock_claude_config()?;
+        let store = setup.manager.switcher_dir.clone();
+        setup.manager.save_account("work/a")?;
+        setup.manager.save_account("work/a")?;
+        setup.manager.save_account("work/b")?;
+        setup.manager.switch_account("work/a", None)?;
+        setup.manager.rename_account("work/b", "work/c")?;
+        assert!(store.join("work/c/config.json").is_file());
+
+        setup.manager.delete_account("work/c", false)?;
+        assert!(!store.join("work/c").exists());
+        setup.manager.restore_trashed("work/c")?;
+        assert!(store.join("work/c/config.json").is_file());
+
+        // Namespaced on a drive, it's still the drive's
+        let drive = setup._temp_dir.path().join("drive");
+        fs::create_dir_all(&drive)?;
+        setup.manager.save_account_in("team/x", Some(&drive))?;
+        setup.manager.save_account("team/x")?;
+        let drive = fs::canonicalize(&drive)?;
+        let config = setup.manager.load_config()?;
+        assert_eq!(
+            setup.manager.custom_store(&config.accounts["team/x"]),
+            Some(drive.as_path())
+        );
+        setup.manager.delete_account("team/x", false)?;
+        assert!(drive.join("team/x/config.json").is_file());
+        Ok(())
+    }
+
+    #[test]
+    fn test_account_kept_on_another_drive() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        let drive = setup._temp_dir.path().join("drive");
+        let unplugged = setup._temp_dir.path().join("unplugged");
+        assert!(
+            setup
+                .manager
+                .save_account_in("secure", Some(&drive))
+                .is_err()
+        );
+        fs::create_dir_all(&drive)?;
+        setup.manager.save_account_in("secure", Some(&drive))?;
+        let drive = fs::canonicalize(&drive)?;
+        assert!(store::is_mounted(&drive));
+        assert!(drive.join("secure/config.json").is_file());
+        assert!(!setup.manager.switcher_dir.join("secure").exists());
+
+        // Saved and renamed where it's kept
+        setup.manager.save_account("secure")?;
+        setup.manager.rename_account("secure", "vault")?;
+        assert!(drive.join("vault/config.json").is_file());
+        let config = setup.manager.load_config()?;
+        assert_eq!(config.accounts["vault"].path, drive.join("vault"));
+        assert!(!config.accounts["vault"].external);
+
+        setup.manager.save_account("work")?;
+        fs::rename(&drive, &unplugged)?;
+        let error = setup.manager.switch_account("vault", None).unwrap_err();
+        assert!(error.to_string().contains("mount the drive"));
+        // The rest still work, and repair leaves it alone
+        setup.manager.list_accounts(
+            None,
+            ListFilter::default(),
+            ListSort::Name,
+            false,
+            &[],
+            None,
+        )?;
+        setup.manager.repair(&[], false)?;
+        assert!(setup.manager.load_config()?.accounts.contains_key("vault"));
+
+        fs::rename(&unplugged, &drive)?;
+        setup.manager.switch_account("vault", None)?;
+        fs::rename(&drive, &unplugged)?;
+        setup.manager.unsaved = Unsaved::Refuse;
+        let error = setup.manager.switch_account("work", None).unwrap_err();
+        assert!(error.to_string().contains("--force"));
+        setup.manager.unsaved = Unsaved::Discard;
+        setup.manager.switch_account("work", None)?;
+        assert!(!drive.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_accounts_empty() {
+        let setup = TestSetup::new().unwrap();
+        let result = setup.manager.list_accounts(
+            None,
+            ListFilter::default(),
+            ListSort::Name,
+            false,
+            &[],
+            None,
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_list_accounts_with_data() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+
+        setup.manager.save_account("account1").unwrap();
+        setup.manager.save_account("account2").unwrap();
+
+        let result = setup.manager.list_accounts(
+            None,
+            ListFilter::default(),
+            ListSort::Name,
+            false,
+            &[],
+            None,
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_list_accounts_grouped() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+
+        setup.manager.save_account("client-a/dev").unwrap();
+        setup.manager.save_account("client-a/prod").unwrap();
+        setup.manager.save_account("personal").unwrap();
+
+        let result = setup.manager.list_accounts(
+            Some(GroupBy::Namespace),
+            ListFilter::default(),
+            ListSort::Name,
+            false,
+            &[],
+            None,
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_token_from_credentials_or_api_key() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        let token = "sk-ant-REDACTED";
+        fs::write(
+            setup.claude_config_dir.join(CREDENTIALS_FILE),
+            format!(r#"{{"claudeAiOauth": {{"accessToken": "{}"}}}}"#, token),
+        )?;
+        setup.manager.save_account("work")?;
+        fs::remove_file(setup.claude_config_dir.join(CREDENTIALS_FILE))?;
+        fs::write(
+            setup.claude_config_dir.join(template::CLAUDE_JSON),
+            r#"{"primaryApiKey": "sk-ant-REDACTED"}"#,
+        )?;
+        setup.manager.save_account("ci")?;
+
+        let config = setup.manager.load_config()?;
+        let token_of = |name: &str| {
+            setup
+                .manager
+                .account_token(&config, name, &config.accounts[name].path)
+        };
+        assert_eq!(token_of("work")?, Some(("oauth", token.to_string())));
+        assert_eq!(
+            token_of("ci")?,
+            Some(("api-key", "sk-ant-REDACTED".to_string()))
+        );
+        setup.manager.show_token("work", false, None)?;
+        assert!(setup.manager.show_token("missing", true, None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_envfile_vars_from_settings_and_secret() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        fs::write(
+            setup.claude_config_dir.join("settings.json"),
+            r#"{"env": {"ANTHROPIC_BASE_URL": "https://proxy", "ANTHROPIC_API_KEY": "old"}}"#,
+        )?;
+        fs::write(
+            setup.claude_config_dir.join(template::CLAUDE_JSON),
+            r#"{"primaryApiKey": "sk-ant-REDACTED"}"#,
+        )?;
+        setup.manager.save_account("ci")?;
+
+        let vars = setup.manager.envfile_vars("ci")?;
+        assert_eq!(
+            vars,
+            [
+                (
+                    "ANTHROPIC_BASE_URL".to_string(),
+                    "https://proxy".to_string()
+                ),
+                (
+                    envfile::API_KEY_VAR.to_string(),
+                    "sk-ant-REDACTED".to_string()
+                ),
+            ]
+        );
+        setup
+            .manager
+            .print_envfile("ci", envfile::Format::GithubActions)?;
+        assert!(setup.manager.envfile_vars("missing").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_archive_and_switch_unarchives() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+        setup.manager.save_account("personal")?;
+        assert!(setup.manager.archive_account("personal").is_err());
+
+        setup.manager.switch_account("work", None)?;
+        setup.manager.archive_account("personal")?;
+        let meta = setup.manager.load_config()?.accounts["personal"].clone();
+        let archive = meta.archive.clone().expect("archive is recorded");
+        assert!(archive.is_file());
+        assert!(!meta.path.exists());
+        assert!(setup.manager.archive_account("personal").is_err());
+        assert_eq!(
+            setup.manager.load_config()?.health("personal"),
+            Some(EntryHealth::Healthy)
+        );
+        setup.manager.list_accounts(
+            None,
+            ListFilter::default(),
+            ListSort::Size,
+            true,
+            &[],
+            None,
+        )?;
+
+        // A rename takes the archive along
+        setup.manager.rename_account("personal", "home")?;
+        let meta = setup.manager.load_config()?.accounts["home"].clone();
+        assert!(!archive.exists());
+        assert!(
+            meta.archive
+                .as_ref()
+                .is_some_and(|archive| archive.is_file())
+        );
+
+        setup.manager.switch_account("home", None)?;
+        let meta = setup.manager.load_config()?.accounts["home"].clone();
+        assert!(meta.archive.is_none());
+        assert!(meta.path.join("config.json").is_file());
+        assert!(
+            setup
+                .manager
+                .switcher_dir
+                .join(archive::COLD_DIR)
+                .read_dir()?
+                .next()
+                .is_none()
+        );
+        assert!(setup.manager.unarchive_account("home").is_err());
+
+        setup.manager.archive_account("work")?;
+        setup.manager.unarchive_account("work")?;
+        assert!(setup.manager.load_config()?.accounts["work"].path.is_dir());
+        Ok(())
+    }
+
+    #[test]
+    fn test_list_sizes_come_from_the_cache_until_the_snapshot_changes() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+        setup.manager.save_account("personal")?;
+        setup.manager.list_accounts(
+            None,
+            ListFilter::default(),
+            ListSort::Size,
+            true,
+            &[],
+            None,
+        )?;
+
+        let config = setup.manager.load_config()?;
+        let accounts: Vec<_> = config.accounts.iter().collect();
+        let measured = setup.manager.cached_usages(&config, &accounts)?;
+        let mut cache = ListCache::load(&setup.manager.state_dir, Utc::now());
+        assert_eq!(cache.accounts.len(), 2);
+        assert_eq!(cache.accounts["work"].usage, Some(measured["work"]));
+
+        // What's cached is what's shown while the snapshot is as it was
+        let fake = DirUsage {
+            files: 99,
+            bytes: 12345,
+        };
+        cache.accounts.get_mut("work").unwrap().usage = Some(fake);
+        cache.save(&setup.manager.state_dir)?;
+        assert_eq!(
+            setup.manager.cached_usages(&config, &accounts)?["work"],
+            fake
+        );
+
+        // Saving again and deleting leave nothing stale behind
+        setup.manager.save_account("personal")?;
+        setup.manager.delete_account("work", false)?;
+        let cache = ListCache::load(&setup.manager.state_dir, Utc::now());
+        assert!(cache.accounts.is_empty());
+        Ok(())
+    }
+
+    fn tagged(tags: &[&str]) -> AccountMetadata {
+        AccountMetadata {
+            saved_at: "2024-01-01T00:00:00+00:00".to_string(),
+            path: PathBuf::new(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_group_key_namespace() {
+        let meta = tagged(&["work"]);
+        assert_eq!(
+            group_keys(GroupBy::Namespace, "client-a/dev", &meta),
+            ["client-a"]
+        );
+        assert_eq!(
+            group_keys(GroupBy::Namespace, "work/client/prod", &meta),
+            ["work/client"]
+        );
+        assert_eq!(group_keys(GroupBy::Namespace, "personal", &meta), [""]);
+    }
+
+    #[test]
+    fn test_group_key_identity() {
+        let mut meta = tagged(&["work"]);
+        assert_eq!(group_keys(GroupBy::Identity, "work", &meta), [""]);
+        meta.identity = Some(AccountIdentity {
+            email: Some("dev@example.com".to_string()),
+            organization: Some("Acme".to_string()),
+            plan: None,
+        });
+        assert_eq!(
+            group_keys(GroupBy::Identity, "work", &meta),
+            ["dev@example.com"]
+        );
+    }
+
+    #[test]
+    fn test_sort_accounts() {
+        let names = ["alpha", "beta", "gamma"].map(str::to_string);
+        let mut metas = [tagged(&[]), tagged(&[]), tagged(&[])];
+        metas[0].saved_at = "2024-01-03T00:00:00+00:00".to_string();
+        metas[1].last_used = Some("2024-01-05T00:00:00+00:00".to_string());
+        metas[2].last_used = Some("2024-01-04T00:00:00Z".to_string());
+        let usages = BTreeMap::from([
+            (
+                "alpha",
+                DirUsage {
+                    files: 1,
+                    bytes: 10,
+                },
+            ),
+            (
+                "gamma",
+                DirUsage {
+                    files: 1,
+                    bytes: 20,
+                },
+            ),
+        ]);
+        let order = |sort| {
+            let mut accounts: Vec<_> = names.iter().zip(metas.iter()).collect();
+            sort_accounts(&mut accounts, sort, &usages);
+            accounts
+                .into_iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(order(ListSort::Name), ["alpha", "beta", "gamma"]);
+        assert_eq!(order(ListSort::Saved), ["alpha", "beta", "gamma"]);
+        // Never used last
+        assert_eq!(order(ListSort::LastUsed), ["beta", "gamma", "alpha"]);
+        assert_eq!(order(ListSort::Size), ["gamma", "alpha", "beta"]);
+    }
+
+    #[test]
+    fn test_group_keys_by_tag() {
+        assert_eq!(
+            group_keys(GroupBy::Tag, "work", &tagged(&["client-a", "billing"])),
+            ["billing", "client-a"]
+        );
+        assert_eq!(group_keys(GroupBy::Tag, "personal", &tagged(&[])), [""]);
+    }
+
+    #[test]
+    fn test_note_set_kept_and_cleared() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+
+        setup
+            .manager
+            .note_account("work", Some("  Acme, billed to ops\n"), false, false)?;
+        setup.manager.save_account("work")?;
+        let config = setup.manager.load_config()?;
+        assert_eq!(
+            config.accounts["work"].note.as_deref(),
+            Some("Acme, billed to ops")
+        );
+        setup.manager.note_account("work", None, false, false)?;
+        setup.manager.show_info("work")?;
+
+        setup.manager.note_account("work", None, false, true)?;
+        assert!(setup.manager.load_config()?.accounts["work"].note.is_none());
+        // Blank is the same as none
+        setup
+            .manager
+            .note_account("work", Some(" "), false, false)?;
+        assert!(setup.manager.load_config()?.accounts["work"].note.is_none());
+        assert!(
+            setup
+                .manager
+                .note_account("missing", Some("x"), false, false)
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_tag_add_and_remove() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+        setup.manager.save_account("personal")?;
+
+        let tags = ["client-a".to_string(), "billing".to_string()];
+        setup.manager.tag_account("work", &tags, true)?;
+        setup.manager.tag_account("work", &tags[..1], true)?;
+        assert!(
+            setup
+                .manager
+                .tag_account("work", &["a b".to_string()], true)
+                .is_err()
+        );
+        assert!(setup.manager.tag_account("missing", &tags, true).is_err());
+        // A save keeps them, and the switch count
+        setup.manager.switch_account("work", None)?;
+        setup.manager.save_account("work")?;
+        let config = setup.manager.load_config()?;
+        assert_eq!(config.accounts["work"].switch_count, 1);
+        assert_eq!(
+            config.accounts["work"].tags,
+            ["billing", "client-a"].map(str::to_string).into()
+        );
+
+        setup.manager.tag_account("work", &tags[1..], false)?;
+        let config = setup.manager.load_config()?;
+        assert_eq!(
+            config.accounts["work"].tags,
+            ["client-a"].map(str::to_string).into()
+        );
+        setup.manager.list_accounts(
+            Some(GroupBy::Tag),
+            ListFilter {
+                tags: &tags[..1],
+                ..Default::default()
+            },
+            ListSort::Name,
+            false,
+            &[],
+            None,
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_delete_account_not_found() {
+        let setup = TestSetup::new().unwrap();
+        let result = setup.manager.delete_account("nonexistent", false);
+
+        assert!(result.is_err());
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("not found"));
+    }
+
+    #[test]
+    fn test_delete_account_success() {
+        let mut setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+
+        setup.manager.save_account("test_account").unwrap();
+
+        let account_dir = setup.manager.switcher_dir.join("test_account");
+        assert!(account_dir.exists());
+
+        // Deleting the active account asks for confirmation
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["y"]));
+        setup.manager.delete_account("test_account", false).unwrap();
+
+        let config = setup.manager.load_config().unwrap();
+        assert!(config.get_account("test_account").is_none());
+        assert_eq!(config.current, None);
+        assert!(!account_dir.exists());
+    }
+
+    #[test]
+    fn test_delete_active_account_cancelled() {
+        let mut setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("test_account").unwrap();
+
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["n"]));
+        setup.manager.delete_account("test_account", false).unwrap();
+
+        let config = setup.manager.load_config().unwrap();
+        assert!(config.get_account("test_account").is_some());
+        assert!(setup.manager.switcher_dir.join("test_account").exists());
+    }
+
+    #[test]
+    fn test_assume_yes_answers_confirmations() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("test_account").unwrap();
+
+        // Nothing scripted, so a confirmation that got as far as the
+        // prompter would fail
+        let manager = setup
+            .manager
+            .with_prompter(ScriptedPrompter::new(Vec::<String>::new()))
+            .with_assume_yes(true);
+        manager.delete_account("test_account", false).unwrap();
+
+        let config = manager.load_config().unwrap();
+        assert!(config.get_account("test_account").is_none());
+    }
+
+    #[test]
+    fn test_delete_inactive_account_skips_prompt() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("old").unwrap();
+        setup.manager.save_account("new").unwrap();
+
+        // The default test prompter fails if it's asked anything
+        setup.manager.delete_account("old", false).unwrap();
+        assert!(
+            setup
+                .manager
+                .load_config()
+                .unwrap()
+                .get_account("old")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_delete_keep_data_leaves_snapshot() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("old").unwrap();
+        setup.manager.save_account("new").unwrap();
+
+        setup.manager.delete_account("old", true).unwrap();
+
+        let config = setup.manager.load_config().unwrap();
+        assert!(config.get_account("old").is_none());
+        assert!(setup.manager.switcher_dir.join("old/config.json").exists());
+    }
+
+    #[test]
+    fn test_add_existing_is_used_in_place() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("work").unwrap();
+
+        let dotfiles = setup._temp_dir.path().join("dotfiles/claude");
+        fs::create_dir_all(&dotfiles).unwrap();
+        fs::write(dotfiles.join("settings.json"), "dotfiles").unwrap();
+        setup.manager.add_existing("dots", &dotfiles).unwrap();
+        assert!(!setup.manager.switcher_dir.join("dots").exists());
+
+        setup.manager.switch_account("dots", None).unwrap();
+        assert_eq!(
+            fs::read_to_string(setup.claude_config_dir.join("settings.json")).unwrap(),
+            "dotfiles"
+        );
+
+        // Saving writes back to the registered directory
+        fs::write(setup.claude_config_dir.join("settings.json"), "edited").unwrap();
+        setup.manager.save_account("dots").unwrap();
+        assert_eq!(
+            fs::read_to_string(dotfiles.join("settings.json")).unwrap(),
+            "edited"
+        );
+        assert!(setup.manager.load_config().unwrap().accounts["dots"].external);
+
+        setup.manager.rename_account("dots", "dotfiles").unwrap();
+        setup.manager.switch_account("work", None).unwrap();
+        setup.manager.delete_account("dotfiles", false).unwrap();
+        assert!(dotfiles.join("settings.json").exists());
+    }
+
+    #[test]
+    fn test_add_existing_validation() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("work").unwrap();
+
+        let missing = setup._temp_dir.path().join("missing");
+        assert!(setup.manager.add_existing("other", &missing).is_err());
+
+        let err = setup
+            .manager
+            .add_existing("work", &setup.claude_config_dir)
+            .unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+    }
+
+    #[test]
+    fn test_switch_interactive_picks_by_number() {
+        let mut setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("personal").unwrap();
+        setup.manager.save_account("work").unwrap();
+
+        // Entries are listed by name, so 1 is "personal"
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["1"]));
+        setup.manager.switch_interactive(None).unwrap();
+        assert_eq!(
+            setup.manager.load_config().unwrap().current.as_deref(),
+            Some("personal")
+        );
+
+        setup.manager.prompter = Box::new(ScriptedPrompter::new([""]));
+        setup.manager.switch_interactive(None).unwrap();
+        assert_eq!(
+            setup.manager.load_config().unwrap().current.as_deref(),
+            Some("personal")
+        );
+    }
+
+    #[test]
+    fn test_protected_account_requires_typed_name() {
+        let mut setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("prod").unwrap();
+        setup.manager.save_account("work").unwrap();
+        setup.manager.set_protected("prod", true).unwrap();
+
+        // Re-saving keeps the flag
+        setup.manager.save_account("work").unwrap();
+        let current = |manager: &AccountManager| manager.load_config().unwrap().current;
+
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["n"]));
+        setup.manager.switch_account("prod", None).unwrap();
+        assert_eq!(current(&setup.manager).as_deref(), Some("work"));
+
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["y", "PROD"]));
+        let err = setup.manager.switch_account("prod", None).unwrap_err();
+        assert!(err.to_string().contains("prod"));
+        assert_eq!(current(&setup.manager).as_deref(), Some("work"));
+
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["y", "prod"]));
+        setup.manager.switch_account("prod", None).unwrap();
+        assert_eq!(current(&setup.manager).as_deref(), Some("prod"));
+        assert!(setup.manager.load_config().unwrap().accounts["prod"].protected);
+
+        setup.manager.set_protected("prod", false).unwrap();
+        setup.manager.switch_account("work", None).unwrap();
+        setup.manager.switch_account("prod", None).unwrap();
+    }
+
+    #[test]
+    fn test_pinned_account_needs_its_claude_version() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.claude_version = OnceLock::from(Some("2.0.14".to_string()));
+        setup.manager.save_account("fragile")?;
+        setup.manager.save_account("work")?;
+        let config = setup.manager.load_config()?;
+        assert_eq!(
+            config.accounts["fragile"].claude_version.as_deref(),
+            Some("2.0.14")
+        );
+
+        // Defaults to the installed version, and survives a save
+        setup.manager.pin("fragile", None, false)?;
+        setup.manager.switch_account("fragile", None)?;
+        setup.manager.save_account("fragile")?;
+        let config = setup.manager.load_config()?;
+        assert_eq!(
+            config.accounts["fragile"].pinned_version.as_deref(),
+            Some("2.0.14")
+        );
+
+        setup.manager.switch_account("work", None)?;
+        setup.manager.pin("fragile", Some("2.0"), false)?;
+        setup.manager.claude_version = OnceLock::from(Some("2.1.0".to_string()));
+        let err = setup.manager.switch_account("fragile", None).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<AccountError>(),
+            Some(AccountError::VersionMismatch(..))
+        ));
+        assert_eq!(
+            setup.manager.load_config()?.current.as_deref(),
+            Some("work")
+        );
+
+        // Without a pin a newer release only warns
+        setup.manager.pin("fragile", None, true)?;
+        setup.manager.switch_account("fragile", None)?;
+        assert_eq!(
+            setup.manager.load_config()?.current.as_deref(),
+            Some("fragile")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_locked_account_is_left_alone() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("golden")?;
+        setup.manager.set_locked("golden", true)?;
+        let config_file = setup.claude_config_dir.join("config.json");
+        let saved = setup.manager.switcher_dir.join("golden/config.json");
+
+        fs::write(&config_file, "edited")?;
+        let err = setup.manager.save_account("golden").unwrap_err();
+        assert!(matches!(
+            crate::error::classify(&err),
+            Some(AccountError::Locked(_))
+        ));
+        // Switching away neither stops for the changes nor saves them
+        setup.manager.save_account("work")?;
+        setup.manager.switch_account("golden", None)?;
+        fs::write(&config_file, "edited again")?;
+        setup.manager.switch_account("work", None)?;
+        assert!(fs::read_to_string(&saved)?.contains("test_key"));
+
+        assert!(setup.manager.delete_account("golden", false).is_err());
+        setup.manager.prune(Some(0), Some("0s"), &[], false)?;
+        assert!(setup.manager.load_config()?.accounts.contains_key("golden"));
+        let only = [PathBuf::from("settings.json")];
+        let err = setup
+            .manager
+            .merge_files("work", "golden", &only, false, false)
+            .unwrap_err();
+        assert!(matches!(
+            crate::error::classify(&err),
+            Some(AccountError::Locked(_))
+        ));
+
+        setup.manager.force = true;
+        setup.manager.save_account("golden")?;
+        assert!(setup.manager.load_config()?.accounts["golden"].locked);
+        setup.manager.set_locked("golden", false)?;
+        setup.manager.force = false;
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["y"]));
+        setup.manager.delete_account("golden", false)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_delete_by_glob_carries_on_past_failures() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        for name in ["test-1", "test-2", "test-3", "work"] {
+            setup.manager.save_account(name)?;
+        }
+        setup.manager.set_locked("test-2", true)?;
+        let names = |manager: &AccountManager| -> Result<Vec<String>> {
+            Ok(manager.load_config()?.accounts.into_keys().collect())
+        };
+
+        let patterns = ["test-*".to_string(), "work".to_string()];
+        assert_eq!(
+            setup.manager.expand_names(&patterns)?,
+            ["test-1", "test-2", "test-3", "work"]
+        );
+        assert!(setup.manager.expand_names(&["prod-*".to_string()]).is_err());
+
+        // Declined, nothing goes
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["n"]));
+        setup.manager.delete_accounts(&patterns[..1], false)?;
+        assert_eq!(names(&setup.manager)?.len(), 4);
+
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["y"]));
+        let err = setup
+            .manager
+            .delete_accounts(&patterns[..1], false)
+            .unwrap_err();
+        assert!(err.to_string().contains("test-2"));
+        let mut left = names(&setup.manager)?;
+        left.sort();
+        assert_eq!(left, ["test-2", "work"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_account_summaries() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        fs::write(
+            setup.claude_config_dir.join(".credentials.json"),
+            r#"{"claudeAiOauth": {"subscriptionType": "pro"}}"#,
+        )
+        .unwrap();
+        fs::write(
+            setup.claude_config_dir.join(".claude.json"),
+            r#"{"oauthAccount": {"emailAddress": "dev@example.com"}}"#,
+        )
+        .unwrap();
+        setup.manager.save_account("work").unwrap();
+        setup.manager.save_account("personal").unwrap();
+        setup.manager.switch_account("work", None).unwrap();
+
+        let summaries = setup.manager.account_summaries().unwrap();
+        let names: Vec<_> = summaries.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["personal", "work"]);
+
+        let work = &summaries[1];
+        assert!(work.current);
+        assert!(work.last_used.is_some());
+        assert!(summaries[0].last_used.is_none());
+        assert!(work.login.is_none() && work.usage.is_none());
+
+        let login = setup.manager.account_login(&work.name, &work.path);
+        assert_eq!(login.email.as_deref(), Some("dev@example.com"));
+        assert_eq!(login.identity.unwrap().plan.as_deref(), Some("pro"));
+
+        // The loader fills in the rest from background threads
+        let mut received = Vec::new();
+        thread::scope(|scope| {
+            let rx = loader::spawn(scope, &setup.manager, &summaries);
+            received.extend(rx);
+        });
+        assert_eq!(received.len(), 4);
+        assert!(received.iter().any(|loaded| matches!(
+            loaded,
+            Loaded::Usage(name, usage) if name == "work" && usage.files == 4
+        )));
+    }
+
+    #[test]
+    fn test_switch_dash_flips_back() {
+        let mut setup = TestSetup::new().unwrap();
+        setup.manager.assume_yes = true;
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("work").unwrap();
+        setup.manager.save_account("personal").unwrap();
+        assert!(setup.manager.switch_account("-", None).is_err());
+
+        setup.manager.switch_account("work", None).unwrap();
+        setup.manager.switch_account("-", None).unwrap();
+        let config = setup.manager.load_config().unwrap();
+        assert_eq!(config.current.as_deref(), Some("personal"));
+        assert_eq!(config.previous.as_deref(), Some("work"));
+
+        // Switching to the active account again doesn't lose the way back
+        setup.manager.switch_account("personal", None).unwrap();
+        setup.manager.switch_account("-", None).unwrap();
+        let config = setup.manager.load_config().unwrap();
+        assert_eq!(config.current.as_deref(), Some("work"));
+
+        setup.manager.rename_account("personal", "home").unwrap();
+        let config = setup.manager.load_config().unwrap();
+        assert_eq!(config.previous.as_deref(), Some("home"));
+        setup.manager.delete_account("home", false).unwrap();
+        assert!(setup.manager.switch_account("-", None).is_err());
+    }
+
+    #[test]
+    fn test_switch_by_alias_and_number() {
+        let mut setup = TestSetup::new().unwrap();
+        setup.manager.assume_yes = true;
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("work-main").unwrap();
+        setup.manager.save_account("personal").unwrap();
+
+        setup.manager.set_alias("w", "work-main").unwrap();
+        assert!(setup.manager.set_alias("7", "work-main").is_err());
+        assert!(setup.manager.set_alias("personal", "work-main").is_err());
+        assert!(setup.manager.set_alias("x", "missing").is_err());
+
+        let current = |setup: &TestSetup| setup.manager.load_config().unwrap().current;
+        setup.manager.switch_account("w", None).unwrap();
+        assert_eq!(current(&setup).as_deref(), Some("work-main"));
+        // Numbered in the order they were saved
+        setup.manager.switch_account("2", None).unwrap();
+        assert_eq!(current(&setup).as_deref(), Some("personal"));
+        assert!(setup.manager.switch_account("3", None).is_err());
+
+        setup.manager.remove_alias("w").unwrap();
+        assert!(setup.manager.remove_alias("w").is_err());
+        assert!(setup.manager.switch_account("w", None).is_err());
+    }
+
+    #[test]
+    fn test_limit_hit_recorded_kept_and_cleared() {
+        let mut setup = TestSetup::new().unwrap();
+        let at = DateTime::parse_from_rfc3339("2024-01-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        setup.manager.clock = Box::new(FixedClock(at));
+        setup.create_mock_claude_config().unwrap();
+        assert!(setup.manager.limit_hit(None, None, false).is_err());
+        setup.manager.save_account("work").unwrap();
+        setup.manager.save_account("personal").unwrap();
+
+        setup.manager.limit_hit(None, None, false).unwrap();
+        setup
+            .manager
+            .limit_hit(Some("work"), Some("30m"), false)
+            .unwrap();
+        let limit = |setup: &TestSetup, name: &str| {
+            let config = setup.manager.load_config().unwrap();
+            quota::active(config.accounts[name].limited_until.as_deref(), at)
+        };
+        assert_eq!(
+            limit(&setup, "personal"),
+            Some(at + chrono::Duration::hours(5))
+        );
+        assert_eq!(
+            limit(&setup, "work"),
+            Some(at + chrono::Duration::minutes(30))
+        );
+
+        // Saving again doesn't lift it; only the window or --clear does
+        setup.manager.save_account("personal").unwrap();
+        assert!(limit(&setup, "personal").is_some());
+        setup
+            .manager
+            .limit_hit(Some("personal"), None, true)
+            .unwrap();
+        assert!(limit(&setup, "personal").is_none());
+    }
+
+    #[test]
+    fn test_list_points_out_stale_and_expiring_accounts() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        let at = DateTime::parse_from_rfc3339("2024-01-01T12:00:00Z")?.with_timezone(&Utc);
+        setup.manager.clock = Box::new(FixedClock(at));
+        setup.create_mock_claude_config()?;
+        let credentials = setup.claude_config_dir.join(CREDENTIALS_FILE);
+        setup.manager.save_account("old")?;
+
+        let later = at + chrono::Duration::days(40);
+        setup.manager.clock = Box::new(FixedClock(later));
+        let in_hours = |hours: i64| later.timestamp_millis() + hours * 3_600_000;
+        fs::write(
+            &credentials,
+            format!(r#"{{"claudeAiOauth": {{"expiresAt": {}}}}}"#, in_hours(2)),
+        )?;
+        setup.manager.save_account("expiring")?;
+        fs::write(
+            &credentials,
+            format!(r#"{{"claudeAiOauth": {{"expiresAt": {}}}}}"#, in_hours(72)),
+        )?;
+        setup.manager.save_account("fresh")?;
+
+        let config = setup.manager.load_config()?;
+        let accounts: Vec<_> = config.accounts.iter().collect();
+        let attention = setup.manager.attention(&accounts, Some(30))?;
+        assert_eq!(attention["old"].stale_days, Some(40));
+        assert_eq!(attention["expiring"].stale_days, None);
+        assert_eq!(attention["expiring"].expiry, Some(health::Expiry::Soon));
+        assert_eq!(
+            (attention["fresh"].stale_days, attention["fresh"].expiry),
+            (None, None)
+        );
+
+        // Nothing's stale without `remind-after` or `--stale-days`
+        let attention = setup.manager.attention(&accounts, None)?;
+        assert_eq!(
+            (attention["old"].stale_days, attention["old"].expiry),
+            (None, None)
+        );
+
+        let filter = ListFilter {
+            stale_days: Some(30),
+            ..Default::default()
+        };
+        setup
+            .manager
+            .list_accounts(None, filter, ListSort::Name, false, &[], None)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_rotate_passes_over_limited_and_expired() {
+        let mut setup = TestSetup::new().unwrap();
+        let at = DateTime::parse_from_rfc3339("2024-01-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        setup.manager.clock = Box::new(FixedClock(at));
+        setup.create_mock_claude_config().unwrap();
+        let credentials = setup.claude_config_dir.join(CREDENTIALS_FILE);
+        setup.manager.save_account("work/a").unwrap();
+        // Signed in with a token that ran out an hour ago
+        let expired = at.timestamp_millis() - 3_600_000;
+        fs::write(
+            &credentials,
+            format!(r#"{{"claudeAiOauth": {{"expiresAt": {}}}}}"#, expired),
+        )
+        .unwrap();
+        setup.manager.save_account("work/b").unwrap();
+        fs::remove_file(&credentials).unwrap();
+        setup.manager.save_account("work/c").unwrap();
+        setup.manager.save_account("personal").unwrap();
+        let current = |setup: &TestSetup| setup.manager.load_config().unwrap().current.unwrap();
+
+        // By name from `personal`, which is last, round to the start
+        setup.manager.rotate(None).unwrap();
+        assert_eq!(current(&setup), "work/a");
+        setup.manager.rotate(Some("work")).unwrap();
+        assert_eq!(current(&setup), "work/c");
+
+        setup
+            .manager
+            .limit_hit(Some("work/a"), None, false)
+            .unwrap();
+        assert!(setup.manager.rotate(Some("work")).is_err());
+        assert!(setup.manager.rotate(Some("nobody")).is_err());
+
+        setup.manager.settings.rotation = vec!["personal".to_string(), "work/c".to_string()];
+        setup.manager.rotate(None).unwrap();
+        assert_eq!(current(&setup), "personal");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_and_modes_survive_save_and_switch() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        let dir = &setup.claude_config_dir;
+        fs::write(dir.join(CREDENTIALS_FILE), "{}").unwrap();
+        fs::set_permissions(
+            dir.join(CREDENTIALS_FILE),
+            fs::Permissions::from_mode(0o644),
+        )
+        .unwrap();
+        fs::write(dir.join("hook.sh"), "#!/bin/sh\n").unwrap();
+        fs::set_permissions(dir.join("hook.sh"), fs::Permissions::from_mode(0o755)).unwrap();
+        std::os::unix::fs::symlink("config.json", dir.join("linked.json")).unwrap();
+        setup.manager.save_account("work").unwrap();
+
+        fs::remove_file(dir.join("linked.json")).unwrap();
+        fs::remove_file(dir.join("hook.sh")).unwrap();
+        setup.manager.save_account("other").unwrap();
+        setup.manager.switch_account("work", None).unwrap();
+
+        let mode = |name: &str| fs::metadata(dir.join(name)).unwrap().permissions().mode() & 0o777;
+        assert_eq!(
+            fs::read_link(dir.join("linked.json")).unwrap(),
+            Path::new("config.json")
+        );
+        assert_eq!(mode("hook.sh"), 0o755);
+        assert_eq!(mode(CREDENTIALS_FILE), 0o600);
+    }
+
+    #[test]
+    fn test_verify_finds_damage_and_repairs_current() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("work").unwrap();
+        setup.manager.save_account("other").unwrap();
+        setup.manager.verify(&[], true, false).unwrap();
+
+        let snapshot = setup.manager.switcher_dir.join("work");
+        fs::write(snapshot.join("config.json"), "tampered").unwrap();
+        let err = setup
+            .manager
+            .verify(&["work".to_string()], false, false)
+            .unwrap_err();
+        assert!(err.to_string().contains("1 snapshot(s) are damaged"));
+        fs::remove_file(setup.manager.switcher_dir.join("other/session.json")).unwrap();
+        assert!(setup.manager.verify(&[], false, true).is_ok());
+        assert!(setup.manager.verify(&[], true, false).is_err());
+
+        // Only the current account has a live copy to repair from
+        assert!(
+            setup
+                .manager
+                .verify(&["work".to_string()], false, true)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_migrate_versions_file_and_adopts_orphans() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("work").unwrap();
+        let accounts_file = &setup.manager.accounts_file;
+        let unversioned = fs::read_to_string(accounts_file)
+            .unwrap()
+            .replace(r#""schema_version": 1,"#, "");
+        assert!(!unversioned.contains("schema_version"));
+        fs::write(accounts_file, &unversioned).unwrap();
+        let stray = setup.manager.switcher_dir.join("stray");
+        fs::create_dir_all(&stray).unwrap();
+        fs::write(stray.join("config.json"), "{}").unwrap();
+
+        setup.manager.migrate(true).unwrap();
+        assert_eq!(fs::read_to_string(accounts_file).unwrap(), unversioned);
+
+        setup.manager.migrate(false).unwrap();
+        let contents = fs::read_to_string(accounts_file).unwrap();
+        assert_eq!(
+            config::stored_version(&contents).unwrap(),
+            config::SCHEMA_VERSION
+        );
+        let config = setup.manager.load_config().unwrap();
+        assert_eq!(config.get_account("stray").unwrap().path, stray);
+        assert!(config.get_account("work").is_some());
+        assert_eq!(
+            fs::read_to_string(accounts_file.with_extension("json.bak")).unwrap(),
+            unversioned
+        );
+    }
+
+    #[test]
+    fn test_adopt_in_place_copied_and_scanned() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("work").unwrap();
+
+        let copied_in = setup.manager.switcher_dir.join("team/imported");
+        fs::create_dir_all(&copied_in).unwrap();
+        fs::write(copied_in.join("config.json"), "{}").unwrap();
+        setup.manager.adopt(&copied_in, None).unwrap();
+        let config = setup.manager.load_config().unwrap();
+        let meta = config.get_account("team/imported").unwrap();
+        assert_eq!(meta.path, fs::canonicalize(&copied_in).unwrap());
+        assert!(!meta.external);
+
+        let elsewhere = TempDir::new().unwrap();
+        fs::write(elsewhere.path().join("settings.json"), "{}").unwrap();
+        setup
+            .manager
+            .adopt(elsewhere.path(), Some("laptop"))
+            .unwrap();
+        let snapshot = setup.manager.switcher_dir.join("laptop");
+        assert!(snapshot.join("settings.json").is_file());
+        assert!(elsewhere.path().join("settings.json").is_file());
+        assert_eq!(
+            setup.manager.load_config().unwrap().accounts["laptop"].path,
+            snapshot
+        );
+
+        let err = setup.manager.adopt(&copied_in, Some("again")).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("already the snapshot of 'team/imported'")
+        );
+
+        fs::create_dir_all(setup.manager.switcher_dir.join("stray")).unwrap();
+        setup.manager.adopt_all().unwrap();
+        assert!(
+            setup
+                .manager
+                .load_config()
+                .unwrap()
+                .get_account("stray")
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_gc_orphans_trashes_or_adopts_them() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+        let store = setup.manager.switcher_dir.clone();
+        for stray in ["stray", "leftover", "bad name"] {
+            fs::create_dir_all(store.join(stray))?;
+            fs::write(store.join(stray).join("config.json"), "{}")?;
+        }
+
+        setup.manager.collect_orphans(false, true)?;
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["n"]));
+        setup.manager.collect_orphans(false, false)?;
+        assert!(store.join("stray").is_dir() && store.join("leftover").is_dir());
+
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["y"]));
+        setup.manager.collect_orphans(true, false)?;
+        let config = setup.manager.load_config()?;
+        assert!(config.get_account("stray").is_some() && config.get_account("leftover").is_some());
+        assert!(config.get_account("bad name").is_none());
+
+        setup.manager.delete_account("leftover", false)?;
+        fs::remove_dir_all(store.join("bad name"))?;
+        fs::create_dir_all(store.join("broken"))?;
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["y"]));
+        setup.manager.collect_orphans(false, false)?;
+        // Only the new one: the account deleted is in the trash already
+        assert!(!store.join("broken").exists());
+        assert!(store.join("stray").is_dir());
+        assert_eq!(trash::list(&store)?.len(), 2);
+        setup.manager.restore_trashed("broken")?;
+        assert!(setup.manager.load_config()?.get_account("broken").is_some());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_add_logs_in_and_comes_back() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        let live_credentials = setup.claude_config_dir.join(CREDENTIALS_FILE);
+        fs::write(&live_credentials, "work-login").unwrap();
+        setup.manager.save_account("work").unwrap();
+
+        let login = |script: &str| ["sh", "-c", script].map(String::from);
+        let write_login = login(r#"printf new-login > "$CLAUDE_CONFIG_DIR/.credentials.json""#);
+        setup.manager.add_account("new", &write_login).unwrap();
+        let config = setup.manager.load_config().unwrap();
+        assert_eq!(config.current.as_deref(), Some("work"));
+        assert_eq!(
+            fs::read_to_string(config.accounts["new"].path.join(CREDENTIALS_FILE)).unwrap(),
+            "new-login"
+        );
+        assert_eq!(fs::read_to_string(&live_credentials).unwrap(), "work-login");
+
+        // A login that leaves no credentials saves nothing, and puts the
+        // current account back all the same
+        let err = setup
+            .manager
+            .add_account("other", &login("true"))
+            .unwrap_err();
+        assert!(err.to_string().contains("No new login"));
+        assert!(
+            setup
+                .manager
+                .load_config()
+                .unwrap()
+                .get_account("other")
+                .is_none()
+        );
+        assert_eq!(fs::read_to_string(&live_credentials).unwrap(), "work-login");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_renew_switches_and_saves_the_new_login() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        let live_credentials = setup.claude_config_dir.join(CREDENTIALS_FILE);
+        fs::write(&live_credentials, "expired")?;
+        setup.manager.save_account("work")?;
+        fs::write(&live_credentials, "personal-login")?;
+        setup.manager.save_account("personal")?;
+
+        let login = |script: &str| ["sh", "-c", script].map(String::from);
+        let refresh = login(r#"printf fresh > "$CLAUDE_CONFIG_DIR/.credentials.json""#);
+        setup.manager.renew("work", &refresh)?;
+        let config = setup.manager.load_config()?;
+        assert_eq!(config.current.as_deref(), Some("work"));
+        assert_eq!(
+            fs::read_to_string(config.accounts["work"].path.join(CREDENTIALS_FILE))?,
+            "fresh"
+        );
+
+        // A failed login leaves the snapshot as it was
+        assert!(setup.manager.renew("work", &login("false")).is_err());
+        assert_eq!(
+            fs::read_to_string(config.accounts["work"].path.join(CREDENTIALS_FILE))?,
+            "fresh"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_logout_saves_then_signs_out() {
+        let mut setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        let dir = setup.claude_config_dir.clone();
+        fs::write(dir.join(CREDENTIALS_FILE), "work-login").unwrap();
+        fs::write(dir.join("settings.json"), "{}").unwrap();
+        setup.manager.save_account("work").unwrap();
+        fs::write(dir.join("config.json"), "changed since the save").unwrap();
+
+        setup.manager.logout(false, false).unwrap();
+        assert!(!dir.join(CREDENTIALS_FILE).exists());
+        assert!(dir.join("config.json").exists());
+        let config = setup.manager.load_config().unwrap();
+        assert_eq!(config.current, None);
+        assert_eq!(config.previous.as_deref(), Some("work"));
+
+        setup.manager.switch_account("-", None).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join(CREDENTIALS_FILE)).unwrap(),
+            "work-login"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("config.json")).unwrap(),
+            "changed since the save"
+        );
+
+        setup.manager.logout(true, true).unwrap();
+        let left: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(left, ["settings.json"]);
+
+        // Nothing saves what's live now, so it's asked first
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["n"]));
+        setup.manager.logout(true, false).unwrap();
+        assert!(dir.exists());
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["y"]));
+        setup.manager.logout(true, false).unwrap();
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_banner_written_on_switch_and_stripped_on_save() {
+        let mut setup = TestSetup::new().unwrap();
+        setup.manager.settings.banner = Some("ACTIVE ACCOUNT: {name}".to_string());
+        setup.create_mock_claude_config().unwrap();
+        let live = setup.claude_config_dir.join("CLAUDE.md");
+
+        fs::write(&live, "Be brief.\n").unwrap();
+        setup.manager.save_account("work").unwrap();
+        fs::remove_file(&live).unwrap();
+        setup.manager.save_account("personal").unwrap();
+
+        setup.manager.switch_account("work", None).unwrap();
+        let contents = fs::read_to_string(&live).unwrap();
+        assert!(contents.contains("ACTIVE ACCOUNT: work"));
+        assert!(contents.ends_with("Be brief.\n"));
+
+        setup.manager.switch_account("personal", None).unwrap();
+        assert!(
+            fs::read_to_string(&live)
+                .unwrap()
+                .contains("ACTIVE ACCOUNT: personal")
+        );
+
+        // Neither snapshot picked the banner up through the auto-save
+        let store = &setup.manager.switcher_dir;
+        assert_eq!(
+            fs::read_to_string(store.join("work/CLAUDE.md")).unwrap(),
+            "Be brief.\n"
+        );
+        setup.manager.save_account("personal").unwrap();
+        assert!(!store.join("personal/CLAUDE.md").exists());
+    }
+
+    #[test]
+    fn test_profile_use_switches_and_writes_its_env() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("personal").unwrap();
+        setup.manager.save_account("work").unwrap();
+
+        let env = ["ANTHROPIC_MODEL=claude-haiku".to_string()];
+        assert!(
+            setup
+                .manager
+                .create_profile("fast", "personal", &["MODEL".to_string()], false)
+                .is_err()
+        );
+        assert!(
+            setup
+                .manager
+                .create_profile("fast", "missing", &env, false)
+                .is_err()
+        );
+        setup
+            .manager
+            .create_profile("fast", "personal", &env, false)
+            .unwrap();
+        assert!(
+            setup
+                .manager
+                .create_profile("fast", "work", &env, false)
+                .is_err()
+        );
+
+        setup.manager.use_profile("fast", None, true).unwrap();
+        let config = setup.manager.load_config().unwrap();
+        assert_eq!(config.current.as_deref(), Some("personal"));
+        let settings: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(setup.claude_config_dir.join("settings.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(settings["env"]["ANTHROPIC_MODEL"], "claude-haiku");
+
+        setup.manager.rename_account("personal", "home").unwrap();
+        let config = setup.manager.load_config().unwrap();
+        assert_eq!(config.profiles["fast"].account, "home");
+        setup.manager.delete_profile("fast").unwrap();
+        assert!(setup.manager.use_profile("fast", None, true).is_err());
+    }
+
+    #[test]
+    fn test_import_from_profiles_copies_each_one_in() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("work").unwrap();
+
+        let profiles = setup._temp_dir.path().join(".claude-profiles");
+        fs::create_dir_all(profiles.join("work")).unwrap();
+        fs::write(profiles.join("work/.credentials.json"), "{}").unwrap();
+        fs::create_dir_all(profiles.join("client/.claude")).unwrap();
+        fs::write(profiles.join("client/.claude/settings.json"), "{}").unwrap();
+        fs::write(
+            profiles.join("client/.claude.json"),
+            r#"{"numStartups": 1}"#,
+        )
+        .unwrap();
+
+        assert!(
+            setup
+                .manager
+                .import_from(Tool::Profiles, Some(&profiles), false, None)
+                .is_err()
+        );
+        assert!(!setup.manager.switcher_dir.join("client").exists());
+        setup
+            .manager
+            .import_from(
+                Tool::Profiles,
+                Some(&profiles),
+                false,
+                Some(OnConflict::Rename),
+            )
+            .unwrap();
+
+        let config = setup.manager.load_config().unwrap();
+        let store = &setup.manager.switcher_dir;
+        assert!(config.accounts.contains_key("work-2"));
+        assert!(store.join("work-2/.credentials.json").is_file());
+        assert!(store.join("client/settings.json").is_file());
+        assert!(store.join("client/.claude.json").is_file());
+        // Left where it was
+        assert!(profiles.join("client/.claude.json").is_file());
+    }
+
+    #[test]
+    fn test_team_overlay_applied_after_switch() {
+        let mut setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        let settings = setup.claude_config_dir.join("settings.json");
+        fs::write(&settings, r#"{"model": "opus"}"#).unwrap();
+        setup.manager.save_account("work").unwrap();
+        setup.manager.save_account("personal").unwrap();
+
+        let overlay = setup._temp_dir.path().join("team");
+        fs::create_dir_all(overlay.join("hooks")).unwrap();
+        fs::write(
+            overlay.join("settings.json"),
+            r#"{"hooks": {"Stop": [{"command": "~/.claude/hooks/log.sh"}]}}"#,
+        )
+        .unwrap();
+        fs::write(overlay.join("hooks/log.sh"), "#!/bin/sh\n").unwrap();
+        setup.manager.settings.team_overlay = Some(overlay.clone());
+
+        setup.manager.switch_account("work", None).unwrap();
+        let live: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&settings).unwrap()).unwrap();
+        assert_eq!(live["model"], "opus");
+        assert_eq!(
+            live["hooks"]["Stop"][0]["command"],
+            "~/.claude/hooks/log.sh"
+        );
+        assert!(setup.claude_config_dir.join("hooks/log.sh").is_file());
+
+        // Gone is no reason to fail the switch
+        fs::remove_dir_all(&overlay).unwrap();
+        setup.manager.switch_account("personal", None).unwrap();
+        assert_eq!(
+            setup.manager.load_config().unwrap().current.as_deref(),
+            Some("personal")
+        );
+    }
+
+    #[test]
+    fn test_ensure_bound_switches_to_project_account() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("client").unwrap();
+        setup.manager.save_account("personal").unwrap();
+
+        let project = setup._temp_dir.path().join("project");
+        let nested = project.join("src");
+        fs::create_dir_all(&nested).unwrap();
+        setup.manager.bind(&project, "client").unwrap();
+        assert!(setup.manager.bind(&project, "missing").is_err());
+
+        let current = |manager: &AccountManager| manager.load_config().unwrap().current;
+        setup
+            .manager
+            .ensure_bound(&nested, true, EventSource::Wrapper)
+            .unwrap();
+        assert_eq!(current(&setup.manager).as_deref(), Some("personal"));
+
+        setup
+            .manager
+            .ensure_bound(&nested, false, EventSource::Wrapper)
+            .unwrap();
+        assert_eq!(current(&setup.manager).as_deref(), Some("client"));
+
+        // A binding to an account that no longer exists is an error
+        fs::write(project.join(binding::FILE), "gone\n").unwrap();
+        assert!(
+            setup
+                .manager
+                .ensure_bound(&nested, false, EventSource::Wrapper)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_project_mappings_switch_here() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("client").unwrap();
+        setup.manager.save_account("personal").unwrap();
+
+        let project = setup._temp_dir.path().join("project");
+        let nested = project.join("src");
+        fs::create_dir_all(&nested).unwrap();
+        assert!(setup.manager.switch_here(&nested, None).is_err());
+        assert!(setup.manager.set_project(&project, "missing").is_err());
+        setup.manager.set_project(&project, "client").unwrap();
+        // Nothing is written into the project
+        assert!(!project.join(binding::FILE).exists());
+
+        let current = |manager: &AccountManager| manager.load_config().unwrap().current;
+        setup.manager.switch_here(&nested, None).unwrap();
+        assert_eq!(current(&setup.manager).as_deref(), Some("client"));
+
+        // The hooks see the mapping too
+        setup.manager.switch_account("personal", None).unwrap();
+        setup
+            .manager
+            .ensure_bound(&nested, false, EventSource::Auto)
+            .unwrap();
+        assert_eq!(current(&setup.manager).as_deref(), Some("client"));
+
+        // Renaming follows the mapping; deleting drops it
+        setup.manager.rename_account("client", "acme").unwrap();
+        let projects = setup.manager.load_config().unwrap().projects;
+        assert_eq!(
+            projects.values().map(String::as_str).collect::<Vec<_>>(),
+            ["acme"]
+        );
+        setup.manager.unset_project(&project).unwrap();
+        assert!(setup.manager.unset_project(&project).is_err());
+        assert!(setup.manager.load_config().unwrap().projects.is_empty());
+    }
+
+    #[test]
+    fn test_switch_leaves_an_unchanged_account_it_just_saved() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+        setup.manager.save_account("personal")?;
+        let saved_at = |manager: &AccountManager, name: &str| -> Result<String> {
+            Ok(manager.load_config()?.accounts[name].saved_at.clone())
+        };
+
+        // Saved a moment ago, with nothing changed since
+        let before = saved_at(&setup.manager, "personal")?;
+        setup.manager.switch_account("work", None)?;
+        assert_eq!(saved_at(&setup.manager, "personal")?, before);
+
+        // A refreshed login is a change all the same
+        fs::write(setup.claude_config_dir.join(CREDENTIALS_FILE), "refreshed")?;
+        let before = saved_at(&setup.manager, "work")?;
+        setup.manager.switch_account("personal", None)?;
+        assert_ne!(saved_at(&setup.manager, "work")?, before);
+
+        // `--autosave`
+        setup.manager.autosave = Some(true);
+        let before = saved_at(&setup.manager, "personal")?;
+        setup.manager.switch_account("work", None)?;
+        assert_ne!(saved_at(&setup.manager, "personal")?, before);
+
+        // Past the debounce, only `autosave = false` leaves it
+        setup.manager.autosave = None;
+        setup.manager.settings.autosave_debounce = Some("0s".to_string());
+        setup.manager.settings.autosave = Some(false);
+        let before = saved_at(&setup.manager, "work")?;
+        setup.manager.switch_account("personal", None)?;
+        assert_eq!(saved_at(&setup.manager, "work")?, before);
+        setup.manager.settings.autosave = None;
+        let before = saved_at(&setup.manager, "personal")?;
+        setup.manager.switch_account("work", None)?;
+        assert_ne!(saved_at(&setup.manager, "personal")?, before);
+        Ok(())
+    }
+
+    #[test]
+    fn test_history_records_event_sources() {
+        let mut setup = TestSetup::new().unwrap();
+        // Saved a moment ago, `personal` would be left as it is
+        setup.manager.settings.autosave_debounce = Some("0s".to_string());
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("work").unwrap();
+        setup.manager.save_account("personal").unwrap();
+        setup.manager.switch_account("work", None).unwrap();
+        setup
+            .manager
+            .resave_if_current("work", Duration::ZERO)
+            .unwrap();
+
+        let events = history::load(&setup.manager.history_file()).unwrap();
+        let logged: Vec<_> = events
+            .iter()
+            .map(|event| (event.kind, event.account.as_str(), event.source))
+            .collect();
+        assert_eq!(
+            logged,
+            [
+                (EventKind::Save, "work", EventSource::Cli),
+                (EventKind::Save, "personal", EventSource::Cli),
+                (EventKind::Save, "personal", EventSource::AutoSave),
+                (EventKind::Switch, "work", EventSource::Cli),
+                (EventKind::Save, "work", EventSource::Resave),
+            ]
+        );
+        // The switch knows where it came from and what it copied
+        assert_eq!(events[3].previous.as_deref(), Some("personal"));
+        assert!(events[3].files.is_some_and(|files| files > 0));
+
+        setup
+            .manager
+            .show_history(Some(EventSource::AutoSave), None, Some(1))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_save_and_switch_report_copies() {
+        let mut setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.format = OutputFormat::Json;
+
+        let saved = setup.manager.save_from("work", EventSource::Cli).unwrap();
+        assert_eq!(saved.unwrap().usage.files, 2);
+        setup.manager.save_account("prod").unwrap();
+        setup.manager.set_protected("prod", true).unwrap();
+
+        let switched = setup.manager.switch_from("work", None, EventSource::Cli);
+        assert_eq!(switched.unwrap().flatten().unwrap().usage.files, 2);
+
+        // Backing out of a protected switch is reported as cancelled
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["n"]));
+        let cancelled = setup.manager.switch_from("prod", None, EventSource::Cli);
+        assert!(cancelled.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_export_all_and_import_elsewhere() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("client/dev").unwrap();
+        setup.manager.save_account("personal").unwrap();
+        setup.manager.set_protected("personal", true).unwrap();
+
+        let archive = setup._temp_dir.path().join("all.tar.zst");
+        setup
+            .manager
+            .export_accounts(&[], Some(&archive), false, None, false)
+            .unwrap();
+
+        let other = TestSetup::new().unwrap();
+        other
+            .manager
+            .import_account(&archive, None, None, false, None)
+            .unwrap();
+
+        let config = other.manager.load_config().unwrap();
+        assert_eq!(config.accounts.len(), 2);
+        let personal = &config.accounts["personal"];
+        assert!(personal.protected);
+        assert_eq!(personal.path, other.manager.switcher_dir.join("personal"));
+        assert!(
+            other
+                .manager
+                .switcher_dir
+                .join("client/dev/config.json")
+                .exists()
+        );
+        assert!(config.current.is_none());
+
+        // --name can't pick one of several accounts
+        let result = other
+            .manager
+            .import_account(&archive, Some("x"), None, false, None);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_redacted_export_leaves_out_secrets() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        fs::write(setup.claude_config_dir.join(CREDENTIALS_FILE), "{}")?;
+        fs::write(setup.claude_config_dir.join("CLAUDE.md"), "Use tabs.\n")?;
+        setup.manager.save_account("work")?;
+
+        let archive = setup._temp_dir.path().join("setup.tar.zst");
+        setup
+            .manager
+            .export_accounts(&["work".to_string()], Some(&archive), false, None, true)?;
+        setup
+            .manager
+            .import_account(&archive, Some("shared"), None, false, None)?;
+
+        let shared = setup.manager.switcher_dir.join("shared");
+        assert!(!shared.join(CREDENTIALS_FILE).exists());
+        assert_eq!(fs::read_to_string(shared.join("CLAUDE.md"))?, "Use tabs.\n");
+        let config: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(shared.join("config.json"))?)?;
+        assert_eq!(config["api_key"], redact::MASK);
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_conflict_resolution() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("work").unwrap();
+        setup.manager.save_account("personal").unwrap();
+
+        let archive = setup._temp_dir.path().join("work.tar.zst");
+        setup
+            .manager
+            .export_accounts(
+                &["work".to_string()],
+                Some(&archive),
+                true,
+                Some("secret"),
+                false,
+            )
+            .unwrap();
+        assert!(crypto::is_encrypted(&fs::read(&archive).unwrap()));
+
+        let import = |on_conflict| {
+            setup
+                .manager
+                .import_account(&archive, None, Some("secret"), false, on_conflict)
+        };
+        assert!(
+            import(None)
+                .unwrap_err()
+                .to_string()
+                .contains("already exists")
+        );
+
+        import(Some(OnConflict::Rename)).unwrap();
+        import(Some(OnConflict::Rename)).unwrap();
+        import(Some(OnConflict::Skip)).unwrap();
+        let config = setup.manager.load_config().unwrap();
+        let mut names: Vec<_> = config.accounts.keys().map(String::as_str).collect();
+        names.sort();
+        assert_eq!(names, ["personal", "work", "work-2", "work-3"]);
+
+        // The active account can't be replaced, others can
+        setup.manager.switch_account("work", None).unwrap();
+        assert!(import(Some(OnConflict::Replace)).is_err());
+        setup.manager.switch_account("work-2", None).unwrap();
+        fs::remove_file(setup.manager.switcher_dir.join("work/config.json")).unwrap();
+        import(Some(OnConflict::Replace)).unwrap();
+        assert!(setup.manager.switcher_dir.join("work/config.json").exists());
+
+        // Accounts nested in the one replaced keep their snapshots
+        setup.manager.save_account("work/dev").unwrap();
+        import(Some(OnConflict::Replace)).unwrap();
+        assert!(
+            setup
+                .manager
+                .switcher_dir
+                .join("work/dev/config.json")
+                .exists()
+        );
+    }
+
+    #[test]
+    fn test_rollback_restores_previous_save() {
+        let mut setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        let live = setup.claude_config_dir.join("config.json");
+        let snapshot = setup.manager.switcher_dir.join("work/config.json");
+
+        fs::write(&live, "good").unwrap();
+        setup.manager.save_account("work").unwrap();
+        fs::write(&live, "bad").unwrap();
+        setup.manager.save_account("work").unwrap();
+
+        let versions = versions::list(&setup.manager.switcher_dir, "work").unwrap();
+        assert_eq!(versions.len(), 1);
+
+        // Rolling back the active account puts the snapshot live as well
+        setup.manager.rollback("work", None).unwrap();
+        assert_eq!(fs::read_to_string(&snapshot).unwrap(), "good");
+        assert_eq!(fs::read_to_string(&live).unwrap(), "good");
+
+        // The replaced snapshot became a version, so the rollback can be undone
+        let versions = versions::list(&setup.manager.switcher_dir, "work").unwrap();
+        assert_eq!(versions.len(), 1);
+        setup
+            .manager
+            .rollback("work", Some(&versions[0].id))
+            .unwrap();
+        assert_eq!(fs::read_to_string(&snapshot).unwrap(), "bad");
+        assert!(setup.manager.rollback("work", Some("nope")).is_err());
+
+        // Old versions are pruned, and versions follow renames and deletes
+        setup.manager.settings.keep_versions = Some(2);
+        for _ in 0..4 {
+            setup.manager.save_account("work").unwrap();
+        }
+        assert_eq!(
+            versions::list(&setup.manager.switcher_dir, "work")
+                .unwrap()
+                .len(),
+            2
+        );
+        setup.manager.rename_account("work", "job").unwrap();
+        assert_eq!(
+            versions::list(&setup.manager.switcher_dir, "job")
+                .unwrap()
+                .len(),
+            2
+        );
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["y"]));
+        setup.manager.delete_account("job", false).unwrap();
+        assert!(!versions::account_dir(&setup.manager.switcher_dir, "job").exists());
+    }
+
+    #[test]
+    fn test_versions_can_be_turned_off() {
+        let mut setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.settings.keep_versions = Some(0);
+        setup.manager.save_account("work").unwrap();
+        setup.manager.save_account("work").unwrap();
+
+        assert!(
+            versions::list(&setup.manager.switcher_dir, "work")
+                .unwrap()
+                .is_empty()
+        );
+        assert!(setup.manager.rollback("work", None).is_err());
+        assert!(setup.manager.save_account(".versions").is_err());
+    }
+
+    #[test]
+    fn test_new_names_stay_in_the_store() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+
+        assert!(setup.manager.save_account("../evil").is_err());
+        assert!(!setup._temp_dir.path().join(".local/share/evil").exists());
+        setup.manager.save_account("work/client-a")?;
+        assert!(
+            setup
+                .manager
+                .switcher_dir
+                .join("work/client-a/config.json")
+                .exists()
+        );
+        assert!(
+            setup
+                .manager
+                .rename_account("work/client-a", "work//b")
+                .is_err()
+        );
+        assert!(setup.manager.copy_account("work/client-a", "café").is_err());
+        assert!(
+            setup
+                .manager
+                .add_existing("../x", &setup.claude_config_dir)
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_nested_snapshots_stay_out_of_their_parent() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.manager.unsaved = Unsaved::Discard;
+        setup.create_mock_claude_config()?;
+        let live = setup.claude_config_dir.clone();
+        let store = setup.manager.switcher_dir.clone();
+        setup.manager.save_account("client")?;
+        fs::write(live.join("dev.json"), r#"{"model": "dev"}"#)?;
+        setup.manager.save_account("client/dev")?;
+
+        setup.manager.switch_account("client", None)?;
+        assert!(!live.join("dev").exists());
+        assert!(setup.manager.diff_live(Some("client"), false)?);
+        assert!(
+            setup
+                .manager
+                .found_accounts(None, Some("model"))?
+                .iter()
+                .all(|found| found.name == "client/dev")
+        );
+        assert!(
+            setup
+                .manager
+                .restore_files("client", &[PathBuf::from("dev/dev.json")], None)
+                .is_err()
+        );
+
+        // Saved again and rolled back, the parent leaves the child in place
+        setup.manager.save_account("client")?;
+        assert!(store.join("client/dev/dev.json").is_file());
+        setup.manager.rollback("client", None)?;
+        assert!(store.join("client/dev/dev.json").is_file());
+        assert!(
+            versions::list(&store, "client")?
+                .iter()
+                .all(|version| !version.path.join("dev").exists())
+        );
+        setup.manager.switch_account("client/dev", None)?;
+        assert!(live.join("dev.json").is_file());
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_legacy_moves_snapshots() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+
+        let legacy_dir = setup.manager.legacy_dir.clone();
+        let mut legacy = AccountsConfig::default();
+        for name in ["personal", "work"] {
+            fs::create_dir_all(legacy_dir.join(name))?;
+            fs::write(legacy_dir.join(name).join("config.json"), name)?;
+            legacy.add_account(
+                name.to_string(),
+                AccountMetadata {
+                    saved_at: "2023-05-01T00:00:00+00:00".to_string(),
+                    path: legacy_dir.join(name),
+                    ..Default::default()
+                },
+            );
+        }
+        legacy.current = Some("personal".to_string());
+        legacy.save(&legacy_dir.join(LEGACY_ACCOUNTS_FILE))?;
+
+        setup.manager.migrate_legacy(None)?;
+
+        let config = setup.manager.load_config()?;
+        let personal = config.get_account("personal").unwrap();
+        assert_eq!(personal.path, setup.manager.switcher_dir.join("personal"));
+        assert_eq!(personal.saved_at, "2023-05-01T00:00:00+00:00");
+        assert_eq!(
+            fs::read_to_string(personal.path.join("config.json"))?,
+            "personal"
+        );
+        assert!(!legacy_dir.join("personal").exists());
+        // The taken name stays behind, and so does the current account
+        assert_eq!(config.current.as_deref(), Some("work"));
+        let left = AccountsConfig::load(&legacy_dir.join(LEGACY_ACCOUNTS_FILE))?;
+        assert!(left.get_account("work").is_some());
+        assert!(left.get_account("personal").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_legacy_from_moved_directory() -> Result<()> {
+        let setup = TestSetup::new()?;
+        let from = setup.claude_config_dir.with_file_name("old-accounts");
+        fs::create_dir_all(from.join("work"))?;
+        let mut legacy = AccountsConfig::default();
+        legacy.add_account(
+            "work".to_string(),
+            AccountMetadata {
+                saved_at: "2023-05-01T00:00:00+00:00".to_string(),
+                // Written before the directory was moved
+                path: PathBuf::from("/nonexistent/.claude-accounts/work"),
+                ..Default::default()
+            },
+        );
+        legacy.current = Some("work".to_string());
+        legacy.save(&from.join(LEGACY_ACCOUNTS_FILE))?;
+
+        assert!(setup.manager.migrate_legacy(None).is_err());
+        setup.manager.migrate_legacy(Some(&from))?;
+
+        let config = setup.manager.load_config()?;
+        assert_eq!(config.current.as_deref(), Some("work"));
+        assert!(config.get_account("work").unwrap().path.is_dir());
+        assert!(!from.exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_doctor_finds_and_fixes_store_problems() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+        setup.manager.save_account("personal")?;
+        fs::remove_dir_all(setup.manager.switcher_dir.join("work"))?;
+        fs::create_dir_all(setup.manager.switcher_dir.join("stray"))?;
+        fs::create_dir_all(setup.manager.switcher_dir.join("bad name"))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let credentials = setup.claude_config_dir.join(CREDENTIALS_FILE);
+            fs::write(&credentials, "{}")?;
+            fs::set_permissions(&credentials, fs::Permissions::from_mode(0o644))?;
+        }
+
+        assert!(!setup.manager.doctor(false)?);
+        assert!(setup.manager.load_config()?.get_account("work").is_some());
+
+        assert!(setup.manager.doctor(true)?);
+        let config = setup.manager.load_config()?;
+        assert!(config.get_account("work").is_none());
+        assert!(config.get_account("stray").is_some());
+        // Left for `adopt --name`, as no account can be called that
+        assert!(config.get_account("bad name").is_none());
+        assert_eq!(config.current.as_deref(), Some("personal"));
+        #[cfg(unix)]
+        assert!(!doctor::is_too_open(
+            &setup.claude_config_dir.join(CREDENTIALS_FILE)
+        )?);
+        assert!(setup.manager.doctor(false)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_debug_bundle_is_redacted() -> Result<()> {
+        use std::io::Read;
+
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        fs::write(
+            setup.claude_config_dir.join(".claude.json"),
+            r#"{"oauthAccount": {"emailAddress": "dev@example.com", "organizationName": "Acme"}}"#,
+        )?;
+        setup.manager.save_account("work")?;
+        let crashes = setup.manager.state_dir.join("crashes");
+        fs::create_dir_all(&crashes)?;
+        fs::write(
+            crashes.join("crash.json"),
+            r#"{"message": "bad token sk-ant-REDACTED"}"#,
+        )?;
+
+        let path = setup.manager.state_dir.join("bundle.tar.zst");
+        setup.manager.debug_bundle(Some(&path))?;
+
+        let mut tar = Vec::new();
+        zstd::Decoder::new(fs::File::open(&path)?)?.read_to_end(&mut tar)?;
+        let mut archive = tar::Archive::new(tar.as_slice());
+        let mut names = Vec::new();
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let mut contents = String::new();
+            entry.read_to_string(&mut contents)?;
+            assert!(!contents.contains("abcdefghijklmnopqrstuvwxyz"));
+            assert!(!contents.contains("dev@example.com"));
+            assert!(!contents.contains("Acme"));
+            names.push(entry.path()?.display().to_string());
+        }
+        for name in [
+            "system.json",
+            "doctor.json",
+            "accounts.json",
+            "crashes/crash.json",
+        ] {
+            assert!(names.iter().any(|entry| entry == name), "{} missing", name);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_doctor_recovers_from_broken_config() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+        fs::write(&setup.manager.accounts_file, "{not json")?;
+
+        assert!(!setup.manager.doctor(false)?);
+        assert!(setup.manager.doctor(true)?);
+
+        let config = setup.manager.load_config()?;
+        assert!(config.get_account("work").is_some());
+        assert!(
+            setup
+                .manager
+                .accounts_file
+                .with_extension("json.broken")
+                .exists()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_failed_switch_restores_live_config() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.capture = CapturePolicy::new(&[], &["projects/"])?;
+        setup.manager.save_account("work")?;
+        // Writing the banner fails on a snapshot where CLAUDE.md is a directory
+        fs::create_dir_all(setup.manager.switcher_dir.join("work/CLAUDE.md"))?;
+
+        let live = setup.claude_config_dir.clone();
+        fs::write(live.join("config.json"), "personal")?;
+        fs::create_dir_all(live.join("projects"))?;
+        fs::write(live.join("projects/log.jsonl"), "log")?;
+        setup.manager.save_account("personal")?;
+        setup.manager.settings.banner = Some("ACTIVE ACCOUNT: {name}".to_string());
+
+        assert!(setup.manager.switch_account("work", None).is_err());
+
+        assert_eq!(fs::read_to_string(live.join("config.json"))?, "personal");
+        assert_eq!(fs::read_to_string(live.join("projects/log.jsonl"))?, "log");
+        assert!(!live.join("CLAUDE.md").exists());
+        assert!(!setup.manager.live_sibling(STAGED_SUFFIX).exists());
+        assert!(!setup.manager.live_sibling(PREVIOUS_SUFFIX).exists());
+        assert_eq!(
+            setup.manager.load_config()?.current.as_deref(),
+            Some("personal")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_failed_staging_leaves_live_config_alone() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+        fs::write(setup.claude_config_dir.join("config.json"), "personal")?;
+        setup.manager.save_account("personal")?;
+
+        // A file where the staging directory goes can't be cleared as a leftover
+        fs::write(setup.manager.live_sibling(STAGED_SUFFIX), "")?;
+        assert!(setup.manager.switch_account("work", None).is_err());
+        assert_eq!(
+            fs::read_to_string(setup.claude_config_dir.join("config.json"))?,
+            "personal"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_interrupted_switch_is_recovered() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+        fs::write(setup.claude_config_dir.join("config.json"), "personal")?;
+        setup.manager.save_account("personal")?;
+
+        // Killed after moving the live directory aside
+        let previous = setup.manager.live_sibling(PREVIOUS_SUFFIX);
+        fs::rename(&setup.claude_config_dir, &previous)?;
+        fs::write(previous.join("late.json"), "unsaved")?;
+
+        setup.manager.switch_account("work", None)?;
+        assert!(!previous.exists());
+        // The recovered directory was saved before being replaced
+        assert_eq!(
+            fs::read_to_string(setup.manager.switcher_dir.join("personal/late.json"))?,
+            "unsaved"
+        );
+        assert!(
+            fs::read_to_string(setup.claude_config_dir.join("config.json"))?.contains("test_key")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_inspection_stays_inside_it() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+
+        setup.manager.list_snapshot("work", None)?;
+        setup
+            .manager
+            .cat_snapshot("work", Path::new("config.json"), false)?;
+        assert!(
+            setup
+                .manager
+                .cat_snapshot("work", Path::new("../accounts.json"), false)
+                .is_err()
+        );
+        assert!(
+            setup
+                .manager
+                .list_snapshot("work", Some(Path::new("config.json")))
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_rolls_back_a_killed_save() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+        let snapshot = setup.manager.switcher_dir.join("work");
+
+        // Killed partway through copying over the snapshot it had kept aside
+        let kept = versions::allocate(&setup.manager.switcher_dir, "work", "2024-01-01T00:00:00Z")?;
+        fs::rename(&snapshot, &kept)?;
+        fs::create_dir_all(&snapshot)?;
+        fs::write(snapshot.join("half.json"), "{")?;
+        let mut marker = Marker::new(Operation::Save, "work", None, Utc::now());
+        marker.step = Step::CopyingSnapshot;
+        marker.undo = Some(Undo {
+            snapshot: snapshot.clone(),
+            kept: Some(kept.clone()),
+        });
+        fs::write(
+            setup.manager.state_dir.join(interrupt::FILE),
+            serde_json::to_string(&marker)?,
+        )?;
+
+        setup.manager.recover(Some(Recovery::Rollback))?;
+        assert!(!snapshot.join("half.json").exists());
+        assert!(snapshot.join("config.json").exists());
+        assert!(!kept.exists());
+        assert_eq!(interrupt::load(&setup.manager.state_dir)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_rolls_back_a_killed_switch() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+        fs::write(setup.claude_config_dir.join("config.json"), "personal")?;
+        setup.manager.save_account("personal")?;
+
+        // Killed once the half-copied new directory had been swapped in
+        let previous = setup.manager.live_sibling(PREVIOUS_SUFFIX);
+        fs::rename(&setup.claude_config_dir, &previous)?;
+        fs::create_dir_all(&setup.claude_config_dir)?;
+        let mut marker = Marker::new(Operation::Switch, "work", Some("personal"), Utc::now());
+        marker.step = Step::SwappingLive;
+        fs::write(
+            setup.manager.state_dir.join(interrupt::FILE),
+            serde_json::to_string(&marker)?,
+        )?;
+
+        setup.manager.recover(Some(Recovery::Rollback))?;
+        assert!(!previous.exists());
+        assert_eq!(
+            fs::read_to_string(setup.claude_config_dir.join("config.json"))?,
+            "personal"
+        );
+        let config = setup.manager.load_config()?;
+        assert_eq!(config.current.as_deref(), Some("personal"));
+
+        // Nothing is left to recover, so nothing switches
+        setup.manager.recover(Some(Recovery::Resume))?;
+        assert_eq!(
+            setup.manager.load_config()?.current.as_deref(),
+            Some("personal")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_upgrade_config_rewrites_with_backups() -> Result<()> {
+        let setup = TestSetup::new()?;
+        let manager = &setup.manager;
+        let old_accounts = r#"{"current": null, "accounts": {"work": {"saved_at": "2024-01-01T00:00:00+00:00", "path": "/tmp/work", "external": false}}}"#;
+        fs::write(&manager.accounts_file, old_accounts)?;
+        fs::create_dir_all(manager.settings_file.parent().unwrap())?;
+        fs::write(&manager.settings_file, "# mine\nmode = \"copy\"\n")?;
+
+        manager.upgrade_config(true)?;
+        assert_eq!(fs::read_to_string(&manager.accounts_file)?, old_accounts);
+
+        manager.upgrade_config(false)?;
+        let upgraded = fs::read_to_string(&manager.accounts_file)?;
+        assert!(!upgraded.contains("external"));
+        assert_eq!(
+            fs::read_to_string(manager.accounts_file.with_extension("json.bak"))?,
+            old_accounts
+        );
+        assert!(manager.settings_file.with_extension("toml.bak").exists());
+        assert!(manager.load_config()?.get_account("work").is_some());
+
+        // A second run finds nothing to change, so leaves the backups alone
+        fs::remove_file(manager.accounts_file.with_extension("json.bak"))?;
+        manager.upgrade_config(false)?;
+        assert!(!manager.accounts_file.with_extension("json.bak").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_upgrade_config_validates_before_writing() -> Result<()> {
+        let setup = TestSetup::new()?;
+        let manager = &setup.manager;
+        fs::write(
+            &manager.accounts_file,
+            r#"{ "current": null, "accounts": {} }"#,
+        )?;
+        fs::create_dir_all(manager.settings_file.parent().unwrap())?;
+        fs::write(&manager.settings_file, "resave-after = \"soon\"\n")?;
+
+        assert!(manager.upgrade_config(false).is_err());
+        assert!(!manager.accounts_file.with_extension("json.bak").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_capabilities_fall_back() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.manager.settings.credentials = CredentialStorage::Keychain;
+        setup.manager.capabilities.keychain = false;
+        setup.manager.capabilities.symlinks = false;
+        setup.create_mock_claude_config()?;
+        fs::write(setup.claude_config_dir.join(CREDENTIALS_FILE), "token")?;
+        setup.manager.save_account("work")?;
+        setup.manager.save_account("personal")?;
+
+        let work_dir = setup.manager.switcher_dir.join("work");
+        assert!(work_dir.join(CREDENTIALS_FILE).exists());
+        assert_eq!(setup.manager.credstore.load("work")?, None);
+
+        setup
+            .manager
+            .switch_account("work", Some(SwitchMode::Symlink))?;
+        assert!(!setup.claude_config_dir.is_symlink());
+        assert_eq!(
+            fs::read_to_string(setup.claude_config_dir.join(CREDENTIALS_FILE))?,
+            "token"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_changes_wait_for_the_state_lock() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+
+        let held = lock::acquire(&setup.manager.state_dir.join(lock::FILE), lock::Wait::Never)?;
+        let err = setup.manager.save_account("personal").unwrap_err();
+        assert!(err.to_string().contains("--wait"));
+        assert!(
+            setup
+                .manager
+                .load_config()?
+                .get_account("personal")
+                .is_none()
+        );
+        // Reading doesn't need the lock
+        setup.manager.list_accounts(
+            None,
+            ListFilter::default(),
+            ListSort::Name,
+            false,
+            &[],
+            None,
+        )?;
+
+        drop(held);
+        // A switch saves the outgoing account under the lock it already holds
+        setup.manager.save_account("personal")?;
+        setup.manager.switch_account("work", None)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_records_identity() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("blank")?;
+        assert!(
+            setup.manager.load_config()?.accounts["blank"]
+                .identity
+                .is_none()
+        );
+
+        fs::write(
+            setup.claude_config_dir.join(".credentials.json"),
+            r#"{"claudeAiOauth": {"subscriptionType": "max"}}"#,
+        )?;
+        fs::write(
+            setup.claude_config_dir.join(".claude.json"),
+            r#"{"oauthAccount": {"emailAddress": "dev@example.com", "organizationName": "Acme"}}"#,
+        )?;
+        setup.manager.save_account("work2")?;
+
+        let config = setup.manager.load_config()?;
+        assert_eq!(
+            config.accounts["work2"].identity,
+            Some(AccountIdentity {
+                email: Some("dev@example.com".to_string()),
+                organization: Some("Acme".to_string()),
+                plan: Some("max".to_string()),
+            })
+        );
+        setup.manager.show_current(false)?;
+        setup.manager.show_info("work2")?;
+        assert!(setup.manager.show_info("missing").is_err());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_switches_back_however_the_command_ends() -> Result<()> {
+        let setup = TestSetup::new()?;
+        let live = setup.claude_config_dir.join("config.json");
+        setup.create_mock_claude_config()?;
+        fs::write(&live, "work")?;
+        setup.manager.save_account("work")?;
+        fs::write(&live, "personal")?;
+        setup.manager.save_account("personal")?;
+
+        let check = |expected: &str| -> Vec<String> {
+            let script = format!("test \"$(cat '{}')\" = {}", live.display(), expected);
+            vec!["sh".to_string(), "-c".to_string(), script]
+        };
+        assert_eq!(setup.manager.run_as("work", &check("work"))?, 0);
+        assert_eq!(setup.manager.run_as("work", &check("personal"))?, 1);
+        assert!(
+            setup
+                .manager
+                .run_as("work", &["cas-no-such-program".to_string()])
+                .is_err()
+        );
+
+        assert_eq!(fs::read_to_string(&live)?, "personal");
+        let config = setup.manager.load_config()?;
+        assert_eq!(config.current.as_deref(), Some("personal"));
+        let sources: Vec<_> = history::load(&setup.manager.history_file())?
+            .into_iter()
+            .filter(|event| event.kind == EventKind::Switch)
+            .map(|event| event.source)
+            .collect();
+        assert_eq!(sources, [EventSource::Run; 6]);
+
+        // Already active: the command just runs
+        assert_eq!(setup.manager.run_as("personal", &check("personal"))?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_env_points_at_other_snapshots() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+        setup.manager.save_account("personal")?;
+        let manifest_path = manifest::path(&setup.manager.state_dir, "work");
+        assert!(manifest_path.exists());
+
+        let (name, dir) = setup.manager.direct_dir("work")?;
+        assert_eq!(name, "work");
+        assert_eq!(dir, setup.manager.switcher_dir.join("work"));
+        assert!(dir.join("config.json").is_file());
+        // Claude Code is about to change the files past it
+        assert!(!manifest_path.exists());
+
+        // The terminal is recorded as using it, once however often it asks
+        setup.manager.print_env("work", Some(Shell::Bash))?;
+        setup.manager.print_env("work", Some(Shell::Bash))?;
+        let sessions = sessions::live(&setup.manager.sessions_file())?;
+        if cfg!(unix) {
+            assert_eq!(sessions.len(), 1);
+            assert_eq!(sessions[0].account, "work");
+            assert_eq!(sessions[0].kind, SessionKind::Env);
+        }
+
+        // The active account is in the live configuration already
+        assert!(setup.manager.direct_dir("personal").is_err());
+        assert!(setup.manager.direct_dir("nope").is_err());
+        setup.manager.settings.capture_scope = CaptureScope::Credentials;
+        assert!(setup.manager.direct_dir("work").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_config_refuses_a_stale_copy() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+
+        let mut stale = setup.manager.load_config()?;
+        let mut other = setup.manager.load_config()?;
+        other.current = None;
+        setup.manager.save_config(&mut other)?;
+        // Saved again from the copy that's up to date
+        setup.manager.save_config(&mut other)?;
+
+        stale.current = Some("work".to_string());
+        let err = setup.manager.save_config(&mut stale).unwrap_err();
+        assert!(matches!(
+            crate::error::classify(&err),
+            Some(AccountError::Conflict)
+        ));
+        assert_eq!(setup.manager.load_config()?.current, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_run_needs_an_account_to_return_to() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+        let mut config = setup.manager.load_config()?;
+        config.current = None;
+        setup.manager.save_config(&mut config)?;
+
+        let err = setup
+            .manager
+            .run_as("work", &["true".to_string()])
+            .unwrap_err();
+        assert!(err.to_string().contains("save <name>"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_status_notices_unsaved_changes() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.status(true, false)?;
+        setup.manager.status(false, false)?;
+        setup.manager.save_account("work")?;
+
+        let meta = setup.manager.load_config()?.accounts["work"].clone();
+        assert!(!setup.manager.live_changed_since(&meta)?);
+
+        fs::File::options()
+            .write(true)
+            .open(setup.claude_config_dir.join("session.json"))?
+            .set_modified(SystemTime::now() + Duration::from_secs(60))?;
+        assert!(setup.manager.live_changed_since(&meta)?);
+        setup.manager.status(true, true)?;
+        setup.manager.status(false, false)?;
+        assert!(
+            setup
+                .manager
+                .sync_pending(&setup.manager.load_config()?)?
+                .is_none()
+        );
+
+        // Only captured files count
+        let manager = setup
+            .manager
+            .with_capture_overrides(&[], &["session.json".to_string()])?;
+        assert!(!manager.live_changed_since(&meta)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_compares_two_accounts() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        let live = &setup.claude_config_dir;
+        fs::write(
+            live.join("settings.json"),
+            r#"{"theme": "dark", "model": "opus"}"#,
+        )?;
+        fs::write(live.join("gone.json"), "{}")?;
+        setup.manager.save_account("work")?;
+        assert!(setup.manager.diff_accounts("work", "work", true)?);
+
+        // The same settings written differently are no change
+        fs::write(
+            live.join("settings.json"),
+            "{\n  \"model\": \"opus\",\n  \"theme\": \"dark\"\n}\n",
+        )?;
+        fs::remove_file(live.join("gone.json"))?;
+        fs::write(live.join("config.json"), r#"{"api_key": "personal"}"#)?;
+        setup.manager.save_account("personal")?;
+        assert!(!setup.manager.diff_accounts("work", "personal", true)?);
+
+        let changes = setup.manager.account_changes("work", "personal", true)?;
+        let summary: Vec<_> = changes
+            .iter()
+            .map(|change| (change.path.to_str().unwrap(), change.change))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("config.json", Change::Modified),
+                ("gone.json", Change::Removed),
+            ]
+        );
+        let diff = changes[0].diff.as_deref().unwrap();
+        assert!(diff.starts_with("--- work/config.json\n+++ personal/config.json\n"));
+        assert!(diff.contains("+  \"api_key\": \"personal\""));
+        assert!(
+            setup
+                .manager
+                .diff_accounts("work", "missing", false)
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_copies_files_between_accounts() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        let live = setup.claude_config_dir.clone();
+        fs::write(live.join("settings.json"), r#"{"mcp": "work"}"#)?;
+        fs::write(live.join("CLAUDE.md"), "work notes")?;
+        setup.manager.save_account("work")?;
+        fs::write(live.join("settings.json"), r#"{"mcp": "personal"}"#)?;
+        fs::remove_file(live.join("CLAUDE.md"))?;
+        setup.manager.save_account("personal")?;
+        setup.manager.save_account("spare")?;
+
+        let config = setup.manager.load_config()?;
+        let stored =
+            |name: &str, file: &str| fs::read_to_string(config.accounts[name].path.join(file)).ok();
+        let only = [PathBuf::from("settings.json"), PathBuf::from("CLAUDE.md")];
+
+        // A dry run writes nothing
+        setup
+            .manager
+            .merge_files("work", "personal", &only, false, true)?;
+        assert_eq!(stored("personal", "CLAUDE.md"), None);
+
+        // Declining the replacement still copies the new file
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["n"]));
+        setup
+            .manager
+            .merge_files("work", "personal", &only, false, false)?;
+        assert_eq!(
+            stored("personal", "CLAUDE.md").as_deref(),
+            Some("work notes")
+        );
+        assert_eq!(
+            stored("personal", "settings.json").as_deref(),
+            Some(r#"{"mcp": "personal"}"#)
+        );
+        // Not active, so ~/.claude was left alone
+        assert!(!live.join("CLAUDE.md").exists());
+        // What it held before is kept as a version
+        let versions = versions::list(&setup.manager.switcher_dir, "personal")?;
+        assert_eq!(versions.len(), 1);
+        assert!(!versions[0].path.join("CLAUDE.md").exists());
+        assert_eq!(
+            fs::read_to_string(versions[0].path.join("settings.json"))?,
+            r#"{"mcp": "personal"}"#
+        );
+
+        // Into the active account, the live files follow
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["y"]));
+        setup
+            .manager
+            .merge_files("work", "spare", &only[..1], false, false)?;
+        assert_eq!(
+            fs::read_to_string(live.join("settings.json"))?,
+            r#"{"mcp": "work"}"#
+        );
+
+        let credentials = [PathBuf::from(".credentials.json")];
+        assert!(
+            setup
+                .manager
+                .merge_files("work", "spare", &credentials, false, false)
+                .is_err()
+        );
+        assert!(
+            setup
+                .manager
+                .merge_files("work", "work", &only, false, false)
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_copies_single_files_out() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        let live = &setup.claude_config_dir;
+        fs::write(live.join("settings.json"), "{\"theme\": \"old\"}")?;
+        fs::create_dir_all(live.join("agents"))?;
+        fs::write(live.join("agents/review.md"), "review")?;
+        setup.manager.save_account("old")?;
+        fs::write(live.join("settings.json"), "{\"theme\": \"new\"}")?;
+        setup.manager.save_account("new")?;
+
+        let files = |paths: &[&str]| -> Vec<PathBuf> { paths.iter().map(PathBuf::from).collect() };
+        setup
+            .manager
+            .restore_files("old", &files(&["settings.json"]), None)?;
+        assert_eq!(
+            fs::read_to_string(live.join("settings.json"))?,
+            "{\"theme\": \"old\"}"
+        );
+        // Nothing else changed, and no switch happened
+        assert_eq!(setup.manager.load_config()?.current.as_deref(), Some("new"));
+
+        let out = setup._temp_dir.path().join("out");
+        setup.manager.restore_files(
+            "old",
+            &files(&["settings.json", "agents/review.md"]),
+            Some(&out),
+        )?;
+        assert_eq!(fs::read_to_string(out.join("agents/review.md"))?, "review");
+        let copy = setup._temp_dir.path().join("settings.old.json");
+        setup
+            .manager
+            .restore_files("old", &files(&["settings.json"]), Some(&copy))?;
+        assert!(copy.is_file());
+
+        for bad in ["../escape.json", "missing.json", ".credentials.json"] {
+            assert!(
+                setup
+                    .manager
+                    .restore_files("old", &files(&[bad]), None)
+                    .is_err()
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_lists_what_changed_since_saving() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        fs::write(setup.claude_config_dir.join("gone.json"), "{}")?;
+        setup.manager.save_account("work")?;
+        assert!(setup.manager.diff_live(None, false)?);
+
+        let live = &setup.claude_config_dir;
+        fs::write(live.join("config.json"), "{\"api_key\": \"other\"}\n")?;
+        fs::write(live.join("new.json"), "{}")?;
+        fs::remove_file(live.join("gone.json"))?;
+        // The switch-time banner isn't a change
+        setup.manager.write_banner("Account: work")?;
+
+        let meta = setup.manager.load_config()?.accounts["work"].clone();
+        let changes = setup.manager.live_changes("work", &meta.path, true)?;
+        let summary: Vec<_> = changes
+            .iter()
+            .map(|change| (change.path.to_str().unwrap(), change.change))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("config.json", Change::Modified),
+                ("gone.json", Change::Removed),
+                ("new.json", Change::Added),
+            ]
+        );
+        let diff = changes[0].diff.as_deref().unwrap();
+        assert!(diff.starts_with("--- work/config.json\n+++ live/config.json\n"));
+        assert!(diff.contains("+{\"api_key\": \"other\"}"));
+
+        assert!(!setup.manager.diff_live(Some("work"), true)?);
+        assert!(setup.manager.diff_live(Some("missing"), false).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_switch_stops_for_unsaved_changes() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("personal")?;
+        setup.manager.save_account("work")?;
+        setup.manager.unsaved = Unsaved::Refuse;
+        let live = setup.claude_config_dir.clone();
+        let saved = setup.manager.switcher_dir.join("work/config.json");
+
+        // A refreshed token alone doesn't count
+        fs::write(live.join(CREDENTIALS_FILE), "{}")?;
+        setup.manager.switch_account("personal", None)?;
+        setup.manager.switch_account("work", None)?;
+
+        fs::write(live.join("config.json"), "{\"api_key\": \"edited\"}")?;
+        let error = setup
+            .manager
+            .switch_account("personal", None)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("1 file(s)"));
+        assert_eq!(
+            setup.manager.load_config()?.current.as_deref(),
+            Some("work")
+        );
+
+        setup.manager.unsaved = Unsaved::Discard;
+        setup.manager.switch_account("personal", None)?;
+        assert!(!fs::read_to_string(&saved)?.contains("edited"));
+
+        setup.manager.switch_account("work", None)?;
+        fs::write(live.join("config.json"), "{\"api_key\": \"edited\"}")?;
+        setup.manager.unsaved = Unsaved::Save;
+        setup.manager.switch_account("personal", None)?;
+        assert!(fs::read_to_string(&saved)?.contains("edited"));
+
+        // Renamed along with the account
+        setup.manager.rename_account("work", "job")?;
+        assert!(manifest::path(&setup.manager.state_dir, "job").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_over_another_account_and_unsaved_switch_ask_first() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("personal")?;
+        setup.manager.save_account("work")?;
+        let live = setup.claude_config_dir.clone();
+        let store = setup.manager.switcher_dir.clone();
+        let saved = |name: &str| fs::read_to_string(store.join(name).join("config.json"));
+        let mut manager = setup
+            .manager
+            .with_prompter(ScriptedPrompter::new(["n", ""]));
+        manager.unsaved = Unsaved::Refuse;
+
+        // Declined, so 'personal' keeps what it had
+        fs::write(live.join("config.json"), "{\"api_key\": \"edited\"}")?;
+        manager.save_account("personal")?;
+        assert!(!saved("personal")?.contains("edited"));
+
+        // An empty answer takes the default: save them to 'work' first
+        manager.switch_account("personal", None)?;
+        assert!(saved("work")?.contains("edited"));
+        assert_eq!(manager.load_config()?.current.as_deref(), Some("personal"));
+
+        // With nobody left to ask, it takes --overwrite
+        let error = manager.save_account("work").unwrap_err();
+        assert!(matches!(
+            crate::error::classify(&error),
+            Some(AccountError::AlreadyExists(_))
+        ));
+        let manager = manager.with_overwrite(true);
+        manager.save_account("work")?;
+        assert_eq!(manager.load_config()?.current.as_deref(), Some("work"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_repeated_save_copies_only_changes() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        let live = setup.claude_config_dir.clone();
+        fs::create_dir_all(live.join("projects"))?;
+        fs::write(live.join("projects/log.jsonl"), "log")?;
+        setup.manager.save_from("work", EventSource::Cli)?;
+
+        fs::write(live.join("config.json"), "edited")?;
+        fs::remove_file(live.join("projects/log.jsonl"))?;
+        let transfer = setup.manager.save_from("work", EventSource::Cli)?.unwrap();
+        assert_eq!(transfer.usage.files, 1);
+
+        let snapshot = setup.manager.switcher_dir.join("work");
+        assert_eq!(fs::read_to_string(snapshot.join("config.json"))?, "edited");
+        assert!(snapshot.join("session.json").is_file());
+        assert!(!snapshot.join("projects/log.jsonl").exists());
+        // The version it replaced kept everything as it was
+        let version = &versions::list(&setup.manager.switcher_dir, "work")?[0];
+        assert_eq!(
+            fs::read_to_string(version.path.join("config.json"))?,
+            r#"{"api_key": "test_key"}"#
+        );
+        assert!(version.path.join("projects/log.jsonl").is_file());
+
+        // Without versions the snapshot is updated where it is
+        setup.manager.settings.keep_versions = Some(0);
+        fs::write(live.join("session.json"), "ended")?;
+        fs::remove_file(live.join("config.json"))?;
+        let transfer = setup.manager.save_from("work", EventSource::Cli)?.unwrap();
+        assert_eq!(transfer.usage.files, 1);
+        assert_eq!(fs::read_to_string(snapshot.join("session.json"))?, "ended");
+        assert!(!snapshot.join("config.json").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_notices_rewrites_keeping_size_and_time() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        let settings = setup.claude_config_dir.join("settings.json");
+        fs::write(&settings, "aaaa")?;
+        let modified = fs::metadata(&settings)?.modified()?;
+        setup.manager.save_from("work", EventSource::Cli)?;
+
+        // Same size, and the time put back as it was
+        fs::write(&settings, "bbbb")?;
+        fs::File::options()
+            .write(true)
+            .open(&settings)?
+            .set_modified(modified)?;
+        let transfer = setup.manager.save_from("work", EventSource::Cli)?.unwrap();
+        assert_eq!(transfer.usage.files, 1);
+        let snapshot = setup.manager.switcher_dir.join("work");
+        assert_eq!(fs::read_to_string(snapshot.join("settings.json"))?, "bbbb");
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dedup_shares_files_and_gc_collects() -> Result<()> {
+        use std::os::unix::fs::MetadataExt;
+        let mut setup = TestSetup::new()?;
+        setup.manager.settings.dedup = true;
+        setup.manager.settings.keep_versions = Some(0);
+        setup.create_mock_claude_config()?;
+        setup.manager.save_from("work", EventSource::Cli)?;
+        setup.manager.save_from("personal", EventSource::Cli)?;
+
+        let (work, personal) = (
+            setup.manager.switcher_dir.join("work/config.json"),
+            setup.manager.switcher_dir.join("personal/config.json"),
+        );
+        assert_eq!(fs::metadata(&work)?.ino(), fs::metadata(&personal)?.ino());
+        assert_eq!(fs::metadata(&work)?.nlink(), 3);
+
+        // Still linked from the other snapshot
+        setup.manager.delete_account("work", false)?;
+        let objects = setup.manager.objects_dir();
+        let hash = hashing::hash_file(&personal)?;
+        setup.manager.collect_garbage(false)?;
+        assert!(objects::object_path(&objects, &hash).exists());
+
+        // And from the trash, until the deleted account is purged from it
+        fs::write(setup.claude_config_dir.join("config.json"), "edited")?;
+        setup.manager.save_from("personal", EventSource::Cli)?;
+        setup.manager.collect_garbage(false)?;
+        assert!(objects::object_path(&objects, &hash).exists());
+        trash::purge(&setup.manager.switcher_dir, None)?;
+        setup.manager.collect_garbage(false)?;
+        assert!(!objects::object_path(&objects, &hash).exists());
+        assert_eq!(fs::metadata(&personal)?.nlink(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compressed_store_roundtrip() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.manager.settings.compress = Some(3);
+        setup.manager.settings.banner = Some("ACTIVE ACCOUNT: {name}".to_string());
+        setup.create_mock_claude_config()?;
+        let live = setup.claude_config_dir.clone();
+        let work_log = "{\"type\": \"work\"}\n".repeat(200);
+        fs::create_dir_all(live.join("projects"))?;
+        fs::write(live.join("projects/log.jsonl"), &work_log)?;
+        setup.manager.save_account("work")?;
+
+        let work = setup.manager.switcher_dir.join("work");
+        assert!(compression::is_compressed(&fs::read(
+            work.join("projects/log.jsonl")
+        )?));
+        // Too small to gain anything
+        assert_eq!(
+            fs::read_to_string(work.join("config.json"))?,
+            r#"{"api_key": "test_key"}"#
+        );
+
+        let personal_log = "{\"type\": \"personal\"}\n".repeat(200);
+        fs::write(live.join("projects/log.jsonl"), &personal_log)?;
+        setup.manager.save_account("personal")?;
+        setup.manager.switch_account("work", None)?;
+        assert_eq!(
+            fs::read_to_string(live.join("projects/log.jsonl"))?,
+            work_log
+        );
+
+        // The banner comes off a compressed CLAUDE.md as well
+        let notes = "Keep answers short.\n".repeat(50);
+        let with_banner = fs::read_to_string(live.join(banner::FILE))?;
+        fs::write(live.join(banner::FILE), format!("{}{}", with_banner, notes))?;
+        setup.manager.save_account("work")?;
+        let saved = fs::read(work.join(banner::FILE))?;
+        assert!(compression::is_compressed(&saved));
+        assert_eq!(compression::decompress(saved)?, notes.as_bytes());
+
+        // Files stay readable once compression is turned off again
+        setup.manager.settings.compress = None;
+        setup.manager.switch_account("personal", None)?;
+        assert_eq!(
+            fs::read_to_string(live.join("projects/log.jsonl"))?,
+            personal_log
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_measure_accounts_leaves_out_nested_snapshots() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.manager.settings.keep_versions = Some(2);
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("client")?;
+        setup.manager.save_account("client")?;
+        fs::write(setup.claude_config_dir.join("extra.json"), "{}")?;
+        setup.manager.save_account("client/dev")?;
+
+        let config = setup.manager.load_config()?;
+        let accounts: Vec<_> = config.accounts.iter().collect();
+        let usages = setup
+            .manager
+            .measure_accounts(&config, &accounts, true, 2)?;
+        let usage = |name: &str| {
+            let index = accounts.iter().position(|(n, _)| *n == name).unwrap();
+            usages[index]
+        };
+        assert_eq!(usage("client").0.files, 2);
+        assert_eq!(usage("client").1.files, 2);
+        assert_eq!(usage("client/dev").0.files, 3);
+        assert_eq!(usage("client/dev").1, DirUsage::default());
+
+        setup.manager.show_sizes(None, 2)?;
+        setup.manager.show_sizes(Some("client"), 1)?;
+        assert!(setup.manager.show_sizes(Some("missing"), 1).is_err());
+        setup.manager.list_accounts(
+            None,
+            ListFilter::default(),
+            ListSort::Name,
+            true,
+            &[],
+            None,
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune_applies_retention_rules() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        let at = |date: &str| -> Result<DateTime<Utc>> {
+            Ok(DateTime::parse_from_rfc3339(date)?.with_timezone(&Utc))
+        };
+        setup.create_mock_claude_config()?;
+        let live = setup.claude_config_dir.clone();
+        fs::create_dir_all(live.join("projects/app"))?;
+        fs::write(live.join("projects/app/log.jsonl"), "log")?;
+
+        setup.manager.clock = Box::new(FixedClock(at("2024-01-01T00:00:00Z")?));
+        setup.manager.save_account("old")?;
+        setup.manager.save_account("client")?;
+        setup.manager.set_protected("client", true)?;
+        setup.manager.clock = Box::new(FixedClock(at("2024-06-01T00:00:00Z")?));
+        for _ in 0..3 {
+            setup.manager.save_account("work")?;
+        }
+
+        let work = setup.manager.switcher_dir.join("work");
+        let strip = ["projects/".to_string()];
+        setup.manager.prune(Some(1), Some("90d"), &strip, true)?;
+        assert_eq!(
+            versions::list(&setup.manager.switcher_dir, "work")?.len(),
+            2
+        );
+        assert!(work.join("projects/app/log.jsonl").is_file());
+
+        // Declining keeps the accounts but applies the other rules
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["n"]));
+        setup.manager.prune(Some(1), Some("90d"), &strip, false)?;
+        let kept = versions::list(&setup.manager.switcher_dir, "work")?;
+        assert_eq!(kept.len(), 1);
+        assert!(!kept[0].path.join("projects").exists());
+        assert!(!work.join("projects").exists());
+        assert!(work.join("config.json").is_file());
+        assert!(setup.manager.load_config()?.get_account("old").is_some());
+        setup.manager.verify(&[], true, false)?;
+
+        // The active and protected accounts are never unused
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["y"]));
+        setup.manager.prune(None, Some("90d"), &[], false)?;
+        let config = setup.manager.load_config()?;
+        assert!(config.get_account("old").is_none());
+        assert!(!setup.manager.switcher_dir.join("old").exists());
+        assert!(config.get_account("client").is_some());
+        assert!(config.get_account("work").is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy_account_leaves_live_config_alone() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.manager.settings.keep_versions = Some(2);
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+        setup.manager.save_account("work")?;
+        fs::write(setup.claude_config_dir.join("config.json"), "unsaved")?;
+
+        setup.manager.copy_account("work", "work/mcp")?;
+        let copy = setup.manager.switcher_dir.join("work/mcp");
+        assert_eq!(
+            fs::read_to_string(copy.join("config.json"))?,
+            r#"{"api_key": "test_key"}"#
+        );
+        assert_eq!(
+            fs::read_to_string(setup.claude_config_dir.join("config.json"))?,
+            "unsaved"
+        );
+        assert!(versions::list(&setup.manager.switcher_dir, "work/mcp")?.is_empty());
+
+        // Copying the parent leaves the nested copy out
+        setup.manager.copy_account("work", "home")?;
+        assert!(!setup.manager.switcher_dir.join("home/mcp").exists());
+
+        let config = setup.manager.load_config()?;
+        assert_eq!(config.current.as_deref(), Some("work"));
+        assert_eq!(config.get_account("work/mcp").unwrap().path, copy);
+        assert!(setup.manager.copy_account("work", "home").is_err());
+        assert!(setup.manager.copy_account("missing", "other").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_preset_carries_setup_without_logins() -> Result<()> {
+        let source = TestSetup::new()?;
+        source.create_mock_claude_config()?;
+        fs::write(source.claude_config_dir.join("settings.json"), "{}")?;
+        source.manager.save_account("work")?;
+        source.manager.save_account("personal")?;
+        source.manager.set_alias("w", "work")?;
+        source.manager.set_alias("p", "personal")?;
+        source
+            .manager
+            .tag_account("work", &["client".to_string()], true)?;
+        source.manager.save_template("strict", None)?;
+        let hooks = Settings {
+            hooks: crate::settings::HookSettings {
+                post_switch: Some("tmux refresh-client -S".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        hooks.save(&source.manager.settings_file)?;
+        let file = source._temp_dir.path().join("preset.json");
+        source.manager.export_preset(Some(&file))?;
+        let contents = fs::read_to_string(&file)?;
+        assert!(!contents.contains("test_key"));
+
+        let mut target = TestSetup::new()?;
+        target.create_mock_claude_config()?;
+        target.manager.save_account("work")?;
+        Settings {
+            keep_versions: Some(2),
+            ..Default::default()
+        }
+        .save(&target.manager.settings_file)?;
+        // Declined, the hooks aren't the only thing left out
+        target.manager.prompter = Box::new(ScriptedPrompter::new(["n"]));
+        target.manager.import_preset(&file)?;
+        assert!(target.manager.load_config()?.aliases.is_empty());
+        target.manager.assume_yes = true;
+        target.manager.import_preset(&file)?;
+
+        let config = target.manager.load_config()?;
+        assert_eq!(config.resolve("w"), Some("work"));
+        // `personal` isn't saved here
+        assert!(!config.aliases.contains_key("p"));
+        assert!(config.accounts["work"].tags.contains("client"));
+        let settings = Settings::load(&target.manager.settings_file)?;
+        assert_eq!(settings.hooks, hooks.hooks);
+        assert_eq!(settings.keep_versions, Some(2));
+        assert_eq!(
+            template::list(&target.manager.switcher_dir)?,
+            ["strict".to_string()]
+        );
+
+        fs::write(
+            &file,
+            contents.replace("\"settings.json\"", "\"../escape.json\""),
+        )?;
+        assert!(target.manager.import_preset(&file).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_template_layers_settings_over_live_login() -> Result<()> {
+        let setup = TestSetup::new()?;
+        let live = &setup.claude_config_dir;
+        fs::create_dir_all(live)?;
+        fs::write(
+            live.join("settings.json"),
+            r#"{"permissions": {"defaultMode": "plan"}}"#,
+        )?;
+        fs::write(live.join("CLAUDE.md"), "Be strict.\n")?;
+        fs::write(
+            live.join(".claude.json"),
+            r#"{"oauthAccount": {"emailAddress": "a@example.com"}, "mcpServers": {"github": {"command": "gh-mcp"}}}"#,
+        )?;
+        fs::write(live.join(".credentials.json"), "token-a")?;
+        setup.manager.save_template("strict", None)?;
+
+        let template = template::dir(&setup.manager.switcher_dir, "strict");
+        assert!(template.join(template::MCP_FILE).exists());
+        assert!(!template.join(".credentials.json").exists());
+        assert!(!template.join(template::CLAUDE_JSON).exists());
+
+        // Another login, with settings of its own
+        fs::write(live.join("settings.json"), r#"{"model": "opus"}"#)?;
+        fs::remove_file(live.join("CLAUDE.md"))?;
+        fs::write(
+            live.join(".claude.json"),
+            r#"{"oauthAccount": {"emailAddress": "b@example.com"}}"#,
+        )?;
+        fs::write(live.join(".credentials.json"), "token-b")?;
+        setup.manager.apply_template("strict")?;
+
+        let settings: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(live.join("settings.json"))?)?;
+        assert_eq!(
+            settings,
+            serde_json::json!({"model": "opus", "permissions": {"defaultMode": "plan"}})
+        );
+        assert_eq!(fs::read_to_string(live.join("CLAUDE.md"))?, "Be strict.\n");
+        let claude_json = fs::read_to_string(live.join(".claude.json"))?;
+        assert_eq!(
+            claude::read_email(&claude_json).as_deref(),
+            Some("b@example.com")
+        );
+        assert!(claude_json.contains("gh-mcp"));
+        assert_eq!(
+            fs::read_to_string(live.join(".credentials.json"))?,
+            "token-b"
+        );
+
+        assert!(setup.manager.apply_template("missing").is_err());
+        assert!(setup.manager.save_template("..", None).is_err());
+        assert!(setup.manager.save_account(template::DIR).is_err());
+        setup.manager.delete_template("strict")?;
+        assert!(template::list(&setup.manager.switcher_dir)?.is_empty());
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hooks_run_around_switch() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+        setup.manager.save_account("personal")?;
+
+        let log = setup._temp_dir.path().join("switches");
+        setup.manager.settings.hooks.post_switch = Some(format!(
+            "echo \"$CLAUDE_ACCOUNT_OLD>$CLAUDE_ACCOUNT_NEW $CLAUDE_ACCOUNT_SOURCE\" >> {}",
+            log.display()
+        ));
+        setup.manager.switch_account("work", None)?;
+        assert_eq!(fs::read_to_string(&log)?, "personal>work cli\n");
+
+        // A failing pre-switch hook keeps the account that was active
+        setup.manager.settings.hooks.pre_switch = Some("exit 1".to_string());
+        assert!(setup.manager.switch_account("personal", None).is_err());
+        let config = setup.manager.load_config()?;
+        assert_eq!(config.current.as_deref(), Some("work"));
+        assert_eq!(fs::read_to_string(&log)?, "personal>work cli\n");
+
+        // So does one vetoing the save of the changes it would replace
+        setup.manager.settings.hooks.pre_switch = None;
+        setup.manager.settings.hooks.pre_save = Some("exit 1".to_string());
+        setup.manager.unsaved = Unsaved::Save;
+        let scratch = setup.claude_config_dir.join("scratch.txt");
+        fs::write(&scratch, "unsaved")?;
+        assert!(setup.manager.switch_account("personal", None).is_err());
+        let config = setup.manager.load_config()?;
+        assert_eq!(config.current.as_deref(), Some("work"));
+        assert_eq!(fs::read_to_string(&scratch)?, "unsaved");
+        Ok(())
+    }
+
+    #[test]
+    fn test_data_api_returns_outcomes() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        let manager = setup.manager.with_quiet(true);
+
+        assert!(
+            manager
+                .save("work")?
+                .is_some_and(|copied| copied.usage.files > 0)
+        );
+        manager.save("personal")?;
+        assert_eq!(manager.current_account()?.as_deref(), Some("personal"));
+
+        let outcome = manager.switch("work", None)?;
+        assert!(matches!(outcome, SwitchOutcome::Switched(Some(_))));
+        assert_eq!(manager.current_account()?.as_deref(), Some("work"));
+        assert!(manager.accounts_config()?.get_account("personal").is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_undo_switch_restores_replaced_live() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        let live = setup.claude_config_dir.clone();
+        setup.manager.save_account("work")?;
+        fs::write(live.join("config.json"), "personal")?;
+        setup.manager.save_account("personal")?;
+        // Changed after the save, and thrown away by the switch
+        fs::write(live.join("scratch.txt"), "unsaved")?;
+        setup.manager.unsaved = Unsaved::Discard;
+        setup.manager.switch_account("work", None)?;
+        assert!(!live.join("scratch.txt").exists());
+        // Set aside after the switch; undoing it still takes the one it made
+        let later = setup.manager.live_backups_dir().join("29991231T000000Z");
+        fs::create_dir_all(&later)?;
+
+        setup.manager.undo()?;
+        assert!(later.exists());
+        assert_eq!(fs::read_to_string(live.join("scratch.txt"))?, "unsaved");
+        assert_eq!(fs::read_to_string(live.join("config.json"))?, "personal");
+        let config = setup.manager.load_config()?;
+        assert_eq!(config.current.as_deref(), Some("personal"));
+        // Only once
+        assert!(setup.manager.undo().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_undo_switch_without_live_backups() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.settings.keep_live_backups = Some(0);
+        setup.manager.save_account("work")?;
+        setup.manager.save_account("personal")?;
+        setup.manager.unsaved = Unsaved::Discard;
+        setup.manager.switch_account("work", None)?;
+
+        let err = setup.manager.undo().unwrap_err();
+        assert!(err.to_string().contains("keep-live-backups is 0"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_live_brings_back_replaced_directories() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        let live = setup.claude_config_dir.clone();
+        setup.manager.settings.keep_live_backups = Some(2);
+        setup.manager.save_account("work")?;
+        setup.manager.save_account("personal")?;
+        setup.manager.unsaved = Unsaved::Discard;
+        assert!(setup.manager.recover_live(None).is_err());
+
+        for step in 1..=3 {
+            fs::write(live.join("scratch.txt"), format!("step {}", step))?;
+            let name = if step % 2 == 1 { "work" } else { "personal" };
+            setup.manager.switch_account(name, None)?;
+        }
+        // Only the newest two are kept
+        let backups = live_backups::list(&setup.manager.live_backups_dir())?;
+        let accounts: Vec<_> = backups
+            .iter()
+            .map(|backup| backup.account.as_deref())
+            .collect();
+        assert_eq!(accounts, [Some("personal"), Some("work")]);
+
+        // The older one, by id; the account active in it is active again
+        setup.manager.recover_live(Some(&backups[1].id))?;
+        assert_eq!(fs::read_to_string(live.join("scratch.txt"))?, "step 2");
+        assert_eq!(
+            setup.manager.load_config()?.current.as_deref(),
+            Some("work")
+        );
+
+        // And the directory that replaced is kept in turn
+        let kept = live_backups::list(&setup.manager.live_backups_dir())?;
+        assert_eq!(kept[0].account.as_deref(), Some("work"));
+        assert!(setup.manager.recover_live(Some("nope")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_account_vars() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("client")?;
+        setup
+            .manager
+            .set_vars("client", &["HTTPS_PROXY=http://proxy:8080".to_string()])?;
+        assert!(
+            setup
+                .manager
+                .set_vars("client", &["NO VALUE".to_string()])
+                .is_err()
+        );
+        assert!(
+            setup
+                .manager
+                .set_vars("client", &["1BAD=x".to_string()])
+                .is_err()
+        );
+        // Saving the account again keeps them
+        setup.manager.save_account("client")?;
+        let config = setup.manager.load_config()?;
+        assert_eq!(
+            config.accounts["client"].env,
+            BTreeMap::from([("HTTPS_PROXY".to_string(), "http://proxy:8080".to_string())])
+        );
+        // What the hook would export in a new shell
+        let contents = fs::read_to_string(&setup.manager.accounts_file)?;
+        assert!(
+            hook::env_updates(clap_complete::Shell::Bash, &contents, None)?
+                .contains("export HTTPS_PROXY='http://proxy:8080'")
+        );
+
+        setup
+            .manager
+            .unset_vars("client", &["HTTPS_PROXY".to_string()])?;
+        assert!(
+            setup.manager.load_config()?.accounts["client"]
+                .env
+                .is_empty()
+        );
+        setup.manager.list_vars("client")?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_system_store_keeps_the_active_account_per_user() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        let root = setup._temp_dir.path().join("system");
+        fs::create_dir_all(root.join("accounts"))?;
+        setup.manager.system = Some(root.clone());
+        setup.manager.switcher_dir = root.join("accounts");
+        setup.manager.accounts_file = root.join(config::FILE);
+        setup.manager.settings.mode = SwitchMode::Symlink;
+
+        setup.manager.save_account("team")?;
+        setup.manager.save_account("ops")?;
+        let shared = AccountsConfig::load(&root.join(config::FILE))?;
+        assert_eq!(shared.current, None);
+        assert_eq!(shared.accounts.len(), 2);
+        assert!(
+            shared.accounts["team"]
+                .path
+                .starts_with(root.join("accounts"))
+        );
+
+        // The mode setting is for this user's own accounts
+        setup.manager.switch_account("team", None)?;
+        assert!(!setup.claude_config_dir.is_symlink());
+        assert!(
+            setup
+                .manager
+                .switch_account("ops", Some(SwitchMode::Symlink))
+                .is_err()
+        );
+        let active = Activation::load(&setup.manager.state_dir.join(system::ACTIVE_FILE))?;
+        assert_eq!(active.current.as_deref(), Some("team"));
+        assert_eq!(active.previous.as_deref(), Some("ops"));
+        assert_eq!(
+            AccountsConfig::load(&root.join(config::FILE))?.current,
+            None
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |path: &Path| -> Result<u32> {
+                Ok(fs::metadata(path)?.permissions().mode() & 0o7777)
             };
+            assert_eq!(mode(&root.join("accounts/team"))?, 0o2750);
+            assert_eq!(mode(&root.join("accounts/team/config.json"))?, 0o640);
+            assert_eq!(mode(&setup.claude_config_dir)?, 0o700);
+            assert_eq!(mode(&setup.claude_config_dir.join("config.json"))?, 0o600);
+        }
+        Ok(())
+    }
 
-            Ok(Self {
-                _temp_dir: temp_dir,
-                manager,
-                claude_config_dir,
-            })
+    #[test]
+    fn test_system_store_shows_only_what_access_grants() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        let root = setup._temp_dir.path().join("system");
+        fs::create_dir_all(root.join("accounts"))?;
+        setup.manager.system = Some(root.clone());
+        setup.manager.switcher_dir = root.join("accounts");
+        setup.manager.accounts_file = root.join(config::FILE);
+        setup.manager.save_account("pool-1")?;
+        setup.manager.save_account("admin")?;
+
+        let access: system::Access = toml::from_str("[groups]\nlab = [\"pool-*\"]")?;
+        setup.manager.grant = Some(access.grant("alice", &["lab".to_string()]));
+        let config = setup.manager.load_config()?;
+        assert_eq!(config.accounts.keys().collect::<Vec<_>>(), ["pool-1"]);
+        assert!(setup.manager.switch_account("admin", None).is_err());
+        setup.manager.switch_account("pool-1", None)?;
+        // The accounts kept from this user are still in the shared file
+        let shared = AccountsConfig::load(&root.join(config::FILE))?;
+        assert!(shared.accounts.contains_key("admin"));
+        assert_eq!(shared.accounts["pool-1"].switch_count, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_preview_switch_asks_first() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        let live = setup.claude_config_dir.clone();
+        fs::write(live.join("settings.json"), r#"{"model": "opus"}"#)?;
+        setup.manager.save_account("work")?;
+        fs::write(live.join("settings.json"), r#"{"model": "sonnet"}"#)?;
+        fs::write(live.join("notes.md"), "personal")?;
+        setup.manager.save_account("personal")?;
+        setup.manager.unsaved = Unsaved::Discard;
+        setup.manager.switch_account("work", None)?;
+
+        let config = setup.manager.load_config()?;
+        let mut changes =
+            setup
+                .manager
+                .live_changes("personal", &config.accounts["personal"].path, false)?;
+        changes.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(changes.len(), 2);
+
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["n"]));
+        setup.manager.preview_switch("personal", None)?;
+        assert_eq!(
+            setup.manager.load_config()?.current.as_deref(),
+            Some("work")
+        );
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["y"]));
+        setup.manager.preview_switch("personal", None)?;
+        assert_eq!(
+            setup.manager.load_config()?.current.as_deref(),
+            Some("personal")
+        );
+        assert!(is_settings_file(Path::new("settings.local.json")));
+        assert!(!is_settings_file(Path::new("config.json")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_checkpoint_save_and_restore() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        let live = setup.claude_config_dir.clone();
+        setup.manager.settings.keep_checkpoints = Some(2);
+        setup.manager.save_account("work")?;
+        setup.manager.save_checkpoint("before-upgrade")?;
+        assert!(setup.manager.save_checkpoint("before-upgrade").is_err());
+
+        fs::write(live.join("settings.json"), "risky")?;
+        setup.manager.restore_checkpoint("before-upgrade")?;
+        assert!(!live.join("settings.json").exists());
+        assert_eq!(
+            fs::read_to_string(live.join("config.json"))?,
+            r#"{"api_key": "test_key"}"#
+        );
+        // What it replaced can still be put back
+        let kept = live_backups::list(&setup.manager.live_backups_dir())?;
+        assert_eq!(kept[0].account.as_deref(), Some("work"));
+        assert!(kept[0].path.join("settings.json").exists());
+
+        // Only the newest two are kept, and a label is replaced with --force
+        setup.manager.clock = Box::new(FixedClock(Utc::now() + chrono::Duration::minutes(1)));
+        setup.manager.save_checkpoint("risky")?;
+        setup.manager.clock = Box::new(FixedClock(Utc::now() + chrono::Duration::minutes(2)));
+        setup.manager.save_checkpoint("third")?;
+        setup.manager.force = true;
+        setup.manager.save_checkpoint("third")?;
+        let labels: Vec<_> = checkpoints::list(&setup.manager.checkpoints_dir())?
+            .into_iter()
+            .map(|checkpoint| checkpoint.label)
+            .collect();
+        assert_eq!(labels, ["third", "risky"]);
+
+        setup.manager.delete_checkpoint("risky")?;
+        assert!(setup.manager.restore_checkpoint("risky").is_err());
+        setup.manager.list_checkpoints()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_undo_delete_and_rename() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+        setup.manager.save_account("work")?;
+        setup.manager.save_account("personal")?;
+
+        setup.manager.delete_account("work", false)?;
+        assert!(!setup.manager.switcher_dir.join("work").exists());
+        setup.manager.undo()?;
+        let config = setup.manager.load_config()?;
+        assert!(config.get_account("work").is_some());
+        assert!(
+            setup
+                .manager
+                .switcher_dir
+                .join("work/config.json")
+                .is_file()
+        );
+        assert_eq!(
+            versions::list(&setup.manager.switcher_dir, "work")?.len(),
+            1
+        );
+        assert!(trash::latest(&setup.manager.switcher_dir, "work")?.is_none());
+
+        setup.manager.rename_account("work", "client")?;
+        setup.manager.undo()?;
+        let config = setup.manager.load_config()?;
+        assert!(config.get_account("work").is_some());
+        assert!(config.get_account("client").is_none());
+
+        // Saves can't be undone
+        setup.manager.save_account("personal")?;
+        assert!(setup.manager.undo().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_trash_restore_and_empty() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.manager.assume_yes = true;
+        setup.create_mock_claude_config()?;
+        let live = &setup.claude_config_dir;
+        fs::write(live.join("config.json"), "first")?;
+        setup.manager.save_account("work")?;
+        setup.manager.save_account("personal")?;
+        setup.manager.delete_account("work", false)?;
+        fs::write(live.join("config.json"), "second")?;
+        setup.manager.save_account("work")?;
+        setup.manager.delete_account("work", false)?;
+        setup.manager.delete_account("personal", false)?;
+
+        let entries = trash::list(&setup.manager.switcher_dir)?;
+        assert_eq!(entries.len(), 3);
+        // The newer of the two deletes comes back
+        setup.manager.restore_trashed("work")?;
+        let snapshot = setup.manager.switcher_dir.join("work/config.json");
+        assert_eq!(fs::read_to_string(snapshot)?, "second");
+        assert!(setup.manager.restore_trashed("work").is_err());
+        assert!(setup.manager.restore_trashed("client").is_err());
+
+        // Both left were deleted just now
+        setup.manager.empty_trash(Some("1h"))?;
+        assert_eq!(trash::list(&setup.manager.switcher_dir)?.len(), 2);
+        setup.manager.empty_trash(Some("0s"))?;
+        assert!(trash::list(&setup.manager.switcher_dir)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_backup_and_restore() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.manager.assume_yes = true;
+        setup.create_mock_claude_config()?;
+        let live = &setup.claude_config_dir;
+        fs::write(live.join("config.json"), "work")?;
+        setup.manager.save_account("work")?;
+        fs::write(live.join("config.json"), "personal")?;
+        setup.manager.save_account("personal")?;
+        setup.manager.create_backup(None)?;
+
+        let backups_dir = setup.manager.state_dir.join(backup::DIR);
+        let backups = backup::list(&backups_dir)?;
+        assert_eq!(backups.len(), 1);
+        setup.manager.delete_account("work", false)?;
+        fs::write(live.join("config.json"), "changed")?;
+        setup.manager.save_account("personal")?;
+
+        setup.manager.restore_backup(&backups[0])?;
+        let config = setup.manager.load_config()?;
+        assert!(config.get_account("work").is_some());
+        let snapshot = setup.manager.switcher_dir.join("personal/config.json");
+        assert_eq!(fs::read_to_string(snapshot)?, "personal");
+        assert_eq!(
+            setup.manager.current_account()?.as_deref(),
+            Some("personal")
+        );
+        // What was there before the restore was kept too
+        assert_eq!(backup::list(&backups_dir)?.len(), 2);
+        assert!(!setup.manager.state_dir.join(backup::STAGING_DIR).exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_auto_backup_rotates() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("work")?;
+        let dir = setup._temp_dir.path().join("backups");
+        for hour in 0..3 {
+            let at = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z")?.with_timezone(&Utc);
+            let at = at + chrono::Duration::hours(hour);
+            setup.manager.clock = Box::new(FixedClock(at));
+            setup.manager.auto_backup(Some(&dir), 2)?;
         }
+        assert_eq!(backup::list(&dir)?.len(), 2);
+        Ok(())
+    }
 
-        fn create_mock_claude_config(&self) -> Result<()> {
-            fs::create_dir_all(&self.claude_config_dir)?;
-            fs::write(
-                self.claude_config_dir.join("config.json"),
-                r#"{"api_key": "test_key"}"#,
-            )?;
+    #[cfg(unix)]
+    #[test]
+    fn test_sync_between_machines() -> Result<()> {
+        let remote = TempDir::new()?;
+        let status = std::process::Command::new("git")
+            .args(["init", "--quiet", "--bare"])
+            .arg(remote.path())
+            .status()?;
+        assert!(status.success());
+        let remote = remote.path().to_string_lossy().to_string();
+
+        let mut first = TestSetup::new()?;
+        first.manager.assume_yes = true;
+        first.create_mock_claude_config()?;
+        first.manager.save_account("work")?;
+        // Unencrypted credentials never leave the machine
+        assert!(first.manager.sync_init(&remote).is_err());
+        first.manager.prompter = Box::new(ScriptedPrompter::new(["pass", "pass"]));
+        first.manager.enable_encryption()?;
+        fs::write(first.claude_config_dir.join("config.json"), "personal")?;
+        first.manager.save_account("personal")?;
+        first.manager.sync_init(&remote)?;
+        first.manager.sync_push()?;
+
+        // Set up with a passphrase of its own, so the synced store's is asked for
+        let mut second = TestSetup::new()?;
+        second.manager.assume_yes = true;
+        second.manager.prompter = Box::new(ScriptedPrompter::new(["other", "other", "pass"]));
+        second.manager.enable_encryption()?;
+        second.manager.sync_init(&remote)?;
+        second.manager.sync_pull()?;
+        let config = second.manager.load_config()?;
+        assert_eq!(config.accounts.len(), 2);
+        let saved = config.accounts["personal"].path.join("config.json");
+        assert!(crypto::is_sealed(&fs::read(&saved)?));
+        assert_eq!(
+            second.manager.read_stored_bytes(&saved)?.as_deref(),
+            Some(&b"personal"[..])
+        );
+
+        second.manager.delete_account("work", false)?;
+        second.manager.prompter = Box::new(ScriptedPrompter::new(["pass"]));
+        second.manager.sync_push()?;
+        // Locked here, the deletion only comes across with --force
+        first.manager.set_locked("work", true)?;
+        first.manager.sync_pull()?;
+        assert!(first.manager.load_config()?.accounts.contains_key("work"));
+        first.manager.force = true;
+        first.manager.sync_pull()?;
+        let config = first.manager.load_config()?;
+        assert!(config.accounts.contains_key("personal"));
+        assert!(!config.accounts.contains_key("work"));
+        assert!(trash::latest(&first.manager.switcher_dir, "work")?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_keychain_credentials_roundtrip() {
+        let mut setup = TestSetup::new().unwrap();
+        setup.manager.settings.credentials = CredentialStorage::Keychain;
+        setup.create_mock_claude_config().unwrap();
+        let live = setup.claude_config_dir.join(".credentials.json");
+
+        fs::write(&live, "work-token").unwrap();
+        setup.manager.save_account("work").unwrap();
+        fs::write(&live, "personal-token").unwrap();
+        setup.manager.save_account("personal").unwrap();
+
+        // Secrets go to the keychain, not the snapshot
+        let work_dir = setup.manager.switcher_dir.join("work");
+        assert!(work_dir.join("config.json").exists());
+        assert!(!work_dir.join(".credentials.json").exists());
+        assert_eq!(
+            setup.manager.credstore.load("work").unwrap().as_deref(),
+            Some("work-token")
+        );
+
+        setup.manager.switch_account("work", None).unwrap();
+        assert_eq!(fs::read_to_string(&live).unwrap(), "work-token");
+
+        setup.manager.rename_account("personal", "home").unwrap();
+        assert_eq!(setup.manager.credstore.load("personal").unwrap(), None);
+
+        setup.manager.delete_account("home", false).unwrap();
+        assert_eq!(setup.manager.credstore.load("home").unwrap(), None);
+    }
+
+    #[test]
+    fn test_save_records_clock_time() {
+        let mut setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+
+        let instant = DateTime::parse_from_rfc3339("2024-05-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        setup.manager.clock = Box::new(FixedClock(instant));
+        setup.manager.save_account("work").unwrap();
+
+        let config = setup.manager.load_config().unwrap();
+        assert_eq!(
+            config.get_account("work").unwrap().saved_at,
+            "2024-05-01T12:00:00+00:00"
+        );
+    }
+
+    #[test]
+    fn test_rename_account_not_found() {
+        let setup = TestSetup::new().unwrap();
+        let result = setup.manager.rename_account("old", "new");
+
+        assert!(result.is_err());
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("not found"));
+    }
+
+    #[test]
+    fn test_rename_account_already_exists() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+
+        setup.manager.save_account("account1").unwrap();
+        setup.manager.save_account("account2").unwrap();
+
+        let result = setup.manager.rename_account("account1", "account2");
+        assert!(result.is_err());
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("already exists"));
+    }
+
+    #[test]
+    fn test_rename_account_success() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+
+        setup.manager.save_account("old_name").unwrap();
+
+        let old_dir = setup.manager.switcher_dir.join("old_name");
+        let new_dir = setup.manager.switcher_dir.join("new_name");
+
+        assert!(old_dir.exists());
+        assert!(!new_dir.exists());
+
+        let result = setup.manager.rename_account("old_name", "new_name");
+        assert!(result.is_ok());
+
+        assert!(!old_dir.exists());
+        assert!(new_dir.exists());
+
+        let config = setup.manager.load_config().unwrap();
+        assert!(config.get_account("old_name").is_none());
+        assert!(config.get_account("new_name").is_some());
+        assert_eq!(config.current, Some("new_name".to_string()));
+    }
+
+    #[test]
+    fn test_rename_and_delete_leave_the_keychain_alone_with_files() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("old_name")?;
+        setup.manager.save_account("other")?;
+        // What another store keeps there under the same names
+        setup.manager.credstore.store("old_name", "elsewhere")?;
+        setup.manager.credstore.store("new_name", "elsewhere too")?;
+
+        setup.manager.rename_account("old_name", "new_name")?;
+        setup.manager.delete_account("new_name", false)?;
+        assert_eq!(
+            setup
+                .manager
+                .load_config()?
+                .accounts
+                .keys()
+                .collect::<Vec<_>>(),
+            ["other"]
+        );
+        let credstore = &setup.manager.credstore;
+        assert_eq!(credstore.load("old_name")?.as_deref(), Some("elsewhere"));
+        assert_eq!(
+            credstore.load("new_name")?.as_deref(),
+            Some("elsewhere too")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_show_current_no_account() {
+        let setup = TestSetup::new().unwrap();
+        let result = setup.manager.show_current(false);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_show_current_with_account() {
+        let setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+
+        setup.manager.save_account("test_account").unwrap();
+
+        let config = setup.manager.load_config().unwrap();
+        assert_eq!(config.current, Some("test_account".to_string()));
+
+        let result = setup.manager.show_current(false);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_current_details_report_drift_and_expiry() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        let at = DateTime::parse_from_rfc3339("2024-01-01T12:00:00Z")?.with_timezone(&Utc);
+        setup.manager.clock = Box::new(FixedClock(at));
+        setup.create_mock_claude_config()?;
+        fs::write(
+            setup.claude_config_dir.join(CREDENTIALS_FILE),
+            format!(
+                r#"{{"claudeAiOauth": {{"expiresAt": {}}}}}"#,
+                at.timestamp_millis() + 3_600_000
+            ),
+        )?;
+        setup.manager.save_account("work")?;
+        setup.manager.show_current(true)?;
+
+        let config = setup.manager.load_config()?;
+        let meta = &config.accounts["work"];
+        let details = setup.manager.current_details(&config, "work", meta)?;
+        assert!(!details.drifted);
+        assert_eq!(details.expires_in, Some(3600));
+
+        fs::write(
+            setup.claude_config_dir.join("config.json"),
+            r#"{"api_key": "changed"}"#,
+        )?;
+        let details = setup.manager.current_details(&config, "work", meta)?;
+        assert!(details.drifted);
+        assert_eq!(details.changes, vec![PathBuf::from("config.json")]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_expiring_login_is_reported_once() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        let at = DateTime::parse_from_rfc3339("2024-01-01T12:00:00Z")?.with_timezone(&Utc);
+        setup.manager.clock = Box::new(FixedClock(at));
+        setup.create_mock_claude_config()?;
+        let expires_in = |hours: i64| -> Result<()> {
             fs::write(
-                self.claude_config_dir.join("session.json"),
-                r#"{"session": "test_session"}"#,
+                setup.claude_config_dir.join(CREDENTIALS_FILE),
+                format!(
+                    r#"{{"claudeAiOauth": {{"expiresAt": {}}}}}"#,
+                    at.timestamp_millis() + hours * 3_600_000
+                ),
             )?;
             Ok(())
-        }
+        };
+        expires_in(3)?;
+        setup.manager.save_account("work")?;
+
+        let mut reported = None;
+        setup.manager.settings.notify.expiring_within = Some("2h".to_string());
+        setup.manager.check_expiry(&mut reported)?;
+        assert_eq!(reported, None);
+
+        setup.manager.settings.notify.expiring_within = None;
+        setup.manager.check_expiry(&mut reported)?;
+        let expires_at = at + chrono::Duration::hours(3);
+        assert_eq!(reported, Some(("work".to_string(), expires_at)));
+
+        // Signed in again, the new login is reported when its turn comes
+        expires_in(5)?;
+        setup.manager.check_expiry(&mut reported)?;
+        assert_eq!(
+            reported.map(|(_, at)| at),
+            Some(expires_at + chrono::Duration::hours(2))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_exists_and_is_current() {
+        let setup = TestSetup::new().unwrap();
+        assert!(!setup.manager.account_exists("work").unwrap());
+        assert!(!setup.manager.is_current("work").unwrap());
+
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("work").unwrap();
+        setup.manager.save_account("personal").unwrap();
+        assert!(setup.manager.account_exists("work").unwrap());
+        assert!(setup.manager.is_current("personal").unwrap());
+        assert!(!setup.manager.is_current("work").unwrap());
     }
 
     #[test]
-    fn test_save_account_no_configuration() {
+    fn test_show_current_if_any_empty() {
         let setup = TestSetup::new().unwrap();
-        let result = setup.manager.save_account("test");
-
-        assert!(result.is_err());
-        let err = result.unwrap_err().to_string();
-        assert!(err.contains("No Claude Code configuration found"));
+        let result = setup.manager.show_current_if_any();
+        assert!(result.is_ok());
     }
 
     #[test]
-    fn test_save_account_success() {
+    fn test_copy_dir_recursive() {
         let setup = TestSetup::new().unwrap();
         setup.create_mock_claude_config().unwrap();
 
-        let result = setup.manager.save_account("test_account");
-        assert!(result.is_ok());
+        let nested_dir = setup.claude_config_dir.join("nested");
+        fs::create_dir_all(&nested_dir).unwrap();
+        fs::write(nested_dir.join("file.txt"), "content").unwrap();
 
-        let config = setup.manager.load_config().unwrap();
-        assert!(config.get_account("test_account").is_some());
-        assert_eq!(config.current, Some("test_account".to_string()));
+        let dest = setup.manager.switcher_dir.join("copied");
+        fs::create_dir_all(&dest).unwrap();
 
-        let account_dir = setup.manager.switcher_dir.join("test_account");
-        assert!(account_dir.exists());
-        assert!(account_dir.join("config.json").exists());
-        assert!(account_dir.join("session.json").exists());
+        let live = size::dir_usage(&setup.claude_config_dir).unwrap();
+        let mut progress = Progress::new("Copying".to_string(), 3, true).with_bytes(live.bytes);
+        let result = setup.manager.copy_dir_recursive(
+            &setup.claude_config_dir,
+            &dest,
+            Path::new(""),
+            &CapturePolicy::default(),
+            Codec::Plain,
+            Some(&mut progress),
+        );
+        let usage = result.unwrap();
+        assert_eq!(usage.files, 3);
+        assert_eq!(usage.bytes, size::dir_usage(&dest).unwrap().bytes);
+        assert!(progress.line().starts_with("Copying 3/3"));
+
+        assert!(dest.join("config.json").exists());
+        assert!(dest.join("session.json").exists());
+        assert!(dest.join("nested").exists());
+        assert!(dest.join("nested/file.txt").exists());
+
+        let content = fs::read_to_string(dest.join("nested/file.txt")).unwrap();
+        assert_eq!(content, "content");
     }
 
     #[test]
-    fn test_save_multiple_accounts() {
-        let setup = TestSetup::new().unwrap();
+    fn test_encryption_enable_switch_disable() {
+        let mut setup = TestSetup::new().unwrap();
         setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("work").unwrap();
+        let saved = setup.manager.switcher_dir.join("work/config.json");
 
-        setup.manager.save_account("account1").unwrap();
-        setup.manager.save_account("account2").unwrap();
-        setup.manager.save_account("account3").unwrap();
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["pass", "pass"]));
+        setup.manager.enable_encryption().unwrap();
+        assert!(crypto::is_sealed(&fs::read(&saved).unwrap()));
 
-        let config = setup.manager.load_config().unwrap();
-        assert_eq!(config.accounts.len(), 3);
-        assert!(config.get_account("account1").is_some());
-        assert!(config.get_account("account2").is_some());
-        assert!(config.get_account("account3").is_some());
+        // New saves are sealed too, and a fresh unlock is needed to restore them
+        fs::write(setup.claude_config_dir.join("config.json"), "personal").unwrap();
+        setup.manager.save_account("personal").unwrap();
+        assert!(crypto::is_sealed(
+            &fs::read(setup.manager.switcher_dir.join("personal/config.json")).unwrap()
+        ));
+
+        setup.manager.vault = OnceLock::new();
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["pass"]));
+        setup.manager.switch_account("work", None).unwrap();
+        let live = fs::read_to_string(setup.claude_config_dir.join("config.json")).unwrap();
+        assert!(live.contains("test_key"));
+
+        // An archive is sealed as a whole, and keeps the store as it was
+        setup.manager.archive_account("personal").unwrap();
+        let archive = setup.manager.load_config().unwrap().accounts["personal"]
+            .archive
+            .clone()
+            .unwrap();
+        assert!(crypto::is_sealed(&fs::read(&archive).unwrap()));
+        assert!(setup.manager.disable_encryption().is_err());
+        setup.manager.unarchive_account("personal").unwrap();
+
+        setup.manager.disable_encryption().unwrap();
+        assert!(fs::read_to_string(&saved).unwrap().contains("test_key"));
+        assert!(!setup.manager.key_file().exists());
     }
 
     #[test]
-    fn test_switch_account_not_found() {
-        let setup = TestSetup::new().unwrap();
-        let result = setup.manager.switch_account("nonexistent");
+    fn test_encryption_wrong_passphrase() {
+        let mut setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("work").unwrap();
 
-        assert!(result.is_err());
-        let err = result.unwrap_err().to_string();
-        assert!(err.contains("not found"));
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["pass", "pass"]));
+        setup.manager.enable_encryption().unwrap();
+
+        setup.manager.vault = OnceLock::new();
+        // Once for the auto-save of the active account, once for the restore
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["wrong", "wrong"]));
+        let err = setup.manager.switch_account("work", None).unwrap_err();
+        assert!(err.to_string().contains("Wrong store passphrase"));
     }
 
     #[test]
-    fn test_switch_account_success() {
-        let setup = TestSetup::new().unwrap();
-        setup.create_mock_claude_config().unwrap();
+    fn test_encryption_passphrase_mismatch() {
+        let mut setup = TestSetup::new().unwrap();
+        setup.manager.prompter = Box::new(ScriptedPrompter::new(["one", "two"]));
 
-        setup.manager.save_account("account1").unwrap();
+        let err = setup.manager.enable_encryption().unwrap_err();
+        assert!(err.to_string().contains("don't match"));
+        assert!(!setup.manager.key_file().exists());
+    }
 
+    #[test]
+    fn test_save_respects_capture_policy() {
+        let mut setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        fs::create_dir_all(setup.claude_config_dir.join("projects/foo")).unwrap();
         fs::write(
-            setup.claude_config_dir.join("config.json"),
-            r#"{"api_key": "modified_key"}"#,
+            setup.claude_config_dir.join("projects/foo/log.jsonl"),
+            "log",
         )
         .unwrap();
 
-        setup.manager.save_account("account2").unwrap();
+        setup.manager.capture = CapturePolicy::new(&["*.json"], &["projects/"]).unwrap();
+        setup.manager.save_account("work").unwrap();
 
-        setup.manager.switch_account("account1").unwrap();
+        let saved = setup.manager.switcher_dir.join("work");
+        assert!(saved.join("config.json").exists());
+        assert!(!saved.join("projects").exists());
+    }
 
-        let config = setup.manager.load_config().unwrap();
-        assert_eq!(config.current, Some("account1".to_string()));
+    #[test]
+    fn test_switch_keeps_uncaptured_files() {
+        let mut setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.capture = CapturePolicy::new(&[], &["projects/"]).unwrap();
+        setup.manager.save_account("work").unwrap();
 
-        let content = fs::read_to_string(setup.claude_config_dir.join("config.json")).unwrap();
-        assert!(content.contains("test_key"));
+        fs::write(setup.claude_config_dir.join("config.json"), "personal").unwrap();
+        fs::create_dir_all(setup.claude_config_dir.join("projects")).unwrap();
+        fs::write(setup.claude_config_dir.join("projects/log.jsonl"), "log").unwrap();
+        setup.manager.save_account("personal").unwrap();
+
+        setup.manager.switch_account("work", None).unwrap();
+
+        let live = &setup.claude_config_dir;
+        assert!(
+            fs::read_to_string(live.join("config.json"))
+                .unwrap()
+                .contains("test_key")
+        );
+        assert_eq!(
+            fs::read_to_string(live.join("projects/log.jsonl")).unwrap(),
+            "log"
+        );
     }
 
     #[test]
-    fn test_switch_account_directory_not_found() {
-        let setup = TestSetup::new().unwrap();
-        setup.create_mock_claude_config().unwrap();
-        setup.manager.save_account("test_account").unwrap();
+    fn test_switch_swaps_paths_outside_the_claude_directory() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        let home = setup._temp_dir.path().to_path_buf();
+        setup.manager.extra_paths = extra_paths::resolve(&home, &setup.claude_config_dir, None)?;
+        setup.create_mock_claude_config()?;
+        let claude_json = home.join(".claude.json");
+        let login = |email: &str| format!(r#"{{"oauthAccount": {{"emailAddress": "{}"}}}}"#, email);
+        fs::write(&claude_json, login("me@work.example"))?;
+        setup.manager.save_account("work")?;
 
-        // Remove both the account directory and the claude config directory
-        // to prevent auto-save from recreating the account
-        let account_dir = setup.manager.switcher_dir.join("test_account");
-        fs::remove_dir_all(&account_dir).unwrap();
-        fs::remove_dir_all(&setup.claude_config_dir).unwrap();
+        let work_dir = setup.manager.switcher_dir.join("work");
+        assert!(work_dir.join(".cas-paths/home/.claude.json").is_file());
+        assert_eq!(
+            setup
+                .manager
+                .account_login("work", &work_dir)
+                .email
+                .as_deref(),
+            Some("me@work.example")
+        );
 
-        let result = setup.manager.switch_account("test_account");
-        assert!(result.is_err());
-        let err = result.unwrap_err().to_string();
-        assert!(err.contains("Account directory not found"));
+        // Not signed in here, so switching back removes it
+        fs::remove_file(&claude_json)?;
+        setup.manager.save_account("personal")?;
+        setup.manager.switch_account("work", None)?;
+        assert_eq!(fs::read_to_string(&claude_json)?, login("me@work.example"));
+        // Nor copied into the Claude directory
+        assert!(!setup.claude_config_dir.join(".cas-paths").exists());
+
+        fs::write(&claude_json, login("me@work.example.org"))?;
+        setup.manager.switch_account("personal", None)?;
+        assert!(!claude_json.exists());
+        setup.manager.switch_account("work", None)?;
+        assert_eq!(
+            fs::read_to_string(&claude_json)?,
+            login("me@work.example.org")
+        );
+        Ok(())
     }
 
     #[test]
-    fn test_list_accounts_empty() {
-        let setup = TestSetup::new().unwrap();
-        let result = setup.manager.list_accounts();
-        assert!(result.is_ok());
+    fn test_switch_keeps_the_paths_outside_it_replaced() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        let home = setup._temp_dir.path().to_path_buf();
+        setup.manager.extra_paths = extra_paths::resolve(&home, &setup.claude_config_dir, None)?;
+        setup.create_mock_claude_config()?;
+        let claude_json = home.join(".claude.json");
+        fs::write(&claude_json, "work")?;
+        setup.manager.save_account("work")?;
+        fs::remove_file(&claude_json)?;
+        setup.manager.save_account("personal")?;
+        setup.manager.switch_account("work", None)?;
+
+        // Not saved anywhere else
+        setup.manager.unsaved = Unsaved::Discard;
+        fs::write(&claude_json, "edited")?;
+        setup.manager.switch_account("personal", None)?;
+        assert!(!claude_json.exists());
+        let backups = live_backups::list(&setup.manager.live_backups_dir())?;
+        assert_eq!(
+            fs::read_to_string(backups[0].path.join(".cas-paths/home/.claude.json"))?,
+            "edited"
+        );
+        assert!(!setup.manager.live_sibling(PREVIOUS_PATHS_SUFFIX).exists());
+
+        setup.manager.recover_live(None)?;
+        assert_eq!(fs::read_to_string(&claude_json)?, "edited");
+        assert!(!setup.claude_config_dir.join(".cas-paths").exists());
+        Ok(())
     }
 
     #[test]
-    fn test_list_accounts_with_data() {
+    fn test_credentials_scope_swaps_only_the_login() -> Result<()> {
+        let mut setup = TestSetup::new()?;
+        setup.manager.settings.capture_scope = CaptureScope::Credentials;
+        setup.manager.capture = setup.manager.settings.capture_policy(&[], &[])?;
+        setup.create_mock_claude_config()?;
+        let live = setup.claude_config_dir.clone();
+        fs::write(live.join(".credentials.json"), "work-token")?;
+        setup.manager.save_account("work")?;
+
+        let work_dir = setup.manager.switcher_dir.join("work");
+        assert!(work_dir.join(".credentials.json").exists());
+        assert!(!work_dir.join("config.json").exists());
+
+        fs::write(live.join(".credentials.json"), "personal-token")?;
+        fs::create_dir_all(live.join("projects"))?;
+        fs::write(live.join("projects/log.jsonl"), "log")?;
+        setup.manager.save_account("personal")?;
+        fs::write(live.join("config.json"), "edited")?;
+
+        // Settings edited since the save aren't lost on switching
+        setup.manager.switch_account("work", None)?;
+        assert_eq!(
+            fs::read_to_string(live.join(".credentials.json"))?,
+            "work-token"
+        );
+        assert_eq!(fs::read_to_string(live.join("config.json"))?, "edited");
+        assert_eq!(fs::read_to_string(live.join("projects/log.jsonl"))?, "log");
+
+        setup.manager.switch_account("personal", None)?;
+        assert_eq!(
+            fs::read_to_string(live.join(".credentials.json"))?,
+            "personal-token"
+        );
+        assert_eq!(fs::read_to_string(live.join("config.json"))?, "edited");
+        Ok(())
+    }
+
+    #[test]
+    fn test_capture_overrides_replace_settings() {
+        let mut setup = TestSetup::new().unwrap();
+        setup.manager.settings.capture.exclude = vec!["cache/".to_string()];
+        setup.manager.settings.capture.include = vec!["*.json".to_string()];
+
+        let manager = setup
+            .manager
+            .with_capture_overrides(&[], &["projects/".to_string()])
+            .unwrap();
+        assert!(manager.capture.captures(Path::new("cache/settings.json")));
+        assert!(!manager.capture.captures(Path::new("projects/a.json")));
+        assert!(!manager.capture.captures(Path::new("CLAUDE.md")));
+    }
+
+    #[test]
+    fn test_save_case_variants() {
         let setup = TestSetup::new().unwrap();
         setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("Work").unwrap();
 
-        setup.manager.save_account("account1").unwrap();
-        setup.manager.save_account("account2").unwrap();
+        let insensitive = platform::is_case_insensitive(&setup.manager.switcher_dir).unwrap();
+        let result = setup.manager.save_account("work");
 
-        let result = setup.manager.list_accounts();
-        assert!(result.is_ok());
+        if insensitive {
+            assert!(
+                result
+                    .unwrap_err()
+                    .to_string()
+                    .contains("differs only by case")
+            );
+        } else {
+            assert!(result.is_ok());
+            assert_eq!(setup.manager.load_config().unwrap().accounts.len(), 2);
+        }
     }
 
     #[test]
-    fn test_delete_account_not_found() {
+    fn test_rename_case_only() {
         let setup = TestSetup::new().unwrap();
-        let result = setup.manager.delete_account("nonexistent");
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("work").unwrap();
 
-        assert!(result.is_err());
-        let err = result.unwrap_err().to_string();
-        assert!(err.contains("not found"));
+        setup.manager.rename_account("work", "Work").unwrap();
+
+        let config = setup.manager.load_config().unwrap();
+        assert!(config.get_account("Work").is_some());
+        assert!(config.get_account("work").is_none());
     }
 
     #[test]
-    fn test_delete_account_success() {
+    fn test_grep_accounts() {
         let setup = TestSetup::new().unwrap();
         setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("work").unwrap();
 
-        setup.manager.save_account("test_account").unwrap();
+        assert!(setup.manager.grep_accounts("api_key", None, false).is_ok());
+        assert!(
+            setup
+                .manager
+                .grep_accounts("API_KEY", Some("work"), true)
+                .is_ok()
+        );
 
-        let account_dir = setup.manager.switcher_dir.join("test_account");
-        assert!(account_dir.exists());
+        let result = setup.manager.grep_accounts("x", Some("nonexistent"), false);
+        assert!(result.unwrap_err().to_string().contains("not found"));
+    }
 
-        // Note: This test doesn't actually call delete_account because it requires user input
-        // Instead, we manually simulate the deletion
-        let mut config = setup.manager.load_config().unwrap();
-        config.remove_account("test_account");
-        config.current = None;
-        setup.manager.save_config(&config).unwrap();
+    #[test]
+    fn test_find_accounts_by_email_and_contents() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        let claude_json = setup.claude_config_dir.join(".claude.json");
+        for (name, email) in [("work", "Dev@Acme.com"), ("personal", "me@example.com")] {
+            let login = format!(r#"{{"oauthAccount": {{"emailAddress": "{}"}}}}"#, email);
+            fs::write(&claude_json, login)?;
+            setup.manager.save_account(name)?;
+        }
+        fs::write(
+            setup.claude_config_dir.join("CLAUDE.md"),
+            "Acme style guide",
+        )?;
+        setup.manager.save_account("acme-docs")?;
 
-        fs::remove_dir_all(&account_dir).unwrap();
+        let names = |email, text| -> Result<Vec<String>> {
+            let found = setup.manager.found_accounts(email, text)?;
+            Ok(found.into_iter().map(|account| account.name).collect())
+        };
+        assert_eq!(names(Some("dev@acme.com"), None)?, ["work"]);
+        assert_eq!(
+            names(Some("@example.com"), None)?,
+            ["acme-docs", "personal"]
+        );
+        assert_eq!(names(None, Some("acme style"))?, ["acme-docs"]);
+        assert_eq!(names(Some("@example.com"), Some("style"))?, ["acme-docs"]);
+        assert!(names(Some("nobody@"), None)?.is_empty());
+        Ok(())
+    }
 
-        let config = setup.manager.load_config().unwrap();
-        assert!(config.get_account("test_account").is_none());
-        assert!(!account_dir.exists());
+    #[test]
+    fn test_login_checks_ask_the_api_per_token() -> Result<()> {
+        struct Api;
+        impl health::Pinger for Api {
+            fn ping(&self, token: &str) -> Result<u16> {
+                Ok(if token == "good-token" { 200 } else { 401 })
+            }
+        }
+
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        let credentials = setup.claude_config_dir.join(CREDENTIALS_FILE);
+        let later = (setup.manager.clock.now() + chrono::Duration::hours(1)).timestamp_millis();
+        for (name, token) in [("work", "good-token"), ("old", "revoked-token")] {
+            let login = format!(
+                r#"{{"claudeAiOauth": {{"accessToken": "{}", "expiresAt": {}}}}}"#,
+                token, later
+            );
+            fs::write(&credentials, login)?;
+            setup.manager.save_account(name)?;
+        }
+
+        let health = |pinger: Option<&dyn health::Pinger>| -> Result<Vec<_>> {
+            let checks = setup.manager.login_checks(&[], true, pinger)?;
+            Ok(checks
+                .into_iter()
+                .map(|check| (check.name, check.health))
+                .collect())
+        };
+        assert_eq!(
+            health(None)?,
+            [
+                ("old".to_string(), health::Health::Unexpired),
+                ("work".to_string(), health::Health::Unexpired),
+            ]
+        );
+        assert_eq!(
+            health(Some(&Api))?,
+            [
+                ("old".to_string(), health::Health::Revoked),
+                ("work".to_string(), health::Health::Valid),
+            ]
+        );
+        // The live login, not the snapshot, for the current account
+        fs::write(&credentials, "{}")?;
+        let checks = setup.manager.login_checks(&[], false, Some(&Api))?;
+        assert_eq!(checks[0].health, health::Health::NoLogin);
+        Ok(())
     }
 
     #[test]
-    fn test_rename_account_not_found() {
-        let setup = TestSetup::new().unwrap();
-        let result = setup.manager.rename_account("old", "new");
+    fn test_suggest_picks_the_best_account_and_switches() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        let credentials = setup.claude_config_dir.join(CREDENTIALS_FILE);
+        let later = (setup.manager.clock.now() + chrono::Duration::hours(1)).timestamp_millis();
+        for (name, plan) in [("big", "max"), ("small", "pro"), ("current", "pro")] {
+            let login = format!(
+                r#"{{"claudeAiOauth": {{"accessToken": "token-{}", "subscriptionType": "{}", "expiresAt": {}}}}}"#,
+                name, plan, later
+            );
+            fs::write(&credentials, login)?;
+            setup.manager.save_account(name)?;
+        }
+        let ranked = |manager: &AccountManager| -> Result<Vec<String>> {
+            let ranked = manager.ranked_accounts(None, None)?;
+            Ok(ranked.into_iter().map(|candidate| candidate.name).collect())
+        };
+        // Never used before the one in use
+        assert_eq!(ranked(&setup.manager)?, ["big", "small", "current"]);
 
-        assert!(result.is_err());
-        let err = result.unwrap_err().to_string();
-        assert!(err.contains("not found"));
+        setup.manager.limit_hit(Some("big"), Some("2h"), false)?;
+        assert_eq!(ranked(&setup.manager)?, ["small", "current", "big"]);
+        setup.manager.suggest(None, false, true)?;
+        assert_eq!(setup.manager.current_account()?.as_deref(), Some("small"));
+        Ok(())
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_rename_account_already_exists() {
-        let setup = TestSetup::new().unwrap();
-        setup.create_mock_claude_config().unwrap();
+    fn test_menu_switches_to_the_account_picked() -> Result<()> {
+        let setup = TestSetup::new()?;
+        setup.create_mock_claude_config()?;
+        setup.manager.save_account("personal")?;
+        setup.manager.save_account("work")?;
 
-        setup.manager.save_account("account1").unwrap();
-        setup.manager.save_account("account2").unwrap();
+        let shown = setup._temp_dir.path().join("shown");
+        let command = format!("tee {} | grep ^personal", shown.display());
+        setup.manager.menu(None, Some(&command))?;
+        assert_eq!(
+            setup.manager.current_account()?.as_deref(),
+            Some("personal")
+        );
+        let shown = fs::read_to_string(&shown)?;
+        let names: Vec<_> = shown
+            .lines()
+            .map(|line| line.split_whitespace().next())
+            .collect();
+        assert_eq!(names, [Some("personal"), Some("work")]);
+        assert!(shown.lines().nth(1).unwrap().ends_with("(current)"));
 
-        let result = setup.manager.rename_account("account1", "account2");
-        assert!(result.is_err());
-        let err = result.unwrap_err().to_string();
-        assert!(err.contains("already exists"));
+        // Closed without a choice
+        setup.manager.menu(None, Some("cat >/dev/null; exit 1"))?;
+        assert_eq!(
+            setup.manager.current_account()?.as_deref(),
+            Some("personal")
+        );
+        Ok(())
     }
 
     #[test]
-    fn test_rename_account_success() {
+    fn test_checksum_roundtrip() {
         let setup = TestSetup::new().unwrap();
         setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("work").unwrap();
 
-        setup.manager.save_account("old_name").unwrap();
-
-        let old_dir = setup.manager.switcher_dir.join("old_name");
-        let new_dir = setup.manager.switcher_dir.join("new_name");
-
-        assert!(old_dir.exists());
-        assert!(!new_dir.exists());
+        let account_dir = setup.manager.switcher_dir.join("work");
+        let list = setup._temp_dir.path().join("work.sha256");
+        let lines: Vec<_> = store::walk_dir(&account_dir)
+            .map(|entry| {
+                let entry = entry.unwrap();
+                format!(
+                    "{}  {}",
+                    entry.hash().unwrap(),
+                    entry.relative_path.display()
+                )
+            })
+            .collect();
+        fs::write(&list, lines.join("\n")).unwrap();
 
-        let result = setup.manager.rename_account("old_name", "new_name");
-        assert!(result.is_ok());
+        assert!(setup.manager.checksum_account("work", None, 2).is_ok());
+        assert!(
+            setup
+                .manager
+                .checksum_account("work", Some(&list), 2)
+                .is_ok()
+        );
 
-        assert!(!old_dir.exists());
-        assert!(new_dir.exists());
+        fs::write(account_dir.join("config.json"), "tampered").unwrap();
+        let result = setup.manager.checksum_account("work", Some(&list), 2);
+        assert!(
+            result
+                .unwrap_err()
+                .to_string()
+                .contains("1 file(s) in 'work' failed verification")
+        );
 
-        let config = setup.manager.load_config().unwrap();
-        assert!(config.get_account("old_name").is_none());
-        assert!(config.get_account("new_name").is_some());
-        assert_eq!(config.current, Some("new_name".to_string()));
+        // Nothing to check, lines that aren't checksums and paths out of
+        // the snapshot all fail
+        let hash = hashing::hash_bytes(b"");
+        for contents in [
+            String::new(),
+            "not a checksum".to_string(),
+            format!("{}  ../outside", hash),
+            format!("{}  /etc/passwd", hash),
+        ] {
+            fs::write(&list, contents).unwrap();
+            assert!(
+                setup
+                    .manager
+                    .checksum_account("work", Some(&list), 2)
+                    .is_err()
+            );
+        }
     }
 
     #[test]
-    fn test_show_current_no_account() {
+    fn test_share_and_import_account() {
         let setup = TestSetup::new().unwrap();
-        let result = setup.manager.show_current();
-        assert!(result.is_ok());
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("service").unwrap();
+
+        let bundle = setup._temp_dir.path().join("service.casbundle");
+        setup
+            .manager
+            .share_account("service", "24h", Some(&bundle))
+            .unwrap();
+
+        let data = fs::read(&bundle).unwrap();
+        assert!(crypto::is_encrypted(&data));
+
+        // Importing under the same name collides with the original
+        let result = setup
+            .manager
+            .import_account(&bundle, None, Some("irrelevant"), false, None);
+        assert!(result.is_err());
+
+        let result =
+            setup
+                .manager
+                .import_account(&bundle, Some("copy"), Some("wrong"), false, None);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("wrong passphrase"));
     }
 
     #[test]
-    fn test_show_current_with_account() {
+    fn test_import_dry_run_writes_nothing() {
         let setup = TestSetup::new().unwrap();
         setup.create_mock_claude_config().unwrap();
 
-        setup.manager.save_account("test_account").unwrap();
+        let manifest = ArchiveManifest::new("teammate", "2024-01-01T00:00:00Z", Utc::now(), None);
+        let data = archive::pack(&manifest, &setup.claude_config_dir).unwrap();
+        let bundle = setup._temp_dir.path().join("teammate.tar");
+        fs::write(&bundle, data).unwrap();
 
-        let config = setup.manager.load_config().unwrap();
-        assert_eq!(config.current, Some("test_account".to_string()));
+        setup
+            .manager
+            .import_account(&bundle, None, None, true, None)
+            .unwrap();
+        setup.manager.inspect_bundle(&bundle, None).unwrap();
 
-        let result = setup.manager.show_current();
-        assert!(result.is_ok());
+        let config = setup.manager.load_config().unwrap();
+        assert!(config.is_empty());
+        assert!(!setup.manager.switcher_dir.join("teammate").exists());
     }
 
     #[test]
-    fn test_show_current_if_any_empty() {
+    fn test_import_dry_run_reports_conflicts() {
         let setup = TestSetup::new().unwrap();
-        let result = setup.manager.show_current_if_any();
-        assert!(result.is_ok());
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("teammate").unwrap();
+
+        let manifest = ArchiveManifest::new("teammate", "2024-01-01T00:00:00Z", Utc::now(), None);
+        let data = archive::pack(&manifest, &setup.claude_config_dir).unwrap();
+        let bundle = setup._temp_dir.path().join("teammate.tar");
+        fs::write(&bundle, data).unwrap();
+
+        let result = setup
+            .manager
+            .import_account(&bundle, None, None, true, None);
+        assert!(result.unwrap_err().to_string().contains("already exists"));
     }
 
     #[test]
-    fn test_copy_dir_recursive() {
+    fn test_import_plain_archive() {
         let setup = TestSetup::new().unwrap();
         setup.create_mock_claude_config().unwrap();
 
-        let nested_dir = setup.claude_config_dir.join("nested");
-        fs::create_dir_all(&nested_dir).unwrap();
-        fs::write(nested_dir.join("file.txt"), "content").unwrap();
-
-        let dest = setup.manager.switcher_dir.join("copied");
-        fs::create_dir_all(&dest).unwrap();
+        let manifest = ArchiveManifest::new("teammate", "2024-01-01T00:00:00Z", Utc::now(), None);
+        let data = archive::pack(&manifest, &setup.claude_config_dir).unwrap();
+        let bundle = setup._temp_dir.path().join("teammate.tar");
+        fs::write(&bundle, data).unwrap();
 
-        let result = setup
+        setup
             .manager
-            .copy_dir_recursive(&setup.claude_config_dir, &dest);
-        assert!(result.is_ok());
+            .import_account(&bundle, None, None, false, None)
+            .unwrap();
 
-        assert!(dest.join("config.json").exists());
-        assert!(dest.join("session.json").exists());
-        assert!(dest.join("nested").exists());
-        assert!(dest.join("nested/file.txt").exists());
+        let config = setup.manager.load_config().unwrap();
+        let meta = config.get_account("teammate").unwrap();
+        assert_eq!(meta.saved_at, "2024-01-01T00:00:00Z");
+        assert!(meta.path.join("config.json").exists());
+        assert!(config.current.is_none());
+    }
 
-        let content = fs::read_to_string(dest.join("nested/file.txt")).unwrap();
-        assert_eq!(content, "content");
+    #[test]
+    fn test_import_expired_bundle() {
+        let mut setup = TestSetup::new().unwrap();
+        setup.create_mock_claude_config().unwrap();
+        setup.manager.save_account("service").unwrap();
+
+        // The generated passphrase is only printed, so encrypt with a known one
+        let bundle = setup._temp_dir.path().join("service.casbundle");
+        let created = Utc::now();
+        let manifest = ArchiveManifest::new(
+            "service",
+            "2024-01-01T00:00:00Z",
+            created,
+            Some(created + chrono::Duration::hours(1)),
+        );
+        let data = archive::pack(&manifest, &setup.claude_config_dir).unwrap();
+        fs::write(&bundle, crypto::encrypt(&data, "secret").unwrap()).unwrap();
+
+        setup.manager.clock = Box::new(FixedClock(created + chrono::Duration::hours(2)));
+
+        let result =
+            setup
+                .manager
+                .import_account(&bundle, Some("copy"), Some("secret"), false, None);
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("expired"));
+        assert!(!setup.manager.switcher_dir.join("copy").exists());
+    }
+
+    #[test]
+    fn test_share_account_not_found() {
+        let setup = TestSetup::new().unwrap();
+        let result = setup.manager.share_account("nonexistent", "24h", None);
+
+        assert!(result.is_err());
+        assert!(result.unwrap_err().to_string().contains("not found"));
     }
 
     #[test]
@@ -565,16 +15644,17 @@ mod tests {
         let setup = TestSetup::new().unwrap();
 
         let mut config = AccountsConfig::default();
-        config.current = Some("test".to_string());
         config.add_account(
             "test".to_string(),
             AccountMetadata {
                 saved_at: Utc::now().to_rfc3339(),
                 path: PathBuf::from("/test"),
+                ..Default::default()
             },
         );
+        config.current = Some("test".to_string());
 
-        let save_result = setup.manager.save_config(&config);
+        let save_result = setup.manager.save_config(&mut config);
         assert!(save_result.is_ok());
 
         let loaded = setup.manager.load_config().unwrap();
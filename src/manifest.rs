@@ -0,0 +1,220 @@
+//! What the live configuration held when each account was last saved or
+//! switched to: the content hash of every captured file. A switch compares
+//! against it to tell whether leaving the account would lose anything.
+//!
+//! Manifests live in `manifests/<account>.json` in the state directory.
+//! Credential files are left out: Claude Code refreshes the token on its
+//! own, and that alone shouldn't count as a change.
+//!
+//! Each entry also keeps the live file's size and modification time, so a
+//! file that still has both isn't read again: that's what lets a save copy
+//! only what changed. A file hashed within moments of being written is read
+//! again all the same, as it could have been written over since without
+//! either changing.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+pub const DIR: &str = "manifests";
+
+// Some filesystems keep modification times only to the second, FAT to two
+const RACY_WINDOW: Duration = Duration::from_secs(2);
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Manifest {
+    /// By path relative to the configuration directory
+    pub files: BTreeMap<PathBuf, FileHash>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct FileHash {
+    pub sha256: String,
+    pub size: u64,
+    pub modified: SystemTime,
+    /// When the file was read to hash it; absent in manifests written
+    /// before this was kept
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hashed: Option<SystemTime>,
+}
+
+impl FileHash {
+    /// Whether a file with this metadata can be taken to be unchanged
+    /// without hashing it again.
+    pub fn matches(&self, metadata: &fs::Metadata) -> bool {
+        self.same_metadata(metadata) && !self.is_racy()
+    }
+
+    /// Whether the file still has the size and modification time it had
+    /// when it was hashed.
+    pub fn same_metadata(&self, metadata: &fs::Metadata) -> bool {
+        metadata.len() == self.size && metadata.modified().ok() == Some(self.modified)
+    }
+
+    /// Whether the file was hashed so soon after it was written that a
+    /// write since could have left its modification time as it was.
+    pub fn is_racy(&self) -> bool {
+        self.hashed.is_none_or(|hashed| {
+            !hashed
+                .duration_since(self.modified)
+                .is_ok_and(|gap| gap >= RACY_WINDOW)
+        })
+    }
+}
+
+impl Manifest {
+    /// The manifest saved at `path`, if one was. One that can't be parsed
+    /// (written by another version, say) counts as missing.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(path).context("Failed to read manifest")?;
+        Ok(serde_json::from_str(&contents).ok())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create manifests directory")?;
+        }
+        let contents = serde_json::to_string(self).context("Failed to serialize manifest")?;
+        fs::write(path, contents).context("Failed to write manifest")
+    }
+
+    /// The paths added, removed or changed in `other`.
+    pub fn changes(&self, other: &Manifest) -> BTreeSet<PathBuf> {
+        let paths: BTreeSet<&PathBuf> = self.files.keys().chain(other.files.keys()).collect();
+        paths
+            .into_iter()
+            .filter(|path| {
+                let hash =
+                    |manifest: &Manifest| manifest.files.get(*path).map(|file| file.sha256.clone());
+                hash(self) != hash(other)
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+/// Where the manifest of `name` is kept, escaped the way versions are.
+pub fn path(state_dir: &Path, name: &str) -> PathBuf {
+    state_dir.join(DIR).join(format!(
+        "{}.json",
+        name.replace('%', "%25").replace('/', "%2F")
+    ))
+}
+
+/// Drops the manifest of a deleted account. A missing one is fine.
+pub fn remove(state_dir: &Path, name: &str) -> Result<()> {
+    let path = path(state_dir, name);
+    if path.exists() {
+        fs::remove_file(&path).context("Failed to remove manifest")?;
+    }
+    Ok(())
+}
+
+/// Moves the manifest along with a renamed account.
+pub fn rename(state_dir: &Path, old_name: &str, new_name: &str) -> Result<()> {
+    let from = path(state_dir, old_name);
+    if !from.exists() {
+        return Ok(());
+    }
+    fs::rename(&from, path(state_dir, new_name)).context("Failed to rename manifest")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn manifest(files: &[(&str, &str)]) -> Manifest {
+        Manifest {
+            files: files
+                .iter()
+                .map(|(path, hash)| {
+                    let file = FileHash {
+                        sha256: hash.to_string(),
+                        size: 0,
+                        modified: SystemTime::now(),
+                        hashed: None,
+                    };
+                    (PathBuf::from(path), file)
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_changes() {
+        let saved = manifest(&[("a.json", "1"), ("b.json", "2"), ("c.json", "3")]);
+        let live = manifest(&[("a.json", "1"), ("b.json", "9"), ("d.json", "4")]);
+
+        let changes: Vec<_> = saved.changes(&live).into_iter().collect();
+        assert_eq!(
+            changes,
+            ["b.json", "c.json", "d.json"].map(PathBuf::from).to_vec()
+        );
+        assert!(saved.changes(&saved.clone()).is_empty());
+        // Only the contents count, not when they were written
+        assert!(
+            saved
+                .changes(&manifest(&[
+                    ("a.json", "1"),
+                    ("b.json", "2"),
+                    ("c.json", "3")
+                ]))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_recently_written_files_are_hashed_again() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("settings.json");
+        fs::write(&path, "{}")?;
+        let metadata = fs::metadata(&path)?;
+        let modified = metadata.modified()?;
+        let file = |hashed| FileHash {
+            sha256: String::new(),
+            size: metadata.len(),
+            modified,
+            hashed,
+        };
+
+        assert!(file(Some(modified + RACY_WINDOW)).matches(&metadata));
+        assert!(!file(Some(modified + Duration::from_millis(500))).matches(&metadata));
+        assert!(!file(None).matches(&metadata));
+        assert!(file(None).same_metadata(&metadata));
+        Ok(())
+    }
+
+    #[test]
+    fn test_unreadable_manifest_counts_as_missing() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = path(dir.path(), "work");
+        fs::create_dir_all(path.parent().unwrap())?;
+        fs::write(&path, "{\"files\": {\"a.json\": \"abc\"}}")?;
+        assert_eq!(Manifest::load(&path)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_load_rename_remove() -> Result<()> {
+        let dir = TempDir::new()?;
+        let saved = manifest(&[("settings.json", "abc")]);
+        assert_eq!(Manifest::load(&path(dir.path(), "client/dev"))?, None);
+
+        saved.save(&path(dir.path(), "client/dev"))?;
+        rename(dir.path(), "client/dev", "client")?;
+        assert_eq!(Manifest::load(&path(dir.path(), "client"))?, Some(saved));
+        assert!(!path(dir.path(), "client/dev").exists());
+
+        remove(dir.path(), "client")?;
+        remove(dir.path(), "client")?;
+        assert_eq!(Manifest::load(&path(dir.path(), "client"))?, None);
+        Ok(())
+    }
+}
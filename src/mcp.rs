@@ -0,0 +1,218 @@
+//! `mcp-serve`: a Model Context Protocol server on stdin and stdout, so
+//! Claude (or another agent) can look at the accounts and switch between
+//! them through tools.
+//!
+//! Only what MCP needs for tools is spoken: `initialize`, `ping`,
+//! `tools/list` and `tools/call`. Notifications are read and ignored.
+
+use crate::completions::BIN_NAME;
+use crate::jsonrpc;
+use anyhow::{Context, Result};
+use serde_json::{Value, json};
+use std::io::{BufRead, Write};
+
+/// Protocol versions understood, newest first
+pub const PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// A tool as `tools/list` describes it.
+struct Tool {
+    name: &'static str,
+    description: &'static str,
+    /// The one required string argument, if it takes one
+    argument: Option<(&'static str, &'static str)>,
+}
+
+const TOOLS: &[Tool] = &[
+    Tool {
+        name: "list_accounts",
+        description: "List the saved Claude Code accounts, which one is active, and when each was last saved and used.",
+        argument: None,
+    },
+    Tool {
+        name: "current_account",
+        description: "Name the active Claude Code account, or null when none is.",
+        argument: None,
+    },
+    Tool {
+        name: "switch_account",
+        description: "Make another saved account the active one, saving the current one first. Claude Code sessions already running keep the account they started with.",
+        argument: Some((
+            "name",
+            "The account to switch to: a name, an alias or `-` for the previous one",
+        )),
+    },
+];
+
+fn tool_list() -> Value {
+    let tools: Vec<Value> = TOOLS
+        .iter()
+        .map(|tool| {
+            let schema = match tool.argument {
+                Some((name, description)) => json!({
+                    "type": "object",
+                    "properties": {name: {"type": "string", "description": description}},
+                    "required": [name],
+                }),
+                None => json!({"type": "object", "properties": {}}),
+            };
+            json!({
+                "name": tool.name,
+                "description": tool.description,
+                "inputSchema": schema,
+            })
+        })
+        .collect();
+    json!({ "tools": tools })
+}
+
+/// Reads requests from `input` until it ends, answering each on `output`.
+/// `call` runs a tool by name with its arguments, returning its text, or
+/// `None` for a tool it doesn't know.
+pub fn serve(
+    input: impl BufRead,
+    mut output: impl Write,
+    mut call: impl FnMut(&str, &Value) -> Option<Result<String>>,
+) -> Result<()> {
+    for line in input.lines() {
+        let line = line.context("Failed to read a request")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match jsonrpc::read(&line) {
+            Ok(message) => {
+                // A notification, which gets no answer
+                let Some(id) = message.id else { continue };
+                let outcome = handle(&message.method, message.params.as_ref(), &mut call);
+                jsonrpc::response(Some(id), outcome)
+            }
+            Err(error) => jsonrpc::response(None, Err(error)),
+        };
+        writeln!(output, "{}", response).context("Failed to write a response")?;
+        output.flush().context("Failed to write a response")?;
+    }
+    Ok(())
+}
+
+fn handle(
+    method: &str,
+    params: Option<&Value>,
+    call: &mut impl FnMut(&str, &Value) -> Option<Result<String>>,
+) -> std::result::Result<Value, jsonrpc::Error> {
+    let param = |key: &str| params.and_then(|params| params.get(key));
+    match method {
+        "initialize" => {
+            // The client's version if it's one of ours, else the newest
+            let version = param("protocolVersion")
+                .and_then(Value::as_str)
+                .filter(|version| PROTOCOL_VERSIONS.contains(version))
+                .unwrap_or(PROTOCOL_VERSIONS[0]);
+            Ok(json!({
+                "protocolVersion": version,
+                "capabilities": {"tools": {}},
+                "serverInfo": {"name": BIN_NAME, "version": env!("CARGO_PKG_VERSION")},
+            }))
+        }
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(tool_list()),
+        "tools/call" => {
+            let name = param("name").and_then(Value::as_str).ok_or_else(|| {
+                jsonrpc::Error::new(jsonrpc::INVALID_PARAMS, "tools/call needs a tool name")
+            })?;
+            let arguments = param("arguments").cloned().unwrap_or_else(|| json!({}));
+            // A tool that fails is still an answer, for the model to read
+            let (text, failed) = match call(name, &arguments) {
+                Some(Ok(text)) => (text, false),
+                Some(Err(e)) => (format!("{:#}", e), true),
+                None => {
+                    let message = format!("Unknown tool: {}", name);
+                    return Err(jsonrpc::Error::new(jsonrpc::INVALID_PARAMS, message));
+                }
+            };
+            Ok(json!({
+                "content": [{"type": "text", "text": text}],
+                "isError": failed,
+            }))
+        }
+        _ => Err(jsonrpc::Error::unknown_method(method)),
+    }
+}
+
+/// The string argument `key` of a tool call.
+pub fn argument<'a>(arguments: &'a Value, key: &str) -> Result<&'a str> {
+    arguments
+        .get(key)
+        .and_then(Value::as_str)
+        .with_context(|| format!("Missing the `{}` argument", key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(requests: &[Value]) -> Vec<Value> {
+        let input: String = requests.iter().map(|r| format!("{}\n", r)).collect();
+        let mut output = Vec::new();
+        serve(
+            input.as_bytes(),
+            &mut output,
+            |name, arguments| match name {
+                "current_account" => Some(Ok("work".to_string())),
+                "switch_account" => Some(argument(arguments, "name").map(|name| name.to_string())),
+                _ => None,
+            },
+        )
+        .unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_tools_listed_and_called() {
+        let responses = session(&[
+            json!({"jsonrpc": "2.0", "id": 1, "method": "initialize",
+                   "params": {"protocolVersion": "2025-03-26", "capabilities": {}}}),
+            json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+            json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"}),
+            json!({"jsonrpc": "2.0", "id": 3, "method": "tools/call",
+                   "params": {"name": "current_account"}}),
+            json!({"jsonrpc": "2.0", "id": 4, "method": "tools/call",
+                   "params": {"name": "switch_account", "arguments": {}}}),
+            json!({"jsonrpc": "2.0", "id": 5, "method": "tools/call",
+                   "params": {"name": "delete_everything"}}),
+        ]);
+        // Nothing for the notification
+        assert_eq!(responses.len(), 5);
+        assert_eq!(responses[0]["result"]["protocolVersion"], "2025-03-26");
+
+        let names: Vec<_> = responses[1]["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|tool| tool["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            ["list_accounts", "current_account", "switch_account"]
+        );
+
+        assert_eq!(
+            responses[2]["result"],
+            json!({"content": [{"type": "text", "text": "work"}], "isError": false})
+        );
+        assert_eq!(responses[3]["result"]["isError"], true);
+        assert_eq!(responses[4]["error"]["code"], jsonrpc::INVALID_PARAMS);
+    }
+
+    #[test]
+    fn test_unknown_version_gets_the_newest() {
+        let responses = session(&[json!({"jsonrpc": "2.0", "id": 1, "method": "initialize",
+                                         "params": {"protocolVersion": "1999-01-01"}})]);
+        assert_eq!(
+            responses[0]["result"]["protocolVersion"],
+            PROTOCOL_VERSIONS[0]
+        );
+    }
+}
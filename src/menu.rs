@@ -0,0 +1,213 @@
+//! `menu`: picking the account to switch to from a launcher such as rofi or
+//! dmenu, which can be bound to a global hotkey and so works without a
+//! terminal to type `switch` in.
+//!
+//! The accounts go to the launcher's stdin one per line, and the line it
+//! prints back is the one chosen; exiting with an error (as all of them do
+//! on Escape) means nothing was. `--command` takes any other program that
+//! works the same way, run by the shell.
+
+use crate::tr;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Launcher {
+    Rofi,
+    Dmenu,
+    Wofi,
+    Fuzzel,
+    /// choose, on macOS
+    Choose,
+    /// fzf, in a terminal
+    Fzf,
+}
+
+impl Launcher {
+    /// The ones tried when none is named, in order: for the desktop in use
+    /// first, then the rest.
+    fn preferred() -> Vec<Launcher> {
+        if cfg!(target_os = "macos") {
+            return vec![Launcher::Choose, Launcher::Fzf];
+        }
+        let wayland =
+            std::env::var_os("WAYLAND_DISPLAY").is_some_and(|display| !display.is_empty());
+        match wayland {
+            true => vec![
+                Launcher::Fuzzel,
+                Launcher::Wofi,
+                Launcher::Rofi,
+                Launcher::Fzf,
+            ],
+            false => vec![Launcher::Rofi, Launcher::Dmenu, Launcher::Fzf],
+        }
+    }
+
+    fn program(self) -> &'static str {
+        match self {
+            Launcher::Rofi => "rofi",
+            Launcher::Dmenu => "dmenu",
+            Launcher::Wofi => "wofi",
+            Launcher::Fuzzel => "fuzzel",
+            Launcher::Choose => "choose",
+            Launcher::Fzf => "fzf",
+        }
+    }
+
+    fn command(self, prompt: &str) -> Command {
+        let mut command = Command::new(self.program());
+        match self {
+            Launcher::Rofi => command.args(["-dmenu", "-i", "-p", prompt]),
+            Launcher::Dmenu => command.args(["-i", "-p", prompt]),
+            Launcher::Wofi => command.args(["--dmenu", "--insensitive", "--prompt", prompt]),
+            Launcher::Fuzzel => command.args(["--dmenu", "--prompt", &format!("{} ", prompt)]),
+            Launcher::Choose => &mut command,
+            Launcher::Fzf => command.args(["--prompt", &format!("{} ", prompt)]),
+        };
+        command
+    }
+}
+
+/// What shows the menu: a launcher, or a command of the user's.
+pub enum Menu {
+    Launcher(Launcher),
+    Shell(String),
+}
+
+impl Menu {
+    /// `command` if given, else `via`, else the first launcher installed.
+    pub fn resolve(via: Option<Launcher>, command: Option<&str>) -> Result<Self> {
+        if let Some(command) = command.filter(|command| !command.trim().is_empty()) {
+            return Ok(Menu::Shell(command.to_string()));
+        }
+        if let Some(launcher) = via {
+            return Ok(Menu::Launcher(launcher));
+        }
+        let preferred = Launcher::preferred();
+        preferred
+            .iter()
+            .copied()
+            .find(|launcher| on_path(launcher.program()))
+            .map(Menu::Launcher)
+            .ok_or_else(|| {
+                let names: Vec<_> = preferred
+                    .iter()
+                    .map(|launcher| launcher.program())
+                    .collect();
+                anyhow::anyhow!(tr!("menu-no-launcher", launchers = names.join(", ")))
+            })
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Menu::Launcher(launcher) => launcher.program(),
+            Menu::Shell(command) => command,
+        }
+    }
+
+    /// Shows `lines` and returns which one was chosen, or `None` when the
+    /// menu was closed without a choice.
+    pub fn pick(&self, prompt: &str, lines: &[String]) -> Result<Option<usize>> {
+        let mut command = match self {
+            Menu::Launcher(launcher) => launcher.command(prompt),
+            Menu::Shell(shell) if cfg!(windows) => {
+                let mut command = Command::new("cmd");
+                command.arg("/C").arg(shell);
+                command
+            }
+            Menu::Shell(shell) => {
+                let mut command = Command::new("sh");
+                command.arg("-c").arg(shell);
+                command
+            }
+        };
+        tracing::info!(menu = self.name(), "showing the menu");
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .with_context(|| tr!("menu-start-failed", menu = self.name()))?;
+
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        // One that quits before reading everything closes the pipe early,
+        // which is no reason to fail
+        let _ = stdin.write_all(lines.join("\n").as_bytes());
+        let _ = stdin.write_all(b"\n");
+        drop(stdin);
+
+        let output = child
+            .wait_with_output()
+            .with_context(|| tr!("menu-start-failed", menu = self.name()))?;
+        if !output.status.success() {
+            return Ok(None);
+        }
+        let chosen = String::from_utf8_lossy(&output.stdout);
+        Ok(chosen_line(
+            lines,
+            chosen.lines().next().unwrap_or_default(),
+        ))
+    }
+}
+
+// The line `chosen` is, or failing that the one starting with the same
+// word, for a menu that prints back only part of what it was given
+fn chosen_line(lines: &[String], chosen: &str) -> Option<usize> {
+    let chosen = chosen.trim();
+    if chosen.is_empty() {
+        return None;
+    }
+    lines
+        .iter()
+        .position(|line| line.trim() == chosen)
+        .or_else(|| {
+            let word = chosen.split_whitespace().next()?;
+            lines
+                .iter()
+                .position(|line| line.split_whitespace().next() == Some(word))
+        })
+}
+
+fn on_path(program: &str) -> bool {
+    let Some(path) = std::env::var_os("PATH") else {
+        return false;
+    };
+    std::env::split_paths(&path).any(|dir| is_program(&dir.join(program)))
+}
+
+fn is_program(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        path.metadata()
+            .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+    }
+    #[cfg(not(unix))]
+    {
+        path.with_extension("exe").is_file()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_reads_back_the_chosen_line() -> Result<()> {
+        let lines = vec!["personal".to_string(), "work  you@work.com  *".to_string()];
+        let menu = |command: &str| Menu::resolve(Some(Launcher::Rofi), Some(command)).unwrap();
+
+        assert_eq!(menu("tail -n 1").pick(">", &lines)?, Some(1));
+        // Only the name printed back
+        assert_eq!(
+            menu("cat >/dev/null; echo work").pick(">", &lines)?,
+            Some(1)
+        );
+        // Closed with Escape
+        assert_eq!(menu("cat >/dev/null; exit 1").pick(">", &lines)?, None);
+        assert_eq!(menu("cat >/dev/null; echo other").pick(">", &lines)?, None);
+        Ok(())
+    }
+}
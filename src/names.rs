@@ -0,0 +1,170 @@
+//! What an account may be called. A name becomes a directory in the store
+//! (and a keychain entry), so it keeps to characters every filesystem takes.
+//! `/` puts an account in a namespace (`work/client-a`): each part is a
+//! directory of its own, so `client` and `client/dev` can both exist.
+//!
+//! Only new names are checked, on save, rename, copy, import and
+//! add-existing; accounts saved before keep working under the name they have.
+
+use crate::archive;
+use crate::capture;
+use crate::compression;
+use crate::crypto;
+use crate::objects;
+use crate::template;
+use crate::tr;
+use crate::trash;
+use crate::versions;
+use anyhow::Result;
+
+/// The longest name, namespaces included
+pub const MAX_LEN: usize = 100;
+
+/// The separator between a namespace and what's in it
+pub const SEPARATOR: char = '/';
+
+/// What `switch` takes for the account active before the current one
+pub const PREVIOUS: &str = "-";
+
+// Device names Windows won't create a file or directory as, with or
+// without an extension
+const DEVICES: &[&str] = &[
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+    "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+// The headers compressed and encrypted files start with; a file holding
+// nothing but a name starting with one would be read as one of them
+const MAGICS: &[&[u8]] = &[compression::MAGIC, crypto::MAGIC, crypto::SEALED_MAGIC];
+
+/// Fails, saying why, unless `name` is fine for a new account.
+pub fn validate(name: &str) -> Result<()> {
+    if name.is_empty() {
+        anyhow::bail!(tr!("name-empty"));
+    }
+    // Versions and the rest share the store directory
+    if [
+        versions::DIR,
+        objects::DIR,
+        template::DIR,
+        trash::DIR,
+        archive::COLD_DIR,
+        PREVIOUS,
+    ]
+    .iter()
+    .any(|reserved| name.eq_ignore_ascii_case(reserved))
+    {
+        anyhow::bail!(tr!("reserved-name", name = name));
+    }
+    if name.split(SEPARATOR).any(|part| {
+        MAGICS.iter().any(|magic| {
+            part.as_bytes()
+                .get(..magic.len())
+                .is_some_and(|start| start.eq_ignore_ascii_case(magic))
+        })
+    }) {
+        anyhow::bail!(tr!("reserved-name", name = name));
+    }
+    if name.chars().count() > MAX_LEN {
+        anyhow::bail!(tr!("name-too-long", name = name, max = MAX_LEN));
+    }
+    if let Some(bad) = name.chars().find(|c| !allowed(*c)) {
+        anyhow::bail!(tr!("name-bad-char", name = name, char = bad.escape_debug()));
+    }
+
+    for part in name.split(SEPARATOR) {
+        // Empty, `.`, `..` and hidden parts, and a trailing dot Windows drops
+        if part.is_empty() || part.starts_with('.') || part.ends_with('.') {
+            anyhow::bail!(tr!("name-bad-part", name = name));
+        }
+        let stem = part.split('.').next().unwrap_or(part);
+        if DEVICES
+            .iter()
+            .any(|device| stem.eq_ignore_ascii_case(device))
+        {
+            anyhow::bail!(tr!("name-device", name = name, part = part));
+        }
+    }
+    Ok(())
+}
+
+/// Whether `arg` names accounts by a glob (`test-*`, `client/?`) rather than
+/// by name. Names can't contain `*` or `?`, so the two never clash.
+pub fn is_pattern(arg: &str) -> bool {
+    arg.contains(['*', '?'])
+}
+
+/// The names among `names` that `pattern` matches. `*` goes across
+/// namespaces too, so `client*` matches `client/dev`.
+pub fn matching<'a>(pattern: &str, names: impl IntoIterator<Item = &'a String>) -> Vec<String> {
+    names
+        .into_iter()
+        .filter(|name| capture::glob_match(pattern, name))
+        .cloned()
+        .collect()
+}
+
+fn allowed(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '@' | '+' | SEPARATOR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patterns_match_across_namespaces() {
+        let names = [
+            "client".to_string(),
+            "client/dev".to_string(),
+            "test-1".to_string(),
+        ];
+        assert!(is_pattern("test-*") && !is_pattern("test-1"));
+        assert_eq!(matching("client*", &names), ["client", "client/dev"]);
+        assert_eq!(matching("test-?", &names), ["test-1"]);
+        assert!(matching("prod-*", &names).is_empty());
+    }
+
+    #[test]
+    fn test_accepts_namespaced_names() {
+        for name in [
+            "work",
+            "work/client-a",
+            "me@example.com",
+            "team_2/dev+test",
+            "cassandra",
+        ] {
+            assert!(validate(name).is_ok(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_rejects_names_leaving_the_store() {
+        for name in [
+            "",
+            "..",
+            "../evil",
+            "work/../..",
+            "/abs",
+            "work/",
+            "a//b",
+            ".hidden",
+            "dots.",
+            "back\\slash",
+            "spa ce",
+            "café",
+            "new\nline",
+            ".TRASH",
+            "-",
+            "nul",
+            "work/COM1.txt",
+            "CASZSTD1",
+            "casenc01-work",
+            "team/CasSeal1",
+        ] {
+            assert!(validate(name).is_err(), "{:?}", name);
+        }
+        assert!(validate(&"a".repeat(MAX_LEN + 1)).is_err());
+        assert!(validate(&"a".repeat(MAX_LEN)).is_ok());
+    }
+}
@@ -0,0 +1,161 @@
+//! Files that are the same across snapshots, stored once. With `dedup` on,
+//! each saved file is a hard link to an object in `.objects` next to the
+//! snapshots, named by its SHA-256 (`.objects/ab/cdef...`), so accounts that
+//! share settings, MCP configuration or project caches share the space too.
+//!
+//! Linked files share their contents, so a stored file is only ever
+//! replaced, never written to in place. `gc` removes the objects no snapshot
+//! or version links to any more.
+
+use crate::store;
+use anyhow::{Context, Result};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const DIR: &str = ".objects";
+
+/// Where the object with `hash` is kept below `root` (the objects directory).
+pub fn object_path(root: &Path, hash: &str) -> PathBuf {
+    let (prefix, rest) = hash.split_at(2.min(hash.len()));
+    root.join(prefix).join(rest)
+}
+
+/// Whether `path` is already linked to an object (or anything else), so
+/// interning it again would change nothing.
+pub fn is_linked(path: &Path) -> Result<bool> {
+    Ok(link_count(
+        &fs::metadata(path).with_context(|| format!("Failed to read file: {}", path.display()))?,
+    ) > 1)
+}
+
+/// Replaces the file at `path`, whose contents hash to `hash`, with a link
+/// to the object holding them, adding the object when it's new. Returns
+/// whether an existing object was reused.
+pub fn intern(root: &Path, path: &Path, hash: &str) -> Result<bool> {
+    let object = object_path(root, hash);
+    if let Some(parent) = object.parent() {
+        fs::create_dir_all(parent).context("Failed to create objects directory")?;
+    }
+    match fs::hard_link(path, &object) {
+        Ok(()) => return Ok(false),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to store {}", path.display()));
+        }
+    }
+
+    // Staged beside the file and renamed over it, so it's never missing
+    let mut staged = path.as_os_str().to_owned();
+    staged.push(".cas-link");
+    let staged = PathBuf::from(staged);
+    let _ = fs::remove_file(&staged);
+    fs::hard_link(&object, &staged)
+        .and_then(|()| fs::rename(&staged, path))
+        .with_context(|| format!("Failed to link {} to its stored copy", path.display()))?;
+    Ok(true)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Collected {
+    pub objects: u64,
+    pub bytes: u64,
+}
+
+/// Removes the objects below `root` that nothing links to any more, or with
+/// `dry_run` only counts them.
+pub fn gc(root: &Path, dry_run: bool) -> Result<Collected> {
+    let mut collected = Collected::default();
+    if !root.is_dir() {
+        return Ok(collected);
+    }
+
+    for entry in store::walk_dir(root) {
+        let entry = entry?;
+        let metadata = fs::metadata(&entry.path)
+            .with_context(|| format!("Failed to read object: {}", entry.path.display()))?;
+        if link_count(&metadata) > 1 {
+            continue;
+        }
+        if !dry_run {
+            fs::remove_file(&entry.path)
+                .with_context(|| format!("Failed to remove object: {}", entry.path.display()))?;
+        }
+        collected.objects += 1;
+        collected.bytes += entry.size;
+    }
+
+    // Drop the prefix directories left empty; a full one just stays
+    if !dry_run {
+        for entry in fs::read_dir(root).context("Failed to read objects directory")? {
+            let _ = fs::remove_dir(entry?.path());
+        }
+    }
+    Ok(collected)
+}
+
+/// How many names the file has. Where that can't be told, files count as
+/// linked, so nothing is collected and nothing interned twice.
+fn link_count(metadata: &fs::Metadata) -> u64 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        metadata.nlink()
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        u64::MAX
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::hashing;
+    use std::os::unix::fs::MetadataExt;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_identical_files_share_one_object() -> Result<()> {
+        let dir = TempDir::new()?;
+        let root = dir.path().join(DIR);
+        let (a, b) = (dir.path().join("a.json"), dir.path().join("b.json"));
+        fs::write(&a, "{}")?;
+        fs::write(&b, "{}")?;
+        let hash = hashing::hash_file(&a)?;
+
+        assert!(!intern(&root, &a, &hash)?);
+        assert!(intern(&root, &b, &hash)?);
+        assert!(is_linked(&a)? && is_linked(&b)?);
+        assert_eq!(fs::read_to_string(&b)?, "{}");
+        assert_eq!(fs::metadata(object_path(&root, &hash))?.nlink(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_gc_removes_unreferenced_objects() -> Result<()> {
+        let dir = TempDir::new()?;
+        let root = dir.path().join(DIR);
+        let (kept, dropped) = (dir.path().join("kept"), dir.path().join("dropped"));
+        fs::write(&kept, "kept")?;
+        fs::write(&dropped, "dropped")?;
+        let kept_hash = hashing::hash_file(&kept)?;
+        let dropped_hash = hashing::hash_file(&dropped)?;
+        intern(&root, &kept, &kept_hash)?;
+        intern(&root, &dropped, &dropped_hash)?;
+        fs::remove_file(&dropped)?;
+
+        let expected = Collected {
+            objects: 1,
+            bytes: 7,
+        };
+        assert_eq!(gc(&root, true)?, expected);
+        assert!(object_path(&root, &dropped_hash).exists());
+        assert_eq!(gc(&root, false)?, expected);
+        assert!(!object_path(&root, &dropped_hash).exists());
+        assert_eq!(fs::metadata(&kept)?.nlink(), 2);
+        assert_eq!(gc(&root, false)?, Collected::default());
+        Ok(())
+    }
+}
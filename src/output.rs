@@ -0,0 +1,2545 @@
+//! Rendering of human-readable output, and the result types printed with
+//! `--json`.
+//!
+//! Colors mark the current account, warnings (yellow) and errors (red).
+//! They're decided once, by `init_color`: `--color` first, then `NO_COLOR`,
+//! then whether the stream is a terminal. Nothing is colored before that,
+//! which keeps tests and library callers on plain text.
+
+use crate::audit::Secret;
+use crate::bench::Measurement;
+use crate::checkpoints::Checkpoint;
+use crate::config::AccountIdentity;
+use crate::diff::{Change, FileChange};
+use crate::doctor::{Finding, Severity};
+use crate::duration;
+use crate::error;
+use crate::health::{Expiry, Health};
+use crate::history::Event;
+use crate::live_backups::LiveBackup;
+use crate::profile::Profile;
+use crate::sessions::Session;
+use crate::settings::SwitchMode;
+use crate::size::{self, DirUsage};
+use crate::stats::Transfer;
+use crate::table::Table;
+use crate::timings::{Phase, Spent};
+use crate::tr;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use crossterm::style::Stylize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// `--color`: when output gets colors.
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorChoice {
+    /// On a terminal, unless `NO_COLOR` is set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// What a colored piece of output is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Tone {
+    Current,
+    Ok,
+    Warning,
+    Error,
+}
+
+static COLOR_STDOUT: AtomicBool = AtomicBool::new(false);
+static COLOR_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Decides whether stdout and stderr get colors, for the rest of the run.
+pub fn init_color(choice: ColorChoice) {
+    let no_color = std::env::var_os("NO_COLOR");
+    COLOR_STDOUT.store(
+        wants_color(choice, no_color.as_ref(), io::stdout().is_terminal()),
+        Ordering::Relaxed,
+    );
+    COLOR_STDERR.store(
+        wants_color(choice, no_color.as_ref(), io::stderr().is_terminal()),
+        Ordering::Relaxed,
+    );
+}
+
+/// `NO_COLOR` counts when set to anything but the empty string, as
+/// no-color.org says; `--color always` wins over it.
+fn wants_color(choice: ColorChoice, no_color: Option<&OsString>, terminal: bool) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => terminal && no_color.is_none_or(|value| value.is_empty()),
+    }
+}
+
+fn styled(text: &str, tone: Tone) -> String {
+    match tone {
+        Tone::Current => text.green().bold().to_string(),
+        Tone::Ok => text.green().to_string(),
+        Tone::Warning => text.yellow().to_string(),
+        Tone::Error => text.red().bold().to_string(),
+    }
+}
+
+/// `text` in the color of `tone`, for stdout.
+pub fn paint(text: &str, tone: Tone) -> String {
+    match COLOR_STDOUT.load(Ordering::Relaxed) {
+        true => styled(text, tone),
+        false => text.to_string(),
+    }
+}
+
+/// `text` in the color of `tone`, for stderr.
+pub fn paint_stderr(text: &str, tone: Tone) -> String {
+    match COLOR_STDERR.load(Ordering::Relaxed) {
+        true => styled(text, tone),
+        false => text.to_string(),
+    }
+}
+
+/// Prints a warning on stderr.
+pub fn warn(message: impl fmt::Display) {
+    eprintln!("{}", paint_stderr(&message.to_string(), Tone::Warning));
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Aligned columns, markers and tree lines
+    #[default]
+    Table,
+    /// Explicitly labelled lines without alignment or symbols, for screen readers
+    Plain,
+    /// One JSON document on stdout per command; messages go to stderr
+    Json,
+}
+
+pub struct AccountRow<'a> {
+    /// What `switch` takes in place of the name
+    pub number: usize,
+    pub name: &'a str,
+    pub aliases: Vec<&'a str>,
+    pub current: bool,
+    pub saved_at: &'a str,
+    pub identity: Option<&'a AccountIdentity>,
+    pub tags: Vec<&'a str>,
+    /// With `list --long`
+    pub note: Option<&'a str>,
+    /// How long until a rate limit lifts, while it holds
+    pub limited_for: Option<chrono::Duration>,
+    /// Packed away by `archive`
+    pub archived: bool,
+    /// What the snapshot takes up, with `list --long`
+    pub usage: Option<DirUsage>,
+    pub last_used: Option<&'a str>,
+    /// Files changed since the last save, for the active account when the
+    /// `unsaved` column is shown
+    pub unsaved: Option<usize>,
+    /// When the saved login's token expires, in `list`
+    pub expires_at: Option<DateTime<Utc>>,
+    /// How many days ago the snapshot was saved, when that's longer than
+    /// `list` lets it be
+    pub stale_days: Option<i64>,
+    /// The login is about to expire, or already has
+    pub expiry: Option<Expiry>,
+}
+
+impl AccountRow<'_> {
+    /// Whether `list --stale-days` shows the account.
+    pub fn needs_attention(&self) -> bool {
+        self.stale_days.is_some() || self.expiry.is_some()
+    }
+}
+
+/// A column `list --columns` can show.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Column {
+    /// What `switch` takes in place of the name
+    Number,
+    Name,
+    Aliases,
+    Email,
+    Organization,
+    Plan,
+    Tags,
+    Saved,
+    LastUsed,
+    Files,
+    Size,
+    /// Files the active account changed since it was saved
+    #[value(alias = "dirty")]
+    Unsaved,
+    /// When the saved login's token expires
+    Expires,
+    /// The first line of the note
+    Note,
+}
+
+impl Column {
+    /// What `list --long` shows
+    pub const LONG: &[Column] = &[
+        Column::Number,
+        Column::Name,
+        Column::Email,
+        Column::Plan,
+        Column::Tags,
+        Column::LastUsed,
+        Column::Size,
+        Column::Unsaved,
+        Column::Note,
+    ];
+
+    /// What `list --format csv` and `markdown` show unless told otherwise
+    pub const DOCUMENT: &[Column] = &[
+        Column::Name,
+        Column::Email,
+        Column::Organization,
+        Column::Plan,
+        Column::Tags,
+        Column::Saved,
+        Column::LastUsed,
+        Column::Expires,
+        Column::Files,
+        Column::Size,
+    ];
+
+    /// Whether showing it means measuring the snapshots.
+    pub fn needs_usage(self) -> bool {
+        matches!(self, Column::Files | Column::Size)
+    }
+
+    fn label(self) -> String {
+        match self {
+            Column::Number => "#".to_string(),
+            Column::Name => tr!("label-name"),
+            Column::Aliases => tr!("label-aliases"),
+            Column::Email => tr!("label-email"),
+            Column::Organization => tr!("label-organization"),
+            Column::Plan => tr!("label-plan"),
+            Column::Tags => tr!("label-tags"),
+            Column::Saved => tr!("label-saved"),
+            Column::LastUsed => tr!("label-last-used"),
+            Column::Files => tr!("label-files"),
+            Column::Size => tr!("label-size"),
+            Column::Unsaved => tr!("label-unsaved"),
+            Column::Expires => tr!("label-expires"),
+            Column::Note => tr!("label-note"),
+        }
+    }
+
+    /// `row`'s value, or `-` when it has none.
+    fn cell(self, row: &AccountRow) -> String {
+        self.value(row).unwrap_or_else(|| "-".to_string())
+    }
+
+    fn value(self, row: &AccountRow) -> Option<String> {
+        let identity = |field: fn(&AccountIdentity) -> &Option<String>| {
+            row.identity.and_then(|identity| field(identity).clone())
+        };
+        let joined = |items: &[&str]| Some(items.join(", ")).filter(|items| !items.is_empty());
+        let time = |time: &str| time.get(..19).unwrap_or(time).to_string();
+        match self {
+            Column::Number => Some(row.number.to_string()),
+            Column::Name => Some(row.name.to_string()),
+            Column::Aliases => joined(&row.aliases),
+            Column::Email => identity(|identity| &identity.email),
+            Column::Organization => identity(|identity| &identity.organization),
+            Column::Plan => identity(|identity| &identity.plan),
+            Column::Tags => joined(&row.tags),
+            Column::Saved => Some(time(row.saved_at)),
+            Column::LastUsed => row.last_used.map(time),
+            Column::Files => row.usage.map(|usage| usage.files.to_string()),
+            Column::Size if row.archived => Some(tr!("label-archived")),
+            Column::Size => row.usage.map(|usage| size::format_bytes(usage.bytes)),
+            Column::Unsaved => row.unsaved.map(|changes| match changes {
+                0 => tr!("no"),
+                changes => tr!("info-unsaved", count = changes),
+            }),
+            Column::Expires => row
+                .expires_at
+                .map(|expires_at| expires_at.format("%Y-%m-%dT%H:%M:%S").to_string()),
+            Column::Note => row
+                .note
+                .and_then(|note| note.lines().next())
+                .map(str::to_string),
+        }
+    }
+}
+
+/// A document `list --format` writes in place of its listing, to paste into
+/// a spreadsheet or a wiki.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Document {
+    Csv,
+    Markdown,
+}
+
+impl Document {
+    /// `rows` as a table of `columns`, with a header.
+    pub fn render(self, columns: &[Column], rows: &[AccountRow]) -> Vec<String> {
+        let header: Vec<String> = columns.iter().map(|column| column.label()).collect();
+        let values = rows.iter().map(|row| {
+            columns
+                .iter()
+                .map(|column| column.value(row))
+                .collect::<Vec<_>>()
+        });
+        match self {
+            Document::Csv => std::iter::once(header.iter().map(|label| csv_field(label)).collect())
+                .chain(values.map(|values| {
+                    values
+                        .iter()
+                        .map(|value| csv_field(value.as_deref().unwrap_or_default()))
+                        .collect()
+                }))
+                .map(|fields: Vec<String>| fields.join(","))
+                .collect(),
+            Document::Markdown => {
+                let line = |cells: Vec<String>| format!("| {} |", cells.join(" | "));
+                let mut lines = vec![
+                    line(header.iter().map(|label| markdown_cell(label)).collect()),
+                    line(columns.iter().map(|_| "---".to_string()).collect()),
+                ];
+                lines.extend(values.map(|values| {
+                    line(
+                        values
+                            .iter()
+                            .map(|value| markdown_cell(value.as_deref().unwrap_or("-")))
+                            .collect(),
+                    )
+                }));
+                lines
+            }
+        }
+    }
+}
+
+// Quoted when it holds a comma, a quote or a line break, as RFC 4180 has it
+fn csv_field(value: &str) -> String {
+    match value.contains([',', '"', '\n', '\r']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value.to_string(),
+    }
+}
+
+fn markdown_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace(['\n', '\r'], " ")
+}
+
+/// An account as listed by `list --json`.
+#[derive(Serialize, Debug)]
+pub struct AccountJson<'a> {
+    pub number: usize,
+    pub name: &'a str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<&'a str>,
+    pub current: bool,
+    pub saved_at: &'a str,
+    pub last_used: Option<&'a str>,
+    pub switch_count: u64,
+    pub protected: bool,
+    pub locked: bool,
+    pub external: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity: Option<&'a AccountIdentity>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<&'a str>,
+    /// While a rate limit holds, when it lifts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limited_until: Option<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub archived: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub files: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<String>,
+    /// How many days ago the snapshot was saved, when it's stale
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stale_days: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiry: Option<Expiry>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CurrentJson<'a> {
+    pub current: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity: Option<&'a AccountIdentity>,
+    /// Only with `--verbose`
+    #[serde(flatten)]
+    pub details: Option<CurrentDetails<'a>>,
+}
+
+/// What `current --verbose` adds: when the account was saved, whether the
+/// live configuration drifted from it since, and the token's expiry.
+#[derive(Serialize, Debug)]
+pub struct CurrentDetails<'a> {
+    pub saved_at: &'a str,
+    pub last_used: Option<&'a str>,
+    /// The live configuration changed after it was last saved or switched to
+    pub drifted: bool,
+    /// The captured files that changed, as far as the snapshot's manifest
+    /// tells
+    pub changes: Vec<PathBuf>,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Seconds until the token expires; negative once it has
+    pub expires_in: Option<i64>,
+}
+
+/// The snapshot `env` points Claude Code at.
+#[derive(Serialize, Debug)]
+pub struct EnvJson<'a> {
+    pub account: &'a str,
+    pub claude_config_dir: &'a Path,
+}
+
+/// A profile as `profile list` and `profile use` show it.
+#[derive(Serialize, Debug)]
+pub struct ProfileJson<'a> {
+    pub name: &'a str,
+    #[serde(flatten)]
+    pub profile: &'a Profile,
+}
+
+/// A live directory `recover-live` can put back.
+#[derive(Serialize, Debug)]
+pub struct LiveBackupJson<'a> {
+    pub id: &'a str,
+    pub account: Option<&'a str>,
+    pub replaced_at: Option<DateTime<Utc>>,
+    pub path: &'a Path,
+}
+
+/// A copy of the live directory `checkpoint restore` can put back.
+#[derive(Serialize, Debug)]
+pub struct CheckpointJson<'a> {
+    pub label: &'a str,
+    pub account: Option<&'a str>,
+    pub created_at: DateTime<Utc>,
+    pub path: &'a Path,
+}
+
+/// What's in a snapshot directory, as `ls` lists it.
+#[derive(Serialize, Debug)]
+pub struct SnapshotEntryJson {
+    pub name: String,
+    pub kind: EntryKind,
+    /// Of a file, as kept in the store
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    File,
+    Dir,
+    Symlink,
+}
+
+/// A directory mapped to an account with `project set`.
+#[derive(Serialize, Debug)]
+pub struct ProjectJson<'a> {
+    pub path: &'a Path,
+    pub account: &'a str,
+}
+
+/// What `diff` found.
+#[derive(Serialize, Debug)]
+pub struct DiffJson<'a> {
+    pub account: &'a str,
+    /// The account compared with, when it isn't the live configuration
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub other: Option<&'a str>,
+    pub changes: &'a [FileChange],
+}
+
+/// What `switch --preview` found switching would change.
+#[derive(Serialize, Debug)]
+pub struct SwitchPreviewJson<'a> {
+    pub account: &'a str,
+    pub changes: &'a [FileChange],
+    /// Who the live configuration is signed in as
+    pub identity_from: Option<&'a AccountIdentity>,
+    /// Who the account is signed in as
+    pub identity_to: Option<&'a AccountIdentity>,
+}
+
+/// The active account at a glance, as reported by `status`.
+#[derive(Serialize, Debug)]
+pub struct Status {
+    pub account: String,
+    pub plan: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Seconds until the token expires; negative once it has
+    pub expires_in: Option<i64>,
+    /// The live configuration changed after it was last saved or switched to
+    pub dirty: bool,
+    /// Something above needs attention: the token has expired or is about
+    /// to, or there are unsaved changes
+    pub stale: bool,
+}
+
+impl Status {
+    /// One line of `key=value` fields for status bars. The fields and their
+    /// order are stable; a missing value is `-`.
+    pub fn porcelain(&self) -> String {
+        format!(
+            "name={} plan={} expires={} dirty={}",
+            self.account,
+            self.plan.as_deref().unwrap_or("-"),
+            self.expiry(),
+            u8::from(self.dirty)
+        )
+    }
+
+    /// `2h05m`, `expired`, or `-` when the expiry is unknown.
+    fn expiry(&self) -> String {
+        match self.expires_in {
+            Some(seconds) if seconds <= 0 => "expired".to_string(),
+            Some(seconds) => duration::format_countdown(chrono::Duration::seconds(seconds)),
+            None => "-".to_string(),
+        }
+    }
+}
+
+/// What `status` shows without `--porcelain`: the active account's status,
+/// when there is one, and the store around it.
+#[derive(Serialize, Debug)]
+pub struct Overview {
+    #[serde(flatten)]
+    pub status: Option<Status>,
+    pub account_count: usize,
+    pub store_bytes: u64,
+    /// What the instance changing accounts right now says about itself
+    pub locked_by: Option<String>,
+    /// Absent when sync isn't set up
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sync: Option<SyncPending>,
+}
+
+/// What the next `sync push` and `sync pull` would do, as of the last one.
+#[derive(Serialize, Debug)]
+pub struct SyncPending {
+    pub remote: String,
+    pub to_push: usize,
+    pub to_pull: usize,
+}
+
+/// An account's details, as shown by `info`.
+#[derive(Serialize, Debug)]
+pub struct AccountInfo<'a> {
+    pub name: &'a str,
+    pub current: bool,
+    /// Absent when the snapshot holds no login
+    pub identity: Option<AccountIdentity>,
+    pub saved_at: &'a str,
+    pub last_used: Option<&'a str>,
+    pub switch_count: u64,
+    pub path: &'a Path,
+    pub protected: bool,
+    pub locked: bool,
+    pub external: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<&'a str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<&'a str>,
+    pub files: u64,
+    pub size_bytes: u64,
+    /// When the login's access token expires, if the snapshot has one
+    pub token_expires_at: Option<DateTime<Utc>>,
+    /// Files changed in the live configuration since the last save; only
+    /// known for the active account
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unsaved_changes: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limited_until: Option<&'a str>,
+    /// The Claude Code version the snapshot was saved with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub claude_version: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pinned_version: Option<&'a str>,
+}
+
+/// The outcome of `save` or `switch`.
+#[derive(Serialize, Debug)]
+pub struct ActionJson<'a> {
+    pub action: &'a str,
+    pub account: &'a str,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub cancelled: bool,
+    /// Absent when nothing was copied, as when a symlink was swapped instead
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub copied: Option<CopiedJson>,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub struct CopiedJson {
+    pub files: u64,
+    pub bytes: u64,
+    pub duration_ms: u128,
+}
+
+impl From<Transfer> for CopiedJson {
+    fn from(transfer: Transfer) -> Self {
+        Self {
+            files: transfer.usage.files,
+            bytes: transfer.usage.bytes,
+            duration_ms: transfer.elapsed.as_millis(),
+        }
+    }
+}
+
+/// What `--timings` reports on stderr once the command is done.
+#[derive(Serialize, Debug)]
+pub struct TimingsJson {
+    pub phases: Vec<TimingJson>,
+    pub total_ms: f64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TimingJson {
+    pub phase: Phase,
+    pub duration_ms: f64,
+    /// How many times the phase was entered
+    pub count: u64,
+}
+
+impl TimingsJson {
+    pub fn new(phases: &[(Phase, Spent)], total: Duration) -> Self {
+        Self {
+            phases: phases
+                .iter()
+                .map(|(phase, spent)| TimingJson {
+                    phase: *phase,
+                    duration_ms: millis(spent.elapsed),
+                    count: spent.count,
+                })
+                .collect(),
+            total_ms: millis(total),
+        }
+    }
+}
+
+/// One way of putting files in place that `bench` measured.
+#[derive(Serialize, Debug)]
+pub struct BenchJson {
+    pub mode: SwitchMode,
+    pub files: usize,
+    pub bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<f64>,
+    /// Left out for a symlink, which takes the same time however much
+    /// there is
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bytes_per_sec: Option<f64>,
+    /// Why it can't be done on this machine
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl BenchJson {
+    pub fn new(measurement: &Measurement, files: usize, bytes: u64) -> Self {
+        let elapsed = measurement.elapsed.as_ref().ok();
+        Self {
+            mode: measurement.mode,
+            files,
+            bytes,
+            duration_ms: elapsed.map(|elapsed| millis(*elapsed)),
+            bytes_per_sec: elapsed
+                .filter(|_| measurement.mode != SwitchMode::Symlink)
+                .map(|elapsed| bytes as f64 / elapsed.as_secs_f64().max(f64::EPSILON)),
+            error: measurement.elapsed.as_ref().err().cloned(),
+        }
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// The outcome of `gc`.
+#[derive(Serialize, Debug)]
+pub struct GcJson {
+    /// Files in older snapshots found to match a stored object
+    pub shared_files: u64,
+    pub shared_bytes: u64,
+    pub removed_objects: u64,
+    pub removed_bytes: u64,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
+}
+
+/// The directories `gc --orphans` found in the store that no account uses,
+/// and what became of them.
+#[derive(Serialize, Debug)]
+pub struct OrphansJson {
+    pub orphans: Vec<OrphanJson>,
+    /// Moved to the trash
+    pub deleted: Vec<PathBuf>,
+    /// Registered as accounts, by name
+    pub adopted: Vec<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct OrphanJson {
+    pub path: PathBuf,
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// A backup `backup create` or `backup auto` wrote, and for `auto` the
+/// older ones it deleted.
+#[derive(Serialize, Debug)]
+pub struct BackupJson {
+    pub path: PathBuf,
+    pub accounts: usize,
+    pub bytes: u64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub removed: Vec<PathBuf>,
+}
+
+/// The token `token` found, whole only with `--reveal`.
+#[derive(Serialize, Debug)]
+pub struct TokenJson<'a> {
+    pub name: &'a str,
+    /// `oauth` or `api-key`
+    pub kind: &'a str,
+    pub token: String,
+    pub masked: bool,
+    pub copied: bool,
+}
+
+/// The variables `envfile` prints for an account.
+#[derive(Serialize, Debug)]
+pub struct EnvfileJson<'a> {
+    pub account: &'a str,
+    pub variables: BTreeMap<String, String>,
+}
+
+/// The bundle `debug-bundle` wrote and the files in it.
+#[derive(Serialize, Debug)]
+pub struct DebugBundleJson {
+    pub path: PathBuf,
+    pub files: Vec<PathBuf>,
+}
+
+/// What `prune` removed, or would have with `dry_run`.
+#[derive(Serialize, Debug)]
+pub struct PruneJson {
+    pub deleted_accounts: Vec<String>,
+    pub removed_versions: u64,
+    pub version_bytes: u64,
+    pub stripped_files: u64,
+    pub stripped_bytes: u64,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
+}
+
+/// What `repair` re-linked and dropped, or would have with `dry_run`.
+#[derive(Serialize, Debug, Default)]
+pub struct RepairJson {
+    /// The snapshot directory each account now points at
+    pub relinked: BTreeMap<String, PathBuf>,
+    pub dropped: Vec<String>,
+    /// Left alone, as the drive their snapshot is kept on isn't mounted
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unmounted: Vec<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
+}
+
+/// How a command run on several accounts went for each.
+#[derive(Serialize, Debug, Default)]
+pub struct BulkJson {
+    pub succeeded: Vec<String>,
+    pub failed: Vec<BulkFailureJson>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct BulkFailureJson {
+    pub name: String,
+    pub error: String,
+}
+
+/// One account's disk usage, as `size` reports it.
+#[derive(Serialize, Debug)]
+pub struct SizeRow {
+    pub name: String,
+    pub external: bool,
+    pub files: u64,
+    pub bytes: u64,
+    /// Its earlier versions together
+    pub version_files: u64,
+    pub version_bytes: u64,
+}
+
+impl SizeRow {
+    pub fn total_bytes(&self) -> u64 {
+        self.bytes + self.version_bytes
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct SizeJson {
+    pub accounts: Vec<SizeRow>,
+    /// The whole store, with files shared between snapshots counted once;
+    /// absent when sizing one account
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub store: Option<StoreUsageJson>,
+}
+
+/// One account's login, as `check` found it.
+#[derive(Serialize, Debug)]
+pub struct CheckJson {
+    pub name: String,
+    pub health: Health,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// What the API answered with, with `--online`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+    /// Why it couldn't be checked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// An account as `suggest` ranks it.
+#[derive(Serialize, Debug)]
+pub struct SuggestionJson<'a> {
+    pub name: &'a str,
+    pub available: bool,
+    pub plan: Option<&'a str>,
+    pub health: Health,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limited_until: Option<DateTime<Utc>>,
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+/// A job `service status` reports on.
+#[derive(Serialize, Debug)]
+pub struct ServiceJson {
+    pub service: &'static str,
+    pub installed: bool,
+    /// Whether it's running (waiting, for a timer), when that's known
+    pub active: Option<bool>,
+    pub path: PathBuf,
+}
+
+/// An account `find` matched.
+#[derive(Serialize, Debug)]
+pub struct FoundJson {
+    pub name: String,
+    pub email: Option<String>,
+    pub organization: Option<String>,
+    /// Lines holding the `--grep` text, when it was given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matching_lines: Option<usize>,
+}
+
+/// A line `grep` matched.
+#[derive(Serialize, Debug)]
+pub struct GrepJson<'a> {
+    pub account: &'a str,
+    pub path: PathBuf,
+    pub line_number: usize,
+    pub line: String,
+}
+
+/// A file of a snapshot with its SHA-256, as `checksum` lists it.
+#[derive(Serialize, Debug)]
+pub struct ChecksumJson {
+    pub path: PathBuf,
+    pub sha256: String,
+}
+
+/// The outcome of `checksum --check`.
+#[derive(Serialize, Debug, Default)]
+pub struct ChecksumCheckJson {
+    pub ok: usize,
+    /// Files missing or with other contents, each with `MISSING` or `FAILED`
+    pub failed: Vec<ChecksumFailureJson>,
+    /// Lines of the list that couldn't be read, counting from 1
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub malformed: Vec<usize>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ChecksumFailureJson {
+    pub path: PathBuf,
+    pub status: &'static str,
+}
+
+/// A damaged snapshot `verify` found.
+#[derive(Serialize, Debug)]
+pub struct VerifyJson<'a> {
+    pub name: &'a str,
+    pub problems: Vec<String>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub repaired: bool,
+}
+
+/// An account's note, as `note` shows it.
+#[derive(Serialize, Debug)]
+pub struct NoteJson<'a> {
+    pub name: &'a str,
+    pub note: Option<&'a str>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct StoreUsageJson {
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// An earlier snapshot, as listed by `history <name>`.
+#[derive(Serialize, Debug)]
+pub struct VersionRow {
+    pub id: String,
+    pub saved_at: Option<String>,
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// A deleted account, as listed by `trash list`.
+#[derive(Serialize, Debug)]
+pub struct TrashRow {
+    pub name: String,
+    pub deleted_at: String,
+    pub path: PathBuf,
+    pub files: u64,
+    pub bytes: u64,
+}
+
+/// What `migrate` did to the accounts file.
+#[derive(Serialize, Debug)]
+pub struct MigrateJson {
+    pub from_version: u32,
+    pub to_version: u32,
+    /// Accounts registered for directories the file had lost track of
+    pub adopted: Vec<String>,
+    /// Directories left alone, their names being unusable or taken
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<PathBuf>,
+    /// Where the old file was kept; absent when nothing was written
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup: Option<PathBuf>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
+}
+
+/// One state file as reported by `upgrade-config`.
+#[derive(Serialize, Debug)]
+pub struct FileUpgrade {
+    pub path: PathBuf,
+    /// What the file holds, e.g. "3 accounts"
+    pub contents: String,
+    pub before_bytes: u64,
+    pub after_bytes: u64,
+    pub changed: bool,
+    /// Where the old file was kept; absent when nothing was written
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup: Option<PathBuf>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ErrorJson {
+    /// What went wrong, for branching on (see `error::AccountError::kind`)
+    pub kind: &'static str,
+    /// The exit code the process ends with
+    pub code: u8,
+    pub error: String,
+    /// The context chain below `error`, outermost first
+    pub causes: Vec<String>,
+}
+
+impl ErrorJson {
+    pub fn new(error: &anyhow::Error) -> Self {
+        Self {
+            kind: error::kind(error),
+            code: error::exit_code(error),
+            error: error.to_string(),
+            causes: error.chain().skip(1).map(ToString::to_string).collect(),
+        }
+    }
+}
+
+pub fn print_json(value: &impl Serialize) -> Result<()> {
+    let json = serde_json::to_string_pretty(value).context("Failed to serialize output")?;
+    println!("{}", json);
+    Ok(())
+}
+
+/// As `print_json`, on stderr, for failures.
+pub fn eprint_json(value: &impl Serialize) -> Result<()> {
+    let json = serde_json::to_string_pretty(value).context("Failed to serialize output")?;
+    eprintln!("{}", json);
+    Ok(())
+}
+
+impl OutputFormat {
+    pub fn is_json(self) -> bool {
+        self == Self::Json
+    }
+
+    // JSON output is assembled from the types above, so the renderers below
+    // only fall back to the table layout for it
+
+    pub fn header(self) -> Vec<String> {
+        match self {
+            Self::Table | Self::Json => vec![tr!("accounts-header"), "-".repeat(60)],
+            Self::Plain => vec![tr!("accounts-header")],
+        }
+    }
+
+    pub fn account(self, row: &AccountRow) -> String {
+        let saved_at = row.saved_at.get(..19).unwrap_or(row.saved_at);
+        let mut line = match self {
+            Self::Table | Self::Json if row.current => format!(
+                "{} {:>2} {} ({})",
+                paint("*", Tone::Current),
+                row.number,
+                // Padded before it's painted, so the columns still line up
+                paint(&format!("{:<20}", row.name), Tone::Current),
+                tr!("saved-at", time = saved_at)
+            ),
+            Self::Table | Self::Json => format!(
+                "  {:>2} {:<20} ({})",
+                row.number,
+                row.name,
+                tr!("saved-at", time = saved_at)
+            ),
+            Self::Plain => tr!(
+                "plain-account",
+                number = row.number,
+                name = row.name,
+                current = yes_no(row.current),
+                time = saved_at
+            ),
+        };
+        if !row.aliases.is_empty() {
+            let aliases = row.aliases.join(", ");
+            line.push_str(&match self {
+                Self::Table | Self::Json => {
+                    format!("  {}", tr!("account-aliases", aliases = aliases))
+                }
+                Self::Plain => tr!("plain-account-aliases", aliases = aliases),
+            });
+        }
+        if row.archived {
+            line.push_str(&match self {
+                Self::Table | Self::Json => format!("  ({})", tr!("label-archived")),
+                Self::Plain => tr!("plain-account-archived"),
+            });
+        }
+        if let Some(days) = row.stale_days {
+            line.push_str(&match self {
+                Self::Table | Self::Json => format!(
+                    "  {}",
+                    paint(&tr!("account-stale", days = days), Tone::Warning)
+                ),
+                Self::Plain => tr!("plain-account-stale", days = days),
+            });
+        }
+        if let (Some(expiry), Some(expires_at)) = (row.expiry, row.expires_at) {
+            let time = utc_minutes(expires_at);
+            line.push_str(&match (self, expiry) {
+                (Self::Table | Self::Json, Expiry::Soon) => format!(
+                    "  {}",
+                    paint(&tr!("account-expiring", time = time), Tone::Warning)
+                ),
+                (Self::Table | Self::Json, Expiry::Expired) => format!(
+                    "  {}",
+                    paint(&tr!("account-expired", time = time), Tone::Warning)
+                ),
+                (Self::Plain, Expiry::Soon) => tr!("plain-account-expiring", time = time),
+                (Self::Plain, Expiry::Expired) => tr!("plain-account-expired", time = time),
+            });
+        }
+        if let Some(left) = row.limited_for {
+            let left = duration::format_countdown(left);
+            line.push_str(&match self {
+                Self::Table | Self::Json => format!(
+                    "  {}",
+                    paint(&tr!("account-limited", left = left), Tone::Warning)
+                ),
+                Self::Plain => tr!("plain-account-limited", left = left),
+            });
+        }
+        if let Some(usage) = row.usage {
+            let size = size::format_bytes(usage.bytes);
+            line.push_str(&match self {
+                Self::Table | Self::Json => format!(
+                    "  {}",
+                    tr!("account-usage", files = usage.files, size = size)
+                ),
+                Self::Plain => tr!("plain-account-usage", files = usage.files, size = size),
+            });
+        }
+        if let Some(identity) = row.identity {
+            line.push_str(&self.identity(identity));
+        }
+        if !row.tags.is_empty() {
+            let tags = row.tags.join(", ");
+            line.push_str(&match self {
+                Self::Table | Self::Json => format!("  [{}]", tags),
+                Self::Plain => tr!("plain-account-tags", tags = tags),
+            });
+        }
+        // Its first line, as a reminder; `info` has the rest
+        if let Some(note) = row.note.and_then(|note| note.lines().next()) {
+            line.push_str(&match self {
+                Self::Table | Self::Json => format!("  - {}", note),
+                Self::Plain => tr!("plain-account-note", note = note),
+            });
+        }
+        line
+    }
+
+    /// `rows` as a table of `columns`, the current account marked: a header
+    /// and aligned lines, or for plain output a labelled line each.
+    pub fn account_table(self, columns: &[Column], rows: &[AccountRow]) -> Vec<String> {
+        if self == Self::Plain {
+            return rows
+                .iter()
+                .map(|row| {
+                    let mut details: Vec<String> = columns
+                        .iter()
+                        .map(|column| {
+                            let label = match column {
+                                Column::Number => tr!("label-number"),
+                                column => column.label(),
+                            };
+                            format!("{}: {}", label, column.cell(row))
+                        })
+                        .collect();
+                    details.push(format!("{}: {}", tr!("label-current"), yes_no(row.current)));
+                    details.join(", ")
+                })
+                .collect();
+        }
+
+        let mut header = vec![String::new()];
+        header.extend(columns.iter().map(|column| column.label().to_uppercase()));
+        let mut table = Table::new(header);
+        for row in rows {
+            let mut cells = vec![if row.current { "*" } else { " " }.to_string()];
+            cells.extend(columns.iter().map(|column| column.cell(row)));
+            table.push(cells);
+        }
+        let mut lines = table.render();
+        for (line, row) in lines.iter_mut().skip(1).zip(rows) {
+            if row.current {
+                *line = paint(line, Tone::Current);
+            } else if row.needs_attention() {
+                *line = paint(line, Tone::Warning);
+            }
+        }
+        lines
+    }
+
+    /// The ranking `suggest` shows, numbering the accounts that can be used
+    /// now. `now` is for how long limits have left.
+    pub fn suggestions(self, ranked: &[SuggestionJson], now: DateTime<Utc>) -> Vec<String> {
+        let state = |suggestion: &SuggestionJson| match suggestion.limited_until {
+            _ if !suggestion.health.is_ok() => suggestion.health.label(),
+            Some(until) => tr!(
+                "suggest-limited",
+                left = duration::format_countdown(until - now)
+            ),
+            None => tr!("suggest-available"),
+        };
+        let rows = ranked.iter().enumerate().map(|(i, suggestion)| {
+            let rank = match suggestion.available {
+                true => (i + 1).to_string(),
+                false => "-".to_string(),
+            };
+            [
+                rank,
+                suggestion.name.to_string(),
+                suggestion.plan.unwrap_or("-").to_string(),
+                state(suggestion),
+            ]
+        });
+        match self {
+            Self::Table | Self::Json => {
+                let mut table = Table::new(vec![
+                    "#".to_string(),
+                    tr!("label-name").to_uppercase(),
+                    tr!("label-plan").to_uppercase(),
+                    tr!("label-state").to_uppercase(),
+                ]);
+                for row in rows {
+                    table.push(row.to_vec());
+                }
+                table.render()
+            }
+            Self::Plain => rows
+                .map(|[rank, name, plan, state]| {
+                    [
+                        (tr!("label-number"), rank),
+                        (tr!("label-name"), name),
+                        (tr!("label-plan"), plan),
+                        (tr!("label-state"), state),
+                    ]
+                    .iter()
+                    .map(|(label, value)| format!("{}: {}", label, value))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+                })
+                .collect(),
+        }
+    }
+
+    /// The files `audit` found, with what's wrong with each.
+    pub fn secrets(self, secrets: &[Secret]) -> Vec<String> {
+        let rows = secrets.iter().map(|secret| {
+            let flags: Vec<String> = secret.flags.iter().map(|flag| flag.label()).collect();
+            [
+                secret.path.display().to_string(),
+                secret
+                    .mode
+                    .map(|mode| format!("{:04o}", mode))
+                    .unwrap_or_else(|| "-".to_string()),
+                match secret.encrypted {
+                    true => tr!("yes"),
+                    false => tr!("no"),
+                },
+                match flags.is_empty() {
+                    true => "-".to_string(),
+                    false => flags.join(", "),
+                },
+            ]
+        });
+        match self {
+            Self::Table | Self::Json => {
+                let mut table = Table::new(vec![
+                    tr!("label-path").to_uppercase(),
+                    tr!("label-mode").to_uppercase(),
+                    tr!("label-encrypted").to_uppercase(),
+                    tr!("label-flags").to_uppercase(),
+                ]);
+                for row in rows {
+                    table.push(row.to_vec());
+                }
+                table.render()
+            }
+            Self::Plain => rows
+                .map(|[path, mode, encrypted, flags]| {
+                    [
+                        (tr!("label-path"), path),
+                        (tr!("label-mode"), mode),
+                        (tr!("label-encrypted"), encrypted),
+                        (tr!("label-flags"), flags),
+                    ]
+                    .iter()
+                    .map(|(label, value)| format!("{}: {}", label, value))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+                })
+                .collect(),
+        }
+    }
+
+    /// The profiles `profile list` shows.
+    pub fn profiles(self, profiles: &[ProfileJson]) -> Vec<String> {
+        let rows = profiles.iter().map(|entry| {
+            [
+                entry.name.to_string(),
+                entry.profile.account.clone(),
+                match entry.profile.env.is_empty() {
+                    true => "-".to_string(),
+                    false => entry.profile.env_summary(),
+                },
+            ]
+        });
+        match self {
+            Self::Table | Self::Json => {
+                let mut table = Table::new(vec![
+                    tr!("label-name").to_uppercase(),
+                    tr!("label-account").to_uppercase(),
+                    tr!("label-env").to_uppercase(),
+                ]);
+                for row in rows {
+                    table.push(row.to_vec());
+                }
+                table.render()
+            }
+            Self::Plain => rows
+                .map(|[name, account, env]| {
+                    [
+                        (tr!("label-name"), name),
+                        (tr!("label-account"), account),
+                        (tr!("label-env"), env),
+                    ]
+                    .iter()
+                    .map(|(label, value)| format!("{}: {}", label, value))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+                })
+                .collect(),
+        }
+    }
+
+    /// What `--timings` found, a line per phase and the total.
+    pub fn timings(self, timings: &TimingsJson) -> Vec<String> {
+        let duration = |ms: f64| format!("{:.1} ms", ms);
+        let rows: Vec<[String; 3]> = timings
+            .phases
+            .iter()
+            .map(|timing| {
+                [
+                    timing.phase.label(),
+                    duration(timing.duration_ms),
+                    timing.count.to_string(),
+                ]
+            })
+            .chain(std::iter::once([
+                tr!("timing-total"),
+                duration(timings.total_ms),
+                String::new(),
+            ]))
+            .collect();
+        match self {
+            Self::Table | Self::Json => {
+                let mut table = Table::new(vec![
+                    tr!("label-phase").to_uppercase(),
+                    tr!("label-time").to_uppercase(),
+                    tr!("label-calls").to_uppercase(),
+                ]);
+                for row in rows {
+                    table.push(row.to_vec());
+                }
+                table.render()
+            }
+            Self::Plain => rows
+                .into_iter()
+                .map(|[phase, time, calls]| match calls.is_empty() {
+                    true => tr!("plain-timing-total", time = time),
+                    false => tr!("plain-timing", phase = phase, time = time, calls = calls),
+                })
+                .collect(),
+        }
+    }
+
+    /// How fast each way `bench` measured put the files in place.
+    pub fn bench(self, results: &[BenchJson]) -> Vec<String> {
+        let rows = results.iter().map(|result| {
+            let mode = result
+                .mode
+                .to_possible_value()
+                .map_or_else(String::new, |value| value.get_name().to_string());
+            let (time, speed) = match (&result.error, result.duration_ms) {
+                (Some(error), _) => (tr!("bench-failed", error = error), String::new()),
+                (None, Some(ms)) => (
+                    format!("{:.1} ms", ms),
+                    result.bytes_per_sec.map_or_else(
+                        || "-".to_string(),
+                        |speed| tr!("bench-speed", size = size::format_bytes(speed as u64)),
+                    ),
+                ),
+                (None, None) => (String::new(), String::new()),
+            };
+            [mode, time, speed]
+        });
+        match self {
+            Self::Table | Self::Json => {
+                let mut table = Table::new(vec![
+                    tr!("label-mode").to_uppercase(),
+                    tr!("label-time").to_uppercase(),
+                    tr!("label-speed").to_uppercase(),
+                ]);
+                for row in rows {
+                    table.push(row.to_vec());
+                }
+                table.render()
+            }
+            Self::Plain => rows
+                .map(|[mode, time, speed]| {
+                    [
+                        (tr!("label-mode"), mode),
+                        (tr!("label-time"), time),
+                        (tr!("label-speed"), speed),
+                    ]
+                    .iter()
+                    .filter(|(_, value)| !value.is_empty())
+                    .map(|(label, value)| format!("{}: {}", label, value))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+                })
+                .collect(),
+        }
+    }
+
+    /// One account's line in `check`.
+    pub fn check(self, check: &CheckJson) -> String {
+        let mut health = check.health.label();
+        if let Some(status) = check
+            .http_status
+            .filter(|_| check.health == Health::Unknown)
+        {
+            health = format!("{} (HTTP {})", health, status);
+        }
+        if let Some(error) = &check.error {
+            health = format!("{}: {}", health, error);
+        }
+        match self {
+            Self::Table | Self::Json => {
+                let tone = match check.health.is_ok() {
+                    true => Tone::Ok,
+                    false => Tone::Warning,
+                };
+                format!("{:<20} {}", check.name, paint(&health, tone))
+            }
+            Self::Plain => format!("{}: {}, {}", tr!("label-name"), check.name, health),
+        }
+    }
+
+    /// A job as `service status` found it.
+    pub fn service(self, service: &ServiceJson) -> String {
+        let (state, tone) = match (service.installed, service.active) {
+            (false, _) => (tr!("service-state-not-installed"), None),
+            (true, Some(true)) => (tr!("service-state-active"), Some(Tone::Ok)),
+            (true, Some(false)) => (tr!("service-state-inactive"), Some(Tone::Warning)),
+            (true, None) => (tr!("service-state-installed"), None),
+        };
+        let path = service.path.display();
+        match self {
+            Self::Table | Self::Json => {
+                // Padded before it's painted, which would throw the width off
+                let padded = format!("{:<14}", state);
+                let state = match tone {
+                    Some(tone) => paint(&padded, tone),
+                    None => padded,
+                };
+                format!("{:<8} {} {}", service.service, state, path)
+            }
+            Self::Plain => format!(
+                "{}: {}, {}: {}, {}: {}",
+                tr!("label-name"),
+                service.service,
+                tr!("label-state"),
+                state,
+                tr!("label-path"),
+                path
+            ),
+        }
+    }
+
+    /// One account `find` matched.
+    pub fn found(self, account: &FoundJson) -> String {
+        let identity = AccountIdentity {
+            email: account.email.clone(),
+            organization: account.organization.clone(),
+            plan: None,
+        };
+        let mut line = match self {
+            Self::Table | Self::Json => format!("{:<20}", account.name),
+            Self::Plain => format!("{}: {}", tr!("label-name"), account.name),
+        };
+        if !identity.is_empty() {
+            line.push_str(&self.identity(&identity));
+        }
+        if let Some(count) = account.matching_lines {
+            line.push_str(&match self {
+                Self::Table | Self::Json => format!("  ({})", tr!("find-lines", count = count)),
+                Self::Plain => format!(", {}", tr!("find-lines", count = count)),
+            });
+        }
+        line.trim_end().to_string()
+    }
+
+    /// One line of `size`.
+    pub fn size(self, row: &SizeRow) -> String {
+        let (size, version_size) = (
+            size::format_bytes(row.bytes),
+            size::format_bytes(row.version_bytes),
+        );
+        match self {
+            Self::Table | Self::Json => format!(
+                "{:<20} {}",
+                row.name,
+                tr!(
+                    "size-account",
+                    files = row.files,
+                    size = size,
+                    version_files = row.version_files,
+                    version_size = version_size
+                )
+            ),
+            Self::Plain => tr!(
+                "plain-size",
+                name = row.name,
+                files = row.files,
+                size = size,
+                version_files = row.version_files,
+                version_size = version_size
+            ),
+        }
+    }
+
+    /// The account name `current` prints, with who it's signed in as.
+    pub fn current(self, name: &str, identity: Option<&AccountIdentity>) -> String {
+        match identity {
+            Some(identity) => format!("{}{}", name, self.identity(identity)),
+            None => name.to_string(),
+        }
+    }
+
+    /// The lines `current --verbose` prints under the account, one detail
+    /// each. `now` is for how long the token has left.
+    pub fn current_details(self, details: &CurrentDetails, now: DateTime<Utc>) -> Vec<String> {
+        let drift = match (details.drifted, details.changes.len()) {
+            (false, _) => tr!("no"),
+            (true, 0) => tr!("yes"),
+            (true, changes) => tr!("info-unsaved", count = changes),
+        };
+        let token = match details.expires_at {
+            Some(at) if at <= now => tr!("info-token-expired", time = utc_minutes(at)),
+            Some(at) => tr!(
+                "info-token-expires",
+                time = utc_minutes(at),
+                left = duration::format_countdown(at - now)
+            ),
+            None => tr!("info-unknown"),
+        };
+        [
+            (
+                tr!("label-saved"),
+                details
+                    .saved_at
+                    .get(..19)
+                    .unwrap_or(details.saved_at)
+                    .to_string(),
+            ),
+            (
+                tr!("label-last-used"),
+                details
+                    .last_used
+                    .map(|at| at.get(..19).unwrap_or(at).to_string())
+                    .unwrap_or_else(|| tr!("info-never")),
+            ),
+            (tr!("label-unsaved"), drift),
+            (tr!("label-token"), token),
+        ]
+        .into_iter()
+        .map(|(label, value)| self.detail(&label, &value))
+        .collect()
+    }
+
+    /// Everything `info` knows about an account, one detail per line.
+    /// `now` is for how long the token has left.
+    pub fn account_info(self, info: &AccountInfo, now: DateTime<Utc>) -> Vec<String> {
+        let identity = info.identity.clone().unwrap_or_default();
+        let unknown = || tr!("info-unknown");
+        let mut lines = match self {
+            Self::Table | Self::Json if info.current => {
+                vec![format!("{} {}", info.name, tr!("info-current"))]
+            }
+            Self::Table | Self::Json => vec![info.name.to_string()],
+            Self::Plain => vec![
+                self.detail(&tr!("label-name"), info.name),
+                self.detail(&tr!("label-current"), &yes_no(info.current)),
+            ],
+        };
+        for (label, value) in [
+            (tr!("label-email"), identity.email.unwrap_or_else(unknown)),
+            (
+                tr!("label-organization"),
+                identity.organization.unwrap_or_else(unknown),
+            ),
+            (tr!("label-plan"), identity.plan.unwrap_or_else(unknown)),
+            (
+                tr!("label-saved"),
+                info.saved_at.get(..19).unwrap_or(info.saved_at).to_string(),
+            ),
+            (
+                tr!("label-last-used"),
+                info.last_used
+                    .map(|at| at.get(..19).unwrap_or(at).to_string())
+                    .unwrap_or_else(|| tr!("info-never")),
+            ),
+            (tr!("label-switches"), info.switch_count.to_string()),
+            (tr!("label-path"), info.path.display().to_string()),
+            (
+                tr!("label-size"),
+                tr!(
+                    "info-size",
+                    size = size::format_bytes(info.size_bytes),
+                    files = info.files
+                ),
+            ),
+            (
+                tr!("label-token"),
+                match info.token_expires_at {
+                    Some(at) if at <= now => tr!("info-token-expired", time = utc_minutes(at)),
+                    Some(at) => tr!(
+                        "info-token-expires",
+                        time = utc_minutes(at),
+                        left = duration::format_countdown(at - now)
+                    ),
+                    None => unknown(),
+                },
+            ),
+            (tr!("label-protected"), yes_no(info.protected)),
+            (tr!("label-locked"), yes_no(info.locked)),
+            (tr!("label-external"), yes_no(info.external)),
+        ] {
+            lines.push(self.detail(&label, &value));
+        }
+        if let Some(changes) = info.unsaved_changes {
+            let value = match changes {
+                0 => tr!("no"),
+                changes => tr!("info-unsaved", count = changes),
+            };
+            lines.push(self.detail(&tr!("label-unsaved"), &value));
+        }
+        if let Some(until) = info.limited_until {
+            lines.push(self.detail(&tr!("label-limited"), until.get(..19).unwrap_or(until)));
+        }
+        if let Some(version) = info.claude_version {
+            lines.push(self.detail(&tr!("label-claude-version"), version));
+        }
+        if let Some(version) = info.pinned_version {
+            lines.push(self.detail(&tr!("label-pinned"), version));
+        }
+        if !info.tags.is_empty() {
+            lines.push(self.detail(&tr!("label-tags"), &info.tags.join(", ")));
+        }
+        if !info.aliases.is_empty() {
+            lines.push(self.detail(&tr!("label-aliases"), &info.aliases.join(", ")));
+        }
+        // Lines after the first line up under it in a table
+        let label = tr!("label-note");
+        for (i, line) in info.note.unwrap_or_default().lines().enumerate() {
+            lines.push(match self {
+                Self::Table | Self::Json if i > 0 => self.detail("", line),
+                _ => self.detail(&label, line),
+            });
+        }
+        lines
+    }
+
+    /// `status` without `--porcelain`: the account, then a line per detail.
+    pub fn status(self, status: &Status) -> Vec<String> {
+        let expiry = match status.expires_in {
+            Some(seconds) if seconds <= 0 => tr!("status-expired"),
+            Some(_) => tr!("status-expires-in", time = status.expiry()),
+            None => tr!("info-unknown"),
+        };
+        let mut lines = match self {
+            Self::Table | Self::Json => vec![status.account.clone()],
+            Self::Plain => vec![self.detail(&tr!("label-name"), &status.account)],
+        };
+        for (label, value) in [
+            (
+                tr!("label-plan"),
+                status.plan.clone().unwrap_or_else(|| tr!("info-unknown")),
+            ),
+            (tr!("label-token"), expiry),
+            (tr!("label-unsaved"), yes_no(status.dirty)),
+        ] {
+            lines.push(self.detail(&label, &value));
+        }
+        lines
+    }
+
+    /// `status` without `--porcelain`: the account's status, then the store.
+    pub fn overview(self, overview: &Overview) -> Vec<String> {
+        let mut lines = match &overview.status {
+            Some(status) => self.status(status),
+            None => vec![tr!("no-active-account")],
+        };
+        let lock = match &overview.locked_by {
+            Some(holder) => tr!("status-locked", holder = holder),
+            None => tr!("status-unlocked"),
+        };
+        let sync = match &overview.sync {
+            Some(sync) if sync.to_push == 0 && sync.to_pull == 0 => {
+                tr!("status-synced", remote = sync.remote)
+            }
+            Some(sync) => tr!(
+                "status-sync-pending",
+                push = sync.to_push,
+                pull = sync.to_pull
+            ),
+            None => tr!("status-sync-off"),
+        };
+        for (label, value) in [
+            (tr!("label-accounts"), overview.account_count.to_string()),
+            (tr!("label-store"), size::format_bytes(overview.store_bytes)),
+            (tr!("label-lock"), lock),
+            (tr!("label-sync"), sync),
+        ] {
+            lines.push(self.detail(&label, &value));
+        }
+        lines
+    }
+
+    /// A file `diff` found changed, marked the way `git status --short` does.
+    pub fn file_change(self, change: &FileChange) -> String {
+        let path = change.path.display();
+        match self {
+            Self::Table | Self::Json => {
+                let marker = match change.change {
+                    Change::Added => "A",
+                    Change::Removed => "D",
+                    Change::Modified => "M",
+                };
+                format!("{}  {}", marker, path)
+            }
+            Self::Plain => match change.change {
+                Change::Added => tr!("diff-added", path = path),
+                Change::Removed => tr!("diff-removed", path = path),
+                Change::Modified => tr!("diff-modified", path = path),
+            },
+        }
+    }
+
+    fn detail(self, label: &str, value: &str) -> String {
+        match self {
+            Self::Table | Self::Json => format!("  {:<14} {}", label, value),
+            Self::Plain => format!("{}: {}", label, value),
+        }
+    }
+
+    /// The identity appended to an account's line: comma-separated in a
+    /// table, labelled for plain output.
+    fn identity(self, identity: &AccountIdentity) -> String {
+        match self {
+            Self::Table | Self::Json => format!("  {}", identity),
+            Self::Plain => [
+                (tr!("label-email"), &identity.email),
+                (tr!("label-organization"), &identity.organization),
+                (tr!("label-plan"), &identity.plan),
+            ]
+            .into_iter()
+            .filter_map(|(label, value)| {
+                value
+                    .as_ref()
+                    .map(|value| format!(", {}: {}", label, value))
+            })
+            .collect(),
+        }
+    }
+
+    pub fn group(self, label: &str, count: usize, size: &str) -> String {
+        match self {
+            Self::Table | Self::Json if count == 1 => {
+                tr!("group-summary-one", group = label, size = size)
+            }
+            Self::Table | Self::Json => tr!(
+                "group-summary-many",
+                group = label,
+                count = count,
+                size = size
+            ),
+            Self::Plain => tr!("plain-group", group = label, count = count, size = size),
+        }
+    }
+
+    pub fn history(self, event: &Event) -> String {
+        let at = event.at.get(..19).unwrap_or(&event.at);
+        let mut line = match self {
+            Self::Table | Self::Json => format!(
+                "{}  {:<8} {:<20} ({})",
+                at, event.kind, event.account, event.source
+            ),
+            Self::Plain => tr!(
+                "plain-history",
+                time = at,
+                action = event.kind,
+                account = event.account,
+                source = event.source
+            ),
+        };
+        let plain = self == Self::Plain;
+        if let Some(previous) = &event.previous {
+            line.push_str(&match plain {
+                true => tr!("plain-history-previous", name = previous),
+                false => tr!("history-previous", name = previous),
+            });
+        }
+        if let Some(files) = event.files {
+            line.push_str(&match plain {
+                true => tr!("plain-history-files", count = files),
+                false => tr!("history-files", count = files),
+            });
+        }
+        line
+    }
+
+    pub fn version(self, row: &VersionRow) -> String {
+        let saved_at = row.saved_at.as_deref().unwrap_or("-");
+        let saved_at = saved_at.get(..19).unwrap_or(saved_at);
+        let size = size::format_bytes(row.bytes);
+        match self {
+            Self::Table | Self::Json => format!(
+                "{:<20} ({})  {}",
+                row.id,
+                tr!("saved-at", time = saved_at),
+                tr!("version-usage", files = row.files, size = size)
+            ),
+            Self::Plain => tr!(
+                "plain-version",
+                id = row.id,
+                time = saved_at,
+                files = row.files,
+                size = size
+            ),
+        }
+    }
+
+    pub fn trash_entry(self, row: &TrashRow) -> String {
+        let deleted_at = row.deleted_at.get(..19).unwrap_or(&row.deleted_at);
+        let size = size::format_bytes(row.bytes);
+        match self {
+            Self::Table | Self::Json => format!(
+                "{:<20} ({})  {}",
+                row.name,
+                tr!("deleted-at", time = deleted_at),
+                tr!("version-usage", files = row.files, size = size)
+            ),
+            Self::Plain => tr!(
+                "plain-trashed",
+                name = row.name,
+                time = deleted_at,
+                files = row.files,
+                size = size
+            ),
+        }
+    }
+
+    pub fn session(self, session: &Session) -> String {
+        let since = utc_minutes(session.started_at);
+        match self {
+            Self::Table | Self::Json => format!(
+                "{:<20} {:<5}  {}  ({})",
+                session.account,
+                session.kind,
+                tr!("session-pid", pid = session.pid),
+                tr!("session-since", time = since)
+            ),
+            Self::Plain => tr!(
+                "plain-session",
+                name = session.account,
+                kind = session.kind,
+                pid = session.pid,
+                time = since
+            ),
+        }
+    }
+
+    pub fn live_backup(self, backup: &LiveBackup) -> String {
+        let account = backup.account.as_deref().unwrap_or("-");
+        let time = backup
+            .replaced_at()
+            .map_or_else(|| tr!("info-unknown"), utc_minutes);
+        match self {
+            Self::Table | Self::Json => format!("{:<20} {:<20} {}", backup.id, account, time),
+            Self::Plain => tr!(
+                "plain-live-backup",
+                id = backup.id,
+                name = account,
+                time = time
+            ),
+        }
+    }
+
+    pub fn snapshot_entry(self, entry: &SnapshotEntryJson) -> String {
+        let name = match entry.kind {
+            EntryKind::Dir => format!("{}/", entry.name),
+            EntryKind::Symlink => format!("{}@", entry.name),
+            EntryKind::File => entry.name.clone(),
+        };
+        let size = entry
+            .size
+            .map_or_else(|| "-".to_string(), size::format_bytes);
+        match self {
+            Self::Table | Self::Json => format!("{:>10}  {}", size, name),
+            Self::Plain => tr!("plain-snapshot-entry", path = name, size = size),
+        }
+    }
+
+    pub fn checkpoint(self, checkpoint: &Checkpoint) -> String {
+        let account = checkpoint.account.as_deref().unwrap_or("-");
+        let time = utc_minutes(checkpoint.created_at);
+        match self {
+            Self::Table | Self::Json => {
+                format!("{:<20} {:<20} {}", checkpoint.label, account, time)
+            }
+            Self::Plain => tr!(
+                "plain-checkpoint",
+                label = checkpoint.label,
+                name = account,
+                time = time
+            ),
+        }
+    }
+
+    pub fn project(self, path: &Path, account: &str) -> String {
+        match self {
+            Self::Table | Self::Json => format!("{:<20} {}", account, path.display()),
+            Self::Plain => tr!("plain-project", name = account, path = path.display()),
+        }
+    }
+
+    /// A file's before/after summary, then where its backup went.
+    pub fn file_upgrade(self, upgrade: &FileUpgrade) -> Vec<String> {
+        let path = upgrade.path.display();
+        let mut lines = vec![if upgrade.changed {
+            tr!(
+                "upgrade-changed",
+                path = path,
+                contents = upgrade.contents,
+                before = size::format_bytes(upgrade.before_bytes),
+                after = size::format_bytes(upgrade.after_bytes)
+            )
+        } else {
+            tr!(
+                "upgrade-unchanged",
+                path = path,
+                contents = upgrade.contents
+            )
+        }];
+        if let Some(backup) = &upgrade.backup {
+            let line = tr!("upgrade-backup", path = backup.display());
+            lines.push(match self {
+                Self::Table | Self::Json => format!("  {}", line),
+                Self::Plain => line,
+            });
+        }
+        lines
+    }
+
+    /// A `doctor` finding, followed by its fix when there is one.
+    pub fn finding(self, finding: &Finding) -> Vec<String> {
+        let mut lines = vec![match self {
+            Self::Table | Self::Json => {
+                let marker = match finding.severity {
+                    Severity::Ok => paint("✓", Tone::Ok),
+                    Severity::Warning => paint("!", Tone::Warning),
+                    Severity::Problem => paint("✗", Tone::Error),
+                };
+                format!("{} {}", marker, finding.message)
+            }
+            Self::Plain => {
+                let severity = match finding.severity {
+                    Severity::Ok => tr!("doctor-ok"),
+                    Severity::Warning => tr!("doctor-warning"),
+                    Severity::Problem => tr!("doctor-problem"),
+                };
+                format!("{}: {}", severity, finding.message)
+            }
+        }];
+        if let Some(fix) = &finding.fix {
+            let fix = if finding.fixed {
+                tr!("doctor-fixed", fix = fix)
+            } else {
+                tr!("doctor-fix", fix = fix)
+            };
+            lines.push(match self {
+                Self::Table | Self::Json => format!("  → {}", fix),
+                Self::Plain => fix,
+            });
+        }
+        lines
+    }
+
+    /// Prefix for an account listed under a group.
+    pub fn group_member(self, row: &AccountRow, last: bool) -> String {
+        match self {
+            Self::Table | Self::Json => {
+                format!("{} {}", if last { "└──" } else { "├──" }, self.account(row))
+            }
+            Self::Plain => format!("  {}", self.account(row)),
+        }
+    }
+}
+
+fn utc_minutes(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M UTC").to_string()
+}
+
+fn yes_no(value: bool) -> String {
+    if value { tr!("yes") } else { tr!("no") }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::{EventKind, EventSource};
+
+    fn row(current: bool) -> AccountRow<'static> {
+        AccountRow {
+            number: 1,
+            name: "work",
+            aliases: Vec::new(),
+            current,
+            saved_at: "2024-01-01T00:00:00.123+00:00",
+            identity: None,
+            tags: Vec::new(),
+            note: None,
+            limited_for: None,
+            archived: false,
+            usage: None,
+            last_used: None,
+            unsaved: None,
+            expires_at: None,
+            stale_days: None,
+            expiry: None,
+        }
+    }
+
+    #[test]
+    fn test_account_table() {
+        let identity = AccountIdentity {
+            email: Some("dev@example.com".to_string()),
+            organization: None,
+            plan: Some("max".to_string()),
+        };
+        let rows = [
+            AccountRow {
+                identity: Some(&identity),
+                unsaved: Some(2),
+                usage: Some(DirUsage {
+                    files: 3,
+                    bytes: 2048,
+                }),
+                ..row(true)
+            },
+            AccountRow {
+                number: 2,
+                name: "personal",
+                tags: vec!["home"],
+                ..row(false)
+            },
+        ];
+        let columns = [
+            Column::Number,
+            Column::Name,
+            Column::Email,
+            Column::Tags,
+            Column::Size,
+            Column::Unsaved,
+        ];
+        assert_eq!(
+            OutputFormat::Table.account_table(&columns, &rows),
+            [
+                "   #  NAME      EMAIL            TAGS  SIZE     UNSAVED",
+                "*  1  work      dev@example.com  -     2.0 KiB  2 file(s) changed",
+                "   2  personal  -                home  -        -",
+            ]
+        );
+        assert_eq!(
+            OutputFormat::Plain.account_table(&columns[..3], &rows[1..]),
+            ["number: 2, name: personal, email: -, current: no"]
+        );
+    }
+
+    #[test]
+    fn test_documents_quote_and_escape() {
+        let identity = AccountIdentity {
+            email: Some("dev@example.com".to_string()),
+            organization: Some("Acme, Inc | EU".to_string()),
+            plan: None,
+        };
+        let rows = [
+            AccountRow {
+                identity: Some(&identity),
+                expires_at: DateTime::from_timestamp(1767225600, 0),
+                ..row(true)
+            },
+            AccountRow {
+                name: "personal",
+                ..row(false)
+            },
+        ];
+        let columns = [
+            Column::Name,
+            Column::Organization,
+            Column::Plan,
+            Column::Expires,
+        ];
+        assert_eq!(
+            Document::Csv.render(&columns, &rows),
+            [
+                "name,organization,plan,expires",
+                "work,\"Acme, Inc | EU\",,2026-01-01T00:00:00",
+                "personal,,,",
+            ]
+        );
+        assert_eq!(
+            Document::Markdown.render(&columns, &rows),
+            [
+                "| name | organization | plan | expires |",
+                "| --- | --- | --- | --- |",
+                "| work | Acme, Inc \\| EU | - | 2026-01-01T00:00:00 |",
+                "| personal | - | - | - |",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_account_usage() {
+        let row = AccountRow {
+            usage: Some(DirUsage {
+                files: 3,
+                bytes: 2048,
+            }),
+            ..row(false)
+        };
+        assert_eq!(
+            OutputFormat::Table.account(&row),
+            format!(
+                "   1 {:<20} (saved: 2024-01-01T00:00:00)  3 files, 2.0 KiB",
+                "work"
+            )
+        );
+        assert!(
+            OutputFormat::Plain
+                .account(&row)
+                .ends_with("saved: 2024-01-01T00:00:00, files: 3, size: 2.0 KiB")
+        );
+    }
+
+    #[test]
+    fn test_account_aliases() {
+        let row = AccountRow {
+            aliases: vec!["w", "wm"],
+            ..row(false)
+        };
+        assert!(
+            OutputFormat::Table
+                .account(&row)
+                .ends_with(")  alias w, wm")
+        );
+        assert!(
+            OutputFormat::Plain
+                .account(&row)
+                .ends_with(", aliases: w, wm")
+        );
+    }
+
+    #[test]
+    fn test_account_info_details() {
+        let now = DateTime::parse_from_rfc3339("2024-01-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let info = AccountInfo {
+            name: "work",
+            current: true,
+            identity: None,
+            saved_at: "2024-01-01T10:00:00+00:00",
+            last_used: None,
+            switch_count: 0,
+            path: Path::new("/store/work"),
+            protected: false,
+            locked: false,
+            external: false,
+            tags: Vec::new(),
+            aliases: vec!["w"],
+            note: None,
+            files: 3,
+            size_bytes: 2048,
+            token_expires_at: Some(now + chrono::Duration::minutes(90)),
+            unsaved_changes: Some(2),
+            limited_until: None,
+            claude_version: Some("2.0.14"),
+            pinned_version: None,
+        };
+        let lines = OutputFormat::Table.account_info(&info, now);
+        assert_eq!(lines[0], "work (current)");
+        for expected in [
+            "  size           2.0 KiB in 3 file(s)",
+            "  token          2024-01-01 13:30 UTC (in 1h30m)",
+            "  unsaved        2 file(s) changed",
+            "  aliases        w",
+            "  claude code    2.0.14",
+        ] {
+            assert!(lines.iter().any(|line| line == expected), "{}", expected);
+        }
+
+        let expired = AccountInfo {
+            token_expires_at: Some(now),
+            unsaved_changes: None,
+            ..info
+        };
+        let lines = OutputFormat::Plain.account_info(&expired, now);
+        assert!(lines.contains(&"token: expired 2024-01-01 12:00 UTC".to_string()));
+        assert!(!lines.iter().any(|line| line.starts_with("unsaved")));
+    }
+
+    #[test]
+    fn test_account_archived() {
+        let row = AccountRow {
+            archived: true,
+            ..row(false)
+        };
+        assert!(OutputFormat::Table.account(&row).ends_with(")  (archived)"));
+        assert!(OutputFormat::Plain.account(&row).ends_with(", archived"));
+        assert_eq!(Column::Size.cell(&row), "archived");
+    }
+
+    #[test]
+    fn test_account_limited() {
+        let row = AccountRow {
+            limited_for: Some(chrono::Duration::minutes(125)),
+            ..row(false)
+        };
+        assert!(
+            OutputFormat::Table
+                .account(&row)
+                .ends_with(")  (rate limited, 2h05m left)")
+        );
+        assert!(
+            OutputFormat::Plain
+                .account(&row)
+                .ends_with(", rate limited for: 2h05m")
+        );
+    }
+
+    #[test]
+    fn test_account_needs_attention() {
+        let expires_at = DateTime::parse_from_rfc3339("2024-01-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let row = AccountRow {
+            stale_days: Some(40),
+            expires_at: Some(expires_at),
+            expiry: Some(Expiry::Soon),
+            ..row(false)
+        };
+        assert!(row.needs_attention());
+        assert!(!self::row(false).needs_attention());
+        assert!(
+            OutputFormat::Table
+                .account(&row)
+                .ends_with(")  (saved 40 days ago)  (login expires 2024-01-01 12:00 UTC)")
+        );
+        let row = AccountRow {
+            expiry: Some(Expiry::Expired),
+            ..row
+        };
+        assert!(
+            OutputFormat::Plain
+                .account(&row)
+                .ends_with(", stale: 40 days, login expired: 2024-01-01 12:00 UTC")
+        );
+    }
+
+    #[test]
+    fn test_size_row() {
+        let row = SizeRow {
+            name: "work".to_string(),
+            external: false,
+            files: 10,
+            bytes: 3 * 1024 * 1024,
+            version_files: 20,
+            version_bytes: 1024,
+        };
+        assert_eq!(
+            OutputFormat::Table.size(&row),
+            format!(
+                "{:<20} 10 files, 3.0 MiB (versions: 20 files, 1.0 KiB)",
+                "work"
+            )
+        );
+        assert_eq!(
+            OutputFormat::Plain.size(&row),
+            "name: work, files: 10, size: 3.0 MiB, version files: 20, version size: 1.0 KiB"
+        );
+        assert_eq!(row.total_bytes(), 3 * 1024 * 1024 + 1024);
+    }
+
+    #[test]
+    fn test_table_account() {
+        assert_eq!(
+            OutputFormat::Table.account(&row(true)),
+            format!("*  1 {:<20} (saved: 2024-01-01T00:00:00)", "work")
+        );
+    }
+
+    #[test]
+    fn test_color_choice() {
+        let set = OsString::from("1");
+        let empty = OsString::new();
+        assert!(wants_color(ColorChoice::Auto, None, true));
+        assert!(wants_color(ColorChoice::Auto, Some(&empty), true));
+        assert!(!wants_color(ColorChoice::Auto, Some(&set), true));
+        assert!(!wants_color(ColorChoice::Auto, None, false));
+        assert!(wants_color(ColorChoice::Always, Some(&set), false));
+        assert!(!wants_color(ColorChoice::Never, None, true));
+
+        assert_eq!(
+            styled("careful", Tone::Warning),
+            "\x1b[38;5;11mcareful\x1b[39m"
+        );
+        // Off until `init_color` says otherwise
+        assert_eq!(paint("work", Tone::Current), "work");
+    }
+
+    #[test]
+    fn test_plain_account_uses_labels() {
+        assert_eq!(
+            OutputFormat::Plain.account(&row(true)),
+            "number: 1, name: work, current: yes, saved: 2024-01-01T00:00:00"
+        );
+        assert!(
+            OutputFormat::Plain
+                .account(&row(false))
+                .contains("current: no")
+        );
+    }
+
+    #[test]
+    fn test_account_identity() {
+        let identity = AccountIdentity {
+            email: Some("dev@example.com".to_string()),
+            organization: None,
+            plan: Some("max".to_string()),
+        };
+        let row = AccountRow {
+            identity: Some(&identity),
+            ..row(false)
+        };
+        assert!(
+            OutputFormat::Table
+                .account(&row)
+                .ends_with(")  dev@example.com, max")
+        );
+        assert!(
+            OutputFormat::Plain
+                .account(&row)
+                .ends_with("saved: 2024-01-01T00:00:00, email: dev@example.com, plan: max")
+        );
+        assert_eq!(OutputFormat::Table.current("work", None), "work");
+    }
+
+    #[test]
+    fn test_status_porcelain() {
+        let mut status = Status {
+            account: "work".to_string(),
+            plan: Some("max".to_string()),
+            expires_at: None,
+            expires_in: Some(2 * 3600 + 5 * 60),
+            dirty: true,
+            stale: true,
+        };
+        assert_eq!(
+            status.porcelain(),
+            "name=work plan=max expires=2h05m dirty=1"
+        );
+
+        status.plan = None;
+        status.expires_in = Some(-5);
+        status.dirty = false;
+        assert_eq!(
+            status.porcelain(),
+            "name=work plan=- expires=expired dirty=0"
+        );
+    }
+
+    #[test]
+    fn test_overview() -> Result<()> {
+        let mut overview = Overview {
+            status: None,
+            account_count: 2,
+            store_bytes: 2048,
+            locked_by: Some("pid 42: switch work".to_string()),
+            sync: Some(SyncPending {
+                remote: "s3://bucket".to_string(),
+                to_push: 1,
+                to_pull: 0,
+            }),
+        };
+        assert_eq!(
+            OutputFormat::Plain.overview(&overview),
+            [
+                "No active account",
+                "accounts: 2",
+                "store: 2.0 KiB",
+                "lock: held by pid 42: switch work",
+                "sync: 1 to push, 0 to pull (as of the last sync)",
+            ]
+        );
+
+        // Without an active account its fields are left out, not null
+        overview.sync = None;
+        let json = serde_json::to_value(&overview)?;
+        assert_eq!(json["account_count"], 2);
+        assert!(json.get("account").is_none() && json.get("sync").is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_plain_avoids_drawing_characters() {
+        let lines = [
+            OutputFormat::Plain.header().join("\n"),
+            OutputFormat::Plain.group("client-a", 2, "16 B"),
+            OutputFormat::Plain.group_member(&row(false), true),
+        ];
+
+        for line in lines {
+            assert!(line.is_ascii(), "{}", line);
+            assert!(!line.contains("--"), "{}", line);
+            assert!(!line.contains("  *"), "{}", line);
+        }
+    }
+
+    #[test]
+    fn test_history_lines() {
+        let event = Event {
+            at: "2024-01-01T00:00:00.123+00:00".to_string(),
+            kind: EventKind::Save,
+            account: "work".to_string(),
+            source: EventSource::AutoSave,
+            previous: None,
+            files: None,
+            live_backup: None,
+        };
+        assert_eq!(
+            OutputFormat::Table.history(&event),
+            format!(
+                "2024-01-01T00:00:00  {:<8} {:<20} (auto-save)",
+                "save", "work"
+            )
+        );
+        assert_eq!(
+            OutputFormat::Plain.history(&event),
+            "time: 2024-01-01T00:00:00, action: save, account: work, source: auto-save"
+        );
+
+        let event = Event {
+            kind: EventKind::Switch,
+            source: EventSource::Cli,
+            previous: Some("personal".to_string()),
+            files: Some(12),
+            ..event
+        };
+        assert_eq!(
+            OutputFormat::Table.history(&event),
+            format!(
+                "2024-01-01T00:00:00  {:<8} {:<20} (cli) from personal, 12 files",
+                "switch", "work"
+            )
+        );
+        assert_eq!(
+            OutputFormat::Plain.history(&event),
+            "time: 2024-01-01T00:00:00, action: switch, account: work, source: cli, \
+             previous: personal, files: 12"
+        );
+    }
+
+    #[test]
+    fn test_version_lines() {
+        let row = VersionRow {
+            id: "20240101T000000Z".to_string(),
+            saved_at: Some("2024-01-01T00:00:00+00:00".to_string()),
+            files: 3,
+            bytes: 2048,
+        };
+        assert_eq!(
+            OutputFormat::Table.version(&row),
+            format!(
+                "{:<20} (saved: 2024-01-01T00:00:00)  3 files, 2.0 KiB",
+                "20240101T000000Z"
+            )
+        );
+        assert_eq!(
+            OutputFormat::Plain.version(&row),
+            "id: 20240101T000000Z, saved: 2024-01-01T00:00:00, files: 3, size: 2.0 KiB"
+        );
+    }
+
+    #[test]
+    fn test_action_json() -> Result<()> {
+        let transfer = Transfer {
+            usage: crate::size::DirUsage {
+                files: 3,
+                bytes: 120,
+            },
+            elapsed: std::time::Duration::from_millis(1500),
+        };
+        let action = ActionJson {
+            action: "save",
+            account: "work",
+            cancelled: false,
+            copied: Some(transfer.into()),
+        };
+        assert_eq!(
+            serde_json::to_value(&action)?,
+            serde_json::json!({
+                "action": "save",
+                "account": "work",
+                "copied": {"files": 3, "bytes": 120, "duration_ms": 1500}
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_json_keeps_context_chain() -> Result<()> {
+        let error = anyhow::anyhow!("disk full").context("Failed to copy configuration files");
+        assert_eq!(
+            serde_json::to_value(ErrorJson::new(&error))?,
+            serde_json::json!({
+                "kind": "error",
+                "code": 1,
+                "error": "Failed to copy configuration files",
+                "causes": ["disk full"]
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_table_group_member_tree() {
+        assert!(
+            OutputFormat::Table
+                .group_member(&row(false), true)
+                .starts_with("└──")
+        );
+        assert!(
+            OutputFormat::Table
+                .group_member(&row(false), false)
+                .starts_with("├──")
+        );
+    }
+
+    #[test]
+    fn test_finding_lines() {
+        let finding = Finding::problem("Account 'work' is missing".to_string())
+            .with_fix("remove it from the list".to_string());
+        assert_eq!(
+            OutputFormat::Table.finding(&finding),
+            [
+                "✗ Account 'work' is missing",
+                "  → Fix: remove it from the list"
+            ]
+        );
+        assert_eq!(
+            OutputFormat::Plain.finding(&finding)[0],
+            "problem: Account 'work' is missing"
+        );
+    }
+}
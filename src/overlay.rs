@@ -0,0 +1,139 @@
+//! The team overlay: a directory laid over the live configuration after
+//! every switch, so settings, hooks and MCP servers an organisation asks for
+//! stay in place whichever account is active. It's set with `team-overlay`
+//! and is often a git checkout that everyone pulls.
+//!
+//! It's laid out like `~/.claude`. JSON files are merged into the live ones
+//! key by key (see [`template::merge`]), with the overlay winning; any other
+//! file replaces the live one. `mcp.json` holds MCP servers, merged into
+//! `.claude.json` as a template's are. Its `.git` directory and credential
+//! files are never copied.
+
+use crate::claude;
+use crate::platform;
+use crate::store;
+use crate::template;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+
+const SKIPPED_DIRS: &[&str] = &[".git"];
+
+/// Lays `overlay` over the Claude directory `live`, with `claude_json` as
+/// the `.claude.json` Claude Code reads. Returns how many files it applied.
+pub fn apply(overlay: &Path, live: &Path, claude_json: &Path) -> Result<usize> {
+    if !overlay.is_dir() {
+        anyhow::bail!("Team overlay not found: {}", overlay.display());
+    }
+
+    let mut applied = 0;
+    for entry in store::walk_dir(overlay).skip_dirs(SKIPPED_DIRS) {
+        let entry = entry?;
+        let relative = &entry.relative_path;
+        if claude::is_credential_file(relative) {
+            continue;
+        }
+        let target = match relative == Path::new(template::MCP_FILE) {
+            true => claude_json.to_path_buf(),
+            false => live.join(relative),
+        };
+        let data = fs::read(&entry.path)
+            .with_context(|| format!("Failed to read file: {}", entry.path.display()))?;
+
+        let data = match relative.extension().is_some_and(|ext| ext == "json") {
+            true => merged(&entry.path, data, &target, relative)?,
+            false => data,
+        };
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+        }
+        platform::write_replacing(&target, &data)
+            .with_context(|| format!("Failed to write file: {}", target.display()))?;
+        applied += 1;
+    }
+    Ok(applied)
+}
+
+// The JSON in `data` laid over what `target` holds; `mcp.json` only has the
+// servers, which go under their key
+fn merged(source: &Path, data: Vec<u8>, target: &Path, relative: &Path) -> Result<Vec<u8>> {
+    let mut layer: serde_json::Value = serde_json::from_slice(&data)
+        .with_context(|| format!("Failed to parse {}", source.display()))?;
+    if relative == Path::new(template::MCP_FILE) {
+        layer = serde_json::json!({ template::MCP_KEY: layer });
+    }
+    let mut live = match target.exists() {
+        true => {
+            let contents = fs::read(target)
+                .with_context(|| format!("Failed to read file: {}", target.display()))?;
+            serde_json::from_slice(&contents)
+                .with_context(|| format!("Failed to parse {}", target.display()))?
+        }
+        false => serde_json::json!({}),
+    };
+    template::merge(&mut live, layer);
+    let contents =
+        serde_json::to_string_pretty(&live).context("Failed to serialize the overlay")?;
+    Ok((contents + "\n").into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_overlay_merges_json_and_replaces_the_rest() -> Result<()> {
+        let dir = TempDir::new()?;
+        let overlay = dir.path().join("overlay");
+        let live = dir.path().join(".claude");
+        let claude_json = dir.path().join(".claude.json");
+        fs::create_dir_all(overlay.join("hooks"))?;
+        fs::create_dir_all(overlay.join(".git"))?;
+        fs::create_dir_all(&live)?;
+
+        fs::write(
+            overlay.join("settings.json"),
+            r#"{"permissions": {"deny": ["Bash(curl:*)"]}}"#,
+        )?;
+        fs::write(overlay.join("hooks/audit.sh"), "#!/bin/sh\n")?;
+        fs::write(
+            overlay.join("mcp.json"),
+            r#"{"jira": {"command": "jira-mcp"}}"#,
+        )?;
+        fs::write(overlay.join(".git/HEAD"), "ref: refs/heads/main\n")?;
+        fs::write(overlay.join(".credentials.json"), "{}")?;
+        fs::write(
+            live.join("settings.json"),
+            r#"{"model": "opus", "permissions": {"allow": ["Read"]}}"#,
+        )?;
+        fs::write(
+            &claude_json,
+            r#"{"oauthAccount": {"emailAddress": "me@example.com"}}"#,
+        )?;
+
+        assert_eq!(apply(&overlay, &live, &claude_json)?, 3);
+        let read = |path: &Path| -> Result<Value> {
+            Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+        };
+        assert_eq!(
+            read(&live.join("settings.json"))?,
+            json!({"model": "opus", "permissions": {"allow": ["Read"], "deny": ["Bash(curl:*)"]}})
+        );
+        assert_eq!(
+            read(&claude_json)?,
+            json!({
+                "oauthAccount": {"emailAddress": "me@example.com"},
+                "mcpServers": {"jira": {"command": "jira-mcp"}}
+            })
+        );
+        assert!(live.join("hooks/audit.sh").is_file());
+        assert!(!live.join(".git").exists());
+        assert!(!live.join(".credentials.json").exists());
+
+        assert!(apply(&dir.path().join("missing"), &live, &claude_json).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,304 @@
+//! Where the switcher keeps its files, and where Claude Code keeps its own.
+//!
+//! On Linux and macOS that's the XDG layout, under the home directory unless
+//! `XDG_DATA_HOME`, `XDG_STATE_HOME` or `XDG_CONFIG_HOME` say otherwise. On
+//! Windows settings go to `%APPDATA%`, which roams with the profile, while
+//! snapshots and state stay on the machine in `%LOCALAPPDATA%`.
+//!
+//! Claude Code's own directory is `~/.claude`, unless `--claude-dir`,
+//! `CLAUDE_CONFIG_DIR` or the `claude-dir` setting (in that order) move it.
+//! `CLAUDE_CONFIG_DIR` pointing into the store is an account used from there
+//! (by `env` or `shell`) rather than a live configuration, so it's passed over.
+//!
+//! In system mode (see `system`) snapshots and the accounts file come from
+//! the system store instead, and the rest of the state from a `system`
+//! directory in the user's own state directory.
+//!
+//! `CLAUDE_SWITCHER_HOME` stands in for the home directory, for tests and
+//! sandboxed runs: everything is then in the Linux layout below it, the
+//! Claude directory included, and the variables above are passed over.
+
+use crate::config;
+use anyhow::{Context, Result};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+const APP: &str = "claude-account-switcher";
+/// A directory to use in place of the home directory, keeping the switcher
+/// and Claude Code's directory inside it
+pub const HOME_ENV: &str = "CLAUDE_SWITCHER_HOME";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Paths {
+    /// Account snapshots
+    pub switcher_dir: PathBuf,
+    /// History, locks and other bookkeeping
+    pub state_dir: PathBuf,
+    /// The saved accounts and the active one: in the state directory, unless
+    /// it's shared by a system store
+    pub accounts_file: PathBuf,
+    /// The system store, in system mode
+    pub system: Option<PathBuf>,
+    pub settings_file: PathBuf,
+    /// Where earlier versions kept their accounts
+    pub legacy_dir: PathBuf,
+    home: PathBuf,
+    claude_config_env: Option<PathBuf>,
+}
+
+impl Paths {
+    /// The paths for the current user and environment.
+    pub fn current() -> Result<Self> {
+        // A sandbox is what's in it, whatever else the environment says
+        if let Some(home) = sandbox() {
+            return Ok(Self::resolve(&home, false, |_| None));
+        }
+        let home = dirs::home_dir().context("Failed to determine home directory")?;
+        Ok(Self::resolve(&home, cfg!(windows), |key| {
+            std::env::var_os(key)
+        }))
+    }
+
+    fn resolve(home: &Path, windows: bool, var: impl Fn(&str) -> Option<OsString>) -> Self {
+        // Set but empty counts as unset, as it does for Claude Code
+        let var = |key: &str| {
+            var(key)
+                .filter(|value| !value.is_empty())
+                .map(PathBuf::from)
+        };
+        let claude_config_env = var("CLAUDE_CONFIG_DIR");
+        let legacy_dir = home.join(".claude-accounts");
+
+        if !windows {
+            // XDG Base Directory compliant paths; relative ones are to be
+            // ignored, as the spec says
+            let xdg = |key: &str, default: &str| {
+                var(key)
+                    .filter(|dir| dir.is_absolute())
+                    .unwrap_or_else(|| home.join(default))
+            };
+            let state_dir = xdg("XDG_STATE_HOME", ".local/state").join(APP);
+            return Self {
+                switcher_dir: xdg("XDG_DATA_HOME", ".local/share").join(APP),
+                accounts_file: state_dir.join(config::FILE),
+                state_dir,
+                system: None,
+                settings_file: xdg("XDG_CONFIG_HOME", ".config")
+                    .join(APP)
+                    .join("settings.toml"),
+                legacy_dir,
+                home: home.to_path_buf(),
+                claude_config_env,
+            };
+        }
+
+        let roaming = var("APPDATA")
+            .or_else(dirs::config_dir)
+            .unwrap_or_else(|| home.join("AppData").join("Roaming"));
+        let local = var("LOCALAPPDATA")
+            .or_else(dirs::data_local_dir)
+            .unwrap_or_else(|| home.join("AppData").join("Local"));
+        Self {
+            switcher_dir: local.join(APP).join("accounts"),
+            state_dir: local.join(APP).join("state"),
+            accounts_file: local.join(APP).join("state").join(config::FILE),
+            system: None,
+            settings_file: roaming.join(APP).join("settings.toml"),
+            legacy_dir,
+            home: home.to_path_buf(),
+            claude_config_env,
+        }
+    }
+
+    pub fn home(&self) -> &Path {
+        &self.home
+    }
+
+    /// The paths for the system store in `root`: its snapshots and accounts
+    /// file, with state of this user's own beside their usual state.
+    pub fn system(self, root: &Path) -> Self {
+        Self {
+            switcher_dir: root.join("accounts"),
+            state_dir: self.state_dir.join("system"),
+            accounts_file: root.join(config::FILE),
+            system: Some(root.to_path_buf()),
+            ..self
+        }
+    }
+
+    /// The live Claude Code configuration: `flag` (`--claude-dir`) if given,
+    /// then `CLAUDE_CONFIG_DIR` unless it's in the store, then `configured`
+    /// (the `claude-dir` setting), then `~/.claude`. A leading `~` is the
+    /// home directory.
+    pub fn claude_config_dir(&self, flag: Option<&Path>, configured: Option<&Path>) -> PathBuf {
+        let env = self
+            .claude_config_env
+            .as_deref()
+            .filter(|dir| !dir.starts_with(&self.switcher_dir));
+        let dir = flag
+            .or(env)
+            .or(configured)
+            .unwrap_or(Path::new("~/.claude"));
+        match dir.strip_prefix("~") {
+            Ok(rest) => self.home.join(rest),
+            Err(_) => dir.to_path_buf(),
+        }
+    }
+}
+
+/// The home directory: `CLAUDE_SWITCHER_HOME` when it's set, the user's own
+/// otherwise.
+pub fn home_dir() -> Option<PathBuf> {
+    sandbox().or_else(dirs::home_dir)
+}
+
+/// Whether `CLAUDE_SWITCHER_HOME` keeps this run in a sandbox.
+pub fn is_sandboxed() -> bool {
+    sandbox().is_some()
+}
+
+fn sandbox() -> Option<PathBuf> {
+    let dir = PathBuf::from(std::env::var_os(HOME_ENV).filter(|dir| !dir.is_empty())?);
+    Some(std::path::absolute(&dir).unwrap_or(dir))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env<'a>(vars: &'a [(&str, &str)]) -> impl Fn(&str) -> Option<OsString> + 'a {
+        move |key| {
+            vars.iter()
+                .find(|(name, _)| *name == key)
+                .map(|(_, value)| OsString::from(value))
+        }
+    }
+
+    #[test]
+    fn test_unix_layout_under_home() {
+        let home = Path::new("/home/me");
+        let paths = Paths::resolve(home, false, env(&[("APPDATA", "/ignored")]));
+        assert_eq!(paths.claude_config_dir(None, None), home.join(".claude"));
+        assert_eq!(
+            paths.switcher_dir,
+            home.join(".local/share/claude-account-switcher")
+        );
+        assert_eq!(
+            paths.state_dir,
+            home.join(".local/state/claude-account-switcher")
+        );
+        assert_eq!(
+            paths.settings_file,
+            home.join(".config/claude-account-switcher/settings.toml")
+        );
+    }
+
+    #[test]
+    fn test_xdg_overrides() {
+        let home = Path::new("/home/me");
+        let paths = Paths::resolve(
+            home,
+            false,
+            env(&[
+                ("XDG_DATA_HOME", "/data"),
+                ("XDG_STATE_HOME", "/state"),
+                ("XDG_CONFIG_HOME", "relative/config"),
+            ]),
+        );
+        assert_eq!(
+            paths.switcher_dir,
+            Path::new("/data/claude-account-switcher")
+        );
+        assert_eq!(paths.state_dir, Path::new("/state/claude-account-switcher"));
+        // Relative paths don't count
+        assert_eq!(
+            paths.settings_file,
+            home.join(".config/claude-account-switcher/settings.toml")
+        );
+    }
+
+    #[test]
+    fn test_windows_layout_in_app_data() {
+        let home = Path::new("/Users/me");
+        let paths = Paths::resolve(
+            home,
+            true,
+            env(&[
+                ("APPDATA", "/roaming"),
+                ("LOCALAPPDATA", "/local"),
+                ("XDG_DATA_HOME", "/ignored"),
+            ]),
+        );
+        assert_eq!(paths.claude_config_dir(None, None), home.join(".claude"));
+        assert_eq!(
+            paths.switcher_dir,
+            Path::new("/local/claude-account-switcher/accounts")
+        );
+        assert_eq!(
+            paths.state_dir,
+            Path::new("/local/claude-account-switcher/state")
+        );
+        assert_eq!(
+            paths.settings_file,
+            Path::new("/roaming/claude-account-switcher/settings.toml")
+        );
+    }
+
+    #[test]
+    fn test_claude_config_dir_precedence() {
+        let home = Path::new("/home/me");
+        let (flag, configured) = (Path::new("/flag"), Path::new("~/claude-work"));
+
+        let paths = Paths::resolve(home, false, env(&[("CLAUDE_CONFIG_DIR", "/env")]));
+        assert_eq!(
+            paths.claude_config_dir(Some(flag), Some(configured)),
+            Path::new("/flag")
+        );
+        assert_eq!(
+            paths.claude_config_dir(None, Some(configured)),
+            Path::new("/env")
+        );
+
+        let paths = Paths::resolve(home, false, env(&[("CLAUDE_CONFIG_DIR", "")]));
+        assert_eq!(
+            paths.claude_config_dir(None, Some(configured)),
+            home.join("claude-work")
+        );
+        assert_eq!(paths.claude_config_dir(None, None), home.join(".claude"));
+
+        // An account used from the store isn't the live configuration
+        let paths = Paths::resolve(
+            home,
+            false,
+            env(&[(
+                "CLAUDE_CONFIG_DIR",
+                "/home/me/.local/share/claude-account-switcher/work",
+            )]),
+        );
+        assert_eq!(paths.claude_config_dir(None, None), home.join(".claude"));
+    }
+
+    #[test]
+    fn test_system_store() {
+        let home = Path::new("/home/me");
+        let paths = Paths::resolve(home, false, env(&[])).system(Path::new("/var/lib/cas"));
+        assert_eq!(paths.switcher_dir, Path::new("/var/lib/cas/accounts"));
+        assert_eq!(paths.accounts_file, Path::new("/var/lib/cas/accounts.json"));
+        assert_eq!(
+            paths.state_dir,
+            home.join(".local/state/claude-account-switcher/system")
+        );
+        assert_eq!(
+            paths.settings_file,
+            home.join(".config/claude-account-switcher/settings.toml")
+        );
+        // Nor is an account used from the system store
+        let paths = Paths::resolve(
+            home,
+            false,
+            env(&[("CLAUDE_CONFIG_DIR", "/var/lib/cas/accounts/team")]),
+        )
+        .system(Path::new("/var/lib/cas"));
+        assert_eq!(paths.claude_config_dir(None, None), home.join(".claude"));
+    }
+}
@@ -0,0 +1,256 @@
+use anyhow::{Context, Result};
+use std::borrow::Cow;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// Windows refuses longer paths without the `\\?\` prefix; directories
+// need room for an 8.3 file name below them
+const MAX_PATH: usize = 248;
+
+/// Probes whether `dir` lives on a case-insensitive filesystem (the default on
+/// macOS and Windows), where `Work` and `work` name the same directory.
+pub fn is_case_insensitive(dir: &Path) -> Result<bool> {
+    let probe = dir.join(format!(".CaseProbe-{}", std::process::id()));
+    let folded = dir.join(format!(".caseprobe-{}", std::process::id()));
+
+    fs::write(&probe, b"")
+        .with_context(|| format!("Failed to probe filesystem at {}", dir.display()))?;
+    let insensitive = folded.exists();
+    let _ = fs::remove_file(&probe);
+
+    Ok(insensitive)
+}
+
+/// Creates a symlink at `link` pointing to the directory `target`.
+pub fn symlink_dir(target: &Path, link: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    return std::os::unix::fs::symlink(target, link);
+    #[cfg(windows)]
+    return std::os::windows::fs::symlink_dir(target, link);
+    #[cfg(not(any(unix, windows)))]
+    return Err(io::ErrorKind::Unsupported.into());
+}
+
+/// Removes a directory symlink without touching what it points to.
+pub fn remove_symlink(link: &Path) -> io::Result<()> {
+    // Windows treats directory symlinks as directories
+    if cfg!(windows) {
+        fs::remove_dir(link)
+    } else {
+        fs::remove_file(link)
+    }
+}
+
+/// Writes a file readable only by the current user where the platform
+/// supports it.
+pub fn write_private(path: &Path, contents: &[u8]) -> io::Result<()> {
+    fs::write(path, contents)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
+/// Writes `contents` to a new file put in place of the one at `path`, rather
+/// than into that file: a live file may be a hard link to a snapshot's,
+/// which would otherwise change with it.
+pub fn write_replacing(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temporary = path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()));
+    fs::write(&temporary, contents)?;
+    if let Ok(metadata) = fs::metadata(path) {
+        let _ = fs::set_permissions(&temporary, metadata.permissions());
+    }
+    fs::rename(&temporary, path).inspect_err(|_| {
+        let _ = fs::remove_file(&temporary);
+    })
+}
+
+/// Creates `dst` as a copy-on-write clone of `src`, sharing its blocks. Fails
+/// where the filesystem (or platform) can't clone.
+pub fn clone_file(src: &Path, dst: &Path) -> io::Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::fd::AsRawFd;
+
+        let source = fs::File::open(src)?;
+        let clone = fs::File::create_new(dst)?;
+        // SAFETY: both descriptors are open for the duration of the call
+        let result = unsafe { libc::ioctl(clone.as_raw_fd(), libc::FICLONE, source.as_raw_fd()) };
+        if result == -1 {
+            let error = io::Error::last_os_error();
+            drop(clone);
+            let _ = fs::remove_file(dst);
+            return Err(error);
+        }
+        Ok(())
+    }
+    #[cfg(target_os = "macos")]
+    {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+
+        let src = CString::new(src.as_os_str().as_bytes())?;
+        let dst = CString::new(dst.as_os_str().as_bytes())?;
+        // SAFETY: both are valid NUL-terminated paths
+        if unsafe { libc::clonefile(src.as_ptr(), dst.as_ptr(), 0) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    {
+        let _ = (src, dst);
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+/// `path` in the `\\?\` form Windows needs past `MAX_PATH`, which the
+/// project histories under `~/.claude` can reach. Elsewhere, and for paths
+/// that are short, relative or already in that form, it's left as it is.
+pub fn long_path(path: &Path) -> Cow<'_, Path> {
+    if !cfg!(windows) {
+        return Cow::Borrowed(path);
+    }
+    extended_length(path).map_or(Cow::Borrowed(path), Cow::Owned)
+}
+
+fn extended_length(path: &Path) -> Option<PathBuf> {
+    let path = path.to_str()?;
+    if path.len() < MAX_PATH || path.starts_with(r"\\?\") {
+        return None;
+    }
+    // The prefix turns off the usual normalization, `/` included
+    let path = path.replace('/', "\\");
+    if let Some(share) = path.strip_prefix(r"\\") {
+        return Some(PathBuf::from(format!(r"\\?\UNC\{}", share)));
+    }
+    let drive = path.as_bytes();
+    if drive.len() > 2 && drive[0].is_ascii_alphabetic() && drive[1] == b':' && drive[2] == b'\\' {
+        return Some(PathBuf::from(format!(r"\\?\{}", path)));
+    }
+    None
+}
+
+/// Clears the read-only attribute Windows copies along with a file, which
+/// would otherwise stop it from being replaced or removed later.
+pub fn make_writable(path: &Path) -> io::Result<()> {
+    if !cfg!(windows) {
+        return Ok(());
+    }
+    let mut permissions = fs::metadata(path)?.permissions();
+    if permissions.readonly() {
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        fs::set_permissions(path, permissions)?;
+    }
+    Ok(())
+}
+
+/// Whether both paths resolve to the same existing location.
+pub fn same_location(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_case_probe_cleans_up() -> Result<()> {
+        let dir = TempDir::new()?;
+        is_case_insensitive(dir.path())?;
+
+        assert_eq!(fs::read_dir(dir.path())?.count(), 0);
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_case_sensitive_on_linux_tmp() -> Result<()> {
+        let dir = TempDir::new()?;
+        assert!(!is_case_insensitive(dir.path())?);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_dir_roundtrip() -> Result<()> {
+        let dir = TempDir::new()?;
+        let target = dir.path().join("target");
+        let link = dir.path().join("link");
+        fs::create_dir(&target)?;
+
+        symlink_dir(&target, &link)?;
+        assert!(link.is_symlink());
+        assert!(same_location(&link, &target));
+
+        remove_symlink(&link)?;
+        assert!(!link.exists());
+        assert!(target.is_dir());
+        Ok(())
+    }
+
+    #[test]
+    fn test_clone_file_leaves_nothing_behind_on_failure() -> Result<()> {
+        let dir = TempDir::new()?;
+        let source = dir.path().join("source");
+        let clone = dir.path().join("clone");
+        fs::write(&source, "data")?;
+
+        // Whether cloning works depends on the filesystem the tests run on
+        match clone_file(&source, &clone) {
+            Ok(()) => assert_eq!(fs::read_to_string(&clone)?, "data"),
+            Err(_) => assert!(!clone.exists()),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_extended_length_paths() {
+        let long = format!(r"C:\Users\me\.claude\projects\{}", "a".repeat(260));
+        assert_eq!(
+            extended_length(Path::new(&long)),
+            Some(PathBuf::from(format!(r"\\?\{}", long)))
+        );
+        let share = format!(r"\\server\home\{}", "a".repeat(260));
+        assert_eq!(
+            extended_length(Path::new(&share)),
+            Some(PathBuf::from(format!(
+                r"\\?\UNC\server\home\{}",
+                "a".repeat(260)
+            )))
+        );
+        assert_eq!(extended_length(Path::new(r"C:\Users\me\.claude")), None);
+        assert_eq!(extended_length(Path::new(&format!(r"\\?\{}", long))), None);
+        assert_eq!(extended_length(Path::new(&"a\\".repeat(200))), None);
+    }
+
+    #[test]
+    fn test_same_location_missing_paths() {
+        assert!(!same_location(
+            Path::new("/nonexistent/a"),
+            Path::new("/nonexistent/a")
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_private() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new()?;
+        let path = dir.path().join(".credentials.json");
+        write_private(&path, b"{}")?;
+
+        assert_eq!(fs::metadata(&path)?.permissions().mode() & 0o777, 0o600);
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+//! Presets: the switcher's own setup, without a single account in it, as
+//! one JSON file to carry to a new machine or hand to a teammate. That's the
+//! settings (the hooks among them), the aliases and tags, and the templates.
+//! No snapshot, login or credential goes in, nor a setting holding a secret
+//! or only making sense on the machine it was set on, such as a path.
+//!
+//! Importing lays the preset over what's there: its settings replace the
+//! ones of the same keys, and its aliases and tags go to the accounts of the
+//! same names, where there are such accounts. The hooks it sets are shown
+//! first, as they run on every save or switch from then on.
+
+use crate::settings::{self, SECRET_KEYS, Settings};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct Preset {
+    pub format_version: u32,
+    pub created_at: String,
+    #[serde(default)]
+    pub settings: Settings,
+    /// Each alias and the account it stands for
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, String>,
+    /// Each tagged account's tags
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, BTreeSet<String>>,
+    /// Each template's files, by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub templates: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl Preset {
+    pub fn new(created_at: String) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            created_at,
+            ..Default::default()
+        }
+    }
+
+    pub fn parse(contents: &str) -> Result<Self> {
+        let preset: Self = serde_json::from_str(contents).context("Failed to parse preset")?;
+        if preset.format_version > FORMAT_VERSION {
+            anyhow::bail!(
+                "Preset format version {} is newer than supported version {}",
+                preset.format_version,
+                FORMAT_VERSION
+            );
+        }
+        Ok(preset)
+    }
+}
+
+// Settings that are about this machine: where things are on it, and what
+// it offers
+const MACHINE_KEYS: &[&str] = &[
+    "claude-dir",
+    "system",
+    "system-dir",
+    "extra-paths",
+    "team-overlay",
+    "credentials",
+];
+
+/// `settings` as they go into a preset, or come out of one: without those
+/// about the machine they were set on, nor those holding secrets.
+pub fn shareable(settings: &Settings) -> Result<Settings> {
+    let mut table = toml::Table::try_from(settings).context("Failed to serialize settings")?;
+    for key in MACHINE_KEYS.iter().chain(SECRET_KEYS) {
+        settings::set_key(&mut table, key, None);
+    }
+    Settings::deserialize(table).context("Failed to serialize settings")
+}
+
+/// `local` with every setting `preset` sets laid over it, key by key within
+/// tables, and how many settings that was.
+pub fn merge_settings(local: &Settings, preset: &Settings) -> Result<(Settings, usize)> {
+    let mut merged = toml::Table::try_from(local).context("Failed to serialize settings")?;
+    let layer = toml::Table::try_from(preset).context("Failed to serialize settings")?;
+    let mut count = 0;
+    for (key, value) in layer {
+        match (merged.get_mut(&key), value) {
+            (Some(toml::Value::Table(merged)), toml::Value::Table(layer)) => {
+                count += layer.len();
+                merged.extend(layer);
+            }
+            (_, value) => {
+                count += value.as_table().map_or(1, toml::Table::len);
+                merged.insert(key, value);
+            }
+        }
+    }
+    let settings = Settings::deserialize(merged).context("Invalid settings in preset")?;
+    Ok((settings, count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::{HookSettings, SwitchMode};
+
+    #[test]
+    fn test_settings_are_laid_over_local_ones() -> Result<()> {
+        let local = Settings {
+            keep_versions: Some(2),
+            hooks: HookSettings {
+                pre_switch: Some("! pgrep -x claude".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let preset = Settings {
+            mode: SwitchMode::Symlink,
+            hooks: HookSettings {
+                post_switch: Some("tmux refresh-client -S".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let (merged, count) = merge_settings(&local, &preset)?;
+        assert_eq!(count, 2);
+        assert_eq!(merged.mode, SwitchMode::Symlink);
+        assert_eq!(merged.keep_versions, Some(2));
+        assert!(merged.hooks.pre_switch.is_some());
+        assert!(merged.hooks.post_switch.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_machine_settings_and_secrets_stay_out() -> Result<()> {
+        let settings = Settings {
+            keep_versions: Some(2),
+            notify: crate::settings::NotifySettings {
+                webhook: Some("https://hooks.slack.com/services/T0/B0/SECRET".to_string()),
+                desktop: true,
+                ..Default::default()
+            },
+            system: true,
+            extra_paths: Some(vec!["~/.claude.json".to_string()]),
+            ..Default::default()
+        };
+        let shared = shareable(&settings)?;
+        assert!(!shared.system);
+        assert_eq!(shared.extra_paths, None);
+        assert_eq!(shared.notify.webhook, None);
+        assert!(shared.notify.desktop);
+        assert_eq!(shared.keep_versions, Some(2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_newer_format_is_refused() {
+        let newer = r#"{"format_version": 99, "created_at": "2024-01-01T00:00:00Z"}"#;
+        assert!(Preset::parse(newer).is_err());
+        let current = r#"{"format_version": 1, "created_at": "2024-01-01T00:00:00Z"}"#;
+        assert_eq!(
+            Preset::parse(current).unwrap().settings,
+            Settings::default()
+        );
+    }
+}
@@ -0,0 +1,88 @@
+//! Profiles: an account together with the environment to use it with, such
+//! as a faster model, so one login can stand for several ways of working.
+//! `profile use` switches to the account and then prints the exports for the
+//! shell to `eval`, or writes them into the `env` of the live
+//! `settings.json`, which Claude Code reads as well.
+
+use crate::direct;
+use anyhow::Result;
+use clap_complete::Shell;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Profile {
+    pub account: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+}
+
+/// Splits `KEY=VALUE` into the variable and its value.
+pub fn parse_env(assignment: &str) -> Result<(String, String)> {
+    let Some((var, value)) = assignment.split_once('=') else {
+        anyhow::bail!("Expected KEY=VALUE, got '{}'", assignment);
+    };
+    let valid = var
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && var.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        anyhow::bail!("Invalid environment variable name: '{}'", var);
+    }
+    Ok((var.to_string(), value.to_string()))
+}
+
+impl Profile {
+    /// The lines that set the profile's variables in `shell`.
+    pub fn exports(&self, shell: Shell) -> Result<String> {
+        self.env
+            .iter()
+            .map(|(var, value)| direct::export(shell, var, value))
+            .collect()
+    }
+
+    /// The variables as `KEY=VALUE`, for showing the profile.
+    pub fn env_summary(&self) -> String {
+        self.env
+            .iter()
+            .map(|(var, value)| format!("{}={}", var, value))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_is_parsed_and_exported() -> Result<()> {
+        assert_eq!(
+            parse_env("ANTHROPIC_MODEL=claude-haiku")?,
+            ("ANTHROPIC_MODEL".to_string(), "claude-haiku".to_string())
+        );
+        assert_eq!(parse_env("EMPTY=")?.1, "");
+        assert_eq!(parse_env("A=b=c")?.1, "b=c");
+        assert!(parse_env("NO_VALUE").is_err());
+        assert!(parse_env("1ST=x").is_err());
+        assert!(parse_env("A B=x").is_err());
+
+        let profile = Profile {
+            account: "personal".to_string(),
+            env: BTreeMap::from([
+                ("MAX_THINKING_TOKENS".to_string(), "0".to_string()),
+                ("ANTHROPIC_MODEL".to_string(), "it's".to_string()),
+            ]),
+        };
+        assert_eq!(
+            profile.exports(Shell::Bash)?,
+            "export ANTHROPIC_MODEL='it'\\''s'\nexport MAX_THINKING_TOKENS='0'\n"
+        );
+        assert_eq!(
+            profile.env_summary(),
+            "ANTHROPIC_MODEL=it's MAX_THINKING_TOKENS=0"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+//! A one-line count on stderr for work that can take a while on a large
+//! store. It's only drawn on a terminal, so piped and JSON output stay as
+//! they are, and it's cleared again once the work is done.
+
+use crate::size;
+use crossterm::execute;
+use crossterm::style::Print;
+use crossterm::terminal::{Clear, ClearType};
+use std::io::{self, IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+// Redrawing for every small file would cost more than copying it
+const REDRAW_EVERY: Duration = Duration::from_millis(100);
+
+pub struct Progress {
+    label: String,
+    total: usize,
+    done: usize,
+    /// Bytes done and in all, when they're counted too
+    bytes: Option<(u64, u64)>,
+    shown: bool,
+    drawn_at: Option<Instant>,
+}
+
+impl Progress {
+    /// Counts up to `total` after `label`. With `quiet`, or when stderr
+    /// isn't a terminal, nothing is drawn.
+    pub fn new(label: String, total: usize, quiet: bool) -> Self {
+        let mut progress = Self {
+            label,
+            total,
+            done: 0,
+            bytes: None,
+            shown: !quiet && io::stderr().is_terminal(),
+            drawn_at: None,
+        };
+        progress.draw();
+        progress
+    }
+
+    /// Also counts bytes up to `total`, added with `advance_by`.
+    pub fn with_bytes(mut self, total: u64) -> Self {
+        self.bytes = Some((0, total));
+        self.drawn_at = None;
+        self.draw();
+        self
+    }
+
+    pub fn advance(&mut self) {
+        self.advance_by(0);
+    }
+
+    /// One more done, of `bytes` bytes.
+    pub fn advance_by(&mut self, bytes: u64) {
+        self.done = (self.done + 1).min(self.total);
+        if let Some((done, total)) = &mut self.bytes {
+            *done = (*done + bytes).min(*total);
+        }
+        self.draw();
+    }
+
+    /// The line as drawn, without the carriage return that rewrites it.
+    pub fn line(&self) -> String {
+        match self.bytes {
+            Some((done, total)) => format!(
+                "{} {}/{} ({} / {})",
+                self.label,
+                self.done,
+                self.total,
+                size::format_bytes(done),
+                size::format_bytes(total)
+            ),
+            None => format!("{} {}/{}", self.label, self.done, self.total),
+        }
+    }
+
+    fn draw(&mut self) {
+        if !self.shown {
+            return;
+        }
+        let finished = self.done == self.total;
+        if !finished && self.drawn_at.is_some_and(|at| at.elapsed() < REDRAW_EVERY) {
+            return;
+        }
+        self.drawn_at = Some(Instant::now());
+        // Cleared first, as the line can get shorter when sizes change unit
+        let _ = execute!(io::stderr(), Print('\r'), Clear(ClearType::CurrentLine));
+        eprint!("{}", self.line());
+        let _ = io::stderr().flush();
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if self.shown {
+            let _ = execute!(io::stderr(), Print('\r'), Clear(ClearType::CurrentLine));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_up_to_total() {
+        let mut progress = Progress::new("Measuring".to_string(), 2, true);
+        assert_eq!(progress.line(), "Measuring 0/2");
+        progress.advance();
+        progress.advance();
+        progress.advance();
+        assert_eq!(progress.line(), "Measuring 2/2");
+    }
+
+    #[test]
+    fn test_counts_bytes() {
+        let mut progress = Progress::new("Copying".to_string(), 2, true).with_bytes(3072);
+        assert_eq!(progress.line(), "Copying 0/2 (0 B / 3.0 KiB)");
+        progress.advance_by(1024);
+        assert_eq!(progress.line(), "Copying 1/2 (1.0 KiB / 3.0 KiB)");
+        progress.advance_by(4096);
+        assert_eq!(progress.line(), "Copying 2/2 (3.0 KiB / 3.0 KiB)");
+    }
+}
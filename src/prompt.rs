@@ -0,0 +1,206 @@
+use crate::encryption;
+use crate::error::AccountError;
+use crate::tr;
+use crate::ui::{self, Entry};
+use anyhow::Result;
+use std::collections::VecDeque;
+use std::io::{self, IsTerminal, Write};
+use std::sync::Mutex;
+
+/// Where the manager asks the user for input, so confirmations can be
+/// scripted by tests and embedders.
+pub trait Prompter: Send + Sync {
+    /// Asks a yes/no question, shown with its [`hint`]. An empty answer
+    /// takes `default`; anything else counts as yes only when it's `y` or
+    /// `yes`.
+    fn confirm(&self, question: &str, default: bool) -> Result<bool>;
+
+    /// Whether a question asked now would be answered. Confirmations that
+    /// only guard against a slip are skipped when it wouldn't, leaving the
+    /// command to do what it did before they were added.
+    fn can_ask(&self) -> bool;
+
+    /// Reads a secret such as a bundle passphrase.
+    fn passphrase(&self, prompt: &str) -> Result<String>;
+
+    /// Reads a line of free text.
+    fn input(&self, prompt: &str) -> Result<String>;
+
+    /// Lets the user choose one of `entries`; `None` means they backed out.
+    fn pick(&self, entries: &[Entry]) -> Result<Option<usize>>;
+}
+
+/// What a question with `default` is shown with, the default in capitals.
+pub fn hint(default: bool) -> &'static str {
+    match default {
+        true => "[Y/n]",
+        false => "[y/N]",
+    }
+}
+
+// The answer to a question with `default`
+fn answer(reply: &str, default: bool) -> bool {
+    match reply.trim() {
+        "" => default,
+        reply => reply.eq_ignore_ascii_case("y") || reply.eq_ignore_ascii_case("yes"),
+    }
+}
+
+/// Set to `1`, `StdinPrompter` takes answers piped into stdin too, for
+/// tests that drive the prompts
+pub const PIPED_ANSWERS_ENV: &str = "CLAUDE_SWITCHER_PIPED_ANSWERS";
+
+/// Prompts on stderr and reads answers from stdin. Confirmations need a
+/// terminal, so a script that didn't pass `--yes` fails instead of hanging
+/// or answering with whatever it pipes in, unless `PIPED_ANSWERS_ENV` says
+/// that's what it means to do.
+pub struct StdinPrompter;
+
+impl StdinPrompter {
+    fn ask(&self, prompt: &str) -> Result<String> {
+        eprint!("{}", prompt);
+        io::stderr().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        Ok(input.trim().to_string())
+    }
+}
+
+impl Prompter for StdinPrompter {
+    fn confirm(&self, question: &str, default: bool) -> Result<bool> {
+        if !self.can_ask() {
+            return Err(AccountError::ConfirmationRequired.into());
+        }
+        let reply = self.ask(&format!("{} {} ", question, hint(default)))?;
+        Ok(answer(&reply, default))
+    }
+
+    fn can_ask(&self) -> bool {
+        io::stdin().is_terminal() || std::env::var_os(PIPED_ANSWERS_ENV).is_some_and(|v| v == "1")
+    }
+
+    fn passphrase(&self, prompt: &str) -> Result<String> {
+        self.ask(prompt)
+    }
+
+    fn input(&self, prompt: &str) -> Result<String> {
+        self.ask(prompt)
+    }
+
+    fn pick(&self, entries: &[Entry]) -> Result<Option<usize>> {
+        if ui::is_interactive() {
+            ui::pick_interactive(entries)
+        } else {
+            ui::pick_numbered(entries, |prompt| self.ask(prompt))
+        }
+    }
+}
+
+/// For when stdin isn't the user's, as for the `daemon` and `mcp-serve`:
+/// nothing is asked, so confirmations fail and the store's passphrase has
+/// to come from the environment.
+pub struct Unattended;
+
+impl Prompter for Unattended {
+    fn confirm(&self, _question: &str, _default: bool) -> Result<bool> {
+        Err(AccountError::ConfirmationRequired.into())
+    }
+
+    fn can_ask(&self) -> bool {
+        false
+    }
+
+    fn passphrase(&self, _prompt: &str) -> Result<String> {
+        anyhow::bail!(tr!(
+            "passphrase-unattended",
+            var = encryption::PASSPHRASE_ENV
+        ))
+    }
+
+    fn input(&self, _prompt: &str) -> Result<String> {
+        Err(AccountError::ConfirmationRequired.into())
+    }
+
+    fn pick(&self, _entries: &[Entry]) -> Result<Option<usize>> {
+        Err(AccountError::ConfirmationRequired.into())
+    }
+}
+
+/// Answers prompts in order from a fixed list and fails once it runs out.
+pub struct ScriptedPrompter {
+    answers: Mutex<VecDeque<String>>,
+}
+
+impl ScriptedPrompter {
+    pub fn new<I, S>(answers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            answers: Mutex::new(answers.into_iter().map(Into::into).collect()),
+        }
+    }
+
+    fn next(&self, prompt: &str) -> Result<String> {
+        let mut answers = self
+            .answers
+            .lock()
+            .map_err(|_| anyhow::anyhow!("Prompt script is poisoned"))?;
+        answers
+            .pop_front()
+            .ok_or_else(|| anyhow::anyhow!("No scripted answer for prompt: {}", prompt.trim()))
+    }
+}
+
+impl Prompter for ScriptedPrompter {
+    fn confirm(&self, question: &str, default: bool) -> Result<bool> {
+        Ok(answer(&self.next(question)?, default))
+    }
+
+    /// While there are answers left.
+    fn can_ask(&self) -> bool {
+        self.answers.lock().is_ok_and(|answers| !answers.is_empty())
+    }
+
+    fn passphrase(&self, prompt: &str) -> Result<String> {
+        self.next(prompt)
+    }
+
+    fn input(&self, prompt: &str) -> Result<String> {
+        self.next(prompt)
+    }
+
+    /// Answers with a number or name, as in the numbered fallback.
+    fn pick(&self, entries: &[Entry]) -> Result<Option<usize>> {
+        ui::pick_numbered(entries, |prompt| self.next(prompt))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scripted_prompter_answers_in_order() -> Result<()> {
+        let prompter = ScriptedPrompter::new(["Y", "no", "secret", "", ""]);
+
+        assert!(prompter.confirm("Continue?", false)?);
+        assert!(!prompter.confirm("Continue?", true)?);
+        assert_eq!(prompter.passphrase("Passphrase: ")?, "secret");
+        // An empty answer takes the default
+        assert!(prompter.confirm("Continue?", true)?);
+        assert!(prompter.can_ask());
+        assert!(!prompter.confirm("Continue?", false)?);
+        assert!(!prompter.can_ask());
+        Ok(())
+    }
+
+    #[test]
+    fn test_scripted_prompter_runs_out() {
+        let prompter = ScriptedPrompter::new(Vec::<String>::new());
+        let err = prompter.confirm("Continue?", false).unwrap_err();
+        assert!(err.to_string().contains("Continue?"));
+    }
+}
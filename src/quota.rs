@@ -0,0 +1,47 @@
+//! Rate limits: when an account ran into one, recorded by `limit-hit`
+//! (usually from a hook), so `list` can show which accounts still have
+//! quota left.
+
+use chrono::{DateTime, Duration, Utc};
+
+/// How long a limit lasts when the hit doesn't say: Claude's usage limits
+/// reset on a five-hour window
+pub const DEFAULT_WINDOW_HOURS: i64 = 5;
+
+/// When a limit hit at `now` lifts: after `resets_in`, or a window later.
+pub fn lifts_at(now: DateTime<Utc>, resets_in: Option<Duration>) -> DateTime<Utc> {
+    now + resets_in.unwrap_or_else(|| Duration::hours(DEFAULT_WINDOW_HOURS))
+}
+
+/// When the limit recorded as `limited_until` lifts, while it still holds
+/// at `now`.
+pub fn active(limited_until: Option<&str>, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let until = DateTime::parse_from_rfc3339(limited_until?).ok()?;
+    Some(until.with_timezone(&Utc)).filter(|until| *until > now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limit_holds_until_it_lifts() {
+        let now = DateTime::parse_from_rfc3339("2024-01-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let until = lifts_at(now, None).to_rfc3339();
+        assert!(until.starts_with("2024-01-01T17:00:00"));
+
+        assert!(active(Some(&until), now).is_some());
+        assert!(active(Some(&until), now + Duration::hours(5)).is_none());
+        assert_eq!(
+            active(
+                Some(&lifts_at(now, Some(Duration::minutes(30))).to_rfc3339()),
+                now
+            ),
+            Some(now + Duration::minutes(30))
+        );
+        assert!(active(None, now).is_none());
+        assert!(active(Some("garbage"), now).is_none());
+    }
+}
@@ -0,0 +1,227 @@
+//! `export --redacted`: a snapshot with its secrets taken out, for sharing a
+//! setup (settings, MCP servers, `CLAUDE.md`) rather than an account.
+//!
+//! Files named like credentials (see [`audit`](crate::audit)) are left out
+//! altogether. In JSON, the values of fields named like secrets (`apiKey`,
+//! `accessToken`, an `env` entry such as `GITHUB_TOKEN`, an `Authorization`
+//! header, ...) are masked, and anywhere else an Anthropic key or token is.
+//! Files that aren't text go in as they are.
+
+use crate::audit;
+use serde_json::Value;
+use std::path::Path;
+
+/// What a secret is replaced with
+pub const MASK: &str = "<redacted>";
+
+const TOKEN_PREFIX: &str = "sk-ant-";
+// Lowercased, with `_` and `-` taken out
+const SECRET_KEYS: &[&str] = &[
+    "token",
+    "secret",
+    "password",
+    "passwd",
+    "apikey",
+    "authorization",
+    "credential",
+    "privatekey",
+];
+
+/// How much a redacted export left out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Redaction {
+    /// Files left out
+    pub dropped: usize,
+    /// Values masked in the files kept
+    pub masked: usize,
+}
+
+impl Redaction {
+    /// `contents` of the file at `path` (from the snapshot's root) as it's
+    /// exported, or `None` to leave it out.
+    pub fn file(&mut self, path: &Path, contents: Vec<u8>) -> Option<Vec<u8>> {
+        if audit::has_secret_name(path) {
+            self.dropped += 1;
+            return None;
+        }
+        let text = match String::from_utf8(contents) {
+            Ok(text) => text,
+            Err(e) => return Some(e.into_bytes()),
+        };
+
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+            && let Ok(mut value) = serde_json::from_str::<Value>(&text)
+        {
+            let masked = mask_value(&mut value, false);
+            if masked == 0 {
+                return Some(text.into_bytes());
+            }
+            self.masked += masked;
+            let mut redacted = serde_json::to_vec_pretty(&value).unwrap_or_default();
+            redacted.push(b'\n');
+            return Some(redacted);
+        }
+        let (redacted, masked) = mask_tokens(&text);
+        self.masked += masked;
+        Some(redacted.into_bytes())
+    }
+}
+
+/// `contents` of the file at `path` as `cat` shows it: JSON pretty-printed,
+/// and unless `reveal`, masked as in an export. A credentials file is shown
+/// with each of its values masked rather than left out.
+pub fn show(path: &Path, contents: Vec<u8>, reveal: bool) -> Vec<u8> {
+    let text = match String::from_utf8(contents) {
+        Ok(text) => text,
+        Err(e) => return e.into_bytes(),
+    };
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+        && let Ok(mut value) = serde_json::from_str::<Value>(&text)
+    {
+        if !reveal {
+            mask_value(&mut value, audit::has_secret_name(path));
+        }
+        let mut shown = serde_json::to_vec_pretty(&value).unwrap_or_default();
+        shown.push(b'\n');
+        return shown;
+    }
+    match reveal {
+        true => text.into_bytes(),
+        false => mask_tokens(&text).0.into_bytes(),
+    }
+}
+
+fn is_secret_key(key: &str) -> bool {
+    let key: String = key
+        .chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .collect::<String>()
+        .to_ascii_lowercase()
+        // A count of them, as in `CLAUDE_CODE_MAX_OUTPUT_TOKENS`
+        .replace("tokens", "");
+    // `GITHUB_KEY` and the like, but not `keybindings`
+    SECRET_KEYS.iter().any(|word| key.contains(word)) || key.ends_with("key")
+}
+
+// Masks the strings under secret keys, and tokens in the others. Returns how
+// many values were masked.
+fn mask_value(value: &mut Value, secret: bool) -> usize {
+    match value {
+        Value::String(text) if secret => {
+            *text = MASK.to_string();
+            1
+        }
+        Value::String(text) => {
+            let (masked_text, masked) = mask_tokens(text);
+            *text = masked_text;
+            masked
+        }
+        Value::Array(items) => items.iter_mut().map(|item| mask_value(item, secret)).sum(),
+        Value::Object(fields) => fields
+            .iter_mut()
+            .map(|(key, field)| mask_value(field, secret || is_secret_key(key)))
+            .sum(),
+        _ => 0,
+    }
+}
+
+// Every Anthropic key or token in `text` masked, and how many there were
+fn mask_tokens(text: &str) -> (String, usize) {
+    let mut masked = String::with_capacity(text.len());
+    let mut count = 0;
+    let mut rest = text;
+    while let Some(start) = rest.find(TOKEN_PREFIX) {
+        masked.push_str(&rest[..start]);
+        masked.push_str(MASK);
+        count += 1;
+        let token = &rest[start..];
+        let end = token
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+            .unwrap_or(token.len());
+        rest = &token[end..];
+    }
+    masked.push_str(rest);
+    (masked, count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_secrets_are_left_out_or_masked() {
+        let mut redaction = Redaction::default();
+        assert_eq!(
+            redaction.file(Path::new(".credentials.json"), b"{}".to_vec()),
+            None
+        );
+
+        let settings = json!({
+            "env": {"GITHUB_TOKEN": "ghp_x", "EDITOR": "vim", "OPENAI_KEY": "k"},
+            "mcpServers": {"api": {"headers": {"Authorization": "Bearer y"}}},
+            "keybindings": {"submit": "enter"},
+            "note": "uses sk-ant-api03-abc_DEF-1 for tests",
+        });
+        let redacted = redaction
+            .file(
+                Path::new("settings.json"),
+                settings.to_string().into_bytes(),
+            )
+            .unwrap();
+        let redacted: Value = serde_json::from_slice(&redacted).unwrap();
+        assert_eq!(
+            redacted,
+            json!({
+                "env": {"GITHUB_TOKEN": MASK, "EDITOR": "vim", "OPENAI_KEY": MASK},
+                "mcpServers": {"api": {"headers": {"Authorization": MASK}}},
+                "keybindings": {"submit": "enter"},
+                "note": "uses <redacted> for tests",
+            })
+        );
+
+        // Left as it was when there's nothing to mask
+        let plain = b"# Project\n\nUse tabs.\n".to_vec();
+        assert_eq!(
+            redaction.file(Path::new("CLAUDE.md"), plain.clone()),
+            Some(plain)
+        );
+        assert_eq!(
+            redaction.file(Path::new("notes.txt"), b"key: sk-ant-oat01-zz.".to_vec()),
+            Some(b"key: <redacted>.".to_vec())
+        );
+        assert_eq!(
+            redaction,
+            Redaction {
+                dropped: 1,
+                masked: 5
+            }
+        );
+    }
+
+    #[test]
+    fn test_show_pretty_prints_and_masks() {
+        let credentials = br#"{"claudeAiOauth":{"accessToken":"a","expiresAt":1}}"#.to_vec();
+        let shown = show(Path::new(".credentials.json"), credentials.clone(), false);
+        let shown: Value = serde_json::from_slice(&shown).unwrap();
+        assert_eq!(
+            shown,
+            json!({"claudeAiOauth": {"accessToken": MASK, "expiresAt": 1}})
+        );
+
+        let revealed = show(Path::new(".credentials.json"), credentials, true);
+        assert!(
+            String::from_utf8(revealed)
+                .unwrap()
+                .contains("\n  \"claudeAiOauth\"")
+        );
+        assert_eq!(
+            show(Path::new("CLAUDE.md"), b"sk-ant-api03-x".to_vec(), false),
+            MASK.as_bytes()
+        );
+    }
+}
@@ -0,0 +1,75 @@
+//! `remote`: a command for the switcher on another machine, run over SSH.
+//! The switcher has to be installed there; it's run with `--json`, and what
+//! it prints comes back as it's written, so a script here can read it.
+//!
+//! Nothing is kept about the host: ssh's own configuration (`~/.ssh/config`,
+//! the agent, known hosts) decides how to get there.
+
+use crate::runner;
+use anyhow::Result;
+
+/// What `--program` defaults to: the switcher as `cargo install` names it
+pub const DEFAULT_PROGRAM: &str = "claude-account-switcher";
+
+/// Runs `args` with the switcher `program` on `host`, waiting for it to
+/// finish, and returns its exit code (255 when ssh itself failed). With
+/// `assume_yes`, it answers yes to confirmations there too, as there's no
+/// terminal to ask on.
+pub fn run(host: &str, program: &str, args: &[String], assume_yes: bool) -> Result<i32> {
+    runner::run(&ssh_command(host, program, args, assume_yes), None)
+}
+
+/// The ssh command line for `run`. The remote command is one argument,
+/// quoted for the remote shell, so arguments with spaces stay whole.
+fn ssh_command(host: &str, program: &str, args: &[String], assume_yes: bool) -> Vec<String> {
+    let mut remote = vec![quote(program), "--json".to_string()];
+    if assume_yes {
+        remote.push("--yes".to_string());
+    }
+    remote.extend(args.iter().map(|arg| quote(arg)));
+
+    // No terminal: the output is JSON for this end to read, not a screen
+    [
+        "ssh".to_string(),
+        "-T".to_string(),
+        "--".to_string(),
+        host.to_string(),
+        remote.join(" "),
+    ]
+    .into()
+}
+
+/// `arg` in single quotes for a POSIX shell, the way ssh's remote end runs
+/// it, unless it's made only of characters that need none.
+fn quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./=@:%+,".contains(c));
+    match plain {
+        true => arg.to_string(),
+        false => format!("'{}'", arg.replace('\'', "'\\''")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssh_command_quotes_for_the_remote_shell() {
+        let args = ["note", "work", "Acme's card; rm -rf ~"].map(str::to_string);
+        assert_eq!(
+            ssh_command("me@devbox", DEFAULT_PROGRAM, &args, true),
+            [
+                "ssh",
+                "-T",
+                "--",
+                "me@devbox",
+                "claude-account-switcher --json --yes note work 'Acme'\\''s card; rm -rf ~'",
+            ]
+        );
+        assert_eq!(quote(""), "''");
+        assert_eq!(quote("client/dev"), "client/dev");
+    }
+}
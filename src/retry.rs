@@ -0,0 +1,229 @@
+use crate::tr;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+// Antivirus scanners and the search indexer can hold freshly written files for
+// well over a second, so sharing violations get extra patience
+const SHARING_VIOLATION_EXTRA_ATTEMPTS: u32 = 4;
+const MAX_DELAY: Duration = Duration::from_secs(1);
+
+/// Retries filesystem operations that fail with transient errors, such as a
+/// busy file on a network home directory.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    pub attempts: u32,
+    pub initial_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 4,
+            initial_delay: Duration::from_millis(50),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn run<T>(&self, mut op: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+        let mut delay = self.initial_delay;
+        let mut attempt = 1;
+
+        loop {
+            match op() {
+                Ok(value) => return Ok(value),
+                Err(e) if is_transient(&e) && attempt < self.attempts_for(&e) => {
+                    thread::sleep(delay);
+                    delay = (delay * 2).min(MAX_DELAY);
+                    attempt += 1;
+                }
+                Err(e) if attempt > 1 => {
+                    return Err(io::Error::new(
+                        e.kind(),
+                        GaveUp {
+                            source: e,
+                            attempts: attempt,
+                        },
+                    ));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn attempts_for(&self, error: &io::Error) -> u32 {
+        if is_sharing_violation(error) {
+            self.attempts + SHARING_VIOLATION_EXTRA_ATTEMPTS
+        } else {
+            self.attempts
+        }
+    }
+}
+
+#[derive(Debug)]
+struct GaveUp {
+    source: io::Error,
+    attempts: u32,
+}
+
+impl fmt::Display for GaveUp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (gave up after {} attempts)",
+            self.source, self.attempts
+        )
+    }
+}
+
+impl std::error::Error for GaveUp {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Runs `op` with the default retry policy.
+pub fn retry<T>(op: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    RetryPolicy::default().run(op)
+}
+
+/// Returns true when a file is held open by another process on Windows,
+/// typically an antivirus scanner or the search indexer.
+pub fn is_sharing_violation(error: &io::Error) -> bool {
+    cfg!(windows) && error.raw_os_error().is_some_and(is_sharing_violation_code)
+}
+
+/// Checks whether any error in the chain was caused by a sharing violation.
+pub fn caused_by_sharing_violation(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<io::Error>())
+        .any(is_sharing_violation)
+}
+
+pub fn sharing_violation_guidance(data_dir: &Path) -> String {
+    tr!("sharing-violation", dir = data_dir.display())
+}
+
+// ERROR_SHARING_VIOLATION and ERROR_LOCK_VIOLATION
+fn is_sharing_violation_code(code: i32) -> bool {
+    matches!(code, 32 | 33)
+}
+
+fn is_transient(error: &io::Error) -> bool {
+    if is_sharing_violation(error) {
+        return true;
+    }
+
+    if matches!(
+        error.kind(),
+        io::ErrorKind::Interrupted
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::TimedOut
+            | io::ErrorKind::ResourceBusy
+    ) {
+        return true;
+    }
+
+    #[cfg(unix)]
+    {
+        // As reported by NFS and FUSE mounts; EAGAIN isn't 11 everywhere
+        matches!(error.raw_os_error(), Some(libc::EBUSY) | Some(libc::EAGAIN))
+    }
+    #[cfg(not(unix))]
+    {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy {
+            attempts: 3,
+            initial_delay: Duration::from_millis(1),
+        }
+    }
+
+    #[test]
+    fn test_retry_succeeds_after_transient_errors() {
+        let mut calls = 0;
+        let result = fast_policy().run(|| {
+            calls += 1;
+            if calls < 3 {
+                Err(io::Error::from(io::ErrorKind::ResourceBusy))
+            } else {
+                Ok(calls)
+            }
+        });
+
+        assert_eq!(result.unwrap(), 3);
+    }
+
+    #[test]
+    fn test_retry_gives_up_with_clear_error() {
+        let mut calls = 0;
+        let result: io::Result<()> = fast_policy().run(|| {
+            calls += 1;
+            Err(io::Error::from(io::ErrorKind::ResourceBusy))
+        });
+
+        assert_eq!(calls, 3);
+        let err = result.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ResourceBusy);
+        assert!(err.to_string().contains("gave up after 3 attempts"));
+    }
+
+    #[test]
+    fn test_retry_does_not_retry_permanent_errors() {
+        let mut calls = 0;
+        let result: io::Result<()> = fast_policy().run(|| {
+            calls += 1;
+            Err(io::Error::from(io::ErrorKind::NotFound))
+        });
+
+        assert_eq!(calls, 1);
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_gave_up_error_keeps_source() {
+        let result: io::Result<()> =
+            fast_policy().run(|| Err(io::Error::from(io::ErrorKind::ResourceBusy)));
+        let error = anyhow::Error::from(result.unwrap_err());
+
+        let sources = error
+            .chain()
+            .filter_map(|cause| cause.downcast_ref::<io::Error>())
+            .count();
+        assert_eq!(sources, 2);
+        assert!(!caused_by_sharing_violation(&error));
+    }
+
+    #[test]
+    fn test_sharing_violation_codes() {
+        assert!(is_sharing_violation_code(32));
+        assert!(is_sharing_violation_code(33));
+        assert!(!is_sharing_violation_code(5));
+    }
+
+    #[test]
+    fn test_sharing_violation_guidance_mentions_data_dir() {
+        let guidance = sharing_violation_guidance(Path::new("C:\\Users\\me\\store"));
+        assert!(guidance.contains("antivirus"));
+        assert!(guidance.contains("Add-MpPreference -ExclusionPath \"C:\\Users\\me\\store\""));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_ebusy_is_transient() {
+        assert!(is_transient(&io::Error::from_raw_os_error(libc::EBUSY)));
+        assert!(is_transient(&io::Error::from_raw_os_error(libc::EAGAIN)));
+        assert!(!is_transient(&io::Error::from_raw_os_error(libc::ENOENT)));
+    }
+}
@@ -0,0 +1,163 @@
+//! `run`: one command under another account. While it runs this process only
+//! waits, so it's still there to switch back when the command ends: Ctrl-C
+//! reaches the command through the terminal, and termination signals sent to
+//! this process are passed on to it.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::process::{Command, ExitStatus};
+
+/// Runs `command` (a program and its arguments) to completion and returns
+/// its exit code. With `claude_dir`, the command gets it as
+/// `CLAUDE_CONFIG_DIR`, so Claude Code uses the directory that was switched.
+pub fn run(command: &[String], claude_dir: Option<&Path>) -> Result<i32> {
+    let (program, args) = command.split_first().context("No command to run")?;
+
+    // Installed before the command starts so no signal slips in between;
+    // the command itself starts with the default handlers
+    let _signals = signals::install();
+    let mut child = Command::new(program);
+    child.args(args);
+    if let Some(dir) = claude_dir {
+        child.env(crate::direct::VAR, dir);
+    }
+    let mut child = child
+        .spawn()
+        .with_context(|| format!("Failed to run {}", program))?;
+    signals::forward_to(child.id());
+
+    let status = child.wait().context("Failed to wait for the command")?;
+    Ok(exit_code(status))
+}
+
+/// The code to exit with: the command's own, or the shell convention of
+/// 128 plus the signal number when a signal ended it.
+fn exit_code(status: ExitStatus) -> i32 {
+    if let Some(code) = status.code() {
+        return code;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    1
+}
+
+#[cfg(unix)]
+mod signals {
+    use std::sync::atomic::{AtomicI32, Ordering};
+
+    static CHILD: AtomicI32 = AtomicI32::new(0);
+
+    // The terminal sends these to the whole foreground process group, so the
+    // command already has them
+    const FROM_TERMINAL: [libc::c_int; 2] = [libc::SIGINT, libc::SIGQUIT];
+    // These may be sent to this process alone
+    const FORWARDED: [libc::c_int; 2] = [libc::SIGTERM, libc::SIGHUP];
+
+    extern "C" fn ignore(_: libc::c_int) {}
+
+    extern "C" fn forward(signal: libc::c_int) {
+        let child = CHILD.load(Ordering::SeqCst);
+        if child > 0 {
+            // SAFETY: kill is async-signal-safe
+            unsafe { libc::kill(child, signal) };
+        }
+    }
+
+    /// Puts the previous handlers back when dropped.
+    pub struct Handlers {
+        previous: Vec<(libc::c_int, libc::sigaction)>,
+    }
+
+    /// Handlers rather than SIG_IGN, because ignored signals would stay
+    /// ignored in the command while handled ones are reset when it starts.
+    pub fn install() -> Handlers {
+        let mut previous = Vec::new();
+        let handlers = FROM_TERMINAL
+            .map(|signal| (signal, ignore as extern "C" fn(libc::c_int)))
+            .into_iter()
+            .chain(FORWARDED.map(|signal| (signal, forward as extern "C" fn(libc::c_int))));
+        for (signal, handler) in handlers {
+            // SAFETY: sigaction is plain data, and the handlers only touch an
+            // atomic and call kill
+            unsafe {
+                let mut action: libc::sigaction = std::mem::zeroed();
+                action.sa_sigaction = handler as libc::sighandler_t;
+                action.sa_flags = libc::SA_RESTART;
+                libc::sigemptyset(&mut action.sa_mask);
+
+                let mut old: libc::sigaction = std::mem::zeroed();
+                if libc::sigaction(signal, &action, &mut old) == 0 {
+                    previous.push((signal, old));
+                }
+            }
+        }
+        Handlers { previous }
+    }
+
+    pub fn forward_to(pid: u32) {
+        CHILD.store(pid as i32, Ordering::SeqCst);
+    }
+
+    impl Drop for Handlers {
+        fn drop(&mut self) {
+            CHILD.store(0, Ordering::SeqCst);
+            for (signal, old) in &self.previous {
+                // SAFETY: `old` came from sigaction
+                unsafe { libc::sigaction(*signal, old, std::ptr::null_mut()) };
+            }
+        }
+    }
+}
+
+// Windows stops every process on the console at Ctrl-C, this one included;
+// there the switch back only happens when the command exits by itself
+#[cfg(not(unix))]
+mod signals {
+    pub struct Handlers;
+
+    pub fn install() -> Handlers {
+        Handlers
+    }
+
+    pub fn forward_to(_pid: u32) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_exit_code_is_passed_through() -> Result<()> {
+        assert_eq!(run(&command(&["sh", "-c", "exit 3"]), None)?, 3);
+        assert_eq!(
+            run(&command(&["sh", "-c", "kill -TERM $$"]), None)?,
+            128 + 15
+        );
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_claude_dir_is_passed_on() -> Result<()> {
+        let check = command(&["sh", "-c", "test \"$CLAUDE_CONFIG_DIR\" = /work/claude"]);
+        assert_eq!(run(&check, Some(Path::new("/work/claude")))?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_missing_program_is_an_error() {
+        let error = run(&command(&["cas-no-such-program"]), None).unwrap_err();
+        assert!(error.to_string().contains("cas-no-such-program"));
+        assert!(run(&[], None).is_err());
+    }
+}
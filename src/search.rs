@@ -0,0 +1,136 @@
+use crate::{claude, store};
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// Transcripts and caches dwarf the settings files and aren't worth scanning
+const SKIPPED_DIRS: &[&str] = &["projects", "cache", "statsig", "shell-snapshots"];
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Match {
+    pub path: PathBuf,
+    pub line_number: usize,
+    pub line: String,
+}
+
+/// Searches settings files below `dir` for lines containing `pattern`.
+/// Credential files are never read.
+pub fn search_dir(dir: &Path, pattern: &str, ignore_case: bool) -> Result<Vec<Match>> {
+    search_dir_with(dir, pattern, ignore_case, &[], |path| Ok(fs::read(path)?))
+}
+
+/// Like [`search_dir`], but reads each file through `read`, e.g. to decrypt
+/// snapshots stored encrypted, and leaves out the `nested` directories, such
+/// as the snapshots of accounts nested in this one.
+pub fn search_dir_with(
+    dir: &Path,
+    pattern: &str,
+    ignore_case: bool,
+    nested: &[PathBuf],
+    read: impl Fn(&Path) -> Result<Vec<u8>>,
+) -> Result<Vec<Match>> {
+    let needle = if ignore_case {
+        pattern.to_lowercase()
+    } else {
+        pattern.to_string()
+    };
+
+    let mut matches = Vec::new();
+    for entry in store::walk_dir(dir)
+        .skip_dirs(SKIPPED_DIRS)
+        .skip_dirs(nested)
+    {
+        let entry = entry?;
+        if claude::is_credential_file(&entry.relative_path) || entry.size > MAX_FILE_SIZE {
+            continue;
+        }
+
+        // Binary and non-UTF-8 files can't contain settings we care about
+        let Ok(contents) = String::from_utf8(read(&entry.path)?) else {
+            continue;
+        };
+
+        for (index, line) in contents.lines().enumerate() {
+            let found = if ignore_case {
+                line.to_lowercase().contains(&needle)
+            } else {
+                line.contains(&needle)
+            };
+            if found {
+                matches.push(Match {
+                    path: entry.relative_path.clone(),
+                    line_number: index + 1,
+                    line: line.trim().to_string(),
+                });
+            }
+        }
+    }
+
+    matches.sort_by(|a, b| (&a.path, a.line_number).cmp(&(&b.path, b.line_number)));
+    Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn create_snapshot() -> Result<TempDir> {
+        let dir = TempDir::new()?;
+        fs::write(
+            dir.path().join("settings.json"),
+            "{\n  \"model\": \"opus\",\n  \"mcpServers\": {\"github\": {}}\n}",
+        )?;
+        fs::write(
+            dir.path().join(".credentials.json"),
+            r#"{"token": "github-secret"}"#,
+        )?;
+        fs::create_dir_all(dir.path().join("projects/foo"))?;
+        fs::write(dir.path().join("projects/foo/log.jsonl"), "github")?;
+        fs::create_dir_all(dir.path().join("agents"))?;
+        fs::write(dir.path().join("agents/reviewer.md"), "Uses GitHub")?;
+        Ok(dir)
+    }
+
+    #[test]
+    fn test_search_skips_credentials_and_transcripts() -> Result<()> {
+        let dir = create_snapshot()?;
+        let matches = search_dir(dir.path(), "github", false)?;
+
+        assert_eq!(
+            matches,
+            vec![Match {
+                path: PathBuf::from("settings.json"),
+                line_number: 3,
+                line: "\"mcpServers\": {\"github\": {}}".to_string(),
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_ignore_case() -> Result<()> {
+        let dir = create_snapshot()?;
+        let matches = search_dir(dir.path(), "GITHUB", true)?;
+
+        let paths: Vec<_> = matches.iter().map(|m| m.path.clone()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("agents/reviewer.md"),
+                PathBuf::from("settings.json")
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_skips_binary_files() -> Result<()> {
+        let dir = TempDir::new()?;
+        fs::write(dir.path().join("blob.bin"), [0xff, 0xfe, b'x'])?;
+
+        assert!(search_dir(dir.path(), "x", false)?.is_empty());
+        Ok(())
+    }
+}
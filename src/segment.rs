@@ -0,0 +1,74 @@
+//! `prompt`: the active account for a shell prompt (PS1, starship and the
+//! like). Prompts run it before every command, so it reads just the settings
+//! and accounts files, creates and writes nothing, and prints nothing at all
+//! when there's no active account or anything goes wrong.
+
+use crate::config::{AccountIdentity, AccountsConfig};
+use crate::system;
+
+pub const DEFAULT_TEMPLATE: &str = "{name}";
+
+pub fn run(template: &str) {
+    let Some(contents) = system::accounts_contents() else {
+        return;
+    };
+    if let Some(segment) = segment(&contents, template) {
+        println!("{}", segment);
+    }
+}
+
+/// The prompt text for the accounts file `contents`, or `None` when no
+/// account is active.
+pub fn segment(contents: &str, template: &str) -> Option<String> {
+    let config: AccountsConfig = serde_json::from_str(contents).ok()?;
+    let name = config.current.as_deref()?;
+    let identity = config
+        .get_account(name)
+        .and_then(|meta| meta.identity.clone())
+        .unwrap_or_default();
+    Some(render(template, name, &identity))
+}
+
+/// Fills in `{name}`, `{email}`, `{organization}` and `{plan}`. Details that
+/// aren't known are left empty.
+pub fn render(template: &str, name: &str, identity: &AccountIdentity) -> String {
+    let field = |value: &Option<String>| value.clone().unwrap_or_default();
+    template
+        .replace("{name}", name)
+        .replace("{email}", &field(&identity.email))
+        .replace("{organization}", &field(&identity.organization))
+        .replace("{plan}", &field(&identity.plan))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segment_renders_the_current_account() {
+        let contents = r#"{
+            "current": "work",
+            "accounts": {
+                "work": {
+                    "saved_at": "2024-01-01T00:00:00+00:00",
+                    "path": "/store/work",
+                    "identity": {"email": "dev@example.com", "plan": "max"}
+                }
+            }
+        }"#;
+        assert_eq!(segment(contents, DEFAULT_TEMPLATE).as_deref(), Some("work"));
+        assert_eq!(
+            segment(contents, "{name}:{plan} [{organization}]").as_deref(),
+            Some("work:max []")
+        );
+    }
+
+    #[test]
+    fn test_segment_is_empty_without_an_active_account() {
+        assert_eq!(
+            segment(r#"{"current": null, "accounts": {}}"#, "{name}"),
+            None
+        );
+        assert_eq!(segment("not json", "{name}"), None);
+    }
+}
@@ -0,0 +1,440 @@
+//! `service`: running the daemon and scheduled backups under the user's
+//! service manager, so they start with the session instead of by hand.
+//!
+//! That's systemd on Linux, with a user unit in `~/.config/systemd/user`
+//! (and a timer for the backups), and launchd on macOS, with an agent in
+//! `~/Library/LaunchAgents`. Each runs this binary from where it was when
+//! installed, so it's worth installing again after moving it. The XDG
+//! directories set at install time are written into the unit too, for a
+//! store that isn't in the default place.
+
+use crate::tr;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const APP: &str = "claude-account-switcher";
+/// Passed on to what's installed, so it finds the same store
+pub const ENV_VARS: &[&str] = &["XDG_DATA_HOME", "XDG_STATE_HOME", "XDG_CONFIG_HOME"];
+
+#[derive(ValueEnum, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Schedule {
+    Hourly,
+    Daily,
+    Weekly,
+}
+
+/// Something that can be installed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Job {
+    /// `daemon run`, kept running
+    Daemon,
+    /// `backup auto`, on a schedule
+    Backup { schedule: Schedule, keep: u64 },
+}
+
+/// Each kind of job, for finding what's installed: the schedule makes no
+/// difference to which files they are
+pub const ALL: [Job; 2] = [
+    Job::Daemon,
+    Job::Backup {
+        schedule: Schedule::Daily,
+        keep: 7,
+    },
+];
+
+impl Job {
+    pub fn name(self) -> &'static str {
+        match self {
+            Job::Daemon => "daemon",
+            Job::Backup { .. } => "backup",
+        }
+    }
+
+    fn args(self) -> Vec<String> {
+        match self {
+            Job::Daemon => vec!["daemon".to_string(), "run".to_string()],
+            Job::Backup { keep, .. } => vec![
+                "backup".to_string(),
+                "auto".to_string(),
+                "--keep".to_string(),
+                keep.to_string(),
+            ],
+        }
+    }
+}
+
+/// What every job runs with.
+pub struct Invocation {
+    pub exe: PathBuf,
+    pub claude_dir: PathBuf,
+    pub env: Vec<(String, String)>,
+}
+
+impl Invocation {
+    fn argv(&self, job: Job) -> Vec<String> {
+        let mut argv = vec![self.exe.display().to_string()];
+        argv.extend(job.args());
+        argv.push("--claude-dir".to_string());
+        argv.push(self.claude_dir.display().to_string());
+        argv
+    }
+}
+
+/// A file to install.
+#[derive(Debug, PartialEq, Eq)]
+pub struct UnitFile {
+    pub name: String,
+    pub contents: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum System {
+    Systemd,
+    Launchd,
+}
+
+impl System {
+    /// The service manager of this platform.
+    pub fn current() -> Result<Self> {
+        if cfg!(target_os = "macos") {
+            Ok(System::Launchd)
+        } else if cfg!(target_os = "linux") {
+            Ok(System::Systemd)
+        } else {
+            anyhow::bail!(tr!("service-unsupported"))
+        }
+    }
+
+    /// Where its user services go.
+    pub fn dir(self, home: &Path) -> PathBuf {
+        match self {
+            System::Systemd => std::env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .filter(|dir| dir.is_absolute())
+                .unwrap_or_else(|| home.join(".config"))
+                .join("systemd")
+                .join("user"),
+            System::Launchd => home.join("Library").join("LaunchAgents"),
+        }
+    }
+
+    // What's started: the timer of a scheduled job under systemd
+    fn unit(self, job: Job) -> String {
+        match (self, job) {
+            (System::Systemd, Job::Daemon) => format!("{}-daemon.service", APP),
+            (System::Systemd, Job::Backup { .. }) => format!("{}-backup.timer", APP),
+            (System::Launchd, job) => format!("{}.plist", label(job)),
+        }
+    }
+
+    /// The names of the files `job` is made of, installed or not.
+    pub fn file_names(self, job: Job) -> Vec<String> {
+        match (self, job) {
+            (System::Systemd, Job::Backup { .. }) => vec![
+                format!("{}-backup.service", APP),
+                format!("{}-backup.timer", APP),
+            ],
+            _ => vec![self.unit(job)],
+        }
+    }
+
+    /// The files that run `job` as `invocation`.
+    pub fn files(self, job: Job, invocation: &Invocation) -> Vec<UnitFile> {
+        let names = self.file_names(job);
+        let contents = match self {
+            System::Systemd => systemd_units(job, invocation),
+            System::Launchd => vec![launchd_plist(job, invocation)],
+        };
+        names
+            .into_iter()
+            .zip(contents)
+            .map(|(name, contents)| UnitFile { name, contents })
+            .collect()
+    }
+
+    /// Starts `job` now and with every session, once its files are in `dir`.
+    pub fn enable(self, dir: &Path, job: Job) -> Result<()> {
+        match self {
+            System::Systemd => {
+                run("systemctl", ["--user", "daemon-reload"])?;
+                run("systemctl", ["--user", "enable", "--now", &self.unit(job)])
+            }
+            System::Launchd => {
+                let plist = dir.join(self.unit(job));
+                // Replacing one that's loaded needs it unloaded first
+                let _ = quiet("launchctl", [OsStr::new("unload"), plist.as_os_str()]);
+                run(
+                    "launchctl",
+                    [OsStr::new("load"), OsStr::new("-w"), plist.as_os_str()],
+                )
+            }
+        }
+    }
+
+    /// Stops `job` and keeps it from starting again, before its files go.
+    pub fn disable(self, dir: &Path, job: Job) -> Result<()> {
+        match self {
+            System::Systemd => run("systemctl", ["--user", "disable", "--now", &self.unit(job)]),
+            System::Launchd => {
+                let plist = dir.join(self.unit(job));
+                run(
+                    "launchctl",
+                    [OsStr::new("unload"), OsStr::new("-w"), plist.as_os_str()],
+                )
+            }
+        }
+    }
+
+    /// Called after files were removed.
+    pub fn reload(self) -> Result<()> {
+        match self {
+            System::Systemd => run("systemctl", ["--user", "daemon-reload"]),
+            System::Launchd => Ok(()),
+        }
+    }
+
+    /// Whether `job` is running (or, for a timer, waiting); `None` when the
+    /// service manager couldn't be asked.
+    pub fn is_active(self, job: Job) -> Option<bool> {
+        match self {
+            System::Systemd => quiet(
+                "systemctl",
+                ["--user", "is-active", "--quiet", &self.unit(job)],
+            ),
+            System::Launchd => quiet("launchctl", ["list", &label(job)]),
+        }
+    }
+}
+
+// The launchd label, which names the plist too
+fn label(job: Job) -> String {
+    format!("{}.{}", APP, job.name())
+}
+
+fn systemd_units(job: Job, invocation: &Invocation) -> Vec<String> {
+    let exec = invocation
+        .argv(job)
+        .iter()
+        .map(|arg| systemd_quote(arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let env: String = invocation
+        .env
+        .iter()
+        .map(|(key, value)| {
+            format!(
+                "Environment={}\n",
+                systemd_quote(&format!("{}={}", key, value))
+            )
+        })
+        .collect();
+
+    match job {
+        Job::Daemon => vec![format!(
+            "[Unit]\nDescription=Claude account switcher daemon\n\n\
+             [Service]\nExecStart={}\n{}Restart=on-failure\n\n\
+             [Install]\nWantedBy=default.target\n",
+            exec, env
+        )],
+        Job::Backup { schedule, .. } => vec![
+            format!(
+                "[Unit]\nDescription=Claude account switcher backup\n\n\
+                 [Service]\nType=oneshot\nExecStart={}\n{}",
+                exec, env
+            ),
+            format!(
+                "[Unit]\nDescription=Claude account switcher backup, {}\n\n\
+                 [Timer]\nOnCalendar={}\nPersistent=true\n\n\
+                 [Install]\nWantedBy=timers.target\n",
+                schedule_name(schedule),
+                schedule_name(schedule)
+            ),
+        ],
+    }
+}
+
+fn schedule_name(schedule: Schedule) -> &'static str {
+    match schedule {
+        Schedule::Hourly => "hourly",
+        Schedule::Daily => "daily",
+        Schedule::Weekly => "weekly",
+    }
+}
+
+// Quoted for systemd, which takes `%` as a specifier
+fn systemd_quote(arg: &str) -> String {
+    let escaped = arg
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%");
+    format!("\"{}\"", escaped)
+}
+
+fn launchd_plist(job: Job, invocation: &Invocation) -> String {
+    let string = |value: &str| format!("<string>{}</string>", xml_escape(value));
+    let mut plist = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \
+         \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+         <plist version=\"1.0\">\n<dict>\n",
+    );
+    plist.push_str(&format!("  <key>Label</key>\n  {}\n", string(&label(job))));
+    plist.push_str("  <key>ProgramArguments</key>\n  <array>\n");
+    for arg in invocation.argv(job) {
+        plist.push_str(&format!("    {}\n", string(&arg)));
+    }
+    plist.push_str("  </array>\n");
+    if !invocation.env.is_empty() {
+        plist.push_str("  <key>EnvironmentVariables</key>\n  <dict>\n");
+        for (key, value) in &invocation.env {
+            plist.push_str(&format!(
+                "    <key>{}</key>\n    {}\n",
+                xml_escape(key),
+                string(value)
+            ));
+        }
+        plist.push_str("  </dict>\n");
+    }
+    match job {
+        // Restarted only when it fails, as under systemd: `daemon stop`
+        // shuts it down cleanly and it stays down
+        Job::Daemon => plist.push_str(
+            "  <key>RunAtLoad</key>\n  <true/>\n  <key>KeepAlive</key>\n  <dict>\n    \
+             <key>SuccessfulExit</key>\n    <false/>\n  </dict>\n",
+        ),
+        Job::Backup { schedule, .. } => {
+            // On the hour; daily at 03:00, weekly on Sundays
+            let interval = match schedule {
+                Schedule::Hourly => vec![("Minute", 0)],
+                Schedule::Daily => vec![("Hour", 3), ("Minute", 0)],
+                Schedule::Weekly => vec![("Weekday", 0), ("Hour", 3), ("Minute", 0)],
+            };
+            plist.push_str("  <key>StartCalendarInterval</key>\n  <dict>\n");
+            for (key, value) in interval {
+                plist.push_str(&format!(
+                    "    <key>{}</key>\n    <integer>{}</integer>\n",
+                    key, value
+                ));
+            }
+            plist.push_str("  </dict>\n");
+        }
+    }
+    plist.push_str("</dict>\n</plist>\n");
+    plist
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn run<I, S>(program: &str, args: I) -> Result<()>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let mut command = Command::new(program);
+    command.args(args);
+    let shown = std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|arg| arg.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let status = command
+        .status()
+        .with_context(|| tr!("service-command-failed", command = shown))?;
+    if !status.success() {
+        anyhow::bail!(tr!("service-command-failed", command = shown));
+    }
+    Ok(())
+}
+
+// Whether the command succeeded, with its output kept quiet
+fn quiet<I, S>(program: &str, args: I) -> Option<bool>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    Command::new(program)
+        .args(args)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .ok()
+        .map(|status| status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invocation() -> Invocation {
+        Invocation {
+            exe: PathBuf::from("/opt/cas/claude-account-switcher"),
+            claude_dir: PathBuf::from("/home/me/.claude"),
+            env: vec![("XDG_DATA_HOME".to_string(), "/data/100%".to_string())],
+        }
+    }
+
+    #[test]
+    fn test_systemd_units() {
+        let backup = Job::Backup {
+            schedule: Schedule::Daily,
+            keep: 5,
+        };
+        let files = System::Systemd.files(backup, &invocation());
+        let names: Vec<_> = files.iter().map(|file| file.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "claude-account-switcher-backup.service",
+                "claude-account-switcher-backup.timer"
+            ]
+        );
+        assert!(files[0].contents.contains(
+            "ExecStart=\"/opt/cas/claude-account-switcher\" \"backup\" \"auto\" \"--keep\" \"5\" \
+             \"--claude-dir\" \"/home/me/.claude\"\n"
+        ));
+        assert!(
+            files[0]
+                .contents
+                .contains("Environment=\"XDG_DATA_HOME=/data/100%%\"\n")
+        );
+        assert!(files[1].contents.contains("OnCalendar=daily\n"));
+
+        let daemon = System::Systemd.files(Job::Daemon, &invocation());
+        assert_eq!(daemon.len(), 1);
+        assert!(daemon[0].contents.contains("\"daemon\" \"run\""));
+        assert!(daemon[0].contents.contains("WantedBy=default.target"));
+    }
+
+    #[test]
+    fn test_launchd_plist() {
+        let backup = Job::Backup {
+            schedule: Schedule::Weekly,
+            keep: 7,
+        };
+        let files = System::Launchd.files(backup, &invocation());
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name, "claude-account-switcher.backup.plist");
+        let plist = &files[0].contents;
+        assert!(plist.contains("<string>claude-account-switcher.backup</string>"));
+        assert!(plist.contains("    <string>--keep</string>\n    <string>7</string>\n"));
+        assert!(plist.contains("<key>Weekday</key>\n    <integer>0</integer>"));
+        assert!(plist.contains("<key>XDG_DATA_HOME</key>\n    <string>/data/100%</string>"));
+        assert!(!plist.contains("KeepAlive"));
+
+        let daemon = System::Launchd.files(Job::Daemon, &invocation());
+        assert!(daemon[0].contents.contains(
+            "<key>KeepAlive</key>\n  <dict>\n    <key>SuccessfulExit</key>\n    <false/>\n  </dict>\n"
+        ));
+    }
+}
@@ -0,0 +1,151 @@
+//! Terminals using an account straight from the store, through `env` or
+//! `shell`, so `sessions` can list them and a switch to one of their
+//! accounts can say it's in use.
+//!
+//! A `shell` is known by the switcher process waiting for it, and removed
+//! when it's left. An `env` is known by the terminal's session (its login
+//! shell) on Unix; it stays until the terminal closes. Entries whose process
+//! is gone are dropped whenever the list is read.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+pub const FILE: &str = "sessions.json";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionKind {
+    Env,
+    Shell,
+}
+
+impl fmt::Display for SessionKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad(match self {
+            Self::Env => "env",
+            Self::Shell => "shell",
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Session {
+    pub account: String,
+    pub kind: SessionKind,
+    /// The process the session lasts as long as
+    pub pid: u32,
+    pub started_at: DateTime<Utc>,
+}
+
+/// The sessions whose process is still running.
+pub fn live(path: &Path) -> Result<Vec<Session>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let contents = fs::read_to_string(path).context("Failed to read sessions file")?;
+    let sessions: Vec<Session> =
+        serde_json::from_str(&contents).context("Failed to parse sessions file")?;
+    Ok(sessions
+        .into_iter()
+        .filter(|session| alive(session.pid))
+        .collect())
+}
+
+fn save(path: &Path, sessions: &[Session]) -> Result<()> {
+    let json = serde_json::to_string_pretty(sessions).context("Failed to serialize sessions")?;
+    fs::write(path, json).context("Failed to write sessions file")
+}
+
+/// Records `session`, in place of one the same process had before.
+pub fn register(path: &Path, session: Session) -> Result<()> {
+    let mut sessions = live(path)?;
+    sessions.retain(|known| known.pid != session.pid);
+    sessions.push(session);
+    save(path, &sessions)
+}
+
+/// Forgets the session of `pid`.
+pub fn unregister(path: &Path, pid: u32) -> Result<()> {
+    let mut sessions = live(path)?;
+    sessions.retain(|known| known.pid != pid);
+    save(path, &sessions)
+}
+
+/// The process that stands for this terminal: the leader of its session,
+/// which is the shell the terminal started. Unknown outside Unix.
+pub fn terminal_id() -> Option<u32> {
+    #[cfg(unix)]
+    {
+        // SAFETY: getsid only reads the calling process's session
+        let sid = unsafe { libc::getsid(0) };
+        u32::try_from(sid).ok().filter(|sid| *sid > 0)
+    }
+    #[cfg(not(unix))]
+    None
+}
+
+/// Whether `pid` is still running. Processes can't be looked up outside
+/// Unix, so there they're taken to be.
+pub fn alive(pid: u32) -> bool {
+    #[cfg(unix)]
+    {
+        let Ok(pid) = libc::pid_t::try_from(pid) else {
+            return false;
+        };
+        // SAFETY: signal 0 only checks that the process exists
+        let found = unsafe { libc::kill(pid, 0) } == 0;
+        // One owned by another user exists too
+        found || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = pid;
+        true
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn session(account: &str, pid: u32) -> Session {
+        Session {
+            account: account.to_string(),
+            kind: SessionKind::Shell,
+            pid,
+            started_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_register_replaces_and_drops_gone_processes() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join(FILE);
+        let accounts =
+            || -> Result<Vec<String>> { Ok(live(&path)?.into_iter().map(|s| s.account).collect()) };
+        let me = std::process::id();
+        assert!(accounts()?.is_empty());
+
+        register(&path, session("work", me))?;
+        register(&path, session("personal", me))?;
+        assert_eq!(accounts()?, ["personal"]);
+
+        // A process that has exited is left out
+        let mut child = std::process::Command::new("true").spawn()?;
+        let gone = child.id();
+        child.wait()?;
+        let mut sessions = live(&path)?;
+        sessions.push(session("work", gone));
+        save(&path, &sessions)?;
+        assert_eq!(accounts()?, ["personal"]);
+
+        unregister(&path, me)?;
+        assert!(live(&path)?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,832 @@
+use crate::capture::CapturePolicy;
+use crate::compression;
+use crate::duration::parse_duration;
+use crate::health::EXPIRING_WITHIN;
+use crate::output::ColorChoice;
+use crate::trash;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The keys `config` and the `CLAUDE_ACCOUNT_*` variables can set, dotted
+/// for the ones in tables.
+pub const KEYS: &[&str] = &[
+    "mode",
+    "claude-dir",
+    "system",
+    "system-dir",
+    "resave-after",
+    "autosave",
+    "autosave-debounce",
+    "credentials",
+    "banner",
+    "color",
+    "confirm",
+    "keep-versions",
+    "keep-live-backups",
+    "keep-checkpoints",
+    "keep-trash",
+    "remind-after",
+    "extra-paths",
+    "dedup",
+    "compress",
+    "rotation",
+    "team-overlay",
+    "capture-scope",
+    "capture.include",
+    "capture.exclude",
+    "prune.unused-after",
+    "prune.strip",
+    "hooks.pre-save",
+    "hooks.post-save",
+    "hooks.pre-switch",
+    "hooks.post-switch",
+    "hooks.post-delete",
+    "notify.webhook",
+    "notify.desktop",
+    "notify.events",
+    "notify.expiring-within",
+];
+
+/// The keys whose values are secrets, kept out of what's shared or attached
+/// to a bug report: a webhook's URL carries its token.
+pub const SECRET_KEYS: &[&str] = &["notify.webhook"];
+
+const ENV_PREFIX: &str = "CLAUDE_ACCOUNT_";
+
+/// `autosave-debounce` when it isn't set
+const DEFAULT_AUTOSAVE_DEBOUNCE: chrono::Duration = chrono::Duration::seconds(30);
+
+/// How `switch` puts an account's files in place.
+#[derive(ValueEnum, Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SwitchMode {
+    /// Copy the snapshot into the Claude config directory
+    #[default]
+    Copy,
+    /// Make the Claude config directory a symlink into the account store
+    Symlink,
+    /// Fill the Claude config directory with hard links to the snapshot's
+    /// files, copying the ones Claude Code rewrites
+    Hardlink,
+}
+
+/// Where `save` keeps an account's `.credentials.json`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CredentialStorage {
+    /// In the snapshot directory with the other files
+    #[default]
+    File,
+    /// In the OS keychain, rehydrated into the Claude directory on switch
+    Keychain,
+}
+
+/// How much of the Claude directory belongs to each account.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CaptureScope {
+    /// Everything the capture patterns cover
+    #[default]
+    Full,
+    /// Only the credential files: switching swaps the login, and settings,
+    /// projects and history stay shared by every account
+    Credentials,
+}
+
+/// An event `[notify]` can report.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum NotifyEvent {
+    Save,
+    Switch,
+    /// The active account's login expires soon; the daemon checks
+    Expiring,
+}
+
+impl SwitchMode {
+    fn is_copy(&self) -> bool {
+        *self == Self::Copy
+    }
+}
+
+impl CaptureScope {
+    fn is_full(&self) -> bool {
+        *self == Self::Full
+    }
+}
+
+impl CredentialStorage {
+    fn is_file(&self) -> bool {
+        *self == Self::File
+    }
+}
+
+/// Preferences for the switcher itself, read from `settings.toml`.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct Settings {
+    /// Default for `switch --mode`
+    #[serde(skip_serializing_if = "SwitchMode::is_copy")]
+    pub mode: SwitchMode,
+
+    /// Claude Code's directory when it isn't `~/.claude`; `--claude-dir` and
+    /// `CLAUDE_CONFIG_DIR` win over it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub claude_dir: Option<PathBuf>,
+
+    /// Use the system-wide store, as `--system` does (see `system`)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub system: bool,
+
+    /// Where the system-wide store is, when it isn't
+    /// `/var/lib/claude-account-switcher`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_dir: Option<PathBuf>,
+
+    /// Re-save the active account this long after a switch (e.g. `5m`), so
+    /// the token refresh Claude Code does on first use ends up in the snapshot
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resave_after: Option<String>,
+
+    /// Save the outgoing account on every switch, as by default; `false`
+    /// only saves it when the Claude directory changed since. `switch
+    /// --autosave` and `--no-autosave` win over it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub autosave: Option<bool>,
+
+    /// How soon after an account was saved or switched to a switch away from
+    /// it skips saving it again, when nothing changed since (default `30s`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub autosave_debounce: Option<String>,
+
+    #[serde(skip_serializing_if = "CredentialStorage::is_file")]
+    pub credentials: CredentialStorage,
+
+    /// Notice written into the live `CLAUDE.md` on switch, with `{name}`
+    /// replaced by the account; stripped again before saving
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub banner: Option<String>,
+
+    /// Default for `--color`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<ColorChoice>,
+
+    /// Whether to ask before what can't be undone; false answers yes to
+    /// every question, as `--yes` does
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirm: Option<bool>,
+
+    /// Earlier snapshots kept per account for `rollback`; 0 turns versions off
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_versions: Option<usize>,
+
+    /// Live directories kept after switches replace them, for `recover-live`;
+    /// 0 keeps none
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_live_backups: Option<usize>,
+
+    /// Checkpoints of the live directory kept; the oldest go past this
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_checkpoints: Option<usize>,
+
+    /// How long deleted accounts stay in the trash (e.g. `30d`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_trash: Option<String>,
+
+    /// Warn in `list` and `status` about snapshots saved longer ago than
+    /// this (e.g. `30d`), and mark them stale in `list`, as their login has
+    /// likely expired
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remind_after: Option<String>,
+
+    /// Files and directories outside the Claude directory that each account
+    /// keeps its own of; `~/.claude.json` when unset (see `extra_paths`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extra_paths: Option<Vec<String>>,
+
+    /// Store files that are the same across snapshots once (see `objects`)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub dedup: bool,
+
+    /// zstd level stored files are compressed at (1 to 22, 3 is a good
+    /// start); unset stores them as they are
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compress: Option<i32>,
+
+    /// The accounts `rotate` goes through, in order; all of them by name
+    /// when empty
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rotation: Vec<String>,
+
+    /// A directory laid over the live configuration after every switch,
+    /// such as a checkout of a team's shared settings and hooks (see
+    /// `overlay`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub team_overlay: Option<PathBuf>,
+
+    #[serde(skip_serializing_if = "CaptureScope::is_full")]
+    pub capture_scope: CaptureScope,
+
+    #[serde(skip_serializing_if = "CaptureSettings::is_empty")]
+    pub capture: CaptureSettings,
+
+    #[serde(skip_serializing_if = "PruneSettings::is_empty")]
+    pub prune: PruneSettings,
+
+    #[serde(skip_serializing_if = "HookSettings::is_empty")]
+    pub hooks: HookSettings,
+
+    #[serde(skip_serializing_if = "NotifySettings::is_empty")]
+    pub notify: NotifySettings,
+}
+
+/// Which files `save` copies, as gitignore-style patterns (see `capture`).
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct CaptureSettings {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+}
+
+impl CaptureSettings {
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+}
+
+/// What `prune` removes besides versions past `keep-versions`.
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct PruneSettings {
+    /// Delete accounts not switched to for this long (e.g. `90d`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unused_after: Option<String>,
+    /// Gitignore-style patterns for files removed from stored snapshots
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub strip: Vec<String>,
+}
+
+impl PruneSettings {
+    pub fn is_empty(&self) -> bool {
+        self.unused_after.is_none() && self.strip.is_empty()
+    }
+
+    pub fn unused_after(&self) -> Result<Option<chrono::Duration>> {
+        self.unused_after
+            .as_deref()
+            .map(|value| parse_duration(value).context("Invalid prune.unused-after setting"))
+            .transpose()
+    }
+}
+
+/// Shell commands run around saves, switches and deletes (see `hooks`).
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct HookSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pre_save: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_save: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pre_switch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_switch: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_delete: Option<String>,
+}
+
+impl HookSettings {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+/// Where saves and switches are reported (see `notify`).
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct NotifySettings {
+    /// An http(s) URL each event is POSTed to as JSON
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<String>,
+    /// Show a desktop notification for each event
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub desktop: bool,
+    /// The events reported; all of them when empty
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<NotifyEvent>,
+    /// How long before the active account's login expires the daemon
+    /// reports it (default: 24h)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expiring_within: Option<String>,
+}
+
+impl NotifySettings {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    pub fn expiring_within(&self) -> Result<chrono::Duration> {
+        self.expiring_within
+            .as_deref()
+            .map(|value| parse_duration(value).context("Invalid notify.expiring-within setting"))
+            .transpose()
+            .map(|within| within.unwrap_or(chrono::Duration::hours(EXPIRING_WITHIN)))
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        self.expiring_within()?;
+        match &self.webhook {
+            Some(url) if !url.starts_with("https://") && !url.starts_with("http://") => {
+                anyhow::bail!(
+                    "Invalid notify.webhook setting: {} (expected an http(s) URL)",
+                    url
+                )
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Settings {
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = fs::read_to_string(path).context("Failed to read settings file")?;
+
+        toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse settings file: {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents = self.to_toml()?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Failed to create settings directory")?;
+        }
+        fs::write(path, contents).context("Failed to write settings file")
+    }
+
+    /// The file contents `save` writes. Comments aren't kept.
+    pub fn to_toml(&self) -> Result<String> {
+        toml::to_string_pretty(self).context("Failed to serialize settings")
+    }
+
+    /// The settings with any `CLAUDE_ACCOUNT_*` variables in `vars` laid
+    /// over them (see `env_var`). Other variables are left alone.
+    pub fn with_env(self, vars: impl IntoIterator<Item = (String, String)>) -> Result<Self> {
+        let mut table = toml::Table::try_from(&self).context("Failed to serialize settings")?;
+        let mut applied = Vec::new();
+        for (name, value) in vars {
+            if let Some(key) = KEYS.iter().find(|key| env_var(key) == name) {
+                set_key(&mut table, key, Some(parse_value(&value)));
+                applied.push(name);
+            }
+        }
+        if applied.is_empty() {
+            return Ok(self);
+        }
+        Self::deserialize(table)
+            .with_context(|| format!("Invalid setting in {}", applied.join(", ")))
+    }
+
+    /// Checks the values that are only parsed when they're used.
+    pub fn validate(&self) -> Result<()> {
+        self.resave_delay()?;
+        self.autosave_debounce()?;
+        self.compression_level()?;
+        self.capture_policy(&self.capture.include, &self.capture.exclude)?;
+        self.prune.unused_after()?;
+        self.keep_trash()?;
+        self.remind_after()?;
+        self.notify.validate()?;
+        Ok(())
+    }
+
+    pub fn resave_delay(&self) -> Result<Option<std::time::Duration>> {
+        let Some(value) = &self.resave_after else {
+            return Ok(None);
+        };
+
+        let delay = parse_duration(value)
+            .and_then(|delay| Ok(delay.to_std()?))
+            .context("Invalid resave-after setting")?;
+        Ok(Some(delay))
+    }
+
+    pub fn autosave(&self) -> bool {
+        self.autosave.unwrap_or(true)
+    }
+
+    pub fn confirm(&self) -> bool {
+        self.confirm.unwrap_or(true)
+    }
+
+    pub fn autosave_debounce(&self) -> Result<chrono::Duration> {
+        self.autosave_debounce
+            .as_deref()
+            .map(|value| parse_duration(value).context("Invalid autosave-debounce setting"))
+            .transpose()
+            .map(|debounce| debounce.unwrap_or(DEFAULT_AUTOSAVE_DEBOUNCE))
+    }
+
+    pub fn keep_trash(&self) -> Result<chrono::Duration> {
+        self.keep_trash
+            .as_deref()
+            .map(|value| parse_duration(value).context("Invalid keep-trash setting"))
+            .transpose()
+            .map(|keep| keep.unwrap_or(trash::DEFAULT_KEEP))
+    }
+
+    pub fn remind_after(&self) -> Result<Option<chrono::Duration>> {
+        self.remind_after
+            .as_deref()
+            .map(|value| parse_duration(value).context("Invalid remind-after setting"))
+            .transpose()
+    }
+
+    /// The capture policy for `include` and `exclude`, narrowed down to the
+    /// credential files when `capture-scope` says so.
+    pub fn capture_policy(&self, include: &[String], exclude: &[String]) -> Result<CapturePolicy> {
+        let policy = CapturePolicy::new(include, exclude)?;
+        Ok(match self.capture_scope {
+            CaptureScope::Full => policy,
+            CaptureScope::Credentials => policy.credentials_only(),
+        })
+    }
+
+    pub fn compression_level(&self) -> Result<Option<i32>> {
+        match self.compress {
+            Some(level) if !compression::levels().contains(&level) => {
+                let levels = compression::levels();
+                anyhow::bail!(
+                    "Invalid compress setting: {} (expected {} to {})",
+                    level,
+                    levels.start(),
+                    levels.end()
+                )
+            }
+            level => Ok(level),
+        }
+    }
+}
+
+/// The variable that overrides `key`: `CLAUDE_ACCOUNT_` and the key in
+/// upper case, with `_` for `-` and `__` between a table and its key
+/// (`CLAUDE_ACCOUNT_PRUNE__UNUSED_AFTER`).
+pub fn env_var(key: &str) -> String {
+    format!(
+        "{}{}",
+        ENV_PREFIX,
+        key.to_ascii_uppercase()
+            .replace('.', "__")
+            .replace('-', "_")
+    )
+}
+
+/// `value` as TOML (`true`, `3`, `["projects/"]`), or as a string when it
+/// isn't valid TOML, so plain words don't need quoting.
+pub fn parse_value(value: &str) -> toml::Value {
+    format!("value = {}", value)
+        .parse::<toml::Table>()
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(value.to_string()))
+}
+
+/// The value at the dotted `key`.
+pub fn get_key<'a>(table: &'a toml::Table, key: &str) -> Option<&'a toml::Value> {
+    match key.split_once('.') {
+        Some((parent, rest)) => get_key(table.get(parent)?.as_table()?, rest),
+        None => table.get(key),
+    }
+}
+
+/// Sets the dotted `key` to `value`, or removes it with `None`, along with a
+/// table left empty.
+pub fn set_key(table: &mut toml::Table, key: &str, value: Option<toml::Value>) {
+    let Some((parent, rest)) = key.split_once('.') else {
+        match value {
+            Some(value) => table.insert(key.to_string(), value),
+            None => table.remove(key),
+        };
+        return;
+    };
+
+    let child = table
+        .entry(parent)
+        .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+    if !child.is_table() {
+        *child = toml::Value::Table(toml::Table::new());
+    }
+    if let Some(child_table) = child.as_table_mut() {
+        set_key(child_table, rest, value);
+        if child_table.is_empty() {
+            table.remove(parent);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_missing_file_is_default() -> Result<()> {
+        let settings = Settings::load(Path::new("/nonexistent/settings.toml"))?;
+        assert_eq!(settings, Settings::default());
+        assert_eq!(settings.resave_delay()?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("nested/settings.toml");
+        let settings = Settings {
+            mode: SwitchMode::Symlink,
+            resave_after: Some("5m".to_string()),
+            ..Default::default()
+        };
+
+        settings.save(&path)?;
+        assert_eq!(
+            fs::read_to_string(&path)?.trim(),
+            "mode = \"symlink\"\nresave-after = \"5m\""
+        );
+        assert_eq!(Settings::load(&path)?, settings);
+        Ok(())
+    }
+
+    #[test]
+    fn test_capture_table() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("settings.toml");
+        fs::write(&path, "[capture]\nexclude = [\"projects/\", \"cache/\"]\n")?;
+
+        let settings = Settings::load(&path)?;
+        assert!(settings.capture.include.is_empty());
+        assert_eq!(settings.capture.exclude, ["projects/", "cache/"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_credentials_capture_scope() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("settings.toml");
+        fs::write(&path, "capture-scope = \"credentials\"\n")?;
+
+        let settings = Settings::load(&path)?;
+        assert_eq!(settings.capture_scope, CaptureScope::Credentials);
+        let policy = settings.capture_policy(&[], &[])?;
+        assert!(policy.captures(Path::new(".credentials.json")));
+        assert!(!policy.captures(Path::new("settings.json")));
+        assert!(
+            Settings::default()
+                .capture_policy(&[], &[])?
+                .captures(Path::new("settings.json"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune_table() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("settings.toml");
+        fs::write(
+            &path,
+            "[prune]\nunused-after = \"90d\"\nstrip = [\"projects/\"]\n",
+        )?;
+
+        let prune = Settings::load(&path)?.prune;
+        assert_eq!(prune.unused_after()?, Some(chrono::Duration::days(90)));
+        assert_eq!(prune.strip, ["projects/"]);
+        assert_eq!(PruneSettings::default().unused_after()?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_notify_table() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("settings.toml");
+        fs::write(
+            &path,
+            "[notify]\nwebhook = \"https://hooks.example.com/seats\"\nevents = [\"switch\"]\n",
+        )?;
+
+        let settings = Settings::load(&path)?;
+        assert_eq!(settings.notify.events, [NotifyEvent::Switch]);
+        assert!(!settings.notify.desktop);
+        settings.validate()?;
+
+        let invalid = Settings {
+            notify: NotifySettings {
+                webhook: Some("hooks.example.com".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_claude_dir() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("settings.toml");
+        fs::write(&path, "claude-dir = \"~/claude-work\"\n")?;
+
+        assert_eq!(
+            Settings::load(&path)?.claude_dir,
+            Some(PathBuf::from("~/claude-work"))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_keychain_credentials() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("settings.toml");
+        fs::write(&path, "credentials = \"keychain\"\n")?;
+
+        assert_eq!(
+            Settings::load(&path)?.credentials,
+            CredentialStorage::Keychain
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_resave_delay() -> Result<()> {
+        let settings = Settings {
+            resave_after: Some("90s".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(settings.resave_delay()?, Some(Duration::from_secs(90)));
+
+        let invalid = Settings {
+            resave_after: Some("soon".to_string()),
+            ..Default::default()
+        };
+        assert!(invalid.resave_delay().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_autosave_debounce() -> Result<()> {
+        assert!(Settings::default().autosave());
+        assert_eq!(
+            Settings::default().autosave_debounce()?,
+            chrono::Duration::seconds(30)
+        );
+        let settings = Settings {
+            autosave_debounce: Some("0s".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(settings.autosave_debounce()?, chrono::Duration::zero());
+
+        let invalid = Settings {
+            autosave_debounce: Some("often".to_string()),
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_compression_level() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("settings.toml");
+        fs::write(&path, "compress = 3\n")?;
+        assert_eq!(Settings::load(&path)?.compression_level()?, Some(3));
+
+        let invalid = Settings {
+            compress: Some(99),
+            ..Default::default()
+        };
+        assert!(invalid.compression_level().is_err());
+        assert_eq!(Settings::default().compression_level()?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_keys_cover_every_setting() -> Result<()> {
+        let settings = Settings {
+            mode: SwitchMode::Symlink,
+            claude_dir: Some(PathBuf::from("~/claude")),
+            system: true,
+            system_dir: Some(PathBuf::from("/srv/claude")),
+            resave_after: Some("5m".to_string()),
+            autosave: Some(false),
+            autosave_debounce: Some("1m".to_string()),
+            credentials: CredentialStorage::Keychain,
+            banner: Some("{name}".to_string()),
+            color: Some(ColorChoice::Never),
+            confirm: Some(false),
+            keep_versions: Some(1),
+            keep_live_backups: Some(2),
+            keep_checkpoints: Some(4),
+            keep_trash: Some("7d".to_string()),
+            remind_after: Some("30d".to_string()),
+            extra_paths: Some(vec!["~/.claude.json".to_string()]),
+            dedup: true,
+            compress: Some(3),
+            rotation: vec!["work".to_string()],
+            team_overlay: Some(PathBuf::from("~/team-claude")),
+            capture_scope: CaptureScope::Credentials,
+            capture: CaptureSettings {
+                include: vec!["*.json".to_string()],
+                exclude: vec!["projects/".to_string()],
+            },
+            prune: PruneSettings {
+                unused_after: Some("90d".to_string()),
+                strip: vec!["projects/".to_string()],
+            },
+            hooks: HookSettings {
+                pre_save: Some("true".to_string()),
+                post_save: Some("true".to_string()),
+                pre_switch: Some("true".to_string()),
+                post_switch: Some("true".to_string()),
+                post_delete: Some("true".to_string()),
+            },
+            notify: NotifySettings {
+                webhook: Some("https://hooks.example.com/seats".to_string()),
+                desktop: true,
+                events: vec![NotifyEvent::Switch],
+                expiring_within: Some("2h".to_string()),
+            },
+        };
+        let table = toml::Table::try_from(&settings)?;
+        let mut keys = Vec::new();
+        for (key, value) in &table {
+            match value.as_table() {
+                Some(child) => keys.extend(child.keys().map(|child| format!("{}.{}", key, child))),
+                None => keys.push(key.clone()),
+            }
+        }
+        keys.sort();
+        let mut known: Vec<String> = KEYS.iter().map(|key| key.to_string()).collect();
+        known.sort();
+        assert_eq!(keys, known);
+        Ok(())
+    }
+
+    #[test]
+    fn test_env_overrides() -> Result<()> {
+        assert_eq!(
+            env_var("prune.unused-after"),
+            "CLAUDE_ACCOUNT_PRUNE__UNUSED_AFTER"
+        );
+        let vars = [
+            ("CLAUDE_ACCOUNT_MODE", "symlink"),
+            ("CLAUDE_ACCOUNT_KEEP_VERSIONS", "2"),
+            ("CLAUDE_ACCOUNT_CAPTURE__EXCLUDE", "[\"projects/\"]"),
+            ("CLAUDE_ACCOUNT_PASSPHRASE", "not a setting"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+
+        let settings = Settings::default().with_env(vars)?;
+        assert_eq!(settings.mode, SwitchMode::Symlink);
+        assert_eq!(settings.keep_versions, Some(2));
+        assert_eq!(settings.capture.exclude, ["projects/"]);
+
+        let invalid = [("CLAUDE_ACCOUNT_DEDUP".to_string(), "maybe".to_string())];
+        let error = Settings::default().with_env(invalid).unwrap_err();
+        assert!(error.to_string().contains("CLAUDE_ACCOUNT_DEDUP"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_dotted_keys() {
+        let mut table = toml::Table::new();
+        set_key(&mut table, "prune.unused-after", Some(parse_value("90d")));
+        set_key(&mut table, "dedup", Some(parse_value("true")));
+        assert_eq!(
+            get_key(&table, "prune.unused-after").and_then(toml::Value::as_str),
+            Some("90d")
+        );
+        assert_eq!(get_key(&table, "dedup"), Some(&toml::Value::Boolean(true)));
+
+        set_key(&mut table, "prune.unused-after", None);
+        assert!(!table.contains_key("prune"));
+        assert_eq!(get_key(&table, "prune.strip"), None);
+    }
+
+    #[test]
+    fn test_wrongly_typed_value_is_an_error() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("settings.toml");
+        fs::write(&path, "resave-after = 5")?;
+
+        assert!(Settings::load(&path).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+use crate::store;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::ops::AddAssign;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DirUsage {
+    pub files: u64,
+    pub bytes: u64,
+}
+
+impl AddAssign for DirUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.files += other.files;
+        self.bytes += other.bytes;
+    }
+}
+
+/// Sums file sizes below `path`. Missing directories count as empty.
+pub fn dir_usage(path: &Path) -> Result<DirUsage> {
+    dir_usage_skipping(path, &[])
+}
+
+/// Like `dir_usage`, leaving out the directories in `skipped` (relative to
+/// `path`), such as the snapshots of nested account names.
+pub fn dir_usage_skipping(path: &Path, skipped: &[PathBuf]) -> Result<DirUsage> {
+    let mut usage = DirUsage::default();
+    if !path.exists() {
+        return Ok(usage);
+    }
+
+    for entry in store::walk_dir(path).skip_dirs(skipped) {
+        usage.files += 1;
+        usage.bytes += entry?.size;
+    }
+    Ok(usage)
+}
+
+/// What `path` really takes up: like `dir_usage`, but a file with several
+/// names (as `dedup` links them) counts once.
+pub fn disk_usage(path: &Path) -> Result<DirUsage> {
+    let mut usage = DirUsage::default();
+    if !path.exists() {
+        return Ok(usage);
+    }
+
+    let mut seen = HashSet::new();
+    for entry in store::walk_dir(path) {
+        let entry = entry?;
+        if let Some(id) = linked_file_id(&entry.path)?
+            && !seen.insert(id)
+        {
+            continue;
+        }
+        usage.files += 1;
+        usage.bytes += entry.size;
+    }
+    Ok(usage)
+}
+
+// Device and inode of a file with more than one name
+#[cfg(unix)]
+fn linked_file_id(path: &Path) -> Result<Option<(u64, u64)>> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = fs::symlink_metadata(path)
+        .with_context(|| format!("Failed to read file: {}", path.display()))?;
+    Ok((metadata.nlink() > 1).then(|| (metadata.dev(), metadata.ino())))
+}
+
+#[cfg(not(unix))]
+fn linked_file_id(_path: &Path) -> Result<Option<(u64, u64)>> {
+    Ok(None)
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_dir_usage() -> Result<()> {
+        let dir = TempDir::new()?;
+        fs::write(dir.path().join("a.json"), "12345")?;
+        fs::create_dir_all(dir.path().join("nested"))?;
+        fs::write(dir.path().join("nested/b.txt"), "123")?;
+
+        let usage = dir_usage(dir.path())?;
+        assert_eq!(usage, DirUsage { files: 2, bytes: 8 });
+        Ok(())
+    }
+
+    #[test]
+    fn test_dir_usage_missing() -> Result<()> {
+        assert_eq!(
+            dir_usage(Path::new("/nonexistent/path"))?,
+            DirUsage::default()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_dir_usage_skipping() -> Result<()> {
+        let dir = TempDir::new()?;
+        fs::write(dir.path().join("a.json"), "12345")?;
+        fs::create_dir_all(dir.path().join("dev"))?;
+        fs::write(dir.path().join("dev/b.json"), "123")?;
+
+        let usage = dir_usage_skipping(dir.path(), &[PathBuf::from("dev")])?;
+        assert_eq!(usage, DirUsage { files: 1, bytes: 5 });
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_disk_usage_counts_linked_files_once() -> Result<()> {
+        let dir = TempDir::new()?;
+        fs::write(dir.path().join("a.json"), "12345")?;
+        fs::hard_link(dir.path().join("a.json"), dir.path().join("b.json"))?;
+        fs::write(dir.path().join("c.json"), "123")?;
+
+        assert_eq!(
+            dir_usage(dir.path())?,
+            DirUsage {
+                files: 3,
+                bytes: 13
+            }
+        );
+        assert_eq!(disk_usage(dir.path())?, DirUsage { files: 2, bytes: 8 });
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(5 * 1024 * 1024), "5.0 MiB");
+    }
+}
@@ -0,0 +1,152 @@
+use crate::size::{self, DirUsage};
+use crate::tr;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+// Weight of the newest sample in the rolling average
+const SMOOTHING: f64 = 0.3;
+// Shorter copies are dominated by fixed overhead and would skew the average
+const MIN_SAMPLE: Duration = Duration::from_millis(50);
+const MIN_ETA: Duration = Duration::from_secs(2);
+
+/// Rolling copy throughput, used to estimate how long large operations take.
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct TransferStats {
+    pub samples: u64,
+    pub bytes_per_sec: f64,
+}
+
+impl TransferStats {
+    /// Statistics are advisory, so unreadable files simply start over.
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents =
+            serde_json::to_string_pretty(self).context("Failed to serialize statistics")?;
+        fs::write(path, contents).context("Failed to write statistics file")
+    }
+
+    pub fn record(&mut self, bytes: u64, elapsed: Duration) {
+        if elapsed < MIN_SAMPLE {
+            return;
+        }
+
+        let rate = bytes as f64 / elapsed.as_secs_f64();
+        self.bytes_per_sec = if self.samples == 0 {
+            rate
+        } else {
+            SMOOTHING * rate + (1.0 - SMOOTHING) * self.bytes_per_sec
+        };
+        self.samples += 1;
+    }
+
+    /// Returns an estimate only when it's long enough to be worth showing.
+    pub fn estimate(&self, bytes: u64) -> Option<Duration> {
+        if self.samples == 0 || self.bytes_per_sec <= 0.0 {
+            return None;
+        }
+
+        let eta = Duration::from_secs_f64(bytes as f64 / self.bytes_per_sec);
+        (eta >= MIN_ETA).then_some(eta)
+    }
+}
+
+/// What a copy moved and how long it took.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Transfer {
+    pub usage: DirUsage,
+    pub elapsed: Duration,
+}
+
+pub fn summary(usage: DirUsage, elapsed: Duration) -> String {
+    tr!(
+        "copy-summary",
+        files = usage.files,
+        size = size::format_bytes(usage.bytes),
+        duration = format_duration(elapsed)
+    )
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs_f64();
+    if secs < 60.0 {
+        format!("{:.1}s", secs)
+    } else {
+        format!(
+            "{}m {:02}s",
+            duration.as_secs() / 60,
+            duration.as_secs() % 60
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_record_and_estimate() {
+        let mut stats = TransferStats::default();
+        assert!(stats.estimate(1_000_000).is_none());
+
+        stats.record(10_000_000, Duration::from_secs(1));
+        assert_eq!(stats.samples, 1);
+        assert_eq!(stats.estimate(50_000_000), Some(Duration::from_secs(5)));
+
+        // Quick operations don't need an ETA
+        assert!(stats.estimate(1_000_000).is_none());
+    }
+
+    #[test]
+    fn test_rolling_average() {
+        let mut stats = TransferStats::default();
+        stats.record(100, Duration::from_secs(1));
+        stats.record(200, Duration::from_secs(1));
+
+        assert!((stats.bytes_per_sec - 130.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_short_samples_ignored() {
+        let mut stats = TransferStats::default();
+        stats.record(1_000, Duration::from_millis(1));
+        assert_eq!(stats.samples, 0);
+    }
+
+    #[test]
+    fn test_save_and_load() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("stats.json");
+
+        let mut stats = TransferStats::default();
+        stats.record(1_000, Duration::from_secs(1));
+        stats.save(&path)?;
+
+        let loaded = TransferStats::load(&path);
+        assert_eq!(loaded.samples, 1);
+        assert!(TransferStats::load(&dir.path().join("missing.json")).samples == 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_summary_and_duration_format() {
+        let usage = DirUsage {
+            files: 3,
+            bytes: 2048,
+        };
+        assert_eq!(
+            summary(usage, Duration::from_millis(1500)),
+            "Copied 3 files (2.0 KiB) in 1.5s"
+        );
+        assert_eq!(format_duration(Duration::from_secs(125)), "2m 05s");
+    }
+}
@@ -0,0 +1,453 @@
+//! Where the manager's file operations go: the disk, or memory in tests.
+//!
+//! Saving, switching and deleting read, write, copy, move and remove
+//! snapshots through a [`Storage`], so they can run against
+//! [`MemoryStorage`] without touching the disk, and later against other
+//! backends. Comparing the live files with a manifest still stats them on the
+//! disk, as do the commands that only inspect snapshots. [`LocalStorage`] is the real
+//! filesystem, with the details copies there keep (see `fidelity`).
+
+use crate::fidelity;
+use crate::platform;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// What a path is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Kind {
+    File,
+    Dir,
+    Symlink,
+}
+
+/// An entry of a directory, as [`Storage::read_dir`] lists it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirEntry {
+    pub name: OsString,
+    /// What it is, without following a symlink
+    pub kind: Kind,
+    /// Its size in bytes, when it's a file
+    pub len: u64,
+}
+
+pub trait Storage: Send + Sync {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// The first `len` bytes of the file at `path`, or all of it when it's
+    /// shorter.
+    fn read_prefix(&self, path: &Path, len: usize) -> io::Result<Vec<u8>> {
+        let mut data = self.read(path)?;
+        data.truncate(len);
+        Ok(data)
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()>;
+
+    /// Writes `data` as a new file that takes the place of the one at
+    /// `path`, so a hard link to that one keeps what it held.
+    fn write_replacing(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.write(path, data)
+    }
+
+    /// Copies the file at `src` to `dst`, which mustn't exist, with its mode
+    /// and modification time. Returns how many bytes were copied.
+    fn copy(&self, src: &Path, dst: &Path) -> io::Result<u64>;
+
+    /// Gives `dst` the mode and modification time of `src`.
+    fn keep_metadata(&self, src: &Path, dst: &Path) -> io::Result<()>;
+
+    fn hard_link(&self, src: &Path, dst: &Path) -> io::Result<()>;
+
+    /// Makes `dst` a symlink to where the symlink `src` points.
+    fn copy_symlink(&self, src: &Path, dst: &Path) -> io::Result<()>;
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()>;
+
+    /// Removes the symlink at `path`, never what it points to.
+    fn remove_symlink(&self, path: &Path) -> io::Result<()>;
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>>;
+
+    /// What `path` is once symlinks are followed, or `None` when there's
+    /// nothing there.
+    fn kind(&self, path: &Path) -> Option<Kind>;
+
+    fn is_symlink(&self, path: &Path) -> bool;
+
+    fn exists(&self, path: &Path) -> bool {
+        self.kind(path).is_some()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.kind(path) == Some(Kind::Dir)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.kind(path) == Some(Kind::File)
+    }
+}
+
+/// The files under `root`, each with its path relative to `root` and its
+/// size. Symlinks aren't followed or listed.
+pub fn files(storage: &dyn Storage, root: &Path) -> io::Result<Vec<(PathBuf, u64)>> {
+    let mut files = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+    while let Some(relative) = pending.pop() {
+        for entry in storage.read_dir(&root.join(&relative))? {
+            let path = relative.join(&entry.name);
+            match entry.kind {
+                Kind::Dir => pending.push(path),
+                Kind::File => files.push((path, entry.len)),
+                Kind::Symlink => {}
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// The filesystem.
+pub struct LocalStorage;
+
+impl Storage for LocalStorage {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn read_prefix(&self, path: &Path, len: usize) -> io::Result<Vec<u8>> {
+        let mut data = Vec::with_capacity(len);
+        fs::File::open(path)?
+            .take(len as u64)
+            .read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        fs::write(path, data)
+    }
+
+    fn write_replacing(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        platform::write_replacing(path, data)
+    }
+
+    fn copy(&self, src: &Path, dst: &Path) -> io::Result<u64> {
+        // fs::copy clones the file where the filesystem can: copy_file_range
+        // on Linux, which btrfs and XFS turn into a reflink, and clonefile
+        // on APFS
+        let copied = fs::copy(src, dst)?;
+        platform::make_writable(dst)?;
+        fidelity::keep_metadata(src, dst)?;
+        Ok(copied)
+    }
+
+    fn keep_metadata(&self, src: &Path, dst: &Path) -> io::Result<()> {
+        fidelity::keep_metadata(src, dst)
+    }
+
+    fn hard_link(&self, src: &Path, dst: &Path) -> io::Result<()> {
+        fs::hard_link(src, dst)
+    }
+
+    fn copy_symlink(&self, src: &Path, dst: &Path) -> io::Result<()> {
+        fidelity::copy_symlink(src, dst)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        platform::make_writable(path)?;
+        fs::remove_file(path)
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir_all(path)
+    }
+
+    fn remove_symlink(&self, path: &Path) -> io::Result<()> {
+        platform::remove_symlink(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>> {
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(platform::long_path(path))? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            let (kind, len) = if file_type.is_dir() {
+                (Kind::Dir, 0)
+            } else if file_type.is_symlink() {
+                (Kind::Symlink, 0)
+            } else {
+                (Kind::File, entry.metadata()?.len())
+            };
+            entries.push(DirEntry {
+                name: entry.file_name(),
+                kind,
+                len,
+            });
+        }
+        Ok(entries)
+    }
+
+    fn kind(&self, path: &Path) -> Option<Kind> {
+        let metadata = fs::metadata(path).ok()?;
+        Some(match metadata.is_dir() {
+            true => Kind::Dir,
+            false => Kind::File,
+        })
+    }
+
+    fn is_symlink(&self, path: &Path) -> bool {
+        path.is_symlink()
+    }
+}
+
+enum Node {
+    File(Vec<u8>),
+    Dir,
+}
+
+/// Files kept in memory, for tests. It has no symlinks or hard links, and
+/// keeps no modes or times.
+#[derive(Default)]
+pub struct MemoryStorage {
+    nodes: Mutex<BTreeMap<PathBuf, Node>>,
+}
+
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, path.display().to_string())
+}
+
+fn unsupported() -> io::Error {
+    io::Error::new(io::ErrorKind::Unsupported, "not supported in memory")
+}
+
+impl MemoryStorage {
+    fn nodes(&self) -> std::sync::MutexGuard<'_, BTreeMap<PathBuf, Node>> {
+        self.nodes
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // The paths at or under `path`
+    fn subtree(nodes: &BTreeMap<PathBuf, Node>, path: &Path) -> Vec<PathBuf> {
+        nodes
+            .keys()
+            .filter(|key| key.starts_with(path))
+            .cloned()
+            .collect()
+    }
+
+    fn ensure_parent(nodes: &BTreeMap<PathBuf, Node>, path: &Path) -> io::Result<()> {
+        match path.parent() {
+            Some(parent) if parent.as_os_str().is_empty() => Ok(()),
+            Some(parent) if !matches!(nodes.get(parent), Some(Node::Dir)) => Err(not_found(parent)),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        match self.nodes().get(path) {
+            Some(Node::File(data)) => Ok(data.clone()),
+            _ => Err(not_found(path)),
+        }
+    }
+
+    fn write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        let mut nodes = self.nodes();
+        Self::ensure_parent(&nodes, path)?;
+        if matches!(nodes.get(path), Some(Node::Dir)) {
+            return Err(io::Error::new(
+                io::ErrorKind::IsADirectory,
+                path.display().to_string(),
+            ));
+        }
+        nodes.insert(path.to_path_buf(), Node::File(data.to_vec()));
+        Ok(())
+    }
+
+    fn copy(&self, src: &Path, dst: &Path) -> io::Result<u64> {
+        let data = self.read(src)?;
+        self.write(dst, &data)?;
+        Ok(data.len() as u64)
+    }
+
+    fn keep_metadata(&self, src: &Path, _dst: &Path) -> io::Result<()> {
+        self.read(src).map(drop)
+    }
+
+    fn hard_link(&self, _src: &Path, _dst: &Path) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    fn copy_symlink(&self, _src: &Path, _dst: &Path) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let mut nodes = self.nodes();
+        for dir in path.ancestors().filter(|dir| !dir.as_os_str().is_empty()) {
+            match nodes.get(dir) {
+                Some(Node::Dir) => break,
+                Some(Node::File(_)) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::NotADirectory,
+                        dir.display().to_string(),
+                    ));
+                }
+                None => {
+                    nodes.insert(dir.to_path_buf(), Node::Dir);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let mut nodes = self.nodes();
+        if !nodes.contains_key(from) {
+            return Err(not_found(from));
+        }
+        Self::ensure_parent(&nodes, to)?;
+        // As on Unix, an empty directory or a file is replaced
+        let replaced = Self::subtree(&nodes, to);
+        if replaced.len() > 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::DirectoryNotEmpty,
+                to.display().to_string(),
+            ));
+        }
+        for path in replaced {
+            nodes.remove(&path);
+        }
+        for path in Self::subtree(&nodes, from) {
+            let node = nodes.remove(&path).expect("listed above");
+            let moved = to.join(path.strip_prefix(from).expect("listed under it"));
+            nodes.insert(moved, node);
+        }
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let mut nodes = self.nodes();
+        match nodes.get(path) {
+            Some(Node::File(_)) => {
+                nodes.remove(path);
+                Ok(())
+            }
+            _ => Err(not_found(path)),
+        }
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        let mut nodes = self.nodes();
+        if !matches!(nodes.get(path), Some(Node::Dir)) {
+            return Err(not_found(path));
+        }
+        for path in Self::subtree(&nodes, path) {
+            nodes.remove(&path);
+        }
+        Ok(())
+    }
+
+    fn remove_symlink(&self, path: &Path) -> io::Result<()> {
+        Err(not_found(path))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>> {
+        let nodes = self.nodes();
+        if !matches!(nodes.get(path), Some(Node::Dir)) {
+            return Err(not_found(path));
+        }
+        Ok(nodes
+            .iter()
+            .filter(|(key, _)| key.parent() == Some(path))
+            .map(|(key, node)| {
+                let (kind, len) = match node {
+                    Node::File(data) => (Kind::File, data.len() as u64),
+                    Node::Dir => (Kind::Dir, 0),
+                };
+                DirEntry {
+                    name: key.file_name().unwrap_or_default().to_os_string(),
+                    kind,
+                    len,
+                }
+            })
+            .collect())
+    }
+
+    fn kind(&self, path: &Path) -> Option<Kind> {
+        self.nodes().get(path).map(|node| match node {
+            Node::File(_) => Kind::File,
+            Node::Dir => Kind::Dir,
+        })
+    }
+
+    fn is_symlink(&self, _path: &Path) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    // What the storage holds under `root`, as the local and in-memory ones
+    // should agree on it
+    fn exercise(storage: &dyn Storage, root: &Path) -> io::Result<Vec<(PathBuf, u64)>> {
+        storage.create_dir_all(&root.join("a/b"))?;
+        storage.write(&root.join("a/b/one.json"), b"{}")?;
+        storage.write(&root.join("a/two.txt"), b"hello")?;
+        assert_eq!(storage.read_prefix(&root.join("a/two.txt"), 2)?, b"he");
+        assert!(storage.is_dir(&root.join("a")));
+        assert!(storage.is_file(&root.join("a/two.txt")));
+        assert!(!storage.exists(&root.join("missing")));
+        assert!(storage.write(&root.join("missing/file"), b"").is_err());
+
+        assert_eq!(
+            storage.copy(&root.join("a/two.txt"), &root.join("three"))?,
+            5
+        );
+        storage.rename(&root.join("a"), &root.join("moved"))?;
+        assert!(!storage.exists(&root.join("a")));
+        assert_eq!(storage.read(&root.join("moved/b/one.json"))?, b"{}");
+
+        storage.create_dir_all(&root.join("gone/deep"))?;
+        storage.remove_dir_all(&root.join("gone"))?;
+        storage.remove_file(&root.join("three"))?;
+        assert!(storage.remove_file(&root.join("three")).is_err());
+        files(storage, root)
+    }
+
+    #[test]
+    fn test_local_and_memory_storage_agree() -> io::Result<()> {
+        let dir = TempDir::new()?;
+        let expected = [
+            (PathBuf::from("moved/b/one.json"), 2),
+            (PathBuf::from("moved/two.txt"), 5),
+        ];
+        assert_eq!(exercise(&LocalStorage, dir.path())?, expected);
+        let memory = MemoryStorage::default();
+        memory.create_dir_all(Path::new("/root"))?;
+        assert_eq!(exercise(&memory, Path::new("/root"))?, expected);
+        Ok(())
+    }
+}
@@ -0,0 +1,246 @@
+use crate::hashing;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Left in a directory given to `save --store`, so a drive that isn't
+/// mounted can be told apart from a snapshot that's gone.
+pub const MARKER: &str = ".claude-account-store";
+
+/// Whether `dir`, which `save --store` keeps snapshots in, is there to use:
+/// mounted, when it's on a drive.
+pub fn is_mounted(dir: &Path) -> bool {
+    dir.join(MARKER).is_file()
+}
+
+/// Marks `dir` as a place snapshots are kept, for [`is_mounted`].
+pub fn claim(dir: &Path) -> Result<()> {
+    let marker = dir.join(MARKER);
+    if !marker.is_file() {
+        fs::write(&marker, "")
+            .with_context(|| format!("Failed to write file: {}", marker.display()))?;
+    }
+    Ok(())
+}
+
+/// Read access to the account snapshots kept under the switcher data directory.
+pub struct SnapshotStore {
+    root: PathBuf,
+}
+
+impl SnapshotStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn account_dir(&self, name: &str) -> PathBuf {
+        self.root.join(name)
+    }
+
+    /// Lazily walks the files of one account snapshot.
+    pub fn walk(&self, name: &str) -> Result<Walk> {
+        let dir = self.account_dir(name);
+        if !dir.is_dir() {
+            anyhow::bail!("Account directory not found: {}", dir.display());
+        }
+        Ok(walk_dir(dir))
+    }
+}
+
+/// A regular file found while walking a snapshot.
+#[derive(Clone, Debug)]
+pub struct FileEntry {
+    /// Path relative to the snapshot root
+    pub relative_path: PathBuf,
+    pub path: PathBuf,
+    pub size: u64,
+}
+
+impl FileEntry {
+    /// Computes the SHA-256 of the file contents, streaming through a small buffer.
+    pub fn hash(&self) -> Result<String> {
+        hashing::hash_file(&self.path)
+    }
+}
+
+/// Walks `dir` depth-first, reading one directory at a time. Symlinks and
+/// other special files are skipped, matching what snapshots capture.
+pub fn walk_dir(dir: impl Into<PathBuf>) -> Walk {
+    Walk {
+        root: dir.into(),
+        pending: vec![PathBuf::new()],
+        current: None,
+        skipped: Vec::new(),
+    }
+}
+
+/// Rewrites every file below `dir` in place through `transform`. Returns
+/// the number of files.
+///
+/// Each file is replaced via rename so an interruption never leaves it half
+/// written.
+pub fn rewrite_tree(dir: &Path, transform: impl Fn(Vec<u8>) -> Result<Vec<u8>>) -> Result<u64> {
+    let mut count = 0;
+    for entry in walk_dir(dir) {
+        let entry = entry?;
+        let data = fs::read(&entry.path)
+            .with_context(|| format!("Failed to read file: {}", entry.path.display()))?;
+        let rewritten = transform(data)?;
+
+        let mut staged = entry.path.clone().into_os_string();
+        staged.push(".cas-tmp");
+        fs::write(&staged, rewritten)
+            .with_context(|| format!("Failed to write file: {}", entry.path.display()))?;
+        fs::rename(&staged, &entry.path)
+            .with_context(|| format!("Failed to replace file: {}", entry.path.display()))?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+pub struct Walk {
+    root: PathBuf,
+    pending: Vec<PathBuf>,
+    current: Option<(PathBuf, fs::ReadDir)>,
+    skipped: Vec<PathBuf>,
+}
+
+impl Walk {
+    /// Doesn't descend into these directories (relative to the walk root).
+    pub fn skip_dirs<P: AsRef<Path>>(mut self, dirs: &[P]) -> Self {
+        self.skipped
+            .extend(dirs.iter().map(|dir| dir.as_ref().to_path_buf()));
+        self
+    }
+
+    fn next_entry(&mut self) -> Result<Option<FileEntry>> {
+        loop {
+            let Some((dir, read_dir)) = &mut self.current else {
+                let Some(dir) = self.pending.pop() else {
+                    return Ok(None);
+                };
+                let full = self.root.join(&dir);
+                let read_dir = fs::read_dir(&full)
+                    .with_context(|| format!("Failed to read directory: {}", full.display()))?;
+                self.current = Some((dir, read_dir));
+                continue;
+            };
+
+            let Some(entry) = read_dir.next() else {
+                self.current = None;
+                continue;
+            };
+
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            let relative_path = dir.join(entry.file_name());
+
+            if file_type.is_dir() {
+                if !self.skipped.contains(&relative_path) {
+                    self.pending.push(relative_path);
+                }
+            } else if file_type.is_file() {
+                return Ok(Some(FileEntry {
+                    relative_path,
+                    size: entry.metadata()?.len(),
+                    path: entry.path(),
+                }));
+            }
+        }
+    }
+}
+
+impl Iterator for Walk {
+    type Item = Result<FileEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_entry().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn create_store() -> Result<TempDir> {
+        let dir = TempDir::new()?;
+        let account = dir.path().join("work");
+        fs::create_dir_all(account.join("nested/deep"))?;
+        fs::create_dir_all(account.join("cache"))?;
+        fs::write(account.join("settings.json"), "{}")?;
+        fs::write(account.join("nested/deep/file.txt"), "hello")?;
+        fs::write(account.join("cache/blob"), "cached")?;
+        Ok(dir)
+    }
+
+    fn sorted_paths(walk: Walk) -> Result<Vec<PathBuf>> {
+        let mut paths = walk
+            .map(|entry| entry.map(|e| e.relative_path))
+            .collect::<Result<Vec<_>>>()?;
+        paths.sort();
+        Ok(paths)
+    }
+
+    #[test]
+    fn test_walk_yields_relative_paths() -> Result<()> {
+        let dir = create_store()?;
+        let store = SnapshotStore::new(dir.path());
+
+        assert_eq!(
+            sorted_paths(store.walk("work")?)?,
+            vec![
+                PathBuf::from("cache/blob"),
+                PathBuf::from("nested/deep/file.txt"),
+                PathBuf::from("settings.json"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_walk_skip_dirs() -> Result<()> {
+        let dir = create_store()?;
+        let store = SnapshotStore::new(dir.path());
+
+        assert_eq!(
+            sorted_paths(store.walk("work")?.skip_dirs(&["cache"]))?,
+            vec![
+                PathBuf::from("nested/deep/file.txt"),
+                PathBuf::from("settings.json"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_walk_missing_account() {
+        let store = SnapshotStore::new("/nonexistent/store");
+        assert!(store.walk("work").is_err());
+    }
+
+    #[test]
+    fn test_file_entry_size_and_hash() -> Result<()> {
+        let dir = create_store()?;
+        let store = SnapshotStore::new(dir.path());
+
+        let entry = store
+            .walk("work")?
+            .find(|e| {
+                e.as_ref()
+                    .is_ok_and(|e| e.relative_path.ends_with("file.txt"))
+            })
+            .unwrap()?;
+
+        assert_eq!(entry.size, 5);
+        assert_eq!(
+            entry.hash()?,
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+//! `suggest`: which account is best to use now, from what's known about
+//! each: whether its login works (see [`health`](crate::health)), whether a
+//! rate limit recorded by `limit-hit` still holds, and its plan.
+//!
+//! Usable accounts come first, the biggest plan first and among equals the
+//! one used longest ago, so the load spreads. Rate-limited ones follow,
+//! soonest to lift first, then the ones whose login can't be used.
+
+use crate::health::Health;
+use chrono::{DateTime, Utc};
+use std::cmp::Reverse;
+
+/// An account as ranked.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Candidate {
+    pub name: String,
+    pub plan: Option<String>,
+    pub health: Health,
+    /// When a rate limit lifts, while one holds
+    pub limited_until: Option<DateTime<Utc>>,
+    pub last_used: Option<DateTime<Utc>>,
+}
+
+impl Candidate {
+    /// Whether it can be used right away.
+    pub fn available(&self) -> bool {
+        self.health.is_ok() && self.limited_until.is_none()
+    }
+}
+
+/// How much quota a plan comes with, as Claude Code names them
+/// (`max`, `pro`, ...): higher is more. Unknown ones, and API keys, rank last.
+pub fn plan_tier(plan: Option<&str>) -> u8 {
+    let plan = plan.unwrap_or_default().to_ascii_lowercase();
+    match plan.as_str() {
+        plan if plan.contains("max") => 3,
+        plan if plan.contains("team") || plan.contains("enterprise") => 2,
+        plan if plan.contains("pro") => 1,
+        _ => 0,
+    }
+}
+
+/// Puts `candidates` best first.
+pub fn rank(candidates: &mut [Candidate]) {
+    candidates.sort_by_key(|candidate| {
+        let group = match (candidate.health.is_ok(), candidate.limited_until) {
+            (true, None) => 0,
+            (true, Some(_)) => 1,
+            (false, _) => 2,
+        };
+        (
+            group,
+            candidate.limited_until,
+            Reverse(plan_tier(candidate.plan.as_deref())),
+            // Never used sorts first, as the oldest
+            candidate.last_used,
+            candidate.name.clone(),
+        )
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank_prefers_usable_big_and_rested() {
+        let at = |hour: u32| {
+            DateTime::parse_from_rfc3339(&format!("2024-01-01T{:02}:00:00Z", hour))
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let candidate = |name: &str, plan: &str, last_used| Candidate {
+            name: name.to_string(),
+            plan: Some(plan.to_string()),
+            health: Health::Unexpired,
+            limited_until: None,
+            last_used,
+        };
+        let mut candidates = vec![
+            candidate("pro", "pro", None),
+            candidate("max-busy", "max", Some(at(11))),
+            candidate("max-rested", "max", Some(at(9))),
+            Candidate {
+                limited_until: Some(at(15)),
+                ..candidate("limited-late", "max", None)
+            },
+            Candidate {
+                limited_until: Some(at(13)),
+                ..candidate("limited-soon", "pro", None)
+            },
+            Candidate {
+                health: Health::Revoked,
+                ..candidate("revoked", "max", None)
+            },
+        ];
+        rank(&mut candidates);
+        let names: Vec<_> = candidates.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "max-rested",
+                "max-busy",
+                "pro",
+                "limited-soon",
+                "limited-late",
+                "revoked"
+            ]
+        );
+        assert!(candidates[0].available() && !candidates[3].available());
+        assert_eq!(plan_tier(Some("claude_max_20x")), 3);
+        assert_eq!(plan_tier(None), 0);
+    }
+}
@@ -0,0 +1,413 @@
+//! `sync`: the account store shared between machines through a git
+//! repository, cloned into the state directory, or object storage kept the
+//! same way (see `cloud`).
+//!
+//! The repository holds `accounts.json` (what describes each account, by
+//! name), the store's key file and a snapshot of each account under
+//! `accounts/`, every file sealed. Only an encrypted store can sync, so
+//! credentials never reach the repository readable.
+//!
+//! Accounts are merged by name: the newer save wins, and an account that
+//! both sides had at the last sync and one of them no longer has was
+//! deleted there.
+
+use crate::cloud::{self, ObjectBackend, S3, WebDav};
+use crate::config::AccountIdentity;
+use crate::tr;
+use anyhow::{Context, Result};
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+/// The clone, in the state directory
+pub const DIR: &str = "sync";
+/// What the last push or pull left, in the state directory
+pub const STATE_FILE: &str = "sync.json";
+/// The key file of the store that first pushed, in the repository
+pub const KEY_FILE: &str = "store-key.json";
+
+const ACCOUNTS_FILE: &str = "accounts.json";
+const ACCOUNTS_DIR: &str = "accounts";
+const COMMIT_NAME: &str = "claude-account-switcher";
+const COMMIT_EMAIL: &str = "claude-account-switcher@localhost";
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct SyncState {
+    pub remote: String,
+    /// Accounts both here and in the repository after the last sync, so an
+    /// account missing from one side can be told apart as deleted or new
+    #[serde(default)]
+    pub synced: BTreeSet<String>,
+}
+
+impl SyncState {
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(path).context("Failed to read sync state")?;
+        serde_json::from_str(&contents)
+            .map(Some)
+            .context("Failed to parse sync state")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize sync state")?;
+        fs::write(path, json).context("Failed to write sync state")
+    }
+}
+
+/// An account as the repository describes it. Where it's kept and when it
+/// was last used only mean something on one machine.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SyncedAccount {
+    pub saved_at: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub protected: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<AccountIdentity>,
+}
+
+#[derive(Serialize, Deserialize, Default, Debug)]
+pub struct SyncedAccounts {
+    pub accounts: BTreeMap<String, SyncedAccount>,
+}
+
+impl SyncedAccounts {
+    pub fn load(repo: &Path) -> Result<Self> {
+        let path = repo.join(ACCOUNTS_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(&path).context("Failed to read synced accounts")?;
+        serde_json::from_str(&contents).context("Failed to parse synced accounts")
+    }
+
+    pub fn save(&self, repo: &Path) -> Result<()> {
+        let json =
+            serde_json::to_string_pretty(self).context("Failed to serialize synced accounts")?;
+        fs::write(repo.join(ACCOUNTS_FILE), json + "\n").context("Failed to write synced accounts")
+    }
+
+    /// Each account's `saved_at`, for [`plan`].
+    pub fn saved_at(&self) -> BTreeMap<String, String> {
+        self.accounts
+            .iter()
+            .map(|(name, account)| (name.clone(), account.saved_at.clone()))
+            .collect()
+    }
+}
+
+/// Where the repository keeps the snapshot of `name`, escaped the way
+/// versions are so `client` and `client/dev` stay apart.
+pub fn account_dir(repo: &Path, name: &str) -> PathBuf {
+    repo.join(ACCOUNTS_DIR)
+        .join(name.replace('%', "%25").replace('/', "%2F"))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Saved here more recently, or only here: for `push`
+    Upload(String),
+    /// Deleted here since the last sync: for `push`
+    RemoveRemote(String),
+    /// Saved elsewhere more recently, or only there: for `pull`
+    Download(String),
+    /// Deleted elsewhere since the last sync: for `pull`
+    RemoveLocal(String),
+}
+
+/// What would bring the two sides together, given each account's `saved_at`
+/// here and in the repository, and which accounts both had last time.
+pub fn plan(
+    local: &BTreeMap<String, String>,
+    remote: &BTreeMap<String, String>,
+    synced: &BTreeSet<String>,
+) -> Vec<Action> {
+    let names: BTreeSet<&String> = local.keys().chain(remote.keys()).collect();
+    let mut changes = Vec::new();
+    for name in names {
+        let change = match (local.get(name), remote.get(name)) {
+            (Some(here), Some(there)) => match newer(here, there) {
+                Some(true) => Action::Upload(name.clone()),
+                Some(false) => Action::Download(name.clone()),
+                None => continue,
+            },
+            (Some(_), None) if synced.contains(name) => Action::RemoveLocal(name.clone()),
+            (Some(_), None) => Action::Upload(name.clone()),
+            (None, Some(_)) if synced.contains(name) => Action::RemoveRemote(name.clone()),
+            (None, Some(_)) => Action::Download(name.clone()),
+            (None, None) => continue,
+        };
+        changes.push(change);
+    }
+    changes
+}
+
+/// Whether `here` is the later time, or `None` when they're the same.
+fn newer(here: &str, there: &str) -> Option<bool> {
+    let order = match (
+        DateTime::parse_from_rfc3339(here),
+        DateTime::parse_from_rfc3339(there),
+    ) {
+        (Ok(here), Ok(there)) => here.cmp(&there),
+        _ => here.cmp(there),
+    };
+    order.is_ne().then_some(order.is_gt())
+}
+
+/// The accounts to remember as synced: those on both sides now, and those
+/// on one side that a change not made yet will still remove.
+pub fn next_synced<'a>(
+    local: impl IntoIterator<Item = &'a String>,
+    remote: impl IntoIterator<Item = &'a String>,
+    synced: &BTreeSet<String>,
+) -> BTreeSet<String> {
+    let local: BTreeSet<&String> = local.into_iter().collect();
+    let remote: BTreeSet<&String> = remote.into_iter().collect();
+    local
+        .union(&remote)
+        .filter(|name| (local.contains(*name) && remote.contains(*name)) || synced.contains(**name))
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// How the clone reaches the remote it's synced with.
+pub trait SyncBackend {
+    /// Sets up `mirror` for the first time, from what the remote has.
+    fn init(&self, mirror: &Path) -> Result<()>;
+    /// Brings `mirror` up to date, dropping anything left over in it.
+    fn fetch(&self, mirror: &Path) -> Result<()>;
+    /// Sends what changed in `mirror` since [`fetch`](Self::fetch). Returns
+    /// false when nothing did.
+    fn publish(&self, mirror: &Path, message: &str) -> Result<bool>;
+}
+
+/// The backend for `remote` as `sync init` was given it: `s3://bucket/prefix`,
+/// `webdav://host/path` (`webdav+http://` without TLS), or a git URL.
+pub fn backend(remote: &str, state_dir: &Path) -> Result<Box<dyn SyncBackend>> {
+    let record = state_dir.join(cloud::RECORD_FILE);
+    if let Some(rest) = remote.strip_prefix("s3://") {
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() {
+            anyhow::bail!(tr!("sync-bad-remote", remote = remote));
+        }
+        let store = S3::from_env(bucket, prefix)?;
+        return Ok(Box::new(ObjectBackend::new(Box::new(store), record)));
+    }
+    let webdav = [
+        ("webdav://", "https://"),
+        ("webdav+https://", "https://"),
+        ("webdav+http://", "http://"),
+    ]
+    .into_iter()
+    .find_map(|(scheme, http)| {
+        remote
+            .strip_prefix(scheme)
+            .map(|rest| format!("{}{}", http, rest))
+    });
+    if let Some(url) = webdav {
+        let store = WebDav::from_env(&url);
+        return Ok(Box::new(ObjectBackend::new(Box::new(store), record)));
+    }
+    Ok(Box::new(Git {
+        remote: remote.to_string(),
+    }))
+}
+
+/// A git repository, pushed to and fetched from with the `git` on `PATH`.
+pub struct Git {
+    pub remote: String,
+}
+
+impl SyncBackend for Git {
+    fn init(&self, mirror: &Path) -> Result<()> {
+        clone(&self.remote, mirror)
+    }
+
+    fn fetch(&self, mirror: &Path) -> Result<()> {
+        update(mirror)
+    }
+
+    fn publish(&self, mirror: &Path, message: &str) -> Result<bool> {
+        commit_and_push(mirror, message)
+    }
+}
+
+/// Clones `remote` into `dest`. An empty repository is fine.
+pub fn clone(remote: &str, dest: &Path) -> Result<()> {
+    let dest = dest.to_string_lossy();
+    git(Path::new("."), &["clone", "--quiet", remote, &dest])?;
+    Ok(())
+}
+
+/// Brings the clone up to date with the remote, dropping anything left over
+/// from a push that didn't get through.
+pub fn update(repo: &Path) -> Result<()> {
+    git(repo, &["fetch", "--quiet", "origin"])?;
+    let upstream = upstream(repo)?;
+    if run(repo, &["rev-parse", "--verify", "--quiet", &upstream])?
+        .status
+        .success()
+    {
+        git(repo, &["reset", "--quiet", "--hard", &upstream])?;
+    }
+    git(repo, &["clean", "--quiet", "-fd"])?;
+    Ok(())
+}
+
+/// Commits everything in the clone and pushes it. Returns false when there
+/// was nothing to commit.
+pub fn commit_and_push(repo: &Path, message: &str) -> Result<bool> {
+    git(repo, &["add", "--all"])?;
+    if git(repo, &["status", "--porcelain"])?.trim().is_empty() {
+        return Ok(false);
+    }
+    // Without an identity of its own, git refuses to commit
+    let mut commit = Vec::new();
+    if !run(repo, &["config", "user.email"])?.status.success() {
+        commit.extend([
+            "-c".to_string(),
+            format!("user.name={}", COMMIT_NAME),
+            "-c".to_string(),
+            format!("user.email={}", COMMIT_EMAIL),
+        ]);
+    }
+    commit.extend(["commit", "--quiet", "-m", message].map(str::to_string));
+    git(repo, &commit.iter().map(String::as_str).collect::<Vec<_>>())?;
+
+    let branch = git(repo, &["symbolic-ref", "--short", "HEAD"])?;
+    let refspec = format!("HEAD:refs/heads/{}", branch.trim());
+    git(repo, &["push", "--quiet", "origin", &refspec])?;
+    Ok(true)
+}
+
+fn upstream(repo: &Path) -> Result<String> {
+    let branch = git(repo, &["symbolic-ref", "--short", "HEAD"])?;
+    Ok(format!("refs/remotes/origin/{}", branch.trim()))
+}
+
+/// Runs git in `repo`, failing with what it printed when it fails.
+fn git(repo: &Path, args: &[&str]) -> Result<String> {
+    let output = run(repo, args)?;
+    if !output.status.success() {
+        anyhow::bail!(tr!(
+            "sync-git-failed",
+            command = args.first().copied().unwrap_or_default(),
+            output = String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn run(repo: &Path, args: &[&str]) -> Result<Output> {
+    tracing::debug!(repo = %repo.display(), ?args, "git");
+    Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        // A remote that wants a password fails rather than waiting on it
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .context(tr!("sync-git-missing"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn times(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+        entries
+            .iter()
+            .map(|(name, time)| (name.to_string(), time.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_merges_by_name() {
+        let local = times(&[
+            ("newer-here", "2024-01-02T00:00:00+00:00"),
+            ("newer-there", "2024-01-01T00:00:00+00:00"),
+            ("same", "2024-01-01T00:00:00+00:00"),
+            ("new-here", "2024-01-01T00:00:00+00:00"),
+            ("deleted-there", "2024-01-01T00:00:00+00:00"),
+        ]);
+        let remote = times(&[
+            ("newer-here", "2024-01-01T00:00:00Z"),
+            ("newer-there", "2024-01-03T00:00:00Z"),
+            ("same", "2024-01-01T00:00:00Z"),
+            ("new-there", "2024-01-01T00:00:00Z"),
+            ("deleted-here", "2024-01-01T00:00:00Z"),
+        ]);
+        let synced = ["same", "deleted-here", "deleted-there"]
+            .map(str::to_string)
+            .into();
+
+        assert_eq!(
+            plan(&local, &remote, &synced),
+            [
+                Action::RemoveRemote("deleted-here".to_string()),
+                Action::RemoveLocal("deleted-there".to_string()),
+                Action::Upload("new-here".to_string()),
+                Action::Download("new-there".to_string()),
+                Action::Upload("newer-here".to_string()),
+                Action::Download("newer-there".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_next_synced_keeps_pending_deletes() {
+        let local = ["both", "deleted-there", "new-here"].map(str::to_string);
+        let remote = ["both", "new-there"].map(str::to_string);
+        let synced = ["both", "deleted-there", "gone"].map(str::to_string).into();
+
+        let next = next_synced(&local, &remote, &synced);
+        // Neither new one is, or the other side would take it as deleted
+        assert_eq!(next, ["both", "deleted-there"].map(str::to_string).into());
+    }
+
+    #[test]
+    fn test_escaped_account_dirs() {
+        let repo = Path::new("repo");
+        assert_eq!(
+            account_dir(repo, "client/dev"),
+            Path::new("repo/accounts/client%2Fdev")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_push_reaches_another_clone() -> Result<()> {
+        let dir = TempDir::new()?;
+        let remote = dir.path().join("remote.git");
+        git(
+            dir.path(),
+            &["init", "--quiet", "--bare", &remote.to_string_lossy()],
+        )?;
+        let remote = remote.to_string_lossy();
+
+        let first = dir.path().join("first");
+        clone(&remote, &first)?;
+        SyncedAccounts::default().save(&first)?;
+        assert!(commit_and_push(&first, "Add accounts")?);
+        assert!(!commit_and_push(&first, "Nothing")?);
+
+        let second = dir.path().join("second");
+        clone(&remote, &second)?;
+        assert!(second.join(ACCOUNTS_FILE).is_file());
+        fs::write(second.join(KEY_FILE), "{}")?;
+        commit_and_push(&second, "Add key")?;
+
+        // Left over from a push that failed
+        fs::write(first.join("stray"), "")?;
+        update(&first)?;
+        assert!(first.join(KEY_FILE).is_file());
+        assert!(!first.join("stray").exists());
+        Ok(())
+    }
+}
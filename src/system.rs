@@ -0,0 +1,515 @@
+//! The system-wide store, used with `--system` or the `system` setting: an
+//! administrator saves team accounts into `/var/lib/claude-account-switcher`
+//! (or the `system-dir` setting), and every user on the machine switches to
+//! them from there.
+//!
+//! The snapshots and the accounts file are shared. Which account is active
+//! is each user's own, kept in `active.json` in their state directory with
+//! their history, locks and caches, so one user's switch doesn't change
+//! another's. A user who can't write the store can still switch: what's only
+//! bookkeeping (when an account was last used, how often) isn't written back
+//! for them, and their accounts' changes aren't saved.
+//!
+//! Snapshots are made readable by the store's group and no one else, so the
+//! store should belong to the team's group with the setgid bit set on it.
+//! The live directory they're copied into is made private to its user.
+//!
+//! An `access.toml` in the store narrows which accounts each user sees: its
+//! `[users]` and `[groups]` tables grant account patterns, and a user who
+//! can't write the store only lists and switches to the accounts matching
+//! those granted to them or to one of their groups. The file stops the
+//! switcher from showing the rest, the snapshots' own groups stop anyone
+//! from reading them, so an account kept from some of the team should also
+//! belong to a group they aren't in.
+
+use crate::capture;
+use crate::config::AccountsConfig;
+use crate::paths::Paths;
+use crate::settings::Settings;
+use crate::tr;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where the store is unless `system-dir` says otherwise
+#[cfg(not(windows))]
+pub const DEFAULT_DIR: &str = "/var/lib/claude-account-switcher";
+#[cfg(windows)]
+pub const DEFAULT_DIR: &str = r"C:\ProgramData\claude-account-switcher";
+/// Each user's active account, in their state directory
+pub const ACTIVE_FILE: &str = "active.json";
+/// Who may see which accounts, in the store
+pub const ACCESS_FILE: &str = "access.toml";
+
+// What a switch records about an account; a user who can't write the store
+// goes without it
+const BOOKKEEPING: &[&str] = &["last_used", "switch_count", "identity"];
+
+/// The store the settings choose, `--system` (`flag`) or not.
+pub fn root(settings: &Settings, flag: bool) -> Option<PathBuf> {
+    (flag || settings.system).then(|| {
+        settings
+            .system_dir
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_DIR))
+    })
+}
+
+/// Fails unless the store in `root` has been set up.
+pub fn check(root: &Path) -> Result<()> {
+    if !root.is_dir() {
+        anyhow::bail!(tr!("system-missing", path = root.display()));
+    }
+    Ok(())
+}
+
+/// The account patterns `access.toml` grants, by user and by group.
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Access {
+    #[serde(default)]
+    users: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    groups: BTreeMap<String, Vec<String>>,
+}
+
+impl Access {
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(path).context("Failed to read access rules")?;
+        toml::from_str(&contents).map(Some).map_err(|e| {
+            anyhow::anyhow!(tr!(
+                "system-access-invalid",
+                path = path.display(),
+                error = e
+            ))
+        })
+    }
+
+    /// What `user`, in `groups`, is granted.
+    pub fn grant(&self, user: &str, groups: &[String]) -> Grant {
+        let by_group = groups.iter().filter_map(|group| self.groups.get(group));
+        Grant(
+            self.users
+                .get(user)
+                .into_iter()
+                .chain(by_group)
+                .flatten()
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
+/// The account patterns one user is granted; `*` goes across namespaces.
+#[derive(Debug, Default)]
+pub struct Grant(Vec<String>);
+
+impl Grant {
+    pub fn allows(&self, name: &str) -> bool {
+        self.0
+            .iter()
+            .any(|pattern| capture::glob_match(pattern, name))
+    }
+}
+
+/// What this user is granted of the store in `root`. `None` when they see
+/// every account: there's no `access.toml`, or they can write the store.
+pub fn grant(root: &Path) -> Result<Option<Grant>> {
+    let Some(access) = Access::load(&root.join(ACCESS_FILE))? else {
+        return Ok(None);
+    };
+    if writable(root) {
+        return Ok(None);
+    }
+    // Without a name to go by, nothing's granted
+    let (user, groups) = current_user().unwrap_or_default();
+    Ok(Some(access.grant(&user, &groups)))
+}
+
+/// Keeps the accounts of `config` that `grant` allows.
+pub fn restrict(config: &mut AccountsConfig, grant: &Grant) {
+    config.accounts.retain(|name, _| grant.allows(name));
+}
+
+/// This user's name and their groups' names, as the system has them rather
+/// than as the environment says.
+#[cfg(unix)]
+fn current_user() -> Option<(String, Vec<String>)> {
+    use std::ffi::CStr;
+    let mut buf = vec![0 as libc::c_char; 16 * 1024];
+    // SAFETY: the records point into `buf`, which outlives every read of
+    // them, and `getgroups` is given the length of `gids`
+    unsafe {
+        let mut passwd: libc::passwd = std::mem::zeroed();
+        let mut found = std::ptr::null_mut();
+        let status = libc::getpwuid_r(
+            libc::getuid(),
+            &mut passwd,
+            buf.as_mut_ptr(),
+            buf.len(),
+            &mut found,
+        );
+        if status != 0 || found.is_null() {
+            return None;
+        }
+        let user = CStr::from_ptr(passwd.pw_name)
+            .to_string_lossy()
+            .into_owned();
+
+        let count = libc::getgroups(0, std::ptr::null_mut());
+        let mut gids = vec![0 as libc::gid_t; count.max(0) as usize];
+        let count = libc::getgroups(gids.len() as libc::c_int, gids.as_mut_ptr());
+        gids.truncate(count.max(0) as usize);
+        gids.push(libc::getgid());
+        let groups = gids
+            .into_iter()
+            .filter_map(|gid| {
+                let mut group: libc::group = std::mem::zeroed();
+                let mut found = std::ptr::null_mut();
+                let status =
+                    libc::getgrgid_r(gid, &mut group, buf.as_mut_ptr(), buf.len(), &mut found);
+                (status == 0 && !found.is_null())
+                    .then(|| CStr::from_ptr(group.gr_name).to_string_lossy().into_owned())
+            })
+            .collect();
+        Some((user, groups))
+    }
+}
+
+#[cfg(not(unix))]
+fn current_user() -> Option<(String, Vec<String>)> {
+    Some((std::env::var("USERNAME").ok()?, Vec::new()))
+}
+
+/// This user's share of the accounts file: which account is active, and
+/// which was before it.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct Activation {
+    pub current: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous: Option<String>,
+}
+
+impl Activation {
+    pub fn of(config: &AccountsConfig) -> Self {
+        Self {
+            current: config.current.clone(),
+            previous: config.previous.clone(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let contents = fs::read_to_string(path).context("Failed to read active account")?;
+        serde_json::from_str(&contents).context("Failed to parse active account")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let contents =
+            serde_json::to_string_pretty(self).context("Failed to serialize active account")?;
+        fs::write(path, contents).context("Failed to write active account")
+    }
+
+    /// Lays this over the shared `config`, leaving out accounts it no longer
+    /// has.
+    pub fn apply(self, config: &mut AccountsConfig) {
+        let known = |name: &String| config.accounts.contains_key(name);
+        config.current = self.current.filter(known);
+        config.previous = self.previous.filter(known);
+    }
+}
+
+/// Writes `config` to the shared accounts file at `path`, without anyone's
+/// active account, and with the accounts `grant` kept from this user as
+/// they are. Nothing is written when that's what's there already, nor when
+/// only bookkeeping changed and this user can't write it; any other change
+/// by such a user is an error.
+pub fn save_shared(path: &Path, config: &AccountsConfig, grant: Option<&Grant>) -> Result<()> {
+    let mut shared: Value =
+        serde_json::from_str(&config.to_json()?).context("Failed to serialize configuration")?;
+    if let Some(shared) = shared.as_object_mut() {
+        shared.insert("current".to_string(), Value::Null);
+        shared.remove("previous");
+    }
+    let stored = fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<Value>(&contents).ok());
+    if let (Some(grant), Some(stored)) = (grant, &stored) {
+        let hidden = stored["accounts"]
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter(|(name, _)| !grant.allows(name));
+        if let Some(accounts) = shared["accounts"].as_object_mut() {
+            for (name, meta) in hidden {
+                accounts.insert(name.clone(), meta.clone());
+            }
+        }
+    }
+    if stored.as_ref() == Some(&shared) {
+        return Ok(());
+    }
+    if !writable(path) {
+        if stored.map(without_bookkeeping) == Some(without_bookkeeping(shared)) {
+            return Ok(());
+        }
+        anyhow::bail!(tr!("system-read-only", path = path.display()));
+    }
+    let contents =
+        serde_json::to_string_pretty(&shared).context("Failed to serialize configuration")?;
+    fs::write(path, contents).context("Failed to write accounts configuration file")?;
+    share(path)
+}
+
+fn without_bookkeeping(mut document: Value) -> Value {
+    if let Some(document) = document.as_object_mut() {
+        document.remove("revision");
+    }
+    if let Some(accounts) = document.get_mut("accounts").and_then(Value::as_object_mut) {
+        for meta in accounts.values_mut().filter_map(Value::as_object_mut) {
+            meta.retain(|key, _| !BOOKKEEPING.contains(&key.as_str()));
+        }
+    }
+    document
+}
+
+/// The accounts file as the manager reads it, for the commands that run
+/// before it's set up (`prompt`, `hook-env` and the directory hook): in
+/// system mode, the shared one with this user's active account. `None` when
+/// it can't be read.
+pub fn accounts_contents() -> Option<String> {
+    let paths = Paths::current().ok()?;
+    // A broken settings file is for the commands that read it to report
+    let settings = Settings::load(&paths.settings_file)
+        .and_then(|settings| settings.with_env(std::env::vars()))
+        .unwrap_or_default();
+    let Some(root) = root(&settings, false) else {
+        return fs::read_to_string(&paths.accounts_file).ok();
+    };
+    let paths = paths.system(&root);
+    let contents = fs::read_to_string(&paths.accounts_file).ok()?;
+    let mut config: AccountsConfig = serde_json::from_str(&contents).ok()?;
+    if let Some(grant) = grant(&root).ok()? {
+        restrict(&mut config, &grant);
+    }
+    Activation::load(&paths.state_dir.join(ACTIVE_FILE))
+        .ok()?
+        .apply(&mut config);
+    config.to_json().ok()
+}
+
+/// Creates the store's accounts directory `dir` in `root`, shared with the
+/// team, when it isn't there and this user can. One that's there is left as
+/// it is: its files are likely someone else's, whose modes no one but they
+/// could change.
+pub fn create_store(root: &Path, dir: &Path) -> Result<()> {
+    if dir.exists() || !writable(root) {
+        return Ok(());
+    }
+    fs::create_dir_all(dir).with_context(|| tr!("failed-create-account-storage-directory"))?;
+    share(dir)
+}
+
+/// Whether this user can change `path`, or create it when it doesn't exist.
+pub fn writable(path: &Path) -> bool {
+    let path = match (path.exists(), path.parent()) {
+        (false, Some(parent)) => parent,
+        _ => path,
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        let Ok(path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+            return false;
+        };
+        // SAFETY: `path` is a valid C string for the length of the call
+        unsafe { libc::access(path.as_ptr(), libc::W_OK) == 0 }
+    }
+    #[cfg(not(unix))]
+    {
+        fs::metadata(path).is_ok_and(|metadata| !metadata.permissions().readonly())
+    }
+}
+
+/// Makes `path`, and everything in it when it's a directory, readable by
+/// its group and no one else: for what's saved into the store. Directories
+/// get the setgid bit, so what's saved into them later keeps the group.
+pub fn share(path: &Path) -> Result<()> {
+    set_modes(path, 0o2750, |mode| match mode & 0o100 {
+        0 => 0o640,
+        _ => 0o750,
+    })
+}
+
+/// Makes `path`, and everything in it when it's a directory, its owner's
+/// alone: for the live directory copied out of the store.
+pub fn make_private(path: &Path) -> Result<()> {
+    set_modes(path, 0o700, |mode| mode & 0o700)
+}
+
+fn set_modes(path: &Path, dir_mode: u32, file_mode: impl Fn(u32) -> u32 + Copy) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let metadata = fs::symlink_metadata(path)
+            .with_context(|| format!("Failed to read permissions: {}", path.display()))?;
+        if metadata.is_symlink() {
+            return Ok(());
+        }
+        let mode = match metadata.is_dir() {
+            true => dir_mode,
+            false => file_mode(metadata.permissions().mode()),
+        };
+        fs::set_permissions(path, fs::Permissions::from_mode(mode))
+            .with_context(|| format!("Failed to set permissions: {}", path.display()))?;
+        if metadata.is_dir() {
+            for entry in fs::read_dir(path)
+                .with_context(|| format!("Failed to read directory: {}", path.display()))?
+            {
+                set_modes(&entry?.path(), dir_mode, file_mode)?;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = (path, dir_mode, file_mode);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn config(contents: &str) -> AccountsConfig {
+        serde_json::from_str(contents).unwrap()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_existing_store_is_left_as_it_is() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+        let root = TempDir::new()?;
+        let dir = root.path().join("accounts");
+        create_store(root.path(), &dir)?;
+        assert_eq!(fs::metadata(&dir)?.permissions().mode() & 0o7777, 0o2750);
+
+        // Another user's store: changing its modes would fail for us
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o755))?;
+        fs::write(dir.join("accounts.json"), "{}")?;
+        create_store(root.path(), &dir)?;
+        assert_eq!(fs::metadata(&dir)?.permissions().mode() & 0o7777, 0o755);
+        Ok(())
+    }
+
+    #[test]
+    fn test_activation_is_laid_over_the_shared_file() {
+        let mut shared = config(
+            r#"{"current": "admin", "accounts": {
+                "team": {"saved_at": "2024-01-01T00:00:00+00:00", "path": "/store/team"}
+            }}"#,
+        );
+        Activation {
+            current: Some("team".to_string()),
+            previous: Some("gone".to_string()),
+        }
+        .apply(&mut shared);
+        assert_eq!(shared.current.as_deref(), Some("team"));
+        assert_eq!(shared.previous, None);
+    }
+
+    #[test]
+    fn test_save_shared_leaves_out_the_active_account() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("accounts.json");
+        let mut shared = config(
+            r#"{"current": "team", "accounts": {
+                "team": {"saved_at": "2024-01-01T00:00:00+00:00", "path": "/store/team"}
+            }}"#,
+        );
+        save_shared(&path, &shared, None)?;
+        let stored = AccountsConfig::load(&path)?;
+        assert_eq!(stored.current, None);
+        assert!(stored.accounts.contains_key("team"));
+
+        // Bookkeeping alone is all the same when it can't be written
+        shared.accounts.get_mut("team").unwrap().switch_count = 3;
+        let written = serde_json::from_str::<Value>(&fs::read_to_string(&path)?)?;
+        let changed = serde_json::from_str::<Value>(&shared.to_json()?)?;
+        assert_eq!(
+            without_bookkeeping(written)["accounts"],
+            without_bookkeeping(changed)["accounts"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_access_grants_by_user_and_group() -> Result<()> {
+        let access: Access = toml::from_str(
+            r#"
+            [users]
+            alice = ["client/*"]
+
+            [groups]
+            lab = ["pool-?"]
+            "#,
+        )?;
+        let grant = access.grant("alice", &["lab".to_string()]);
+        assert!(grant.allows("client/dev"));
+        assert!(grant.allows("pool-1"));
+        assert!(!grant.allows("admin"));
+        assert!(!access.grant("bob", &[]).allows("pool-1"));
+
+        // What's kept from this user goes back as it was
+        let dir = TempDir::new()?;
+        let path = dir.path().join("accounts.json");
+        let mut config = config(
+            r#"{"accounts": {
+                "pool-1": {"saved_at": "2024-01-01T00:00:00+00:00", "path": "/store/pool-1"},
+                "admin": {"saved_at": "2024-01-01T00:00:00+00:00", "path": "/store/admin"}
+            }}"#,
+        );
+        save_shared(&path, &config, None)?;
+        restrict(&mut config, &grant);
+        assert_eq!(config.accounts.len(), 1);
+        save_shared(&path, &config, Some(&grant))?;
+        assert!(AccountsConfig::load(&path)?.accounts.contains_key("admin"));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_share_and_make_private() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = TempDir::new()?;
+        let snapshot = dir.path().join("team");
+        fs::create_dir_all(snapshot.join("hooks"))?;
+        fs::write(snapshot.join(".credentials.json"), "{}")?;
+        fs::write(snapshot.join("hooks/check.sh"), "")?;
+        fs::set_permissions(
+            snapshot.join("hooks/check.sh"),
+            fs::Permissions::from_mode(0o755),
+        )?;
+        let mode = |path: &str| -> Result<u32> {
+            Ok(fs::metadata(snapshot.join(path))?.permissions().mode() & 0o7777)
+        };
+
+        share(&snapshot)?;
+        assert_eq!(mode("")?, 0o2750);
+        assert_eq!(mode(".credentials.json")?, 0o640);
+        assert_eq!(mode("hooks/check.sh")?, 0o750);
+
+        make_private(&snapshot)?;
+        assert_eq!(mode("hooks")?, 0o700);
+        assert_eq!(mode(".credentials.json")?, 0o600);
+        assert_eq!(mode("hooks/check.sh")?, 0o700);
+        Ok(())
+    }
+}
@@ -0,0 +1,74 @@
+//! Aligned columns for listings, each as wide as its widest cell, so what's
+//! shown can grow without every line being formatted by hand.
+
+/// Rows of cells under a header, laid out by `render`.
+pub struct Table {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+// Between two columns
+const GAP: &str = "  ";
+
+impl Table {
+    pub fn new(header: Vec<String>) -> Self {
+        Self {
+            header,
+            rows: Vec::new(),
+        }
+    }
+
+    /// Adds a row, with a cell for each column of the header.
+    pub fn push(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+
+    /// The header line and then one line per row. The last column isn't
+    /// padded, so no line ends in spaces.
+    pub fn render(&self) -> Vec<String> {
+        let mut widths: Vec<usize> = self.header.iter().map(|cell| width(cell)).collect();
+        for row in &self.rows {
+            for (width_of, cell) in widths.iter_mut().zip(row) {
+                *width_of = (*width_of).max(width(cell));
+            }
+        }
+
+        std::iter::once(&self.header)
+            .chain(&self.rows)
+            .map(|row| {
+                let last = row.len().saturating_sub(1);
+                let cells: Vec<String> = row
+                    .iter()
+                    .zip(&widths)
+                    .enumerate()
+                    .map(|(i, (cell, &width_of))| match i == last {
+                        true => cell.clone(),
+                        false => format!("{}{}", cell, " ".repeat(width_of - width(cell))),
+                    })
+                    .collect();
+                cells.join(GAP).trim_end().to_string()
+            })
+            .collect()
+    }
+}
+
+// Characters rather than bytes, for names and notes that aren't ASCII
+fn width(cell: &str) -> usize {
+    cell.chars().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_columns_fit_their_widest_cell() {
+        let mut table = Table::new(vec!["NAME".to_string(), "SIZE".to_string()]);
+        table.push(vec!["work".to_string(), "12 KB".to_string()]);
+        table.push(vec!["café-client".to_string(), String::new()]);
+        assert_eq!(
+            table.render(),
+            ["NAME         SIZE", "work         12 KB", "café-client"]
+        );
+    }
+}
@@ -0,0 +1,104 @@
+//! Templates: the settings of a configuration without its login, so one
+//! setup (a strict `settings.json`, shared instructions, MCP servers) can be
+//! layered over whichever account is active.
+//!
+//! Templates live in `.templates/<name>` next to the snapshots. The MCP
+//! servers are kept on their own in `mcp.json`, as `.claude.json` around
+//! them also holds the login.
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const DIR: &str = ".templates";
+/// Files a template takes as they are
+pub const FILES: &[&str] = &["settings.json", "CLAUDE.md"];
+/// Where Claude Code keeps the MCP servers, along with the login
+pub const CLAUDE_JSON: &str = ".claude.json";
+pub const MCP_FILE: &str = "mcp.json";
+pub const MCP_KEY: &str = "mcpServers";
+
+/// Where the template `name` is kept, escaped the way versions are.
+pub fn dir(root: &Path, name: &str) -> PathBuf {
+    root.join(DIR)
+        .join(name.replace('%', "%25").replace('/', "%2F"))
+}
+
+/// The names of the saved templates, sorted.
+pub fn list(root: &Path) -> Result<Vec<String>> {
+    let dir = root.join(DIR);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut names = Vec::new();
+    for entry in fs::read_dir(&dir).context("Failed to read templates directory")? {
+        let entry = entry.context("Failed to read templates directory")?;
+        if entry.file_type()?.is_dir() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            names.push(name.replace("%2F", "/").replace("%25", "%"));
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Layers `layer` over `base`: objects are merged key by key, anything else
+/// in `layer` replaces what `base` had.
+pub fn merge(base: &mut Value, layer: Value) {
+    match (base, layer) {
+        (Value::Object(base), Value::Object(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_merge_layers_objects() {
+        let mut base = json!({
+            "model": "sonnet",
+            "permissions": {"allow": ["Read"], "defaultMode": "default"},
+            "env": {"KEEP": "1"},
+        });
+        merge(
+            &mut base,
+            json!({
+                "permissions": {"allow": ["Bash(git:*)"], "defaultMode": "plan"},
+                "env": {"STRICT": "1"},
+            }),
+        );
+        assert_eq!(
+            base,
+            json!({
+                "model": "sonnet",
+                "permissions": {"allow": ["Bash(git:*)"], "defaultMode": "plan"},
+                "env": {"KEEP": "1", "STRICT": "1"},
+            })
+        );
+    }
+
+    #[test]
+    fn test_list_unescapes_names() -> Result<()> {
+        let root = TempDir::new()?;
+        assert!(list(root.path())?.is_empty());
+        fs::create_dir_all(dir(root.path(), "strict"))?;
+        fs::create_dir_all(dir(root.path(), "team/review"))?;
+        assert_eq!(list(root.path())?, ["strict", "team/review"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+//! `--timings`: where a command's time went. Scanning, hashing, copying and
+//! the accounts file each add up over the whole run, the save ahead of a
+//! switch included, and are reported on stderr once the command is done.
+//!
+//! Nothing is measured unless `enable` was called, so the phases cost an
+//! atomic load otherwise.
+
+use crate::tr;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum Phase {
+    /// Walking directories for the files to capture or copy
+    Scanning,
+    /// Hashing files, for manifests and unsaved changes
+    Hashing,
+    /// Copying and linking files
+    Copying,
+    /// Reading and writing the accounts file
+    Config,
+}
+
+impl Phase {
+    pub fn label(self) -> String {
+        match self {
+            Self::Scanning => tr!("timing-scanning"),
+            Self::Hashing => tr!("timing-hashing"),
+            Self::Copying => tr!("timing-copying"),
+            Self::Config => tr!("timing-config"),
+        }
+    }
+}
+
+/// The time spent in a phase, over how many times it was entered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Spent {
+    pub elapsed: Duration,
+    pub count: u64,
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static STARTED: OnceLock<Instant> = OnceLock::new();
+static SPENT: Mutex<BTreeMap<Phase, Spent>> = Mutex::new(BTreeMap::new());
+
+/// Starts measuring, for the rest of the run.
+pub fn enable() {
+    let _ = STARTED.set(Instant::now());
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Runs `f`, adding the time it took to `phase`. Phases aren't meant to
+/// nest: the time would be counted twice.
+pub fn time<T>(phase: Phase, f: impl FnOnce() -> T) -> T {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return f();
+    }
+    let started = Instant::now();
+    let result = f();
+    add(phase, started.elapsed());
+    result
+}
+
+/// Adds the time until it's dropped to its phase, for a loop `time` can't
+/// wrap.
+#[must_use]
+pub struct Timer(Option<(Phase, Instant)>);
+
+impl Timer {
+    pub fn start(phase: Phase) -> Self {
+        Self(
+            ENABLED
+                .load(Ordering::Relaxed)
+                .then(|| (phase, Instant::now())),
+        )
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        if let Some((phase, started)) = self.0 {
+            add(phase, started.elapsed());
+        }
+    }
+}
+
+fn add(phase: Phase, elapsed: Duration) {
+    let mut spent = SPENT.lock().unwrap_or_else(|e| e.into_inner());
+    let spent = spent.entry(phase).or_default();
+    spent.elapsed += elapsed;
+    spent.count += 1;
+}
+
+/// What each phase took so far, in order, and how long the run has taken,
+/// or nothing when measuring wasn't enabled.
+pub fn report() -> Option<(Vec<(Phase, Spent)>, Duration)> {
+    let started = STARTED.get()?;
+    let spent = SPENT.lock().unwrap_or_else(|e| e.into_inner());
+    Some((
+        spent
+            .iter()
+            .map(|(phase, spent)| (*phase, *spent))
+            .collect(),
+        started.elapsed(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phases_add_up() {
+        add(Phase::Copying, Duration::from_millis(30));
+        add(Phase::Copying, Duration::from_millis(20));
+        add(Phase::Scanning, Duration::from_millis(5));
+        let spent = SPENT.lock().unwrap();
+        assert_eq!(
+            spent[&Phase::Copying],
+            Spent {
+                elapsed: Duration::from_millis(50),
+                count: 2
+            }
+        );
+        // Listed in the order the phases come in a save
+        assert_eq!(spent.keys().next(), Some(&Phase::Scanning));
+    }
+}
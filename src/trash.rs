@@ -0,0 +1,215 @@
+//! Deleted accounts, kept for a while so `undo` or `trash restore` can bring
+//! one back.
+//!
+//! Each delete lives in `.trash/<account>-<id>` next to the snapshots, where
+//! the id is the time it was deleted (`20240101T093000Z`): the snapshot in
+//! `snapshot/`, its versions in `versions/` and its details in
+//! `account.json`. Deleting an account of the same name again keeps both.
+
+use crate::config::AccountMetadata;
+use crate::retry::retry;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const DIR: &str = ".trash";
+/// How long a deleted account is kept unless `keep-trash` says otherwise
+pub const DEFAULT_KEEP: Duration = Duration::days(30);
+
+const ID_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+const META_FILE: &str = "account.json";
+const SNAPSHOT_DIR: &str = "snapshot";
+const VERSIONS_DIR: &str = "versions";
+
+#[derive(Serialize, Deserialize)]
+pub struct Trashed {
+    pub name: String,
+    pub deleted_at: String,
+    pub meta: AccountMetadata,
+}
+
+impl Trashed {
+    pub fn deleted_at(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.deleted_at)
+            .ok()
+            .map(|time| time.with_timezone(&Utc))
+    }
+}
+
+/// One delete in the trash.
+pub struct Entry {
+    /// The directory it's kept in
+    pub path: PathBuf,
+    pub trashed: Trashed,
+}
+
+pub fn snapshot_dir(entry: &Path) -> PathBuf {
+    entry.join(SNAPSHOT_DIR)
+}
+
+pub fn versions_dir(entry: &Path) -> PathBuf {
+    entry.join(VERSIONS_DIR)
+}
+
+/// Makes a place for `name` deleted at `now`, so a snapshot and versions can
+/// be moved in. `name` is escaped the way versions are; two deletes within
+/// the same second get `-2`, `-3`, ... suffixes.
+pub fn allocate(root: &Path, name: &str, now: DateTime<Utc>) -> Result<PathBuf> {
+    let trash = root.join(DIR);
+    fs::create_dir_all(&trash).context("Failed to create trash directory")?;
+
+    let base = format!(
+        "{}-{}",
+        name.replace('%', "%25").replace('/', "%2F"),
+        now.format(ID_FORMAT)
+    );
+    let mut path = trash.join(&base);
+    for n in 2.. {
+        if !path.exists() {
+            break;
+        }
+        path = trash.join(format!("{}-{}", base, n));
+    }
+    fs::create_dir(&path).context("Failed to create trash directory")?;
+    Ok(path)
+}
+
+/// Records the details of a delete, before its files are moved into
+/// `entry`: one without them is taken for a broken entry and purged.
+pub fn write(entry: &Path, trashed: &Trashed) -> Result<()> {
+    let json = serde_json::to_string_pretty(trashed).context("Failed to serialize account")?;
+    fs::write(entry.join(META_FILE), json).context("Failed to write trashed account")
+}
+
+/// Everything in the trash that can be read, most recently deleted first.
+pub fn list(root: &Path) -> Result<Vec<Entry>> {
+    let trash = root.join(DIR);
+    if !trash.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(&trash).context("Failed to read trash directory")? {
+        let path = entry.context("Failed to read trash directory")?.path();
+        if let Some(trashed) = read(&path) {
+            entries.push(Entry { path, trashed });
+        }
+    }
+    // Within a second, the `-2` after the first
+    entries.sort_by_key(|entry| Reverse((entry.trashed.deleted_at(), entry.path.clone())));
+    Ok(entries)
+}
+
+/// The most recent delete of `name`, if the trash still has one.
+pub fn latest(root: &Path, name: &str) -> Result<Option<Entry>> {
+    Ok(list(root)?
+        .into_iter()
+        .find(|entry| entry.trashed.name == name))
+}
+
+pub fn remove(entry: &Path) -> Result<()> {
+    if !entry.exists() {
+        return Ok(());
+    }
+    retry(|| fs::remove_dir_all(entry)).context("Failed to remove trashed account")
+}
+
+/// Removes what was deleted before `before`, or everything without it, and
+/// anything that can't be read. Returns how many entries went.
+pub fn purge(root: &Path, before: Option<DateTime<Utc>>) -> Result<usize> {
+    let trash = root.join(DIR);
+    if !trash.is_dir() {
+        return Ok(0);
+    }
+
+    let mut purged = 0;
+    for entry in fs::read_dir(&trash).context("Failed to read trash directory")? {
+        let path = entry.context("Failed to read trash directory")?.path();
+        let deleted_at = read(&path).and_then(|trashed| trashed.deleted_at());
+        let expired = match (deleted_at, before) {
+            (Some(deleted_at), Some(before)) => deleted_at < before,
+            _ => true,
+        };
+        if expired {
+            retry(|| fs::remove_dir_all(&path)).context("Failed to remove trashed account")?;
+            purged += 1;
+        }
+    }
+    Ok(purged)
+}
+
+fn read(entry: &Path) -> Option<Trashed> {
+    let contents = fs::read_to_string(entry.join(META_FILE)).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn time(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn trash(root: &Path, name: &str, deleted_at: &str) -> Result<PathBuf> {
+        let entry = allocate(root, name, time(deleted_at))?;
+        write(
+            &entry,
+            &Trashed {
+                name: name.to_string(),
+                deleted_at: deleted_at.to_string(),
+                meta: AccountMetadata {
+                    saved_at: deleted_at.to_string(),
+                    path: root.join(name),
+                    ..Default::default()
+                },
+            },
+        )?;
+        Ok(entry)
+    }
+
+    #[test]
+    fn test_deletes_of_one_name_are_kept_apart() -> Result<()> {
+        let root = TempDir::new()?;
+        let first = trash(root.path(), "team/dev", "2024-01-01T00:00:00+00:00")?;
+        let second = trash(root.path(), "team/dev", "2024-01-01T00:00:00+00:00")?;
+        trash(root.path(), "team/dev", "2024-01-05T00:00:00+00:00")?;
+
+        assert_ne!(first, second);
+        assert!(second.ends_with("team%2Fdev-20240101T000000Z-2"));
+        assert_eq!(list(root.path())?.len(), 3);
+        let newest = latest(root.path(), "team/dev")?.unwrap();
+        assert_eq!(newest.trashed.deleted_at, "2024-01-05T00:00:00+00:00");
+        assert!(latest(root.path(), "team")?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_purge_keeps_recent_deletes() -> Result<()> {
+        let root = TempDir::new()?;
+        trash(root.path(), "old", "2024-01-01T00:00:00+00:00")?;
+        trash(root.path(), "team/recent", "2024-01-09T00:00:00+00:00")?;
+        fs::create_dir_all(root.path().join(DIR).join("unreadable"))?;
+
+        assert_eq!(
+            purge(root.path(), Some(time("2024-01-05T00:00:00+00:00")))?,
+            2
+        );
+        let entries = list(root.path())?;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries[0].trashed.meta.path,
+            root.path().join("team/recent")
+        );
+
+        assert_eq!(purge(root.path(), None)?, 1);
+        assert!(list(root.path())?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,571 @@
+//! `tui`: a dashboard listing every account with its login details, with
+//! keys to switch, rename, delete and back up without leaving it.
+//!
+//! Actions run with the terminal handed back to the shell, so their output
+//! and any prompts (a protected account, the store passphrase) work exactly
+//! as they do on the command line.
+//!
+//! The list is drawn from the config as soon as the dashboard opens; login
+//! details and sizes are read in the background and fill in as they arrive.
+
+use crate::loader::{self, Loaded};
+use crate::manager::{AccountManager, AccountSummary};
+use crate::prompt;
+use crate::size;
+use crate::tr;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState};
+use ratatui::{DefaultTerminal, Frame};
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::thread::{self, Scope};
+use std::time::Duration;
+
+// How often the screen refreshes while details are still loading
+const LOAD_POLL: Duration = Duration::from_millis(100);
+// Shown in place of a value that hasn't been read yet
+const PENDING: &str = "…";
+
+/// What the dashboard asks the manager to do after a key press.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Action {
+    None,
+    Quit,
+    Switch(String),
+    Delete(String),
+    Rename(String, String),
+    /// Re-save the live session into the active account
+    Backup(String),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Mode {
+    Browse,
+    ConfirmDelete(String),
+    Rename { from: String, to: String },
+}
+
+pub struct App {
+    accounts: Vec<AccountSummary>,
+    table: TableState,
+    mode: Mode,
+    status: Option<String>,
+    now: DateTime<Utc>,
+}
+
+impl App {
+    pub fn new(accounts: Vec<AccountSummary>, now: DateTime<Utc>) -> Self {
+        let selected = accounts.iter().position(|account| account.current);
+        let mut app = Self {
+            accounts,
+            table: TableState::default(),
+            mode: Mode::Browse,
+            status: None,
+            now,
+        };
+        app.table
+            .select(selected.or(Some(0)).filter(|_| !app.accounts.is_empty()));
+        app
+    }
+
+    /// Replaces the listed accounts, keeping the selection on the same name
+    /// when it still exists. Details already read are kept until the loader
+    /// replaces them, so the table doesn't blank out after every action.
+    fn reload(&mut self, mut accounts: Vec<AccountSummary>) {
+        let selected = self.selected().map(|account| account.name.clone());
+        for account in &mut accounts {
+            if let Some(old) = self.accounts.iter().find(|old| old.name == account.name) {
+                account.login = old.login.clone();
+                account.usage = old.usage;
+            }
+        }
+        self.accounts = accounts;
+        let index = selected
+            .and_then(|name| {
+                self.accounts
+                    .iter()
+                    .position(|account| account.name == name)
+            })
+            .unwrap_or(0);
+        self.table
+            .select((!self.accounts.is_empty()).then_some(index));
+    }
+
+    /// Fills in a detail read by the loader.
+    fn apply(&mut self, loaded: Loaded) {
+        let name = match &loaded {
+            Loaded::Login(name, _) | Loaded::Usage(name, _) => name,
+        };
+        let Some(account) = self
+            .accounts
+            .iter_mut()
+            .find(|account| &account.name == name)
+        else {
+            return;
+        };
+        match loaded {
+            Loaded::Login(_, login) => account.login = Some(login),
+            Loaded::Usage(_, usage) => account.usage = Some(usage),
+        }
+    }
+
+    fn selected(&self) -> Option<&AccountSummary> {
+        self.table.selected().and_then(|i| self.accounts.get(i))
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> Action {
+        self.status = None;
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Action::Quit;
+        }
+
+        match std::mem::replace(&mut self.mode, Mode::Browse) {
+            Mode::Browse => self.browse(key),
+            Mode::ConfirmDelete(name) => match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => Action::Delete(name),
+                _ => Action::None,
+            },
+            Mode::Rename { from, mut to } => {
+                match key.code {
+                    KeyCode::Enter if !to.trim().is_empty() && to != from => {
+                        return Action::Rename(from, to.trim().to_string());
+                    }
+                    KeyCode::Enter | KeyCode::Esc => return Action::None,
+                    KeyCode::Backspace => {
+                        to.pop();
+                    }
+                    KeyCode::Char(c) => to.push(c),
+                    _ => {}
+                }
+                self.mode = Mode::Rename { from, to };
+                Action::None
+            }
+        }
+    }
+
+    fn browse(&mut self, key: KeyEvent) -> Action {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => Action::Quit,
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.table.select_next();
+                self.clamp_selection();
+                Action::None
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.table.select_previous();
+                Action::None
+            }
+            KeyCode::Enter | KeyCode::Char('s') => self
+                .selected()
+                .map_or(Action::None, |account| Action::Switch(account.name.clone())),
+            KeyCode::Char('d') => {
+                if let Some(account) = self.selected() {
+                    self.mode = Mode::ConfirmDelete(account.name.clone());
+                }
+                Action::None
+            }
+            KeyCode::Char('r') => {
+                if let Some(account) = self.selected() {
+                    self.mode = Mode::Rename {
+                        from: account.name.clone(),
+                        to: account.name.clone(),
+                    };
+                }
+                Action::None
+            }
+            KeyCode::Char('b') => match self.accounts.iter().find(|account| account.current) {
+                Some(account) => Action::Backup(account.name.clone()),
+                None => {
+                    self.status = Some(tr!("tui-no-active"));
+                    Action::None
+                }
+            },
+            _ => Action::None,
+        }
+    }
+
+    fn clamp_selection(&mut self) {
+        if let Some(i) = self.table.selected() {
+            self.table
+                .select(Some(i.min(self.accounts.len().saturating_sub(1))));
+        }
+    }
+
+    pub fn render(&mut self, frame: &mut Frame) {
+        let [table_area, details_area, footer_area] = Layout::vertical([
+            Constraint::Min(3),
+            Constraint::Length(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let header = Row::new([
+            Cell::from(""),
+            Cell::from(tr!("tui-col-account")),
+            Cell::from(tr!("tui-col-email")),
+            Cell::from(tr!("tui-col-plan")),
+            Cell::from(tr!("tui-col-expires")),
+            Cell::from(tr!("tui-col-last-used")),
+            Cell::from(tr!("tui-col-size")),
+        ])
+        .style(Style::new().add_modifier(Modifier::BOLD));
+
+        let rows: Vec<Row> = self
+            .accounts
+            .iter()
+            .map(|account| self.row(account))
+            .collect();
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(2),
+                Constraint::Fill(2),
+                Constraint::Fill(3),
+                Constraint::Length(8),
+                Constraint::Length(16),
+                Constraint::Length(16),
+                Constraint::Length(10),
+            ],
+        )
+        .header(header)
+        .block(
+            Block::new()
+                .borders(Borders::ALL)
+                .title(tr!("tui-title", count = self.accounts.len())),
+        )
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(table, table_area, &mut self.table);
+
+        let details = self.selected().map(|account| {
+            tr!(
+                "tui-details",
+                saved = short_time(&account.saved_at),
+                files = account
+                    .usage
+                    .map_or_else(|| PENDING.into(), |usage| usage.files.to_string()),
+                token = match &account.login {
+                    Some(login) => login
+                        .identity
+                        .as_ref()
+                        .and_then(|identity| identity.token.clone())
+                        .unwrap_or_else(|| "-".into()),
+                    None => PENDING.into(),
+                }
+            )
+        });
+        frame.render_widget(
+            Paragraph::new(details.unwrap_or_default()).block(Block::new().borders(Borders::ALL)),
+            details_area,
+        );
+
+        let footer = match &self.mode {
+            Mode::Browse => self.status.clone().unwrap_or_else(|| tr!("tui-help")),
+            Mode::ConfirmDelete(name) => format!(
+                "{} {}",
+                tr!("tui-confirm-delete", name = name),
+                prompt::hint(false)
+            ),
+            Mode::Rename { from, to } => format!("{}{}", tr!("tui-rename", name = from), to),
+        };
+        frame.render_widget(Paragraph::new(Line::from(footer)), footer_area);
+    }
+
+    fn row(&self, account: &AccountSummary) -> Row<'static> {
+        let login = account.login.as_ref();
+        let identity = login.and_then(|login| login.identity.as_ref());
+        let login_cell = |value: Option<String>| match login {
+            Some(_) => Cell::from(value.unwrap_or_else(|| "-".into())),
+            None => Cell::from(PENDING),
+        };
+
+        Row::new([
+            Cell::from(markers(account)),
+            Cell::from(account.name.clone()),
+            login_cell(login.and_then(|login| login.email.clone())),
+            login_cell(identity.and_then(|identity| identity.plan.clone())),
+            login_cell(
+                identity
+                    .and_then(|identity| identity.expires_at)
+                    .map(|expires| self.expiry(expires)),
+            ),
+            Cell::from(
+                account
+                    .last_used
+                    .as_deref()
+                    .map_or_else(|| tr!("tui-never"), |time| short_time(time).into()),
+            ),
+            Cell::from(
+                account
+                    .usage
+                    .map_or_else(|| PENDING.into(), |usage| size::format_bytes(usage.bytes)),
+            ),
+        ])
+    }
+
+    fn expiry(&self, expires: DateTime<Utc>) -> String {
+        if expires <= self.now {
+            tr!("tui-expired")
+        } else {
+            expires.format("%Y-%m-%d %H:%M").to_string()
+        }
+    }
+}
+
+/// Opens the dashboard until the user quits.
+pub fn run(manager: &AccountManager) -> Result<()> {
+    let mut app = App::new(manager.account_summaries()?, Utc::now());
+    let mut terminal = ratatui::try_init().context("Failed to set up the terminal")?;
+
+    let result = thread::scope(|scope| event_loop(scope, manager, &mut app, &mut terminal));
+    ratatui::restore();
+    result
+}
+
+fn event_loop<'scope, 'env>(
+    scope: &'scope Scope<'scope, 'env>,
+    manager: &'env AccountManager,
+    app: &mut App,
+    terminal: &mut DefaultTerminal,
+) -> Result<()> {
+    let mut loading = Some(loader::spawn(scope, manager, &app.accounts));
+    loop {
+        if loading.as_ref().is_some_and(|rx| !drain(app, rx)) {
+            loading = None;
+        }
+        terminal.draw(|frame| app.render(frame))?;
+
+        // Wake up regularly while details are arriving, to show them
+        if loading.is_some() && !event::poll(LOAD_POLL).context("Failed to read from terminal")? {
+            continue;
+        }
+        let Event::Key(key) = event::read().context("Failed to read from terminal")? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        let action = app.handle_key(key);
+        let run: Box<dyn FnOnce() -> Result<()>> = match action {
+            Action::None => continue,
+            Action::Quit => return Ok(()),
+            Action::Switch(name) => Box::new(move || manager.switch_account(&name, None)),
+            Action::Delete(name) => Box::new(move || manager.delete_account(&name, false)),
+            Action::Rename(from, to) => Box::new(move || manager.rename_account(&from, &to)),
+            Action::Backup(name) => Box::new(move || manager.save_account(&name)),
+        };
+
+        suspend(terminal, run)?;
+        app.reload(manager.account_summaries()?);
+        app.now = Utc::now();
+        loading = Some(loader::spawn(scope, manager, &app.accounts));
+    }
+}
+
+/// Applies everything the loader has sent so far. Returns false once it has
+/// finished.
+fn drain(app: &mut App, rx: &Receiver<Loaded>) -> bool {
+    loop {
+        match rx.try_recv() {
+            Ok(loaded) => app.apply(loaded),
+            Err(TryRecvError::Empty) => return true,
+            Err(TryRecvError::Disconnected) => return false,
+        }
+    }
+}
+
+/// Runs `action` on the normal screen and waits for Enter before redrawing.
+fn suspend(terminal: &mut DefaultTerminal, action: impl FnOnce() -> Result<()>) -> Result<()> {
+    ratatui::try_restore().context("Failed to restore the terminal")?;
+
+    if let Err(e) = action() {
+        eprintln!("Error: {:#}", e);
+    }
+    eprint!("{}", tr!("tui-press-enter"));
+    io::stderr().flush()?;
+    io::stdin().lock().read_line(&mut String::new())?;
+
+    *terminal = ratatui::try_init().context("Failed to set up the terminal")?;
+    terminal.clear()?;
+    Ok(())
+}
+
+fn markers(account: &AccountSummary) -> String {
+    let mut markers = String::new();
+    if account.current {
+        markers.push('*');
+    }
+    if account.protected {
+        markers.push('!');
+    }
+    markers
+}
+
+fn short_time(timestamp: &str) -> &str {
+    timestamp.get(..16).unwrap_or(timestamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude::Identity;
+    use crate::manager::AccountLogin;
+    use crate::size::DirUsage;
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+    use std::path::PathBuf;
+    use std::sync::mpsc;
+
+    fn account(name: &str, current: bool) -> AccountSummary {
+        AccountSummary {
+            name: name.to_string(),
+            current,
+            protected: false,
+            external: false,
+            saved_at: "2024-01-01T09:30:00+00:00".to_string(),
+            last_used: None,
+            path: PathBuf::from(name),
+            login: Some(AccountLogin {
+                identity: Some(Identity {
+                    plan: Some("max".to_string()),
+                    token: None,
+                    expires_at: DateTime::from_timestamp(1_700_000_000, 0),
+                }),
+                email: Some(format!("{}@example.com", name)),
+                organization: None,
+            }),
+            usage: Some(DirUsage {
+                files: 2,
+                bytes: 2048,
+            }),
+        }
+    }
+
+    fn app() -> App {
+        let now = DateTime::from_timestamp(1_600_000_000, 0).unwrap();
+        App::new(vec![account("personal", false), account("work", true)], now)
+    }
+
+    fn press(app: &mut App, code: KeyCode) -> Action {
+        app.handle_key(KeyEvent::from(code))
+    }
+
+    #[test]
+    fn test_starts_on_current_account() {
+        let app = app();
+        assert_eq!(app.selected().unwrap().name, "work");
+    }
+
+    #[test]
+    fn test_navigation_and_switch() {
+        let mut app = app();
+        assert_eq!(press(&mut app, KeyCode::Down), Action::None);
+        assert_eq!(app.selected().unwrap().name, "work");
+
+        press(&mut app, KeyCode::Up);
+        assert_eq!(
+            press(&mut app, KeyCode::Enter),
+            Action::Switch("personal".to_string())
+        );
+        assert_eq!(press(&mut app, KeyCode::Char('q')), Action::Quit);
+    }
+
+    #[test]
+    fn test_delete_needs_confirmation() {
+        let mut app = app();
+        assert_eq!(press(&mut app, KeyCode::Char('d')), Action::None);
+        assert_eq!(press(&mut app, KeyCode::Char('n')), Action::None);
+        assert_eq!(app.mode, Mode::Browse);
+
+        press(&mut app, KeyCode::Char('d'));
+        assert_eq!(
+            press(&mut app, KeyCode::Char('y')),
+            Action::Delete("work".to_string())
+        );
+    }
+
+    #[test]
+    fn test_rename_edits_name() {
+        let mut app = app();
+        press(&mut app, KeyCode::Char('r'));
+        for _ in 0.."work".len() {
+            press(&mut app, KeyCode::Backspace);
+        }
+        for c in "job".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        assert_eq!(
+            press(&mut app, KeyCode::Enter),
+            Action::Rename("work".to_string(), "job".to_string())
+        );
+    }
+
+    #[test]
+    fn test_backup_targets_active_account() {
+        let mut app = app();
+        assert_eq!(
+            press(&mut app, KeyCode::Char('b')),
+            Action::Backup("work".to_string())
+        );
+
+        let mut app = App::new(vec![account("personal", false)], Utc::now());
+        assert_eq!(press(&mut app, KeyCode::Char('b')), Action::None);
+        assert!(app.status.is_some());
+    }
+
+    fn screen(app: &mut App) -> Result<String> {
+        let mut terminal = Terminal::new(TestBackend::new(120, 12))?;
+        terminal.draw(|frame| app.render(frame))?;
+
+        Ok(terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect())
+    }
+
+    #[test]
+    fn test_details_fill_in_as_loaded() -> Result<()> {
+        let mut pending = account("work", true);
+        pending.login = None;
+        pending.usage = None;
+        let mut app = App::new(vec![pending.clone()], Utc::now());
+        assert!(screen(&mut app)?.contains(PENDING));
+        assert!(!screen(&mut app)?.contains("work@example.com"));
+
+        let (tx, rx) = mpsc::channel();
+        let loaded = account("work", true);
+        tx.send(Loaded::Login("work".into(), loaded.login.unwrap()))?;
+        tx.send(Loaded::Usage("work".into(), loaded.usage.unwrap()))?;
+        assert!(drain(&mut app, &rx));
+        drop(tx);
+        assert!(!drain(&mut app, &rx));
+
+        let screen = screen(&mut app)?;
+        assert!(screen.contains("work@example.com"));
+        assert!(!screen.contains(PENDING));
+
+        // Reloading keeps what was read until the loader refreshes it
+        app.reload(vec![pending]);
+        assert!(app.selected().unwrap().usage.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_shows_details() -> Result<()> {
+        let mut app = app();
+        let screen = screen(&mut app)?;
+        assert!(screen.contains("work@example.com"));
+        assert!(screen.contains("2023-11-14 22:13"));
+        assert!(screen.contains("2.0 KiB"));
+        Ok(())
+    }
+}
@@ -0,0 +1,326 @@
+//! Picking an account interactively when `switch` is run without a name.
+//!
+//! On a terminal this is an fzf-style picker: typing narrows the list with a
+//! fuzzy match, arrows move the selection and Enter picks it. Without one it
+//! falls back to a numbered list read line by line.
+
+use crate::tr;
+use anyhow::{Context, Result};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, Clear, ClearType};
+use crossterm::{cursor, queue};
+use std::io::{self, IsTerminal, Write};
+
+// Rows shown below the query line in the interactive picker
+const VISIBLE_ROWS: usize = 10;
+
+/// One account offered by the picker.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub name: String,
+    pub saved_at: String,
+    pub current: bool,
+}
+
+impl Entry {
+    fn line(&self) -> String {
+        let saved_at = self.saved_at.get(..19).unwrap_or(&self.saved_at);
+        format!(
+            "{} {:<20} ({})",
+            if self.current { "*" } else { " " },
+            self.name,
+            tr!("saved-at", time = saved_at)
+        )
+    }
+}
+
+/// Whether the full-screen picker can run, i.e. both input and the output
+/// it draws on are terminals.
+pub fn is_interactive() -> bool {
+    io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+/// Scores `candidate` against `query` as a case-insensitive subsequence,
+/// favouring consecutive runs and matches at the start of words. Returns
+/// `None` when not every query character appears in order.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let candidate: Vec<char> = candidate.chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+
+    for wanted in query.chars().filter(|c| !c.is_whitespace()) {
+        let offset = candidate[position..]
+            .iter()
+            .position(|c| c.eq_ignore_ascii_case(&wanted))?;
+        let index = position + offset;
+
+        score += 1;
+        if previous.is_some_and(|previous| previous + 1 == index) {
+            score += 5;
+        }
+        if index == 0 || !candidate[index - 1].is_alphanumeric() {
+            score += 3;
+        }
+        // Gaps make a match less convincing, up to a point
+        score -= offset.min(3) as i64;
+
+        previous = Some(index);
+        position = index + 1;
+    }
+    Some(score)
+}
+
+/// Indexes of the entries matching `query`, best match first. An empty query
+/// keeps the original order.
+pub fn filter(query: &str, entries: &[Entry]) -> Vec<usize> {
+    let mut matches: Vec<(i64, usize)> = entries
+        .iter()
+        .enumerate()
+        .filter_map(|(i, entry)| fuzzy_score(query, &entry.name).map(|score| (score, i)))
+        .collect();
+    // Stable, so equal scores stay in list order
+    matches.sort_by_key(|(score, _)| -score);
+    matches.into_iter().map(|(_, i)| i).collect()
+}
+
+/// Prints a numbered list and reads the choice with `read_line`, which may be
+/// a number or an exact name. An empty answer cancels.
+pub fn pick_numbered(
+    entries: &[Entry],
+    mut read_line: impl FnMut(&str) -> Result<String>,
+) -> Result<Option<usize>> {
+    for (i, entry) in entries.iter().enumerate() {
+        eprintln!("{:>3}) {}", i + 1, entry.line());
+    }
+
+    let answer = read_line(&tr!("pick-prompt", count = entries.len()))?;
+    let answer = answer.trim();
+    if answer.is_empty() {
+        return Ok(None);
+    }
+
+    if let Ok(number) = answer.parse::<usize>()
+        && (1..=entries.len()).contains(&number)
+    {
+        return Ok(Some(number - 1));
+    }
+    entries
+        .iter()
+        .position(|entry| entry.name == answer)
+        .map(Some)
+        .ok_or_else(|| anyhow::anyhow!(tr!("pick-invalid", answer = answer)))
+}
+
+/// Runs the fuzzy picker on stderr. Returns `None` when cancelled with Esc
+/// or Ctrl-C.
+pub fn pick_interactive(entries: &[Entry]) -> Result<Option<usize>> {
+    let _raw = RawMode::enable()?;
+    let mut out = io::stderr();
+    let mut query = String::new();
+    let mut selected = 0;
+
+    loop {
+        let matches = filter(&query, entries);
+        selected = selected.min(matches.len().saturating_sub(1));
+        draw(&mut out, &query, entries, &matches, selected)?;
+
+        let Event::Key(key) = event::read().context("Failed to read from terminal")? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+
+        match key {
+            KeyEvent {
+                code: KeyCode::Esc, ..
+            } => break,
+            KeyEvent {
+                code: KeyCode::Char('c'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => break,
+            KeyEvent {
+                code: KeyCode::Enter,
+                ..
+            } => {
+                if let Some(&index) = matches.get(selected) {
+                    clear(&mut out)?;
+                    return Ok(Some(index));
+                }
+            }
+            KeyEvent {
+                code: KeyCode::Up, ..
+            }
+            | KeyEvent {
+                code: KeyCode::Char('p'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => selected = selected.saturating_sub(1),
+            KeyEvent {
+                code: KeyCode::Down,
+                ..
+            }
+            | KeyEvent {
+                code: KeyCode::Char('n'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => selected += 1,
+            KeyEvent {
+                code: KeyCode::Backspace,
+                ..
+            } => {
+                query.pop();
+                selected = 0;
+            }
+            KeyEvent {
+                code: KeyCode::Char(c),
+                modifiers,
+                ..
+            } if !modifiers.contains(KeyModifiers::CONTROL) => {
+                query.push(c);
+                selected = 0;
+            }
+            _ => {}
+        }
+    }
+
+    clear(&mut out)?;
+    Ok(None)
+}
+
+fn draw(
+    out: &mut impl Write,
+    query: &str,
+    entries: &[Entry],
+    matches: &[usize],
+    selected: usize,
+) -> Result<()> {
+    // Scroll so the selection stays visible
+    let first = selected.saturating_sub(VISIBLE_ROWS - 1);
+    let rows = &matches[first..matches.len().min(first + VISIBLE_ROWS)];
+
+    queue!(
+        out,
+        cursor::MoveToColumn(0),
+        Clear(ClearType::FromCursorDown)
+    )?;
+    for (offset, &index) in rows.iter().enumerate() {
+        let line = entries[index].line();
+        if first + offset == selected {
+            queue!(
+                out,
+                Print("\r\n> "),
+                SetAttribute(Attribute::Reverse),
+                Print(line),
+                SetAttribute(Attribute::Reset)
+            )?;
+        } else {
+            queue!(out, Print("\r\n  "), Print(line))?;
+        }
+    }
+    if matches.is_empty() {
+        queue!(out, Print("\r\n  "), Print(tr!("pick-no-match")))?;
+    }
+
+    // Back up to the query line, leaving the cursor after the typed text
+    let drawn = rows.len().max(usize::from(matches.is_empty()));
+    if drawn > 0 {
+        queue!(out, cursor::MoveUp(drawn as u16))?;
+    }
+    queue!(
+        out,
+        cursor::MoveToColumn(0),
+        Print(tr!(
+            "pick-query",
+            count = matches.len(),
+            total = entries.len()
+        )),
+        Print(query)
+    )?;
+    out.flush()?;
+    Ok(())
+}
+
+fn clear(out: &mut impl Write) -> Result<()> {
+    queue!(
+        out,
+        cursor::MoveToColumn(0),
+        Clear(ClearType::FromCursorDown)
+    )?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Restores the terminal even when the picker returns early with an error.
+struct RawMode;
+
+impl RawMode {
+    fn enable() -> Result<Self> {
+        terminal::enable_raw_mode().context("Failed to set up the terminal")?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(names: &[&str]) -> Vec<Entry> {
+        names
+            .iter()
+            .map(|name| Entry {
+                name: name.to_string(),
+                saved_at: "2024-01-01T00:00:00.000+00:00".to_string(),
+                current: false,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fuzzy_score_requires_subsequence() {
+        assert!(fuzzy_score("wk", "work").is_some());
+        assert!(fuzzy_score("WORK", "work").is_some());
+        assert!(fuzzy_score("kw", "work").is_none());
+        assert_eq!(fuzzy_score("", "work"), Some(0));
+    }
+
+    #[test]
+    fn test_fuzzy_score_prefers_tight_matches() {
+        let prefix = fuzzy_score("cli", "client/prod").unwrap();
+        let scattered = fuzzy_score("cli", "acme/legacy-infra").unwrap();
+        assert!(prefix > scattered);
+
+        // Word starts after a separator count as well
+        let word = fuzzy_score("prod", "client/prod").unwrap();
+        let inner = fuzzy_score("prod", "reproduce").unwrap();
+        assert!(word > inner);
+    }
+
+    #[test]
+    fn test_filter_orders_by_score() {
+        let entries = entries(&["personal", "client/prod", "prod"]);
+        assert_eq!(filter("", &entries), vec![0, 1, 2]);
+        assert_eq!(filter("prod", &entries), vec![2, 1]);
+        assert!(filter("xyz", &entries).is_empty());
+    }
+
+    #[test]
+    fn test_pick_numbered() -> Result<()> {
+        let entries = entries(&["personal", "work"]);
+
+        assert_eq!(pick_numbered(&entries, |_| Ok("2".into()))?, Some(1));
+        assert_eq!(pick_numbered(&entries, |_| Ok("personal".into()))?, Some(0));
+        assert_eq!(pick_numbered(&entries, |_| Ok(" ".into()))?, None);
+        assert!(pick_numbered(&entries, |_| Ok("3".into())).is_err());
+        Ok(())
+    }
+}
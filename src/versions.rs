@@ -0,0 +1,162 @@
+//! Earlier snapshots of each account. Every save moves the snapshot it's
+//! about to replace in here, so one bad save can be rolled back.
+//!
+//! Versions live in `.versions/<account>/<id>` next to the snapshots, where
+//! the id is the time the snapshot was saved (`20240101T093000Z`).
+
+use crate::retry::retry;
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const DIR: &str = ".versions";
+/// How many versions each account keeps unless `keep-versions` says otherwise
+pub const DEFAULT_KEEP: usize = 5;
+
+const ID_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+// Length of a formatted id, before any `-2` suffix
+const ID_LEN: usize = "20240101T093000Z".len();
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Version {
+    pub id: String,
+    pub path: PathBuf,
+}
+
+impl Version {
+    /// When the snapshot was saved, recovered from the id.
+    pub fn saved_at(&self) -> Option<DateTime<Utc>> {
+        let stamp = self.id.get(..ID_LEN)?;
+        NaiveDateTime::parse_from_str(stamp, ID_FORMAT)
+            .ok()
+            .map(|time| time.and_utc())
+    }
+}
+
+/// Where the versions of `name` are kept. Names may contain `/`, so it's
+/// escaped to keep `client` and `client/dev` apart.
+pub fn account_dir(root: &Path, name: &str) -> PathBuf {
+    root.join(DIR)
+        .join(name.replace('%', "%25").replace('/', "%2F"))
+}
+
+/// Every version of `name`, newest first.
+pub fn list(root: &Path, name: &str) -> Result<Vec<Version>> {
+    let dir = account_dir(root, name);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut versions = Vec::new();
+    for entry in fs::read_dir(&dir).context("Failed to read versions directory")? {
+        let entry = entry.context("Failed to read versions directory")?;
+        if entry.file_type()?.is_dir() {
+            versions.push(Version {
+                id: entry.file_name().to_string_lossy().into_owned(),
+                path: entry.path(),
+            });
+        }
+    }
+    versions.sort_by(|a, b| b.id.cmp(&a.id));
+    Ok(versions)
+}
+
+/// Picks an unused path for a version of `name` saved at `saved_at`. Two
+/// saves within the same second get `-2`, `-3`, ... suffixes.
+pub fn allocate(root: &Path, name: &str, saved_at: &str) -> Result<PathBuf> {
+    let dir = account_dir(root, name);
+    fs::create_dir_all(&dir).context("Failed to create versions directory")?;
+
+    let id = DateTime::parse_from_rfc3339(saved_at)
+        .map(|time| time.with_timezone(&Utc))
+        .unwrap_or_else(|_| Utc::now())
+        .format(ID_FORMAT)
+        .to_string();
+
+    let mut path = dir.join(&id);
+    for n in 2.. {
+        if !path.exists() {
+            break;
+        }
+        path = dir.join(format!("{}-{}", id, n));
+    }
+    Ok(path)
+}
+
+/// Removes all but the newest `keep` versions of `name`.
+pub fn prune(root: &Path, name: &str, keep: usize) -> Result<()> {
+    for version in list(root, name)?.into_iter().skip(keep) {
+        retry(|| fs::remove_dir_all(&version.path)).context("Failed to remove old version")?;
+    }
+    Ok(())
+}
+
+/// Moves the versions of `old` along with a rename.
+pub fn rename(root: &Path, old: &str, new: &str) -> Result<()> {
+    let from = account_dir(root, old);
+    if !from.exists() {
+        return Ok(());
+    }
+    retry(|| fs::rename(&from, account_dir(root, new))).context("Failed to move account versions")
+}
+
+pub fn remove(root: &Path, name: &str) -> Result<()> {
+    let dir = account_dir(root, name);
+    if !dir.exists() {
+        return Ok(());
+    }
+    retry(|| fs::remove_dir_all(&dir)).context("Failed to remove account versions")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn add(root: &Path, name: &str, saved_at: &str) -> Result<PathBuf> {
+        let path = allocate(root, name, saved_at)?;
+        fs::create_dir_all(&path)?;
+        Ok(path)
+    }
+
+    #[test]
+    fn test_allocate_list_and_prune() -> Result<()> {
+        let root = TempDir::new()?;
+        add(root.path(), "work", "2024-01-01T09:30:00.123+00:00")?;
+        add(root.path(), "work", "2024-01-02T09:30:00+00:00")?;
+        let again = add(root.path(), "work", "2024-01-02T09:30:00+00:00")?;
+        assert!(again.ends_with("20240102T093000Z-2"));
+
+        let versions = list(root.path(), "work")?;
+        let ids: Vec<_> = versions.iter().map(|v| v.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            ["20240102T093000Z-2", "20240102T093000Z", "20240101T093000Z"]
+        );
+        assert_eq!(
+            versions[2].saved_at().map(|time| time.to_rfc3339()),
+            Some("2024-01-01T09:30:00+00:00".to_string())
+        );
+
+        prune(root.path(), "work", 1)?;
+        assert_eq!(list(root.path(), "work")?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_nested_names_are_kept_apart() -> Result<()> {
+        let root = TempDir::new()?;
+        add(root.path(), "client", "2024-01-01T00:00:00Z")?;
+        add(root.path(), "client/dev", "2024-01-01T00:00:00Z")?;
+
+        assert_eq!(list(root.path(), "client")?.len(), 1);
+        rename(root.path(), "client/dev", "client/staging")?;
+        assert_eq!(list(root.path(), "client/staging")?.len(), 1);
+        assert!(list(root.path(), "client/dev")?.is_empty());
+
+        remove(root.path(), "client")?;
+        assert!(list(root.path(), "client")?.is_empty());
+        Ok(())
+    }
+}
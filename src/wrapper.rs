@@ -0,0 +1,85 @@
+//! `wrapper`: a `claude` shell function that makes sure the account bound to
+//! the current project is active before starting Claude Code.
+
+use crate::completions::{self, BIN_NAME};
+use anyhow::{Context, Result};
+use clap_complete::Shell;
+
+pub fn run(shell: Option<Shell>, warn_only: bool) -> Result<()> {
+    let shell = match shell {
+        Some(shell) => shell,
+        None => completions::detect_shell(std::env::var("SHELL").ok().as_deref())
+            .context("Could not detect your shell; pass it explicitly (bash, zsh, fish, ...)")?,
+    };
+
+    print!("{}", script(shell, warn_only)?);
+    Ok(())
+}
+
+/// The function definition for `shell`. With `warn_only` a mismatch is
+/// reported but Claude Code starts under the current account anyway. The
+/// check writes to stderr so piped `claude -p` output stays clean.
+pub fn script(shell: Shell, warn_only: bool) -> Result<String> {
+    let check = if warn_only {
+        format!("{} ensure-bound --warn", BIN_NAME)
+    } else {
+        format!("{} ensure-bound", BIN_NAME)
+    };
+
+    let script = match shell {
+        Shell::Bash | Shell::Zsh => format!(
+            "# Generated by `{bin} wrapper {shell}`\n\
+             claude() {{\n    \
+                 command {check} >&2 || return $?\n    \
+                 command claude \"$@\"\n\
+             }}\n",
+            bin = BIN_NAME,
+        ),
+        Shell::Fish => format!(
+            "# Generated by `{bin} wrapper fish`\n\
+             function claude --wraps claude\n    \
+                 command {check} >&2; or return $status\n    \
+                 command claude $argv\n\
+             end\n",
+            bin = BIN_NAME,
+        ),
+        Shell::PowerShell => format!(
+            "# Generated by `{bin} wrapper powershell`\n\
+             function claude {{\n    \
+                 & {check} | Out-Host\n    \
+                 if ($LASTEXITCODE -ne 0) {{ return }}\n    \
+                 $real = Get-Command claude -CommandType Application | Select-Object -First 1\n    \
+                 & $real.Source @args\n\
+             }}\n",
+            bin = BIN_NAME,
+        ),
+        _ => anyhow::bail!("The claude wrapper is not available for {}", shell),
+    };
+    Ok(script)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_posix_wrapper() -> Result<()> {
+        let script = script(Shell::Bash, false)?;
+        assert!(script.contains("claude() {"));
+        assert!(script.contains("command claude-account-switcher ensure-bound >&2 || return $?"));
+        assert!(script.contains("command claude \"$@\""));
+        assert_eq!(
+            script,
+            super::script(Shell::Zsh, false)?.replace("zsh", "bash")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_warn_only_and_other_shells() -> Result<()> {
+        assert!(script(Shell::Fish, true)?.contains("ensure-bound --warn >&2; or return $status"));
+        assert!(script(Shell::PowerShell, false)?.contains("& $real.Source @args"));
+        assert!(script(Shell::Elvish, false).is_err());
+        Ok(())
+    }
+}
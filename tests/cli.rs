@@ -0,0 +1,197 @@
+//! The binary itself, run against a sandboxed home directory: nothing but
+//! `CLAUDE_SWITCHER_HOME` and `PATH` is passed on, so the user's own
+//! accounts and Claude directory are never looked at.
+
+use serde_json::Value;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use tempfile::TempDir;
+
+struct Sandbox {
+    home: TempDir,
+}
+
+impl Sandbox {
+    fn new() -> Self {
+        let sandbox = Self {
+            home: TempDir::new().unwrap(),
+        };
+        fs::create_dir_all(sandbox.claude_dir()).unwrap();
+        sandbox
+    }
+
+    fn claude_dir(&self) -> PathBuf {
+        self.home.path().join(".claude")
+    }
+
+    fn write_live(&self, file: &str, contents: &str) {
+        fs::write(self.claude_dir().join(file), contents).unwrap();
+    }
+
+    fn read_live(&self, file: &str) -> String {
+        fs::read_to_string(self.claude_dir().join(file)).unwrap()
+    }
+
+    fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_claude-account-switcher"));
+        command
+            .args(args)
+            .env_clear()
+            .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+            .env("CLAUDE_SWITCHER_HOME", self.home.path());
+        command
+    }
+
+    /// Runs the switcher with `args`, failing the test unless it succeeds.
+    fn run(&self, args: &[&str]) -> Output {
+        let output = self.command(args).output().unwrap();
+        assert!(
+            output.status.success(),
+            "{:?} failed: {}",
+            args,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        output
+    }
+
+    /// Runs the switcher with `args`, piping `answers` in for its prompts.
+    fn answer(&self, args: &[&str], answers: &str) -> Output {
+        let mut child = self
+            .command(args)
+            .env("CLAUDE_SWITCHER_PIPED_ANSWERS", "1")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(answers.as_bytes())
+            .unwrap();
+        child.wait_with_output().unwrap()
+    }
+
+    fn json(&self, args: &[&str]) -> Value {
+        let mut args = args.to_vec();
+        args.push("--json");
+        serde_json::from_slice(&self.run(&args).stdout).unwrap()
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.json(&["list"])
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|account| account["name"].as_str().unwrap().to_string())
+            .collect()
+    }
+}
+
+fn is_inside(path: &Path, dir: &Path) -> bool {
+    path.canonicalize()
+        .unwrap()
+        .starts_with(dir.canonicalize().unwrap())
+}
+
+#[test]
+fn test_save_and_switch_between_accounts() {
+    let sandbox = Sandbox::new();
+    sandbox.write_live("settings.json", r#"{"theme": "dark"}"#);
+    sandbox.run(&["save", "work"]);
+    sandbox.write_live("settings.json", r#"{"theme": "light"}"#);
+    sandbox.run(&["save", "personal"]);
+
+    sandbox.run(&["switch", "work"]);
+    assert_eq!(sandbox.read_live("settings.json"), r#"{"theme": "dark"}"#);
+    assert_eq!(sandbox.json(&["current"])["current"], "work");
+    assert_eq!(sandbox.names(), ["personal", "work"]);
+
+    sandbox.run(&["switch", "-"]);
+    assert_eq!(sandbox.read_live("settings.json"), r#"{"theme": "light"}"#);
+}
+
+#[test]
+fn test_store_is_kept_in_the_sandbox() {
+    let sandbox = Sandbox::new();
+    sandbox.write_live("settings.json", "{}");
+    sandbox.run(&["save", "work"]);
+
+    let store = sandbox
+        .home
+        .path()
+        .join(".local/share/claude-account-switcher");
+    assert!(is_inside(&store.join("work"), sandbox.home.path()));
+    assert!(
+        sandbox
+            .home
+            .path()
+            .join(".local/state/claude-account-switcher/accounts.json")
+            .is_file()
+    );
+}
+
+#[test]
+fn test_prompts_take_piped_answers() {
+    let sandbox = Sandbox::new();
+    sandbox.write_live("settings.json", "{}");
+    sandbox.run(&["save", "work"]);
+
+    // Without a terminal or the opt-in, confirmations fail rather than guess
+    let output = sandbox
+        .command(&["delete", "work"])
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(7));
+
+    let output = sandbox.answer(&["delete", "work"], "n\n");
+    assert!(output.status.success());
+    assert_eq!(sandbox.names(), ["work"]);
+
+    let output = sandbox.answer(&["delete", "work"], "y\n");
+    assert!(output.status.success());
+    assert!(sandbox.names().is_empty());
+}
+
+#[test]
+fn test_failures_exit_with_their_code_and_kind() {
+    let sandbox = Sandbox::new();
+    let output = sandbox
+        .command(&["switch", "missing", "--json"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+
+    let error: Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(error["kind"], "not-found");
+}
+
+#[test]
+fn test_json_covers_lookups() {
+    let sandbox = Sandbox::new();
+    sandbox.write_live("settings.json", r#"{"theme": "dark"}"#);
+    sandbox.run(&["save", "work"]);
+    sandbox.run(&["note", "work", "the day job"]);
+
+    assert_eq!(sandbox.json(&["note", "work"])["note"], "the day job");
+
+    let matches = sandbox.json(&["grep", "theme"]);
+    assert_eq!(matches[0]["account"], "work");
+    assert_eq!(matches[0]["line_number"], 1);
+
+    let listed = sandbox.json(&["checksum", "work"]);
+    let listed = listed.as_array().unwrap();
+    assert!(listed.iter().any(|file| file["path"] == "settings.json"));
+
+    let list = sandbox.home.path().join("work.sha256");
+    fs::write(&list, sandbox.run(&["checksum", "work"]).stdout).unwrap();
+    let checked = sandbox.json(&["checksum", "work", "--check", list.to_str().unwrap()]);
+    assert_eq!(checked["ok"], listed.len());
+    assert_eq!(checked["failed"], Value::Array(Vec::new()));
+
+    assert_eq!(sandbox.json(&["verify"]), Value::Array(Vec::new()));
+}